@@ -2,20 +2,28 @@
 
 use std::path::PathBuf;
 
-use zair_sdk::commands::{GapTreeMode, OrchardParamsMode};
+use zair_sdk::commands::{
+    DEFAULT_TRANSPARENT_GAP_LIMIT, ExportFormat, GapTreeMode, OrchardParamsMode,
+};
 
 use super::constants::{
-    DEFAULT_CLAIMS_FILE, DEFAULT_CONFIG_FILE, DEFAULT_GAP_TREE_MODE, DEFAULT_ORCHARD_PARAMS_FILE,
+    DEFAULT_ADDRESS_FORMAT, DEFAULT_CLAIMS_FILE, DEFAULT_CONFIG_FILE, DEFAULT_EXPORT_FILE,
+    DEFAULT_GAP_TREE_MODE, DEFAULT_MESSAGE_FILE, DEFAULT_ORCHARD_PARAMS_FILE,
     DEFAULT_ORCHARD_PARAMS_MODE, DEFAULT_PROOFS_FILE, DEFAULT_SAPLING_PK_FILE,
     DEFAULT_SECRETS_FILE, DEFAULT_SUBMISSION_FILE, DEFAULT_UFVK_FILE, ZAIR_ACCOUNT_ID,
-    ZAIR_BIRTHDAY, ZAIR_CLAIMS_IN, ZAIR_CLAIMS_OUT, ZAIR_CONFIG_FILE, ZAIR_GAP_TREE_MODE,
-    ZAIR_GAP_TREE_ORCHARD_FILE, ZAIR_GAP_TREE_SAPLING_FILE, ZAIR_LIGHTWALLETD_URL,
-    ZAIR_MESSAGE_FILE, ZAIR_MESSAGES_FILE, ZAIR_ORCHARD_PARAMS_FILE, ZAIR_ORCHARD_PARAMS_MODE,
-    ZAIR_PROOFS_IN, ZAIR_PROOFS_OUT, ZAIR_SAPLING_PK_FILE, ZAIR_SECRETS_IN, ZAIR_SECRETS_OUT,
-    ZAIR_SEED_FILE, ZAIR_SNAPSHOT_ORCHARD_FILE, ZAIR_SNAPSHOT_SAPLING_FILE, ZAIR_SUBMISSION_OUT,
-    ZAIR_UFVK_FILE,
+    ZAIR_ADDRESS_FORMAT, ZAIR_AIRDROP_TARGET, ZAIR_BIRTHDAY, ZAIR_CHAIN_ID, ZAIR_CLAIMS_IN,
+    ZAIR_CLAIMS_OUT, ZAIR_CONFIG_FILE, ZAIR_EXPORT_FORMAT, ZAIR_EXPORT_OUT, ZAIR_GAP_LIMIT,
+    ZAIR_GAP_TREE_MODE, ZAIR_GAP_TREE_ORCHARD_FILE, ZAIR_GAP_TREE_SAPLING_FILE,
+    ZAIR_GAP_TREE_SHARDS, ZAIR_LIGHTWALLETD_URL, ZAIR_MEMO, ZAIR_MESSAGE_FILE, ZAIR_MESSAGE_OUT,
+    ZAIR_MESSAGES_FILE, ZAIR_ORCHARD_PARAMS_FILE, ZAIR_ORCHARD_PARAMS_MODE, ZAIR_PROOFS_IN,
+    ZAIR_PROOFS_OUT, ZAIR_RECIPIENT, ZAIR_SAPLING_PK_FILE, ZAIR_SCAN_CACHE, ZAIR_SECRETS_IN,
+    ZAIR_SECRETS_OUT, ZAIR_SEED_FILE, ZAIR_SNAPSHOT_ORCHARD_FILE, ZAIR_SNAPSHOT_SAPLING_FILE,
+    ZAIR_SUBMISSION_IN, ZAIR_SUBMISSION_OUT, ZAIR_TRANSPARENT_BALANCES, ZAIR_UFVK_FILE,
+    ZAIR_WALLET_DB, ZAIR_WITNESS_SERVER_URL,
+};
+use super::{
+    TrustedOrganizerArgs, parse_export_format, parse_gap_tree_mode, parse_orchard_params_mode,
 };
-use super::{parse_gap_tree_mode, parse_orchard_params_mode};
 
 /// Arguments for the end-to-end claim pipeline.
 #[cfg(feature = "prove")]
@@ -29,6 +37,11 @@ pub struct ClaimRunArgs {
         default_value = DEFAULT_CONFIG_FILE
     )]
     pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
+    /// Airdrop target of the configuration to claim, instead of its default airdrop.
+    #[arg(long, env = ZAIR_AIRDROP_TARGET, value_name = "NAME")]
+    pub target: Option<String>,
     /// Path to file containing 64-byte seed as hex.
     #[arg(long, env = ZAIR_SEED_FILE, value_name = "SEED_FILE")]
     pub seed: PathBuf,
@@ -53,7 +66,8 @@ pub struct ClaimRunArgs {
     #[arg(long, env = ZAIR_GAP_TREE_ORCHARD_FILE)]
     pub gap_tree_orchard: Option<PathBuf>,
     /// Gap-tree mode: `none` (require files), `rebuild` (recompute and persist), `sparse`
-    /// (in-memory only).
+    /// (in-memory only), `remote` (fetch and verify witnesses from `--witness-server`),
+    /// `sharded` (fetch and verify only the needed shards from `--gap-tree-shards`).
     #[arg(
         long,
         env = ZAIR_GAP_TREE_MODE,
@@ -61,6 +75,12 @@ pub struct ClaimRunArgs {
         value_parser = parse_gap_tree_mode
     )]
     pub gap_tree_mode: GapTreeMode,
+    /// Witness server base URL used by `--gap-tree-mode remote`.
+    #[arg(long, env = ZAIR_WITNESS_SERVER_URL, value_name = "URL")]
+    pub witness_server: Option<String>,
+    /// Gap-tree shard base URL or directory used by `--gap-tree-mode sharded`.
+    #[arg(long, env = ZAIR_GAP_TREE_SHARDS, value_name = "URL_OR_DIR")]
+    pub gap_tree_shards: Option<String>,
     /// Path to Sapling proving key file.
     #[arg(
         long = "sapling-pk",
@@ -88,9 +108,17 @@ pub struct ClaimRunArgs {
     /// ZIP-32 account index used to derive Sapling keys from the seed.
     #[arg(long, env = ZAIR_ACCOUNT_ID, default_value_t = 0)]
     pub account: u32,
-    /// Scan start height for note discovery.
-    #[arg(long, env = ZAIR_BIRTHDAY)]
-    pub birthday: u64,
+    /// Scan start height for note discovery; not needed with `--wallet-db`.
+    #[arg(long, env = ZAIR_BIRTHDAY, required_unless_present = "wallet_db")]
+    pub birthday: Option<u64>,
+    /// Directory caching the note scan per viewing key and birthday, so repeated claims only
+    /// scan blocks not seen before.
+    #[arg(long, env = ZAIR_SCAN_CACHE, value_name = "DIR")]
+    pub scan_cache: Option<PathBuf>,
+    /// `zcash_client_sqlite` wallet database to import notes and witnesses from instead of
+    /// scanning; the wallet must be synced past the snapshot height.
+    #[arg(long, env = ZAIR_WALLET_DB, value_name = "FILE", conflicts_with = "scan_cache")]
+    pub wallet_db: Option<PathBuf>,
     /// Optional lightwalletd gRPC endpoint URL override.
     #[arg(long, env = ZAIR_LIGHTWALLETD_URL)]
     pub lightwalletd: Option<String>,
@@ -127,6 +155,8 @@ pub struct ClaimPrepareArgs {
         default_value = DEFAULT_CONFIG_FILE
     )]
     pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
     /// File containing the Unified Full Viewing Key (bech32).
     #[arg(long, env = ZAIR_UFVK_FILE, default_value = DEFAULT_UFVK_FILE)]
     pub ufvk: PathBuf,
@@ -145,7 +175,8 @@ pub struct ClaimPrepareArgs {
     #[arg(long, env = ZAIR_GAP_TREE_ORCHARD_FILE)]
     pub gap_tree_orchard: Option<PathBuf>,
     /// Gap-tree mode: `none` (require files), `rebuild` (recompute and persist), `sparse`
-    /// (in-memory only).
+    /// (in-memory only), `remote` (fetch and verify witnesses from `--witness-server`),
+    /// `sharded` (fetch and verify only the needed shards from `--gap-tree-shards`).
     #[arg(
         long,
         env = ZAIR_GAP_TREE_MODE,
@@ -153,9 +184,23 @@ pub struct ClaimPrepareArgs {
         value_parser = parse_gap_tree_mode
     )]
     pub gap_tree_mode: GapTreeMode,
-    /// Scan start height for note discovery.
-    #[arg(long, env = ZAIR_BIRTHDAY)]
-    pub birthday: u64,
+    /// Witness server base URL used by `--gap-tree-mode remote`.
+    #[arg(long, env = ZAIR_WITNESS_SERVER_URL, value_name = "URL")]
+    pub witness_server: Option<String>,
+    /// Gap-tree shard base URL or directory used by `--gap-tree-mode sharded`.
+    #[arg(long, env = ZAIR_GAP_TREE_SHARDS, value_name = "URL_OR_DIR")]
+    pub gap_tree_shards: Option<String>,
+    /// Scan start height for note discovery; not needed with `--wallet-db`.
+    #[arg(long, env = ZAIR_BIRTHDAY, required_unless_present = "wallet_db")]
+    pub birthday: Option<u64>,
+    /// Directory caching the note scan per viewing key and birthday, so repeated claims only
+    /// scan blocks not seen before.
+    #[arg(long, env = ZAIR_SCAN_CACHE, value_name = "DIR")]
+    pub scan_cache: Option<PathBuf>,
+    /// `zcash_client_sqlite` wallet database to import notes and witnesses from instead of
+    /// scanning; the wallet must be synced past the snapshot height.
+    #[arg(long, env = ZAIR_WALLET_DB, value_name = "FILE", conflicts_with = "scan_cache")]
+    pub wallet_db: Option<PathBuf>,
     /// Optional lightwalletd gRPC endpoint URL override.
     #[arg(long, env = ZAIR_LIGHTWALLETD_URL)]
     pub lightwalletd: Option<String>,
@@ -176,6 +221,11 @@ pub struct ClaimProveArgs {
         default_value = DEFAULT_CONFIG_FILE
     )]
     pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
+    /// Airdrop target of the configuration to claim, instead of its default airdrop.
+    #[arg(long, env = ZAIR_AIRDROP_TARGET, value_name = "NAME")]
+    pub target: Option<String>,
     /// Input file containing claim inputs.
     #[arg(long, env = ZAIR_CLAIMS_IN, default_value = DEFAULT_CLAIMS_FILE)]
     pub claims_in: PathBuf,
@@ -232,6 +282,11 @@ pub struct ClaimSignArgs {
         default_value = DEFAULT_CONFIG_FILE
     )]
     pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
+    /// Airdrop target of the configuration to claim, instead of its default airdrop.
+    #[arg(long, env = ZAIR_AIRDROP_TARGET, value_name = "NAME")]
+    pub target: Option<String>,
     /// Proofs file generated by `claim prove`.
     #[arg(long, env = ZAIR_PROOFS_IN, default_value = DEFAULT_PROOFS_FILE)]
     pub proofs_in: PathBuf,
@@ -263,6 +318,87 @@ pub struct ClaimSignArgs {
     pub submission_out: PathBuf,
 }
 
+/// Arguments for transparent claim signing.
+#[derive(Debug, clap::Args)]
+pub struct ClaimTransparentArgs {
+    /// Airdrop configuration file.
+    #[arg(
+        long,
+        env = ZAIR_CONFIG_FILE,
+        value_name = "CONFIG_FILE",
+        default_value = DEFAULT_CONFIG_FILE
+    )]
+    pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
+    /// Airdrop target of the configuration to claim, instead of its default airdrop.
+    #[arg(long, env = ZAIR_AIRDROP_TARGET, value_name = "NAME")]
+    pub target: Option<String>,
+    /// Published transparent balance listing the configuration was built from.
+    #[arg(long, env = ZAIR_TRANSPARENT_BALANCES, value_name = "FILE")]
+    pub transparent_balances: PathBuf,
+    /// Path to file containing 64-byte seed as hex for deriving transparent keys.
+    #[arg(long, env = ZAIR_SEED_FILE, value_name = "SEED_FILE")]
+    pub seed: PathBuf,
+    /// Shared message payload file fallback used for claim signatures.
+    #[arg(long = "message", env = ZAIR_MESSAGE_FILE, value_name = "MESSAGE_FILE")]
+    pub message: Option<PathBuf>,
+    /// Per-claim message assignments JSON.
+    #[arg(long = "messages", env = ZAIR_MESSAGES_FILE, value_name = "MESSAGES_FILE")]
+    pub messages: Option<PathBuf>,
+    /// BIP-44 account index used to derive transparent keys from the seed.
+    #[arg(long, env = ZAIR_ACCOUNT_ID, default_value_t = 0)]
+    pub account: u32,
+    /// Consecutive unfunded addresses per scope after which address discovery stops.
+    #[arg(long, env = ZAIR_GAP_LIMIT, default_value_t = DEFAULT_TRANSPARENT_GAP_LIMIT)]
+    pub gap_limit: u32,
+    /// Output file for signed submission bundle.
+    #[arg(
+        long,
+        env = ZAIR_SUBMISSION_OUT,
+        default_value = DEFAULT_SUBMISSION_FILE
+    )]
+    pub submission_out: PathBuf,
+}
+
+/// Arguments for claim payload export.
+#[derive(Debug, clap::Args)]
+pub struct ClaimExportArgs {
+    /// Signed submission file generated by `claim sign`.
+    #[arg(
+        long,
+        env = ZAIR_SUBMISSION_IN,
+        default_value = DEFAULT_SUBMISSION_FILE
+    )]
+    pub submission_in: PathBuf,
+    /// Target payload format: `namada`.
+    #[arg(long, env = ZAIR_EXPORT_FORMAT, value_parser = parse_export_format)]
+    pub format: ExportFormat,
+    /// Output file for the exported payloads.
+    #[arg(long, env = ZAIR_EXPORT_OUT, default_value = DEFAULT_EXPORT_FILE)]
+    pub output: PathBuf,
+}
+
+/// Arguments for structured claim message generation.
+#[derive(Debug, clap::Args)]
+pub struct ClaimMessageArgs {
+    /// Chain id of the target chain the airdrop is paid out on.
+    #[arg(long, env = ZAIR_CHAIN_ID)]
+    pub chain_id: String,
+    /// Address receiving the airdrop on the target chain.
+    #[arg(long, env = ZAIR_RECIPIENT, value_name = "ADDRESS")]
+    pub recipient: String,
+    /// Address format the recipient is validated against: `namada`.
+    #[arg(long, env = ZAIR_ADDRESS_FORMAT, default_value = DEFAULT_ADDRESS_FORMAT)]
+    pub address_format: String,
+    /// Free-form note carried with the claim.
+    #[arg(long, env = ZAIR_MEMO)]
+    pub memo: Option<String>,
+    /// Output file for the claim message, to pass as `--message` when signing.
+    #[arg(long, env = ZAIR_MESSAGE_OUT, default_value = DEFAULT_MESSAGE_FILE)]
+    pub output: PathBuf,
+}
+
 /// Claim command group.
 #[derive(Debug, clap::Subcommand)]
 pub enum ClaimCommands {
@@ -277,7 +413,7 @@ pub enum ClaimCommands {
     ))]
     Run {
         #[command(flatten)]
-        args: ClaimRunArgs,
+        args: Box<ClaimRunArgs>,
     },
     /// Prepare the airdrop claim.
     #[command(verbatim_doc_comment)]
@@ -302,4 +438,26 @@ pub enum ClaimCommands {
         #[command(flatten)]
         args: ClaimSignArgs,
     },
+    /// Sign claims for the funded transparent addresses of a seed into a submission package.
+    #[command(group(
+        clap::ArgGroup::new("message_input")
+            .args(["message", "messages"])
+            .required(true)
+            .multiple(true)
+    ))]
+    Transparent {
+        #[command(flatten)]
+        args: ClaimTransparentArgs,
+    },
+    /// Export a signed submission as target-chain transaction payloads.
+    Export {
+        #[command(flatten)]
+        args: ClaimExportArgs,
+    },
+    /// Write a claim message naming the recipient on the target chain, after validating its
+    /// address.
+    Message {
+        #[command(flatten)]
+        args: ClaimMessageArgs,
+    },
 }
@@ -2,20 +2,28 @@
 
 use std::path::PathBuf;
 
-use zair_core::schema::config::ValueCommitmentScheme;
+use zair_core::schema::config::{OrchardGapTreeHash, ValueCommitmentScheme};
 use zair_sdk::common::PoolSelection;
 
 use super::constants::{
-    DEFAULT_CONFIG_FILE, DEFAULT_GAP_TREE_ORCHARD_FILE, DEFAULT_GAP_TREE_SAPLING_FILE,
-    DEFAULT_POOL, DEFAULT_SCHEME, DEFAULT_SNAPSHOT_ORCHARD_FILE, DEFAULT_SNAPSHOT_SAPLING_FILE,
-    DEFAULT_TARGET_ORCHARD, DEFAULT_TARGET_SAPLING, ZAIR_CONFIG_OUT, ZAIR_GAP_TREE_OUT_ORCHARD,
-    ZAIR_GAP_TREE_OUT_SAPLING, ZAIR_NO_GAP_TREE, ZAIR_POOL, ZAIR_SCHEME_ORCHARD,
-    ZAIR_SCHEME_SAPLING, ZAIR_SNAPSHOT_OUT_ORCHARD, ZAIR_SNAPSHOT_OUT_SAPLING, ZAIR_TARGET_ORCHARD,
-    ZAIR_TARGET_SAPLING,
+    DEFAULT_AUDIT_FILE, DEFAULT_AUDIT_MARKDOWN_FILE, DEFAULT_CONFIG_FILE,
+    DEFAULT_CONFIG_SIGNATURE_FILE, DEFAULT_EXCLUSION_REPORT_FILE, DEFAULT_GAP_TREE_HASH,
+    DEFAULT_GAP_TREE_ORCHARD_FILE, DEFAULT_GAP_TREE_SAPLING_FILE, DEFAULT_GAP_TREE_SHARDS_DIR,
+    DEFAULT_ORGANIZER_KEY_FILE, DEFAULT_POOL, DEFAULT_SCHEME, DEFAULT_SNAPSHOT_ORCHARD_FILE,
+    DEFAULT_SNAPSHOT_SAPLING_FILE, DEFAULT_TARGET_ORCHARD, DEFAULT_TARGET_SAPLING,
+    DEFAULT_TARGET_TRANSPARENT, ZAIR_AUDIT_MARKDOWN_OUT, ZAIR_AUDIT_OFFLINE, ZAIR_AUDIT_OUT,
+    ZAIR_AUDIT_RESCAN, ZAIR_AUDITOR, ZAIR_CONFIG_FILE, ZAIR_CONFIG_OUT, ZAIR_CONFIG_SIGNATURE_OUT,
+    ZAIR_EXCLUSION_REPORT_OUT, ZAIR_GAP_TREE_HASH_ORCHARD, ZAIR_GAP_TREE_ORCHARD_FILE,
+    ZAIR_GAP_TREE_OUT_ORCHARD, ZAIR_GAP_TREE_OUT_SAPLING, ZAIR_GAP_TREE_SAPLING_FILE,
+    ZAIR_GAP_TREE_SHARD_DEPTH, ZAIR_GAP_TREE_SHARDS_OUT, ZAIR_LIGHTWALLETD_URL, ZAIR_NO_GAP_TREE,
+    ZAIR_ORGANIZER_KEY_FILE, ZAIR_POOL, ZAIR_SCHEME_ORCHARD, ZAIR_SCHEME_SAPLING,
+    ZAIR_SNAPSHOT_ORCHARD_FILE, ZAIR_SNAPSHOT_OUT_ORCHARD, ZAIR_SNAPSHOT_OUT_SAPLING,
+    ZAIR_SNAPSHOT_SAPLING_FILE, ZAIR_TARGET_ORCHARD, ZAIR_TARGET_SAPLING, ZAIR_TARGET_TRANSPARENT,
+    ZAIR_TRANSPARENT_BALANCES, ZAIR_VALUE_POOLS,
 };
 use super::{
-    BuildConfigArgs, parse_orchard_target_id, parse_pool_selection, parse_sapling_target_id,
-    parse_value_commitment_scheme,
+    BuildConfigArgs, parse_orchard_gap_tree_hash, parse_orchard_target_id, parse_pool_selection,
+    parse_sapling_target_id, parse_transparent_target_id, parse_value_commitment_scheme,
 };
 
 /// Arguments for `config build`.
@@ -32,7 +40,8 @@ pub struct ConfigBuildArgs {
         value_parser = parse_pool_selection
     )]
     pub pool: PoolSelection,
-    /// Sapling target id used for hiding nullifier derivation. Must be exactly 8 bytes.
+    /// Sapling target id used for hiding nullifier derivation. Must be the personalization the
+    /// Sapling claim circuit is built with.
     #[arg(
         long,
         env = ZAIR_TARGET_SAPLING,
@@ -64,9 +73,40 @@ pub struct ConfigBuildArgs {
         value_parser = parse_value_commitment_scheme
     )]
     pub scheme_orchard: ValueCommitmentScheme,
+    /// Orchard gap-tree hash (`sinsemilla` or `poseidon`).
+    #[arg(
+        long,
+        env = ZAIR_GAP_TREE_HASH_ORCHARD,
+        default_value = DEFAULT_GAP_TREE_HASH,
+        value_parser = parse_orchard_gap_tree_hash
+    )]
+    pub gap_tree_hash_orchard: OrchardGapTreeHash,
+    /// Full-node `address,zatoshis` listing of transparent balances at the snapshot height.
+    /// Enables transparent claims for its P2PKH addresses.
+    #[arg(long, env = ZAIR_TRANSPARENT_BALANCES, value_name = "FILE")]
+    pub transparent_balances: Option<PathBuf>,
+    /// Transparent target id used for airdrop nullifier derivation. Must be <= 32 bytes.
+    #[arg(
+        long,
+        env = ZAIR_TARGET_TRANSPARENT,
+        default_value = DEFAULT_TARGET_TRANSPARENT,
+        value_parser = parse_transparent_target_id
+    )]
+    pub target_transparent: String,
+    /// Full-node `getblock <height> 1` JSON at the snapshot height. Writes the value of every
+    /// pool that cannot claim, including Sprout, to `--exclusion-report-out`.
+    #[arg(long, env = ZAIR_VALUE_POOLS, value_name = "FILE")]
+    pub value_pools: Option<PathBuf>,
     /// Configuration output file.
     #[arg(long, env = ZAIR_CONFIG_OUT, default_value = DEFAULT_CONFIG_FILE)]
     pub config_out: PathBuf,
+    /// Exclusion report output file, written when `--value-pools` is set.
+    #[arg(
+        long,
+        env = ZAIR_EXCLUSION_REPORT_OUT,
+        default_value = DEFAULT_EXCLUSION_REPORT_FILE
+    )]
+    pub exclusion_report_out: PathBuf,
     /// Sapling snapshot nullifiers output file.
     #[arg(
         long,
@@ -98,6 +138,104 @@ pub struct ConfigBuildArgs {
     /// Skip writing gap-tree artifacts.
     #[arg(long, env = ZAIR_NO_GAP_TREE, default_value_t = false)]
     pub no_gap_tree: bool,
+    /// Output directory for gap-tree shards, written when `--gap-tree-shard-depth` is set.
+    #[arg(
+        long,
+        env = ZAIR_GAP_TREE_SHARDS_OUT,
+        default_value = DEFAULT_GAP_TREE_SHARDS_DIR
+    )]
+    pub gap_tree_shards_out: PathBuf,
+    /// Split each gap tree into shards of `2^depth` gaps for `--gap-tree-mode sharded` claims.
+    #[arg(long, env = ZAIR_GAP_TREE_SHARD_DEPTH, value_name = "DEPTH")]
+    pub gap_tree_shard_depth: Option<u8>,
+}
+
+/// Arguments for `config audit`.
+#[derive(Debug, clap::Args)]
+pub struct ConfigAuditArgs {
+    /// Airdrop configuration file to audit.
+    #[arg(
+        long,
+        env = ZAIR_CONFIG_FILE,
+        value_name = "CONFIG_FILE",
+        default_value = DEFAULT_CONFIG_FILE
+    )]
+    pub config: PathBuf,
+    /// Sapling snapshot nullifiers file.
+    #[arg(
+        long,
+        env = ZAIR_SNAPSHOT_SAPLING_FILE,
+        default_value = DEFAULT_SNAPSHOT_SAPLING_FILE
+    )]
+    pub snapshot_sapling: PathBuf,
+    /// Orchard snapshot nullifiers file.
+    #[arg(
+        long,
+        env = ZAIR_SNAPSHOT_ORCHARD_FILE,
+        default_value = DEFAULT_SNAPSHOT_ORCHARD_FILE
+    )]
+    pub snapshot_orchard: PathBuf,
+    /// Published Sapling gap-tree file to check against the configured root.
+    #[arg(long, env = ZAIR_GAP_TREE_SAPLING_FILE)]
+    pub gap_tree_sapling: Option<PathBuf>,
+    /// Published Orchard gap-tree file to check against the configured root.
+    #[arg(long, env = ZAIR_GAP_TREE_ORCHARD_FILE)]
+    pub gap_tree_orchard: Option<PathBuf>,
+    /// Transparent balance listing the configuration was built from. Required when the
+    /// configuration has a transparent snapshot.
+    #[arg(long, env = ZAIR_TRANSPARENT_BALANCES, value_name = "FILE")]
+    pub transparent_balances: Option<PathBuf>,
+    /// Optional lightwalletd gRPC endpoint URL override.
+    #[arg(long, env = ZAIR_LIGHTWALLETD_URL)]
+    pub lightwalletd: Option<String>,
+    /// Skip the lightwalletd checks; note commitment roots are left unchecked.
+    #[arg(long, env = ZAIR_AUDIT_OFFLINE, default_value_t = false, conflicts_with = "rescan")]
+    pub offline: bool,
+    /// Re-fetch the snapshot nullifiers from the chain and compare them with the snapshot files.
+    #[arg(long, env = ZAIR_AUDIT_RESCAN, default_value_t = false)]
+    pub rescan: bool,
+    /// Auditor name recorded in the sign-off.
+    #[arg(long, env = ZAIR_AUDITOR)]
+    pub auditor: Option<String>,
+    /// JSON audit report output file.
+    #[arg(long, env = ZAIR_AUDIT_OUT, default_value = DEFAULT_AUDIT_FILE)]
+    pub report_out: PathBuf,
+    /// Markdown audit report output file.
+    #[arg(
+        long,
+        env = ZAIR_AUDIT_MARKDOWN_OUT,
+        default_value = DEFAULT_AUDIT_MARKDOWN_FILE
+    )]
+    pub markdown_out: PathBuf,
+}
+
+/// Arguments for `config sign`.
+#[derive(Debug, clap::Args)]
+pub struct ConfigSignArgs {
+    /// Airdrop configuration file to sign.
+    #[arg(
+        long,
+        env = ZAIR_CONFIG_FILE,
+        value_name = "CONFIG_FILE",
+        default_value = DEFAULT_CONFIG_FILE
+    )]
+    pub config: PathBuf,
+    /// Organizer Ed25519 secret key file (hex), as written by `key generate-organizer`.
+    #[arg(
+        long,
+        env = ZAIR_ORGANIZER_KEY_FILE,
+        value_name = "KEY_FILE",
+        default_value = DEFAULT_ORGANIZER_KEY_FILE
+    )]
+    pub signing_key: PathBuf,
+    /// Output file for the detached signature over the configuration file bytes.
+    #[arg(
+        long,
+        env = ZAIR_CONFIG_SIGNATURE_OUT,
+        value_name = "SIGNATURE_FILE",
+        default_value = DEFAULT_CONFIG_SIGNATURE_FILE
+    )]
+    pub signature_out: PathBuf,
 }
 
 /// Config command group.
@@ -108,4 +246,14 @@ pub enum ConfigCommands {
         #[command(flatten)]
         args: ConfigBuildArgs,
     },
+    /// Recompute a configuration's roots from its snapshot artifacts and write an audit report.
+    Audit {
+        #[command(flatten)]
+        args: ConfigAuditArgs,
+    },
+    /// Sign a configuration with the organizer key so claimants can pin it.
+    Sign {
+        #[command(flatten)]
+        args: ConfigSignArgs,
+    },
 }
@@ -6,6 +6,9 @@
 
 // Common
 pub const ZAIR_CONFIG_FILE: &str = "ZAIR_CONFIG_FILE";
+pub const ZAIR_AIRDROP_TARGET: &str = "ZAIR_AIRDROP_TARGET";
+pub const ZAIR_CURRENT_HEIGHT: &str = "ZAIR_CURRENT_HEIGHT";
+pub const ZAIR_CURRENT_TIME: &str = "ZAIR_CURRENT_TIME";
 pub const ZAIR_SEED_FILE: &str = "ZAIR_SEED_FILE";
 pub const ZAIR_MESSAGE_FILE: &str = "ZAIR_MESSAGE_FILE";
 pub const ZAIR_MESSAGES_FILE: &str = "ZAIR_MESSAGES_FILE";
@@ -14,6 +17,7 @@ pub const ZAIR_NETWORK: &str = "ZAIR_NETWORK";
 pub const ZAIR_LIGHTWALLETD_URL: &str = "ZAIR_LIGHTWALLETD_URL";
 pub const ZAIR_BIRTHDAY: &str = "ZAIR_BIRTHDAY";
 pub const ZAIR_SNAPSHOT_HEIGHT: &str = "ZAIR_SNAPSHOT_HEIGHT";
+pub const ZAIR_OUTPUT_FORMAT: &str = "ZAIR_OUTPUT_FORMAT";
 
 // Snapshot files
 pub const ZAIR_SNAPSHOT_SAPLING_FILE: &str = "ZAIR_SNAPSHOT_SAPLING_FILE";
@@ -23,6 +27,16 @@ pub const ZAIR_SNAPSHOT_ORCHARD_FILE: &str = "ZAIR_SNAPSHOT_ORCHARD_FILE";
 pub const ZAIR_GAP_TREE_SAPLING_FILE: &str = "ZAIR_GAP_TREE_SAPLING_FILE";
 pub const ZAIR_GAP_TREE_ORCHARD_FILE: &str = "ZAIR_GAP_TREE_ORCHARD_FILE";
 pub const ZAIR_GAP_TREE_MODE: &str = "ZAIR_GAP_TREE_MODE";
+pub const ZAIR_WITNESS_SERVER_URL: &str = "ZAIR_WITNESS_SERVER_URL";
+pub const ZAIR_GAP_TREE_SHARDS: &str = "ZAIR_GAP_TREE_SHARDS";
+
+// Serve
+pub const ZAIR_LISTEN_ADDR: &str = "ZAIR_LISTEN_ADDR";
+pub const ZAIR_VERIFY_MAX_CONCURRENT: &str = "ZAIR_VERIFY_MAX_CONCURRENT";
+
+// Registry
+pub const ZAIR_REGISTRY_DIR: &str = "ZAIR_REGISTRY_DIR";
+pub const ZAIR_REGISTRY_EXPORT_OUT: &str = "ZAIR_REGISTRY_EXPORT_OUT";
 
 // Proving keys
 pub const ZAIR_SAPLING_PK_FILE: &str = "ZAIR_SAPLING_PK_FILE";
@@ -49,12 +63,33 @@ pub const ZAIR_TARGET_SAPLING: &str = "ZAIR_TARGET_SAPLING";
 pub const ZAIR_SCHEME_SAPLING: &str = "ZAIR_SCHEME_SAPLING";
 pub const ZAIR_TARGET_ORCHARD: &str = "ZAIR_TARGET_ORCHARD";
 pub const ZAIR_SCHEME_ORCHARD: &str = "ZAIR_SCHEME_ORCHARD";
+pub const ZAIR_GAP_TREE_HASH_ORCHARD: &str = "ZAIR_GAP_TREE_HASH_ORCHARD";
+pub const ZAIR_TARGET_TRANSPARENT: &str = "ZAIR_TARGET_TRANSPARENT";
+pub const ZAIR_TRANSPARENT_BALANCES: &str = "ZAIR_TRANSPARENT_BALANCES";
+pub const ZAIR_VALUE_POOLS: &str = "ZAIR_VALUE_POOLS";
+pub const ZAIR_EXCLUSION_REPORT_OUT: &str = "ZAIR_EXCLUSION_REPORT_OUT";
 pub const ZAIR_CONFIG_OUT: &str = "ZAIR_CONFIG_OUT";
 pub const ZAIR_SNAPSHOT_OUT_SAPLING: &str = "ZAIR_SNAPSHOT_OUT_SAPLING";
 pub const ZAIR_SNAPSHOT_OUT_ORCHARD: &str = "ZAIR_SNAPSHOT_OUT_ORCHARD";
 pub const ZAIR_GAP_TREE_OUT_SAPLING: &str = "ZAIR_GAP_TREE_OUT_SAPLING";
 pub const ZAIR_GAP_TREE_OUT_ORCHARD: &str = "ZAIR_GAP_TREE_OUT_ORCHARD";
 pub const ZAIR_NO_GAP_TREE: &str = "ZAIR_NO_GAP_TREE";
+pub const ZAIR_GAP_TREE_SHARDS_OUT: &str = "ZAIR_GAP_TREE_SHARDS_OUT";
+pub const ZAIR_GAP_TREE_SHARD_DEPTH: &str = "ZAIR_GAP_TREE_SHARD_DEPTH";
+
+// Audit
+pub const ZAIR_AUDIT_OUT: &str = "ZAIR_AUDIT_OUT";
+pub const ZAIR_AUDIT_MARKDOWN_OUT: &str = "ZAIR_AUDIT_MARKDOWN_OUT";
+pub const ZAIR_AUDITOR: &str = "ZAIR_AUDITOR";
+pub const ZAIR_AUDIT_OFFLINE: &str = "ZAIR_AUDIT_OFFLINE";
+pub const ZAIR_AUDIT_RESCAN: &str = "ZAIR_AUDIT_RESCAN";
+
+// Organizer signatures
+pub const ZAIR_ORGANIZER_KEY_FILE: &str = "ZAIR_ORGANIZER_KEY_FILE";
+pub const ZAIR_ORGANIZER_KEY_OUT: &str = "ZAIR_ORGANIZER_KEY_OUT";
+pub const ZAIR_CONFIG_SIGNATURE_OUT: &str = "ZAIR_CONFIG_SIGNATURE_OUT";
+pub const ZAIR_CONFIG_SIGNATURE: &str = "ZAIR_CONFIG_SIGNATURE";
+pub const ZAIR_TRUSTED_ORGANIZER_KEY: &str = "ZAIR_TRUSTED_ORGANIZER_KEY";
 
 // Claim
 pub const ZAIR_CLAIMS_OUT: &str = "ZAIR_CLAIMS_OUT";
@@ -65,7 +100,18 @@ pub const ZAIR_SECRETS_OUT: &str = "ZAIR_SECRETS_OUT";
 pub const ZAIR_SECRETS_IN: &str = "ZAIR_SECRETS_IN";
 pub const ZAIR_SUBMISSION_OUT: &str = "ZAIR_SUBMISSION_OUT";
 pub const ZAIR_SUBMISSION_IN: &str = "ZAIR_SUBMISSION_IN";
+pub const ZAIR_VALID_SUBMISSION_OUT: &str = "ZAIR_VALID_SUBMISSION_OUT";
+pub const ZAIR_EXPORT_FORMAT: &str = "ZAIR_EXPORT_FORMAT";
+pub const ZAIR_EXPORT_OUT: &str = "ZAIR_EXPORT_OUT";
 pub const ZAIR_UFVK_FILE: &str = "ZAIR_UFVK_FILE";
+pub const ZAIR_SCAN_CACHE: &str = "ZAIR_SCAN_CACHE";
+pub const ZAIR_WALLET_DB: &str = "ZAIR_WALLET_DB";
+pub const ZAIR_GAP_LIMIT: &str = "ZAIR_GAP_LIMIT";
+pub const ZAIR_CHAIN_ID: &str = "ZAIR_CHAIN_ID";
+pub const ZAIR_RECIPIENT: &str = "ZAIR_RECIPIENT";
+pub const ZAIR_ADDRESS_FORMAT: &str = "ZAIR_ADDRESS_FORMAT";
+pub const ZAIR_MEMO: &str = "ZAIR_MEMO";
+pub const ZAIR_MESSAGE_OUT: &str = "ZAIR_MESSAGE_OUT";
 
 // -------------------------
 // Default values
@@ -77,6 +123,8 @@ pub const DEFAULT_CLAIMS_FILE: &str = "claim-prepared.json";
 pub const DEFAULT_PROOFS_FILE: &str = "claim-proofs.json";
 pub const DEFAULT_SECRETS_FILE: &str = "claim-proofs-secrets.json";
 pub const DEFAULT_SUBMISSION_FILE: &str = "claim-submission.json";
+pub const DEFAULT_EXPORT_FILE: &str = "claim-export.json";
+pub const DEFAULT_MESSAGE_FILE: &str = "claim-message.json";
 pub const DEFAULT_SAPLING_PK_FILE: &str = "setup-sapling-pk.params";
 pub const DEFAULT_SAPLING_VK_FILE: &str = "setup-sapling-vk.params";
 pub const DEFAULT_ORCHARD_PARAMS_FILE: &str = "setup-orchard-params.bin";
@@ -84,14 +132,26 @@ pub const DEFAULT_SNAPSHOT_SAPLING_FILE: &str = "snapshot-sapling.bin";
 pub const DEFAULT_SNAPSHOT_ORCHARD_FILE: &str = "snapshot-orchard.bin";
 pub const DEFAULT_GAP_TREE_SAPLING_FILE: &str = "gaptree-sapling.bin";
 pub const DEFAULT_GAP_TREE_ORCHARD_FILE: &str = "gaptree-orchard.bin";
+pub const DEFAULT_GAP_TREE_SHARDS_DIR: &str = "gaptree-shards";
 pub const DEFAULT_UFVK_FILE: &str = "ufvk.txt";
+pub const DEFAULT_REGISTRY_DIR: &str = "claim-registry";
+pub const DEFAULT_REGISTRY_EXPORT_FILE: &str = "claim-registry.json";
 pub const DEFAULT_SEED_FILE: &str = "seed.txt";
+pub const DEFAULT_ORGANIZER_KEY_FILE: &str = "organizer-key.txt";
+pub const DEFAULT_CONFIG_SIGNATURE_FILE: &str = "config.sig.json";
+pub const DEFAULT_EXCLUSION_REPORT_FILE: &str = "exclusion-report.json";
+pub const DEFAULT_AUDIT_FILE: &str = "config-audit.json";
+pub const DEFAULT_AUDIT_MARKDOWN_FILE: &str = "config-audit.md";
 
 // Parsed values
 pub const DEFAULT_NETWORK: &str = "mainnet";
+pub const DEFAULT_ADDRESS_FORMAT: &str = "namada";
 pub const DEFAULT_SCHEME: &str = "native";
 pub const DEFAULT_GAP_TREE_MODE: &str = "none";
+pub const DEFAULT_GAP_TREE_HASH: &str = "sinsemilla";
 pub const DEFAULT_ORCHARD_PARAMS_MODE: &str = "auto";
 pub const DEFAULT_POOL: &str = "both";
+pub const DEFAULT_LISTEN_ADDR: &str = "127.0.0.1:8080";
 pub const DEFAULT_TARGET_SAPLING: &str = "ZAIRTEST";
 pub const DEFAULT_TARGET_ORCHARD: &str = "ZAIRTEST:O";
+pub const DEFAULT_TARGET_TRANSPARENT: &str = "ZAIRTEST:T";
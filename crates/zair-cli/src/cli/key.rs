@@ -6,9 +6,9 @@ use clap::ArgGroup;
 use zcash_protocol::consensus::Network;
 
 use super::constants::{
-    DEFAULT_NETWORK, DEFAULT_SEED_FILE, DEFAULT_UFVK_FILE, ZAIR_ACCOUNT_ID, ZAIR_MNEMONIC_FILE,
-    ZAIR_MNEMONIC_STDIN, ZAIR_NETWORK, ZAIR_NO_PASSPHRASE, ZAIR_SEED_FILE, ZAIR_SEED_OUT,
-    ZAIR_UFVK_OUT,
+    DEFAULT_NETWORK, DEFAULT_ORGANIZER_KEY_FILE, DEFAULT_SEED_FILE, DEFAULT_UFVK_FILE,
+    ZAIR_ACCOUNT_ID, ZAIR_MNEMONIC_FILE, ZAIR_MNEMONIC_STDIN, ZAIR_NETWORK, ZAIR_NO_PASSPHRASE,
+    ZAIR_ORGANIZER_KEY_OUT, ZAIR_SEED_FILE, ZAIR_SEED_OUT, ZAIR_UFVK_OUT,
 };
 use super::parse_network;
 
@@ -69,6 +69,14 @@ pub struct DeriveUfvkArgs {
     pub output: PathBuf,
 }
 
+/// Arguments for `zair key generate-organizer`.
+#[derive(Debug, clap::Args)]
+pub struct GenerateOrganizerArgs {
+    /// Output file for the organizer Ed25519 secret key (hex).
+    #[arg(long, env = ZAIR_ORGANIZER_KEY_OUT, default_value = DEFAULT_ORGANIZER_KEY_FILE)]
+    pub output: PathBuf,
+}
+
 /// Key command group.
 #[derive(Debug, clap::Subcommand)]
 pub enum KeyCommands {
@@ -93,4 +101,10 @@ pub enum KeyCommands {
         #[command(flatten)]
         args: DeriveUfvkArgs,
     },
+
+    /// Generate an organizer Ed25519 key for signing airdrop configurations.
+    GenerateOrganizer {
+        #[command(flatten)]
+        args: GenerateOrganizerArgs,
+    },
 }
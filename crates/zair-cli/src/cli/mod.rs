@@ -4,30 +4,53 @@ mod claim;
 mod config;
 pub mod constants;
 mod key;
+mod registry;
+mod serve;
 #[cfg(feature = "prove")]
 mod setup;
 mod verify;
 
+use std::path::PathBuf;
+
 use clap::Parser;
 use eyre::{Result, ensure, eyre};
-use zair_core::schema::config::ValueCommitmentScheme;
-use zair_sdk::commands::{GapTreeMode, OrchardParamsMode};
+use zair_core::base::SAPLING_HIDING_NF_PERSONALIZATION;
+use zair_core::schema::config::{OrchardGapTreeHash, ValueCommitmentScheme};
+use zair_sdk::commands::{
+    ExportFormat, GapTreeMode, OrchardParamsMode, TrustedOrganizer, parse_organizer_public_key,
+};
 use zair_sdk::common::{CommonConfig, PoolSelection};
 use zcash_protocol::consensus::Network;
 
 pub use self::claim::ClaimCommands;
 pub use self::config::ConfigCommands;
-use self::constants::{DEFAULT_NETWORK, ZAIR_LIGHTWALLETD_URL, ZAIR_NETWORK, ZAIR_SNAPSHOT_HEIGHT};
+use self::constants::{
+    DEFAULT_CONFIG_SIGNATURE_FILE, DEFAULT_NETWORK, ZAIR_CONFIG_SIGNATURE, ZAIR_CURRENT_HEIGHT,
+    ZAIR_CURRENT_TIME, ZAIR_LIGHTWALLETD_URL, ZAIR_NETWORK, ZAIR_OUTPUT_FORMAT,
+    ZAIR_SNAPSHOT_HEIGHT, ZAIR_TRUSTED_ORGANIZER_KEY,
+};
 pub use self::key::KeyCommands;
+pub use self::registry::RegistryCommands;
+pub use self::serve::ServeCommands;
 #[cfg(feature = "prove")]
 pub use self::setup::SetupCommands;
 pub use self::verify::VerifyCommands;
+use crate::output::OutputFormat;
 
 /// Command-line interface definition.
 #[derive(Debug, Parser)]
 #[command(name = "zair")]
 #[command(about = "Zcash airdrop tools")]
 pub struct Cli {
+    /// Result format: `text` (logs only) or `json` (one result document on stdout, logs on
+    /// stderr). Must precede the subcommand.
+    #[arg(
+        long,
+        env = ZAIR_OUTPUT_FORMAT,
+        default_value = "text",
+        value_parser = parse_output_format
+    )]
+    pub output: OutputFormat,
     /// CLI top-level command group.
     #[command(subcommand)]
     pub command: Commands,
@@ -67,6 +90,63 @@ pub enum Commands {
         #[command(subcommand)]
         command: VerifyCommands,
     },
+    /// Claimed airdrop-nullifier registry for verifiers.
+    Registry {
+        /// Registry subcommands.
+        #[command(subcommand)]
+        command: RegistryCommands,
+    },
+    /// Long-running services.
+    Serve {
+        /// Serve subcommands.
+        #[command(subcommand)]
+        command: ServeCommands,
+    },
+}
+
+/// Organizer key an airdrop configuration must be signed with.
+#[derive(Debug, clap::Args)]
+pub struct TrustedOrganizerArgs {
+    /// Ed25519 public key (hex) of the airdrop organizer. The configuration is refused unless
+    /// it is signed by this key.
+    #[arg(
+        long,
+        env = ZAIR_TRUSTED_ORGANIZER_KEY,
+        value_name = "PUBLIC_KEY",
+        value_parser = parse_organizer_key
+    )]
+    pub trusted_organizer_key: Option<[u8; 32]>,
+    /// Detached signature file written by `config sign`, checked against the trusted organizer
+    /// key.
+    #[arg(
+        long,
+        env = ZAIR_CONFIG_SIGNATURE,
+        value_name = "SIGNATURE_FILE",
+        default_value = DEFAULT_CONFIG_SIGNATURE_FILE
+    )]
+    pub config_signature: PathBuf,
+}
+
+/// Target-chain position a claim window is checked against.
+#[derive(Debug, clap::Args)]
+pub struct ChainClockArgs {
+    /// Current target-chain block height. Required when the claim window is bounded by height.
+    #[arg(long, env = ZAIR_CURRENT_HEIGHT, value_name = "HEIGHT")]
+    pub current_height: Option<u64>,
+    /// Current target-chain time in seconds since the Unix epoch. Defaults to the system clock.
+    #[arg(long, env = ZAIR_CURRENT_TIME, value_name = "UNIX_SECONDS")]
+    pub current_time: Option<u64>,
+}
+
+impl TrustedOrganizerArgs {
+    /// Organizer the configuration must be signed by, if a trusted key is set.
+    pub fn into_trusted_organizer(self) -> Option<TrustedOrganizer> {
+        self.trusted_organizer_key
+            .map(|public_key| TrustedOrganizer {
+                public_key,
+                signature_file: self.config_signature,
+            })
+    }
 }
 
 /// Common arguments for `config build`.
@@ -108,6 +188,10 @@ pub fn parse_network(s: &str) -> Result<Network> {
     }
 }
 
+pub fn parse_organizer_key(s: &str) -> Result<[u8; 32]> {
+    parse_organizer_public_key(s)
+}
+
 pub fn parse_pool_selection(s: &str) -> Result<PoolSelection> {
     match s {
         "sapling" => Ok(PoolSelection::Sapling),
@@ -120,7 +204,12 @@ pub fn parse_pool_selection(s: &str) -> Result<PoolSelection> {
 }
 
 pub fn parse_sapling_target_id(s: &str) -> Result<String> {
-    ensure!(s.len() == 8, "Sapling target_id must be exactly 8 bytes");
+    ensure!(
+        s.as_bytes() == SAPLING_HIDING_NF_PERSONALIZATION,
+        "Sapling target_id must be {:?}, the hiding nullifier personalization of the Sapling \
+         claim circuit",
+        String::from_utf8_lossy(&SAPLING_HIDING_NF_PERSONALIZATION)
+    );
     Ok(s.to_string())
 }
 
@@ -129,6 +218,14 @@ pub fn parse_orchard_target_id(s: &str) -> Result<String> {
     Ok(s.to_string())
 }
 
+pub fn parse_transparent_target_id(s: &str) -> Result<String> {
+    ensure!(
+        s.len() <= 32,
+        "Transparent target_id must be at most 32 bytes"
+    );
+    Ok(s.to_string())
+}
+
 pub fn parse_value_commitment_scheme(s: &str) -> Result<ValueCommitmentScheme> {
     match s {
         "native" => Ok(ValueCommitmentScheme::Native),
@@ -139,13 +236,43 @@ pub fn parse_value_commitment_scheme(s: &str) -> Result<ValueCommitmentScheme> {
     }
 }
 
+pub fn parse_orchard_gap_tree_hash(s: &str) -> Result<OrchardGapTreeHash> {
+    match s {
+        "sinsemilla" => Ok(OrchardGapTreeHash::Sinsemilla),
+        "poseidon" => Ok(OrchardGapTreeHash::Poseidon),
+        other => Err(eyre!(
+            "Invalid Orchard gap-tree hash: {other}. Expected 'sinsemilla' or 'poseidon'."
+        )),
+    }
+}
+
 pub fn parse_gap_tree_mode(s: &str) -> Result<GapTreeMode> {
     match s {
         "none" => Ok(GapTreeMode::None),
         "rebuild" => Ok(GapTreeMode::Rebuild),
         "sparse" => Ok(GapTreeMode::Sparse),
+        "remote" => Ok(GapTreeMode::Remote),
+        "sharded" => Ok(GapTreeMode::Sharded),
+        other => Err(eyre!(
+            "Invalid gap-tree mode: {other}. Expected 'none', 'rebuild', 'sparse', 'remote', or \
+             'sharded'."
+        )),
+    }
+}
+
+pub fn parse_export_format(s: &str) -> Result<ExportFormat> {
+    match s {
+        "namada" => Ok(ExportFormat::Namada),
+        other => Err(eyre!("Invalid export format: {other}. Expected 'namada'.")),
+    }
+}
+
+pub fn parse_output_format(s: &str) -> Result<OutputFormat> {
+    match s {
+        "text" => Ok(OutputFormat::Text),
+        "json" => Ok(OutputFormat::Json),
         other => Err(eyre!(
-            "Invalid gap-tree mode: {other}. Expected 'none', 'rebuild', or 'sparse'."
+            "Invalid output format: {other}. Expected 'text' or 'json'."
         )),
     }
 }
@@ -162,7 +289,6 @@ pub fn parse_orchard_params_mode(s: &str) -> Result<OrchardParamsMode> {
 
 #[cfg(test)]
 mod tests {
-    use clap::Parser as _;
 
     use super::*;
 
@@ -175,6 +301,16 @@ mod tests {
         assert!(parse_network("invalid_network").is_err());
     }
 
+    #[test]
+    fn organizer_key_parse() {
+        assert_eq!(
+            parse_organizer_key(&"ab".repeat(32)).expect("key should parse"),
+            [0xab; 32]
+        );
+        assert!(parse_organizer_key("abcd").is_err());
+        assert!(parse_organizer_key(&"zz".repeat(32)).is_err());
+    }
+
     #[test]
     fn pool_selection_parse() {
         assert!(matches!(
@@ -206,9 +342,52 @@ mod tests {
             parse_gap_tree_mode("sparse").expect("sparse should parse"),
             GapTreeMode::Sparse
         ));
+        assert!(matches!(
+            parse_gap_tree_mode("remote").expect("remote should parse"),
+            GapTreeMode::Remote
+        ));
+        assert!(matches!(
+            parse_gap_tree_mode("sharded").expect("sharded should parse"),
+            GapTreeMode::Sharded
+        ));
         assert!(parse_gap_tree_mode("invalid").is_err());
     }
 
+    #[test]
+    fn output_format_parse() {
+        assert_eq!(
+            parse_output_format("json").expect("json should parse"),
+            OutputFormat::Json
+        );
+        assert_eq!(
+            parse_output_format("text").expect("text should parse"),
+            OutputFormat::Text
+        );
+        assert!(parse_output_format("yaml").is_err());
+    }
+
+    #[test]
+    fn export_format_parse() {
+        assert_eq!(
+            parse_export_format("namada").expect("namada should parse"),
+            ExportFormat::Namada
+        );
+        assert!(parse_export_format("cosmos").is_err());
+    }
+
+    #[test]
+    fn orchard_gap_tree_hash_parse() {
+        assert_eq!(
+            parse_orchard_gap_tree_hash("sinsemilla").expect("sinsemilla should parse"),
+            OrchardGapTreeHash::Sinsemilla
+        );
+        assert_eq!(
+            parse_orchard_gap_tree_hash("poseidon").expect("poseidon should parse"),
+            OrchardGapTreeHash::Poseidon
+        );
+        assert!(parse_orchard_gap_tree_hash("blake2b").is_err());
+    }
+
     #[test]
     fn orchard_params_mode_parse() {
         assert!(matches!(
@@ -250,6 +429,22 @@ mod tests {
         assert!(cli.is_ok());
     }
 
+    #[test]
+    fn parse_serve_witnesses_command() {
+        let cli = Cli::try_parse_from(["zair", "serve", "witnesses", "--listen", "0.0.0.0:9000"])
+            .expect("serve witnesses should parse");
+        let Commands::Serve {
+            command: ServeCommands::Witnesses { args },
+        } = cli.command
+        else {
+            panic!("expected serve witnesses command");
+        };
+        assert_eq!(args.listen.port(), 9000);
+
+        let cli = Cli::try_parse_from(["zair", "serve", "witnesses", "--listen", "not-an-addr"]);
+        assert!(cli.is_err());
+    }
+
     #[test]
     fn parse_verify_run_command_requires_message_input() {
         let cli = Cli::try_parse_from(["zair", "verify", "run"]);
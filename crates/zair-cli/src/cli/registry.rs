@@ -0,0 +1,70 @@
+//! Claim registry subcommands.
+
+use std::path::PathBuf;
+
+use super::TrustedOrganizerArgs;
+use super::constants::{
+    DEFAULT_CONFIG_FILE, DEFAULT_REGISTRY_DIR, DEFAULT_REGISTRY_EXPORT_FILE,
+    DEFAULT_SUBMISSION_FILE, ZAIR_AIRDROP_TARGET, ZAIR_CONFIG_FILE, ZAIR_REGISTRY_DIR,
+    ZAIR_REGISTRY_EXPORT_OUT, ZAIR_SUBMISSION_IN,
+};
+
+/// Arguments for commands that read a signed submission against the registry.
+#[derive(Debug, clap::Args)]
+pub struct RegistrySubmissionArgs {
+    /// Claim registry database directory.
+    #[arg(long, env = ZAIR_REGISTRY_DIR, default_value = DEFAULT_REGISTRY_DIR)]
+    pub registry: PathBuf,
+    /// Signed submission file generated by `claim sign`.
+    #[arg(long, env = ZAIR_SUBMISSION_IN, default_value = DEFAULT_SUBMISSION_FILE)]
+    pub submission_in: PathBuf,
+    /// Airdrop configuration file, whose target ids key the recorded claims.
+    #[arg(
+        long,
+        env = ZAIR_CONFIG_FILE,
+        value_name = "CONFIG_FILE",
+        default_value = DEFAULT_CONFIG_FILE
+    )]
+    pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
+    /// Airdrop target of the configuration the claims were made for, instead of its default
+    /// airdrop.
+    #[arg(long, env = ZAIR_AIRDROP_TARGET, value_name = "NAME")]
+    pub target: Option<String>,
+}
+
+/// Arguments for `zair registry export`.
+#[derive(Debug, clap::Args)]
+pub struct RegistryExportArgs {
+    /// Claim registry database directory.
+    #[arg(long, env = ZAIR_REGISTRY_DIR, default_value = DEFAULT_REGISTRY_DIR)]
+    pub registry: PathBuf,
+    /// Output JSON file for the recorded claims.
+    #[arg(long, env = ZAIR_REGISTRY_EXPORT_OUT, default_value = DEFAULT_REGISTRY_EXPORT_FILE)]
+    pub output: PathBuf,
+}
+
+/// Registry command group.
+#[derive(Debug, clap::Subcommand)]
+pub enum RegistryCommands {
+    /// Record an already verified submission's claims, rejecting replays.
+    Import {
+        #[command(flatten)]
+        args: RegistrySubmissionArgs,
+        /// Acknowledge that the submission is not verified by this command. Use
+        /// `verify run --registry` to verify and record in one step.
+        #[arg(long)]
+        unverified: bool,
+    },
+    /// Check that none of a submission's claims have been recorded.
+    Check {
+        #[command(flatten)]
+        args: RegistrySubmissionArgs,
+    },
+    /// Export all recorded claims as JSON.
+    Export {
+        #[command(flatten)]
+        args: RegistryExportArgs,
+    },
+}
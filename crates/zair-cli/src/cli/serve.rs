@@ -0,0 +1,113 @@
+//! Serve subcommands.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use zair_sdk::commands::{DEFAULT_MAX_CONCURRENT_VERIFICATIONS, OrchardParamsMode};
+
+use super::constants::{
+    DEFAULT_CONFIG_FILE, DEFAULT_LISTEN_ADDR, DEFAULT_ORCHARD_PARAMS_FILE,
+    DEFAULT_ORCHARD_PARAMS_MODE, DEFAULT_SAPLING_VK_FILE, ZAIR_AIRDROP_TARGET, ZAIR_CONFIG_FILE,
+    ZAIR_GAP_TREE_ORCHARD_FILE, ZAIR_GAP_TREE_SAPLING_FILE, ZAIR_LISTEN_ADDR,
+    ZAIR_ORCHARD_PARAMS_FILE, ZAIR_ORCHARD_PARAMS_MODE, ZAIR_SAPLING_VK_FILE,
+    ZAIR_SNAPSHOT_ORCHARD_FILE, ZAIR_SNAPSHOT_SAPLING_FILE, ZAIR_VERIFY_MAX_CONCURRENT,
+};
+use super::{ChainClockArgs, TrustedOrganizerArgs, parse_orchard_params_mode};
+
+/// Arguments for the gap-tree witness server.
+#[derive(Debug, clap::Args)]
+pub struct ServeWitnessesArgs {
+    /// Airdrop configuration file whose roots the served trees must match.
+    #[arg(
+        long,
+        env = ZAIR_CONFIG_FILE,
+        value_name = "CONFIG_FILE",
+        default_value = DEFAULT_CONFIG_FILE
+    )]
+    pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
+    /// Sapling snapshot nullifiers file.
+    /// Defaults to `snapshot-sapling.bin` when Sapling is enabled in config.
+    #[arg(long, env = ZAIR_SNAPSHOT_SAPLING_FILE)]
+    pub snapshot_sapling: Option<PathBuf>,
+    /// Orchard snapshot nullifiers file.
+    /// Defaults to `snapshot-orchard.bin` when Orchard is enabled in config.
+    #[arg(long, env = ZAIR_SNAPSHOT_ORCHARD_FILE)]
+    pub snapshot_orchard: Option<PathBuf>,
+    /// Sapling gap-tree file. Defaults to `gaptree-sapling.bin` when Sapling is enabled.
+    #[arg(long, env = ZAIR_GAP_TREE_SAPLING_FILE)]
+    pub gap_tree_sapling: Option<PathBuf>,
+    /// Orchard gap-tree file. Defaults to `gaptree-orchard.bin` when Orchard is enabled.
+    #[arg(long, env = ZAIR_GAP_TREE_ORCHARD_FILE)]
+    pub gap_tree_orchard: Option<PathBuf>,
+    /// Socket address to listen on.
+    #[arg(long, env = ZAIR_LISTEN_ADDR, default_value = DEFAULT_LISTEN_ADDR)]
+    pub listen: SocketAddr,
+}
+
+/// Arguments for the claim verification server.
+#[derive(Debug, clap::Args)]
+pub struct ServeVerifyArgs {
+    /// Airdrop configuration file used for proof/signature binding checks.
+    #[arg(
+        long,
+        env = ZAIR_CONFIG_FILE,
+        value_name = "CONFIG_FILE",
+        default_value = DEFAULT_CONFIG_FILE
+    )]
+    pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
+    /// Airdrop target of the configuration whose claims are verified, instead of its default
+    /// airdrop.
+    #[arg(long, env = ZAIR_AIRDROP_TARGET, value_name = "NAME")]
+    pub target: Option<String>,
+    #[command(flatten)]
+    pub clock: ChainClockArgs,
+    /// Path to the Sapling verifying key file.
+    #[arg(
+        long = "sapling-vk",
+        env = ZAIR_SAPLING_VK_FILE,
+        value_name = "SAPLING_VK_FILE",
+        default_value = DEFAULT_SAPLING_VK_FILE
+    )]
+    pub sapling_vk: PathBuf,
+    /// Path to the Orchard Halo2 params file.
+    #[arg(
+        long,
+        env = ZAIR_ORCHARD_PARAMS_FILE,
+        value_name = "ORCHARD_PARAMS_FILE",
+        default_value = DEFAULT_ORCHARD_PARAMS_FILE
+    )]
+    pub orchard_params: PathBuf,
+    /// Orchard params handling mode: `require` (fail if missing) or `auto` (generate and persist).
+    #[arg(
+        long,
+        env = ZAIR_ORCHARD_PARAMS_MODE,
+        default_value = DEFAULT_ORCHARD_PARAMS_MODE,
+        value_parser = parse_orchard_params_mode
+    )]
+    pub orchard_params_mode: OrchardParamsMode,
+    /// Maximum number of submissions verified concurrently.
+    #[arg(long, env = ZAIR_VERIFY_MAX_CONCURRENT, default_value_t = DEFAULT_MAX_CONCURRENT_VERIFICATIONS)]
+    pub max_concurrent: usize,
+    /// Socket address to listen on.
+    #[arg(long, env = ZAIR_LISTEN_ADDR, default_value = DEFAULT_LISTEN_ADDR)]
+    pub listen: SocketAddr,
+}
+
+/// Serve command group.
+#[derive(Debug, clap::Subcommand)]
+pub enum ServeCommands {
+    /// Serve gap-tree witnesses for `claim prepare --gap-tree-mode remote`.
+    Witnesses {
+        #[command(flatten)]
+        args: ServeWitnessesArgs,
+    },
+    /// Serve claim submission verification over HTTP.
+    Verify {
+        #[command(flatten)]
+        args: ServeVerifyArgs,
+    },
+}
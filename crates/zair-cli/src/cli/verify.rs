@@ -6,11 +6,12 @@ use zair_sdk::commands::OrchardParamsMode;
 
 use super::constants::{
     DEFAULT_CONFIG_FILE, DEFAULT_ORCHARD_PARAMS_FILE, DEFAULT_ORCHARD_PARAMS_MODE,
-    DEFAULT_PROOFS_FILE, DEFAULT_SAPLING_VK_FILE, DEFAULT_SUBMISSION_FILE, ZAIR_CONFIG_FILE,
-    ZAIR_MESSAGE_FILE, ZAIR_MESSAGES_FILE, ZAIR_ORCHARD_PARAMS_FILE, ZAIR_ORCHARD_PARAMS_MODE,
-    ZAIR_PROOFS_IN, ZAIR_SAPLING_VK_FILE, ZAIR_SUBMISSION_IN,
+    DEFAULT_PROOFS_FILE, DEFAULT_SAPLING_VK_FILE, DEFAULT_SUBMISSION_FILE, ZAIR_AIRDROP_TARGET,
+    ZAIR_CONFIG_FILE, ZAIR_MESSAGE_FILE, ZAIR_MESSAGES_FILE, ZAIR_ORCHARD_PARAMS_FILE,
+    ZAIR_ORCHARD_PARAMS_MODE, ZAIR_PROOFS_IN, ZAIR_REGISTRY_DIR, ZAIR_SAPLING_VK_FILE,
+    ZAIR_SUBMISSION_IN, ZAIR_VALID_SUBMISSION_OUT,
 };
-use super::parse_orchard_params_mode;
+use super::{ChainClockArgs, TrustedOrganizerArgs, parse_orchard_params_mode};
 
 /// Arguments for end-to-end verification.
 #[derive(Debug, clap::Args)]
@@ -23,6 +24,14 @@ pub struct VerifyRunArgs {
         default_value = DEFAULT_CONFIG_FILE
     )]
     pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
+    /// Airdrop target of the configuration the claims were made for, instead of its default
+    /// airdrop.
+    #[arg(long, env = ZAIR_AIRDROP_TARGET, value_name = "NAME")]
+    pub target: Option<String>,
+    #[command(flatten)]
+    pub clock: ChainClockArgs,
     /// Path to the Sapling verifying key file.
     #[arg(
         long = "sapling-vk",
@@ -56,6 +65,12 @@ pub struct VerifyRunArgs {
     /// Per-claim message assignments JSON.
     #[arg(long = "messages", env = ZAIR_MESSAGES_FILE, value_name = "MESSAGES_FILE")]
     pub messages: Option<PathBuf>,
+    /// Claim registry directory: reject already-recorded claims and record verified ones.
+    #[arg(long, env = ZAIR_REGISTRY_DIR, value_name = "REGISTRY_DIR")]
+    pub registry: Option<PathBuf>,
+    /// Write the claims that pass every check to this file as a filtered submission.
+    #[arg(long, env = ZAIR_VALID_SUBMISSION_OUT, value_name = "VALID_SUBMISSION_FILE")]
+    pub valid_submission_out: Option<PathBuf>,
 }
 
 /// Arguments for proof verification.
@@ -69,6 +84,12 @@ pub struct VerifyProofArgs {
         default_value = DEFAULT_CONFIG_FILE
     )]
     pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
+    /// Airdrop target of the configuration the claims were made for, instead of its default
+    /// airdrop.
+    #[arg(long, env = ZAIR_AIRDROP_TARGET, value_name = "NAME")]
+    pub target: Option<String>,
     /// Path to the Sapling verifying key file.
     #[arg(
         long = "sapling-vk",
@@ -109,6 +130,14 @@ pub struct VerifySignatureArgs {
         default_value = DEFAULT_CONFIG_FILE
     )]
     pub config: PathBuf,
+    #[command(flatten)]
+    pub organizer: TrustedOrganizerArgs,
+    /// Airdrop target of the configuration the claims were made for, instead of its default
+    /// airdrop.
+    #[arg(long, env = ZAIR_AIRDROP_TARGET, value_name = "NAME")]
+    pub target: Option<String>,
+    #[command(flatten)]
+    pub clock: ChainClockArgs,
     /// Signed submission file generated by `claim sign`.
     #[arg(long, env = ZAIR_SUBMISSION_IN, default_value = DEFAULT_SUBMISSION_FILE)]
     pub submission_in: PathBuf,
@@ -1,15 +1,25 @@
 //! ZAIR CLI Application
 
 mod cli;
+mod output;
 
-use clap::Parser as _;
+use std::io::Write as _;
+
+use clap::{CommandFactory as _, FromArgMatches as _};
 #[cfg(feature = "prove")]
 use cli::SetupCommands;
-use cli::{ClaimCommands, Cli, Commands, ConfigCommands, KeyCommands, VerifyCommands};
+use cli::{
+    ClaimCommands, Cli, Commands, ConfigCommands, KeyCommands, RegistryCommands, ServeCommands,
+    VerifyCommands,
+};
 use eyre::Context as _;
-use zair_sdk::commands::build_airdrop_configuration;
+use output::{Document, OutputFormat};
+use serde_json::{Value, json};
+use zair_sdk::commands::{ErrorClass, build_airdrop_configuration};
+use zair_sdk::progress::Progress;
 
-fn init_tracing() -> eyre::Result<()> {
+/// Initialize tracing; in JSON mode logs go to stderr so stdout carries only the result.
+fn init_tracing(format: OutputFormat) -> eyre::Result<()> {
     #[cfg(feature = "tokio-console")]
     {
         // tokio-console: layers the console subscriber with fmt
@@ -17,10 +27,18 @@ fn init_tracing() -> eyre::Result<()> {
         tracing_subscriber::registry()
             .with(console_subscriber::spawn())
             .with(
-                tracing_subscriber::fmt::layer().with_filter(
-                    tracing_subscriber::EnvFilter::try_from_default_env()
-                        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-                ),
+                tracing_subscriber::fmt::layer()
+                    .with_writer(move || -> Box<dyn std::io::Write> {
+                        if format == OutputFormat::Json {
+                            Box::new(std::io::stderr())
+                        } else {
+                            Box::new(std::io::stdout())
+                        }
+                    })
+                    .with_filter(
+                        tracing_subscriber::EnvFilter::try_from_default_env()
+                            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+                    ),
             )
             .try_init()
             .map_err(|e| eyre::eyre!("Failed to initialize tracing: {:?}", e))?;
@@ -35,6 +53,13 @@ fn init_tracing() -> eyre::Result<()> {
             )
             .with_timer(tracing_subscriber::fmt::time::uptime())
             .with_target(false)
+            .with_writer(move || -> Box<dyn std::io::Write> {
+                if format == OutputFormat::Json {
+                    Box::new(std::io::stderr())
+                } else {
+                    Box::new(std::io::stdout())
+                }
+            })
             .try_init()
             .map_err(|e| eyre::eyre!("Failed to initialize tracing: {:?}", e))?;
     }
@@ -43,10 +68,6 @@ fn init_tracing() -> eyre::Result<()> {
 }
 
 #[tokio::main(flavor = "multi_thread")]
-#[allow(
-    clippy::too_many_lines,
-    reason = "Top-level CLI dispatch keeps all command wiring in one place"
-)]
 async fn main() -> eyre::Result<()> {
     // Initialize rustls crypto provider (required for TLS connections)
     rustls::crypto::ring::default_provider()
@@ -56,24 +77,56 @@ async fn main() -> eyre::Result<()> {
     // Load .env file (fails silently if not found)
     let _ = dotenvy::dotenv();
 
-    init_tracing()?;
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let format = cli.output;
+    init_tracing(format)?;
 
-    let cli = Cli::parse();
+    let res = run(cli.command).await;
 
-    let res = match cli.command {
+    if format == OutputFormat::Json {
+        let document = Document::new(command_path(&matches), &res);
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer(&mut stdout, &document)?;
+        writeln!(stdout)?;
+    }
+    if let Err(e) = res {
+        tracing::error!("Error: {:?}", e);
+        std::process::exit(ErrorClass::of(&e).exit_code());
+    }
+
+    Ok(())
+}
+
+/// Run `command` and return its result document.
+#[allow(
+    clippy::too_many_lines,
+    reason = "Top-level CLI dispatch keeps all command wiring in one place"
+)]
+async fn run(command: Commands) -> eyre::Result<Value> {
+    match command {
         #[cfg(feature = "prove")]
         Commands::Setup { command } => match command {
             SetupCommands::Sapling {
                 scheme,
                 pk_out,
                 vk_out,
-            } => zair_sdk::commands::generate_claim_params(pk_out, vk_out, scheme).await,
+            } => zair_sdk::commands::generate_claim_params(pk_out.clone(), vk_out.clone(), scheme)
+                .await
+                .map(|()| json!({ "proving_key": pk_out, "verifying_key": vk_out })),
             SetupCommands::Orchard { scheme, params_out } => {
-                zair_sdk::commands::generate_orchard_params(params_out, scheme).await
+                zair_sdk::commands::generate_orchard_params(params_out.clone(), scheme)
+                    .await
+                    .map(|()| json!({ "params": params_out }))
             }
         },
         Commands::Config { command } => match command {
             ConfigCommands::Build { args } => {
+                let config_out = args.config_out.clone();
+                let exclusion_report = args
+                    .value_pools
+                    .is_some()
+                    .then(|| args.exclusion_report_out.clone());
                 build_airdrop_configuration(
                     args.config.into(),
                     args.pool,
@@ -83,17 +136,65 @@ async fn main() -> eyre::Result<()> {
                     args.gap_tree_out_sapling,
                     args.gap_tree_out_orchard,
                     args.no_gap_tree,
+                    args.gap_tree_shards_out,
+                    args.gap_tree_shard_depth,
                     args.target_sapling,
                     args.scheme_sapling,
                     args.target_orchard,
                     args.scheme_orchard,
+                    args.gap_tree_hash_orchard,
+                    args.transparent_balances,
+                    args.target_transparent,
+                    args.value_pools,
+                    args.exclusion_report_out,
+                    &Progress::default(),
+                )
+                .await
+                .map(|configuration| {
+                    json!({
+                        "config_out": config_out,
+                        "exclusion_report": exclusion_report,
+                        "configuration": configuration,
+                    })
+                })
+            }
+            ConfigCommands::Audit { args } => zair_sdk::commands::audit_airdrop_configuration(
+                args.config,
+                args.snapshot_sapling,
+                args.snapshot_orchard,
+                args.gap_tree_sapling,
+                args.gap_tree_orchard,
+                args.transparent_balances,
+                args.lightwalletd,
+                args.offline,
+                args.rescan,
+                args.auditor,
+                args.report_out,
+                args.markdown_out,
+            )
+            .await
+            .and_then(|report| Ok(serde_json::to_value(report)?)),
+            ConfigCommands::Sign { args } => {
+                let (config, signature_out) = (args.config.clone(), args.signature_out.clone());
+                zair_sdk::commands::sign_airdrop_configuration(
+                    args.config,
+                    args.signing_key,
+                    args.signature_out,
                 )
                 .await
+                .map(|signature| {
+                    json!({
+                        "config": config,
+                        "signature": signature_out,
+                        "public_key": hex::encode(signature.public_key),
+                    })
+                })
             }
         },
         Commands::Claim { command } => match command {
             #[cfg(feature = "prove")]
             ClaimCommands::Run { args } => {
+                let submission_out = args.submission_out.clone();
                 zair_sdk::commands::claim_run(
                     args.lightwalletd,
                     args.snapshot_sapling,
@@ -101,7 +202,11 @@ async fn main() -> eyre::Result<()> {
                     args.gap_tree_sapling,
                     args.gap_tree_orchard,
                     args.gap_tree_mode,
+                    args.witness_server,
+                    args.gap_tree_shards,
                     args.birthday,
+                    args.scan_cache,
+                    args.wallet_db,
                     args.claims_out,
                     args.proofs_out,
                     args.secrets_out,
@@ -114,13 +219,23 @@ async fn main() -> eyre::Result<()> {
                     args.message,
                     args.messages,
                     args.config,
+                    args.target,
+                    args.organizer.into_trusted_organizer(),
                 )
                 .await
+                .map(|run| {
+                    json!({
+                        "submission_out": submission_out,
+                        "notes": output::claim_inputs_summary(&run.claims),
+                        "submission": output::submission_summary(&run.submission),
+                    })
+                })
             }
             ClaimCommands::Prepare { args } => {
                 let ufvk = tokio::fs::read_to_string(&args.ufvk)
                     .await
                     .with_context(|| format!("Failed to read UFVK file {}", args.ufvk.display()))?;
+                let claims_out = args.claims_out.clone();
                 zair_sdk::commands::airdrop_claim(
                     args.lightwalletd,
                     args.snapshot_sapling,
@@ -128,15 +243,28 @@ async fn main() -> eyre::Result<()> {
                     args.gap_tree_sapling,
                     args.gap_tree_orchard,
                     args.gap_tree_mode,
+                    args.witness_server,
+                    args.gap_tree_shards,
                     ufvk.trim().to_owned(),
                     args.birthday,
+                    args.scan_cache,
+                    args.wallet_db,
                     args.claims_out,
                     args.config,
+                    args.organizer.into_trusted_organizer(),
+                    &Progress::default(),
                 )
                 .await
+                .map(|claims| {
+                    json!({
+                        "claims_out": claims_out,
+                        "notes": output::claim_inputs_summary(&claims),
+                    })
+                })
             }
             #[cfg(feature = "prove")]
             ClaimCommands::Prove { args } => {
+                let (proofs_out, secrets_out) = (args.proofs_out.clone(), args.secrets_out.clone());
                 zair_sdk::commands::generate_claim_proofs(
                     args.claims_in,
                     args.proofs_out,
@@ -147,56 +275,186 @@ async fn main() -> eyre::Result<()> {
                     args.orchard_params_mode,
                     args.secrets_out,
                     args.config,
+                    args.target,
+                    args.organizer.into_trusted_organizer(),
+                    &Progress::default(),
                 )
                 .await
+                .map(|proofs| {
+                    json!({
+                        "proofs_out": proofs_out,
+                        "secrets_out": secrets_out,
+                        "proofs": output::proofs_summary(&proofs),
+                    })
+                })
             }
             ClaimCommands::Sign { args } => {
+                let submission_out = args.submission_out.clone();
                 zair_sdk::commands::sign_claim_submission(
                     args.proofs_in,
                     args.secrets_in,
                     args.seed,
                     args.account,
                     args.config,
+                    args.target,
+                    args.organizer.into_trusted_organizer(),
                     args.message,
                     args.messages,
                     args.submission_out,
                 )
                 .await
+                .map(|submission| {
+                    json!({
+                        "submission_out": submission_out,
+                        "submission": output::submission_summary(&submission),
+                    })
+                })
             }
-        },
-        Commands::Verify { command } => match command {
-            VerifyCommands::Run { args } => {
-                zair_sdk::commands::verify_run(
-                    args.sapling_vk,
-                    args.orchard_params,
-                    args.orchard_params_mode,
-                    args.submission_in,
+            ClaimCommands::Transparent { args } => {
+                let submission_out = args.submission_out.clone();
+                zair_sdk::commands::sign_transparent_claim_submission(
+                    args.config,
+                    args.target,
+                    args.organizer.into_trusted_organizer(),
+                    args.transparent_balances,
+                    args.seed,
+                    args.account,
+                    args.gap_limit,
                     args.message,
                     args.messages,
-                    args.config,
+                    args.submission_out,
                 )
                 .await
+                .map(|submission| {
+                    json!({
+                        "submission_out": submission_out,
+                        "submission": output::submission_summary(&submission),
+                    })
+                })
             }
-            VerifyCommands::Proof { args } => {
-                zair_sdk::commands::verify_claim_proofs(
-                    args.proofs_in,
-                    args.sapling_vk,
-                    args.orchard_params,
-                    args.orchard_params_mode,
-                    args.config,
+            ClaimCommands::Export { args } => {
+                let export_out = args.output.clone();
+                zair_sdk::commands::export_claim_submission(
+                    args.submission_in,
+                    args.format,
+                    args.output,
                 )
                 .await
+                .map(|()| json!({ "output": export_out }))
             }
+            ClaimCommands::Message { args } => {
+                let output = args.output.clone();
+                zair_sdk::commands::write_claim_message(
+                    args.chain_id,
+                    args.address_format,
+                    args.recipient,
+                    args.memo,
+                    args.output,
+                )
+                .await
+                .map(|message| json!({ "output": output, "message": message }))
+            }
+        },
+        Commands::Verify { command } => match command {
+            VerifyCommands::Run { args } => zair_sdk::commands::verify_run(
+                args.sapling_vk,
+                args.orchard_params,
+                args.orchard_params_mode,
+                args.submission_in,
+                args.message,
+                args.messages,
+                args.config,
+                args.target,
+                args.organizer.into_trusted_organizer(),
+                args.clock.current_height,
+                args.clock.current_time,
+                args.registry,
+                args.valid_submission_out,
+                &Progress::default(),
+            )
+            .await
+            .and_then(|report| Ok(serde_json::to_value(report)?)),
+            VerifyCommands::Proof { args } => zair_sdk::commands::verify_claim_proofs(
+                args.proofs_in,
+                args.sapling_vk,
+                args.orchard_params,
+                args.orchard_params_mode,
+                args.config,
+                args.target,
+                args.organizer.into_trusted_organizer(),
+                &Progress::default(),
+            )
+            .await
+            .and_then(|report| Ok(serde_json::to_value(report)?)),
             VerifyCommands::Signature { args } => {
                 zair_sdk::commands::verify_claim_submission_signature(
                     args.submission_in,
                     args.message,
                     args.messages,
                     args.config,
+                    args.target,
+                    args.organizer.into_trusted_organizer(),
+                    args.clock.current_height,
+                    args.clock.current_time,
                 )
                 .await
+                .and_then(|report| Ok(serde_json::to_value(report)?))
             }
         },
+        Commands::Registry { command } => match command {
+            RegistryCommands::Import { args, unverified } => zair_sdk::commands::registry_import(
+                args.registry,
+                args.submission_in,
+                args.config,
+                args.target,
+                args.organizer.into_trusted_organizer(),
+                unverified,
+            )
+            .await
+            .map(|entries| json!({ "recorded": entries })),
+            RegistryCommands::Check { args } => zair_sdk::commands::registry_check(
+                args.registry,
+                args.submission_in,
+                args.config,
+                args.target,
+                args.organizer.into_trusted_organizer(),
+            )
+            .await
+            .map(|entries| json!({ "unclaimed": entries })),
+            RegistryCommands::Export { args } => {
+                let export_out = args.output.clone();
+                zair_sdk::commands::registry_export(args.registry, args.output)
+                    .await
+                    .map(|entries| json!({ "output": export_out, "claims": entries.len() }))
+            }
+        },
+        Commands::Serve { command } => match command {
+            ServeCommands::Witnesses { args } => zair_sdk::commands::serve_witnesses(
+                args.config,
+                args.organizer.into_trusted_organizer(),
+                args.snapshot_sapling,
+                args.snapshot_orchard,
+                args.gap_tree_sapling,
+                args.gap_tree_orchard,
+                args.listen,
+            )
+            .await
+            .map(|()| Value::Null),
+            ServeCommands::Verify { args } => zair_sdk::commands::serve_verifier(
+                args.config,
+                args.target,
+                args.organizer.into_trusted_organizer(),
+                args.sapling_vk,
+                args.orchard_params,
+                args.orchard_params_mode,
+                args.max_concurrent,
+                args.clock.current_height,
+                args.clock.current_time,
+                args.listen,
+            )
+            .await
+            .map(|()| Value::Null),
+        },
         Commands::Key { command } => match command {
             KeyCommands::DeriveSeed { args } => {
                 let mnemonic_source = if args.mnemonic_stdin {
@@ -206,12 +464,14 @@ async fn main() -> eyre::Result<()> {
                 } else {
                     zair_sdk::commands::MnemonicSource::Prompt
                 };
+                let seed_out = args.output.clone();
                 zair_sdk::commands::key_derive_seed(
                     args.output,
                     mnemonic_source,
                     args.no_passphrase,
                 )
                 .await
+                .map(|()| json!({ "output": seed_out }))
             }
             KeyCommands::DeriveUfvk { args } => {
                 let mnemonic_source = if args.mnemonic_stdin {
@@ -220,6 +480,7 @@ async fn main() -> eyre::Result<()> {
                     args.mnemonic_file
                         .map(zair_sdk::commands::MnemonicSource::File)
                 };
+                let ufvk_out = args.output.clone();
                 zair_sdk::commands::key_derive_ufvk(
                     args.network,
                     args.account,
@@ -229,14 +490,27 @@ async fn main() -> eyre::Result<()> {
                     args.output,
                 )
                 .await
+                .map(|()| json!({ "output": ufvk_out }))
+            }
+            KeyCommands::GenerateOrganizer { args } => {
+                let key_out = args.output.clone();
+                zair_sdk::commands::key_generate_organizer(args.output)
+                    .await
+                    .map(|public_key| {
+                        json!({ "output": key_out, "public_key": hex::encode(public_key) })
+                    })
             }
         },
-    };
-
-    if let Err(e) = res {
-        tracing::error!("Error: {:?}", e);
-        std::process::exit(1);
     }
+}
 
-    Ok(())
+/// Space-separated subcommand path of the invocation, e.g. `verify run`.
+fn command_path(matches: &clap::ArgMatches) -> String {
+    let mut path = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        path.push(name);
+        current = sub;
+    }
+    path.join(" ")
 }
@@ -0,0 +1,193 @@
+//! Machine-readable command results for `--output json`.
+//!
+//! In JSON mode every command prints exactly one [`Document`] to stdout and logs go to stderr.
+//! The process exit code follows [`ErrorClass::exit_code`] in both modes.
+
+use serde::Serialize;
+use serde_json::Value;
+use zair_core::base::{Nullifier, Pool};
+use zair_core::schema::proof_inputs::AirdropClaimInputs;
+use zair_core::schema::submission::ClaimSubmission;
+use zair_sdk::commands::{
+    AlreadyClaimed, AuditFailed, ClaimProofsOutput, ErrorClass, VerificationFailed,
+};
+
+/// Result format selected with `--output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Tracing logs only.
+    Text,
+    /// Tracing logs on stderr and one JSON result document on stdout.
+    Json,
+}
+
+/// The single JSON document printed by a command.
+#[derive(Debug, Serialize)]
+pub struct Document {
+    /// Subcommand path, e.g. `verify run`.
+    pub command: String,
+    /// `ok` or `error`.
+    pub status: &'static str,
+    /// Process exit code.
+    pub exit_code: i32,
+    /// Command result; on verification, audit and registry failures, the failing verdicts.
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub result: Value,
+    /// Failure details.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorDetails>,
+}
+
+/// Failure details of a [`Document`].
+#[derive(Debug, Serialize)]
+pub struct ErrorDetails {
+    /// Failure class; determines the exit code.
+    pub class: ErrorClass,
+    /// Error message including its context chain.
+    pub message: String,
+}
+
+impl Document {
+    /// Build the document for a finished command.
+    pub fn new(command: String, result: &eyre::Result<Value>) -> Self {
+        match result {
+            Ok(value) => Self {
+                command,
+                status: "ok",
+                exit_code: 0,
+                result: value.clone(),
+                error: None,
+            },
+            Err(err) => {
+                let class = ErrorClass::of(err);
+                Self {
+                    command,
+                    status: "error",
+                    exit_code: class.exit_code(),
+                    result: error_result(err),
+                    error: Some(ErrorDetails {
+                        class,
+                        message: format!("{err:#}"),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+/// Structured data carried by typed errors, if any.
+fn error_result(err: &eyre::Report) -> Value {
+    if let Some(failed) = err.downcast_ref::<VerificationFailed>() {
+        return serde_json::to_value(&failed.report).unwrap_or_default();
+    }
+    if let Some(failed) = err.downcast_ref::<AuditFailed>() {
+        return serde_json::to_value(&failed.report).unwrap_or_default();
+    }
+    if let Some(claimed) = err.downcast_ref::<AlreadyClaimed>() {
+        return serde_json::json!({ "claimed": claimed.claimed, "total": claimed.total });
+    }
+    Value::Null
+}
+
+/// One claim in a command result.
+#[derive(Debug, Serialize)]
+struct ClaimSummary {
+    pool: Pool,
+    airdrop_nullifier: Nullifier,
+    /// Note value in zatoshis, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<u64>,
+}
+
+/// Claimed notes and their values from prepared claim inputs; no private note data.
+pub fn claim_inputs_summary(inputs: &AirdropClaimInputs) -> Value {
+    let sapling = inputs.sapling_claim_input.iter().map(|claim| ClaimSummary {
+        pool: Pool::Sapling,
+        airdrop_nullifier: claim.public_inputs.airdrop_nullifier,
+        value: Some(claim.private_inputs.value),
+    });
+    let orchard = inputs.orchard_claim_input.iter().map(|claim| ClaimSummary {
+        pool: Pool::Orchard,
+        airdrop_nullifier: claim.public_inputs.airdrop_nullifier,
+        value: Some(claim.private_inputs.value),
+    });
+    let claims: Vec<_> = sapling.chain(orchard).collect();
+    let total_value = claims
+        .iter()
+        .filter_map(|claim| claim.value)
+        .fold(0_u64, u64::saturating_add);
+    serde_json::json!({ "claims": claims, "total_value": total_value })
+}
+
+/// Claimed airdrop nullifiers of generated proofs.
+pub fn proofs_summary(proofs: &ClaimProofsOutput) -> Value {
+    let sapling = proofs.sapling_proofs.iter().map(|proof| ClaimSummary {
+        pool: Pool::Sapling,
+        airdrop_nullifier: proof.airdrop_nullifier,
+        value: None,
+    });
+    let orchard = proofs.orchard_proofs.iter().map(|proof| ClaimSummary {
+        pool: Pool::Orchard,
+        airdrop_nullifier: proof.airdrop_nullifier,
+        value: None,
+    });
+    serde_json::json!({ "claims": sapling.chain(orchard).collect::<Vec<_>>() })
+}
+
+/// Claimed airdrop nullifiers of a signed submission.
+pub fn submission_summary(submission: &ClaimSubmission) -> Value {
+    let sapling = submission.sapling.iter().map(|claim| ClaimSummary {
+        pool: Pool::Sapling,
+        airdrop_nullifier: claim.airdrop_nullifier,
+        value: None,
+    });
+    let orchard = submission.orchard.iter().map(|claim| ClaimSummary {
+        pool: Pool::Orchard,
+        airdrop_nullifier: claim.airdrop_nullifier,
+        value: None,
+    });
+    let transparent = submission.transparent.iter().map(|claim| ClaimSummary {
+        pool: Pool::Transparent,
+        airdrop_nullifier: claim.airdrop_nullifier,
+        value: Some(claim.balance),
+    });
+    let claims: Vec<_> = sapling.chain(orchard).chain(transparent).collect();
+    serde_json::json!({ "claims": claims })
+}
+
+#[cfg(test)]
+mod tests {
+    use zair_sdk::commands::VerificationReport;
+
+    use super::*;
+
+    #[test]
+    fn verification_failure_carries_report_and_exit_code() {
+        let err = eyre::Report::new(VerificationFailed {
+            report: VerificationReport::default(),
+            subject: "proofs",
+        });
+        let document = Document::new("verify proof".to_owned(), &Err(err));
+        let json = serde_json::to_value(&document).expect("serialize document");
+
+        assert_eq!(json.pointer("/status"), Some(&"error".into()));
+        assert_eq!(json.pointer("/exit_code"), Some(&5.into()));
+        assert_eq!(
+            json.pointer("/error/class"),
+            Some(&"verification_failed".into())
+        );
+        assert!(json.pointer("/result/claims").is_some_and(Value::is_array));
+    }
+
+    #[test]
+    fn success_omits_error() {
+        let document = Document::new(
+            "registry export".to_owned(),
+            &Ok(serde_json::json!({ "claims": 0 })),
+        );
+        let json = serde_json::to_value(&document).expect("serialize document");
+
+        assert_eq!(json.pointer("/exit_code"), Some(&0.into()));
+        assert!(json.get("error").is_none());
+    }
+}
@@ -0,0 +1,275 @@
+//! End-to-end CLI workflow against a synthetic chain served by an in-process lightwalletd.
+
+mod test_utils;
+
+use std::fs;
+
+use serde_json::Value;
+use test_utils::offline_harness::{
+    ORCHARD_NOTE_VALUE, OfflineChain, SAPLING_NOTE_VALUE, load_json,
+};
+
+#[test]
+fn offline_config_build_commits_to_chain_roots() {
+    let chain = OfflineChain::start();
+
+    let config = chain.config_build();
+
+    let (sapling_root, orchard_root) = chain.note_commitment_roots;
+    assert_eq!(
+        config.sapling.map(|pool| pool.note_commitment_root),
+        Some(sapling_root)
+    );
+    assert_eq!(
+        config.orchard.map(|pool| pool.note_commitment_root),
+        Some(orchard_root)
+    );
+    for artifact in [
+        "snapshot-sapling.bin",
+        "snapshot-orchard.bin",
+        "gaptree-sapling.bin",
+        "gaptree-orchard.bin",
+    ] {
+        assert!(chain.path(artifact).exists(), "{artifact} should exist");
+    }
+}
+
+#[test]
+fn offline_config_audit_reproduces_snapshot() {
+    let chain = OfflineChain::start();
+    chain.config_build();
+
+    let report = chain.config_audit();
+
+    assert_eq!(report.get("passed"), Some(&Value::Bool(true)));
+    let pools = report
+        .get("pools")
+        .and_then(Value::as_array)
+        .expect("pool audits");
+    assert_eq!(pools.len(), 2);
+    for pool in pools {
+        for check in [
+            "/nullifier_gap_root/matches",
+            "/snapshot_header_root/matches",
+            "/gap_tree/root/matches",
+            "/note_commitment_root/matches",
+            "/rescan/matches_snapshot",
+        ] {
+            assert_eq!(
+                pool.pointer(check),
+                Some(&Value::Bool(true)),
+                "{check} should pass"
+            );
+        }
+    }
+    assert!(chain.path("config-audit.md").exists());
+}
+
+#[test]
+fn offline_claim_prepare_finds_synthetic_notes() {
+    let chain = OfflineChain::start();
+    chain.config_build();
+
+    let claims = chain.claim_prepare();
+
+    for (pool, value) in [
+        ("sapling_claim_input", SAPLING_NOTE_VALUE),
+        ("orchard_claim_input", ORCHARD_NOTE_VALUE),
+    ] {
+        let inputs = claims
+            .get(pool)
+            .and_then(Value::as_array)
+            .unwrap_or_else(|| panic!("{pool} should be an array"));
+        assert_eq!(inputs.len(), 1, "{pool} should hold the synthetic note");
+        assert_eq!(
+            inputs
+                .first()
+                .and_then(|input| input.pointer("/private_inputs/value"))
+                .and_then(Value::as_u64),
+            Some(value),
+            "{pool} should carry the synthetic note value"
+        );
+    }
+}
+
+#[test]
+fn offline_claim_prepare_requires_trusted_organizer_signature() {
+    let chain = OfflineChain::start();
+    chain.config_build();
+    let organizer_key = chain.organizer_key();
+    let signature = chain.path("config.sig.json");
+    let trusted = [
+        "--trusted-organizer-key",
+        organizer_key.as_str(),
+        "--config-signature",
+        signature.to_str().expect("utf-8 path"),
+    ];
+    chain.claim_prepare_assert(&trusted).code(3);
+
+    chain.config_sign();
+    let claims = chain.claim_prepare_with(&trusted);
+    assert!(
+        claims
+            .get("sapling_claim_input")
+            .and_then(Value::as_array)
+            .is_some_and(|claims| !claims.is_empty())
+    );
+
+    let config_path = chain.path("config.json");
+    let mut config = load_json(&config_path);
+    *config
+        .pointer_mut("/orchard/target_id")
+        .expect("orchard target id") = Value::from("PHISHING:O");
+    fs::write(&config_path, config.to_string()).expect("write tampered config");
+    chain.claim_prepare_assert(&trusted).code(5);
+
+    chain.config_sign();
+    let claims = chain.claim_prepare_with(&trusted);
+    assert!(claims.get("sapling_claim_input").is_some());
+    let pretty = serde_json::to_string_pretty(&load_json(&config_path)).expect("reformat");
+    fs::write(&config_path, pretty).expect("write reformatted config");
+    chain.claim_prepare_assert(&trusted).code(5);
+    chain.serve_witnesses_assert(&trusted).code(5);
+}
+
+/// Build a configuration with an Orchard `season-2` target next to the default airdrop and
+/// prepare the claims, checking that they carry a nullifier per target.
+fn prepare_with_season_target() -> OfflineChain {
+    let chain = OfflineChain::start();
+    chain.config_build();
+    let config_path = chain.path("config.json");
+    let mut config = load_json(&config_path);
+    config.as_object_mut().expect("config object").insert(
+        "targets".to_owned(),
+        serde_json::json!([{
+            "name": "season-2",
+            "orchard": { "target_id": "SEASON02:O" },
+        }]),
+    );
+    fs::write(&config_path, config.to_string()).expect("write config with targets");
+
+    let claims = chain.claim_prepare();
+
+    let public_inputs = claims
+        .pointer("/orchard_claim_input/0/public_inputs")
+        .expect("orchard_claim_input should hold the synthetic note");
+    let target_nullifiers = public_inputs
+        .get("target_nullifiers")
+        .and_then(Value::as_object)
+        .expect("orchard claims should carry per-target nullifiers");
+    assert_eq!(target_nullifiers.len(), 2);
+    assert_eq!(
+        target_nullifiers.get("ZAIRTEST:O"),
+        public_inputs.get("airdrop_nullifier"),
+        "the default nullifier should be the default target's"
+    );
+    assert_ne!(
+        target_nullifiers.get("SEASON02:O"),
+        public_inputs.get("airdrop_nullifier"),
+        "targets should be domain separated"
+    );
+    chain
+}
+
+#[test]
+fn offline_claim_prepare_covers_every_airdrop_target() {
+    prepare_with_season_target();
+}
+
+#[test]
+#[ignore = "Full pipeline: heavy computation (parameter setup and proving)"]
+fn offline_claims_verify_for_every_airdrop_target() {
+    let chain = prepare_with_season_target();
+
+    let default_submission = chain.claim_prove_and_sign(None);
+    chain.verify_submission(&default_submission, None).success();
+    let season_submission = chain.claim_prove_and_sign(Some("season-2"));
+    chain
+        .verify_submission(&season_submission, Some("season-2"))
+        .success();
+
+    let default = load_json(&default_submission);
+    let season = load_json(&season_submission);
+    assert_eq!(
+        default
+            .get("sapling")
+            .and_then(Value::as_array)
+            .map(Vec::len),
+        Some(1),
+        "the default airdrop claims the Sapling note"
+    );
+    assert_eq!(
+        season
+            .get("sapling")
+            .and_then(Value::as_array)
+            .map(Vec::len),
+        Some(0),
+        "season-2 has no Sapling airdrop"
+    );
+    assert_ne!(
+        default.pointer("/orchard/0/airdrop_nullifier"),
+        season.pointer("/orchard/0/airdrop_nullifier"),
+    );
+
+    // A claim of one target is not accepted for the other.
+    chain.verify_submission(&season_submission, None).failure();
+    chain
+        .verify_submission(&default_submission, Some("season-2"))
+        .failure();
+}
+
+#[test]
+fn offline_configuration_refuses_sapling_targets() {
+    let chain = OfflineChain::start();
+    chain.config_build();
+    let config_path = chain.path("config.json");
+    let mut config = load_json(&config_path);
+    config.as_object_mut().expect("config object").insert(
+        "targets".to_owned(),
+        serde_json::json!([{
+            "name": "season-2",
+            "sapling": { "target_id": "SEASON02" },
+        }]),
+    );
+    fs::write(&config_path, config.to_string()).expect("write config with targets");
+
+    let assert = chain.claim_prepare_assert(&[]).failure();
+    let output = assert.get_output();
+    let logs = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        logs.contains("personalization"),
+        "expected the Sapling target to be refused, got:\n{logs}"
+    );
+}
+
+#[test]
+fn offline_claim_prepare_reuses_scan_cache() {
+    let mut chain = OfflineChain::start();
+    chain.config_build();
+    let cache_dir = chain.path("scan-cache");
+
+    let scanned = chain.claim_prepare_cached(&cache_dir);
+    let cache_files = fs::read_dir(&cache_dir)
+        .expect("scan cache directory")
+        .count();
+    assert_eq!(cache_files, 1, "one cache file per account");
+
+    // The cached scan already covers the snapshot, so no lightwalletd is needed.
+    chain.stop_lightwalletd();
+    let cached = chain.claim_prepare_cached(&cache_dir);
+    assert_eq!(cached, scanned);
+}
+
+#[test]
+#[ignore = "Full pipeline: heavy computation (parameter setup and proving)"]
+fn offline_pipeline_native() {
+    let chain = OfflineChain::start();
+    chain.config_build();
+
+    chain.claim_run();
+    chain.verify_run().success();
+}
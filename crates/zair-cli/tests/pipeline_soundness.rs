@@ -82,7 +82,7 @@ fn soundness_tamper_proof_hash() {
     flip_hex_byte(&mut data["sapling"][0]["proof_hash"], 0);
     let tampered = write_tampered("tampered-submission-proof-hash.json", &data);
     let stderr = verify_signature_failure_stderr("native", &tampered, &message_path());
-    assert_contains(&stderr, "Sapling proof hash mismatch");
+    assert_contains(&stderr, "proof_hash does not match");
 }
 
 #[test]
@@ -93,7 +93,7 @@ fn soundness_tamper_message_hash() {
     flip_hex_byte(&mut data["sapling"][0]["message_hash"], 0);
     let tampered = write_tampered("tampered-submission-message-hash.json", &data);
     let stderr = verify_signature_failure_stderr("native", &tampered, &message_path());
-    assert_contains(&stderr, "Sapling message hash mismatch");
+    assert_contains(&stderr, "message_hash does not match");
 }
 
 #[test]
@@ -135,7 +135,7 @@ fn soundness_wrong_message() {
 
     let stderr =
         verify_signature_failure_stderr("native", &submission_path("native"), &wrong_message);
-    assert_contains(&stderr, "message hash mismatch");
+    assert_contains(&stderr, "message_hash does not match");
 }
 
 #[test]
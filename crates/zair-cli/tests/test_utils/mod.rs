@@ -1 +1,2 @@
+pub mod offline_harness;
 pub mod pipeline_harness;
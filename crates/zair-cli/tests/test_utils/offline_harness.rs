@@ -0,0 +1,441 @@
+#![allow(
+    dead_code,
+    reason = "Each test binary uses a different subset of the helpers"
+)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use serde_json::Value;
+use tempfile::TempDir;
+use zair_core::schema::config::AirdropConfiguration;
+use zair_testkit::{ChainBuilder, MockLightwalletd};
+
+use super::pipeline_harness::{
+    cached, ensure_setup_orchard, ensure_setup_sapling, orchard_params_path, vk_path,
+};
+
+/// Seed owning the synthetic notes.
+const SEED: [u8; 64] = [0x42; 64];
+const MESSAGE: &[u8] = b"test-pipeline-offline";
+
+/// First block of the synthetic chain, also used as the wallet birthday.
+pub const START_HEIGHT: u64 = 1_900_000;
+const SAPLING_SPEND_HEIGHT: u64 = START_HEIGHT + 1;
+const SAPLING_NOTE_HEIGHT: u64 = START_HEIGHT + 2;
+const ORCHARD_NOTE_HEIGHT: u64 = START_HEIGHT + 3;
+const ORCHARD_SPEND_HEIGHT: u64 = START_HEIGHT + 4;
+/// Snapshot height of the airdrop; the blocks after it are empty.
+pub const SNAPSHOT_HEIGHT: u64 = START_HEIGHT + 5;
+const TIP_HEIGHT: u64 = SNAPSHOT_HEIGHT + 2;
+/// Height whose tree state `config build` reads the note commitment roots from.
+const ANCHOR_HEIGHT: u64 = SNAPSHOT_HEIGHT + 1;
+
+pub const SAPLING_NOTE_VALUE: u64 = 50_000;
+pub const ORCHARD_NOTE_VALUE: u64 = 70_000;
+
+fn s(path: &Path) -> &str {
+    path.to_str().expect("path is valid UTF-8")
+}
+
+fn zair() -> assert_cmd::Command {
+    cargo_bin_cmd!("zair")
+}
+
+/// A synthetic chain served by an in-process lightwalletd, with a scratch directory for the
+/// pipeline artifacts.
+pub struct OfflineChain {
+    dir: TempDir,
+    server: Option<MockLightwalletd>,
+    url: String,
+    /// Sapling and Orchard note commitment roots the configuration must commit to.
+    pub note_commitment_roots: ([u8; 32], [u8; 32]),
+}
+
+impl OfflineChain {
+    /// Generate the chain and start serving it.
+    pub fn start() -> Self {
+        let chain = ChainBuilder::new(SEED, START_HEIGHT)
+            .sapling_spends(SAPLING_SPEND_HEIGHT, 3)
+            .sapling_note(SAPLING_NOTE_HEIGHT, SAPLING_NOTE_VALUE)
+            .orchard_note(ORCHARD_NOTE_HEIGHT, ORCHARD_NOTE_VALUE)
+            .orchard_spends(ORCHARD_SPEND_HEIGHT, 2)
+            .tip_height(TIP_HEIGHT)
+            .build()
+            .expect("build synthetic chain");
+        let note_commitment_roots = chain
+            .note_commitment_roots(ANCHOR_HEIGHT)
+            .expect("anchor tree state");
+        let server = MockLightwalletd::start(chain).expect("start mock lightwalletd");
+        let url = server.url();
+
+        let dir = tempfile::tempdir().expect("create temp dir");
+        fs::write(
+            dir.path().join("seed.txt"),
+            format!("{}\n", hex::encode(SEED)),
+        )
+        .expect("write seed.txt");
+        fs::write(dir.path().join("claim-message.bin"), MESSAGE).expect("write message");
+
+        Self {
+            dir,
+            server: Some(server),
+            url,
+            note_commitment_roots,
+        }
+    }
+
+    /// Shut the lightwalletd down; later commands fail if they try to reach it.
+    pub fn stop_lightwalletd(&mut self) {
+        drop(self.server.take());
+    }
+
+    /// Path of an artifact in the scratch directory.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+
+    /// Run `config build` for both pools with the native scheme.
+    pub fn config_build(&self) -> AirdropConfiguration {
+        let config = self.path("config.json");
+        zair()
+            .args([
+                "config",
+                "build",
+                "--network",
+                "testnet",
+                "--height",
+                &SNAPSHOT_HEIGHT.to_string(),
+                "--lightwalletd",
+                &self.url,
+                "--pool",
+                "both",
+                "--scheme-sapling",
+                "native",
+                "--scheme-orchard",
+                "native",
+                "--config-out",
+                s(&config),
+                "--snapshot-out-sapling",
+                s(&self.path("snapshot-sapling.bin")),
+                "--snapshot-out-orchard",
+                s(&self.path("snapshot-orchard.bin")),
+                "--gap-tree-out-sapling",
+                s(&self.path("gaptree-sapling.bin")),
+                "--gap-tree-out-orchard",
+                s(&self.path("gaptree-orchard.bin")),
+            ])
+            .assert()
+            .success();
+
+        serde_json::from_str(&fs::read_to_string(config).expect("read config"))
+            .expect("parse config")
+    }
+
+    /// Run `config audit --rescan` against the built snapshot, returning the JSON report.
+    pub fn config_audit(&self) -> Value {
+        let report = self.path("config-audit.json");
+        zair()
+            .args([
+                "config",
+                "audit",
+                "--config",
+                s(&self.path("config.json")),
+                "--snapshot-sapling",
+                s(&self.path("snapshot-sapling.bin")),
+                "--snapshot-orchard",
+                s(&self.path("snapshot-orchard.bin")),
+                "--gap-tree-sapling",
+                s(&self.path("gaptree-sapling.bin")),
+                "--gap-tree-orchard",
+                s(&self.path("gaptree-orchard.bin")),
+                "--lightwalletd",
+                &self.url,
+                "--rescan",
+                "--report-out",
+                s(&report),
+                "--markdown-out",
+                s(&self.path("config-audit.md")),
+            ])
+            .assert()
+            .success();
+        load_json(&report)
+    }
+
+    /// Run `key generate-organizer`, returning the organizer public key.
+    pub fn organizer_key(&self) -> String {
+        let output = zair()
+            .args([
+                "--output",
+                "json",
+                "key",
+                "generate-organizer",
+                "--output",
+                s(&self.path("organizer-key.txt")),
+            ])
+            .assert()
+            .success();
+        let document: Value =
+            serde_json::from_slice(&output.get_output().stdout).expect("parse key document");
+        document
+            .pointer("/result/public_key")
+            .and_then(Value::as_str)
+            .expect("organizer public key")
+            .to_owned()
+    }
+
+    /// Run `config sign` on the built configuration with the key of [`Self::organizer_key`],
+    /// writing `config.sig.json`.
+    pub fn config_sign(&self) {
+        zair()
+            .args([
+                "config",
+                "sign",
+                "--config",
+                s(&self.path("config.json")),
+                "--signing-key",
+                s(&self.path("organizer-key.txt")),
+                "--signature-out",
+                s(&self.path("config.sig.json")),
+            ])
+            .assert()
+            .success();
+    }
+
+    /// Run `key derive-ufvk` and `claim prepare`, returning the prepared claims.
+    pub fn claim_prepare(&self) -> Value {
+        self.claim_prepare_with(&[])
+    }
+
+    /// Run [`Self::claim_prepare`] with the note scan cached in `cache_dir`.
+    pub fn claim_prepare_cached(&self, cache_dir: &Path) -> Value {
+        self.claim_prepare_with(&["--scan-cache", s(cache_dir)])
+    }
+
+    /// Run [`Self::claim_prepare`] with `extra_args`.
+    pub fn claim_prepare_with(&self, extra_args: &[&str]) -> Value {
+        self.claim_prepare_assert(extra_args).success();
+        load_json(&self.path("claim-prepared.json"))
+    }
+
+    /// Run `key derive-ufvk` and `claim prepare` with `extra_args`, without checking the outcome.
+    pub fn claim_prepare_assert(&self, extra_args: &[&str]) -> assert_cmd::assert::Assert {
+        let ufvk = self.path("ufvk.txt");
+        zair()
+            .args([
+                "key",
+                "derive-ufvk",
+                "--network",
+                "testnet",
+                "--seed",
+                s(&self.path("seed.txt")),
+                "--output",
+                s(&ufvk),
+            ])
+            .assert()
+            .success();
+
+        zair()
+            .args([
+                "claim",
+                "prepare",
+                "--config",
+                s(&self.path("config.json")),
+                "--ufvk",
+                s(&ufvk),
+                "--birthday",
+                &START_HEIGHT.to_string(),
+                "--lightwalletd",
+                &self.url,
+                "--snapshot-sapling",
+                s(&self.path("snapshot-sapling.bin")),
+                "--snapshot-orchard",
+                s(&self.path("snapshot-orchard.bin")),
+                "--gap-tree-sapling",
+                s(&self.path("gaptree-sapling.bin")),
+                "--gap-tree-orchard",
+                s(&self.path("gaptree-orchard.bin")),
+                "--claims-out",
+                s(&self.path("claim-prepared.json")),
+            ])
+            .args(extra_args)
+            .assert()
+    }
+
+    /// Start `serve witnesses` with `extra_args`, stopping it after a minute if it comes up.
+    pub fn serve_witnesses_assert(&self, extra_args: &[&str]) -> assert_cmd::assert::Assert {
+        zair()
+            .args([
+                "serve",
+                "witnesses",
+                "--config",
+                s(&self.path("config.json")),
+                "--snapshot-sapling",
+                s(&self.path("snapshot-sapling.bin")),
+                "--snapshot-orchard",
+                s(&self.path("snapshot-orchard.bin")),
+                "--gap-tree-sapling",
+                s(&self.path("gaptree-sapling.bin")),
+                "--gap-tree-orchard",
+                s(&self.path("gaptree-orchard.bin")),
+                "--listen",
+                "127.0.0.1:0",
+            ])
+            .args(extra_args)
+            .timeout(Duration::from_secs(60))
+            .assert()
+    }
+
+    /// Run `claim run` with the cached native setup parameters.
+    pub fn claim_run(&self) {
+        ensure_setup_sapling("native");
+        ensure_setup_orchard("native");
+
+        zair()
+            .args([
+                "claim",
+                "run",
+                "--config",
+                s(&self.path("config.json")),
+                "--seed",
+                s(&self.path("seed.txt")),
+                "--birthday",
+                &START_HEIGHT.to_string(),
+                "--lightwalletd",
+                &self.url,
+                "--message",
+                s(&self.path("claim-message.bin")),
+                "--snapshot-sapling",
+                s(&self.path("snapshot-sapling.bin")),
+                "--snapshot-orchard",
+                s(&self.path("snapshot-orchard.bin")),
+                "--gap-tree-sapling",
+                s(&self.path("gaptree-sapling.bin")),
+                "--gap-tree-orchard",
+                s(&self.path("gaptree-orchard.bin")),
+                "--sapling-pk",
+                s(&cached("setup-sapling-pk-native.params")),
+                "--orchard-params",
+                s(&orchard_params_path("native")),
+                "--claims-out",
+                s(&self.path("claim-prepared.json")),
+                "--proofs-out",
+                s(&self.path("claim-proofs.json")),
+                "--secrets-out",
+                s(&self.path("claim-proofs-secrets.json")),
+                "--submission-out",
+                s(&self.path("claim-submission.json")),
+            ])
+            .assert()
+            .success();
+    }
+
+    /// Run `claim prove` and `claim sign` over the prepared claims for `target` (the default
+    /// airdrop when `None`), returning the path of the submission.
+    pub fn claim_prove_and_sign(&self, target: Option<&str>) -> PathBuf {
+        ensure_setup_sapling("native");
+        ensure_setup_orchard("native");
+
+        let suffix = target.unwrap_or("default");
+        let target_args = target.map_or_else(Vec::new, |name| vec!["--target", name]);
+        let proofs = self.path(&format!("claim-proofs-{suffix}.json"));
+        let secrets = self.path(&format!("claim-proofs-secrets-{suffix}.json"));
+        let submission = self.path(&format!("claim-submission-{suffix}.json"));
+
+        zair()
+            .args([
+                "claim",
+                "prove",
+                "--config",
+                s(&self.path("config.json")),
+                "--claims-in",
+                s(&self.path("claim-prepared.json")),
+                "--seed",
+                s(&self.path("seed.txt")),
+                "--sapling-pk",
+                s(&cached("setup-sapling-pk-native.params")),
+                "--orchard-params",
+                s(&orchard_params_path("native")),
+                "--proofs-out",
+                s(&proofs),
+                "--secrets-out",
+                s(&secrets),
+            ])
+            .args(&target_args)
+            .assert()
+            .success();
+
+        zair()
+            .args([
+                "claim",
+                "sign",
+                "--config",
+                s(&self.path("config.json")),
+                "--proofs-in",
+                s(&proofs),
+                "--secrets-in",
+                s(&secrets),
+                "--seed",
+                s(&self.path("seed.txt")),
+                "--message",
+                s(&self.path("claim-message.bin")),
+                "--submission-out",
+                s(&submission),
+            ])
+            .args(&target_args)
+            .assert()
+            .success();
+        submission
+    }
+
+    /// Run `verify run` over `submission` for `target` (the default airdrop when `None`).
+    pub fn verify_submission(
+        &self,
+        submission: &Path,
+        target: Option<&str>,
+    ) -> assert_cmd::assert::Assert {
+        zair()
+            .args([
+                "verify",
+                "run",
+                "--config",
+                s(&self.path("config.json")),
+                "--sapling-vk",
+                s(&vk_path("native")),
+                "--orchard-params",
+                s(&orchard_params_path("native")),
+                "--submission-in",
+                s(submission),
+                "--message",
+                s(&self.path("claim-message.bin")),
+            ])
+            .args(target.map_or_else(Vec::new, |name| vec!["--target", name]))
+            .assert()
+    }
+
+    /// Run `verify run` over the submission written by [`Self::claim_run`].
+    pub fn verify_run(&self) -> assert_cmd::assert::Assert {
+        zair()
+            .args([
+                "verify",
+                "run",
+                "--config",
+                s(&self.path("config.json")),
+                "--sapling-vk",
+                s(&vk_path("native")),
+                "--orchard-params",
+                s(&orchard_params_path("native")),
+                "--submission-in",
+                s(&self.path("claim-submission.json")),
+                "--message",
+                s(&self.path("claim-message.bin")),
+            ])
+            .assert()
+    }
+}
+
+pub fn load_json(path: &Path) -> Value {
+    serde_json::from_str(&fs::read_to_string(path).expect("read json")).expect("parse json")
+}
@@ -173,7 +173,7 @@ fn ensure_config_sha256() {
     fs::write(&sha256_config, output).expect("write config-sha256.json");
 }
 
-fn ensure_setup_sapling(scheme: &str) {
+pub fn ensure_setup_sapling(scheme: &str) {
     let pk = cached(&format!("setup-sapling-pk-{scheme}.params"));
     let vk = vk_path(scheme);
 
@@ -196,7 +196,7 @@ fn ensure_setup_sapling(scheme: &str) {
         .success();
 }
 
-fn ensure_setup_orchard(scheme: &str) {
+pub fn ensure_setup_orchard(scheme: &str) {
     let params = orchard_params_path(scheme);
 
     if params.exists() {
@@ -8,11 +8,13 @@ mod nullifier;
 mod utils;
 mod value_commitment;
 
-pub use nullifier::{NULLIFIER_SIZE, Nullifier, SanitiseNullifiers};
+pub use nullifier::{
+    NULLIFIER_SIZE, Nullifier, SAPLING_HIDING_NF_PERSONALIZATION, SanitiseNullifiers,
+};
 pub use utils::{ReverseBytes, ReversedHex};
 pub use value_commitment::{VALUE_COMMIT_SHA256_PREFIX, cv_sha256, cv_sha256_preimage};
 
-/// Zcash shielded pool identifier.
+/// Zcash value pool identifier.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Pool {
@@ -20,6 +22,8 @@ pub enum Pool {
     Sapling,
     /// Orchard pool.
     Orchard,
+    /// Transparent pool.
+    Transparent,
 }
 
 impl Pool {
@@ -29,6 +33,7 @@ impl Pool {
         match self {
             Self::Sapling => 0,
             Self::Orchard => 1,
+            Self::Transparent => 2,
         }
     }
 }
@@ -38,6 +43,7 @@ impl fmt::Display for Pool {
         match self {
             Self::Sapling => f.write_str("Sapling"),
             Self::Orchard => f.write_str("Orchard"),
+            Self::Transparent => f.write_str("Transparent"),
         }
     }
 }
@@ -14,6 +14,12 @@ use super::utils::ReversedHex;
 /// Size of a nullifier in bytes
 pub const NULLIFIER_SIZE: usize = 32;
 
+/// Personalization of the Sapling hiding nullifier.
+///
+/// The Sapling claim circuit fixes it, so it is the only Sapling `target_id` this build can
+/// prove and verify claims for.
+pub const SAPLING_HIDING_NF_PERSONALIZATION: [u8; 8] = *b"ZAIRTEST";
+
 /// A representation of Nullifiers
 ///
 /// Nullifiers in Zcash Orchard and Sapling pools are both 32 bytes long.
@@ -0,0 +1,542 @@
+//! Versioned on-disk container for snapshot and gap-tree files.
+//!
+//! Layout (all integers little-endian):
+//!
+//! | offset | size | field                                           |
+//! |--------|------|-------------------------------------------------|
+//! | 0      | 4    | magic `b"ZAIR"`                                 |
+//! | 4      | 2    | format version                                  |
+//! | 6      | 1    | content kind                                    |
+//! | 7      | 1    | pool                                            |
+//! | 8      | 1    | network                                         |
+//! | 9      | 1    | compression                                     |
+//! | 10     | 2    | reserved (zero)                                 |
+//! | 12     | 8    | snapshot height                                 |
+//! | 20     | 8    | item count                                      |
+//! | 28     | 32   | non-membership root                             |
+//! | 60     | 8    | stored payload length                           |
+//! | 68     | 32   | BLAKE2b-256 of bytes `0..68` and stored payload |
+//!
+//! Files that do not start with the magic are treated as legacy headerless files.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::base::Pool;
+use crate::schema::config::AirdropNetwork;
+
+/// Container magic bytes.
+pub const CONTAINER_MAGIC: [u8; 4] = *b"ZAIR";
+/// Current container format version.
+pub const CONTAINER_VERSION: u16 = 1;
+/// Serialized header length in bytes.
+pub const CONTAINER_HEADER_LEN: usize = 100;
+
+const CHECKSUM_OFFSET: usize = 68;
+const CHECKSUM_PERSONALIZATION: &[u8; 16] = b"ZAIR-container-1";
+
+/// What a container holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    /// Snapshot nullifiers, 32 bytes each.
+    Snapshot,
+    /// Dense gap-tree nodes, level by level.
+    GapTree,
+    /// Gap bounds for one fixed-size run of gap-tree leaves.
+    GapShard,
+    /// Roots and first bounds of every gap-tree shard.
+    GapShardIndex,
+}
+
+impl fmt::Display for ContainerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Snapshot => f.write_str("snapshot"),
+            Self::GapTree => f.write_str("gap-tree"),
+            Self::GapShard => f.write_str("gap-tree shard"),
+            Self::GapShardIndex => f.write_str("gap-tree shard index"),
+        }
+    }
+}
+
+/// Payload compression.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Payload is stored as-is and can be memory-mapped.
+    #[default]
+    None,
+    /// Payload is zstd-compressed.
+    Zstd,
+}
+
+/// Errors from encoding or decoding a container.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ContainerError {
+    /// The file is shorter than the header.
+    #[error("Container header is truncated")]
+    Truncated,
+    /// The container was written by an unsupported format version.
+    #[error("Unsupported container version {0}")]
+    UnsupportedVersion(u16),
+    /// A header byte does not map to a known value.
+    #[error("Invalid container {field} tag {value}")]
+    InvalidTag {
+        /// Header field name.
+        field: &'static str,
+        /// Raw tag value.
+        value: u8,
+    },
+    /// The stored payload length differs from the header.
+    #[error("Container payload is {found} bytes but header declares {expected}")]
+    LengthMismatch {
+        /// Length declared in the header.
+        expected: u64,
+        /// Actual payload length.
+        found: usize,
+    },
+    /// The checksum does not match the header and payload.
+    #[error("Container checksum mismatch")]
+    ChecksumMismatch,
+    /// The container holds a different kind of content.
+    #[error("Expected a {expected} file but found a {found} file")]
+    KindMismatch {
+        /// Expected content kind.
+        expected: ContainerKind,
+        /// Content kind in the header.
+        found: ContainerKind,
+    },
+    /// The container belongs to a different pool.
+    #[error("Expected a {expected} file but found a {found} file")]
+    PoolMismatch {
+        /// Expected pool.
+        expected: Pool,
+        /// Pool in the header.
+        found: Pool,
+    },
+    /// The container was produced for a different network.
+    #[error("Expected a {expected:?} file but found a {found:?} file")]
+    NetworkMismatch {
+        /// Expected network.
+        expected: AirdropNetwork,
+        /// Network in the header.
+        found: AirdropNetwork,
+    },
+    /// The container was produced at a different snapshot height.
+    #[error("Expected snapshot height {expected} but file was produced at {found}")]
+    HeightMismatch {
+        /// Expected snapshot height.
+        expected: u64,
+        /// Snapshot height in the header.
+        found: u64,
+    },
+    /// Compressing or decompressing the payload failed.
+    #[error("Container compression error: {0}")]
+    Compression(String),
+}
+
+/// Snapshot-level settings used when writing containers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContainerOptions {
+    /// Network the snapshot was taken on.
+    pub network: AirdropNetwork,
+    /// Snapshot block height.
+    pub snapshot_height: u64,
+    /// Payload compression.
+    pub compression: Compression,
+}
+
+impl ContainerOptions {
+    /// Create uncompressed container options.
+    #[must_use]
+    pub const fn new(network: AirdropNetwork, snapshot_height: u64) -> Self {
+        Self {
+            network,
+            snapshot_height,
+            compression: Compression::None,
+        }
+    }
+
+    /// Set the payload compression.
+    #[must_use]
+    pub const fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
+/// Decoded container header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContainerHeader {
+    /// Content kind.
+    pub kind: ContainerKind,
+    /// Shielded pool.
+    pub pool: Pool,
+    /// Network the snapshot was taken on.
+    pub network: AirdropNetwork,
+    /// Snapshot block height.
+    pub snapshot_height: u64,
+    /// Number of 32-byte items (nullifiers, gap-tree leaves, shard bounds or shards).
+    pub item_count: u64,
+    /// Non-membership root of the snapshot.
+    pub root: [u8; 32],
+    /// Payload compression.
+    pub compression: Compression,
+}
+
+impl ContainerHeader {
+    /// Build a header for `item_count` items from snapshot-level options.
+    #[must_use]
+    pub const fn new(
+        kind: ContainerKind,
+        pool: Pool,
+        root: [u8; 32],
+        item_count: u64,
+        options: &ContainerOptions,
+    ) -> Self {
+        Self {
+            kind,
+            pool,
+            network: options.network,
+            snapshot_height: options.snapshot_height,
+            item_count,
+            root,
+            compression: options.compression,
+        }
+    }
+
+    /// Check that the header describes `kind` content for `pool`.
+    ///
+    /// # Errors
+    /// Returns an error on a kind or pool mismatch.
+    pub fn ensure_content(&self, kind: ContainerKind, pool: Pool) -> Result<(), ContainerError> {
+        if self.kind != kind {
+            return Err(ContainerError::KindMismatch {
+                expected: kind,
+                found: self.kind,
+            });
+        }
+        if self.pool != pool {
+            return Err(ContainerError::PoolMismatch {
+                expected: pool,
+                found: self.pool,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that the header was produced for the given network and snapshot height.
+    ///
+    /// # Errors
+    /// Returns an error on a network or height mismatch.
+    pub fn ensure_snapshot(&self, options: &ContainerOptions) -> Result<(), ContainerError> {
+        if self.network != options.network {
+            return Err(ContainerError::NetworkMismatch {
+                expected: options.network,
+                found: self.network,
+            });
+        }
+        if self.snapshot_height != options.snapshot_height {
+            return Err(ContainerError::HeightMismatch {
+                expected: options.snapshot_height,
+                found: self.snapshot_height,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A decoded file: either a versioned container or legacy headerless bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Container<'a> {
+    /// Legacy file without a header.
+    Legacy(&'a [u8]),
+    /// Versioned container with its decompressed payload.
+    Versioned {
+        /// Decoded header.
+        header: ContainerHeader,
+        /// Payload, borrowed when uncompressed.
+        payload: Cow<'a, [u8]>,
+    },
+}
+
+/// Return whether `bytes` start with the container magic.
+#[must_use]
+pub fn is_container(bytes: &[u8]) -> bool {
+    bytes.starts_with(&CONTAINER_MAGIC)
+}
+
+/// Encode a header and uncompressed payload into container bytes.
+///
+/// # Errors
+/// Returns an error if compression fails.
+pub fn encode(header: &ContainerHeader, payload: &[u8]) -> Result<Vec<u8>, ContainerError> {
+    let stored: Cow<'_, [u8]> = match header.compression {
+        Compression::None => Cow::Borrowed(payload),
+        Compression::Zstd => Cow::Owned(
+            zstd::encode_all(payload, 0).map_err(|e| ContainerError::Compression(e.to_string()))?,
+        ),
+    };
+    let stored_len = u64::try_from(stored.len())
+        .map_err(|_| ContainerError::Compression("payload too large".to_string()))?;
+
+    let mut bytes = Vec::with_capacity(CONTAINER_HEADER_LEN.saturating_add(stored.len()));
+    bytes.extend_from_slice(&CONTAINER_MAGIC);
+    bytes.extend_from_slice(&CONTAINER_VERSION.to_le_bytes());
+    bytes.push(kind_tag(header.kind));
+    bytes.push(header.pool.as_byte());
+    bytes.push(network_tag(header.network));
+    bytes.push(compression_tag(header.compression));
+    bytes.extend_from_slice(&[0_u8; 2]);
+    bytes.extend_from_slice(&header.snapshot_height.to_le_bytes());
+    bytes.extend_from_slice(&header.item_count.to_le_bytes());
+    bytes.extend_from_slice(&header.root);
+    bytes.extend_from_slice(&stored_len.to_le_bytes());
+    let checksum = checksum(&bytes, &stored);
+    bytes.extend_from_slice(&checksum);
+    bytes.extend_from_slice(&stored);
+    Ok(bytes)
+}
+
+/// Decode container bytes, verifying the checksum and decompressing the payload.
+///
+/// Bytes without the container magic are returned as [`Container::Legacy`].
+///
+/// # Errors
+/// Returns an error if the header is malformed, the checksum does not match, or
+/// decompression fails.
+pub fn decode(bytes: &[u8]) -> Result<Container<'_>, ContainerError> {
+    if !is_container(bytes) {
+        return Ok(Container::Legacy(bytes));
+    }
+    let (header, stored) = decode_header(bytes)?;
+    let payload = match header.compression {
+        Compression::None => Cow::Borrowed(stored),
+        Compression::Zstd => Cow::Owned(
+            zstd::decode_all(stored).map_err(|e| ContainerError::Compression(e.to_string()))?,
+        ),
+    };
+    Ok(Container::Versioned { header, payload })
+}
+
+/// Parse and verify the header, returning it with the stored (possibly compressed) payload.
+///
+/// # Errors
+/// Returns an error if the header is malformed or the checksum does not match.
+pub fn decode_header(bytes: &[u8]) -> Result<(ContainerHeader, &[u8]), ContainerError> {
+    let (header, stored) = bytes
+        .split_first_chunk::<CONTAINER_HEADER_LEN>()
+        .ok_or(ContainerError::Truncated)?;
+    let (prefix, stored_checksum) = header.split_at(CHECKSUM_OFFSET);
+    let field = |start: usize, end: usize| prefix.get(start..end).ok_or(ContainerError::Truncated);
+
+    if field(0, 4)? != CONTAINER_MAGIC {
+        return Err(ContainerError::Truncated);
+    }
+    let version = u16::from_le_bytes(read_array(field(4, 6)?)?);
+    if version != CONTAINER_VERSION {
+        return Err(ContainerError::UnsupportedVersion(version));
+    }
+    let tags = field(6, 10)?;
+    let tag = |index: usize| tags.get(index).copied().ok_or(ContainerError::Truncated);
+    let kind = parse_kind(tag(0)?)?;
+    let pool = parse_pool(tag(1)?)?;
+    let network = parse_network(tag(2)?)?;
+    let compression = parse_compression(tag(3)?)?;
+    let snapshot_height = u64::from_le_bytes(read_array(field(12, 20)?)?);
+    let item_count = u64::from_le_bytes(read_array(field(20, 28)?)?);
+    let root = read_array(field(28, 60)?)?;
+    let stored_len = u64::from_le_bytes(read_array(field(60, 68)?)?);
+
+    if u64::try_from(stored.len()).ok() != Some(stored_len) {
+        return Err(ContainerError::LengthMismatch {
+            expected: stored_len,
+            found: stored.len(),
+        });
+    }
+    if checksum(prefix, stored) != stored_checksum {
+        return Err(ContainerError::ChecksumMismatch);
+    }
+
+    Ok((
+        ContainerHeader {
+            kind,
+            pool,
+            network,
+            snapshot_height,
+            item_count,
+            root,
+            compression,
+        },
+        stored,
+    ))
+}
+
+fn checksum(prefix: &[u8], stored: &[u8]) -> [u8; 32] {
+    let mut state = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(CHECKSUM_PERSONALIZATION)
+        .to_state();
+    state.update(prefix);
+    state.update(stored);
+    let mut out = [0_u8; 32];
+    out.copy_from_slice(state.finalize().as_bytes());
+    out
+}
+
+fn read_array<const N: usize>(bytes: &[u8]) -> Result<[u8; N], ContainerError> {
+    bytes.try_into().map_err(|_| ContainerError::Truncated)
+}
+
+const fn kind_tag(kind: ContainerKind) -> u8 {
+    match kind {
+        ContainerKind::Snapshot => 1,
+        ContainerKind::GapTree => 2,
+        ContainerKind::GapShard => 3,
+        ContainerKind::GapShardIndex => 4,
+    }
+}
+
+const fn parse_kind(value: u8) -> Result<ContainerKind, ContainerError> {
+    match value {
+        1 => Ok(ContainerKind::Snapshot),
+        2 => Ok(ContainerKind::GapTree),
+        3 => Ok(ContainerKind::GapShard),
+        4 => Ok(ContainerKind::GapShardIndex),
+        _ => Err(ContainerError::InvalidTag {
+            field: "kind",
+            value,
+        }),
+    }
+}
+
+const fn parse_pool(value: u8) -> Result<Pool, ContainerError> {
+    match value {
+        0 => Ok(Pool::Sapling),
+        1 => Ok(Pool::Orchard),
+        _ => Err(ContainerError::InvalidTag {
+            field: "pool",
+            value,
+        }),
+    }
+}
+
+const fn network_tag(network: AirdropNetwork) -> u8 {
+    match network {
+        AirdropNetwork::Mainnet => 0,
+        AirdropNetwork::Testnet => 1,
+    }
+}
+
+const fn parse_network(value: u8) -> Result<AirdropNetwork, ContainerError> {
+    match value {
+        0 => Ok(AirdropNetwork::Mainnet),
+        1 => Ok(AirdropNetwork::Testnet),
+        _ => Err(ContainerError::InvalidTag {
+            field: "network",
+            value,
+        }),
+    }
+}
+
+const fn compression_tag(compression: Compression) -> u8 {
+    match compression {
+        Compression::None => 0,
+        Compression::Zstd => 1,
+    }
+}
+
+const fn parse_compression(value: u8) -> Result<Compression, ContainerError> {
+    match value {
+        0 => Ok(Compression::None),
+        1 => Ok(Compression::Zstd),
+        _ => Err(ContainerError::InvalidTag {
+            field: "compression",
+            value,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(compression: Compression) -> ContainerHeader {
+        let options =
+            ContainerOptions::new(AirdropNetwork::Testnet, 3_000_000).with_compression(compression);
+        ContainerHeader::new(
+            ContainerKind::Snapshot,
+            Pool::Orchard,
+            [7_u8; 32],
+            2,
+            &options,
+        )
+    }
+
+    #[test]
+    fn roundtrip_with_and_without_compression() {
+        let payload = [[1_u8; 32], [2_u8; 32]].concat();
+        for compression in [Compression::None, Compression::Zstd] {
+            let header = header(compression);
+            let bytes = encode(&header, &payload).expect("encode");
+            assert!(is_container(&bytes));
+
+            let Container::Versioned {
+                header: decoded,
+                payload: decoded_payload,
+            } = decode(&bytes).expect("decode")
+            else {
+                panic!("expected a versioned container");
+            };
+            assert_eq!(decoded, header);
+            assert_eq!(decoded_payload.as_ref(), payload.as_slice());
+        }
+    }
+
+    #[test]
+    fn headerless_bytes_are_legacy() {
+        let payload = [3_u8; 64];
+        assert_eq!(decode(&payload), Ok(Container::Legacy(&payload)));
+    }
+
+    #[test]
+    fn corruption_is_detected() {
+        let header = header(Compression::None);
+        let mut bytes = encode(&header, &[5_u8; 32]).expect("encode");
+        if let Some(last) = bytes.last_mut() {
+            *last ^= 1;
+        }
+        assert_eq!(decode(&bytes), Err(ContainerError::ChecksumMismatch));
+
+        bytes.pop();
+        assert!(matches!(
+            decode(&bytes),
+            Err(ContainerError::LengthMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn header_checks_report_mismatches() {
+        let header = header(Compression::None);
+        assert_eq!(
+            header.ensure_content(ContainerKind::Snapshot, Pool::Sapling),
+            Err(ContainerError::PoolMismatch {
+                expected: Pool::Sapling,
+                found: Pool::Orchard,
+            })
+        );
+        assert_eq!(
+            header.ensure_snapshot(&ContainerOptions::new(AirdropNetwork::Mainnet, 3_000_000)),
+            Err(ContainerError::NetworkMismatch {
+                expected: AirdropNetwork::Mainnet,
+                found: AirdropNetwork::Testnet,
+            })
+        );
+        assert!(
+            header
+                .ensure_snapshot(&ContainerOptions::new(AirdropNetwork::Testnet, 3_000_000))
+                .is_ok()
+        );
+    }
+}
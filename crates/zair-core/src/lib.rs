@@ -2,5 +2,9 @@
 
 /// Foundational primitive types and helpers shared across crates.
 pub mod base;
+/// Versioned on-disk container for snapshot and gap-tree files.
+pub mod container;
 /// Serialized/public schema models used across the workspace.
 pub mod schema;
+/// Transparent balance snapshot tree and airdrop nullifiers.
+pub mod transparent;
@@ -3,10 +3,10 @@ use serde::{Deserialize, Serialize};
 use serde_with::hex::Hex;
 use serde_with::serde_as;
 
-use crate::base::ReversedHex;
+use crate::base::{Pool, ReversedHex, SAPLING_HIDING_NF_PERSONALIZATION};
 
 /// Configuration for an airdrop snapshot.
-#[derive(Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq, Clone)]
 pub struct AirdropConfiguration {
     /// Zcash network this snapshot belongs to.
     pub network: AirdropNetwork,
@@ -18,6 +18,22 @@ pub struct AirdropConfiguration {
     /// Orchard snapshot configuration. Present when Orchard pool is enabled.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub orchard: Option<OrchardSnapshot>,
+    /// Transparent snapshot configuration. Present when transparent balances are eligible.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transparent: Option<TransparentSnapshot>,
+    /// Rules for the messages claimants sign into their submissions.
+    #[serde(default, skip_serializing_if = "MessagePolicy::is_unrestricted")]
+    pub message_policy: MessagePolicy,
+    /// Period on the target chain in which claims are accepted. Claims are always accepted
+    /// when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim_window: Option<ClaimWindow>,
+    /// Further airdrops claimable from the same snapshot, each under its own target ids.
+    ///
+    /// The pool entries above describe the default airdrop; [`Self::for_target`] resolves a
+    /// named one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<AirdropTarget>,
 }
 
 /// Value commitment scheme selection.
@@ -31,6 +47,17 @@ pub enum ValueCommitmentScheme {
     Sha256,
 }
 
+/// Hash function used for Orchard nullifier gap-tree nodes.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OrchardGapTreeHash {
+    /// Sinsemilla `MerkleCRH^Orchard`, matching the Orchard note commitment tree.
+    #[default]
+    Sinsemilla,
+    /// Poseidon (`P128Pow5T3`, width 3, rate 2) over the Pallas base field.
+    Poseidon,
+}
+
 /// Network identifier for an airdrop snapshot.
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -77,6 +104,274 @@ pub struct OrchardSnapshot {
     /// Value commitment scheme used by Orchard proofs.
     #[serde(default)]
     pub value_commitment_scheme: ValueCommitmentScheme,
+    /// Hash function used for the Orchard nullifier gap tree.
+    #[serde(default)]
+    pub nullifier_gap_tree_hash: OrchardGapTreeHash,
+}
+
+/// Transparent-specific snapshot data.
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq, Clone)]
+pub struct TransparentSnapshot {
+    /// Root of the transparent balance tree at `snapshot_height`.
+    #[serde_as(as = "Hex")]
+    #[schemars(with = "String")]
+    pub balance_root: [u8; 32],
+    /// Number of P2PKH addresses committed to by the balance tree.
+    pub address_count: u64,
+    /// Sum of the committed balances, in zatoshis.
+    pub total_balance: u64,
+    /// Domain-separation identifier used for transparent airdrop nullifiers.
+    pub target_id: String,
+}
+
+/// Rules for claim messages.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema, PartialEq, Eq, Default)]
+pub struct MessagePolicy {
+    /// Longest accepted message, in bytes. Messages of any length are accepted when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+}
+
+impl MessagePolicy {
+    /// Whether the policy accepts every message.
+    #[must_use]
+    pub const fn is_unrestricted(&self) -> bool {
+        self.max_bytes.is_none()
+    }
+
+    /// Whether the policy accepts `message`.
+    #[must_use]
+    pub fn accepts(&self, message: &[u8]) -> bool {
+        self.max_bytes
+            .is_none_or(|max_bytes| u64::try_from(message.len()).is_ok_and(|len| len <= max_bytes))
+    }
+}
+
+/// Period on the target chain in which claims are accepted.
+///
+/// Both bounds are inclusive; an absent bound leaves that side of the window open.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+pub struct ClaimWindow {
+    /// Whether the bounds are block heights or timestamps.
+    pub unit: ClaimWindowUnit,
+    /// First height or timestamp at which claims are accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<u64>,
+    /// Last height or timestamp at which claims are accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<u64>,
+}
+
+/// How the bounds of a [`ClaimWindow`] are counted.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaimWindowUnit {
+    /// Target-chain block height.
+    Height,
+    /// Target-chain block time, in seconds since the Unix epoch.
+    Timestamp,
+}
+
+impl std::fmt::Display for ClaimWindowUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Height => "height",
+            Self::Timestamp => "timestamp",
+        })
+    }
+}
+
+/// Current position of the target chain, as known to a verifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChainClock {
+    /// Current target-chain block height.
+    pub height: Option<u64>,
+    /// Current target-chain block time, in seconds since the Unix epoch.
+    pub timestamp: Option<u64>,
+}
+
+/// Why a claim falls outside its [`ClaimWindow`].
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ClaimWindowError {
+    /// The window has not opened yet.
+    #[error("claim window opens at {unit} {start}, current {unit} is {current}")]
+    NotOpen {
+        /// Unit of the window bounds.
+        unit: ClaimWindowUnit,
+        /// First accepted height or timestamp.
+        start: u64,
+        /// Current height or timestamp.
+        current: u64,
+    },
+    /// The window has closed.
+    #[error("claim window closed at {unit} {end}, current {unit} is {current}")]
+    Closed {
+        /// Unit of the window bounds.
+        unit: ClaimWindowUnit,
+        /// Last accepted height or timestamp.
+        end: u64,
+        /// Current height or timestamp.
+        current: u64,
+    },
+    /// The clock does not know the current position in the window's unit.
+    #[error("claim window is bounded by {0}, but no current {0} was supplied")]
+    UnknownPosition(ClaimWindowUnit),
+}
+
+impl ClaimWindow {
+    /// Check that `clock` lies within the window.
+    ///
+    /// # Errors
+    /// Returns [`ClaimWindowError`] if the window is not open at `clock` or `clock` lacks the
+    /// window's unit.
+    pub fn check(&self, clock: &ChainClock) -> Result<(), ClaimWindowError> {
+        let current = match self.unit {
+            ClaimWindowUnit::Height => clock.height,
+            ClaimWindowUnit::Timestamp => clock.timestamp,
+        }
+        .ok_or(ClaimWindowError::UnknownPosition(self.unit))?;
+        if let Some(start) = self.start.filter(|&start| current < start) {
+            return Err(ClaimWindowError::NotOpen {
+                unit: self.unit,
+                start,
+                current,
+            });
+        }
+        if let Some(end) = self.end.filter(|&end| current > end) {
+            return Err(ClaimWindowError::Closed {
+                unit: self.unit,
+                end,
+                current,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// An airdrop claimable from the configuration's snapshot under its own target ids.
+///
+/// A target shares the snapshot roots of the configuration and overrides the domain
+/// separation of each pool it lists; pools it omits cannot claim it.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq, Clone)]
+pub struct AirdropTarget {
+    /// Name selecting this target.
+    pub name: String,
+    /// Sapling target id and value commitment scheme.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sapling: Option<ShieldedTarget>,
+    /// Orchard target id and value commitment scheme.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orchard: Option<ShieldedTarget>,
+    /// Transparent target id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transparent: Option<TransparentTarget>,
+    /// Rules for the messages signed into claims of this target. A `max_bytes` left unset
+    /// inherits the configuration's.
+    #[serde(default, skip_serializing_if = "MessagePolicy::is_unrestricted")]
+    pub message_policy: MessagePolicy,
+    /// Period in which claims of this target are accepted. Inherits the configuration's claim
+    /// window when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim_window: Option<ClaimWindow>,
+}
+
+/// Shielded pool settings of an [`AirdropTarget`].
+#[derive(Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq, Clone)]
+pub struct ShieldedTarget {
+    /// Domain-separation identifier used for hiding nullifiers.
+    pub target_id: String,
+    /// Value commitment scheme used by proofs.
+    #[serde(default)]
+    pub value_commitment_scheme: ValueCommitmentScheme,
+}
+
+/// Transparent pool settings of an [`AirdropTarget`].
+#[derive(Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq, Clone)]
+pub struct TransparentTarget {
+    /// Domain-separation identifier used for transparent airdrop nullifiers.
+    pub target_id: String,
+}
+
+/// Errors resolving an [`AirdropTarget`] of a configuration.
+#[derive(Debug, thiserror::Error, Clone, PartialEq, Eq)]
+pub enum TargetError {
+    /// No target has the requested name.
+    #[error("airdrop configuration has no target named {name:?} (available: {available})")]
+    UnknownTarget {
+        /// Requested target name.
+        name: String,
+        /// Comma-separated names of the configured targets.
+        available: String,
+    },
+    /// A target lists a pool the snapshot does not enable.
+    #[error("target {name:?} lists the {pool} pool, which the snapshot does not enable")]
+    MissingPool {
+        /// Target name.
+        name: String,
+        /// Pool missing from the snapshot.
+        pool: Pool,
+    },
+    /// Two targets have the same name.
+    #[error("airdrop configuration has two targets named {name:?}")]
+    DuplicateName {
+        /// Repeated target name.
+        name: String,
+    },
+    /// A target id is used twice in a pool, so claims of one airdrop would be replayable as
+    /// claims of the other.
+    #[error("{pool} target id {target_id:?} is used by more than one airdrop")]
+    DuplicateTargetId {
+        /// Pool the target id is repeated in.
+        pool: Pool,
+        /// Repeated target id.
+        target_id: String,
+    },
+    /// A Sapling target id differs from the hiding nullifier personalization fixed by the
+    /// Sapling claim circuit, so its claims can be neither proven nor verified.
+    #[error(
+        "Sapling target id {target_id:?} differs from the circuit's hiding nullifier \
+         personalization {expected:?}"
+    )]
+    SaplingPersonalization {
+        /// Configured Sapling target id.
+        target_id: String,
+        /// Personalization of the Sapling claim circuit.
+        expected: String,
+    },
+}
+
+/// Ed25519 signature of an airdrop organizer over the bytes of a configuration file.
+///
+/// Stored as a detached signature file next to the configuration it signs.
+#[serde_as]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq, Clone)]
+pub struct OrganizerSignature {
+    /// Ed25519 public key of the organizer.
+    #[serde_as(as = "Hex")]
+    #[schemars(with = "String")]
+    pub public_key: [u8; 32],
+    /// Ed25519 signature over the domain-separated configuration file bytes.
+    #[serde_as(as = "Hex")]
+    #[schemars(with = "String")]
+    pub signature: [u8; 64],
+}
+
+/// Fail on the first target id of `pool` that repeats.
+fn ensure_unique_target_ids<'a>(
+    pool: Pool,
+    target_ids: impl Iterator<Item = &'a str>,
+) -> Result<(), TargetError> {
+    let mut seen = std::collections::BTreeSet::new();
+    for target_id in target_ids {
+        if !seen.insert(target_id) {
+            return Err(TargetError::DuplicateTargetId {
+                pool,
+                target_id: target_id.to_owned(),
+            });
+        }
+    }
+    Ok(())
 }
 
 impl AirdropConfiguration {
@@ -93,7 +388,166 @@ impl AirdropConfiguration {
             snapshot_height,
             sapling,
             orchard,
+            transparent: None,
+            message_policy: MessagePolicy { max_bytes: None },
+            claim_window: None,
+            targets: Vec::new(),
+        }
+    }
+
+    /// Enable transparent claims against `transparent`.
+    #[must_use]
+    pub fn with_transparent(mut self, transparent: TransparentSnapshot) -> Self {
+        self.transparent = Some(transparent);
+        self
+    }
+
+    /// Add airdrop targets sharing this snapshot.
+    #[must_use]
+    pub fn with_targets(mut self, targets: Vec<AirdropTarget>) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    /// Check that target names are unique, that no target id is used twice in a pool,
+    /// counting the default airdrop's, and that every Sapling target id is the personalization
+    /// the Sapling claim circuit fixes.
+    ///
+    /// Airdrop nullifiers and signature digests are derived from the target id, so two
+    /// airdrops sharing one would accept each other's claims. As the circuit admits a single
+    /// Sapling target id, only one airdrop of a configuration can have Sapling claims.
+    ///
+    /// # Errors
+    /// Returns [`TargetError::DuplicateName`], [`TargetError::DuplicateTargetId`] or
+    /// [`TargetError::SaplingPersonalization`].
+    pub fn validate_targets(&self) -> Result<(), TargetError> {
+        let mut names = std::collections::BTreeSet::new();
+        for target in &self.targets {
+            if !names.insert(target.name.as_str()) {
+                return Err(TargetError::DuplicateName {
+                    name: target.name.clone(),
+                });
+            }
         }
+
+        let sapling_target_ids: Vec<&str> =
+            self.sapling
+                .as_ref()
+                .map(|pool| pool.target_id.as_str())
+                .into_iter()
+                .chain(self.targets.iter().filter_map(|target| {
+                    target.sapling.as_ref().map(|pool| pool.target_id.as_str())
+                }))
+                .collect();
+        let personalization = &SAPLING_HIDING_NF_PERSONALIZATION;
+        if let Some(target_id) = sapling_target_ids
+            .iter()
+            .find(|target_id| target_id.as_bytes() != personalization)
+        {
+            return Err(TargetError::SaplingPersonalization {
+                target_id: (*target_id).to_owned(),
+                expected: String::from_utf8_lossy(personalization).into_owned(),
+            });
+        }
+        ensure_unique_target_ids(Pool::Sapling, sapling_target_ids.into_iter())?;
+        ensure_unique_target_ids(
+            Pool::Orchard,
+            self.orchard
+                .as_ref()
+                .map(|pool| pool.target_id.as_str())
+                .into_iter()
+                .chain(self.targets.iter().filter_map(|target| {
+                    target.orchard.as_ref().map(|pool| pool.target_id.as_str())
+                })),
+        )?;
+        ensure_unique_target_ids(
+            Pool::Transparent,
+            self.transparent
+                .as_ref()
+                .map(|pool| pool.target_id.as_str())
+                .into_iter()
+                .chain(self.targets.iter().filter_map(|target| {
+                    target
+                        .transparent
+                        .as_ref()
+                        .map(|pool| pool.target_id.as_str())
+                })),
+        )?;
+        Ok(())
+    }
+
+    /// The configuration of the target called `name`.
+    ///
+    /// The result keeps this snapshot's roots, takes the target ids and value commitment schemes
+    /// of the target, drops the pools the target omits, and carries no targets. A `max_bytes`
+    /// or claim window set on the target replaces the default airdrop's; one left unset is
+    /// inherited from it.
+    ///
+    /// # Errors
+    /// Returns [`TargetError`] if the targets fail [`Self::validate_targets`], no target is
+    /// called `name`, or it lists a pool this configuration does not enable.
+    pub fn for_target(&self, name: &str) -> Result<Self, TargetError> {
+        self.validate_targets()?;
+        let target = self
+            .targets
+            .iter()
+            .find(|target| target.name == name)
+            .ok_or_else(|| TargetError::UnknownTarget {
+                name: name.to_owned(),
+                available: self
+                    .targets
+                    .iter()
+                    .map(|target| target.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            })?;
+        let missing = |pool| TargetError::MissingPool {
+            name: name.to_owned(),
+            pool,
+        };
+
+        let sapling = match &target.sapling {
+            Some(pool) => Some(SaplingSnapshot {
+                target_id: pool.target_id.clone(),
+                value_commitment_scheme: pool.value_commitment_scheme,
+                ..self.sapling.clone().ok_or_else(|| missing(Pool::Sapling))?
+            }),
+            None => None,
+        };
+        let orchard = match &target.orchard {
+            Some(pool) => Some(OrchardSnapshot {
+                target_id: pool.target_id.clone(),
+                value_commitment_scheme: pool.value_commitment_scheme,
+                ..self.orchard.clone().ok_or_else(|| missing(Pool::Orchard))?
+            }),
+            None => None,
+        };
+        let transparent = match &target.transparent {
+            Some(pool) => Some(TransparentSnapshot {
+                target_id: pool.target_id.clone(),
+                ..self
+                    .transparent
+                    .clone()
+                    .ok_or_else(|| missing(Pool::Transparent))?
+            }),
+            None => None,
+        };
+
+        Ok(Self {
+            network: self.network,
+            snapshot_height: self.snapshot_height,
+            sapling,
+            orchard,
+            transparent,
+            message_policy: MessagePolicy {
+                max_bytes: target
+                    .message_policy
+                    .max_bytes
+                    .or(self.message_policy.max_bytes),
+            },
+            claim_window: target.claim_window.or(self.claim_window),
+            targets: Vec::new(),
+        })
     }
 
     /// Build note commitment tree roots in the claim-input shape.
@@ -166,3 +620,225 @@ impl AirdropNetwork {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AirdropConfiguration {
+        AirdropConfiguration::new(
+            AirdropNetwork::Testnet,
+            42,
+            Some(SaplingSnapshot {
+                note_commitment_root: [1; 32],
+                nullifier_gap_root: [2; 32],
+                target_id: "ZAIRTEST".to_owned(),
+                value_commitment_scheme: ValueCommitmentScheme::Native,
+            }),
+            Some(OrchardSnapshot {
+                note_commitment_root: [3; 32],
+                nullifier_gap_root: [4; 32],
+                target_id: "ZAIRTEST:O".to_owned(),
+                value_commitment_scheme: ValueCommitmentScheme::Native,
+                nullifier_gap_tree_hash: OrchardGapTreeHash::Poseidon,
+            }),
+        )
+        .with_targets(vec![AirdropTarget {
+            name: "season-2".to_owned(),
+            sapling: None,
+            orchard: Some(ShieldedTarget {
+                target_id: "SEASON2:O".to_owned(),
+                value_commitment_scheme: ValueCommitmentScheme::Sha256,
+            }),
+            transparent: None,
+            message_policy: MessagePolicy {
+                max_bytes: Some(64),
+            },
+            claim_window: Some(ClaimWindow {
+                unit: ClaimWindowUnit::Height,
+                start: Some(100),
+                end: None,
+            }),
+        }])
+    }
+
+    #[test]
+    fn for_target_keeps_roots_and_swaps_target_ids() {
+        let resolved = config().for_target("season-2").expect("known target");
+
+        assert_eq!(resolved.sapling, None);
+        let orchard = resolved.orchard.expect("orchard target");
+        assert_eq!(orchard.note_commitment_root, [3; 32]);
+        assert_eq!(orchard.nullifier_gap_root, [4; 32]);
+        assert_eq!(
+            orchard.nullifier_gap_tree_hash,
+            OrchardGapTreeHash::Poseidon
+        );
+        assert_eq!(orchard.target_id, "SEASON2:O");
+        assert_eq!(
+            orchard.value_commitment_scheme,
+            ValueCommitmentScheme::Sha256
+        );
+        assert_eq!(resolved.message_policy.max_bytes, Some(64));
+        assert_eq!(
+            resolved.claim_window.map(|window| window.start),
+            Some(Some(100))
+        );
+        assert_eq!(resolved.targets, Vec::new());
+    }
+
+    #[test]
+    fn for_target_inherits_the_default_message_policy_and_claim_window() {
+        let mut config = config();
+        config.message_policy = MessagePolicy {
+            max_bytes: Some(128),
+        };
+        config.claim_window = Some(ClaimWindow {
+            unit: ClaimWindowUnit::Timestamp,
+            start: None,
+            end: Some(1_000),
+        });
+        for target in &mut config.targets {
+            target.message_policy = MessagePolicy::default();
+            target.claim_window = None;
+        }
+
+        let resolved = config.for_target("season-2").expect("known target");
+        assert_eq!(resolved.message_policy, config.message_policy);
+        assert_eq!(resolved.claim_window, config.claim_window);
+    }
+
+    #[test]
+    fn targets_must_have_unique_names_and_target_ids() {
+        let with_target = |name: &str, orchard_target_id: &str| {
+            let mut config = config();
+            config.targets.push(AirdropTarget {
+                name: name.to_owned(),
+                sapling: None,
+                orchard: Some(ShieldedTarget {
+                    target_id: orchard_target_id.to_owned(),
+                    value_commitment_scheme: ValueCommitmentScheme::Native,
+                }),
+                transparent: None,
+                message_policy: MessagePolicy::default(),
+                claim_window: None,
+            });
+            config
+        };
+
+        let duplicate_name = TargetError::DuplicateName {
+            name: "season-2".to_owned(),
+        };
+        let config = with_target("season-2", "SEASON3:O");
+        assert_eq!(config.validate_targets(), Err(duplicate_name.clone()));
+        assert_eq!(config.for_target("season-2"), Err(duplicate_name));
+
+        for target_id in ["SEASON2:O", "ZAIRTEST:O"] {
+            assert_eq!(
+                with_target("season-3", target_id).validate_targets(),
+                Err(TargetError::DuplicateTargetId {
+                    pool: Pool::Orchard,
+                    target_id: target_id.to_owned(),
+                })
+            );
+        }
+
+        assert_eq!(
+            with_target("season-3", "SEASON3:O").validate_targets(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn sapling_targets_must_use_the_circuit_personalization() {
+        let mut config = config();
+        config.targets.push(AirdropTarget {
+            name: "season-3".to_owned(),
+            sapling: Some(ShieldedTarget {
+                target_id: "SEASON03".to_owned(),
+                value_commitment_scheme: ValueCommitmentScheme::Native,
+            }),
+            orchard: None,
+            transparent: None,
+            message_policy: MessagePolicy::default(),
+            claim_window: None,
+        });
+
+        assert_eq!(
+            config.validate_targets(),
+            Err(TargetError::SaplingPersonalization {
+                target_id: "SEASON03".to_owned(),
+                expected: "ZAIRTEST".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn message_policy_limits_message_length() {
+        let policy = MessagePolicy { max_bytes: Some(4) };
+        assert!(policy.accepts(b"1234"));
+        assert!(!policy.accepts(b"12345"));
+        assert!(MessagePolicy::default().accepts(&[0; 1024]));
+    }
+
+    #[test]
+    fn claim_window_bounds_are_inclusive() {
+        let window = ClaimWindow {
+            unit: ClaimWindowUnit::Timestamp,
+            start: Some(10),
+            end: Some(20),
+        };
+        let at = |timestamp| ChainClock {
+            height: Some(15),
+            timestamp: Some(timestamp),
+        };
+        assert_eq!(window.check(&at(10)), Ok(()));
+        assert_eq!(window.check(&at(20)), Ok(()));
+        assert_eq!(
+            window.check(&at(9)),
+            Err(ClaimWindowError::NotOpen {
+                unit: ClaimWindowUnit::Timestamp,
+                start: 10,
+                current: 9,
+            })
+        );
+        assert_eq!(
+            window.check(&at(21)),
+            Err(ClaimWindowError::Closed {
+                unit: ClaimWindowUnit::Timestamp,
+                end: 20,
+                current: 21,
+            })
+        );
+        assert_eq!(
+            window.check(&ChainClock {
+                height: Some(15),
+                timestamp: None,
+            }),
+            Err(ClaimWindowError::UnknownPosition(
+                ClaimWindowUnit::Timestamp
+            ))
+        );
+    }
+
+    #[test]
+    fn for_target_rejects_unknown_targets_and_missing_pools() {
+        let mut config = config();
+        assert_eq!(
+            config.for_target("season-3"),
+            Err(TargetError::UnknownTarget {
+                name: "season-3".to_owned(),
+                available: "season-2".to_owned(),
+            })
+        );
+
+        config.orchard = None;
+        assert_eq!(
+            config.for_target("season-2"),
+            Err(TargetError::MissingPool {
+                name: "season-2".to_owned(),
+                pool: Pool::Orchard,
+            })
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! Structured claim message schema.
+//!
+//! A claim message is whatever bytes the claimant signs into a submission. A structured message
+//! is a JSON document naming the recipient on the target chain, so tools can check the address
+//! before it is signed.
+
+use serde::{Deserialize, Serialize};
+
+/// Value of the `schema` field of a structured claim message.
+pub const CLAIM_MESSAGE_SCHEMA: &str = "zair-claim-message/v1";
+
+/// Prefix of the `schema` field shared by every structured claim message version.
+pub const CLAIM_MESSAGE_SCHEMA_PREFIX: &str = "zair-claim-message/";
+
+/// Recipient of an airdrop claim on the target chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StructuredClaimMessage {
+    /// Message schema, [`CLAIM_MESSAGE_SCHEMA`].
+    pub schema: String,
+    /// Target chain the claim is paid out on.
+    pub chain_id: String,
+    /// Address format of `recipient`, selecting its validator.
+    pub address_format: String,
+    /// Address receiving the airdrop on the target chain.
+    pub recipient: String,
+    /// Free-form note for the target chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+impl StructuredClaimMessage {
+    /// A message of the current schema.
+    #[must_use]
+    pub fn new(
+        chain_id: String,
+        address_format: String,
+        recipient: String,
+        memo: Option<String>,
+    ) -> Self {
+        Self {
+            schema: CLAIM_MESSAGE_SCHEMA.to_owned(),
+            chain_id,
+            address_format,
+            recipient,
+            memo,
+        }
+    }
+}
@@ -2,7 +2,13 @@
 
 /// Airdrop configuration schema types.
 pub mod config;
+/// Structured claim message types.
+pub mod message;
 /// Claim input/output schema types.
 pub mod proof_inputs;
 /// Submission/signature schema types.
 pub mod submission;
+/// Claim verification service request/response types.
+pub mod verification;
+/// Gap-tree witness service request/response types.
+pub mod witness;
@@ -1,11 +1,14 @@
 //! Serializable claim input formats.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use serde_with::hex::Hex;
 use serde_with::serde_as;
 use zip32::Scope;
 
-use crate::base::Nullifier;
+use crate::base::{Nullifier, Pool};
+use crate::schema::config::AirdropConfiguration;
 
 /// Serializable version of `zip32::Scope`.
 ///
@@ -45,6 +48,56 @@ pub struct AirdropClaimInputs {
     pub orchard_claim_input: Vec<ClaimInput<OrchardPrivateInputs>>,
 }
 
+/// A claim prepared without the airdrop nullifier of the requested target.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+#[error(
+    "{pool} claim inputs were not prepared for target id {target_id:?}; rerun claim prepare with this configuration"
+)]
+pub struct MissingTargetNullifier {
+    /// Pool of the claim.
+    pub pool: Pool,
+    /// Requested target id.
+    pub target_id: String,
+}
+
+impl AirdropClaimInputs {
+    /// The claims provable for `config`, with its airdrop nullifiers as public inputs.
+    ///
+    /// Claims of a pool `config` does not enable are dropped, so claims prepared once for a
+    /// configuration with several targets can be proven for a target that omits a pool.
+    /// Claims prepared without per-target nullifiers keep their nullifier.
+    ///
+    /// # Errors
+    /// Returns [`MissingTargetNullifier`] if a claim has per-target nullifiers but none for the
+    /// target id `config` uses.
+    pub fn for_configuration(
+        mut self,
+        config: &AirdropConfiguration,
+    ) -> Result<Self, MissingTargetNullifier> {
+        match &config.sapling {
+            Some(sapling) => {
+                for claim in &mut self.sapling_claim_input {
+                    claim
+                        .public_inputs
+                        .select_target(Pool::Sapling, &sapling.target_id)?;
+                }
+            }
+            None => self.sapling_claim_input.clear(),
+        }
+        match &config.orchard {
+            Some(orchard) => {
+                for claim in &mut self.orchard_claim_input {
+                    claim
+                        .public_inputs
+                        .select_target(Pool::Orchard, &orchard.target_id)?;
+                }
+            }
+            None => self.orchard_claim_input.clear(),
+        }
+        Ok(self)
+    }
+}
+
 /// A non-membership proof demonstrating that a nullifier is not in the snapshot.
 ///
 /// This proof contains the two adjacent nullifiers that bound the target nullifier
@@ -166,12 +219,34 @@ pub struct OrchardPrivateInputs {
 pub struct PublicInputs {
     /// The airdrop nullifier
     pub airdrop_nullifier: Nullifier,
+    /// Airdrop nullifiers of the note under every target id of the configuration, so one
+    /// prepared claim can be proven for any target.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub target_nullifiers: BTreeMap<String, Nullifier>,
+}
+
+impl PublicInputs {
+    fn select_target(&mut self, pool: Pool, target_id: &str) -> Result<(), MissingTargetNullifier> {
+        if self.target_nullifiers.is_empty() {
+            return Ok(());
+        }
+        self.airdrop_nullifier =
+            *self
+                .target_nullifiers
+                .get(target_id)
+                .ok_or_else(|| MissingTargetNullifier {
+                    pool,
+                    target_id: target_id.to_owned(),
+                })?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::PublicInputs;
-    use crate::base::Nullifier;
+    use std::collections::BTreeMap;
+
+    use super::*;
 
     #[test]
     fn public_inputs_serializes_nullifier_in_reversed_hex() {
@@ -180,6 +255,7 @@ mod tests {
         bytes[31] = 0xcd;
         let inputs = PublicInputs {
             airdrop_nullifier: Nullifier::new(bytes),
+            target_nullifiers: BTreeMap::new(),
         };
 
         let json = serde_json::to_string(&inputs).expect("serialize public inputs");
@@ -188,4 +264,36 @@ mod tests {
             format!(r#"{{"airdrop_nullifier":"cd{}ab"}}"#, "00".repeat(30))
         );
     }
+
+    #[test]
+    fn select_target_uses_the_target_nullifier() {
+        let mut inputs = PublicInputs {
+            airdrop_nullifier: Nullifier::new([1; 32]),
+            target_nullifiers: BTreeMap::from([
+                ("ZAIRTEST:O".to_owned(), Nullifier::new([1; 32])),
+                ("SEASON2:O".to_owned(), Nullifier::new([2; 32])),
+            ]),
+        };
+        inputs
+            .select_target(Pool::Orchard, "SEASON2:O")
+            .expect("prepared target");
+        assert_eq!(inputs.airdrop_nullifier, Nullifier::new([2; 32]));
+
+        assert_eq!(
+            inputs.select_target(Pool::Orchard, "SEASON3:O"),
+            Err(MissingTargetNullifier {
+                pool: Pool::Orchard,
+                target_id: "SEASON3:O".to_owned(),
+            })
+        );
+
+        let mut legacy = PublicInputs {
+            airdrop_nullifier: Nullifier::new([3; 32]),
+            target_nullifiers: BTreeMap::new(),
+        };
+        legacy
+            .select_target(Pool::Orchard, "SEASON2:O")
+            .expect("legacy claims keep their nullifier");
+        assert_eq!(legacy.airdrop_nullifier, Nullifier::new([3; 32]));
+    }
 }
@@ -68,6 +68,33 @@ pub struct OrchardSignedClaim {
     pub spend_auth_sig: [u8; 64],
 }
 
+/// A signed transparent claim entry ready for target-chain submission.
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransparentSignedClaim {
+    /// Compressed secp256k1 public key of the claimed P2PKH address.
+    #[serde_as(as = "Hex")]
+    pub pubkey: [u8; 33],
+    /// Snapshot balance of the address, in zatoshis.
+    pub balance: u64,
+    /// Index of the address's leaf in the balance tree.
+    pub leaf_index: u64,
+    /// Authentication path of the leaf, from its sibling up.
+    #[serde_as(as = "Vec<Hex>")]
+    pub merkle_path: Vec<[u8; 32]>,
+    /// Airdrop nullifier used for double-claim prevention.
+    pub airdrop_nullifier: Nullifier,
+    /// Hash of this claim's unsigned fields.
+    #[serde_as(as = "Hex")]
+    pub proof_hash: [u8; 32],
+    /// Hash of this claim's external message payload.
+    #[serde_as(as = "Hex")]
+    pub message_hash: [u8; 32],
+    /// Compact ECDSA signature over the submission digest.
+    #[serde_as(as = "Hex")]
+    pub signature: [u8; 64],
+}
+
 /// Signed claims grouped by pool for submission.
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,4 +105,7 @@ pub struct ClaimSubmission {
     /// Signed Orchard claims.
     #[serde(default)]
     pub orchard: Vec<OrchardSignedClaim>,
+    /// Signed transparent claims.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transparent: Vec<TransparentSignedClaim>,
 }
@@ -0,0 +1,82 @@
+//! Request format for the claim verification service.
+//!
+//! A verification server checks every claim of a signed submission (proof, `proof_hash`,
+//! message hash and spend-auth signature) and reports a verdict per claim; the response and
+//! its verdicts are defined by the SDK.
+
+use serde::{Deserialize, Serialize};
+use serde_with::hex::Hex;
+use serde_with::serde_as;
+
+use crate::base::Nullifier;
+use crate::schema::submission::ClaimSubmission;
+
+/// Message a claim was signed over, either as raw bytes or as its hash.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaimMessage {
+    /// Raw message bytes; the server hashes them.
+    Bytes(#[serde_as(as = "Hex")] Vec<u8>),
+    /// Precomputed message hash.
+    Hash(#[serde_as(as = "Hex")] [u8; 32]),
+}
+
+/// Message assigned to a single claim.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimMessageEntry {
+    /// Airdrop nullifier identifying the claim.
+    pub airdrop_nullifier: Nullifier,
+    /// The claim's message.
+    pub message: ClaimMessage,
+}
+
+/// Request to verify a signed submission.
+///
+/// Per-claim messages override the shared `message` for matching nullifiers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyRequest {
+    /// The signed submission to verify.
+    pub submission: ClaimSubmission,
+    /// Message shared by all claims without a per-claim message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<ClaimMessage>,
+    /// Per-claim messages for Sapling claims.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sapling_messages: Vec<ClaimMessageEntry>,
+    /// Per-claim messages for Orchard claims.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub orchard_messages: Vec<ClaimMessageEntry>,
+    /// Per-claim messages for transparent claims.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transparent_messages: Vec<ClaimMessageEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_request_messages_json() {
+        let json = serde_json::json!({
+            "submission": { "sapling": [], "orchard": [] },
+            "message": { "bytes": "6869" },
+            "orchard_messages": [{
+                "airdrop_nullifier": "01".repeat(32),
+                "message": { "hash": "02".repeat(32) },
+            }],
+        });
+
+        let request: VerifyRequest =
+            serde_json::from_value(json).expect("request should deserialize");
+        assert_eq!(request.message, Some(ClaimMessage::Bytes(b"hi".to_vec())));
+        assert_eq!(request.sapling_messages, Vec::new());
+        assert_eq!(
+            request.orchard_messages,
+            vec![ClaimMessageEntry {
+                airdrop_nullifier: Nullifier::new([1_u8; 32]),
+                message: ClaimMessage::Hash([2_u8; 32]),
+            }]
+        );
+    }
+}
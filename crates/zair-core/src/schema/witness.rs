@@ -0,0 +1,113 @@
+//! Request/response formats for the gap-tree witness service.
+//!
+//! A witness server holds the snapshot and gap trees so thin clients only download the
+//! non-membership witness for their own nullifiers. Clients must recompute the gap-tree root
+//! from each witness and compare it with `nullifier_gap_root` from the airdrop configuration.
+
+use serde::{Deserialize, Serialize};
+use serde_with::hex::Hex;
+use serde_with::serde_as;
+
+use crate::base::{Nullifier, Pool};
+use crate::schema::config::{AirdropNetwork, OrchardGapTreeHash};
+
+/// Snapshot served by a witness server.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessServerInfo {
+    /// Network of the served snapshot.
+    pub network: AirdropNetwork,
+    /// Snapshot block height (inclusive).
+    pub snapshot_height: u64,
+    /// Sapling gap-tree root, when Sapling is served.
+    #[serde_as(as = "Option<Hex>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sapling_root: Option<[u8; 32]>,
+    /// Orchard gap-tree root, when Orchard is served.
+    #[serde_as(as = "Option<Hex>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orchard_root: Option<[u8; 32]>,
+    /// Hash function of the served Orchard gap tree.
+    #[serde(default)]
+    pub orchard_gap_tree_hash: OrchardGapTreeHash,
+}
+
+/// Request for the gap witnesses of a batch of nullifiers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessRequest {
+    /// Pool the nullifiers belong to.
+    pub pool: Pool,
+    /// Zcash nullifiers to look up.
+    pub nullifiers: Vec<Nullifier>,
+}
+
+/// Gap witnesses for the requested nullifiers.
+///
+/// Every requested nullifier is answered: unspent ones in `witnesses`, and nullifiers that are
+/// part of the snapshot in `spent`, with the gap they close as proof.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessResponse {
+    /// Pool the witnesses belong to.
+    pub pool: Pool,
+    /// Gap-tree root the witnesses open to.
+    #[serde_as(as = "Hex")]
+    pub root: [u8; 32],
+    /// One witness per unspent requested nullifier.
+    pub witnesses: Vec<NullifierWitness>,
+    /// One witness per spent requested nullifier, whose gap has the nullifier as `right_bound`.
+    #[serde(default)]
+    pub spent: Vec<NullifierWitness>,
+}
+
+/// Non-membership witness for a single nullifier.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NullifierWitness {
+    /// The queried nullifier.
+    pub nullifier: Nullifier,
+    /// Left bound of the gap: the largest snapshot nullifier below an unspent `nullifier`.
+    pub left_bound: Nullifier,
+    /// Right bound of the gap: the smallest snapshot nullifier above an unspent `nullifier`,
+    /// or `nullifier` itself when it is spent.
+    pub right_bound: Nullifier,
+    /// Position of the `(left_bound, right_bound)` leaf in the gap tree.
+    pub leaf_position: u64,
+    /// Merkle path siblings from the leaf up to the root.
+    #[serde_as(as = "Vec<Hex>")]
+    pub merkle_path: Vec<[u8; 32]>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn witness_response_json_roundtrip() {
+        let response = WitnessResponse {
+            pool: Pool::Orchard,
+            root: [7_u8; 32],
+            witnesses: vec![NullifierWitness {
+                nullifier: Nullifier::new([2_u8; 32]),
+                left_bound: Nullifier::new([1_u8; 32]),
+                right_bound: Nullifier::new([3_u8; 32]),
+                leaf_position: 4,
+                merkle_path: vec![[9_u8; 32]; 2],
+            }],
+            spent: vec![NullifierWitness {
+                nullifier: Nullifier::new([3_u8; 32]),
+                left_bound: Nullifier::new([1_u8; 32]),
+                right_bound: Nullifier::new([3_u8; 32]),
+                leaf_position: 4,
+                merkle_path: vec![[9_u8; 32]; 2],
+            }],
+        };
+
+        let json = serde_json::to_value(&response).expect("response should serialize");
+        assert_eq!(json.get("pool"), Some(&serde_json::json!("orchard")));
+        assert_eq!(json.get("root"), Some(&serde_json::json!("07".repeat(32))));
+        let decoded: WitnessResponse =
+            serde_json::from_value(json).expect("response should deserialize");
+        assert_eq!(decoded, response);
+    }
+}
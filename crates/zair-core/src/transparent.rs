@@ -0,0 +1,292 @@
+//! Transparent balance snapshot tree.
+//!
+//! Transparent holders are committed to as `(pubkey_hash, balance)` leaves of a binary Merkle
+//! tree. Leaves are sorted by P2PKH public key hash, so an address appears at most once, and
+//! the tree is padded to a power of two with [`EMPTY_LEAF`].
+
+use std::fmt::Write as _;
+
+use blake2b_simd::Params;
+
+use crate::base::Nullifier;
+
+/// Size of a P2PKH public key hash (`RIPEMD-160(SHA-256(pubkey))`).
+pub const PUBKEY_HASH_SIZE: usize = 20;
+/// Padding leaf of the balance tree.
+pub const EMPTY_LEAF: [u8; 32] = [0_u8; 32];
+/// Maximum depth of the balance tree.
+pub const MAX_DEPTH: usize = 32;
+
+const LEAF_PERSONALIZATION: &[u8; 16] = b"ZAIR_TBal_Leaf__";
+const NODE_PERSONALIZATION: &[u8; 16] = b"ZAIR_TBal_Node__";
+const NULLIFIER_PERSONALIZATION: &[u8; 16] = b"ZAIR_TBal_Nf____";
+
+/// Errors building a balance tree.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum BalanceTreeError {
+    /// No balances were given.
+    #[error("transparent balance set is empty")]
+    Empty,
+    /// The same public key hash appears twice.
+    #[error("duplicate transparent address with public key hash {0}")]
+    DuplicateAddress(String),
+    /// More leaves than a tree of [`MAX_DEPTH`] holds.
+    #[error("too many transparent balances: {0}")]
+    TooManyLeaves(usize),
+}
+
+/// Balance of one P2PKH address at the snapshot height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TransparentBalance {
+    /// P2PKH public key hash.
+    pub pubkey_hash: [u8; PUBKEY_HASH_SIZE],
+    /// Balance in zatoshis.
+    pub balance: u64,
+}
+
+impl TransparentBalance {
+    /// Leaf hash committing to this balance.
+    #[must_use]
+    pub fn leaf_hash(&self) -> [u8; 32] {
+        hash32(
+            LEAF_PERSONALIZATION,
+            &[&self.pubkey_hash, &self.balance.to_le_bytes()],
+        )
+    }
+}
+
+/// Merkle tree over the sorted balances of a transparent snapshot.
+#[derive(Debug, Clone)]
+pub struct BalanceTree {
+    balances: Vec<TransparentBalance>,
+    /// Node hashes per level, from the padded leaves up to the root.
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl BalanceTree {
+    /// Build the tree over `balances`, in any order.
+    ///
+    /// # Errors
+    /// Returns an error if `balances` is empty, holds a public key hash twice or does not fit a
+    /// tree of [`MAX_DEPTH`].
+    pub fn new(mut balances: Vec<TransparentBalance>) -> Result<Self, BalanceTreeError> {
+        if balances.is_empty() {
+            return Err(BalanceTreeError::Empty);
+        }
+        balances.sort_unstable();
+        if let Some(pair) = balances
+            .windows(2)
+            .find(|pair| matches!(pair, [a, b] if a.pubkey_hash == b.pubkey_hash)) &&
+            let Some(first) = pair.first()
+        {
+            return Err(BalanceTreeError::DuplicateAddress(hex_string(
+                &first.pubkey_hash,
+            )));
+        }
+        let width = balances
+            .len()
+            .checked_next_power_of_two()
+            .filter(|width| {
+                usize::try_from(width.trailing_zeros()).is_ok_and(|depth| depth <= MAX_DEPTH)
+            })
+            .ok_or(BalanceTreeError::TooManyLeaves(balances.len()))?;
+
+        let mut leaves: Vec<[u8; 32]> =
+            balances.iter().map(TransparentBalance::leaf_hash).collect();
+        leaves.resize(width, EMPTY_LEAF);
+        let mut levels = vec![leaves];
+        while let Some(level) = levels.last() &&
+            level.len() > 1
+        {
+            let parents = level
+                .as_chunks::<2>()
+                .0
+                .iter()
+                .map(|[left, right]| node_hash(left, right))
+                .collect();
+            levels.push(parents);
+        }
+        Ok(Self { balances, levels })
+    }
+
+    /// Root of the tree.
+    #[must_use]
+    pub fn root(&self) -> [u8; 32] {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or(EMPTY_LEAF)
+    }
+
+    /// Number of authentication path nodes per leaf.
+    #[must_use]
+    pub const fn depth(&self) -> usize {
+        self.levels.len().saturating_sub(1)
+    }
+
+    /// Committed balances, sorted by public key hash.
+    #[must_use]
+    pub fn balances(&self) -> &[TransparentBalance] {
+        &self.balances
+    }
+
+    /// Sum of all committed balances, or `None` on overflow.
+    #[must_use]
+    pub fn total_balance(&self) -> Option<u64> {
+        self.balances
+            .iter()
+            .try_fold(0_u64, |total, entry| total.checked_add(entry.balance))
+    }
+
+    /// Leaf index and balance of a public key hash.
+    #[must_use]
+    pub fn find(&self, pubkey_hash: &[u8; PUBKEY_HASH_SIZE]) -> Option<(u64, TransparentBalance)> {
+        let index = self
+            .balances
+            .binary_search_by(|entry| entry.pubkey_hash.cmp(pubkey_hash))
+            .ok()?;
+        let entry = self.balances.get(index).copied()?;
+        Some((u64::try_from(index).ok()?, entry))
+    }
+
+    /// Authentication path of a leaf, from the leaf's sibling up.
+    #[must_use]
+    pub fn path(&self, leaf_index: u64) -> Option<Vec<[u8; 32]>> {
+        let mut index = usize::try_from(leaf_index).ok()?;
+        if index >= self.balances.len() {
+            return None;
+        }
+        let mut path = Vec::with_capacity(self.depth());
+        for level in self.levels.iter().take(self.depth()) {
+            path.push(*level.get(index ^ 1)?);
+            index >>= 1;
+        }
+        Some(path)
+    }
+}
+
+/// Root implied by a leaf hash at `leaf_index` and its authentication path.
+///
+/// Returns `None` if the path is longer than [`MAX_DEPTH`] or `leaf_index` does not fit it.
+#[must_use]
+pub fn root_from_path(leaf_hash: [u8; 32], leaf_index: u64, path: &[[u8; 32]]) -> Option<[u8; 32]> {
+    if path.len() > MAX_DEPTH || leaf_index.checked_shr(u32::try_from(path.len()).ok()?)? != 0 {
+        return None;
+    }
+    let mut index = leaf_index;
+    let mut node = leaf_hash;
+    for sibling in path {
+        node = if index & 1 == 0 {
+            node_hash(&node, sibling)
+        } else {
+            node_hash(sibling, &node)
+        };
+        index >>= 1;
+    }
+    Some(node)
+}
+
+/// Airdrop nullifier of a transparent address, preventing it from claiming twice.
+#[must_use]
+pub fn airdrop_nullifier(target_id: &[u8], pubkey_hash: &[u8; PUBKEY_HASH_SIZE]) -> Nullifier {
+    Nullifier::from(hash32(NULLIFIER_PERSONALIZATION, &[target_id, pubkey_hash]))
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hash32(NODE_PERSONALIZATION, &[left, right])
+}
+
+fn hash32(personalization: &[u8; 16], parts: &[&[u8]]) -> [u8; 32] {
+    let mut state = Params::new()
+        .hash_length(32)
+        .personal(personalization)
+        .to_state();
+    for part in parts {
+        state.update(part);
+    }
+    let mut out = [0_u8; 32];
+    out.copy_from_slice(state.finalize().as_bytes());
+    out
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance(byte: u8, balance: u64) -> TransparentBalance {
+        TransparentBalance {
+            pubkey_hash: [byte; PUBKEY_HASH_SIZE],
+            balance,
+        }
+    }
+
+    #[test]
+    fn paths_recompute_root() {
+        let tree = BalanceTree::new(vec![
+            balance(5, 50),
+            balance(1, 10),
+            balance(3, 30),
+            balance(2, 20),
+            balance(4, 40),
+        ])
+        .expect("tree");
+        assert_eq!(tree.depth(), 3);
+        assert_eq!(tree.total_balance(), Some(150));
+
+        for entry in tree.balances() {
+            let (index, found) = tree.find(&entry.pubkey_hash).expect("leaf");
+            assert_eq!(&found, entry);
+            let path = tree.path(index).expect("path");
+            assert_eq!(
+                root_from_path(entry.leaf_hash(), index, &path),
+                Some(tree.root())
+            );
+        }
+
+        let (index, _) = tree.find(&[3; PUBKEY_HASH_SIZE]).expect("leaf");
+        let path = tree.path(index).expect("path");
+        assert_ne!(
+            root_from_path(balance(3, 31).leaf_hash(), index, &path),
+            Some(tree.root())
+        );
+        assert_eq!(root_from_path(balance(3, 30).leaf_hash(), 8, &path), None);
+        assert!(tree.path(5).is_none());
+    }
+
+    #[test]
+    fn single_leaf_tree_is_the_leaf() {
+        let tree = BalanceTree::new(vec![balance(9, 1)]).expect("tree");
+        assert_eq!(tree.depth(), 0);
+        assert_eq!(tree.root(), balance(9, 1).leaf_hash());
+        assert_eq!(tree.path(0), Some(Vec::new()));
+    }
+
+    #[test]
+    fn rejects_duplicates_and_empty_sets() {
+        assert_eq!(
+            BalanceTree::new(Vec::new()).err(),
+            Some(BalanceTreeError::Empty)
+        );
+        assert!(matches!(
+            BalanceTree::new(vec![balance(1, 1), balance(1, 2)]),
+            Err(BalanceTreeError::DuplicateAddress(_))
+        ));
+    }
+
+    #[test]
+    fn nullifier_binds_target_id() {
+        let pubkey_hash = [7_u8; PUBKEY_HASH_SIZE];
+        assert_ne!(
+            airdrop_nullifier(b"ZAIRTEST:T", &pubkey_hash),
+            airdrop_nullifier(b"ZAIRPROD:T", &pubkey_hash)
+        );
+    }
+}
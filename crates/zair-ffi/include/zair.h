@@ -0,0 +1,311 @@
+#ifndef ZAIR_H
+#define ZAIR_H
+
+/* Generated by cbindgen from crates/zair-ffi. Do not edit by hand; see tests/header.rs. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Memory-map precomputed gap-tree files and fail if they are missing.
+#define ZAIR_GAP_TREE_MODE_NONE 0
+
+// Rebuild gap trees from the snapshots and persist them.
+#define ZAIR_GAP_TREE_MODE_REBUILD 1
+
+// Build sparse in-memory gap trees from the snapshots.
+#define ZAIR_GAP_TREE_MODE_SPARSE 2
+
+// Fetch gap witnesses from a witness server.
+#define ZAIR_GAP_TREE_MODE_REMOTE 3
+
+// Splice gap witnesses from published gap-tree shards.
+#define ZAIR_GAP_TREE_MODE_SHARDED 4
+
+// Require the Orchard params file to exist.
+#define ZAIR_ORCHARD_PARAMS_REQUIRE 0
+
+// Generate and persist the Orchard params file if it is missing.
+#define ZAIR_ORCHARD_PARAMS_AUTO 1
+
+// Result code returned by every fallible `zair_*` function.
+//
+// On any code other than `ZAIR_STATUS_OK`, `zair_context_last_error` returns a description.
+typedef enum ZairStatus {
+  // The call succeeded.
+  ZAIR_STATUS_OK = 0,
+  // A required pointer argument was null.
+  ZAIR_STATUS_NULL_ARGUMENT = 1,
+  // A string argument was not valid UTF-8.
+  ZAIR_STATUS_INVALID_UTF8 = 2,
+  // An argument had an invalid value.
+  ZAIR_STATUS_INVALID_ARGUMENT = 3,
+  // A file could not be read or written.
+  ZAIR_STATUS_IO = 4,
+  // The operation failed, for example on an invalid proof or signature.
+  ZAIR_STATUS_FAILED = 5,
+  // The operation is not available in this build.
+  ZAIR_STATUS_UNSUPPORTED = 6,
+  // The library panicked; the context remains usable.
+  ZAIR_STATUS_PANIC = 7,
+  // The call was stopped by `zair_context_cancel`.
+  ZAIR_STATUS_CANCELLED = 8,
+} ZairStatus;
+
+// Pipeline step reported to the progress callback.
+typedef enum ZairStage {
+  // `zair_claim_prepare`.
+  ZAIR_STAGE_PREPARE = 0,
+  // `zair_claim_prove`.
+  ZAIR_STAGE_PROVE = 1,
+  // `zair_claim_sign`.
+  ZAIR_STAGE_SIGN = 2,
+  // `zair_verify`.
+  ZAIR_STAGE_VERIFY = 3,
+} ZairStage;
+
+// Opaque handle owning the runtime that drives the SDK.
+//
+// A context may be moved between threads but must not be used by two threads at once, except
+// for `zair_context_cancel`.
+typedef struct ZairContext ZairContext;
+
+// Progress callback.
+//
+// Called with `completed == 0` when a stage starts and `completed == total` when it finishes
+// successfully. In between, the SDK's scan, gap-tree, proving and verification phases report
+// their own counts, where `total == 0` means the total is not known yet. Those calls may come
+// from runtime worker threads, but never two at once and never after the call has returned.
+typedef void (*ZairProgressCallback)(void *user_data,
+                                     enum ZairStage stage,
+                                     uint32_t completed,
+                                     uint32_t total);
+
+// Arguments for [`zair_claim_prepare`]; mirrors `zair claim prepare`.
+typedef struct ZairPrepareArgs {
+  // `sizeof(ZairPrepareArgs)` as compiled by the caller.
+  size_t struct_size;
+  // Airdrop configuration file.
+  const char *config;
+  // Unified full viewing key, encoded.
+  const char *ufvk;
+  // Wallet birthday height; ignored when `wallet_db` is set.
+  uint64_t birthday;
+  // Lightwalletd URL, or null for the network default.
+  const char *lightwalletd_url;
+  // Sapling snapshot file, or null.
+  const char *snapshot_sapling;
+  // Orchard snapshot file, or null.
+  const char *snapshot_orchard;
+  // Sapling gap-tree file, or null.
+  const char *gap_tree_sapling;
+  // Orchard gap-tree file, or null.
+  const char *gap_tree_orchard;
+  // One of the `ZAIR_GAP_TREE_MODE_*` constants.
+  uint32_t gap_tree_mode;
+  // Witness server URL for `ZAIR_GAP_TREE_MODE_REMOTE`, or null.
+  const char *witness_server_url;
+  // Gap-tree shard URL or directory for `ZAIR_GAP_TREE_MODE_SHARDED`, or null.
+  const char *gap_tree_shards;
+  // Directory caching the note scan between runs, or null to always scan.
+  const char *scan_cache;
+  // `zcash_client_sqlite` wallet database to import notes from instead of scanning, or null.
+  const char *wallet_db;
+  // Output file for the prepared claims.
+  const char *claims_out;
+  // Hex Ed25519 public key of the airdrop organizer, or null. When set, the configuration is
+  // refused unless `config_signature` is the organizer's signature over it.
+  const char *trusted_organizer_key;
+  // Detached configuration signature file written by `zair config sign`; required when
+  // `trusted_organizer_key` is set.
+  const char *config_signature;
+} ZairPrepareArgs;
+
+// Arguments for [`zair_claim_prove`]; mirrors `zair claim prove`.
+typedef struct ZairProveArgs {
+  // `sizeof(ZairProveArgs)` as compiled by the caller.
+  size_t struct_size;
+  // Airdrop configuration file.
+  const char *config;
+  // Prepared claims file from [`zair_claim_prepare`].
+  const char *claims_in;
+  // File holding the 64-byte seed as hex.
+  const char *seed;
+  // ZIP-32 account index.
+  uint32_t account;
+  // Sapling proving key file.
+  const char *sapling_pk;
+  // Orchard params file.
+  const char *orchard_params;
+  // One of the `ZAIR_ORCHARD_PARAMS_*` constants.
+  uint32_t orchard_params_mode;
+  // Output file for the proofs.
+  const char *proofs_out;
+  // Output file for the local-only proof secrets.
+  const char *secrets_out;
+  // Airdrop target of the configuration to prove for, or null for its default airdrop.
+  const char *target;
+  // Hex Ed25519 public key of the airdrop organizer, or null. When set, the configuration is
+  // refused unless `config_signature` is the organizer's signature over it.
+  const char *trusted_organizer_key;
+  // Detached configuration signature file written by `zair config sign`; required when
+  // `trusted_organizer_key` is set.
+  const char *config_signature;
+} ZairProveArgs;
+
+// Arguments for [`zair_claim_sign`]; mirrors `zair claim sign`.
+typedef struct ZairSignArgs {
+  // `sizeof(ZairSignArgs)` as compiled by the caller.
+  size_t struct_size;
+  // Airdrop configuration file.
+  const char *config;
+  // Proofs file from [`zair_claim_prove`].
+  const char *proofs_in;
+  // Secrets file from [`zair_claim_prove`].
+  const char *secrets_in;
+  // File holding the 64-byte seed as hex.
+  const char *seed;
+  // ZIP-32 account index.
+  uint32_t account;
+  // Shared message payload file, or null.
+  const char *message;
+  // Per-claim message assignments JSON, or null.
+  const char *messages;
+  // Output file for the signed submission.
+  const char *submission_out;
+  // Airdrop target of the configuration to sign for, or null for its default airdrop.
+  const char *target;
+  // Hex Ed25519 public key of the airdrop organizer, or null. When set, the configuration is
+  // refused unless `config_signature` is the organizer's signature over it.
+  const char *trusted_organizer_key;
+  // Detached configuration signature file written by `zair config sign`; required when
+  // `trusted_organizer_key` is set.
+  const char *config_signature;
+} ZairSignArgs;
+
+// Arguments for [`zair_verify`]; mirrors `zair verify run`.
+typedef struct ZairVerifyArgs {
+  // `sizeof(ZairVerifyArgs)` as compiled by the caller.
+  size_t struct_size;
+  // Airdrop configuration file.
+  const char *config;
+  // Signed submission file.
+  const char *submission_in;
+  // Sapling verifying key file.
+  const char *sapling_vk;
+  // Orchard params file.
+  const char *orchard_params;
+  // One of the `ZAIR_ORCHARD_PARAMS_*` constants.
+  uint32_t orchard_params_mode;
+  // Shared message payload file, or null.
+  const char *message;
+  // Per-claim message assignments JSON, or null.
+  const char *messages;
+  // Airdrop target of the configuration the claims were made for, or null for its default
+  // airdrop.
+  const char *target;
+  // Claim registry directory, or null. Claims already recorded there fail, and the claims
+  // that passed every check are recorded.
+  const char *registry;
+  // Output file for the claims that passed every check, or null.
+  const char *valid_submission_out;
+  // Hex Ed25519 public key of the airdrop organizer, or null. When set, the configuration is
+  // refused unless `config_signature` is the organizer's signature over it.
+  const char *trusted_organizer_key;
+  // Detached configuration signature file written by `zair config sign`; required when
+  // `trusted_organizer_key` is set.
+  const char *config_signature;
+  // Current target-chain block height a height-bounded claim window is checked against, or
+  // 0 for none, which such a window rejects.
+  uint64_t current_height;
+  // Current target-chain time in seconds since the Unix epoch a time-bounded claim window is
+  // checked against, or 0 for the system clock.
+  uint64_t current_time;
+} ZairVerifyArgs;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Library version as a static NUL-terminated string.
+const char *zair_version(void);
+
+// Create a context. Returns null if the runtime cannot be started.
+//
+// Free it with [`zair_context_free`].
+struct ZairContext *zair_context_new(void);
+
+// Free a context. Null is ignored.
+//
+// # Safety
+// `ctx` must be null or a pointer returned by [`zair_context_new`] that has not been freed.
+void zair_context_free(struct ZairContext *ctx);
+
+// Message describing the last failed call on `ctx`, or null if the last call succeeded.
+//
+// The string is owned by the context and valid until the next call on it.
+//
+// # Safety
+// `ctx` must be null or a live context.
+const char *zair_context_last_error(const struct ZairContext *ctx);
+
+// Register a progress callback, or clear it by passing null. `user_data` is passed back
+// unchanged.
+//
+// # Safety
+// `ctx` must be null or a live context, and `callback` must be safe to call with `user_data`
+// for as long as it is registered.
+enum ZairStatus zair_context_set_progress_callback(struct ZairContext *ctx,
+                                                   ZairProgressCallback callback,
+                                                   void *user_data);
+
+// Cancel the call running on `ctx`, which then returns `ZAIR_STATUS_CANCELLED`.
+//
+// Unlike every other function, this one may be called from another thread while a call runs
+// on the context. With no call running it does nothing; the next call starts uncancelled.
+//
+// # Safety
+// `ctx` must be null or a live context.
+enum ZairStatus zair_context_cancel(const struct ZairContext *ctx);
+
+// Scan for the UFVK's eligible notes and write the prepared claims.
+//
+// # Safety
+// `ctx` must be a live context and `args` must point to a [`ZairPrepareArgs`] of `struct_size`
+// bytes whose string fields are null or valid NUL-terminated strings.
+enum ZairStatus zair_claim_prepare(struct ZairContext *ctx, const struct ZairPrepareArgs *args);
+
+// Generate claim proofs for prepared claims.
+//
+// Returns `ZAIR_STATUS_UNSUPPORTED` if the library was built without the `prove` feature.
+//
+// # Safety
+// `ctx` must be a live context and `args` must point to a [`ZairProveArgs`] of `struct_size` bytes
+// whose string fields are null or valid NUL-terminated strings.
+enum ZairStatus zair_claim_prove(struct ZairContext *ctx, const struct ZairProveArgs *args);
+
+// Sign claim proofs into a submission.
+//
+// At least one of `message` and `messages` must be set.
+//
+// # Safety
+// `ctx` must be a live context and `args` must point to a [`ZairSignArgs`] of `struct_size` bytes
+// whose string fields are null or valid NUL-terminated strings.
+enum ZairStatus zair_claim_sign(struct ZairContext *ctx, const struct ZairSignArgs *args);
+
+// Verify a signed submission's signatures and proofs.
+//
+// Returns `ZAIR_STATUS_OK` only if every claim is valid.
+//
+// # Safety
+// `ctx` must be a live context and `args` must point to a [`ZairVerifyArgs`] of `struct_size`
+// bytes whose string fields are null or valid NUL-terminated strings.
+enum ZairStatus zair_verify(struct ZairContext *ctx, const struct ZairVerifyArgs *args);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ZAIR_H */
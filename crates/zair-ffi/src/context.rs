@@ -0,0 +1,407 @@
+//! The opaque context handle owning the async runtime and per-call state.
+
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::future::Future;
+use std::mem::{MaybeUninit, size_of};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use zair_sdk::commands::{TrustedOrganizer, parse_organizer_public_key};
+use zair_sdk::progress::{CancellationToken, Progress};
+
+use crate::status::{FfiError, ZairStatus};
+
+/// Pipeline step reported to the progress callback.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZairStage {
+    /// `zair_claim_prepare`.
+    Prepare = 0,
+    /// `zair_claim_prove`.
+    Prove = 1,
+    /// `zair_claim_sign`.
+    Sign = 2,
+    /// `zair_verify`.
+    Verify = 3,
+}
+
+/// Progress callback.
+///
+/// Called with `completed == 0` when a stage starts and `completed == total` when it finishes
+/// successfully. In between, the SDK's scan, gap-tree, proving and verification phases report
+/// their own counts, where `total == 0` means the total is not known yet. Those calls may come
+/// from runtime worker threads, but never two at once and never after the call has returned.
+pub type ZairProgressCallback = Option<
+    unsafe extern "C" fn(user_data: *mut c_void, stage: ZairStage, completed: u32, total: u32),
+>;
+
+/// A registered progress callback and the user data it is called with.
+#[derive(Clone, Copy)]
+struct Callback {
+    function: unsafe extern "C" fn(*mut c_void, ZairStage, u32, u32),
+    user_data: *mut c_void,
+}
+
+// SAFETY: whoever registers the callback promises it may be called with `user_data` while it is
+// registered; `StageProgress` serializes the calls, so neither is used from two threads at once.
+unsafe impl Send for Callback {}
+// SAFETY: see `Send`.
+unsafe impl Sync for Callback {}
+
+impl Callback {
+    fn call(self, stage: ZairStage, completed: u32, total: u32) {
+        // SAFETY: the caller registered `function` together with `user_data`.
+        unsafe { (self.function)(self.user_data, stage, completed, total) };
+    }
+}
+
+/// Opaque handle owning the runtime that drives the SDK.
+///
+/// A context may be moved between threads but must not be used by two threads at once, except
+/// for `zair_context_cancel`.
+pub struct ZairContext {
+    runtime: tokio::runtime::Runtime,
+    last_error: Mutex<Option<CString>>,
+    progress: Mutex<Option<Callback>>,
+    cancel: Mutex<CancellationToken>,
+}
+
+impl ZairContext {
+    /// Create a context with a fresh multi-threaded runtime.
+    ///
+    /// # Errors
+    /// Returns an error if the runtime cannot be started.
+    pub fn new() -> std::io::Result<Self> {
+        Ok(Self {
+            runtime: tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()?,
+            last_error: Mutex::new(None),
+            progress: Mutex::new(None),
+            cancel: Mutex::new(CancellationToken::new()),
+        })
+    }
+
+    /// Register or clear the progress callback.
+    pub fn set_progress(&self, callback: ZairProgressCallback, user_data: *mut c_void) {
+        *lock(&self.progress) = callback.map(|function| Callback {
+            function,
+            user_data,
+        });
+    }
+
+    /// Message of the last failed call, or null.
+    ///
+    /// The string lives until the next call replaces it.
+    pub fn last_error(&self) -> *const c_char {
+        lock(&self.last_error)
+            .as_deref()
+            .map_or(std::ptr::null(), CStr::as_ptr)
+    }
+
+    /// Cancel the running call, if any.
+    pub fn cancel(&self) {
+        lock(&self.cancel).cancel();
+    }
+
+    /// Drive an SDK future to completion on the context's runtime, stopping it once the call is
+    /// cancelled.
+    ///
+    /// # Errors
+    /// Returns the future's error, classified into a [`ZairStatus`].
+    pub fn block_on<F, T>(&self, progress: &Progress, future: F) -> Result<(), FfiError>
+    where
+        F: Future<Output = eyre::Result<T>>,
+    {
+        self.runtime.block_on(progress.run_cancellable(future))?;
+        Ok(())
+    }
+
+    /// Start a call: clear the last error, arm a fresh cancellation token, and build the
+    /// [`Progress`] the SDK reports to.
+    fn begin(&self, stage: ZairStage) -> StageProgress {
+        *lock(&self.last_error) = None;
+        let cancel = CancellationToken::new();
+        *lock(&self.cancel) = cancel.clone();
+        let callback = *lock(&self.progress);
+        let reporter = Arc::new(Mutex::new(callback));
+        let sink = Arc::clone(&reporter);
+        let progress = Progress::new(move |event| {
+            report(&sink, stage, saturate(event.current), saturate(event.total));
+        })
+        .with_cancellation(cancel);
+        StageProgress {
+            stage,
+            reporter,
+            progress,
+        }
+    }
+
+    fn record_error(&self, message: &str) {
+        *lock(&self.last_error) = CString::new(message.replace('\0', " ")).ok();
+    }
+}
+
+/// The progress handle of one call and the callback it reports to.
+struct StageProgress {
+    stage: ZairStage,
+    reporter: Arc<Mutex<Option<Callback>>>,
+    progress: Progress,
+}
+
+impl StageProgress {
+    fn report(&self, completed: u32) {
+        report(&self.reporter, self.stage, completed, 1);
+    }
+}
+
+impl Drop for StageProgress {
+    /// Detach the callback, so SDK work still winding down after a cancelled call cannot reach
+    /// it once the call has returned.
+    fn drop(&mut self) {
+        *lock(&self.reporter) = None;
+    }
+}
+
+/// Call the callback of `reporter`, if still attached, holding the lock for the whole call.
+#[allow(
+    clippy::significant_drop_in_scrutinee,
+    reason = "the lock serializes callback calls and detaching"
+)]
+fn report(reporter: &Mutex<Option<Callback>>, stage: ZairStage, completed: u32, total: u32) {
+    if let Some(callback) = *lock(reporter) {
+        callback.call(stage, completed, total);
+    }
+}
+
+fn saturate(count: u64) -> u32 {
+    u32::try_from(count).unwrap_or(u32::MAX)
+}
+
+/// Lock `mutex`, ignoring poisoning: every value it guards is valid after any panic.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Run one stage against a context handle, recording errors and catching panics.
+///
+/// `operation` gets the [`Progress`] of the call, which forwards SDK progress to the registered
+/// callback and is cancelled by `zair_context_cancel`.
+///
+/// # Safety
+/// `ctx` must be null or a pointer returned by `zair_context_new` that has not been freed.
+pub unsafe fn run_stage(
+    ctx: *const ZairContext,
+    stage: ZairStage,
+    operation: impl FnOnce(&ZairContext, &Progress) -> Result<(), FfiError>,
+) -> ZairStatus {
+    // SAFETY: guaranteed by the caller.
+    let Some(ctx) = (unsafe { ctx.as_ref() }) else {
+        return ZairStatus::NullArgument;
+    };
+    let call = ctx.begin(stage);
+    call.report(0);
+    let result =
+        catch_unwind(AssertUnwindSafe(|| operation(ctx, &call.progress))).unwrap_or_else(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(ToString::to_string)
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(FfiError::new(
+                ZairStatus::Panic,
+                format!("panic: {message}"),
+            ))
+        });
+    match result {
+        Ok(()) => {
+            call.report(1);
+            ZairStatus::Ok
+        }
+        Err(err) => {
+            ctx.record_error(&err.message);
+            err.status
+        }
+    }
+}
+
+/// Borrow a required NUL-terminated UTF-8 string argument.
+///
+/// # Safety
+/// `ptr` must be null or point to a NUL-terminated string valid for the call.
+pub unsafe fn required_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    // SAFETY: guaranteed by the caller.
+    unsafe { optional_str(ptr, name) }?.ok_or_else(|| FfiError::null_argument(name))
+}
+
+/// Borrow an optional NUL-terminated UTF-8 string argument; null means absent.
+///
+/// # Safety
+/// `ptr` must be null or point to a NUL-terminated string valid for the call.
+pub unsafe fn optional_str<'a>(
+    ptr: *const c_char,
+    name: &str,
+) -> Result<Option<&'a str>, FfiError> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: `ptr` is non-null and NUL-terminated per the caller's contract.
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map(Some)
+        .map_err(|_| FfiError::new(ZairStatus::InvalidUtf8, format!("`{name}` is not UTF-8")))
+}
+
+/// Read a required path argument.
+///
+/// # Safety
+/// See [`required_str`].
+pub unsafe fn required_path(ptr: *const c_char, name: &str) -> Result<PathBuf, FfiError> {
+    // SAFETY: guaranteed by the caller.
+    unsafe { required_str(ptr, name) }.map(PathBuf::from)
+}
+
+/// Read an optional path argument; null means absent.
+///
+/// # Safety
+/// See [`optional_str`].
+pub unsafe fn optional_path(ptr: *const c_char, name: &str) -> Result<Option<PathBuf>, FfiError> {
+    // SAFETY: guaranteed by the caller.
+    Ok(unsafe { optional_str(ptr, name) }?.map(PathBuf::from))
+}
+
+/// Read the organizer an airdrop configuration must be signed by: a hex Ed25519 public key and
+/// the detached signature file. A null key means the configuration is not checked.
+///
+/// # Errors
+/// Returns an error if the key is not 64 hex characters, or the signature file is null while
+/// the key is set.
+///
+/// # Safety
+/// See [`optional_str`].
+pub unsafe fn trusted_organizer(
+    key: *const c_char,
+    signature: *const c_char,
+) -> Result<Option<TrustedOrganizer>, FfiError> {
+    // SAFETY: guaranteed by the caller.
+    let Some(key) = (unsafe { optional_str(key, "trusted_organizer_key") })? else {
+        return Ok(None);
+    };
+    let public_key = parse_organizer_public_key(key)
+        .map_err(|err| FfiError::new(ZairStatus::InvalidArgument, format!("{err}")))?;
+    Ok(Some(TrustedOrganizer {
+        public_key,
+        // SAFETY: guaranteed by the caller.
+        signature_file: unsafe { required_path(signature, "config_signature") }?,
+    }))
+}
+
+/// An argument struct led by a `struct_size` field, all of whose fields are valid when zeroed.
+///
+/// # Safety
+/// The struct must be `#[repr(C)]`, start with a `usize` holding its size, and hold only raw
+/// pointers and integers, for which zero means absent.
+pub unsafe trait SizedArgs: Sized {}
+
+/// Copy an argument struct, reading only the `struct_size` bytes the caller declares.
+///
+/// Fields are only ever appended to the argument structs, so a caller built against an older
+/// header passes a smaller `struct_size` and the fields it does not know read as zero.
+///
+/// # Errors
+/// Returns an error if `args` is null, `struct_size` is not set, or it is larger than this
+/// library's struct, meaning the caller was built against a newer header.
+///
+/// # Safety
+/// `args` must be null or point to a struct whose first `struct_size` bytes are readable.
+pub unsafe fn read_args<T: SizedArgs>(args: *const T, name: &str) -> Result<T, FfiError> {
+    if args.is_null() {
+        return Err(FfiError::null_argument(name));
+    }
+    // SAFETY: `args` is non-null and every argument struct starts with its `usize` size.
+    let struct_size = unsafe { args.cast::<usize>().read_unaligned() };
+    if struct_size < size_of::<usize>() {
+        return Err(FfiError::new(
+            ZairStatus::InvalidArgument,
+            format!("`{name}.struct_size` must be set to the size of the struct"),
+        ));
+    }
+    if struct_size > size_of::<T>() {
+        return Err(FfiError::new(
+            ZairStatus::InvalidArgument,
+            format!(
+                "`{name}.struct_size` is {struct_size} bytes, larger than the {} bytes this \
+                 library knows; the caller was built against a newer zair.h",
+                size_of::<T>()
+            ),
+        ));
+    }
+    let mut copy = MaybeUninit::<T>::zeroed();
+    // SAFETY: the caller declared `struct_size` readable bytes, which fit in `T`; the bytes
+    // past them stay zeroed, which `SizedArgs` makes a valid `T`.
+    unsafe {
+        std::ptr::copy_nonoverlapping(
+            args.cast::<u8>(),
+            copy.as_mut_ptr().cast::<u8>(),
+            struct_size,
+        );
+        Ok(copy.assume_init())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zair_sdk::progress::Phase;
+
+    use super::*;
+
+    unsafe extern "C" fn record(user_data: *mut c_void, _stage: ZairStage, done: u32, total: u32) {
+        // SAFETY: the tests register a `Vec<(u32, u32)>` that outlives the context.
+        let log = unsafe { &mut *user_data.cast::<Vec<(u32, u32)>>() };
+        log.push((done, total));
+    }
+
+    #[test]
+    fn sdk_progress_reaches_the_callback() {
+        let ctx = ZairContext::new().expect("runtime");
+        let mut log = Vec::<(u32, u32)>::new();
+        ctx.set_progress(Some(record), (&raw mut log).cast());
+
+        // SAFETY: `ctx` is a live context.
+        let status = unsafe {
+            run_stage(&raw const ctx, ZairStage::Prove, |_, progress| {
+                progress.report(Phase::Prove, None, 2, 5);
+                progress.report(Phase::Prove, None, u64::MAX, 0);
+                Ok(())
+            })
+        };
+        assert_eq!(status, ZairStatus::Ok);
+        assert_eq!(log, [(0, 1), (2, 5), (u32::MAX, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn cancel_stops_the_running_call_only() {
+        let ctx = ZairContext::new().expect("runtime");
+        // SAFETY: `ctx` is a live context.
+        let status = unsafe {
+            run_stage(&raw const ctx, ZairStage::Verify, |ctx, progress| {
+                ctx.cancel();
+                ctx.block_on(progress, std::future::pending::<eyre::Result<()>>())
+            })
+        };
+        assert_eq!(status, ZairStatus::Cancelled);
+        assert!(
+            !ctx.last_error().is_null(),
+            "a cancelled call leaves an error message"
+        );
+
+        // SAFETY: `ctx` is a live context.
+        let status = unsafe {
+            run_stage(&raw const ctx, ZairStage::Verify, |ctx, progress| {
+                ctx.block_on(progress, async { Ok(()) })
+            })
+        };
+        assert_eq!(status, ZairStatus::Ok);
+    }
+}
@@ -0,0 +1,480 @@
+//! C ABI for embedding ZAIR airdrop claims in wallets.
+//!
+//! Every call takes a [`ZairContext`] handle from [`zair_context_new`], returns a
+//! [`ZairStatus`], and on failure leaves a message for [`zair_context_last_error`]. Inputs and
+//! outputs are the same files the `zair` CLI reads and writes; string arguments are
+//! NUL-terminated UTF-8 and optional ones may be null. The C header is `include/zair.h`,
+//! generated by cbindgen from this crate.
+//!
+//! Every argument struct starts with `struct_size`, which the caller sets to `sizeof` the
+//! struct. Fields are only ever appended, so a caller built against an older header keeps
+//! working: the fields it does not know are read as zero, which means absent.
+#![allow(unsafe_code, reason = "C ABI boundary")]
+
+mod context;
+mod status;
+
+use std::ffi::{c_char, c_void};
+
+use context::{
+    SizedArgs, optional_path, optional_str, read_args, required_path, required_str, run_stage,
+    trusted_organizer,
+};
+pub use context::{ZairContext, ZairProgressCallback, ZairStage};
+use status::FfiError;
+pub use status::ZairStatus;
+use zair_sdk::commands::{GapTreeMode, OrchardParamsMode};
+use zair_sdk::progress::Progress;
+
+/// Memory-map precomputed gap-tree files and fail if they are missing.
+pub const ZAIR_GAP_TREE_MODE_NONE: u32 = 0;
+/// Rebuild gap trees from the snapshots and persist them.
+pub const ZAIR_GAP_TREE_MODE_REBUILD: u32 = 1;
+/// Build sparse in-memory gap trees from the snapshots.
+pub const ZAIR_GAP_TREE_MODE_SPARSE: u32 = 2;
+/// Fetch gap witnesses from a witness server.
+pub const ZAIR_GAP_TREE_MODE_REMOTE: u32 = 3;
+/// Splice gap witnesses from published gap-tree shards.
+pub const ZAIR_GAP_TREE_MODE_SHARDED: u32 = 4;
+
+/// Require the Orchard params file to exist.
+pub const ZAIR_ORCHARD_PARAMS_REQUIRE: u32 = 0;
+/// Generate and persist the Orchard params file if it is missing.
+pub const ZAIR_ORCHARD_PARAMS_AUTO: u32 = 1;
+
+/// Arguments for [`zair_claim_prepare`]; mirrors `zair claim prepare`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ZairPrepareArgs {
+    /// `sizeof(ZairPrepareArgs)` as compiled by the caller.
+    pub struct_size: usize,
+    /// Airdrop configuration file.
+    pub config: *const c_char,
+    /// Unified full viewing key, encoded.
+    pub ufvk: *const c_char,
+    /// Wallet birthday height; ignored when `wallet_db` is set.
+    pub birthday: u64,
+    /// Lightwalletd URL, or null for the network default.
+    pub lightwalletd_url: *const c_char,
+    /// Sapling snapshot file, or null.
+    pub snapshot_sapling: *const c_char,
+    /// Orchard snapshot file, or null.
+    pub snapshot_orchard: *const c_char,
+    /// Sapling gap-tree file, or null.
+    pub gap_tree_sapling: *const c_char,
+    /// Orchard gap-tree file, or null.
+    pub gap_tree_orchard: *const c_char,
+    /// One of the `ZAIR_GAP_TREE_MODE_*` constants.
+    pub gap_tree_mode: u32,
+    /// Witness server URL for `ZAIR_GAP_TREE_MODE_REMOTE`, or null.
+    pub witness_server_url: *const c_char,
+    /// Gap-tree shard URL or directory for `ZAIR_GAP_TREE_MODE_SHARDED`, or null.
+    pub gap_tree_shards: *const c_char,
+    /// Directory caching the note scan between runs, or null to always scan.
+    pub scan_cache: *const c_char,
+    /// `zcash_client_sqlite` wallet database to import notes from instead of scanning, or null.
+    pub wallet_db: *const c_char,
+    /// Output file for the prepared claims.
+    pub claims_out: *const c_char,
+    /// Hex Ed25519 public key of the airdrop organizer, or null. When set, the configuration is
+    /// refused unless `config_signature` is the organizer's signature over it.
+    pub trusted_organizer_key: *const c_char,
+    /// Detached configuration signature file written by `zair config sign`; required when
+    /// `trusted_organizer_key` is set.
+    pub config_signature: *const c_char,
+}
+
+// SAFETY: `#[repr(C)]`, led by `struct_size`, and only raw pointers and integers.
+unsafe impl SizedArgs for ZairPrepareArgs {}
+
+/// Arguments for [`zair_claim_prove`]; mirrors `zair claim prove`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ZairProveArgs {
+    /// `sizeof(ZairProveArgs)` as compiled by the caller.
+    pub struct_size: usize,
+    /// Airdrop configuration file.
+    pub config: *const c_char,
+    /// Prepared claims file from [`zair_claim_prepare`].
+    pub claims_in: *const c_char,
+    /// File holding the 64-byte seed as hex.
+    pub seed: *const c_char,
+    /// ZIP-32 account index.
+    pub account: u32,
+    /// Sapling proving key file.
+    pub sapling_pk: *const c_char,
+    /// Orchard params file.
+    pub orchard_params: *const c_char,
+    /// One of the `ZAIR_ORCHARD_PARAMS_*` constants.
+    pub orchard_params_mode: u32,
+    /// Output file for the proofs.
+    pub proofs_out: *const c_char,
+    /// Output file for the local-only proof secrets.
+    pub secrets_out: *const c_char,
+    /// Airdrop target of the configuration to prove for, or null for its default airdrop.
+    pub target: *const c_char,
+    /// Hex Ed25519 public key of the airdrop organizer, or null. When set, the configuration is
+    /// refused unless `config_signature` is the organizer's signature over it.
+    pub trusted_organizer_key: *const c_char,
+    /// Detached configuration signature file written by `zair config sign`; required when
+    /// `trusted_organizer_key` is set.
+    pub config_signature: *const c_char,
+}
+
+// SAFETY: `#[repr(C)]`, led by `struct_size`, and only raw pointers and integers.
+unsafe impl SizedArgs for ZairProveArgs {}
+
+/// Arguments for [`zair_claim_sign`]; mirrors `zair claim sign`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ZairSignArgs {
+    /// `sizeof(ZairSignArgs)` as compiled by the caller.
+    pub struct_size: usize,
+    /// Airdrop configuration file.
+    pub config: *const c_char,
+    /// Proofs file from [`zair_claim_prove`].
+    pub proofs_in: *const c_char,
+    /// Secrets file from [`zair_claim_prove`].
+    pub secrets_in: *const c_char,
+    /// File holding the 64-byte seed as hex.
+    pub seed: *const c_char,
+    /// ZIP-32 account index.
+    pub account: u32,
+    /// Shared message payload file, or null.
+    pub message: *const c_char,
+    /// Per-claim message assignments JSON, or null.
+    pub messages: *const c_char,
+    /// Output file for the signed submission.
+    pub submission_out: *const c_char,
+    /// Airdrop target of the configuration to sign for, or null for its default airdrop.
+    pub target: *const c_char,
+    /// Hex Ed25519 public key of the airdrop organizer, or null. When set, the configuration is
+    /// refused unless `config_signature` is the organizer's signature over it.
+    pub trusted_organizer_key: *const c_char,
+    /// Detached configuration signature file written by `zair config sign`; required when
+    /// `trusted_organizer_key` is set.
+    pub config_signature: *const c_char,
+}
+
+// SAFETY: `#[repr(C)]`, led by `struct_size`, and only raw pointers and integers.
+unsafe impl SizedArgs for ZairSignArgs {}
+
+/// Arguments for [`zair_verify`]; mirrors `zair verify run`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ZairVerifyArgs {
+    /// `sizeof(ZairVerifyArgs)` as compiled by the caller.
+    pub struct_size: usize,
+    /// Airdrop configuration file.
+    pub config: *const c_char,
+    /// Signed submission file.
+    pub submission_in: *const c_char,
+    /// Sapling verifying key file.
+    pub sapling_vk: *const c_char,
+    /// Orchard params file.
+    pub orchard_params: *const c_char,
+    /// One of the `ZAIR_ORCHARD_PARAMS_*` constants.
+    pub orchard_params_mode: u32,
+    /// Shared message payload file, or null.
+    pub message: *const c_char,
+    /// Per-claim message assignments JSON, or null.
+    pub messages: *const c_char,
+    /// Airdrop target of the configuration the claims were made for, or null for its default
+    /// airdrop.
+    pub target: *const c_char,
+    /// Claim registry directory, or null. Claims already recorded there fail, and the claims
+    /// that passed every check are recorded.
+    pub registry: *const c_char,
+    /// Output file for the claims that passed every check, or null.
+    pub valid_submission_out: *const c_char,
+    /// Hex Ed25519 public key of the airdrop organizer, or null. When set, the configuration is
+    /// refused unless `config_signature` is the organizer's signature over it.
+    pub trusted_organizer_key: *const c_char,
+    /// Detached configuration signature file written by `zair config sign`; required when
+    /// `trusted_organizer_key` is set.
+    pub config_signature: *const c_char,
+    /// Current target-chain block height a height-bounded claim window is checked against, or
+    /// 0 for none, which such a window rejects.
+    pub current_height: u64,
+    /// Current target-chain time in seconds since the Unix epoch a time-bounded claim window is
+    /// checked against, or 0 for the system clock.
+    pub current_time: u64,
+}
+
+// SAFETY: `#[repr(C)]`, led by `struct_size`, and only raw pointers and integers.
+unsafe impl SizedArgs for ZairVerifyArgs {}
+
+fn gap_tree_mode(value: u32) -> Result<GapTreeMode, FfiError> {
+    match value {
+        ZAIR_GAP_TREE_MODE_NONE => Ok(GapTreeMode::None),
+        ZAIR_GAP_TREE_MODE_REBUILD => Ok(GapTreeMode::Rebuild),
+        ZAIR_GAP_TREE_MODE_SPARSE => Ok(GapTreeMode::Sparse),
+        ZAIR_GAP_TREE_MODE_REMOTE => Ok(GapTreeMode::Remote),
+        ZAIR_GAP_TREE_MODE_SHARDED => Ok(GapTreeMode::Sharded),
+        other => Err(FfiError::new(
+            ZairStatus::InvalidArgument,
+            format!("Invalid gap-tree mode: {other}"),
+        )),
+    }
+}
+
+fn orchard_params_mode(value: u32) -> Result<OrchardParamsMode, FfiError> {
+    match value {
+        ZAIR_ORCHARD_PARAMS_REQUIRE => Ok(OrchardParamsMode::Require),
+        ZAIR_ORCHARD_PARAMS_AUTO => Ok(OrchardParamsMode::Auto),
+        other => Err(FfiError::new(
+            ZairStatus::InvalidArgument,
+            format!("Invalid orchard params mode: {other}"),
+        )),
+    }
+}
+
+/// Library version as a static NUL-terminated string.
+#[unsafe(no_mangle)]
+pub const extern "C" fn zair_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Create a context. Returns null if the runtime cannot be started.
+///
+/// Free it with [`zair_context_free`].
+#[unsafe(no_mangle)]
+pub extern "C" fn zair_context_new() -> *mut ZairContext {
+    ZairContext::new().map_or(std::ptr::null_mut(), |ctx| Box::into_raw(Box::new(ctx)))
+}
+
+/// Free a context. Null is ignored.
+///
+/// # Safety
+/// `ctx` must be null or a pointer returned by [`zair_context_new`] that has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zair_context_free(ctx: *mut ZairContext) {
+    if !ctx.is_null() {
+        // SAFETY: `ctx` came from `Box::into_raw` in `zair_context_new`.
+        drop(unsafe { Box::from_raw(ctx) });
+    }
+}
+
+/// Message describing the last failed call on `ctx`, or null if the last call succeeded.
+///
+/// The string is owned by the context and valid until the next call on it.
+///
+/// # Safety
+/// `ctx` must be null or a live context.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zair_context_last_error(ctx: *const ZairContext) -> *const c_char {
+    // SAFETY: guaranteed by the caller.
+    unsafe { ctx.as_ref() }.map_or(std::ptr::null(), ZairContext::last_error)
+}
+
+/// Register a progress callback, or clear it by passing null. `user_data` is passed back
+/// unchanged.
+///
+/// # Safety
+/// `ctx` must be null or a live context, and `callback` must be safe to call with `user_data`
+/// for as long as it is registered.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zair_context_set_progress_callback(
+    ctx: *mut ZairContext,
+    callback: ZairProgressCallback,
+    user_data: *mut c_void,
+) -> ZairStatus {
+    // SAFETY: guaranteed by the caller.
+    let Some(ctx) = (unsafe { ctx.as_ref() }) else {
+        return ZairStatus::NullArgument;
+    };
+    ctx.set_progress(callback, user_data);
+    ZairStatus::Ok
+}
+
+/// Cancel the call running on `ctx`, which then returns `ZAIR_STATUS_CANCELLED`.
+///
+/// Unlike every other function, this one may be called from another thread while a call runs
+/// on the context. With no call running it does nothing; the next call starts uncancelled.
+///
+/// # Safety
+/// `ctx` must be null or a live context.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zair_context_cancel(ctx: *const ZairContext) -> ZairStatus {
+    // SAFETY: guaranteed by the caller.
+    let Some(ctx) = (unsafe { ctx.as_ref() }) else {
+        return ZairStatus::NullArgument;
+    };
+    ctx.cancel();
+    ZairStatus::Ok
+}
+
+/// Scan for the UFVK's eligible notes and write the prepared claims.
+///
+/// # Safety
+/// `ctx` must be a live context and `args` must point to a [`ZairPrepareArgs`] of `struct_size`
+/// bytes whose string fields are null or valid NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zair_claim_prepare(
+    ctx: *mut ZairContext,
+    args: *const ZairPrepareArgs,
+) -> ZairStatus {
+    // SAFETY: guaranteed by the caller.
+    unsafe {
+        run_stage(ctx, ZairStage::Prepare, |ctx, progress| {
+            let args = read_args(args, "args")?;
+            ctx.block_on(
+                progress,
+                zair_sdk::commands::airdrop_claim(
+                    optional_str(args.lightwalletd_url, "lightwalletd_url")?.map(str::to_owned),
+                    optional_path(args.snapshot_sapling, "snapshot_sapling")?,
+                    optional_path(args.snapshot_orchard, "snapshot_orchard")?,
+                    optional_path(args.gap_tree_sapling, "gap_tree_sapling")?,
+                    optional_path(args.gap_tree_orchard, "gap_tree_orchard")?,
+                    gap_tree_mode(args.gap_tree_mode)?,
+                    optional_str(args.witness_server_url, "witness_server_url")?.map(str::to_owned),
+                    optional_str(args.gap_tree_shards, "gap_tree_shards")?.map(str::to_owned),
+                    required_str(args.ufvk, "ufvk")?.to_owned(),
+                    Some(args.birthday),
+                    optional_path(args.scan_cache, "scan_cache")?,
+                    optional_path(args.wallet_db, "wallet_db")?,
+                    required_path(args.claims_out, "claims_out")?,
+                    required_path(args.config, "config")?,
+                    trusted_organizer(args.trusted_organizer_key, args.config_signature)?,
+                    progress,
+                ),
+            )
+        })
+    }
+}
+
+/// Generate claim proofs for prepared claims.
+///
+/// Returns `ZAIR_STATUS_UNSUPPORTED` if the library was built without the `prove` feature.
+///
+/// # Safety
+/// `ctx` must be a live context and `args` must point to a [`ZairProveArgs`] of `struct_size` bytes
+/// whose string fields are null or valid NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zair_claim_prove(
+    ctx: *mut ZairContext,
+    args: *const ZairProveArgs,
+) -> ZairStatus {
+    // SAFETY: guaranteed by the caller.
+    unsafe {
+        run_stage(ctx, ZairStage::Prove, |ctx, progress| {
+            let args = read_args(args, "args")?;
+            prove(ctx, progress, &args)
+        })
+    }
+}
+
+#[cfg(feature = "prove")]
+unsafe fn prove(
+    ctx: &ZairContext,
+    progress: &Progress,
+    args: &ZairProveArgs,
+) -> Result<(), FfiError> {
+    // SAFETY: guaranteed by the caller of `zair_claim_prove`.
+    unsafe {
+        ctx.block_on(
+            progress,
+            zair_sdk::commands::generate_claim_proofs(
+                required_path(args.claims_in, "claims_in")?,
+                required_path(args.proofs_out, "proofs_out")?,
+                required_path(args.seed, "seed")?,
+                args.account,
+                required_path(args.sapling_pk, "sapling_pk")?,
+                required_path(args.orchard_params, "orchard_params")?,
+                orchard_params_mode(args.orchard_params_mode)?,
+                required_path(args.secrets_out, "secrets_out")?,
+                required_path(args.config, "config")?,
+                optional_str(args.target, "target")?.map(str::to_owned),
+                trusted_organizer(args.trusted_organizer_key, args.config_signature)?,
+                progress,
+            ),
+        )
+    }
+}
+
+#[cfg(not(feature = "prove"))]
+#[allow(clippy::unnecessary_wraps, reason = "matches the `prove` build")]
+unsafe fn prove(
+    _ctx: &ZairContext,
+    _progress: &Progress,
+    _args: &ZairProveArgs,
+) -> Result<(), FfiError> {
+    Err(FfiError::new(
+        ZairStatus::Unsupported,
+        "zair-ffi was built without the `prove` feature",
+    ))
+}
+
+/// Sign claim proofs into a submission.
+///
+/// At least one of `message` and `messages` must be set.
+///
+/// # Safety
+/// `ctx` must be a live context and `args` must point to a [`ZairSignArgs`] of `struct_size` bytes
+/// whose string fields are null or valid NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zair_claim_sign(
+    ctx: *mut ZairContext,
+    args: *const ZairSignArgs,
+) -> ZairStatus {
+    // SAFETY: guaranteed by the caller.
+    unsafe {
+        run_stage(ctx, ZairStage::Sign, |ctx, progress| {
+            let args = read_args(args, "args")?;
+            ctx.block_on(
+                progress,
+                zair_sdk::commands::sign_claim_submission(
+                    required_path(args.proofs_in, "proofs_in")?,
+                    required_path(args.secrets_in, "secrets_in")?,
+                    required_path(args.seed, "seed")?,
+                    args.account,
+                    required_path(args.config, "config")?,
+                    optional_str(args.target, "target")?.map(str::to_owned),
+                    trusted_organizer(args.trusted_organizer_key, args.config_signature)?,
+                    optional_path(args.message, "message")?,
+                    optional_path(args.messages, "messages")?,
+                    required_path(args.submission_out, "submission_out")?,
+                ),
+            )
+        })
+    }
+}
+
+/// Verify a signed submission's signatures and proofs.
+///
+/// Returns `ZAIR_STATUS_OK` only if every claim is valid.
+///
+/// # Safety
+/// `ctx` must be a live context and `args` must point to a [`ZairVerifyArgs`] of `struct_size`
+/// bytes whose string fields are null or valid NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn zair_verify(
+    ctx: *mut ZairContext,
+    args: *const ZairVerifyArgs,
+) -> ZairStatus {
+    // SAFETY: guaranteed by the caller.
+    unsafe {
+        run_stage(ctx, ZairStage::Verify, |ctx, progress| {
+            let args = read_args(args, "args")?;
+            ctx.block_on(
+                progress,
+                zair_sdk::commands::verify_run(
+                    required_path(args.sapling_vk, "sapling_vk")?,
+                    required_path(args.orchard_params, "orchard_params")?,
+                    orchard_params_mode(args.orchard_params_mode)?,
+                    required_path(args.submission_in, "submission_in")?,
+                    optional_path(args.message, "message")?,
+                    optional_path(args.messages, "messages")?,
+                    required_path(args.config, "config")?,
+                    optional_str(args.target, "target")?.map(str::to_owned),
+                    trusted_organizer(args.trusted_organizer_key, args.config_signature)?,
+                    (args.current_height != 0).then_some(args.current_height),
+                    (args.current_time != 0).then_some(args.current_time),
+                    optional_path(args.registry, "registry")?,
+                    optional_path(args.valid_submission_out, "valid_submission_out")?,
+                    progress,
+                ),
+            )
+        })
+    }
+}
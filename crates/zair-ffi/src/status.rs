@@ -0,0 +1,94 @@
+//! Status codes and error reporting across the C boundary.
+
+use std::io;
+
+use zair_sdk::progress::Cancelled;
+
+/// Result code returned by every fallible `zair_*` function.
+///
+/// On any code other than `ZAIR_STATUS_OK`, `zair_context_last_error` returns a description.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZairStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullArgument = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// An argument had an invalid value.
+    InvalidArgument = 3,
+    /// A file could not be read or written.
+    Io = 4,
+    /// The operation failed, for example on an invalid proof or signature.
+    Failed = 5,
+    /// The operation is not available in this build.
+    Unsupported = 6,
+    /// The library panicked; the context remains usable.
+    Panic = 7,
+    /// The call was stopped by `zair_context_cancel`.
+    Cancelled = 8,
+}
+
+/// An error on its way to the C caller.
+#[derive(Debug)]
+pub struct FfiError {
+    pub status: ZairStatus,
+    pub message: String,
+}
+
+impl FfiError {
+    pub fn new(status: ZairStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub fn null_argument(name: &str) -> Self {
+        Self::new(
+            ZairStatus::NullArgument,
+            format!("`{name}` must not be null"),
+        )
+    }
+}
+
+impl From<eyre::Report> for FfiError {
+    fn from(err: eyre::Report) -> Self {
+        let status = if err.chain().any(<dyn std::error::Error>::is::<Cancelled>) {
+            ZairStatus::Cancelled
+        } else if err
+            .chain()
+            .any(|cause| cause.downcast_ref::<io::Error>().is_some())
+        {
+            ZairStatus::Io
+        } else {
+            ZairStatus::Failed
+        };
+        Self::new(status, format!("{err:#}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eyre::WrapErr as _;
+
+    use super::*;
+
+    #[test]
+    fn io_errors_are_classified_through_context() {
+        let err: eyre::Result<()> = Err(io::Error::from(io::ErrorKind::NotFound).into());
+        let err = FfiError::from(
+            err.context("Failed to read config")
+                .expect_err("is an error"),
+        );
+        assert_eq!(err.status, ZairStatus::Io);
+        assert!(err.message.starts_with("Failed to read config"), "{err:?}");
+
+        let err = FfiError::from(eyre::eyre!("Invalid proof"));
+        assert_eq!(err.status, ZairStatus::Failed);
+
+        let err = FfiError::from(eyre::Report::new(Cancelled).wrap_err("Proving stopped"));
+        assert_eq!(err.status, ZairStatus::Cancelled);
+    }
+}
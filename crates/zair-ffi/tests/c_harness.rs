@@ -0,0 +1,55 @@
+//! Compiles `tests/harness.c` against `include/zair.h` and the built shared library, then
+//! runs it.
+
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `target/<profile>/deps`, where cargo builds the `cdylib` alongside the test executable.
+fn library_dir() -> PathBuf {
+    let exe = std::env::current_exe().expect("test executable path");
+    exe.parent()
+        .expect("test executable is in target/<profile>/deps")
+        .to_path_buf()
+}
+
+#[test]
+fn c_harness_passes() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib_dir = library_dir();
+    let scratch = tempfile::tempdir().expect("tempdir");
+    let harness = scratch.path().join("harness");
+
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg(crate_dir.join("tests").join("harness.c"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lzair_ffi")
+        .arg("-o")
+        .arg(&harness)
+        .status()
+        .expect("C compiler should run");
+    assert!(status.success(), "harness should compile");
+
+    // cargo's `LD_LIBRARY_PATH` also lists `target/<profile>`, which can hold a library from an
+    // earlier `cargo build`; load the one next to the test through the rpath instead.
+    let output = Command::new(&harness)
+        .env_remove("LD_LIBRARY_PATH")
+        .arg(scratch.path())
+        .output()
+        .expect("harness should run");
+    assert!(
+        output.status.success(),
+        "harness failed:\n{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,228 @@
+/* Drives the zair-ffi C ABI the way a wallet would. Usage: harness <scratch-dir> */
+
+#include <stddef.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "zair.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                   \
+  do {                                                                \
+    if (!(cond)) {                                                    \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+              #cond);                                                 \
+      failures++;                                                     \
+    }                                                                 \
+  } while (0)
+
+struct progress_log {
+  int calls;
+  ZairStage last_stage;
+  uint32_t last_completed;
+  uint32_t last_total;
+};
+
+static void on_progress(void *user_data, ZairStage stage, uint32_t completed,
+                        uint32_t total) {
+  struct progress_log *log = user_data;
+  log->calls++;
+  log->last_stage = stage;
+  log->last_completed = completed;
+  log->last_total = total;
+}
+
+static int contains(const char *haystack, const char *needle) {
+  return haystack != NULL && strstr(haystack, needle) != NULL;
+}
+
+static int write_file(const char *path, const char *contents) {
+  FILE *file = fopen(path, "w");
+  if (file == NULL) {
+    return 0;
+  }
+  int written = fputs(contents, file) >= 0;
+  return fclose(file) == 0 && written;
+}
+
+int main(int argc, char **argv) {
+  if (argc != 2) {
+    fprintf(stderr, "usage: %s <scratch-dir>\n", argv[0]);
+    return 2;
+  }
+  char missing[4096];
+  snprintf(missing, sizeof missing, "%s/missing.json", argv[1]);
+
+  const char *version = zair_version();
+  CHECK(version != NULL && version[0] != '\0');
+
+  ZairVerifyArgs verify_args = {
+      .struct_size = sizeof(ZairVerifyArgs),
+      .config = missing,
+      .submission_in = missing,
+      .sapling_vk = missing,
+      .orchard_params = missing,
+      .orchard_params_mode = ZAIR_ORCHARD_PARAMS_REQUIRE,
+  };
+  CHECK(zair_verify(NULL, &verify_args) == ZAIR_STATUS_NULL_ARGUMENT);
+  CHECK(zair_context_last_error(NULL) == NULL);
+
+  ZairContext *ctx = zair_context_new();
+  CHECK(ctx != NULL);
+  if (ctx == NULL) {
+    return 1;
+  }
+  CHECK(zair_context_last_error(ctx) == NULL);
+
+  struct progress_log log = {0};
+  CHECK(zair_context_set_progress_callback(ctx, on_progress, &log) ==
+        ZAIR_STATUS_OK);
+
+  /* Null argument struct. */
+  CHECK(zair_claim_sign(ctx, NULL) == ZAIR_STATUS_NULL_ARGUMENT);
+  CHECK(contains(zair_context_last_error(ctx), "args"));
+  CHECK(log.calls == 1 && log.last_stage == ZAIR_STAGE_SIGN &&
+        log.last_completed == 0 && log.last_total == 1);
+
+  /* Missing required field. */
+  ZairSignArgs sign_args = {
+      .struct_size = sizeof(ZairSignArgs),
+      .config = missing,
+      .proofs_in = missing,
+      .secrets_in = missing,
+      .seed = NULL,
+      .message = missing,
+      .submission_out = missing,
+  };
+  CHECK(zair_claim_sign(ctx, &sign_args) == ZAIR_STATUS_NULL_ARGUMENT);
+  CHECK(contains(zair_context_last_error(ctx), "seed"));
+
+  /* Missing input files surface as I/O errors. */
+  sign_args.seed = missing;
+  CHECK(zair_claim_sign(ctx, &sign_args) == ZAIR_STATUS_IO);
+  CHECK(zair_context_last_error(ctx) != NULL);
+  CHECK(log.calls == 3);
+
+  /* Out-of-range enum values are rejected before any work. */
+  verify_args.orchard_params_mode = 42;
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_INVALID_ARGUMENT);
+  CHECK(contains(zair_context_last_error(ctx), "orchard params mode"));
+  CHECK(log.last_stage == ZAIR_STAGE_VERIFY);
+
+  ZairPrepareArgs prepare_args = {
+      .struct_size = sizeof(ZairPrepareArgs),
+      .config = missing,
+      .ufvk = "uview1",
+      .gap_tree_mode = 99,
+      .claims_out = missing,
+  };
+  CHECK(zair_claim_prepare(ctx, &prepare_args) ==
+        ZAIR_STATUS_INVALID_ARGUMENT);
+
+  /* Strings must be UTF-8. */
+  const char bad_utf8[] = {(char)0xff, (char)0xfe, '\0'};
+  prepare_args.gap_tree_mode = ZAIR_GAP_TREE_MODE_SPARSE;
+  prepare_args.ufvk = bad_utf8;
+  CHECK(zair_claim_prepare(ctx, &prepare_args) == ZAIR_STATUS_INVALID_UTF8);
+  CHECK(contains(zair_context_last_error(ctx), "ufvk"));
+
+  ZairProveArgs prove_args = {
+      .struct_size = sizeof(ZairProveArgs),
+      .config = missing,
+      .claims_in = missing,
+      .seed = missing,
+      .sapling_pk = missing,
+      .orchard_params = missing,
+      .orchard_params_mode = ZAIR_ORCHARD_PARAMS_REQUIRE,
+      .proofs_out = missing,
+      .secrets_out = missing,
+  };
+  ZairStatus prove_status = zair_claim_prove(ctx, &prove_args);
+  CHECK(prove_status == ZAIR_STATUS_IO ||
+        prove_status == ZAIR_STATUS_UNSUPPORTED);
+
+  /* The struct size must be set, and no larger than the library's struct. */
+  verify_args.orchard_params_mode = ZAIR_ORCHARD_PARAMS_REQUIRE;
+  verify_args.struct_size = 0;
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_INVALID_ARGUMENT);
+  CHECK(contains(zair_context_last_error(ctx), "struct_size"));
+  verify_args.struct_size = sizeof(ZairVerifyArgs) + 8;
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_INVALID_ARGUMENT);
+  CHECK(contains(zair_context_last_error(ctx), "newer zair.h"));
+
+  /* Fields past a caller's struct_size are not read. */
+  verify_args.struct_size = sizeof(ZairVerifyArgs);
+  verify_args.target = bad_utf8;
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_INVALID_UTF8);
+  CHECK(contains(zair_context_last_error(ctx), "target"));
+  verify_args.struct_size = offsetof(ZairVerifyArgs, target);
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_IO);
+  verify_args.struct_size = sizeof(ZairVerifyArgs);
+  verify_args.target = NULL;
+
+  /* A trusted organizer key must be hex and comes with a signature file. */
+  verify_args.trusted_organizer_key = "not-a-key";
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_INVALID_ARGUMENT);
+  CHECK(contains(zair_context_last_error(ctx), "organizer key"));
+  verify_args.trusted_organizer_key =
+      "0000000000000000000000000000000000000000000000000000000000000000";
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_NULL_ARGUMENT);
+  CHECK(contains(zair_context_last_error(ctx), "config_signature"));
+  verify_args.trusted_organizer_key = NULL;
+
+  /* A claim window is checked against the caller's chain clock; 0 means absent. */
+  char windowed_config[4096];
+  char submission[4096];
+  snprintf(windowed_config, sizeof windowed_config, "%s/windowed-config.json",
+           argv[1]);
+  snprintf(submission, sizeof submission, "%s/submission.json", argv[1]);
+  CHECK(write_file(windowed_config,
+                   "{\"network\":\"testnet\",\"snapshot_height\":1,"
+                   "\"claim_window\":{\"unit\":\"height\",\"start\":100}}"));
+  char zero[65];
+  memset(zero, '0', 64);
+  zero[64] = '\0';
+  char claims[1024];
+  snprintf(claims, sizeof claims,
+           "{\"orchard\":[{\"zkproof\":\"00\",\"rk\":\"%s\","
+           "\"airdrop_nullifier\":\"%s\",\"proof_hash\":\"%s\","
+           "\"message_hash\":\"%s\",\"spend_auth_sig\":\"%s%s\"}]}",
+           zero, zero, zero, zero, zero, zero);
+  CHECK(write_file(submission, claims));
+  verify_args.config = windowed_config;
+  verify_args.submission_in = submission;
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_FAILED);
+  CHECK(contains(zair_context_last_error(ctx), "no current height"));
+  verify_args.current_height = 50;
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_FAILED);
+  CHECK(contains(zair_context_last_error(ctx), "opens at height 100"));
+  verify_args.current_height = 150;
+  CHECK(zair_verify(ctx, &verify_args) != ZAIR_STATUS_OK);
+  CHECK(!contains(zair_context_last_error(ctx), "claim window"));
+  verify_args.current_height = 0;
+  verify_args.config = missing;
+  verify_args.submission_in = missing;
+
+  verify_args.orchard_params_mode = 42;
+
+  /* Clearing the callback stops progress reports. */
+  int calls = log.calls;
+  CHECK(zair_context_set_progress_callback(ctx, NULL, NULL) == ZAIR_STATUS_OK);
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_INVALID_ARGUMENT);
+  CHECK(log.calls == calls);
+
+  /* Cancelling with no call running does not cancel the next call. */
+  CHECK(zair_context_cancel(NULL) == ZAIR_STATUS_NULL_ARGUMENT);
+  CHECK(zair_context_cancel(ctx) == ZAIR_STATUS_OK);
+  CHECK(zair_verify(ctx, &verify_args) == ZAIR_STATUS_INVALID_ARGUMENT);
+  CHECK(!contains(zair_context_last_error(ctx), "cancelled"));
+
+  zair_context_free(ctx);
+  zair_context_free(NULL);
+
+  if (failures == 0) {
+    printf("zair-ffi harness passed (version %s)\n", version);
+  }
+  return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,28 @@
+//! Checks that the checked-in C header matches the library.
+//!
+//! Regenerate it with `ZAIR_FFI_UPDATE_HEADER=1 cargo test -p zair-ffi --test header`.
+
+use std::path::Path;
+
+#[test]
+fn header_is_up_to_date() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_root_or_default(crate_dir);
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("header should generate")
+        .write(&mut generated);
+
+    let header_path = crate_dir.join("include").join("zair.h");
+    if std::env::var_os("ZAIR_FFI_UPDATE_HEADER").is_some() {
+        std::fs::write(&header_path, &generated).expect("header should be written");
+    }
+    let checked_in = std::fs::read(&header_path).expect("include/zair.h should exist");
+    assert!(
+        checked_in == generated,
+        "include/zair.h is stale; regenerate it with ZAIR_FFI_UPDATE_HEADER=1"
+    );
+}
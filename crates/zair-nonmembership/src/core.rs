@@ -2,10 +2,11 @@
 
 use incrementalmerkletree::Position;
 use thiserror::Error;
-use zair_core::base::Nullifier;
+use zair_core::base::{Nullifier, Pool};
+use zair_core::container::ContainerError;
 
 /// Mapping a nullifier to its gap index (leaf position).
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TreePosition {
     /// The nullifier.
     pub nullifier: Nullifier,
@@ -65,6 +66,26 @@ pub enum MerklePathError {
         index: usize,
     },
 
+    /// A persisted tree container is malformed or belongs to another pool.
+    #[error("Gap-tree container error: {0}")]
+    Container(#[from] ContainerError),
+
+    /// Reading a persisted tree failed.
+    #[error("I/O error: {0}")]
+    Io(String),
+
+    /// The nullifier is already stored in the indexed tree.
+    #[error("Nullifier {0} is already present in the tree")]
+    NullifierPresent(Nullifier),
+
+    /// The nullifier is not below the pool maximum and cannot be bracketed by a low leaf.
+    #[error("Nullifier {0} is outside the indexed tree range")]
+    NullifierOutOfRange(Nullifier),
+
+    /// The pool has no nullifiers to build a gap tree over.
+    #[error("{0} pool has no nullifier gap tree")]
+    NoGapTree(Pool),
+
     /// Unexpected error.
     #[error("Unexpected error: {0}")]
     Unexpected(&'static str),
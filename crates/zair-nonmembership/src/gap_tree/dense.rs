@@ -1,4 +1,15 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
 use incrementalmerkletree::Level;
+use memmap2::Mmap;
+use zair_core::base::Pool;
+use zair_core::container::{
+    self, CONTAINER_HEADER_LEN, Compression, Container, ContainerHeader, ContainerKind,
+    ContainerOptions,
+};
 
 use crate::core::{MerklePathError, validate_leaf_count};
 use crate::node::NON_MEMBERSHIP_TREE_DEPTH;
@@ -26,14 +37,113 @@ fn level_layout(
     (widths, offsets, offset)
 }
 
+/// Validated layout of a serialized gap tree.
+struct ParsedLayout<'a> {
+    header: Option<ContainerHeader>,
+    leaf_count: usize,
+    leaf_count_u64: u64,
+    level_widths: [usize; TREE_LEVEL_COUNT],
+    level_offsets: [usize; TREE_LEVEL_COUNT],
+    /// Byte offset of the first node within the file, when nodes are stored uncompressed.
+    payload_offset: Option<usize>,
+    payload: Cow<'a, [u8]>,
+}
+
+/// Validate a serialized gap tree (versioned container or legacy) without copying nodes.
+///
+/// Compressed containers are decompressed into memory.
+fn parse_layout(bytes: &[u8], pool: Pool) -> Result<ParsedLayout<'_>, MerklePathError> {
+    let (header, leaf_count_u64, payload_offset, payload) = if container::is_container(bytes) {
+        let (header, stored) = container::decode_header(bytes)?;
+        header.ensure_content(ContainerKind::GapTree, pool)?;
+        let (payload_offset, payload) = match header.compression {
+            Compression::None => (Some(CONTAINER_HEADER_LEN), Cow::Borrowed(stored)),
+            Compression::Zstd => match container::decode(bytes)? {
+                Container::Versioned { payload, .. } => (None, payload),
+                Container::Legacy(_) => {
+                    return Err(MerklePathError::Unexpected(
+                        "gap-tree container header vanished",
+                    ));
+                }
+            },
+        };
+        (Some(header), header.item_count, payload_offset, payload)
+    } else {
+        let (&leaf_count, payload) = bytes
+            .split_first_chunk::<SERIALIZED_LEAF_COUNT_BYTES>()
+            .ok_or(MerklePathError::Unexpected("gap-tree file is too short"))?;
+        (
+            None,
+            u64::from_le_bytes(leaf_count),
+            Some(SERIALIZED_LEAF_COUNT_BYTES),
+            Cow::Borrowed(payload),
+        )
+    };
+
+    let leaf_count = usize::try_from(leaf_count_u64)
+        .map_err(|_| MerklePathError::Unexpected("leaf count does not fit into usize"))?;
+
+    validate_leaf_count(leaf_count)?;
+    let (level_widths, level_offsets, total_nodes) = level_layout(leaf_count);
+
+    if payload.len() != total_nodes * SERIALIZED_NODE_BYTES {
+        return Err(MerklePathError::Unexpected("gap-tree file length mismatch"));
+    }
+    if let Some(header) = &header {
+        let root =
+            payload
+                .last_chunk::<SERIALIZED_NODE_BYTES>()
+                .ok_or(MerklePathError::Unexpected(
+                    "gap-tree must contain at least one node",
+                ))?;
+        if *root != header.root {
+            return Err(MerklePathError::Unexpected(
+                "gap-tree root does not match the container header",
+            ));
+        }
+    }
+
+    Ok(ParsedLayout {
+        header,
+        leaf_count,
+        leaf_count_u64,
+        level_widths,
+        level_offsets,
+        payload_offset,
+        payload,
+    })
+}
+
+/// Backing storage for dense gap-tree nodes.
+#[derive(Debug, Clone)]
+enum NodeStore {
+    /// Nodes held in memory.
+    Owned(Vec<[u8; 32]>),
+    /// A memory-mapped serialized gap-tree file with nodes starting at `offset`.
+    Mapped { mmap: Arc<Mmap>, offset: usize },
+}
+
+impl NodeStore {
+    fn nodes(&self) -> &[[u8; 32]] {
+        match self {
+            Self::Owned(nodes) => nodes,
+            Self::Mapped { mmap, offset } => {
+                let (_, payload) = mmap.split_at(*offset);
+                payload.as_chunks::<SERIALIZED_NODE_BYTES>().0
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(super) struct DenseGapTree {
     leaf_count: usize,
     leaf_count_u64: u64,
     level_widths: [usize; TREE_LEVEL_COUNT],
     level_offsets: [usize; TREE_LEVEL_COUNT],
-    nodes: Vec<[u8; 32]>,
+    nodes: NodeStore,
     root: [u8; 32],
+    header: Option<ContainerHeader>,
 }
 
 impl DenseGapTree {
@@ -75,37 +185,60 @@ impl DenseGapTree {
         )
     }
 
-    pub(super) fn from_bytes(bytes: &[u8]) -> Result<Self, MerklePathError> {
-        let (&header, payload) = bytes
-            .split_first_chunk::<SERIALIZED_LEAF_COUNT_BYTES>()
-            .ok_or(MerklePathError::Unexpected("gap-tree file is too short"))?;
-
-        let leaf_count_u64 = u64::from_le_bytes(header);
-        let leaf_count = usize::try_from(leaf_count_u64)
-            .map_err(|_| MerklePathError::Unexpected("leaf count does not fit into usize"))?;
-
-        validate_leaf_count(leaf_count)?;
-        let (level_widths, level_offsets, total_nodes) = level_layout(leaf_count);
-
-        if payload.len() != total_nodes * SERIALIZED_NODE_BYTES {
-            return Err(MerklePathError::Unexpected("gap-tree file length mismatch"));
-        }
-
-        let mut nodes = Vec::with_capacity(total_nodes);
-        for chunk in payload.chunks_exact(SERIALIZED_NODE_BYTES) {
-            let mut node = [0_u8; SERIALIZED_NODE_BYTES];
-            node.copy_from_slice(chunk);
-            nodes.push(node);
-        }
+    pub(super) fn from_bytes(bytes: &[u8], pool: Pool) -> Result<Self, MerklePathError> {
+        Self::from_layout(&parse_layout(bytes, pool)?)
+    }
 
-        Self::from_nodes(
-            leaf_count,
-            leaf_count_u64,
-            &level_widths,
-            &level_offsets,
+    /// Copy the nodes of an already validated (and, if needed, decompressed) layout.
+    fn from_layout(layout: &ParsedLayout<'_>) -> Result<Self, MerklePathError> {
+        let nodes = layout
+            .payload
+            .as_chunks::<SERIALIZED_NODE_BYTES>()
+            .0
+            .to_vec();
+        let total_nodes = nodes.len();
+        let mut tree = Self::from_nodes(
+            layout.leaf_count,
+            layout.leaf_count_u64,
+            &layout.level_widths,
+            &layout.level_offsets,
             total_nodes,
             nodes,
-        )
+        )?;
+        tree.header = layout.header;
+        Ok(tree)
+    }
+
+    /// Memory-map a serialized gap-tree file and serve witnesses straight from the mapping.
+    ///
+    /// Only the header, checksum and file length are validated; nodes are not copied.
+    /// Compressed containers cannot be mapped and are decompressed into memory instead.
+    pub(super) fn map_file(path: &Path, pool: Pool) -> Result<Self, MerklePathError> {
+        let file = File::open(path).map_err(|e| MerklePathError::Io(e.to_string()))?;
+        // SAFETY: the mapping is read-only. Gap-tree files are written once by `rebuild` and
+        // are not expected to be modified while a claim is being prepared.
+        #[allow(unsafe_code, reason = "memory-mapping a read-only gap-tree file")]
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| MerklePathError::Io(e.to_string()))?;
+
+        let layout = parse_layout(&mmap, pool)?;
+        let Some(offset) = layout.payload_offset else {
+            return Self::from_layout(&layout);
+        };
+        let root = *layout.payload.last_chunk::<SERIALIZED_NODE_BYTES>().ok_or(
+            MerklePathError::Unexpected("gap-tree must contain at least one node"),
+        )?;
+        Ok(Self {
+            leaf_count: layout.leaf_count,
+            leaf_count_u64: layout.leaf_count_u64,
+            level_widths: layout.level_widths,
+            level_offsets: layout.level_offsets,
+            header: layout.header,
+            nodes: NodeStore::Mapped {
+                mmap: Arc::new(mmap),
+                offset,
+            },
+            root,
+        })
     }
 
     fn from_nodes(
@@ -127,8 +260,9 @@ impl DenseGapTree {
             leaf_count_u64,
             level_widths: *level_widths,
             level_offsets: *level_offsets,
-            nodes,
+            nodes: NodeStore::Owned(nodes),
             root,
+            header: None,
         })
     }
 
@@ -137,6 +271,24 @@ impl DenseGapTree {
         self.root
     }
 
+    /// Container header of the file this tree was loaded from, if it was versioned.
+    #[must_use]
+    pub(super) const fn header(&self) -> Option<&ContainerHeader> {
+        self.header.as_ref()
+    }
+
+    #[must_use]
+    pub(super) const fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Nodes at `level`, i.e. the roots of the aligned `2^level`-leaf subtrees.
+    pub(super) fn level_nodes(&self, level: u8) -> Vec<[u8; 32]> {
+        let level = usize::from(level);
+        let start = self.level_offsets[level];
+        self.nodes.nodes()[start..start + self.level_widths[level]].to_vec()
+    }
+
     pub(super) fn witness_bytes(
         &self,
         leaf_position: u64,
@@ -164,19 +316,57 @@ impl DenseGapTree {
         Ok(witness)
     }
 
-    #[must_use]
-    pub(super) fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = Vec::with_capacity(
-            SERIALIZED_LEAF_COUNT_BYTES + self.nodes.len() * SERIALIZED_NODE_BYTES,
+    pub(super) fn to_bytes(
+        &self,
+        pool: Pool,
+        options: &ContainerOptions,
+    ) -> Result<Vec<u8>, MerklePathError> {
+        let header = ContainerHeader::new(
+            ContainerKind::GapTree,
+            pool,
+            self.root,
+            self.leaf_count_u64,
+            options,
         );
-        bytes.extend_from_slice(&self.leaf_count_u64.to_le_bytes());
-        for node in &self.nodes {
-            bytes.extend_from_slice(node);
-        }
-        bytes
+        Ok(container::encode(
+            &header,
+            self.nodes.nodes().as_flattened(),
+        )?)
     }
 
     fn node_at(&self, level: usize, index: usize) -> [u8; 32] {
-        self.nodes[self.level_offsets[level] + index]
+        self.nodes.nodes()[self.level_offsets[level] + index]
+    }
+}
+
+/// Recompute a gap-tree root from a leaf, its position and a witness from `witness_bytes`.
+pub(super) fn root_from_witness<T>(
+    leaf: T,
+    leaf_position: u64,
+    witness: &[[u8; 32]],
+    from_bytes: impl Fn([u8; 32]) -> Option<T>,
+    combine: impl Fn(Level, &T, &T) -> T,
+    to_bytes: impl Fn(T) -> [u8; 32],
+) -> Result<[u8; 32], MerklePathError> {
+    if witness.len() != usize::from(NON_MEMBERSHIP_TREE_DEPTH) {
+        return Err(MerklePathError::Unexpected(
+            "gap-tree witness length mismatch",
+        ));
+    }
+    if leaf_position >> NON_MEMBERSHIP_TREE_DEPTH != 0 {
+        return Err(MerklePathError::NotMarked(leaf_position));
+    }
+
+    let mut current = leaf;
+    for (level, sibling) in (0..NON_MEMBERSHIP_TREE_DEPTH).zip(witness) {
+        let sibling = from_bytes(*sibling).ok_or(MerklePathError::Unexpected(
+            "gap-tree witness contains an invalid node encoding",
+        ))?;
+        current = if (leaf_position >> level) & 1 == 0 {
+            combine(Level::from(level), &current, &sibling)
+        } else {
+            combine(Level::from(level), &sibling, &current)
+        };
     }
+    Ok(to_bytes(current))
 }
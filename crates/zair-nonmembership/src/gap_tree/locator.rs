@@ -0,0 +1,137 @@
+use std::cmp::Ordering;
+
+use zair_core::base::{Nullifier, Pool, SanitiseNullifiers};
+
+use crate::core::{MerklePathError, TreePosition};
+use crate::pool::orchard::{
+    canonicalize_orchard_chain_nullifiers, canonicalize_orchard_user_nullifiers, orchard_cmp,
+    orchard_max_nullifier,
+};
+use crate::pool::sapling::sapling_gap_bounds;
+
+/// Snapshot nullifiers in pool order, for locating the gap of one nullifier at a time.
+///
+/// This is the per-query counterpart of `map_*_user_positions`: the chain is sorted once
+/// up front so each lookup is a binary search.
+#[derive(Debug, Clone)]
+pub struct GapLocator {
+    pool: Pool,
+    chain: Vec<Nullifier>,
+}
+
+impl GapLocator {
+    /// Sort `chain_nullifiers` in `pool` order.
+    pub fn new(pool: Pool, chain_nullifiers: &SanitiseNullifiers) -> Result<Self, MerklePathError> {
+        let chain = match pool {
+            Pool::Sapling => chain_nullifiers.to_vec(),
+            Pool::Orchard => canonicalize_orchard_chain_nullifiers("chain", chain_nullifiers)?
+                .into_iter()
+                .map(|item| item.bytes)
+                .collect(),
+            Pool::Transparent => return Err(MerklePathError::NoGapTree(pool)),
+        };
+        Ok(Self { pool, chain })
+    }
+
+    #[must_use]
+    pub const fn pool(&self) -> Pool {
+        self.pool
+    }
+
+    /// Number of snapshot nullifiers.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.chain.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
+
+    /// Find the gap containing `nullifier`, or `None` if it is part of the snapshot.
+    pub fn locate(&self, nullifier: Nullifier) -> Result<Option<TreePosition>, MerklePathError> {
+        match self.search(nullifier)? {
+            (nullifier, Err(gap_idx)) => self.position(nullifier, gap_idx).map(Some),
+            (_, Ok(_)) => Ok(None),
+        }
+    }
+
+    /// Find the gap whose right bound is `nullifier`, or `None` if it is not part of the snapshot.
+    ///
+    /// Every snapshot nullifier closes exactly one gap, so that gap's Merkle path proves the
+    /// nullifier was spent at the snapshot height.
+    pub fn locate_spent(
+        &self,
+        nullifier: Nullifier,
+    ) -> Result<Option<TreePosition>, MerklePathError> {
+        match self.search(nullifier)? {
+            (nullifier, Ok(gap_idx)) => self.position(nullifier, gap_idx).map(Some),
+            (_, Err(_)) => Ok(None),
+        }
+    }
+
+    /// Binary-search the chain for `nullifier`, canonicalized for the pool.
+    fn search(
+        &self,
+        nullifier: Nullifier,
+    ) -> Result<(Nullifier, Result<usize, usize>), MerklePathError> {
+        match self.pool {
+            Pool::Sapling => Ok((nullifier, self.chain.binary_search(&nullifier))),
+            Pool::Orchard => {
+                let canonical = canonicalize_orchard_user_nullifiers("user", &[nullifier])?;
+                let nullifier = canonical
+                    .first()
+                    .copied()
+                    .ok_or(MerklePathError::Unexpected(
+                        "canonical Orchard nullifier vanished",
+                    ))?;
+                let search = self
+                    .chain
+                    .binary_search_by(|candidate| orchard_cmp(candidate, &nullifier));
+                Ok((nullifier, search))
+            }
+            Pool::Transparent => Err(MerklePathError::NoGapTree(self.pool)),
+        }
+    }
+
+    /// Bounds of gap `gap_idx`, reported for `nullifier`.
+    fn position(
+        &self,
+        nullifier: Nullifier,
+        gap_idx: usize,
+    ) -> Result<TreePosition, MerklePathError> {
+        let (left, right) = match self.pool {
+            Pool::Sapling => sapling_gap_bounds(&self.chain, gap_idx)?,
+            Pool::Orchard => {
+                let left = gap_idx
+                    .checked_sub(1)
+                    .and_then(|idx| self.chain.get(idx).copied())
+                    .unwrap_or(Nullifier::MIN);
+                let right = self
+                    .chain
+                    .get(gap_idx)
+                    .copied()
+                    .unwrap_or_else(orchard_max_nullifier);
+                (left, right)
+            }
+            Pool::Transparent => return Err(MerklePathError::NoGapTree(self.pool)),
+        };
+        TreePosition::new(nullifier, gap_idx, left, right)
+    }
+}
+
+/// Compare nullifiers in `pool` order.
+pub(super) fn pool_cmp(pool: Pool, lhs: &Nullifier, rhs: &Nullifier) -> Ordering {
+    match pool {
+        Pool::Sapling | Pool::Transparent => lhs.cmp(rhs),
+        Pool::Orchard => orchard_cmp(lhs, rhs),
+    }
+}
+
+/// Check that `position.nullifier` lies strictly between its gap bounds in `pool` order.
+#[must_use]
+pub fn nullifier_in_gap(pool: Pool, position: &TreePosition) -> bool {
+    pool_cmp(pool, &position.left_bound, &position.nullifier).is_lt() &&
+        pool_cmp(pool, &position.nullifier, &position.right_bound).is_lt()
+}
@@ -12,11 +12,17 @@
 )]
 
 mod dense;
+mod locator;
 mod orchard;
+mod orchard_poseidon;
 mod sapling;
+mod shard;
 
+pub use locator::{GapLocator, nullifier_in_gap};
 pub use orchard::OrchardGapTree;
+pub use orchard_poseidon::OrchardPoseidonGapTree;
 pub use sapling::SaplingGapTree;
+pub use shard::{DEFAULT_GAP_SHARD_DEPTH, GapShard, GapShardIndex, GapShards};
 use zair_core::base::SanitiseNullifiers;
 
 use crate::{MerklePathError, TreePosition};
@@ -47,19 +53,33 @@ pub fn map_orchard_user_positions(
 mod tests {
     use ff::PrimeField as _;
     use pasta_curves::pallas;
-    use zair_core::base::{Nullifier, SanitiseNullifiers};
+    use zair_core::base::{Nullifier, Pool, SanitiseNullifiers};
+    use zair_core::container::{
+        CONTAINER_HEADER_LEN, Compression, ContainerError, ContainerOptions,
+    };
+    use zair_core::schema::config::AirdropNetwork;
 
     use super::*;
     use crate::MerklePathError;
 
+    const OPTIONS: ContainerOptions = ContainerOptions::new(AirdropNetwork::Testnet, 3_000_000);
+
+    /// Rewrite an uncompressed container as a legacy `leaf_count || nodes` file.
+    fn legacy_bytes(encoded: &[u8], leaf_count: u64) -> Vec<u8> {
+        let nodes = encoded
+            .get(CONTAINER_HEADER_LEN..)
+            .expect("container should have a payload");
+        [leaf_count.to_le_bytes().as_slice(), nodes].concat()
+    }
+
     fn assert_roundtrip<T>(
         tree: &T,
-        to_bytes: impl Fn(&T) -> Vec<u8>,
+        to_bytes: impl Fn(&T, &ContainerOptions) -> Result<Vec<u8>, MerklePathError>,
         from_bytes: impl Fn(&[u8]) -> Result<T, MerklePathError>,
         root_bytes: impl Fn(&T) -> [u8; 32],
         witness_bytes: impl Fn(&T, u64) -> Result<Vec<[u8; 32]>, MerklePathError>,
     ) {
-        let encoded = to_bytes(tree);
+        let encoded = to_bytes(tree, &OPTIONS).expect("tree should encode");
         let decoded = from_bytes(&encoded).expect("tree should decode");
 
         assert_eq!(root_bytes(&decoded), root_bytes(tree));
@@ -100,5 +120,361 @@ mod tests {
             OrchardGapTree::root_bytes,
             OrchardGapTree::witness_bytes,
         );
+
+        let poseidon_tree =
+            OrchardPoseidonGapTree::from_nullifiers_with_progress(&orchard_nullifiers, |_, _| {})
+                .expect("poseidon tree should build");
+        assert_ne!(poseidon_tree.root_bytes(), orchard_tree.root_bytes());
+        assert_roundtrip(
+            &poseidon_tree,
+            OrchardPoseidonGapTree::to_bytes,
+            OrchardPoseidonGapTree::from_bytes,
+            OrchardPoseidonGapTree::root_bytes,
+            OrchardPoseidonGapTree::witness_bytes,
+        );
+    }
+
+    #[test]
+    fn mapped_gap_tree_matches_in_memory_tree() {
+        let nullifiers = SanitiseNullifiers::new(vec![
+            Nullifier::from([1_u8; 32]),
+            Nullifier::from([3_u8; 32]),
+            Nullifier::from([7_u8; 32]),
+        ]);
+        let tree = SaplingGapTree::from_nullifiers(&nullifiers).expect("sapling tree should build");
+        let encoded = tree.to_bytes(&OPTIONS).expect("tree should encode");
+
+        let file = tempfile::NamedTempFile::new().expect("temp file should be created");
+        std::fs::write(file.path(), &encoded).expect("tree should be written");
+        let mapped = SaplingGapTree::map_file(file.path()).expect("tree should map");
+
+        assert_eq!(mapped.root_bytes(), tree.root_bytes());
+        for position in 0..4 {
+            assert_eq!(
+                mapped.witness_bytes(position).expect("mapped witness"),
+                tree.witness_bytes(position).expect("in-memory witness")
+            );
+        }
+        assert_eq!(mapped.header().map(|header| header.item_count), Some(4));
+        assert_eq!(mapped.to_bytes(&OPTIONS), Ok(encoded.clone()));
+
+        let truncated = encoded
+            .get(..encoded.len() - 1)
+            .expect("non-empty encoding");
+        std::fs::write(file.path(), truncated).expect("tree should be written");
+        assert!(matches!(
+            SaplingGapTree::map_file(file.path()),
+            Err(MerklePathError::Container(
+                ContainerError::LengthMismatch { .. }
+            ))
+        ));
+
+        let legacy = legacy_bytes(&encoded, 4);
+        std::fs::write(file.path(), &legacy).expect("legacy tree should be written");
+        let mapped = SaplingGapTree::map_file(file.path()).expect("legacy tree should map");
+        assert_eq!(mapped.root_bytes(), tree.root_bytes());
+        assert!(mapped.header().is_none());
+        assert_eq!(
+            SaplingGapTree::map_file(file.path())
+                .and_then(|tree| tree.witness_bytes(2))
+                .expect("legacy witness"),
+            tree.witness_bytes(2).expect("in-memory witness")
+        );
+        std::fs::write(file.path(), &legacy[..legacy.len() - 1]).expect("tree should be written");
+        assert_eq!(
+            SaplingGapTree::map_file(file.path()).map(|_| ()),
+            Err(MerklePathError::Unexpected("gap-tree file length mismatch"))
+        );
+    }
+
+    #[test]
+    fn gap_tree_container_rejects_other_pool_and_accepts_compression() {
+        let nullifiers = SanitiseNullifiers::new(vec![Nullifier::from([9_u8; 32])]);
+        let tree = SaplingGapTree::from_nullifiers(&nullifiers).expect("sapling tree should build");
+
+        let encoded = tree.to_bytes(&OPTIONS).expect("tree should encode");
+        assert!(matches!(
+            OrchardGapTree::from_bytes(&encoded),
+            Err(MerklePathError::Container(
+                ContainerError::PoolMismatch { .. }
+            ))
+        ));
+
+        let compressed = tree
+            .to_bytes(&OPTIONS.with_compression(Compression::Zstd))
+            .expect("tree should encode compressed");
+        let decoded = SaplingGapTree::from_bytes(&compressed).expect("compressed tree decodes");
+        assert_eq!(decoded.root_bytes(), tree.root_bytes());
+
+        let file = tempfile::NamedTempFile::new().expect("temp file should be created");
+        std::fs::write(file.path(), &compressed).expect("tree should be written");
+        let loaded = SaplingGapTree::map_file(file.path()).expect("compressed tree loads");
+        assert_eq!(loaded.witness_bytes(1), tree.witness_bytes(1));
+
+        let legacy = legacy_bytes(&encoded, 2);
+        let decoded = SaplingGapTree::from_bytes(&legacy).expect("legacy tree decodes");
+        assert_eq!(decoded.root_bytes(), tree.root_bytes());
+    }
+
+    #[test]
+    fn located_gap_witness_recomputes_root() {
+        let sapling_chain = SanitiseNullifiers::new(vec![
+            Nullifier::from([1_u8; 32]),
+            Nullifier::from([3_u8; 32]),
+            Nullifier::from([7_u8; 32]),
+        ]);
+        let locator = GapLocator::new(Pool::Sapling, &sapling_chain).expect("sapling locator");
+        let tree = SaplingGapTree::from_nullifiers(&sapling_chain).expect("sapling tree");
+        assert_eq!(locator.locate(Nullifier::from([3_u8; 32])), Ok(None));
+        let query = Nullifier::from([5_u8; 32]);
+        let position = locator
+            .locate(query)
+            .expect("lookup")
+            .expect("unspent nullifier has a gap");
+        assert_eq!(
+            map_sapling_user_positions(&sapling_chain, &SanitiseNullifiers::new(vec![query])),
+            Ok(vec![position])
+        );
+        assert!(nullifier_in_gap(Pool::Sapling, &position));
+        let leaf_position = u64::from(position.leaf_position);
+        let witness = tree.witness_bytes(leaf_position).expect("witness");
+        assert_eq!(
+            SaplingGapTree::root_from_witness(
+                &position.left_bound,
+                &position.right_bound,
+                leaf_position,
+                &witness
+            ),
+            Ok(tree.root_bytes())
+        );
+        assert_ne!(
+            SaplingGapTree::root_from_witness(
+                &position.left_bound,
+                &position.right_bound,
+                leaf_position ^ 1,
+                &witness
+            ),
+            Ok(tree.root_bytes())
+        );
+
+        let orchard_chain = SanitiseNullifiers::new(vec![
+            Nullifier::from(pallas::Base::from(9_u64).to_repr()),
+            Nullifier::from(pallas::Base::from(2_u64).to_repr()),
+        ]);
+        let locator = GapLocator::new(Pool::Orchard, &orchard_chain).expect("orchard locator");
+        let query = Nullifier::from(pallas::Base::from(4_u64).to_repr());
+        let position = locator
+            .locate(query)
+            .expect("lookup")
+            .expect("unspent nullifier has a gap");
+        assert_eq!(
+            map_orchard_user_positions(&orchard_chain, &SanitiseNullifiers::new(vec![query])),
+            Ok(vec![position])
+        );
+        assert!(nullifier_in_gap(Pool::Orchard, &position));
+        assert!(!nullifier_in_gap(
+            Pool::Orchard,
+            &TreePosition {
+                nullifier: position.right_bound,
+                ..position
+            }
+        ));
+        let leaf_position = u64::from(position.leaf_position);
+        assert_eq!(leaf_position, 1);
+
+        let sinsemilla =
+            OrchardGapTree::from_nullifiers_with_progress(&orchard_chain, |_, _| {}).expect("tree");
+        let witness = sinsemilla.witness_bytes(leaf_position).expect("witness");
+        assert_eq!(
+            OrchardGapTree::root_from_witness(
+                &position.left_bound,
+                &position.right_bound,
+                leaf_position,
+                &witness
+            ),
+            Ok(sinsemilla.root_bytes())
+        );
+
+        let poseidon =
+            OrchardPoseidonGapTree::from_nullifiers_with_progress(&orchard_chain, |_, _| {})
+                .expect("tree");
+        let witness = poseidon.witness_bytes(leaf_position).expect("witness");
+        assert_eq!(
+            OrchardPoseidonGapTree::root_from_witness(
+                &position.left_bound,
+                &position.right_bound,
+                leaf_position,
+                &witness
+            ),
+            Ok(poseidon.root_bytes())
+        );
+    }
+
+    #[test]
+    fn spent_nullifier_opens_the_gap_it_closes() {
+        let sapling_chain = SanitiseNullifiers::new(vec![
+            Nullifier::from([1_u8; 32]),
+            Nullifier::from([3_u8; 32]),
+        ]);
+        let locator = GapLocator::new(Pool::Sapling, &sapling_chain).expect("sapling locator");
+        let tree = SaplingGapTree::from_nullifiers(&sapling_chain).expect("sapling tree");
+        assert_eq!(locator.locate_spent(Nullifier::from([2_u8; 32])), Ok(None));
+
+        for (spent, leaf_position, left_bound) in [
+            ([1_u8; 32], 0, Nullifier::MIN),
+            ([3_u8; 32], 1, Nullifier::from([1_u8; 32])),
+        ] {
+            let position = locator
+                .locate_spent(Nullifier::from(spent))
+                .expect("lookup")
+                .expect("spent nullifier closes a gap");
+            assert_eq!(position.left_bound, left_bound);
+            assert_eq!(position.right_bound, Nullifier::from(spent));
+            assert_eq!(u64::from(position.leaf_position), leaf_position);
+            let witness = tree.witness_bytes(leaf_position).expect("witness");
+            assert_eq!(
+                SaplingGapTree::root_from_witness(
+                    &position.left_bound,
+                    &position.right_bound,
+                    leaf_position,
+                    &witness
+                ),
+                Ok(tree.root_bytes())
+            );
+        }
+
+        let orchard_chain =
+            SanitiseNullifiers::new(vec![Nullifier::from(pallas::Base::from(2_u64).to_repr())]);
+        let locator = GapLocator::new(Pool::Orchard, &orchard_chain).expect("orchard locator");
+        let spent = Nullifier::from(pallas::Base::from(2_u64).to_repr());
+        let position = locator
+            .locate_spent(spent)
+            .expect("lookup")
+            .expect("spent nullifier closes a gap");
+        assert_eq!(position.right_bound, spent);
+        assert_eq!(u64::from(position.leaf_position), 0);
+    }
+
+    fn shard_covering<'a>(shards: &'a GapShards, nullifier: &Nullifier) -> &'a GapShard {
+        let index = shards.index.shard_for(nullifier).expect("shard exists");
+        shards
+            .shards
+            .get(usize::try_from(index).expect("shard index fits"))
+            .expect("shard exists")
+    }
+
+    #[test]
+    fn sapling_shard_witnesses_match_full_tree() {
+        let sapling_chain = SanitiseNullifiers::new(
+            (1_u8..=9)
+                .map(|byte| Nullifier::from([byte * 2; 32]))
+                .collect(),
+        );
+        let tree = SaplingGapTree::from_nullifiers(&sapling_chain).expect("sapling tree");
+        let shards = tree.shards(&sapling_chain, 2).expect("sapling shards");
+        assert_eq!(shards.index.shard_count(), 3);
+
+        let index = GapShardIndex::from_bytes(
+            &shards.index.to_bytes(&OPTIONS).expect("index encodes"),
+            Pool::Sapling,
+        )
+        .expect("index decodes");
+        assert_eq!(index.root_bytes(), tree.root_bytes());
+        for query in [[1_u8; 32], [7_u8; 32], [19_u8; 32]] {
+            let query = Nullifier::from(query);
+            let shard = GapShard::from_bytes(
+                &shard_covering(&shards, &query)
+                    .to_bytes(&OPTIONS)
+                    .expect("shard encodes"),
+                Pool::Sapling,
+            )
+            .expect("shard decodes");
+            let position = shard.locate(query).expect("lookup").expect("unspent");
+            let leaf_position = u64::from(position.leaf_position);
+            assert_eq!(
+                SaplingGapTree::shard_witnesses(&index, &shard, &[leaf_position]),
+                Ok(vec![tree.witness_bytes(leaf_position).expect("witness")])
+            );
+        }
+        let spent = Nullifier::from([4_u8; 32]);
+        assert_eq!(shard_covering(&shards, &spent).locate(spent), Ok(None));
+        let locator = GapLocator::new(Pool::Sapling, &sapling_chain).expect("sapling locator");
+        for spent in [[4_u8; 32], [8_u8; 32], [10_u8; 32]] {
+            let spent = Nullifier::from(spent);
+            let shard = shard_covering(&shards, &spent);
+            let position = shard.locate_spent(spent).expect("lookup");
+            assert_eq!(position, locator.locate_spent(spent).expect("lookup"));
+            let leaf_position = u64::from(position.expect("spent").leaf_position);
+            assert_eq!(
+                SaplingGapTree::shard_witnesses(&index, shard, &[leaf_position]),
+                Ok(vec![tree.witness_bytes(leaf_position).expect("witness")])
+            );
+        }
+        assert!(matches!(
+            GapShard::from_bytes(
+                &shards.shards[0].to_bytes(&OPTIONS).expect("shard encodes"),
+                Pool::Orchard
+            ),
+            Err(MerklePathError::Container(
+                ContainerError::PoolMismatch { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn orchard_shard_witnesses_match_full_tree() {
+        let orchard_chain = SanitiseNullifiers::new(
+            (1_u64..=5)
+                .map(|value| Nullifier::from(pallas::Base::from(value * 3).to_repr()))
+                .collect(),
+        );
+        let query = Nullifier::from(pallas::Base::from(8_u64).to_repr());
+        let sinsemilla =
+            OrchardGapTree::from_nullifiers_with_progress(&orchard_chain, |_, _| {}).expect("tree");
+        let shards = sinsemilla
+            .shards(&orchard_chain, 1)
+            .expect("orchard shards");
+        let shard = shard_covering(&shards, &query);
+        let leaf_position = u64::from(
+            shard
+                .locate(query)
+                .expect("lookup")
+                .expect("gap")
+                .leaf_position,
+        );
+        assert_eq!(
+            OrchardGapTree::shard_witnesses(&shards.index, shard, &[leaf_position]),
+            Ok(vec![
+                sinsemilla.witness_bytes(leaf_position).expect("witness")
+            ])
+        );
+        assert!(
+            OrchardGapTree::shard_witnesses(&shards.index, &shards.shards[0], &[leaf_position])
+                .is_err()
+        );
+
+        let poseidon =
+            OrchardPoseidonGapTree::from_nullifiers_with_progress(&orchard_chain, |_, _| {})
+                .expect("tree");
+        let poseidon_shards = poseidon.shards(&orchard_chain, 1).expect("poseidon shards");
+        let poseidon_shard = shard_covering(&poseidon_shards, &query);
+        assert_eq!(
+            OrchardPoseidonGapTree::shard_witnesses(
+                &poseidon_shards.index,
+                poseidon_shard,
+                &[leaf_position]
+            ),
+            Ok(vec![
+                poseidon.witness_bytes(leaf_position).expect("witness")
+            ])
+        );
+        assert!(
+            OrchardGapTree::shard_witnesses(
+                &poseidon_shards.index,
+                poseidon_shard,
+                &[leaf_position]
+            )
+            .is_err()
+        );
     }
 }
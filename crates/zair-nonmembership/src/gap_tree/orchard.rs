@@ -1,8 +1,12 @@
+use std::path::Path;
+
 use incrementalmerkletree::Hashable as _;
 use orchard::tree::MerkleHashOrchard;
-use zair_core::base::SanitiseNullifiers;
+use zair_core::base::{Nullifier, Pool, SanitiseNullifiers};
+use zair_core::container::{ContainerHeader, ContainerOptions};
 
-use super::dense::DenseGapTree;
+use super::dense::{self, DenseGapTree};
+use super::shard::{self, GapShard, GapShardIndex, GapShards};
 use crate::core::{MerklePathError, should_report_progress};
 use crate::pool::orchard::{
     ORCHARD_LEAF_HASH_LEVEL, canonicalize_orchard_chain_nullifiers, orchard_gap_bounds,
@@ -18,7 +22,7 @@ impl OrchardGapTree {
         mut on_progress: impl FnMut(usize, usize),
     ) -> Result<Self, MerklePathError> {
         let chain = canonicalize_orchard_chain_nullifiers("chain", nullifiers)?;
-        let min_node = orchard_node_from_bytes(*zair_core::base::Nullifier::MIN.as_ref()).ok_or(
+        let min_node = orchard_node_from_bytes(*Nullifier::MIN.as_ref()).ok_or(
             MerklePathError::Unexpected("invalid Orchard min nullifier encoding"),
         )?;
         let max_node = orchard_node_from_bytes(*orchard_max_nullifier().as_ref()).ok_or(
@@ -60,12 +64,86 @@ impl OrchardGapTree {
         })
     }
 
-    #[must_use]
-    pub fn to_bytes(&self) -> Vec<u8> {
-        self.0.to_bytes()
+    /// Recompute the root committed to by a `(left_bound, right_bound)` gap witness.
+    pub fn root_from_witness(
+        left_bound: &Nullifier,
+        right_bound: &Nullifier,
+        leaf_position: u64,
+        witness: &[[u8; 32]],
+    ) -> Result<[u8; 32], MerklePathError> {
+        dense::root_from_witness(
+            gap_leaf(left_bound, right_bound)?,
+            leaf_position,
+            witness,
+            orchard_node_from_bytes,
+            MerkleHashOrchard::combine,
+            |node| node.to_bytes(),
+        )
+    }
+
+    /// Split the tree, built from `nullifiers`, into shards of `2^shard_depth` gaps.
+    pub fn shards(
+        &self,
+        nullifiers: &SanitiseNullifiers,
+        shard_depth: u8,
+    ) -> Result<GapShards, MerklePathError> {
+        shard::split(Pool::Orchard, &self.0, nullifiers, shard_depth)
     }
 
+    /// Rebuild full witnesses for `leaf_positions` in `shard` without the whole tree.
+    pub fn shard_witnesses(
+        index: &GapShardIndex,
+        shard: &GapShard,
+        leaf_positions: &[u64],
+    ) -> Result<Vec<Vec<[u8; 32]>>, MerklePathError> {
+        shard::shard_witnesses(
+            index,
+            shard,
+            leaf_positions,
+            gap_leaf,
+            orchard_node_from_bytes,
+            MerkleHashOrchard::empty_root,
+            MerkleHashOrchard::combine,
+            |node| node.to_bytes(),
+        )
+    }
+
+    /// Serialize the tree as a versioned gap-tree container.
+    pub fn to_bytes(&self, options: &ContainerOptions) -> Result<Vec<u8>, MerklePathError> {
+        self.0.to_bytes(Pool::Orchard, options)
+    }
+
+    /// Parse a versioned gap-tree container or a legacy headerless gap-tree file.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerklePathError> {
-        DenseGapTree::from_bytes(bytes).map(Self)
+        DenseGapTree::from_bytes(bytes, Pool::Orchard).map(Self)
+    }
+
+    /// Memory-map a persisted gap tree instead of reading it into memory.
+    pub fn map_file(path: &Path) -> Result<Self, MerklePathError> {
+        DenseGapTree::map_file(path, Pool::Orchard).map(Self)
     }
+
+    /// Container header of the file this tree was loaded from, if it was versioned.
+    #[must_use]
+    pub const fn header(&self) -> Option<&ContainerHeader> {
+        self.0.header()
+    }
+}
+
+/// Hash `(left_bound, right_bound)` into a gap leaf.
+fn gap_leaf(
+    left_bound: &Nullifier,
+    right_bound: &Nullifier,
+) -> Result<MerkleHashOrchard, MerklePathError> {
+    let left = orchard_node_from_bytes(*left_bound.as_ref()).ok_or(MerklePathError::Unexpected(
+        "invalid Orchard left gap bound encoding",
+    ))?;
+    let right = orchard_node_from_bytes(*right_bound.as_ref()).ok_or(
+        MerklePathError::Unexpected("invalid Orchard right gap bound encoding"),
+    )?;
+    Ok(MerkleHashOrchard::combine(
+        ORCHARD_LEAF_HASH_LEVEL.into(),
+        &left,
+        &right,
+    ))
 }
@@ -0,0 +1,141 @@
+use std::path::Path;
+
+use incrementalmerkletree::Hashable as _;
+use zair_core::base::{Nullifier, Pool, SanitiseNullifiers};
+use zair_core::container::{ContainerHeader, ContainerOptions};
+
+use super::dense::{self, DenseGapTree};
+use super::shard::{self, GapShard, GapShardIndex, GapShards};
+use crate::core::{MerklePathError, should_report_progress};
+use crate::pool::orchard::{
+    canonicalize_orchard_chain_nullifiers, orchard_base_from_nullifier, orchard_gap_bounds,
+    orchard_max_nullifier, orchard_node_from_bytes,
+};
+use crate::sparse::{OrchardPoseidonNode, orchard_poseidon_gap_leaf};
+
+#[derive(Debug, Clone)]
+pub struct OrchardPoseidonGapTree(DenseGapTree);
+
+impl OrchardPoseidonGapTree {
+    pub fn from_nullifiers_with_progress(
+        nullifiers: &SanitiseNullifiers,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<Self, MerklePathError> {
+        let chain = canonicalize_orchard_chain_nullifiers("chain", nullifiers)?;
+        let min_node = orchard_node_from_bytes(*Nullifier::MIN.as_ref()).ok_or(
+            MerklePathError::Unexpected("invalid Orchard min nullifier encoding"),
+        )?;
+        let max_node = orchard_node_from_bytes(*orchard_max_nullifier().as_ref()).ok_or(
+            MerklePathError::Unexpected("invalid Orchard max nullifier encoding"),
+        )?;
+
+        let leaf_count = chain.len().saturating_add(1);
+        let mut leaves = Vec::with_capacity(leaf_count);
+        let mut last_pct = 0_usize;
+        on_progress(0, leaf_count);
+        for gap_idx in 0..leaf_count {
+            let gap = orchard_gap_bounds(&chain, gap_idx, min_node, max_node)?;
+            leaves.push(orchard_poseidon_gap_leaf(&gap)?);
+            if should_report_progress(gap_idx.saturating_add(1), leaf_count, &mut last_pct) {
+                on_progress(gap_idx.saturating_add(1), leaf_count);
+            }
+        }
+        DenseGapTree::from_leaves(
+            leaves,
+            OrchardPoseidonNode::empty_root,
+            OrchardPoseidonNode::combine,
+            OrchardPoseidonNode::to_bytes,
+        )
+        .map(Self)
+    }
+
+    #[must_use]
+    pub const fn root_bytes(&self) -> [u8; 32] {
+        self.0.root_bytes()
+    }
+
+    pub fn witness_bytes(&self, leaf_position: u64) -> Result<Vec<[u8; 32]>, MerklePathError> {
+        self.0.witness_bytes(leaf_position, |level| {
+            OrchardPoseidonNode::empty_root(level).to_bytes()
+        })
+    }
+
+    /// Recompute the root committed to by a `(left_bound, right_bound)` gap witness.
+    pub fn root_from_witness(
+        left_bound: &Nullifier,
+        right_bound: &Nullifier,
+        leaf_position: u64,
+        witness: &[[u8; 32]],
+    ) -> Result<[u8; 32], MerklePathError> {
+        dense::root_from_witness(
+            gap_leaf(left_bound, right_bound)?,
+            leaf_position,
+            witness,
+            OrchardPoseidonNode::from_bytes,
+            OrchardPoseidonNode::combine,
+            OrchardPoseidonNode::to_bytes,
+        )
+    }
+
+    /// Split the tree, built from `nullifiers`, into shards of `2^shard_depth` gaps.
+    pub fn shards(
+        &self,
+        nullifiers: &SanitiseNullifiers,
+        shard_depth: u8,
+    ) -> Result<GapShards, MerklePathError> {
+        shard::split(Pool::Orchard, &self.0, nullifiers, shard_depth)
+    }
+
+    /// Rebuild full witnesses for `leaf_positions` in `shard` without the whole tree.
+    pub fn shard_witnesses(
+        index: &GapShardIndex,
+        shard: &GapShard,
+        leaf_positions: &[u64],
+    ) -> Result<Vec<Vec<[u8; 32]>>, MerklePathError> {
+        shard::shard_witnesses(
+            index,
+            shard,
+            leaf_positions,
+            gap_leaf,
+            OrchardPoseidonNode::from_bytes,
+            OrchardPoseidonNode::empty_root,
+            OrchardPoseidonNode::combine,
+            OrchardPoseidonNode::to_bytes,
+        )
+    }
+
+    /// Serialize the tree as a versioned gap-tree container.
+    pub fn to_bytes(&self, options: &ContainerOptions) -> Result<Vec<u8>, MerklePathError> {
+        self.0.to_bytes(Pool::Orchard, options)
+    }
+
+    /// Parse a versioned gap-tree container or a legacy headerless gap-tree file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerklePathError> {
+        DenseGapTree::from_bytes(bytes, Pool::Orchard).map(Self)
+    }
+
+    /// Memory-map a persisted gap tree instead of reading it into memory.
+    pub fn map_file(path: &Path) -> Result<Self, MerklePathError> {
+        DenseGapTree::map_file(path, Pool::Orchard).map(Self)
+    }
+
+    /// Container header of the file this tree was loaded from, if it was versioned.
+    #[must_use]
+    pub const fn header(&self) -> Option<&ContainerHeader> {
+        self.0.header()
+    }
+}
+
+/// Hash `(left_bound, right_bound)` into a gap leaf.
+fn gap_leaf(
+    left_bound: &Nullifier,
+    right_bound: &Nullifier,
+) -> Result<OrchardPoseidonNode, MerklePathError> {
+    let left = orchard_base_from_nullifier(left_bound).ok_or(MerklePathError::Unexpected(
+        "invalid Orchard left gap bound encoding",
+    ))?;
+    let right = orchard_base_from_nullifier(right_bound).ok_or(MerklePathError::Unexpected(
+        "invalid Orchard right gap bound encoding",
+    ))?;
+    Ok(OrchardPoseidonNode::leaf_from_bounds(left, right))
+}
@@ -1,7 +1,11 @@
+use std::path::Path;
+
 use incrementalmerkletree::Hashable as _;
-use zair_core::base::SanitiseNullifiers;
+use zair_core::base::{Nullifier, Pool, SanitiseNullifiers};
+use zair_core::container::{ContainerHeader, ContainerOptions};
 
-use super::dense::DenseGapTree;
+use super::dense::{self, DenseGapTree};
+use super::shard::{self, GapShard, GapShardIndex, GapShards};
 use crate::core::{MerklePathError, should_report_progress};
 use crate::node::NonMembershipNode;
 use crate::pool::sapling::sapling_gap_bounds;
@@ -49,12 +53,68 @@ impl SaplingGapTree {
         })
     }
 
-    #[must_use]
-    pub fn to_bytes(&self) -> Vec<u8> {
-        self.0.to_bytes()
+    /// Recompute the root committed to by a `(left_bound, right_bound)` gap witness.
+    pub fn root_from_witness(
+        left_bound: &Nullifier,
+        right_bound: &Nullifier,
+        leaf_position: u64,
+        witness: &[[u8; 32]],
+    ) -> Result<[u8; 32], MerklePathError> {
+        dense::root_from_witness(
+            NonMembershipNode::leaf_from_nullifiers(left_bound, right_bound),
+            leaf_position,
+            witness,
+            |bytes| Some(NonMembershipNode::from(bytes)),
+            NonMembershipNode::combine,
+            |node| node.to_bytes(),
+        )
     }
 
+    /// Split the tree, built from `nullifiers`, into shards of `2^shard_depth` gaps.
+    pub fn shards(
+        &self,
+        nullifiers: &SanitiseNullifiers,
+        shard_depth: u8,
+    ) -> Result<GapShards, MerklePathError> {
+        shard::split(Pool::Sapling, &self.0, nullifiers, shard_depth)
+    }
+
+    /// Rebuild full witnesses for `leaf_positions` in `shard` without the whole tree.
+    pub fn shard_witnesses(
+        index: &GapShardIndex,
+        shard: &GapShard,
+        leaf_positions: &[u64],
+    ) -> Result<Vec<Vec<[u8; 32]>>, MerklePathError> {
+        shard::shard_witnesses(
+            index,
+            shard,
+            leaf_positions,
+            |left, right| Ok(NonMembershipNode::leaf_from_nullifiers(left, right)),
+            |bytes| Some(NonMembershipNode::from(bytes)),
+            NonMembershipNode::empty_root,
+            NonMembershipNode::combine,
+            |node| node.to_bytes(),
+        )
+    }
+
+    /// Serialize the tree as a versioned gap-tree container.
+    pub fn to_bytes(&self, options: &ContainerOptions) -> Result<Vec<u8>, MerklePathError> {
+        self.0.to_bytes(Pool::Sapling, options)
+    }
+
+    /// Parse a versioned gap-tree container or a legacy headerless gap-tree file.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, MerklePathError> {
-        DenseGapTree::from_bytes(bytes).map(Self)
+        DenseGapTree::from_bytes(bytes, Pool::Sapling).map(Self)
+    }
+
+    /// Memory-map a persisted gap tree instead of reading it into memory.
+    pub fn map_file(path: &Path) -> Result<Self, MerklePathError> {
+        DenseGapTree::map_file(path, Pool::Sapling).map(Self)
+    }
+
+    /// Container header of the file this tree was loaded from, if it was versioned.
+    #[must_use]
+    pub const fn header(&self) -> Option<&ContainerHeader> {
+        self.0.header()
     }
 }
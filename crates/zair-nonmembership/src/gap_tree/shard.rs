@@ -0,0 +1,585 @@
+//! Fixed-size gap-tree shards.
+//!
+//! Shard `i` holds the gap bounds for leaf positions `i * 2^depth .. (i + 1) * 2^depth`, so each
+//! shard covers one contiguous nullifier range. The shard index lists every shard's first bound
+//! and its subtree root, which is the gap-tree node at level `depth`. A client downloads the
+//! index plus the shards covering its nullifiers, rebuilds those shard subtrees and splices
+//! their paths with the path through the shard roots. The result is an ordinary witness for the
+//! single gap-tree root, while the publisher only learns which nullifier ranges were fetched.
+//!
+//! Shards are fixed leaf-count runs rather than nullifier-prefix buckets: a prefix bucket holds a
+//! variable number of gaps that does not line up with a gap-tree subtree, so its root could not
+//! be spliced into a witness for the single root. A fetched shard reveals the nullifier range
+//! between two boundaries published in the index, not a fixed prefix.
+
+use std::ops::Range;
+
+use incrementalmerkletree::Level;
+use zair_core::base::{Nullifier, Pool, SanitiseNullifiers};
+use zair_core::container::{self, Container, ContainerHeader, ContainerKind, ContainerOptions};
+
+use super::dense::DenseGapTree;
+use super::locator::pool_cmp;
+use crate::core::{MerklePathError, TreePosition, validate_leaf_count};
+use crate::node::NON_MEMBERSHIP_TREE_DEPTH;
+use crate::pool::orchard::{
+    canonicalize_orchard_chain_nullifiers, canonicalize_orchard_user_nullifiers,
+    orchard_max_nullifier,
+};
+
+/// Default shard depth: `2^16` gaps, about 2 MiB of bounds per shard.
+pub const DEFAULT_GAP_SHARD_DEPTH: u8 = 16;
+
+/// `shard_index`/`leaf_count` (8 bytes), shard depth (1 byte), reserved (7 bytes).
+const PAYLOAD_PREFIX_BYTES: usize = 16;
+const NODE_BYTES: usize = 32;
+
+const fn validate_shard_depth(shard_depth: u8) -> Result<(), MerklePathError> {
+    if shard_depth == 0 || shard_depth >= NON_MEMBERSHIP_TREE_DEPTH {
+        return Err(MerklePathError::Unexpected(
+            "gap-tree shard depth must be between 1 and 31",
+        ));
+    }
+    Ok(())
+}
+
+fn encode_prefix(value: u64, shard_depth: u8) -> [u8; PAYLOAD_PREFIX_BYTES] {
+    let mut prefix = [0_u8; PAYLOAD_PREFIX_BYTES];
+    prefix[..8].copy_from_slice(&value.to_le_bytes());
+    prefix[8] = shard_depth;
+    prefix
+}
+
+/// Decoded shard or shard-index container.
+struct ShardFile {
+    header: ContainerHeader,
+    /// Shard index for a shard, leaf count for an index.
+    value: u64,
+    shard_depth: u8,
+    items: Vec<[u8; 32]>,
+}
+
+/// Decode a shard container and split its payload prefix from the 32-byte items.
+fn decode(bytes: &[u8], kind: ContainerKind, pool: Pool) -> Result<ShardFile, MerklePathError> {
+    let Container::Versioned { header, payload } = container::decode(bytes)? else {
+        return Err(MerklePathError::Unexpected(
+            "gap-tree shard file is missing its container header",
+        ));
+    };
+    header.ensure_content(kind, pool)?;
+    let (prefix, items) =
+        payload
+            .split_first_chunk::<PAYLOAD_PREFIX_BYTES>()
+            .ok_or(MerklePathError::Unexpected(
+                "gap-tree shard file is too short",
+            ))?;
+    let value = u64::from_le_bytes(
+        prefix[..8]
+            .try_into()
+            .map_err(|_| MerklePathError::Unexpected("gap-tree shard file prefix is malformed"))?,
+    );
+    let shard_depth = prefix[8];
+    validate_shard_depth(shard_depth)?;
+    let (items, rest) = items.as_chunks::<NODE_BYTES>();
+    if !rest.is_empty() {
+        return Err(MerklePathError::Unexpected(
+            "gap-tree shard file length mismatch",
+        ));
+    }
+    Ok(ShardFile {
+        header,
+        value,
+        shard_depth,
+        items: items.to_vec(),
+    })
+}
+
+/// Gap bounds in pool order, including the sentinels, for the tree built from `nullifiers`.
+fn gap_bounds(
+    pool: Pool,
+    nullifiers: &SanitiseNullifiers,
+) -> Result<Vec<Nullifier>, MerklePathError> {
+    let mut bounds = Vec::with_capacity(nullifiers.len().saturating_add(2));
+    bounds.push(Nullifier::MIN);
+    match pool {
+        Pool::Sapling => {
+            bounds.extend_from_slice(nullifiers);
+            bounds.push(Nullifier::MAX);
+        }
+        Pool::Orchard => {
+            let chain = canonicalize_orchard_chain_nullifiers("chain", nullifiers)?;
+            bounds.extend(chain.into_iter().map(|item| item.bytes));
+            bounds.push(orchard_max_nullifier());
+        }
+        Pool::Transparent => return Err(MerklePathError::NoGapTree(pool)),
+    }
+    Ok(bounds)
+}
+
+/// Gap bounds for one shard: `bounds[j]..bounds[j + 1]` is gap `first_position() + j`.
+#[derive(Debug, Clone)]
+pub struct GapShard {
+    pool: Pool,
+    root: [u8; 32],
+    shard_depth: u8,
+    shard_index: u64,
+    bounds: Vec<Nullifier>,
+    header: Option<ContainerHeader>,
+}
+
+impl GapShard {
+    #[must_use]
+    pub const fn pool(&self) -> Pool {
+        self.pool
+    }
+
+    #[must_use]
+    pub const fn shard_index(&self) -> u64 {
+        self.shard_index
+    }
+
+    /// Leaf position of the shard's first gap.
+    #[must_use]
+    pub const fn first_position(&self) -> u64 {
+        self.shard_index << self.shard_depth
+    }
+
+    /// Number of gaps in the shard.
+    #[must_use]
+    pub const fn leaf_count(&self) -> usize {
+        self.bounds.len().saturating_sub(1)
+    }
+
+    /// Container header of the file this shard was loaded from.
+    #[must_use]
+    pub const fn header(&self) -> Option<&ContainerHeader> {
+        self.header.as_ref()
+    }
+
+    /// Find the gap containing `nullifier`, or `None` if it is part of the snapshot.
+    ///
+    /// # Errors
+    /// Returns an error if the nullifier lies outside the shard's range.
+    pub fn locate(&self, nullifier: Nullifier) -> Result<Option<TreePosition>, MerklePathError> {
+        let (nullifier, search) = self.search(nullifier)?;
+        match search {
+            Ok(_) => Ok(None),
+            Err(idx) => self.position(nullifier, idx - 1).map(Some),
+        }
+    }
+
+    /// Find the gap whose right bound is `nullifier`, or `None` if it is not part of the snapshot.
+    ///
+    /// # Errors
+    /// Returns an error if the nullifier lies outside the shard's range or is its first bound,
+    /// whose gap belongs to the previous shard.
+    pub fn locate_spent(
+        &self,
+        nullifier: Nullifier,
+    ) -> Result<Option<TreePosition>, MerklePathError> {
+        let (nullifier, search) = self.search(nullifier)?;
+        match search {
+            Ok(0) => Err(MerklePathError::Unexpected(
+                "nullifier closes a gap of the previous gap-tree shard",
+            )),
+            Ok(idx) => self.position(nullifier, idx - 1).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Search the bounds for `nullifier`, canonicalized for the pool.
+    ///
+    /// Misses outside `bounds[0]..bounds[len - 1]` are reported as errors.
+    fn search(
+        &self,
+        nullifier: Nullifier,
+    ) -> Result<(Nullifier, Result<usize, usize>), MerklePathError> {
+        let nullifier = match self.pool {
+            Pool::Sapling => nullifier,
+            Pool::Orchard => canonicalize_orchard_user_nullifiers("user", &[nullifier])?
+                .first()
+                .copied()
+                .ok_or(MerklePathError::Unexpected("Orchard nullifier vanished"))?,
+            Pool::Transparent => return Err(MerklePathError::NoGapTree(self.pool)),
+        };
+        match self
+            .bounds
+            .binary_search_by(|bound| pool_cmp(self.pool, bound, &nullifier))
+        {
+            Err(0) => Err(MerklePathError::Unexpected(
+                "nullifier precedes the gap-tree shard",
+            )),
+            Err(idx) if idx == self.bounds.len() => Err(MerklePathError::Unexpected(
+                "nullifier follows the gap-tree shard",
+            )),
+            search => Ok((nullifier, search)),
+        }
+    }
+
+    /// Gap `bounds[idx]..bounds[idx + 1]` of the shard, reported for `nullifier`.
+    fn position(&self, nullifier: Nullifier, idx: usize) -> Result<TreePosition, MerklePathError> {
+        let first = usize::try_from(self.first_position())?;
+        TreePosition::new(
+            nullifier,
+            first + idx,
+            self.bounds[idx],
+            self.bounds[idx + 1],
+        )
+    }
+
+    /// Serialize the shard as a versioned container whose root is the full gap-tree root.
+    pub fn to_bytes(&self, options: &ContainerOptions) -> Result<Vec<u8>, MerklePathError> {
+        let header = ContainerHeader::new(
+            ContainerKind::GapShard,
+            self.pool,
+            self.root,
+            u64::try_from(self.bounds.len())?,
+            options,
+        );
+        let mut payload = Vec::with_capacity(PAYLOAD_PREFIX_BYTES + self.bounds.len() * NODE_BYTES);
+        payload.extend_from_slice(&encode_prefix(self.shard_index, self.shard_depth));
+        for bound in &self.bounds {
+            payload.extend_from_slice(bound.as_ref());
+        }
+        Ok(container::encode(&header, &payload)?)
+    }
+
+    /// Parse a `pool` shard written by [`GapShard::to_bytes`].
+    pub fn from_bytes(bytes: &[u8], pool: Pool) -> Result<Self, MerklePathError> {
+        let ShardFile {
+            header,
+            value: shard_index,
+            shard_depth,
+            items,
+        } = decode(bytes, ContainerKind::GapShard, pool)?;
+        if u64::try_from(items.len())? != header.item_count {
+            return Err(MerklePathError::Unexpected(
+                "gap-tree shard bound count does not match its header",
+            ));
+        }
+        if items.len() < 2 || items.len() > (1_usize << shard_depth) + 1 {
+            return Err(MerklePathError::Unexpected(
+                "gap-tree shard has an invalid number of bounds",
+            ));
+        }
+        let bounds: Vec<Nullifier> = items.into_iter().map(Nullifier::from).collect();
+        if !bounds
+            .iter()
+            .zip(bounds.iter().skip(1))
+            .all(|(left, right)| pool_cmp(pool, left, right).is_lt())
+        {
+            return Err(MerklePathError::Unexpected(
+                "gap-tree shard bounds are not sorted",
+            ));
+        }
+        Ok(Self {
+            pool,
+            root: header.root,
+            shard_depth,
+            shard_index,
+            bounds,
+            header: Some(header),
+        })
+    }
+}
+
+/// First bound and subtree root of every shard of one gap tree.
+#[derive(Debug, Clone)]
+pub struct GapShardIndex {
+    pool: Pool,
+    root: [u8; 32],
+    shard_depth: u8,
+    leaf_count: u64,
+    first_bounds: Vec<Nullifier>,
+    shard_roots: Vec<[u8; 32]>,
+    header: Option<ContainerHeader>,
+}
+
+impl GapShardIndex {
+    #[must_use]
+    pub const fn pool(&self) -> Pool {
+        self.pool
+    }
+
+    /// Root of the full gap tree.
+    #[must_use]
+    pub const fn root_bytes(&self) -> [u8; 32] {
+        self.root
+    }
+
+    #[must_use]
+    pub const fn shard_depth(&self) -> u8 {
+        self.shard_depth
+    }
+
+    #[must_use]
+    pub const fn shard_count(&self) -> usize {
+        self.shard_roots.len()
+    }
+
+    /// Container header of the file this index was loaded from.
+    #[must_use]
+    pub const fn header(&self) -> Option<&ContainerHeader> {
+        self.header.as_ref()
+    }
+
+    /// Index of the shard whose range contains `nullifier`.
+    #[must_use]
+    pub fn shard_for(&self, nullifier: &Nullifier) -> Option<u64> {
+        self.first_bounds
+            .partition_point(|bound| pool_cmp(self.pool, bound, nullifier).is_lt())
+            .checked_sub(1)
+            .and_then(|index| u64::try_from(index).ok())
+    }
+
+    /// Check that `shard` is the shard this index lists at its position.
+    fn check_shard(&self, shard: &GapShard) -> Result<usize, MerklePathError> {
+        if shard.pool != self.pool ||
+            shard.root != self.root ||
+            shard.shard_depth != self.shard_depth
+        {
+            return Err(MerklePathError::Unexpected(
+                "gap-tree shard belongs to a different shard index",
+            ));
+        }
+        let index = usize::try_from(shard.shard_index)?;
+        let first_bound = self
+            .first_bounds
+            .get(index)
+            .ok_or(MerklePathError::Unexpected(
+                "gap-tree shard is not listed in the shard index",
+            ))?;
+        let remaining = self.leaf_count.checked_sub(shard.first_position()).ok_or(
+            MerklePathError::Unexpected("gap-tree shard starts past the end of the gap tree"),
+        )?;
+        let expected = remaining.min(1_u64 << self.shard_depth);
+        if u64::try_from(shard.leaf_count())? != expected || shard.bounds[0] != *first_bound {
+            return Err(MerklePathError::Unexpected(
+                "gap-tree shard does not match the shard index",
+            ));
+        }
+        Ok(index)
+    }
+
+    /// Serialize the index as a versioned container whose root is the full gap-tree root.
+    pub fn to_bytes(&self, options: &ContainerOptions) -> Result<Vec<u8>, MerklePathError> {
+        let header = ContainerHeader::new(
+            ContainerKind::GapShardIndex,
+            self.pool,
+            self.root,
+            u64::try_from(self.shard_roots.len())?,
+            options,
+        );
+        let mut payload =
+            Vec::with_capacity(PAYLOAD_PREFIX_BYTES + self.shard_roots.len() * 2 * NODE_BYTES);
+        payload.extend_from_slice(&encode_prefix(self.leaf_count, self.shard_depth));
+        for (bound, root) in self.first_bounds.iter().zip(&self.shard_roots) {
+            payload.extend_from_slice(bound.as_ref());
+            payload.extend_from_slice(root);
+        }
+        Ok(container::encode(&header, &payload)?)
+    }
+
+    /// Parse a `pool` shard index written by [`GapShardIndex::to_bytes`].
+    pub fn from_bytes(bytes: &[u8], pool: Pool) -> Result<Self, MerklePathError> {
+        let ShardFile {
+            header,
+            value: leaf_count,
+            shard_depth,
+            items,
+        } = decode(bytes, ContainerKind::GapShardIndex, pool)?;
+        validate_leaf_count(usize::try_from(leaf_count)?)?;
+        let shard_count = leaf_count.div_ceil(1_u64 << shard_depth);
+        if header.item_count != shard_count || u64::try_from(items.len())? != shard_count * 2 {
+            return Err(MerklePathError::Unexpected(
+                "gap-tree shard index does not match its leaf count",
+            ));
+        }
+        let (first_bounds, shard_roots) = items
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|[bound, root]| (Nullifier::from(*bound), *root))
+            .unzip();
+        Ok(Self {
+            pool,
+            root: header.root,
+            shard_depth,
+            leaf_count,
+            first_bounds,
+            shard_roots,
+            header: Some(header),
+        })
+    }
+}
+
+/// A gap tree split into shards, ready to be published.
+#[derive(Debug, Clone)]
+pub struct GapShards {
+    pub index: GapShardIndex,
+    pub shards: Vec<GapShard>,
+}
+
+/// Split `tree`, built from `nullifiers`, into shards of `2^shard_depth` gaps.
+pub(super) fn split(
+    pool: Pool,
+    tree: &DenseGapTree,
+    nullifiers: &SanitiseNullifiers,
+    shard_depth: u8,
+) -> Result<GapShards, MerklePathError> {
+    validate_shard_depth(shard_depth)?;
+    let bounds = gap_bounds(pool, nullifiers)?;
+    let leaf_count = tree.leaf_count();
+    if bounds.len() != leaf_count + 1 {
+        return Err(MerklePathError::Unexpected(
+            "gap-tree was not built from the given nullifiers",
+        ));
+    }
+
+    let shard_size = 1_usize << shard_depth;
+    let shard_roots = tree.level_nodes(shard_depth);
+    let shards: Vec<GapShard> = (0_u64..)
+        .zip((0..leaf_count).step_by(shard_size))
+        .map(|(shard_index, start)| GapShard {
+            pool,
+            root: tree.root_bytes(),
+            shard_depth,
+            shard_index,
+            bounds: bounds[start..=(start + shard_size).min(leaf_count)].to_vec(),
+            header: None,
+        })
+        .collect();
+    let index = GapShardIndex {
+        pool,
+        root: tree.root_bytes(),
+        shard_depth,
+        leaf_count: u64::try_from(leaf_count)?,
+        first_bounds: shards.iter().map(|shard| shard.bounds[0]).collect(),
+        shard_roots,
+        header: None,
+    };
+    Ok(GapShards { index, shards })
+}
+
+/// Every level of the subtree over `leaves` spanning `levels`, the last one being its root.
+///
+/// Missing right children are filled with empty roots, as in `DenseGapTree::from_leaves`.
+fn subtree_levels<T: Copy>(
+    leaves: Vec<T>,
+    levels: Range<u8>,
+    empty_root: &impl Fn(Level) -> T,
+    combine: &impl Fn(Level, &T, &T) -> T,
+) -> Result<Vec<Vec<T>>, MerklePathError> {
+    let mut out = Vec::with_capacity(levels.len() + 1);
+    let mut current = leaves;
+    for level in levels {
+        let level = Level::from(level);
+        let empty = empty_root(level);
+        let next = current
+            .chunks(2)
+            .map(|pair| combine(level, &pair[0], pair.get(1).unwrap_or(&empty)))
+            .collect();
+        out.push(std::mem::replace(&mut current, next));
+    }
+    if current.len() != 1 {
+        return Err(MerklePathError::Unexpected(
+            "gap-tree shard subtree does not fit its depth",
+        ));
+    }
+    out.push(current);
+    Ok(out)
+}
+
+/// Sibling path of `index` through `levels` produced by [`subtree_levels`].
+fn subtree_path<T: Copy>(
+    levels: &[Vec<T>],
+    base_level: u8,
+    mut index: usize,
+    empty_root: &impl Fn(Level) -> T,
+) -> Vec<T> {
+    levels[..levels.len() - 1]
+        .iter()
+        .zip(base_level..)
+        .map(|(nodes, level)| {
+            let sibling = nodes
+                .get(index ^ 1)
+                .copied()
+                .unwrap_or_else(|| empty_root(Level::from(level)));
+            index /= 2;
+            sibling
+        })
+        .collect()
+}
+
+/// Rebuild full gap-tree witnesses for `leaf_positions` from one shard and the shard index.
+///
+/// `leaf` hashes a gap's `(left, right)` bounds into a leaf node.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "Node operations are passed as closures, as in `DenseGapTree`"
+)]
+pub(super) fn shard_witnesses<T: Copy>(
+    index: &GapShardIndex,
+    shard: &GapShard,
+    leaf_positions: &[u64],
+    leaf: impl Fn(&Nullifier, &Nullifier) -> Result<T, MerklePathError>,
+    from_bytes: impl Fn([u8; 32]) -> Option<T>,
+    empty_root: impl Fn(Level) -> T,
+    combine: impl Fn(Level, &T, &T) -> T,
+    to_bytes: impl Fn(T) -> [u8; 32],
+) -> Result<Vec<Vec<[u8; 32]>>, MerklePathError> {
+    let shard_index = index.check_shard(shard)?;
+    let shard_depth = index.shard_depth;
+
+    let leaves = shard
+        .bounds
+        .iter()
+        .zip(shard.bounds.iter().skip(1))
+        .map(|(left, right)| leaf(left, right))
+        .collect::<Result<Vec<_>, _>>()?;
+    let shard_levels = subtree_levels(leaves, 0..shard_depth, &empty_root, &combine)?;
+    if to_bytes(shard_levels[shard_levels.len() - 1][0]) != index.shard_roots[shard_index] {
+        return Err(MerklePathError::Unexpected(
+            "gap-tree shard does not open to its root in the shard index",
+        ));
+    }
+
+    let shard_roots = index
+        .shard_roots
+        .iter()
+        .map(|bytes| {
+            from_bytes(*bytes).ok_or(MerklePathError::Unexpected(
+                "gap-tree shard index contains an invalid node encoding",
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let top_levels = subtree_levels(
+        shard_roots,
+        shard_depth..NON_MEMBERSHIP_TREE_DEPTH,
+        &empty_root,
+        &combine,
+    )?;
+    if to_bytes(top_levels[top_levels.len() - 1][0]) != index.root {
+        return Err(MerklePathError::Unexpected(
+            "gap-tree shard index does not open to its root",
+        ));
+    }
+    let top_path = subtree_path(&top_levels, shard_depth, shard_index, &empty_root);
+
+    let first = shard.first_position();
+    leaf_positions
+        .iter()
+        .map(|&position| {
+            let offset = position
+                .checked_sub(first)
+                .and_then(|offset| usize::try_from(offset).ok())
+                .filter(|&offset| offset < shard.leaf_count())
+                .ok_or(MerklePathError::NotMarked(position))?;
+            Ok(subtree_path(&shard_levels, 0, offset, &empty_root)
+                .into_iter()
+                .chain(top_path.iter().copied())
+                .map(&to_bytes)
+                .collect())
+        })
+        .collect()
+}
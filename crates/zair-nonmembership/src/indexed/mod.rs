@@ -0,0 +1,483 @@
+//! Indexed (linked-leaf) Merkle trees for non-membership proofs.
+//!
+//! Each leaf stores `(value, next_value, next_index)`. The leaves form a linked list sorted by
+//! value that starts at a sentinel leaf `(MIN, MAX, 0)` at position 0, while the leaves
+//! themselves stay in insertion order. A nullifier `nf` is proven absent by opening the *low
+//! leaf*, the leaf with `value < nf < next_value`.
+//!
+//! Inserting `nf` appends `(nf, low.next_value, low.next_index)` and re-points the low leaf at
+//! the new position. Only two authentication paths change, so the tree can follow an evolving
+//! spent set without rebuilding it.
+
+#![allow(
+    clippy::indexing_slicing,
+    clippy::arithmetic_side_effects,
+    reason = "Level and position index math is bounded by the fixed tree depth"
+)]
+
+mod orchard;
+mod sapling;
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use incrementalmerkletree::Level;
+pub use orchard::{OrchardIndexedHasher, orchard_indexed_leaf_hash};
+pub use sapling::{INDEXED_LEAF_HASH_LEVEL, SaplingIndexedHasher, sapling_indexed_leaf_hash};
+use zair_core::base::Nullifier;
+
+use crate::core::{MerklePathError, should_report_progress, validate_leaf_count};
+use crate::node::NON_MEMBERSHIP_TREE_DEPTH;
+
+/// Indexed Merkle tree over Sapling nullifiers.
+pub type SaplingIndexedTree = IndexedMerkleTree<SaplingIndexedHasher>;
+
+/// Indexed Merkle tree over Orchard nullifiers.
+pub type OrchardIndexedTree = IndexedMerkleTree<OrchardIndexedHasher>;
+
+/// A leaf of an indexed Merkle tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexedLeaf {
+    /// The nullifier stored in this leaf.
+    pub value: Nullifier,
+    /// The next larger nullifier in the tree (or the pool maximum for the last leaf).
+    pub next_value: Nullifier,
+    /// The leaf position of `next_value` (0 for the last leaf).
+    pub next_index: u32,
+}
+
+/// A non-membership witness: the low leaf and its authentication path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedWitness {
+    /// Position of the low leaf.
+    pub leaf_position: u64,
+    /// The low leaf, with `value < nf < next_value`.
+    pub leaf: IndexedLeaf,
+    /// Sibling nodes from the leaf level up to the root.
+    pub path: Vec<[u8; 32]>,
+}
+
+/// Pool-specific hashing and ordering for an indexed Merkle tree.
+pub trait IndexedTreeHasher {
+    /// Tree node type.
+    type Node: Copy + Eq + Debug;
+
+    /// Largest representable value; the last leaf points at it.
+    fn max_value() -> Nullifier;
+
+    /// Key whose byte order matches the pool's nullifier ordering.
+    fn sort_key(nullifier: &Nullifier) -> [u8; 32];
+
+    /// Validate a nullifier for this pool.
+    ///
+    /// # Errors
+    /// Returns an error if the nullifier is not a valid encoding for the pool.
+    fn validate(
+        set: &'static str,
+        index: usize,
+        nullifier: Nullifier,
+    ) -> Result<Nullifier, MerklePathError>;
+
+    /// Hash a leaf.
+    ///
+    /// # Errors
+    /// Returns an error if a leaf value is not a valid encoding for the pool.
+    fn hash_leaf(leaf: &IndexedLeaf) -> Result<Self::Node, MerklePathError>;
+
+    /// Combine two child nodes at `level`.
+    fn combine(level: Level, lhs: &Self::Node, rhs: &Self::Node) -> Self::Node;
+
+    /// Root of an empty subtree at `level`.
+    fn empty_root(level: Level) -> Self::Node;
+
+    /// Serialize a node.
+    fn node_to_bytes(node: Self::Node) -> [u8; 32];
+}
+
+/// An indexed Merkle tree of depth [`NON_MEMBERSHIP_TREE_DEPTH`].
+///
+/// All levels are kept in memory so appends and low-leaf updates cost `O(log n)` hashes.
+#[derive(Debug, Clone)]
+pub struct IndexedMerkleTree<H: IndexedTreeHasher> {
+    leaves: Vec<IndexedLeaf>,
+    /// Sort key of each stored value to its leaf position.
+    positions: BTreeMap<[u8; 32], u32>,
+    /// `levels[0]` holds leaf hashes and `levels[DEPTH]` holds the root.
+    levels: Vec<Vec<H::Node>>,
+    /// Empty subtree roots per level, cached to keep updates at one hash per level.
+    empty_roots: Vec<H::Node>,
+}
+
+impl<H: IndexedTreeHasher> IndexedMerkleTree<H> {
+    /// Create a tree holding only the sentinel leaf.
+    ///
+    /// # Errors
+    /// Returns an error if the sentinel leaf cannot be hashed.
+    pub fn new() -> Result<Self, MerklePathError> {
+        Self::from_nullifiers(&[])
+    }
+
+    /// Build a tree from a set of nullifiers.
+    ///
+    /// # Errors
+    /// Returns an error if a nullifier is invalid for the pool or is not below the pool maximum.
+    pub fn from_nullifiers(nullifiers: &[Nullifier]) -> Result<Self, MerklePathError> {
+        Self::from_nullifiers_with_progress(nullifiers, |_, _| {})
+    }
+
+    /// Build a tree from a set of nullifiers, calling `on_progress(current, total)` as leaves
+    /// are hashed.
+    ///
+    /// Leaves are laid out in sorted order, so the result matches inserting the sorted
+    /// nullifiers one by one.
+    ///
+    /// # Errors
+    /// Returns an error if a nullifier is invalid for the pool or is not below the pool maximum.
+    pub fn from_nullifiers_with_progress(
+        nullifiers: &[Nullifier],
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<Self, MerklePathError> {
+        let max_value = H::max_value();
+        let max_key = H::sort_key(&max_value);
+        let min_key = H::sort_key(&Nullifier::MIN);
+        let mut values = Vec::with_capacity(nullifiers.len());
+        for (index, nullifier) in nullifiers.iter().enumerate() {
+            let value = H::validate("chain", index, *nullifier)?;
+            let key = H::sort_key(&value);
+            if key >= max_key {
+                return Err(MerklePathError::NullifierOutOfRange(value));
+            }
+            if key != min_key {
+                values.push(value);
+            }
+        }
+        values.sort_unstable_by_key(H::sort_key);
+        values.dedup();
+
+        let leaf_count = values.len().saturating_add(1);
+        validate_leaf_count(leaf_count)?;
+
+        let mut leaves = Vec::with_capacity(leaf_count);
+        let mut positions = BTreeMap::new();
+        let values_with_sentinel = std::iter::once(Nullifier::MIN).chain(values.iter().copied());
+        for (position, value) in values_with_sentinel.enumerate() {
+            let next = position + 1;
+            let (next_value, next_index) = values
+                .get(position)
+                .map_or((max_value, 0), |next_value| (*next_value, next));
+            leaves.push(IndexedLeaf {
+                value,
+                next_value,
+                next_index: u32::try_from(next_index)?,
+            });
+            positions.insert(H::sort_key(&value), u32::try_from(position)?);
+        }
+
+        let mut level_nodes = Vec::with_capacity(leaf_count);
+        let mut last_pct = 0_usize;
+        on_progress(0, leaf_count);
+        for (position, leaf) in leaves.iter().enumerate() {
+            level_nodes.push(H::hash_leaf(leaf)?);
+            if should_report_progress(position + 1, leaf_count, &mut last_pct) {
+                on_progress(position + 1, leaf_count);
+            }
+        }
+
+        let empty_roots: Vec<H::Node> = (0..NON_MEMBERSHIP_TREE_DEPTH)
+            .map(|level| H::empty_root(Level::from(level)))
+            .collect();
+        let mut levels = Vec::with_capacity(usize::from(NON_MEMBERSHIP_TREE_DEPTH) + 1);
+        for level in 0..NON_MEMBERSHIP_TREE_DEPTH {
+            let empty = empty_roots[usize::from(level)];
+            let parents = level_nodes
+                .chunks(2)
+                .map(|pair| {
+                    let right = pair.get(1).unwrap_or(&empty);
+                    H::combine(Level::from(level), &pair[0], right)
+                })
+                .collect();
+            levels.push(level_nodes);
+            level_nodes = parents;
+        }
+        levels.push(level_nodes);
+
+        Ok(Self {
+            leaves,
+            positions,
+            levels,
+            empty_roots,
+        })
+    }
+
+    /// Insert a nullifier, returning its leaf position.
+    ///
+    /// The low leaf is re-pointed at the new leaf; both paths are rehashed in `O(log n)`.
+    ///
+    /// # Errors
+    /// Returns an error if the nullifier is invalid for the pool, already present, not below the
+    /// pool maximum, or the tree is full.
+    pub fn insert(&mut self, nullifier: Nullifier) -> Result<u64, MerklePathError> {
+        let (low_position, low_leaf) = self.low_leaf(nullifier)?;
+        let new_position = self.leaves.len();
+        validate_leaf_count(new_position + 1)?;
+        let new_position_u32 = u32::try_from(new_position)?;
+        let low_index = usize::try_from(low_position)?;
+
+        let new_leaf = IndexedLeaf {
+            value: nullifier,
+            next_value: low_leaf.next_value,
+            next_index: low_leaf.next_index,
+        };
+        let updated_low = IndexedLeaf {
+            next_value: nullifier,
+            next_index: new_position_u32,
+            ..low_leaf
+        };
+
+        let new_hash = H::hash_leaf(&new_leaf)?;
+        let low_hash = H::hash_leaf(&updated_low)?;
+
+        self.leaves[low_index] = updated_low;
+        self.update_path(low_index, low_hash);
+        self.leaves.push(new_leaf);
+        self.update_path(new_position, new_hash);
+        self.positions
+            .insert(H::sort_key(&nullifier), new_position_u32);
+
+        Ok(u64::from(new_position_u32))
+    }
+
+    /// Find the low leaf for a nullifier that is not in the tree.
+    ///
+    /// # Errors
+    /// Returns an error if the nullifier is invalid for the pool, already present, or not below
+    /// the pool maximum.
+    pub fn low_leaf(&self, nullifier: Nullifier) -> Result<(u64, IndexedLeaf), MerklePathError> {
+        let nullifier = H::validate("query", 0, nullifier)?;
+        let key = H::sort_key(&nullifier);
+        if key >= H::sort_key(&H::max_value()) {
+            return Err(MerklePathError::NullifierOutOfRange(nullifier));
+        }
+        if self.positions.contains_key(&key) {
+            return Err(MerklePathError::NullifierPresent(nullifier));
+        }
+        let (_, &position) =
+            self.positions
+                .range(..key)
+                .next_back()
+                .ok_or(MerklePathError::Unexpected(
+                    "indexed tree is missing its sentinel leaf",
+                ))?;
+        let leaf = self.leaves[usize::try_from(position)?];
+        Ok((u64::from(position), leaf))
+    }
+
+    /// Produce a non-membership witness for a nullifier.
+    ///
+    /// # Errors
+    /// Returns an error if the nullifier is present in the tree or invalid for the pool.
+    pub fn non_membership_witness(
+        &self,
+        nullifier: Nullifier,
+    ) -> Result<IndexedWitness, MerklePathError> {
+        let (leaf_position, leaf) = self.low_leaf(nullifier)?;
+        Ok(IndexedWitness {
+            leaf_position,
+            leaf,
+            path: self.witness_bytes(leaf_position)?,
+        })
+    }
+
+    /// Produce the authentication path of a leaf as node bytes.
+    ///
+    /// # Errors
+    /// Returns an error if `leaf_position` is not in the tree.
+    pub fn witness_bytes(&self, leaf_position: u64) -> Result<Vec<[u8; 32]>, MerklePathError> {
+        let mut index = usize::try_from(leaf_position)?;
+        if index >= self.leaves.len() {
+            return Err(MerklePathError::NotMarked(leaf_position));
+        }
+        let mut path = Vec::with_capacity(usize::from(NON_MEMBERSHIP_TREE_DEPTH));
+        for level in 0..NON_MEMBERSHIP_TREE_DEPTH {
+            let empty = self.empty_roots[usize::from(level)];
+            let sibling = self.levels[usize::from(level)]
+                .get(index ^ 1)
+                .copied()
+                .unwrap_or(empty);
+            path.push(H::node_to_bytes(sibling));
+            index /= 2;
+        }
+        Ok(path)
+    }
+
+    /// Return the root as bytes.
+    #[must_use]
+    pub fn root_bytes(&self) -> [u8; 32] {
+        H::node_to_bytes(self.levels[usize::from(NON_MEMBERSHIP_TREE_DEPTH)][0])
+    }
+
+    /// Return the number of leaves, including the sentinel.
+    #[must_use]
+    pub const fn leaf_count(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Return the leaf at `position`, if any.
+    #[must_use]
+    pub fn leaf(&self, position: u64) -> Option<&IndexedLeaf> {
+        usize::try_from(position)
+            .ok()
+            .and_then(|index| self.leaves.get(index))
+    }
+
+    /// Set the hash of leaf `index` and recompute its ancestors, appending nodes as needed.
+    fn update_path(&mut self, index: usize, leaf_hash: H::Node) {
+        let mut index = index;
+        let mut node = leaf_hash;
+        for level in 0..NON_MEMBERSHIP_TREE_DEPTH {
+            let nodes = &mut self.levels[usize::from(level)];
+            if index == nodes.len() {
+                nodes.push(node);
+            } else {
+                nodes[index] = node;
+            }
+            let empty = self.empty_roots[usize::from(level)];
+            let left = nodes.get(index & !1).copied().unwrap_or(empty);
+            let right = nodes.get(index | 1).copied().unwrap_or(empty);
+            node = H::combine(Level::from(level), &left, &right);
+            index /= 2;
+        }
+        self.levels[usize::from(NON_MEMBERSHIP_TREE_DEPTH)][0] = node;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::PrimeField as _;
+    use pasta_curves::pallas;
+
+    use super::*;
+
+    fn sapling_nf(byte: u8) -> Nullifier {
+        Nullifier::from([byte; 32])
+    }
+
+    fn orchard_nf(value: u64) -> Nullifier {
+        Nullifier::from(pallas::Base::from(value).to_repr())
+    }
+
+    fn assert_inserts_match_bulk<H: IndexedTreeHasher>(nullifiers: &[Nullifier]) {
+        let bulk = IndexedMerkleTree::<H>::from_nullifiers(nullifiers).expect("bulk build");
+        let mut incremental = IndexedMerkleTree::<H>::new().expect("empty tree");
+        let mut sorted = nullifiers.to_vec();
+        sorted.sort_unstable_by_key(H::sort_key);
+        for nullifier in sorted {
+            incremental.insert(nullifier).expect("insert");
+        }
+        assert_eq!(incremental.root_bytes(), bulk.root_bytes());
+        assert_eq!(incremental.leaf_count(), nullifiers.len() + 1);
+    }
+
+    fn root_from_witness<H: IndexedTreeHasher>(
+        witness: &IndexedWitness,
+        node_from_bytes: impl Fn([u8; 32]) -> H::Node,
+    ) -> [u8; 32] {
+        let mut node = H::hash_leaf(&witness.leaf).expect("leaf hash");
+        for (level, sibling) in witness.path.iter().enumerate() {
+            let sibling = node_from_bytes(*sibling);
+            let level_u8 = u8::try_from(level).expect("level fits");
+            node = if (witness.leaf_position >> level) & 1 == 1 {
+                H::combine(Level::from(level_u8), &sibling, &node)
+            } else {
+                H::combine(Level::from(level_u8), &node, &sibling)
+            };
+        }
+        H::node_to_bytes(node)
+    }
+
+    #[test]
+    fn insertion_order_does_not_change_membership() {
+        let mut tree = SaplingIndexedTree::new().expect("empty tree");
+        for byte in [9_u8, 3, 7, 1] {
+            tree.insert(sapling_nf(byte)).expect("insert");
+        }
+        assert_eq!(
+            tree.insert(sapling_nf(7)),
+            Err(MerklePathError::NullifierPresent(sapling_nf(7)))
+        );
+        assert_eq!(
+            tree.low_leaf(sapling_nf(5)).expect("low leaf").1,
+            IndexedLeaf {
+                value: sapling_nf(3),
+                next_value: sapling_nf(7),
+                next_index: 3,
+            }
+        );
+        assert_eq!(
+            tree.low_leaf(sapling_nf(10))
+                .expect("low leaf")
+                .1
+                .next_value,
+            Nullifier::MAX
+        );
+        assert_eq!(
+            tree.low_leaf(Nullifier::MAX),
+            Err(MerklePathError::NullifierOutOfRange(Nullifier::MAX))
+        );
+    }
+
+    #[test]
+    fn sapling_inserts_match_bulk_build() {
+        assert_inserts_match_bulk::<SaplingIndexedHasher>(&[]);
+        assert_inserts_match_bulk::<SaplingIndexedHasher>(&[
+            sapling_nf(4),
+            sapling_nf(1),
+            sapling_nf(200),
+        ]);
+    }
+
+    #[test]
+    fn orchard_inserts_match_bulk_build() {
+        assert_inserts_match_bulk::<OrchardIndexedHasher>(&[
+            orchard_nf(5),
+            orchard_nf(1),
+            orchard_nf(1 << 40),
+        ]);
+    }
+
+    #[test]
+    fn orchard_rejects_non_canonical_nullifier() {
+        let mut tree = OrchardIndexedTree::new().expect("empty tree");
+        assert!(matches!(
+            tree.insert(Nullifier::MAX),
+            Err(MerklePathError::NonCanonicalOrchardNullifier { .. })
+        ));
+    }
+
+    #[test]
+    fn witnesses_open_to_root_after_updates() {
+        let mut tree = SaplingIndexedTree::from_nullifiers(&[sapling_nf(2), sapling_nf(8)])
+            .expect("bulk build");
+        tree.insert(sapling_nf(5)).expect("insert");
+
+        let witness = tree.non_membership_witness(sapling_nf(6)).expect("witness");
+        assert_eq!(witness.leaf.value, sapling_nf(5));
+        assert_eq!(
+            root_from_witness::<SaplingIndexedHasher>(&witness, crate::NonMembershipNode::from),
+            tree.root_bytes()
+        );
+
+        let mut orchard = OrchardIndexedTree::from_nullifiers(&[orchard_nf(10)]).expect("build");
+        orchard.insert(orchard_nf(20)).expect("insert");
+        let witness = orchard
+            .non_membership_witness(orchard_nf(15))
+            .expect("witness");
+        assert_eq!(witness.leaf_position, 1);
+        assert_eq!(
+            root_from_witness::<OrchardIndexedHasher>(&witness, |bytes| {
+                crate::OrchardPoseidonNode::from_bytes(bytes).expect("canonical node")
+            }),
+            orchard.root_bytes()
+        );
+    }
+}
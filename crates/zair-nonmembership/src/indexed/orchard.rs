@@ -0,0 +1,77 @@
+//! Orchard indexed-tree hashing (Poseidon).
+
+use halo2_gadgets::poseidon::primitives::{ConstantLength, Hash, P128Pow5T3};
+use incrementalmerkletree::{Hashable as _, Level};
+use pasta_curves::pallas;
+use zair_core::base::Nullifier;
+
+use super::{IndexedLeaf, IndexedTreeHasher};
+use crate::core::MerklePathError;
+use crate::pool::orchard::{orchard_base_from_nullifier, orchard_max_nullifier};
+use crate::sparse::OrchardPoseidonNode;
+
+/// Hash an Orchard indexed leaf with `Poseidon(value, next_value, next_index)`.
+///
+/// # Errors
+/// Returns an error if `value` or `next_value` is not a canonical `pallas::Base` encoding.
+pub fn orchard_indexed_leaf_hash(
+    leaf: &IndexedLeaf,
+) -> Result<OrchardPoseidonNode, MerklePathError> {
+    let value = orchard_base_from_nullifier(&leaf.value).ok_or(MerklePathError::Unexpected(
+        "invalid Orchard indexed leaf value encoding",
+    ))?;
+    let next_value = orchard_base_from_nullifier(&leaf.next_value).ok_or(
+        MerklePathError::Unexpected("invalid Orchard indexed leaf next value encoding"),
+    )?;
+    let next_index = pallas::Base::from(u64::from(leaf.next_index));
+    Ok(OrchardPoseidonNode::from_base(
+        Hash::<pallas::Base, P128Pow5T3, ConstantLength<3>, 3, 2>::init()
+            .hash([value, next_value, next_index]),
+    ))
+}
+
+/// Poseidon hashing over canonical Orchard nullifiers.
+///
+/// Values are ordered as field elements and bounded by `p - 1`.
+#[derive(Debug, Clone, Copy)]
+pub struct OrchardIndexedHasher;
+
+impl IndexedTreeHasher for OrchardIndexedHasher {
+    type Node = OrchardPoseidonNode;
+
+    fn max_value() -> Nullifier {
+        orchard_max_nullifier()
+    }
+
+    fn sort_key(nullifier: &Nullifier) -> [u8; 32] {
+        let mut key: [u8; 32] = *nullifier.as_ref();
+        key.reverse();
+        key
+    }
+
+    fn validate(
+        set: &'static str,
+        index: usize,
+        nullifier: Nullifier,
+    ) -> Result<Nullifier, MerklePathError> {
+        orchard_base_from_nullifier(&nullifier)
+            .map(|_| nullifier)
+            .ok_or(MerklePathError::NonCanonicalOrchardNullifier { set, index })
+    }
+
+    fn hash_leaf(leaf: &IndexedLeaf) -> Result<Self::Node, MerklePathError> {
+        orchard_indexed_leaf_hash(leaf)
+    }
+
+    fn combine(level: Level, lhs: &Self::Node, rhs: &Self::Node) -> Self::Node {
+        OrchardPoseidonNode::combine(level, lhs, rhs)
+    }
+
+    fn empty_root(level: Level) -> Self::Node {
+        OrchardPoseidonNode::empty_root(level)
+    }
+
+    fn node_to_bytes(node: Self::Node) -> [u8; 32] {
+        node.to_bytes()
+    }
+}
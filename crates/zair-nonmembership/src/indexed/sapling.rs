@@ -0,0 +1,74 @@
+//! Sapling indexed-tree hashing (Pedersen).
+
+use incrementalmerkletree::{Hashable as _, Level};
+use sapling::pedersen_hash::{Personalization, pedersen_hash};
+use zair_core::base::Nullifier;
+
+use super::{IndexedLeaf, IndexedTreeHasher};
+use crate::core::MerklePathError;
+use crate::node::{NonMembershipNode, bytes_to_bits_le};
+
+/// Pedersen level used for hashing indexed leaves.
+///
+/// Distinct from the gap-leaf level (62) and from internal node levels (0-31).
+pub const INDEXED_LEAF_HASH_LEVEL: u8 = 61;
+
+/// Hash a Sapling indexed leaf: `value || next_value || next_index` as little-endian bits.
+#[must_use]
+pub fn sapling_indexed_leaf_hash(leaf: &IndexedLeaf) -> NonMembershipNode {
+    let index_bits = (0..32).map(|bit| (leaf.next_index >> bit) & 1 == 1);
+    let bits = bytes_to_bits_le(leaf.value.as_ref())
+        .into_iter()
+        .chain(bytes_to_bits_le(leaf.next_value.as_ref()))
+        .chain(index_bits);
+
+    let subgroup_point = pedersen_hash(
+        Personalization::MerkleTree(usize::from(INDEXED_LEAF_HASH_LEVEL)),
+        bits,
+    );
+    NonMembershipNode::from(
+        jubjub::AffinePoint::from(jubjub::ExtendedPoint::from(subgroup_point))
+            .get_u()
+            .to_bytes(),
+    )
+}
+
+/// Pedersen hashing over raw 32-byte Sapling nullifiers.
+#[derive(Debug, Clone, Copy)]
+pub struct SaplingIndexedHasher;
+
+impl IndexedTreeHasher for SaplingIndexedHasher {
+    type Node = NonMembershipNode;
+
+    fn max_value() -> Nullifier {
+        Nullifier::MAX
+    }
+
+    fn sort_key(nullifier: &Nullifier) -> [u8; 32] {
+        *nullifier.as_ref()
+    }
+
+    fn validate(
+        _set: &'static str,
+        _index: usize,
+        nullifier: Nullifier,
+    ) -> Result<Nullifier, MerklePathError> {
+        Ok(nullifier)
+    }
+
+    fn hash_leaf(leaf: &IndexedLeaf) -> Result<Self::Node, MerklePathError> {
+        Ok(sapling_indexed_leaf_hash(leaf))
+    }
+
+    fn combine(level: Level, lhs: &Self::Node, rhs: &Self::Node) -> Self::Node {
+        NonMembershipNode::combine(level, lhs, rhs)
+    }
+
+    fn empty_root(level: Level) -> Self::Node {
+        NonMembershipNode::empty_root(level)
+    }
+
+    fn node_to_bytes(node: Self::Node) -> [u8; 32] {
+        node.to_bytes()
+    }
+}
@@ -2,6 +2,7 @@
 
 mod core;
 mod gap_tree;
+mod indexed;
 mod node;
 mod pool;
 mod sparse;
@@ -9,7 +10,17 @@ mod sparse;
 pub use core::{MerklePathError, TreePosition};
 
 pub use gap_tree::{
-    OrchardGapTree, SaplingGapTree, map_orchard_user_positions, map_sapling_user_positions,
+    DEFAULT_GAP_SHARD_DEPTH, GapLocator, GapShard, GapShardIndex, GapShards, OrchardGapTree,
+    OrchardPoseidonGapTree, SaplingGapTree, map_orchard_user_positions, map_sapling_user_positions,
+    nullifier_in_gap,
+};
+pub use indexed::{
+    INDEXED_LEAF_HASH_LEVEL, IndexedLeaf, IndexedMerkleTree, IndexedTreeHasher, IndexedWitness,
+    OrchardIndexedHasher, OrchardIndexedTree, SaplingIndexedHasher, SaplingIndexedTree,
+    orchard_indexed_leaf_hash, sapling_indexed_leaf_hash,
 };
 pub use node::{NON_MEMBERSHIP_TREE_DEPTH, NonMembershipNode};
-pub use sparse::{NonMembershipTree, OrchardNonMembershipTree};
+pub use sparse::{
+    NonMembershipTree, OrchardNonMembershipTree, OrchardPoseidonNode,
+    OrchardPoseidonNonMembershipTree, orchard_poseidon_hash,
+};
@@ -86,7 +86,7 @@ fn gap_leaf_hash_full(left_nf: &Nullifier, right_nf: &Nullifier) -> [u8; 32] {
 
 /// Convert a 32-byte array to 256 boolean bits in little-endian order.
 #[must_use]
-fn bytes_to_bits_le(bytes: &[u8; 32]) -> [bool; 256] {
+pub fn bytes_to_bits_le(bytes: &[u8; 32]) -> [bool; 256] {
     let mut bits = [false; 256];
     for (byte_index, byte) in bytes.iter().enumerate() {
         for bit_in_byte in 0..8 {
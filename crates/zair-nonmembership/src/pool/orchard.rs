@@ -65,6 +65,10 @@ pub fn orchard_node_from_bytes(bytes: [u8; 32]) -> Option<MerkleHashOrchard> {
     Option::<MerkleHashOrchard>::from(MerkleHashOrchard::from_bytes(&bytes))
 }
 
+pub fn orchard_base_from_nullifier(nullifier: &Nullifier) -> Option<pallas::Base> {
+    Option::<pallas::Base>::from(pallas::Base::from_repr(*nullifier.as_ref()))
+}
+
 pub fn canonicalize_orchard_chain_nullifiers(
     set: &'static str,
     nullifiers: &[Nullifier],
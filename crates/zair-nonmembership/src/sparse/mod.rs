@@ -1,7 +1,12 @@
 //! Sparse non-membership tree implementations based on `BridgeTree`.
 
 mod orchard;
+mod orchard_poseidon;
 mod sapling;
 
 pub use orchard::OrchardNonMembershipTree;
+pub use orchard_poseidon::{
+    OrchardPoseidonNode, OrchardPoseidonNonMembershipTree, orchard_poseidon_gap_leaf,
+    orchard_poseidon_hash,
+};
 pub use sapling::NonMembershipTree;
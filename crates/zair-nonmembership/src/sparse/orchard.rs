@@ -22,9 +22,9 @@ use zair_core::base::Nullifier;
 use crate::core::{MerklePathError, TreePosition, validate_leaf_count};
 use crate::node::NON_MEMBERSHIP_TREE_DEPTH;
 use crate::pool::orchard::{
-    CanonicalOrchardNullifier, ORCHARD_LEAF_HASH_LEVEL, canonicalize_orchard_chain_nullifiers,
-    canonicalize_orchard_user_nullifiers, orchard_cmp, orchard_gap_bounds, orchard_max_nullifier,
-    orchard_node_from_bytes,
+    CanonicalOrchardNullifier, ORCHARD_LEAF_HASH_LEVEL, OrchardGap,
+    canonicalize_orchard_chain_nullifiers, canonicalize_orchard_user_nullifiers, orchard_cmp,
+    orchard_gap_bounds, orchard_max_nullifier, orchard_node_from_bytes,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -159,58 +159,19 @@ impl OrchardNonMembershipTree {
     pub fn from_chain_and_user_nullifiers_with_progress(
         chain_nullifiers: &zair_core::base::SanitiseNullifiers,
         user_nullifiers: &zair_core::base::SanitiseNullifiers,
-        mut on_progress: impl FnMut(usize, usize),
+        on_progress: impl FnMut(usize, usize),
     ) -> Result<(Self, Vec<TreePosition>), MerklePathError> {
-        let chain = canonicalize_orchard_chain_nullifiers("chain", chain_nullifiers)?;
-        let user = canonicalize_orchard_user_nullifiers("user", user_nullifiers)?;
-        let min_node = orchard_node_from_bytes(*Nullifier::MIN.as_ref()).ok_or(
-            MerklePathError::Unexpected("invalid Orchard min nullifier encoding"),
-        )?;
-        let max_node = orchard_node_from_bytes(*orchard_max_nullifier().as_ref()).ok_or(
-            MerklePathError::Unexpected("invalid Orchard max nullifier encoding"),
+        let (tree, user_gap_mapping, num_gaps) = build_marked_gap_tree(
+            chain_nullifiers,
+            user_nullifiers,
+            |gap| {
+                Ok(OrchardNonMembershipNode::leaf_from_nodes(
+                    gap.left_node,
+                    gap.right_node,
+                ))
+            },
+            on_progress,
         )?;
-
-        let mut tree: BridgeTree<OrchardNonMembershipNode, (), { NON_MEMBERSHIP_TREE_DEPTH }> =
-            BridgeTree::new(1);
-        let mut user_gap_mapping = Vec::with_capacity(user.len());
-        let mut user_idx = 0usize;
-
-        let num_gaps = chain.len().saturating_add(1);
-        for gap_idx in 0..num_gaps {
-            let gap = orchard_gap_bounds(&chain, gap_idx, min_node, max_node)?;
-            let leaf = OrchardNonMembershipNode::leaf_from_nodes(gap.left_node, gap.right_node);
-            tree.append(leaf);
-
-            let mut should_mark = false;
-            while user_idx < user.len() {
-                let user_nf = user[user_idx];
-                if orchard_cmp(&user_nf, &gap.left_nf) != Ordering::Greater {
-                    user_idx = user_idx.saturating_add(1);
-                    continue;
-                }
-
-                if orchard_cmp(&user_nf, &gap.right_nf) != Ordering::Less {
-                    break;
-                }
-
-                should_mark = true;
-                user_gap_mapping.push(TreePosition::new(
-                    user_nf,
-                    gap_idx,
-                    gap.left_nf,
-                    gap.right_nf,
-                )?);
-                user_idx = user_idx.saturating_add(1);
-            }
-
-            if should_mark {
-                tree.mark();
-            }
-
-            on_progress(gap_idx.saturating_add(1), num_gaps);
-        }
-
-        tree.checkpoint(());
         let cached_root = tree.root(0).ok_or(MerklePathError::Unexpected(
             "Merkle root should exist at this point",
         ))?;
@@ -260,6 +221,73 @@ impl OrchardNonMembershipTree {
     }
 }
 
+/// Checkpointed gap tree, user gap mapping and number of appended leaves.
+type MarkedGapTree<H> = (
+    BridgeTree<H, (), { NON_MEMBERSHIP_TREE_DEPTH }>,
+    Vec<TreePosition>,
+    usize,
+);
+
+/// Append one leaf per Orchard gap and mark the gaps containing user nullifiers.
+///
+/// Shared by the Sinsemilla and Poseidon sparse trees, which differ only in the node hash.
+pub(super) fn build_marked_gap_tree<H: Hashable + Clone + Ord>(
+    chain_nullifiers: &zair_core::base::SanitiseNullifiers,
+    user_nullifiers: &zair_core::base::SanitiseNullifiers,
+    leaf: impl Fn(&OrchardGap) -> Result<H, MerklePathError>,
+    mut on_progress: impl FnMut(usize, usize),
+) -> Result<MarkedGapTree<H>, MerklePathError> {
+    let chain = canonicalize_orchard_chain_nullifiers("chain", chain_nullifiers)?;
+    let user = canonicalize_orchard_user_nullifiers("user", user_nullifiers)?;
+    let min_node = orchard_node_from_bytes(*Nullifier::MIN.as_ref()).ok_or(
+        MerklePathError::Unexpected("invalid Orchard min nullifier encoding"),
+    )?;
+    let max_node = orchard_node_from_bytes(*orchard_max_nullifier().as_ref()).ok_or(
+        MerklePathError::Unexpected("invalid Orchard max nullifier encoding"),
+    )?;
+
+    let mut tree: BridgeTree<H, (), { NON_MEMBERSHIP_TREE_DEPTH }> = BridgeTree::new(1);
+    let mut user_gap_mapping = Vec::with_capacity(user.len());
+    let mut user_idx = 0usize;
+
+    let num_gaps = chain.len().saturating_add(1);
+    for gap_idx in 0..num_gaps {
+        let gap = orchard_gap_bounds(&chain, gap_idx, min_node, max_node)?;
+        tree.append(leaf(&gap)?);
+
+        let mut should_mark = false;
+        while user_idx < user.len() {
+            let user_nf = user[user_idx];
+            if orchard_cmp(&user_nf, &gap.left_nf) != Ordering::Greater {
+                user_idx = user_idx.saturating_add(1);
+                continue;
+            }
+
+            if orchard_cmp(&user_nf, &gap.right_nf) != Ordering::Less {
+                break;
+            }
+
+            should_mark = true;
+            user_gap_mapping.push(TreePosition::new(
+                user_nf,
+                gap_idx,
+                gap.left_nf,
+                gap.right_nf,
+            )?);
+            user_idx = user_idx.saturating_add(1);
+        }
+
+        if should_mark {
+            tree.mark();
+        }
+
+        on_progress(gap_idx.saturating_add(1), num_gaps);
+    }
+
+    tree.checkpoint(());
+    Ok((tree, user_gap_mapping, num_gaps))
+}
+
 #[allow(
     dead_code,
     reason = "Used by test-only leaf-construction path retained for unit tests"
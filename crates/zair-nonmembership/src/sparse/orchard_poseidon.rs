@@ -0,0 +1,224 @@
+//! Orchard non-membership Merkle tree (Poseidon) utilities.
+//!
+//! This is an alternative to the Sinsemilla gap tree that is cheaper to verify in-circuit:
+//! - nullifiers are parsed as canonical `pallas::Base` encodings (same as the Sinsemilla tree),
+//! - gap leaves are `Poseidon(left, right)`,
+//! - internal nodes are `Poseidon(lhs, rhs)` and unused leaves are zero.
+//!
+//! Poseidon is `P128Pow5T3` over Pallas with `ConstantLength<2>`, matching the `Pow5Chip`
+//! configured by the Orchard airdrop circuit. Leaves and internal nodes share the same hash;
+//! the fixed tree depth enforced by the circuit keeps the two from being interchangeable.
+
+use std::collections::BTreeSet;
+
+use bridgetree::BridgeTree;
+use ff::{Field as _, PrimeField as _};
+use halo2_gadgets::poseidon::primitives::{ConstantLength, Hash, P128Pow5T3};
+use incrementalmerkletree::{Hashable, Level, Position};
+use pasta_curves::pallas;
+
+use super::orchard::build_marked_gap_tree;
+use crate::core::{MerklePathError, TreePosition};
+use crate::node::NON_MEMBERSHIP_TREE_DEPTH;
+use crate::pool::orchard::{OrchardGap, orchard_base_from_nullifier};
+
+/// Hash two Pallas base field elements with `Poseidon(P128Pow5T3, ConstantLength<2>)`.
+#[must_use]
+pub fn orchard_poseidon_hash(lhs: pallas::Base, rhs: pallas::Base) -> pallas::Base {
+    Hash::<pallas::Base, P128Pow5T3, ConstantLength<2>, 3, 2>::init().hash([lhs, rhs])
+}
+
+/// Compute the Poseidon leaf for an Orchard gap.
+///
+/// # Errors
+/// Returns an error if a gap bound is not a canonical `pallas::Base` encoding.
+pub fn orchard_poseidon_gap_leaf(gap: &OrchardGap) -> Result<OrchardPoseidonNode, MerklePathError> {
+    let left = orchard_base_from_nullifier(&gap.left_nf).ok_or(MerklePathError::Unexpected(
+        "invalid Orchard left gap bound encoding",
+    ))?;
+    let right = orchard_base_from_nullifier(&gap.right_nf).ok_or(MerklePathError::Unexpected(
+        "invalid Orchard right gap bound encoding",
+    ))?;
+    Ok(OrchardPoseidonNode::leaf_from_bounds(left, right))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A non-membership tree node for the Poseidon Orchard gap tree.
+pub struct OrchardPoseidonNode(pallas::Base);
+
+impl OrchardPoseidonNode {
+    /// Wrap a Pallas base field element.
+    #[must_use]
+    pub const fn from_base(value: pallas::Base) -> Self {
+        Self(value)
+    }
+
+    /// Parse a node from its canonical `pallas::Base` encoding.
+    #[must_use]
+    pub fn from_bytes(bytes: [u8; 32]) -> Option<Self> {
+        Option::<pallas::Base>::from(pallas::Base::from_repr(bytes)).map(Self)
+    }
+
+    /// Convert this node into canonical bytes.
+    #[must_use]
+    pub fn to_bytes(self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+
+    /// Build a gap leaf from its canonical left and right bounds.
+    #[must_use]
+    pub fn leaf_from_bounds(left: pallas::Base, right: pallas::Base) -> Self {
+        Self(orchard_poseidon_hash(left, right))
+    }
+}
+
+impl PartialOrd for OrchardPoseidonNode {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrchardPoseidonNode {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.to_bytes().cmp(&other.to_bytes())
+    }
+}
+
+impl Hashable for OrchardPoseidonNode {
+    fn empty_leaf() -> Self {
+        Self(pallas::Base::ZERO)
+    }
+
+    fn combine(_level: Level, lhs: &Self, rhs: &Self) -> Self {
+        Self(orchard_poseidon_hash(lhs.0, rhs.0))
+    }
+}
+
+/// A space-efficient Poseidon Orchard non-membership tree for nullifier gaps.
+#[derive(Debug, Clone)]
+pub struct OrchardPoseidonNonMembershipTree {
+    inner: BridgeTree<OrchardPoseidonNode, (), { NON_MEMBERSHIP_TREE_DEPTH }>,
+    cached_root: OrchardPoseidonNode,
+    leaf_count: usize,
+}
+
+impl OrchardPoseidonNonMembershipTree {
+    /// Build a Poseidon Orchard non-membership tree from nullifiers (no positions marked).
+    ///
+    /// # Errors
+    /// Returns an error if any nullifier is not canonical Orchard encoding.
+    pub fn from_nullifiers(
+        nullifiers: &zair_core::base::SanitiseNullifiers,
+    ) -> Result<Self, MerklePathError> {
+        let empty_user = zair_core::base::SanitiseNullifiers::new(vec![]);
+        let (tree, _mapping) =
+            Self::from_chain_and_user_nullifiers_with_progress(nullifiers, &empty_user, |_, _| {})?;
+        Ok(tree)
+    }
+
+    /// Build a Poseidon Orchard non-membership tree and mark user gap positions.
+    ///
+    /// # Errors
+    /// Returns an error if any chain/user nullifier is not canonical Orchard encoding.
+    pub fn from_chain_and_user_nullifiers(
+        chain_nullifiers: &zair_core::base::SanitiseNullifiers,
+        user_nullifiers: &zair_core::base::SanitiseNullifiers,
+    ) -> Result<(Self, Vec<TreePosition>), MerklePathError> {
+        Self::from_chain_and_user_nullifiers_with_progress(
+            chain_nullifiers,
+            user_nullifiers,
+            |_, _| {},
+        )
+    }
+
+    /// Build a Poseidon Orchard non-membership tree and mark user gap positions,
+    /// calling `on_progress(current, total)` after each leaf is appended.
+    ///
+    /// # Errors
+    /// Returns an error if any chain/user nullifier is not canonical Orchard encoding.
+    pub fn from_chain_and_user_nullifiers_with_progress(
+        chain_nullifiers: &zair_core::base::SanitiseNullifiers,
+        user_nullifiers: &zair_core::base::SanitiseNullifiers,
+        on_progress: impl FnMut(usize, usize),
+    ) -> Result<(Self, Vec<TreePosition>), MerklePathError> {
+        let (tree, user_gap_mapping, num_gaps) = build_marked_gap_tree(
+            chain_nullifiers,
+            user_nullifiers,
+            orchard_poseidon_gap_leaf,
+            on_progress,
+        )?;
+        let cached_root = tree.root(0).ok_or(MerklePathError::Unexpected(
+            "Merkle root should exist at this point",
+        ))?;
+
+        Ok((
+            Self {
+                inner: tree,
+                cached_root,
+                leaf_count: num_gaps,
+            },
+            user_gap_mapping,
+        ))
+    }
+
+    /// Return root bytes as canonical `pallas::Base`.
+    #[must_use]
+    pub fn root_bytes(&self) -> [u8; 32] {
+        self.cached_root.to_bytes()
+    }
+
+    /// Return number of leaves in this tree.
+    #[must_use]
+    pub const fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    /// Returns the set of positions marked for witnessing.
+    #[must_use]
+    pub fn marked_positions(&self) -> BTreeSet<Position> {
+        self.inner.marked_positions()
+    }
+
+    /// Produce a witness as canonical node bytes.
+    ///
+    /// # Errors
+    /// Returns an error if witness generation fails.
+    pub fn witness_bytes(&self, position: Position) -> Result<Vec<[u8; 32]>, MerklePathError> {
+        self.inner
+            .witness(position, 0)
+            .map(|path| {
+                path.iter()
+                    .copied()
+                    .map(OrchardPoseidonNode::to_bytes)
+                    .collect()
+            })
+            .map_err(|e| MerklePathError::WitnessError(format!("{e:?}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_roots_chain_poseidon_hashes() {
+        let level_one = OrchardPoseidonNode::empty_root(Level::from(1));
+        assert_eq!(
+            level_one,
+            OrchardPoseidonNode::from_base(orchard_poseidon_hash(
+                pallas::Base::ZERO,
+                pallas::Base::ZERO
+            ))
+        );
+        assert_ne!(level_one, OrchardPoseidonNode::empty_leaf());
+    }
+
+    #[test]
+    fn node_bytes_roundtrip() {
+        let node = OrchardPoseidonNode::leaf_from_bounds(
+            pallas::Base::from(3_u64),
+            pallas::Base::from(9_u64),
+        );
+        assert_eq!(OrchardPoseidonNode::from_bytes(node.to_bytes()), Some(node));
+    }
+}
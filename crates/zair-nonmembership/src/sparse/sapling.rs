@@ -446,6 +446,10 @@ impl Iterator for NullifierLeafIterator<'_> {
 impl ExactSizeIterator for NullifierLeafIterator<'_> {}
 
 #[cfg(test)]
+#[allow(
+    clippy::assert_is_empty,
+    reason = "mappings are compared with `is_empty`"
+)]
 mod tests {
     use super::*;
 
@@ -4,7 +4,8 @@ use ff::PrimeField as _;
 use pasta_curves::pallas;
 use zair_core::base::{Nullifier, SanitiseNullifiers};
 use zair_nonmembership::{
-    NonMembershipNode, NonMembershipTree, OrchardGapTree, OrchardNonMembershipTree, SaplingGapTree,
+    NonMembershipNode, NonMembershipTree, OrchardGapTree, OrchardNonMembershipTree,
+    OrchardPoseidonGapTree, OrchardPoseidonNonMembershipTree, SaplingGapTree,
     map_orchard_user_positions, map_sapling_user_positions,
 };
 
@@ -89,3 +90,37 @@ fn orchard_dense_matches_sparse() {
         assert_eq!(dense_witness, sparse_witness);
     }
 }
+
+#[test]
+fn orchard_poseidon_dense_matches_sparse() {
+    let chain = SanitiseNullifiers::new(vec![orchard_nf(5), orchard_nf(10), orchard_nf(20)]);
+    let user = SanitiseNullifiers::new(vec![
+        orchard_nf(1),
+        orchard_nf(7),
+        orchard_nf(15),
+        orchard_nf(20),
+        orchard_nf(21),
+    ]);
+
+    let dense_tree = OrchardPoseidonGapTree::from_nullifiers_with_progress(&chain, |_, _| {})
+        .expect("dense poseidon tree should build");
+    let dense_positions = map_orchard_user_positions(&chain, &user)
+        .expect("dense orchard position mapping should build");
+
+    let (sparse_tree, sparse_positions) =
+        OrchardPoseidonNonMembershipTree::from_chain_and_user_nullifiers(&chain, &user)
+            .expect("sparse poseidon tree should build");
+
+    assert_eq!(dense_tree.root_bytes(), sparse_tree.root_bytes());
+    assert_eq!(dense_positions, sparse_positions);
+
+    for position in &dense_positions {
+        let dense_witness = dense_tree
+            .witness_bytes(position.leaf_position.into())
+            .expect("dense poseidon witness should build");
+        let sparse_witness = sparse_tree
+            .witness_bytes(position.leaf_position)
+            .expect("sparse poseidon witness should build");
+        assert_eq!(dense_witness, sparse_witness);
+    }
+}
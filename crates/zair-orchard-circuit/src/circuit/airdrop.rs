@@ -5,7 +5,8 @@
 //!   consistent with the recipient.
 //! - It keeps the **standard Orchard nullifier** private.
 //! - It exposes an **airdrop nullifier** derived with a domain-separated base point.
-//! - It verifies a non-membership ("gap tree") Merkle path over spent nullifiers.
+//! - It verifies a non-membership ("gap tree") Merkle path over spent nullifiers, hashed with
+//!   either Sinsemilla (as in the note commitment tree) or Poseidon.
 //! - It exposes a value commitment `cv` for binding to a target chain.
 
 use std::vec::Vec;
@@ -23,6 +24,7 @@ use halo2_gadgets::sinsemilla::HashDomains;
 use halo2_gadgets::sinsemilla::chip::{SinsemillaChip, SinsemillaConfig};
 use halo2_gadgets::sinsemilla::merkle::chip::{MerkleChip, MerkleConfig};
 use halo2_gadgets::sinsemilla::merkle::{MerkleInstructions, MerklePath};
+use halo2_gadgets::utilities::cond_swap::{CondSwapChip, CondSwapConfig, CondSwapInstructions};
 use halo2_gadgets::utilities::lookup_range_check::LookupRangeCheckConfig;
 use halo2_proofs::circuit::{Chip as _, Layouter, Value, floor_planner};
 use halo2_proofs::plonk::{
@@ -93,6 +95,16 @@ impl ValueCommitmentScheme {
     }
 }
 
+/// Hash function used for the nullifier gap tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GapTreeHash {
+    /// Sinsemilla `MerkleCRH^Orchard`; the gap leaf is hashed at level 62.
+    #[default]
+    Sinsemilla,
+    /// Poseidon (`P128Pow5T3`, `ConstantLength<2>`) for both gap leaves and internal nodes.
+    Poseidon,
+}
+
 type OrchardMerkleConfig =
     MerkleConfig<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases>;
 type OrchardSinsemillaConfig =
@@ -101,15 +113,16 @@ type OrchardSinsemillaConfig =
 /// Configuration for the Orchard airdrop circuit.
 #[derive(Clone, Debug)]
 pub struct Config {
-    primary: Column<InstanceColumn>,
-    advices: [Column<Advice>; 10],
+    pub(super) primary: Column<InstanceColumn>,
+    pub(super) advices: [Column<Advice>; 10],
     add_config: AddConfig,
     sha256_config: sha256::Table16Config,
     ecc_config: EccConfig<OrchardFixedBases>,
     poseidon_config: PoseidonConfig<pallas::Base, 3, 2>,
+    cond_swap_config: CondSwapConfig,
     merkle_config_1: OrchardMerkleConfig,
     merkle_config_2: OrchardMerkleConfig,
-    sinsemilla_config_1: OrchardSinsemillaConfig,
+    pub(super) sinsemilla_config_1: OrchardSinsemillaConfig,
     sinsemilla_config_2: OrchardSinsemillaConfig,
     commit_ivk_config: CommitIvkConfig,
     note_commit_config: NoteCommitConfig,
@@ -130,10 +143,14 @@ impl Config {
         EccChip::construct(self.ecc_config.clone())
     }
 
-    fn poseidon_chip(&self) -> PoseidonChip<pallas::Base, 3, 2> {
+    pub(super) fn poseidon_chip(&self) -> PoseidonChip<pallas::Base, 3, 2> {
         PoseidonChip::construct(self.poseidon_config.clone())
     }
 
+    fn cond_swap_chip(&self) -> CondSwapChip<pallas::Base> {
+        CondSwapChip::construct(self.cond_swap_config.clone())
+    }
+
     fn sinsemilla_chip_1(
         &self,
     ) -> SinsemillaChip<OrchardHashDomains, OrchardCommitDomains, OrchardFixedBases> {
@@ -226,6 +243,8 @@ pub struct Circuit {
     pub gap_path: Value<[pallas::Base; MERKLE_DEPTH_ORCHARD]>,
     /// Gap leaf position within the gap tree.
     pub gap_pos: Value<u32>,
+    /// Hash function used by the gap tree.
+    pub gap_tree_hash: GapTreeHash,
 }
 
 /// Public inputs to the Orchard airdrop circuit.
@@ -268,9 +287,9 @@ impl Instance {
             }
             ValueCommitmentScheme::Sha256 => {
                 let digest = self.cv_sha256.expect("sha256 scheme requires digest");
-                for (i, word) in digest.chunks_exact(4).enumerate() {
-                    let w: [u8; 4] = word.try_into().expect("chunk length");
-                    instance[DIGEST_0_SHA + i] = vesta::Scalar::from(u32::from_be_bytes(w) as u64);
+                for (i, word) in digest.as_chunks::<4>().0.iter().enumerate() {
+                    instance[DIGEST_0_SHA + i] =
+                        vesta::Scalar::from(u32::from_be_bytes(*word) as u64);
                 }
                 instance[NOTE_ANCHOR_SHA] = self.note_anchor;
                 instance[GAP_ROOT_SHA] = self.gap_root;
@@ -357,6 +376,7 @@ impl plonk::Circuit<pallas::Base> for Circuit {
     fn without_witnesses(&self) -> Self {
         Self {
             value_commitment_scheme: self.value_commitment_scheme,
+            gap_tree_hash: self.gap_tree_hash,
             ..Self::default()
         }
     }
@@ -421,6 +441,9 @@ impl plonk::Circuit<pallas::Base> for Circuit {
             rc_b,
         );
 
+        // Conditional swap used to order Poseidon gap-path siblings.
+        let cond_swap_config = CondSwapChip::configure(meta, advices[..5].try_into().unwrap());
+
         let (sinsemilla_config_1, merkle_config_1) = {
             let sinsemilla_config_1 = SinsemillaChip::configure(
                 meta,
@@ -583,6 +606,7 @@ impl plonk::Circuit<pallas::Base> for Circuit {
             sha256_config,
             ecc_config,
             poseidon_config,
+            cond_swap_config,
             merkle_config_1,
             merkle_config_2,
             sinsemilla_config_1,
@@ -936,103 +960,162 @@ impl plonk::Circuit<pallas::Base> for Circuit {
             (left, right)
         };
 
-        // Canonical decomposition for comparisons (left, nf_old, right).
-        let (left_msb, left_mid, left_low) =
-            decompose_for_compare(&config, &mut layouter, left_cell.clone())?;
-        let (nf_msb, nf_mid, nf_low) =
-            decompose_for_compare(&config, &mut layouter, nf_old.inner().clone())?;
-        let (right_msb, right_mid, right_low) =
-            decompose_for_compare(&config, &mut layouter, right_cell.clone())?;
-
-        // Comparators: low and mid.
-        let left_mid_lt_nf_mid = lt_nbits(
-            &config,
-            &mut layouter,
-            left_mid.clone(),
-            nf_mid.clone(),
-            126,
-        )?;
-        let left_mid_gt_nf_mid = lt_nbits(
-            &config,
-            &mut layouter,
-            nf_mid.clone(),
-            left_mid.clone(),
-            126,
-        )?;
-        let left_low_lt_nf_low = lt_nbits(
-            &config,
-            &mut layouter,
-            left_low.clone(),
-            nf_low.clone(),
-            128,
-        )?;
-
-        assert_order_255(
-            &config,
-            &mut layouter,
-            left_msb,
-            nf_msb.clone(),
-            left_mid_lt_nf_mid,
-            left_mid_gt_nf_mid,
-            left_low_lt_nf_low,
-            "left < nf_old",
-        )?;
-
-        let nf_mid_lt_right_mid = lt_nbits(
-            &config,
-            &mut layouter,
-            nf_mid.clone(),
-            right_mid.clone(),
-            126,
-        )?;
-        let nf_mid_gt_right_mid = lt_nbits(
-            &config,
-            &mut layouter,
-            right_mid.clone(),
-            nf_mid.clone(),
-            126,
-        )?;
-        let nf_low_lt_right_low = lt_nbits(&config, &mut layouter, nf_low, right_low.clone(), 128)?;
-
-        assert_order_255(
+        enforce_strictly_between(
             &config,
             &mut layouter,
-            nf_msb,
-            right_msb,
-            nf_mid_lt_right_mid,
-            nf_mid_gt_right_mid,
-            nf_low_lt_right_low,
-            "nf_old < right",
-        )?;
-
-        // === Gap tree membership (Sinsemilla-only in this milestone) ===
-        let q = OrchardHashDomains::MerkleCrh.Q();
-        let leaf = <MerkleChip<
-            OrchardHashDomains,
-            OrchardCommitDomains,
-            OrchardFixedBases,
-        > as MerkleInstructions<pallas::Affine, MERKLE_DEPTH_ORCHARD, 10, 253>>::hash_layer(
-            &config.merkle_chip_1(),
-            layouter.namespace(|| "gap leaf"),
-            q,
-            62,
             left_cell.clone(),
+            nf_old.inner().clone(),
             right_cell.clone(),
         )?;
 
-        let merkle_inputs = MerklePath::construct(
-            [config.merkle_chip_1(), config.merkle_chip_2()],
-            OrchardHashDomains::MerkleCrh,
-            self.gap_pos,
-            self.gap_path,
-        );
-        let root = merkle_inputs.calculate_root(layouter.namespace(|| "gap Merkle path"), leaf)?;
+        // === Gap tree membership ===
+        let root = match self.gap_tree_hash {
+            GapTreeHash::Sinsemilla => {
+                let q = OrchardHashDomains::MerkleCrh.Q();
+                let leaf = <MerkleChip<
+                    OrchardHashDomains,
+                    OrchardCommitDomains,
+                    OrchardFixedBases,
+                > as MerkleInstructions<pallas::Affine, MERKLE_DEPTH_ORCHARD, 10, 253>>::hash_layer(
+                    &config.merkle_chip_1(),
+                    layouter.namespace(|| "gap leaf"),
+                    q,
+                    62,
+                    left_cell.clone(),
+                    right_cell.clone(),
+                )?;
+
+                let merkle_inputs = MerklePath::construct(
+                    [config.merkle_chip_1(), config.merkle_chip_2()],
+                    OrchardHashDomains::MerkleCrh,
+                    self.gap_pos,
+                    self.gap_path,
+                );
+                merkle_inputs.calculate_root(layouter.namespace(|| "gap Merkle path"), leaf)?
+            }
+            GapTreeHash::Poseidon => poseidon_gap_root(
+                &config,
+                &mut layouter,
+                left_cell,
+                right_cell,
+                self.gap_pos,
+                self.gap_path,
+            )?,
+        };
         layouter.constrain_instance(root.cell(), config.primary, gap_root_idx)?;
 
         Ok(())
     }
 }
 
+/// Enforce `left < x < right` over canonical 255-bit decompositions of the three cells.
+pub(super) fn enforce_strictly_between(
+    config: &Config,
+    layouter: &mut impl Layouter<pallas::Base>,
+    left: halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>,
+    x: halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>,
+    right: halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>,
+) -> Result<(), plonk::Error> {
+    // Canonical decomposition for comparisons (left, x, right).
+    let (left_msb, left_mid, left_low) = decompose_for_compare(config, layouter, left)?;
+    let (x_msb, x_mid, x_low) = decompose_for_compare(config, layouter, x)?;
+    let (right_msb, right_mid, right_low) = decompose_for_compare(config, layouter, right)?;
+
+    // Comparators: low and mid.
+    let left_mid_lt_x_mid = lt_nbits(config, layouter, left_mid.clone(), x_mid.clone(), 126)?;
+    let left_mid_gt_x_mid = lt_nbits(config, layouter, x_mid.clone(), left_mid.clone(), 126)?;
+    let left_low_lt_x_low = lt_nbits(config, layouter, left_low.clone(), x_low.clone(), 128)?;
+
+    assert_order_255(
+        config,
+        layouter,
+        left_msb,
+        x_msb.clone(),
+        left_mid_lt_x_mid,
+        left_mid_gt_x_mid,
+        left_low_lt_x_low,
+        "left < x",
+    )?;
+
+    let x_mid_lt_right_mid = lt_nbits(config, layouter, x_mid.clone(), right_mid.clone(), 126)?;
+    let x_mid_gt_right_mid = lt_nbits(config, layouter, right_mid.clone(), x_mid.clone(), 126)?;
+    let x_low_lt_right_low = lt_nbits(config, layouter, x_low, right_low.clone(), 128)?;
+
+    assert_order_255(
+        config,
+        layouter,
+        x_msb,
+        right_msb,
+        x_mid_lt_right_mid,
+        x_mid_gt_right_mid,
+        x_low_lt_right_low,
+        "x < right",
+    )?;
+
+    Ok(())
+}
+
+fn poseidon_hash_pair(
+    config: &Config,
+    layouter: &mut impl Layouter<pallas::Base>,
+    lhs: halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>,
+    rhs: halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>,
+) -> Result<halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+    let hasher = PoseidonHash::<
+        pallas::Base,
+        PoseidonChip<pallas::Base, 3, 2>,
+        poseidon::P128Pow5T3,
+        poseidon::ConstantLength<2>,
+        3,
+        2,
+    >::init(
+        config.poseidon_chip(),
+        layouter.namespace(|| "Poseidon init"),
+    )?;
+    hasher.hash(layouter.namespace(|| "Poseidon hash"), [lhs, rhs])
+}
+
+/// Compute the Poseidon gap-tree root for the gap leaf `Poseidon(left, right)`.
+fn poseidon_gap_root(
+    config: &Config,
+    layouter: &mut impl Layouter<pallas::Base>,
+    left: halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>,
+    right: halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>,
+    pos: Value<u32>,
+    path: Value<[pallas::Base; MERKLE_DEPTH_ORCHARD]>,
+) -> Result<halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+    let leaf = poseidon_hash_pair(config, layouter, left, right)?;
+    poseidon_merkle_root(config, layouter, leaf, pos, path)
+}
+
+/// Compute a Poseidon Merkle root from a leaf and its authentication path.
+///
+/// Each layer conditionally swaps the running node with its witnessed sibling according to the
+/// corresponding bit of `pos`, then hashes the ordered pair.
+pub(super) fn poseidon_merkle_root(
+    config: &Config,
+    layouter: &mut impl Layouter<pallas::Base>,
+    leaf: halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>,
+    pos: Value<u32>,
+    path: Value<[pallas::Base; MERKLE_DEPTH_ORCHARD]>,
+) -> Result<halo2_proofs::circuit::AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+    let mut node = leaf;
+    let cond_swap_chip = config.cond_swap_chip();
+
+    for layer in 0..MERKLE_DEPTH_ORCHARD {
+        let sibling = path.map(|path| path[layer]);
+        let swap = pos.map(|pos| (pos >> layer) & 1 == 1);
+        let (lhs, rhs) = cond_swap_chip.swap(
+            layouter.namespace(|| format!("layer {layer} swap")),
+            (node, sibling),
+            swap,
+        )?;
+        node = poseidon_hash_pair(config, layouter, lhs, rhs)?;
+    }
+
+    Ok(node)
+}
+
 fn decompose_for_compare(
     config: &Config,
     layouter: &mut impl Layouter<pallas::Base>,
@@ -1240,6 +1323,112 @@ impl Circuit {
             right: Value::known(right),
             gap_path: Value::known(gap_path),
             gap_pos: Value::known(gap_pos),
+            gap_tree_hash: GapTreeHash::Sinsemilla,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use zair_core::base::{Nullifier, SanitiseNullifiers};
+    use zair_nonmembership::OrchardPoseidonGapTree;
+
+    use super::*;
+
+    /// Runs only the Poseidon gap-tree path of the airdrop circuit.
+    #[derive(Clone, Default)]
+    struct GapPathCircuit {
+        left: Value<pallas::Base>,
+        right: Value<pallas::Base>,
+        pos: Value<u32>,
+        path: Value<[pallas::Base; MERKLE_DEPTH_ORCHARD]>,
+    }
+
+    impl plonk::Circuit<pallas::Base> for GapPathCircuit {
+        type Config = Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            <Circuit as plonk::Circuit<pallas::Base>>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), plonk::Error> {
+            SinsemillaChip::load(config.sinsemilla_config_1.clone(), &mut layouter)?;
+            let left =
+                assign_free_advice(layouter.namespace(|| "left"), config.advices[0], self.left)?;
+            let right = assign_free_advice(
+                layouter.namespace(|| "right"),
+                config.advices[0],
+                self.right,
+            )?;
+            let root = poseidon_gap_root(&config, &mut layouter, left, right, self.pos, self.path)?;
+            layouter.constrain_instance(root.cell(), config.primary, 0)
+        }
+    }
+
+    fn base(bytes: [u8; 32]) -> pallas::Base {
+        pallas::Base::from_repr(bytes).unwrap()
+    }
+
+    fn run(tree_root: [u8; 32], circuit: &GapPathCircuit) -> Result<(), Vec<String>> {
+        let prover = MockProver::run(K_AIRDROP_NATIVE, circuit, vec![vec![base(tree_root)]])
+            .expect("synthesize gap path");
+        prover
+            .verify()
+            .map_err(|failures| failures.iter().map(ToString::to_string).collect())
+    }
+
+    fn gap_path_circuit() -> (OrchardPoseidonGapTree, GapPathCircuit) {
+        let chain = [[0x20; 32], [0x30; 32], [0x38; 32]];
+        let nullifiers = SanitiseNullifiers::new(chain.map(Nullifier::from).to_vec());
+        let tree =
+            OrchardPoseidonGapTree::from_nullifiers_with_progress(&nullifiers, |_, _| {}).unwrap();
+
+        // Gap 2 lies between the second and third chain nullifiers.
+        let path: Vec<_> = tree
+            .witness_bytes(2)
+            .unwrap()
+            .into_iter()
+            .map(base)
+            .collect();
+        let circuit = GapPathCircuit {
+            left: Value::known(base(chain[1])),
+            right: Value::known(base(chain[2])),
+            pos: Value::known(2),
+            path: Value::known(path.try_into().unwrap()),
+        };
+        (tree, circuit)
+    }
+
+    #[test]
+    fn poseidon_gap_root_matches_native_tree() {
+        let (tree, circuit) = gap_path_circuit();
+        assert_eq!(run(tree.root_bytes(), &circuit), Ok(()));
+    }
+
+    #[test]
+    fn poseidon_gap_root_rejects_other_gaps() {
+        let (tree, circuit) = gap_path_circuit();
+        let shifted = GapPathCircuit {
+            pos: Value::known(1),
+            ..circuit.clone()
+        };
+        assert!(run(tree.root_bytes(), &shifted).is_err());
+
+        let widened = GapPathCircuit {
+            right: Value::known(base([0x39; 32])),
+            ..circuit
+        };
+        assert!(run(tree.root_bytes(), &widened).is_err());
+    }
+}
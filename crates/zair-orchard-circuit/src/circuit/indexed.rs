@@ -0,0 +1,180 @@
+//! Indexed Merkle tree non-membership gadget (Poseidon).
+//!
+//! Verifies that a nullifier is absent from an indexed (linked-leaf) tree by opening its low
+//! leaf `(value, next_value, next_index)` and enforcing `value < nf < next_value`. The leaf is
+//! `Poseidon(value, next_value, next_index)` with `ConstantLength<3>`; internal nodes are
+//! `Poseidon(lhs, rhs)` as in the Poseidon gap tree.
+//!
+//! The airdrop circuit does not use this gadget yet: claims still prove non-membership against
+//! the gap tree, and no configuration publishes an indexed-tree root.
+
+use halo2_gadgets::poseidon::{
+    Hash as PoseidonHash, Pow5Chip as PoseidonChip, primitives as poseidon,
+};
+use halo2_proofs::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::plonk;
+use pasta_curves::pallas;
+
+use super::airdrop::{Config, enforce_strictly_between, poseidon_merkle_root};
+use super::gadget::assign_free_advice;
+use crate::constants::MERKLE_DEPTH_ORCHARD;
+
+/// Enforce that `nf` lies strictly inside the low leaf's range and return the computed root.
+///
+/// The caller is responsible for constraining the returned root to the expected anchor.
+///
+/// # Errors
+/// Returns an error if region assignment or synthesis fails.
+pub fn indexed_non_membership_root(
+    config: &Config,
+    layouter: &mut impl Layouter<pallas::Base>,
+    nf: AssignedCell<pallas::Base, pallas::Base>,
+    value: Value<pallas::Base>,
+    next_value: Value<pallas::Base>,
+    next_index: Value<u32>,
+    pos: Value<u32>,
+    path: Value<[pallas::Base; MERKLE_DEPTH_ORCHARD]>,
+) -> Result<AssignedCell<pallas::Base, pallas::Base>, plonk::Error> {
+    let value = assign_free_advice(
+        layouter.namespace(|| "low leaf value"),
+        config.advices[0],
+        value,
+    )?;
+    let next_value = assign_free_advice(
+        layouter.namespace(|| "low leaf next value"),
+        config.advices[0],
+        next_value,
+    )?;
+    let next_index = assign_free_advice(
+        layouter.namespace(|| "low leaf next index"),
+        config.advices[0],
+        next_index.map(|index| pallas::Base::from(u64::from(index))),
+    )?;
+
+    enforce_strictly_between(config, layouter, value.clone(), nf, next_value.clone())?;
+
+    let hasher = PoseidonHash::<
+        pallas::Base,
+        PoseidonChip<pallas::Base, 3, 2>,
+        poseidon::P128Pow5T3,
+        poseidon::ConstantLength<3>,
+        3,
+        2,
+    >::init(
+        config.poseidon_chip(),
+        layouter.namespace(|| "Poseidon init (indexed leaf)"),
+    )?;
+    let leaf = hasher.hash(
+        layouter.namespace(|| "Poseidon hash (indexed leaf)"),
+        [value, next_value, next_index],
+    )?;
+
+    poseidon_merkle_root(config, layouter, leaf, pos, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::PrimeField as _;
+    use halo2_gadgets::sinsemilla::chip::SinsemillaChip;
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::plonk::ConstraintSystem;
+    use zair_core::base::Nullifier;
+    use zair_nonmembership::{IndexedWitness, OrchardIndexedTree};
+
+    use super::*;
+    use crate::circuit::airdrop::{Circuit, K_AIRDROP_NATIVE};
+
+    #[derive(Clone, Default)]
+    struct IndexedCircuit {
+        nf: Value<pallas::Base>,
+        value: Value<pallas::Base>,
+        next_value: Value<pallas::Base>,
+        next_index: Value<u32>,
+        pos: Value<u32>,
+        path: Value<[pallas::Base; MERKLE_DEPTH_ORCHARD]>,
+    }
+
+    impl plonk::Circuit<pallas::Base> for IndexedCircuit {
+        type Config = Config;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<pallas::Base>) -> Self::Config {
+            <Circuit as plonk::Circuit<pallas::Base>>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<pallas::Base>,
+        ) -> Result<(), plonk::Error> {
+            SinsemillaChip::load(config.sinsemilla_config_1.clone(), &mut layouter)?;
+            let nf = assign_free_advice(layouter.namespace(|| "nf"), config.advices[0], self.nf)?;
+            let root = indexed_non_membership_root(
+                &config,
+                &mut layouter,
+                nf,
+                self.value,
+                self.next_value,
+                self.next_index,
+                self.pos,
+                self.path,
+            )?;
+            layouter.constrain_instance(root.cell(), config.primary, 0)
+        }
+    }
+
+    fn base(bytes: [u8; 32]) -> pallas::Base {
+        pallas::Base::from_repr(bytes).unwrap()
+    }
+
+    fn verify(nf: [u8; 32], witness: &IndexedWitness, root: [u8; 32]) -> bool {
+        let path: Vec<_> = witness.path.iter().copied().map(base).collect();
+        let circuit = IndexedCircuit {
+            nf: Value::known(base(nf)),
+            value: Value::known(base(*witness.leaf.value.as_ref())),
+            next_value: Value::known(base(*witness.leaf.next_value.as_ref())),
+            next_index: Value::known(witness.leaf.next_index),
+            pos: Value::known(u32::try_from(witness.leaf_position).unwrap()),
+            path: Value::known(path.try_into().unwrap()),
+        };
+        MockProver::run(K_AIRDROP_NATIVE, &circuit, vec![vec![base(root)]])
+            .expect("synthesize indexed gadget")
+            .verify()
+            .is_ok()
+    }
+
+    fn tree() -> OrchardIndexedTree {
+        let mut tree = OrchardIndexedTree::from_nullifiers(&[Nullifier::from([0x20; 32])]).unwrap();
+        tree.insert(Nullifier::from([0x30; 32])).unwrap();
+        tree
+    }
+
+    #[test]
+    fn root_matches_native_tree() {
+        let tree = tree();
+        let nf = [0x28; 32];
+        let witness = tree.non_membership_witness(Nullifier::from(nf)).unwrap();
+        assert_eq!(witness.leaf_position, 1);
+
+        assert!(verify(nf, &witness, tree.root_bytes()));
+    }
+
+    #[test]
+    fn stored_nullifier_is_rejected() {
+        let tree = tree();
+        let witness = tree
+            .non_membership_witness(Nullifier::from([0x28; 32]))
+            .unwrap();
+
+        assert!(!verify(
+            *witness.leaf.next_value.as_ref(),
+            &witness,
+            tree.root_bytes()
+        ));
+    }
+}
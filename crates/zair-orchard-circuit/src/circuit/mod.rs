@@ -2,3 +2,4 @@
 
 pub mod airdrop;
 pub mod gadget;
+pub mod indexed;
@@ -88,8 +88,8 @@ pub(crate) fn to_instance(
         }
         ValueCommitmentScheme::Sha256 => {
             let digest = cv_sha256.ok_or(plonk::Error::Synthesis)?;
-            for chunk in digest.chunks_exact(4) {
-                let word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as u64;
+            for chunk in digest.as_chunks::<4>().0 {
+                let word = u32::from_be_bytes(*chunk) as u64;
                 instance.push(vesta::Scalar::from(word));
             }
             instance.push(base_from_repr(note_commitment_root)?);
@@ -10,7 +10,7 @@ use zair_orchard_circuit::circuit::airdrop::{
 };
 
 use crate::error::ClaimProofError;
-use crate::types::ValueCommitmentScheme;
+use crate::types::{GapTreeHash, ValueCommitmentScheme};
 
 #[derive(Debug)]
 pub(crate) struct Keys {
@@ -30,12 +30,14 @@ fn dummy_circuit(target_id: [u8; 32], target_id_len: u8) -> Circuit {
 fn keygen(
     params: &Params<vesta::Affine>,
     scheme: ValueCommitmentScheme,
+    gap_tree_hash: GapTreeHash,
     target_id: [u8; 32],
     target_id_len: u8,
 ) -> Result<Keys, ClaimProofError> {
     let circuit_scheme: CircuitValueCommitmentScheme = scheme.into();
     let mut circuit = dummy_circuit(target_id, target_id_len);
     circuit.value_commitment_scheme = circuit_scheme;
+    circuit.gap_tree_hash = gap_tree_hash.into();
 
     let vk = plonk::keygen_vk(params, &circuit)?;
     let pk = plonk::keygen_pk(params, vk.clone(), &circuit)?;
@@ -46,6 +48,7 @@ fn keygen(
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 struct CacheKey {
     scheme: ValueCommitmentScheme,
+    gap_tree_hash: GapTreeHash,
     target_id: [u8; 32],
     target_id_len: u8,
 }
@@ -53,6 +56,7 @@ struct CacheKey {
 pub(crate) fn keys_for(
     params: &Params<vesta::Affine>,
     scheme: ValueCommitmentScheme,
+    gap_tree_hash: GapTreeHash,
     target_id: [u8; 32],
     target_id_len: u8,
 ) -> Result<Arc<Keys>, ClaimProofError> {
@@ -68,6 +72,7 @@ pub(crate) fn keys_for(
 
     let cache_key = CacheKey {
         scheme,
+        gap_tree_hash,
         target_id,
         target_id_len,
     };
@@ -81,7 +86,13 @@ pub(crate) fn keys_for(
         return Ok(keys);
     }
 
-    let keys = Arc::new(keygen(params, scheme, target_id, target_id_len)?);
+    let keys = Arc::new(keygen(
+        params,
+        scheme,
+        gap_tree_hash,
+        target_id,
+        target_id_len,
+    )?);
     cache
         .lock()
         .map_err(|_| ClaimProofError::CachePoisoned)?
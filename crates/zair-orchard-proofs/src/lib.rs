@@ -8,7 +8,7 @@ mod types;
 use zair_orchard_circuit::circuit::airdrop::ValueCommitmentScheme as CircuitValueCommitmentScheme;
 
 pub use crate::error::ClaimProofError;
-pub use crate::types::{ClaimProofInputs, ClaimProofOutput, ValueCommitmentScheme};
+pub use crate::types::{ClaimProofInputs, ClaimProofOutput, GapTreeHash, ValueCommitmentScheme};
 
 /// Return the Halo2 `k` parameter for the given scheme.
 #[must_use]
@@ -21,7 +21,7 @@ pub fn k_for_scheme(scheme: ValueCommitmentScheme) -> u32 {
 pub mod prover;
 
 #[cfg(feature = "prove")]
-pub use prover::generate_claim_proof;
+pub use prover::{generate_claim_proof, generate_claim_proof_with_rng};
 
 #[cfg(feature = "verify")]
 pub mod verifier;
@@ -5,6 +5,7 @@ use orchard::note::{RandomSeed, Rho};
 use orchard::primitives::redpallas::{SpendAuth, VerificationKey as RedPallasVerificationKey};
 use orchard::value::{NoteValue, ValueCommitTrapdoor, ValueCommitment};
 use pasta_curves::{pallas, vesta};
+use rand::{CryptoRng, RngCore};
 use zair_orchard_circuit::circuit::airdrop::Circuit;
 
 use crate::error::ClaimProofError;
@@ -22,6 +23,20 @@ use crate::types::{ClaimProofInputs, ClaimProofOutput, ValueCommitmentScheme};
 pub fn generate_claim_proof(
     params: &Params<vesta::Affine>,
     inputs: &ClaimProofInputs,
+) -> Result<ClaimProofOutput, ClaimProofError> {
+    generate_claim_proof_with_rng(params, inputs, &mut rand::rngs::OsRng)
+}
+
+/// Generate an Orchard claim proof, drawing proof randomness from `rng`.
+///
+/// Use this where the operating system RNG is unavailable, such as `wasm32-unknown-unknown`.
+///
+/// # Errors
+/// See [`generate_claim_proof`].
+pub fn generate_claim_proof_with_rng<R: RngCore + CryptoRng>(
+    params: &Params<vesta::Affine>,
+    inputs: &ClaimProofInputs,
+    rng: &mut R,
 ) -> Result<ClaimProofOutput, ClaimProofError> {
     let _target_id = target_id_slice(&inputs.target_id, inputs.target_id_len)?;
 
@@ -108,6 +123,7 @@ pub fn generate_claim_proof(
         right: halo2_proofs::circuit::Value::known(right),
         gap_path: halo2_proofs::circuit::Value::known(gap_path),
         gap_pos: halo2_proofs::circuit::Value::known(inputs.nf_leaf_position),
+        gap_tree_hash: inputs.gap_tree_hash.into(),
     };
 
     let rk = RedPallasVerificationKey::<SpendAuth>::try_from(inputs.ak_p)
@@ -145,6 +161,7 @@ pub fn generate_claim_proof(
     let keys = keys_for(
         params,
         inputs.value_commitment_scheme,
+        inputs.gap_tree_hash,
         inputs.target_id,
         inputs.target_id_len,
     )?;
@@ -154,7 +171,7 @@ pub fn generate_claim_proof(
         &keys.pk,
         &[circuit],
         &instances,
-        &mut rand::rngs::OsRng,
+        rng,
         &mut transcript,
     )?;
     let proof = transcript.finalize();
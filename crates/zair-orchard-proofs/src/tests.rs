@@ -4,7 +4,7 @@ use pasta_curves::{pallas, vesta};
 use rand::RngCore as _;
 
 use crate::instance::to_instance;
-use crate::types::{ClaimProofOutput, ValueCommitmentScheme};
+use crate::types::{ClaimProofOutput, GapTreeHash, ValueCommitmentScheme};
 
 #[test]
 fn cv_sha256_test_vector() {
@@ -126,6 +126,7 @@ fn verify_rejects_invalid_target_id_length() {
         [0_u8; 32],
         [0_u8; 32],
         ValueCommitmentScheme::Native,
+        GapTreeHash::Sinsemilla,
         &[0_u8; 33],
     )
     .unwrap_err();
@@ -141,8 +142,142 @@ fn verify_rejects_non_utf8_target_id() {
         [0_u8; 32],
         [0_u8; 32],
         ValueCommitmentScheme::Native,
+        GapTreeHash::Sinsemilla,
         &[0xff],
     )
     .unwrap_err();
     assert!(matches!(err, crate::ClaimProofError::InvalidTargetIdUtf8));
 }
+
+/// Witness a real Orchard note in a single-note tree and a one-gap nullifier tree.
+#[cfg(feature = "prove")]
+fn real_claim_inputs(gap_tree_hash: GapTreeHash) -> crate::ClaimProofInputs {
+    use ff::{Field as _, PrimeField as _};
+    use orchard::keys::{FullViewingKey, Scope, SpendingKey};
+    use orchard::note::{ExtractedNoteCommitment, RandomSeed, Rho};
+    use orchard::tree::{MerkleHashOrchard, MerklePath};
+    use orchard::value::{NoteValue, ValueCommitTrapdoor};
+    use orchard::{NOTE_COMMITMENT_TREE_DEPTH, Note};
+    use pasta_curves::arithmetic::CurveExt as _;
+    use rand::rngs::OsRng;
+    use zair_core::base::Nullifier;
+    use zair_nonmembership::{OrchardGapTree, OrchardPoseidonGapTree};
+
+    const TARGET_ID: &str = "ZAIRTEST:O";
+
+    let sk = SpendingKey::from_bytes([7; 32]).unwrap();
+    let fvk = FullViewingKey::from(&sk);
+    let fvk_bytes = fvk.to_bytes();
+    let recipient = fvk.address_at(0u32, Scope::External);
+    let raw_address = recipient.to_raw_address_bytes();
+
+    let rho = Rho::from_bytes(&pallas::Base::random(OsRng).to_repr()).unwrap();
+    let (rseed, note) = loop {
+        let mut rseed = [0_u8; 32];
+        OsRng.fill_bytes(&mut rseed);
+        let Some(seed) = Option::<RandomSeed>::from(RandomSeed::from_bytes(rseed, &rho)) else {
+            continue;
+        };
+        if let Some(note) = Option::<Note>::from(Note::from_parts(
+            recipient,
+            NoteValue::from_raw(70_000),
+            rho,
+            seed,
+        )) {
+            break (rseed, note);
+        }
+    };
+
+    let cmx = ExtractedNoteCommitment::from(note.commitment());
+    let zero_node = MerkleHashOrchard::from_bytes(&[0; 32]).unwrap();
+    let note_commitment_root = MerklePath::from_parts(0, [zero_node; NOTE_COMMITMENT_TREE_DEPTH])
+        .root(cmx)
+        .to_bytes();
+
+    // The note's nullifier sits strictly inside the gap of leaf 0.
+    let nf = pallas::Base::from_repr(note.nullifier(&fvk).to_bytes()).unwrap();
+    let left = Nullifier::from((nf - pallas::Base::one()).to_repr());
+    let right = Nullifier::from((nf + pallas::Base::one()).to_repr());
+    let gap_path = [[0_u8; 32]; NOTE_COMMITMENT_TREE_DEPTH];
+    let nullifier_gap_root = match gap_tree_hash {
+        GapTreeHash::Sinsemilla => OrchardGapTree::root_from_witness(&left, &right, 0, &gap_path),
+        GapTreeHash::Poseidon => {
+            OrchardPoseidonGapTree::root_from_witness(&left, &right, 0, &gap_path)
+        }
+    }
+    .unwrap();
+
+    let mut target_id = [0_u8; 32];
+    target_id[..TARGET_ID.len()].copy_from_slice(TARGET_ID.as_bytes());
+    let rcv = loop {
+        let bytes = pallas::Scalar::random(OsRng).to_repr();
+        if Option::<ValueCommitTrapdoor>::from(ValueCommitTrapdoor::from_bytes(bytes)).is_some() {
+            break bytes;
+        }
+    };
+    let mut pk_d = [0_u8; 32];
+    pk_d.copy_from_slice(&raw_address[11..]);
+    let mut diversifier = [0_u8; 11];
+    diversifier.copy_from_slice(&raw_address[..11]);
+    let mut g_d = pallas::Point::hash_to_curve("z.cash:Orchard-gd")(&diversifier);
+    if bool::from(g_d.is_identity()) {
+        g_d = pallas::Point::hash_to_curve("z.cash:Orchard-gd")(&[]);
+    }
+
+    crate::ClaimProofInputs {
+        target_id,
+        target_id_len: TARGET_ID.len() as u8,
+        airdrop_nullifier: note.hiding_nullifier(&fvk, TARGET_ID, b"K").to_bytes(),
+        note_commitment_root,
+        nullifier_gap_root,
+        value_commitment_scheme: ValueCommitmentScheme::Native,
+        rcv_sha256: None,
+        rho: rho.to_bytes(),
+        rseed,
+        g_d: g_d.to_bytes(),
+        pk_d,
+        value: 70_000,
+        cm_note_position: 0,
+        cm_merkle_path: [[0; 32]; NOTE_COMMITMENT_TREE_DEPTH],
+        alpha: pallas::Scalar::random(OsRng).to_repr(),
+        ak_p: fvk_bytes[..32].try_into().unwrap(),
+        nk: fvk_bytes[32..64].try_into().unwrap(),
+        rivk: fvk_bytes[64..].try_into().unwrap(),
+        rcv,
+        left: left.into(),
+        right: right.into(),
+        gap_tree_hash,
+        nf_leaf_position: 0,
+        nf_merkle_path: gap_path,
+    }
+}
+
+#[cfg(feature = "prove")]
+#[test]
+#[ignore = "Heavy: Halo2 keygen and proving"]
+fn poseidon_gap_tree_proof_round_trip() {
+    let params = Params::<vesta::Affine>::new(crate::k_for_scheme(ValueCommitmentScheme::Native));
+    let inputs = real_claim_inputs(GapTreeHash::Poseidon);
+    let output = crate::generate_claim_proof(&params, &inputs).expect("prove");
+    let verify = |gap_tree_hash, nullifier_gap_root| {
+        crate::verifier::verify_claim_proof_output(
+            &params,
+            &output,
+            inputs.note_commitment_root,
+            nullifier_gap_root,
+            ValueCommitmentScheme::Native,
+            gap_tree_hash,
+            b"ZAIRTEST:O",
+        )
+    };
+
+    verify(GapTreeHash::Poseidon, inputs.nullifier_gap_root).expect("proof verifies");
+    assert!(
+        verify(GapTreeHash::Poseidon, [0_u8; 32]).is_err(),
+        "another gap root must be rejected"
+    );
+    assert!(
+        verify(GapTreeHash::Sinsemilla, inputs.nullifier_gap_root).is_err(),
+        "the Sinsemilla circuit must not accept a Poseidon proof"
+    );
+}
@@ -1,6 +1,8 @@
 use std::fmt;
 
-use zair_orchard_circuit::circuit::airdrop::ValueCommitmentScheme as CircuitValueCommitmentScheme;
+use zair_orchard_circuit::circuit::airdrop::{
+    GapTreeHash as CircuitGapTreeHash, ValueCommitmentScheme as CircuitValueCommitmentScheme,
+};
 
 /// Orchard value-commitment scheme selection.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -38,6 +40,43 @@ impl From<ValueCommitmentScheme> for CircuitValueCommitmentScheme {
     }
 }
 
+/// Orchard gap-tree hash selection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GapTreeHash {
+    /// Sinsemilla `MerkleCRH^Orchard` (the gap leaf is hashed at level 62).
+    #[default]
+    Sinsemilla,
+    /// Poseidon over the Pallas base field.
+    Poseidon,
+}
+
+impl fmt::Display for GapTreeHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sinsemilla => f.write_str("sinsemilla"),
+            Self::Poseidon => f.write_str("poseidon"),
+        }
+    }
+}
+
+impl From<zair_core::schema::config::OrchardGapTreeHash> for GapTreeHash {
+    fn from(hash: zair_core::schema::config::OrchardGapTreeHash) -> Self {
+        match hash {
+            zair_core::schema::config::OrchardGapTreeHash::Sinsemilla => Self::Sinsemilla,
+            zair_core::schema::config::OrchardGapTreeHash::Poseidon => Self::Poseidon,
+        }
+    }
+}
+
+impl From<GapTreeHash> for CircuitGapTreeHash {
+    fn from(hash: GapTreeHash) -> Self {
+        match hash {
+            GapTreeHash::Sinsemilla => Self::Sinsemilla,
+            GapTreeHash::Poseidon => Self::Poseidon,
+        }
+    }
+}
+
 /// Claim proof output (public fields + proof bytes).
 ///
 /// Public input ordering mirrors Sapling: rk first, then value commitment(s), then anchors,
@@ -110,6 +149,8 @@ pub struct ClaimProofInputs {
     pub left: [u8; 32],
     /// Right boundary `R` for the spent-nullifier gap, as a canonical Pallas base encoding.
     pub right: [u8; 32],
+    /// Hash function used by the gap tree.
+    pub gap_tree_hash: GapTreeHash,
     /// Position of the gap leaf in the gap tree.
     pub nf_leaf_position: u32,
     /// Gap tree authentication path (sibling nodes, leaf-to-root).
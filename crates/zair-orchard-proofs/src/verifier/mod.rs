@@ -6,7 +6,7 @@ use pasta_curves::vesta;
 use crate::error::ClaimProofError;
 use crate::instance::to_instance;
 use crate::keys::keys_for;
-use crate::types::{ClaimProofOutput, ValueCommitmentScheme};
+use crate::types::{ClaimProofOutput, GapTreeHash, ValueCommitmentScheme};
 
 /// Verify an Orchard claim proof with the given public inputs.
 ///
@@ -18,6 +18,7 @@ pub fn verify_claim_proof_output(
     note_commitment_root: [u8; 32],
     nullifier_gap_root: [u8; 32],
     value_commitment_scheme: ValueCommitmentScheme,
+    gap_tree_hash: GapTreeHash,
     target_id: &[u8],
 ) -> Result<(), ClaimProofError> {
     if target_id.len() > 32 {
@@ -43,6 +44,7 @@ pub fn verify_claim_proof_output(
     let keys = keys_for(
         params,
         value_commitment_scheme,
+        gap_tree_hash,
         target_id_arr,
         target_id_len,
     )?;
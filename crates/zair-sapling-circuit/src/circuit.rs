@@ -23,7 +23,7 @@ use crate::gadgets::enforce_less_than;
 
 /// Personalization for the hiding nullifier (airdrop-specific).
 /// This is used to derive a nullifier that doesn't reveal the Zcash nullifier.
-pub const HIDING_NF_PERSONALIZATION: &[u8; 8] = b"ZAIRTEST";
+pub const HIDING_NF_PERSONALIZATION: &[u8; 8] = &zair_core::base::SAPLING_HIDING_NF_PERSONALIZATION;
 
 /// Prefix for SHA-256 value commitments (`cv_sha256`).
 pub const VALUE_COMMIT_SHA256_PREFIX: &[u8; 4] = &zair_core::base::VALUE_COMMIT_SHA256_PREFIX;
@@ -121,7 +121,7 @@ impl core::fmt::Debug for Claim {
 /// Traverse a Merkle tree path in the circuit, computing the root.
 ///
 /// Returns the computed root and the position bits (one bit per level indicating left/right).
-fn merkle_tree_traverse<CS: ConstraintSystem<bls12_381::Scalar>>(
+pub(crate) fn merkle_tree_traverse<CS: ConstraintSystem<bls12_381::Scalar>>(
     cs: &mut CS,
     initial: num::AllocatedNum<bls12_381::Scalar>,
     path: Vec<Option<(bls12_381::Scalar, bool)>>,
@@ -175,7 +175,7 @@ fn merkle_tree_traverse<CS: ConstraintSystem<bls12_381::Scalar>>(
     clippy::arithmetic_side_effects,
     reason = "byte_idx in 0..32, bit_idx in 0..8, max result 255"
 )]
-fn witness_bytes_as_bits<CS>(
+pub(crate) fn witness_bytes_as_bits<CS>(
     mut cs: CS,
     bytes: Option<&[u8; 32]>,
 ) -> Result<Vec<boolean::Boolean>, SynthesisError>
@@ -293,7 +293,7 @@ where
 
     // Value bits are little-endian per-byte; SHA gadget expects big-endian per-byte.
     let mut value_bits_for_sha = Vec::with_capacity(64);
-    for byte_bits_le in value_bits_le.chunks_exact(8) {
+    for byte_bits_le in value_bits_le.as_chunks::<8>().0 {
         value_bits_for_sha.extend(byte_bits_le.iter().rev().cloned());
     }
 
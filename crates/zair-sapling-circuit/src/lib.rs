@@ -2,5 +2,6 @@
 
 pub mod circuit;
 pub mod gadgets;
+pub mod indexed;
 
 pub use circuit::{Claim, ValueCommitmentOpening, ValueCommitmentScheme};
@@ -3,11 +3,13 @@
 //! This module provides functionality to generate Groth16 proving and verifying
 //! parameters for the Claim circuit.
 
+use std::io::Read;
 use std::path::Path;
 
 use bellman::groth16::{Parameters, generate_random_parameters};
 use bls12_381::Bls12;
 use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
 use zair_nonmembership::NON_MEMBERSHIP_TREE_DEPTH;
 use zair_sapling_circuit::Claim;
 
@@ -45,8 +47,17 @@ pub enum ParameterError {
 pub fn generate_parameters(
     value_commitment_scheme: ValueCommitmentScheme,
 ) -> Result<ClaimParameters, ParameterError> {
-    let mut rng = OsRng;
+    generate_parameters_with_rng(value_commitment_scheme, &mut OsRng)
+}
 
+/// Generate new Groth16 parameters for the Claim circuit from caller-provided randomness.
+///
+/// # Errors
+/// Returns an error if parameter generation fails.
+pub fn generate_parameters_with_rng<R: RngCore + CryptoRng>(
+    value_commitment_scheme: ValueCommitmentScheme,
+    rng: &mut R,
+) -> Result<ClaimParameters, ParameterError> {
     // Create empty circuit for parameter generation
     let empty_circuit = Claim {
         value_commitment_opening: None,
@@ -64,7 +75,7 @@ pub fn generate_parameters(
         rcv_sha256: None,
     };
 
-    let params = generate_random_parameters::<Bls12, _, _>(empty_circuit, &mut rng)
+    let params = generate_random_parameters::<Bls12, _, _>(empty_circuit, rng)
         .map_err(ParameterError::Generation)?;
 
     Ok(ClaimParameters(params))
@@ -115,8 +126,21 @@ pub fn load_parameters(
     checked: bool,
 ) -> Result<ClaimParameters, ParameterError> {
     let file = std::fs::File::open(proving_key_path)?;
-    let reader = std::io::BufReader::new(file);
+    read_parameters(std::io::BufReader::new(file), checked)
+}
 
+/// Read parameters from serialized proving key bytes.
+///
+/// # Arguments
+/// * `reader` - Source of the proving key, as written by [`save_parameters`]
+/// * `checked` - If true, verify the parameters (slower but safer)
+///
+/// # Errors
+/// Returns an error if parsing fails.
+pub fn read_parameters<R: Read>(
+    reader: R,
+    checked: bool,
+) -> Result<ClaimParameters, ParameterError> {
     let params = Parameters::read(reader, checked).map_err(ParameterError::Deserialization)?;
 
     Ok(ClaimParameters(params))
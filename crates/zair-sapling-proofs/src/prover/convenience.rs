@@ -11,6 +11,7 @@ use ff::PrimeField;
 use group::Curve;
 use incrementalmerkletree::Position;
 use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
 use sapling::value::{NoteValue, ValueCommitTrapdoor};
 use sapling::{Diversifier, Note, PaymentAddress, ProofGenerationKey, Rseed};
 
@@ -65,17 +66,30 @@ pub fn bytes_less_than(a: &[u8; 32], b: &[u8; 32]) -> bool {
 ///
 /// # Errors
 /// Returns an error if proof generation fails.
+pub fn generate_claim_proof(
+    params: &ClaimParameters,
+    inputs: &ClaimProofInputs,
+    proof_generation_key: &ProofGenerationKey,
+) -> Result<ClaimProofOutput, ClaimProofError> {
+    generate_claim_proof_with_rng(params, inputs, proof_generation_key, &mut OsRng)
+}
+
+/// Generate a claim proof from raw byte inputs, drawing proof randomness from `rng`.
+///
+/// Use this where the operating system RNG is unavailable, such as `wasm32-unknown-unknown`.
+///
+/// # Errors
+/// Returns an error if proof generation fails.
 #[allow(
     clippy::too_many_lines,
     reason = "End-to-end witness preparation and proving"
 )]
-pub fn generate_claim_proof(
+pub fn generate_claim_proof_with_rng<R: RngCore + CryptoRng>(
     params: &ClaimParameters,
     inputs: &ClaimProofInputs,
     proof_generation_key: &ProofGenerationKey,
+    rng: &mut R,
 ) -> Result<ClaimProofOutput, ClaimProofError> {
-    let mut rng = OsRng;
-
     // Parse inputs
     let value = NoteValue::from_raw(inputs.value);
     let rcm = jubjub::Fr::from_bytes(&inputs.rcm)
@@ -191,7 +205,7 @@ pub fn generate_claim_proof(
     )?;
 
     // Create and encode the proof
-    let proof = create_proof(params, circuit, &mut rng);
+    let proof = create_proof(params, circuit, rng);
     let zkproof = encode_proof(&proof);
 
     // Note: We intentionally do NOT compute or expose the Zcash nullifier
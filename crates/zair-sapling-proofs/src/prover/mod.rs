@@ -5,8 +5,11 @@ mod builder;
 mod convenience;
 mod proving;
 
-pub use builder::{ParameterError, generate_parameters, load_parameters, save_parameters};
-pub use convenience::generate_claim_proof;
+pub use builder::{
+    ParameterError, generate_parameters, generate_parameters_with_rng, load_parameters,
+    read_parameters, save_parameters,
+};
+pub use convenience::{generate_claim_proof, generate_claim_proof_with_rng};
 pub use proving::ClaimParameters;
 
 pub use crate::error::ClaimProofError;
@@ -2,67 +2,130 @@
 
 pub mod chain_nullifiers;
 pub mod light_walletd;
+pub mod scan_cache;
 pub mod scanner;
 pub mod user_nullifiers;
 pub mod viewing_keys;
+pub mod wallet_db;
+
+use std::borrow::Cow;
 
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 pub use viewing_keys::{OrchardViewingKeys, SaplingViewingKeys, ViewingKeys};
 pub use zair_core::base::Pool;
 use zair_core::base::{NULLIFIER_SIZE, Nullifier, SanitiseNullifiers};
+use zair_core::container::{
+    self, Container, ContainerError, ContainerHeader, ContainerKind, ContainerOptions,
+};
 
 /// 1 MiB buffer for file I/O.
 const FILE_BUF_SIZE: usize = 1024 * 1024;
 
-/// Write nullifiers in binary format to an async writer
+/// A nullifier snapshot read from disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Container header, or `None` for legacy headerless files.
+    pub header: Option<ContainerHeader>,
+    /// Snapshot nullifiers in file order.
+    pub nullifiers: Vec<Nullifier>,
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+/// Write nullifiers as a versioned snapshot container to an async writer
 ///
 /// # Errors
-/// If write fails
+/// If encoding or the write fails
 pub async fn write_nullifiers(
     nullifiers: &[Nullifier],
+    pool: Pool,
+    root: [u8; 32],
+    options: &ContainerOptions,
     mut writer: impl AsyncWriteExt + Unpin,
 ) -> std::io::Result<()> {
-    writer.write_all(bytemuck::cast_slice(nullifiers)).await?;
+    let item_count = u64::try_from(nullifiers.len()).map_err(invalid_data)?;
+    let header = ContainerHeader::new(ContainerKind::Snapshot, pool, root, item_count, options);
+    let bytes =
+        container::encode(&header, bytemuck::cast_slice(nullifiers)).map_err(invalid_data)?;
+
+    writer.write_all(&bytes).await?;
     writer.flush().await?;
 
     Ok(())
 }
 
-/// Read nullifiers from an async reader
+/// Read a snapshot, either a versioned container or a legacy raw nullifier file
 ///
 /// # Errors
 ///
 /// Returns an error if:
 /// - Reading from the file fails
-/// - The input size is not a multiple of 32 bytes (nullifier size)
-pub async fn read_nullifiers(
-    mut reader: impl AsyncReadExt + Unpin,
-) -> std::io::Result<Vec<Nullifier>> {
+/// - The container header or checksum is invalid, or it is not a snapshot
+/// - The payload size is not a multiple of 32 bytes (nullifier size)
+/// - The nullifier count does not match the header
+pub async fn read_snapshot(mut reader: impl AsyncReadExt + Unpin) -> std::io::Result<Snapshot> {
     let mut buf = Vec::with_capacity(FILE_BUF_SIZE);
     reader.read_to_end(&mut buf).await?;
 
-    if buf.len() % NULLIFIER_SIZE != 0 {
+    let (header, payload) = match container::decode(&buf).map_err(invalid_data)? {
+        Container::Legacy(payload) => (None, Cow::Borrowed(payload)),
+        Container::Versioned { header, payload } => (Some(header), payload),
+    };
+
+    if payload.len() % NULLIFIER_SIZE != 0 {
         return Err(std::io::Error::new(
             std::io::ErrorKind::InvalidData,
             format!(
                 "file has {} bytes which is not a multiple of nullifier size ({NULLIFIER_SIZE})",
-                buf.len(),
+                payload.len(),
             ),
         ));
     }
 
-    let nullifiers: Vec<Nullifier> = bytemuck::cast_slice(&buf).to_vec();
+    let nullifiers: Vec<Nullifier> = bytemuck::cast_slice(&payload).to_vec();
 
-    Ok(nullifiers)
+    if let Some(header) = header {
+        if header.kind != ContainerKind::Snapshot {
+            return Err(invalid_data(ContainerError::KindMismatch {
+                expected: ContainerKind::Snapshot,
+                found: header.kind,
+            }));
+        }
+        let count = u64::try_from(nullifiers.len()).map_err(invalid_data)?;
+        if count != header.item_count {
+            return Err(invalid_data(format!(
+                "snapshot header declares {} nullifiers but file has {count}",
+                header.item_count
+            )));
+        }
+    }
+
+    Ok(Snapshot { header, nullifiers })
+}
+
+/// Read nullifiers from an async reader
+///
+/// Accepts both versioned snapshot containers and legacy raw nullifier files.
+///
+/// # Errors
+///
+/// See [`read_snapshot`].
+pub async fn read_nullifiers(reader: impl AsyncReadExt + Unpin) -> std::io::Result<Vec<Nullifier>> {
+    Ok(read_snapshot(reader).await?.nullifiers)
 }
 
 #[cfg(test)]
 mod tests {
     use futures::io::Cursor;
     use tokio_util::compat::FuturesAsyncReadCompatExt as _;
+    use zair_core::schema::config::AirdropNetwork;
 
     use super::*;
 
+    const OPTIONS: ContainerOptions = ContainerOptions::new(AirdropNetwork::Testnet, 3_000_000);
+
     mod read {
         use super::*;
 
@@ -125,14 +188,22 @@ mod tests {
 
             let cursor = Cursor::new(Vec::new());
             let mut writer = cursor.compat();
-            write_nullifiers(&nullifiers, &mut writer)
+            write_nullifiers(&nullifiers, Pool::Sapling, [7; 32], &OPTIONS, &mut writer)
                 .await
                 .expect("Failed to write nullifiers");
 
             let buf = writer.into_inner().into_inner();
 
-            assert_eq!(buf.len(), nullifiers.len() * NULLIFIER_SIZE,);
-            assert_eq!(buf, bytemuck::cast_slice::<_, u8>(&nullifiers),);
+            assert_eq!(
+                buf.len(),
+                container::CONTAINER_HEADER_LEN + nullifiers.len() * NULLIFIER_SIZE,
+            );
+            let (header, payload) = container::decode_header(&buf).expect("valid container");
+            assert_eq!(header.kind, ContainerKind::Snapshot);
+            assert_eq!(header.pool, Pool::Sapling);
+            assert_eq!(header.item_count, 3);
+            assert_eq!(header.root, [7; 32]);
+            assert_eq!(payload, bytemuck::cast_slice::<_, u8>(&nullifiers),);
         }
     }
 
@@ -143,21 +214,45 @@ mod tests {
         // Write
         let cursor = Cursor::new(Vec::new());
         let mut writer = cursor.compat();
-        write_nullifiers(&original, &mut writer)
+        write_nullifiers(&original, Pool::Orchard, [1; 32], &OPTIONS, &mut writer)
             .await
             .expect("Failed to write nullifiers");
         let buf = writer.into_inner().into_inner();
 
         // Read back
         let cursor = Cursor::new(buf);
-        let read_back = read_nullifiers(cursor.compat())
+        let snapshot = read_snapshot(cursor.compat())
             .await
             .expect("Failed to read nullifiers");
 
         assert_eq!(
             original.to_vec(),
-            read_back,
+            snapshot.nullifiers,
             "Roundtrip should preserve nullifiers"
         );
+        let header = snapshot.header.expect("container header");
+        assert_eq!(header.pool, Pool::Orchard);
+        assert_eq!(header.ensure_snapshot(&OPTIONS), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn read_rejects_corrupted_container() {
+        #![allow(clippy::indexing_slicing, reason = "Test code")]
+
+        let original = [Nullifier::new([5; 32])];
+        let cursor = Cursor::new(Vec::new());
+        let mut writer = cursor.compat();
+        write_nullifiers(&original, Pool::Sapling, [0; 32], &OPTIONS, &mut writer)
+            .await
+            .expect("Failed to write nullifiers");
+        let mut buf = writer.into_inner().into_inner();
+        let last = buf.len() - 1;
+        buf[last] ^= 1;
+
+        let result = read_nullifiers(Cursor::new(buf).compat()).await;
+        assert!(
+            matches!(result, Err(e) if e.kind() == std::io::ErrorKind::InvalidData),
+            "Expected checksum failure"
+        );
     }
 }
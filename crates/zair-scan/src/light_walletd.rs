@@ -8,7 +8,9 @@ use std::ops::RangeInclusive;
 use std::time::Duration;
 
 pub use config::LightWalletdConfig;
+pub use error::LightWalletdError;
 use futures::{Stream, StreamExt as _};
+use tokio_util::sync::CancellationToken;
 use tonic::transport::{Channel, ClientTlsConfig, Uri};
 use tracing::warn;
 use zcash_client_backend::data_api::BlockMetadata;
@@ -18,7 +20,6 @@ use zcash_client_backend::proto::service::{BlockId, BlockRange, TreeState};
 use zcash_protocol::consensus::BlockHeight;
 
 use crate::light_walletd::config::ValidatedLightWalletdConfig;
-use crate::light_walletd::error::LightWalletdError;
 use crate::light_walletd::retry::retry_with_backoff;
 use crate::scanner::{BlockScanner, ScanVisitor, extract_nullifiers};
 
@@ -193,6 +194,40 @@ impl LightWalletd {
         visitor: V,
         range: &RangeInclusive<u64>,
         initial_metadata: Option<BlockMetadata>,
+    ) -> Result<(V, Option<BlockMetadata>), LightWalletdError> {
+        self.scan_blocks_spawned_with_progress(
+            ufvk,
+            network,
+            visitor,
+            range,
+            initial_metadata,
+            |_, _, _| {},
+            CancellationToken::new(),
+        )
+        .await
+    }
+
+    /// Scan blocks like [`Self::scan_blocks_spawned`], with progress callback and cancellation.
+    ///
+    /// Calls `on_progress(height, scanned, total)` after each block is scanned. Both tasks stop
+    /// once `cancel` is cancelled.
+    ///
+    /// # Errors
+    /// Returns [`LightWalletdError::Cancelled`] if `cancel` fires, or an error if block fetching
+    /// or scanning fails.
+    #[allow(
+        clippy::too_many_arguments,
+        reason = "Scan inputs plus progress and cancellation hooks"
+    )]
+    pub async fn scan_blocks_spawned_with_progress<V: ScanVisitor + Send + 'static>(
+        &self,
+        ufvk: zcash_keys::keys::UnifiedFullViewingKey,
+        network: zcash_protocol::consensus::Network,
+        visitor: V,
+        range: &RangeInclusive<u64>,
+        initial_metadata: Option<BlockMetadata>,
+        mut on_progress: impl FnMut(u64, usize, usize) + Send + 'static,
+        cancel: CancellationToken,
     ) -> Result<(V, Option<BlockMetadata>), LightWalletdError> {
         const CHANNEL_BUFFER_SIZE: usize = 100;
 
@@ -201,39 +236,59 @@ impl LightWalletd {
         let client = self.client.clone();
         let config = self.config.clone();
         let range_clone = range.clone();
+        let total_blocks_u64 = range.end().saturating_sub(*range.start()).saturating_add(1);
+        let total_blocks = usize::try_from(total_blocks_u64).unwrap_or(usize::MAX);
 
+        let fetcher_cancel = cancel.clone();
         let fetcher_handle = tokio::spawn(async move {
             let mut stream = Self::get_block_range_stream(&client, &config, &range_clone).await?;
 
-            while let Some(block) =
-                receive_next_block(&mut stream, config.stream_message_timeout).await?
-            {
+            loop {
+                let block = tokio::select! {
+                    () = fetcher_cancel.cancelled() => return Err(LightWalletdError::Cancelled),
+                    block = receive_next_block(&mut stream, config.stream_message_timeout) => block?,
+                };
+                let Some(block) = block else {
+                    break;
+                };
                 tx.send(block).await?;
             }
 
             Ok::<_, LightWalletdError>(())
         });
 
+        let scanner_cancel = cancel.clone();
         let scanner_handle = tokio::task::spawn_blocking(move || {
             let scanner = BlockScanner::from_ufvk(ufvk, network);
             let mut visitor = visitor;
             let mut prior_metadata = initial_metadata;
+            let mut scanned_blocks = 0_usize;
 
             while let Some(block) = rx.blocking_recv() {
+                if scanner_cancel.is_cancelled() {
+                    return Err(LightWalletdError::Cancelled);
+                }
+                let height = block.height;
                 let metadata = scanner.scan_block(block, &mut visitor, prior_metadata.as_ref())?;
                 prior_metadata = Some(metadata);
+                scanned_blocks = scanned_blocks.saturating_add(1);
+                on_progress(height, scanned_blocks, total_blocks);
             }
 
             Ok::<_, LightWalletdError>((visitor, prior_metadata))
         });
 
-        fetcher_handle
+        let fetched = fetcher_handle
             .await
-            .map_err(|e| LightWalletdError::TaskJoin(e.to_string()))??;
-
-        scanner_handle
+            .map_err(|e| LightWalletdError::TaskJoin(e.to_string()))?;
+        let scanned = scanner_handle
             .await
-            .map_err(|e| LightWalletdError::TaskJoin(e.to_string()))?
+            .map_err(|e| LightWalletdError::TaskJoin(e.to_string()))?;
+        if cancel.is_cancelled() {
+            return Err(LightWalletdError::Cancelled);
+        }
+        fetched?;
+        scanned
     }
 
     /// Get tree state at a height
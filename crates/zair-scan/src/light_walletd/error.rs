@@ -60,6 +60,9 @@ pub enum LightWalletdError {
     /// Task join error
     #[error("Task join error: {0}")]
     TaskJoin(String),
+    /// The scan was cancelled through its cancellation token
+    #[error("Scan cancelled")]
+    Cancelled,
     /// Tokio send error
     #[error("Tokio send: {0}")]
     SendError(
@@ -93,6 +96,7 @@ impl LightWalletdError {
         clippy::wildcard_enum_match_arm,
         reason = "We are interested in specific variants only."
     )]
+    #[must_use]
     pub fn is_retryable(&self) -> bool {
         use tonic::Code;
 
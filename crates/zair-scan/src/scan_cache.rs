@@ -0,0 +1,267 @@
+//! On-disk cache of an account's note scan.
+//!
+//! A cache file holds an [`AccountNotesVisitor`]: the found notes, the Sapling and Orchard
+//! commitment trees with their per-block checkpoints, and the metadata of the last scanned block.
+//! Repeating a claim at the same snapshot then needs no scan, a later snapshot only scans the new
+//! blocks, and an earlier one rewinds to its checkpoint while it is still retained.
+//!
+//! Each (viewing key, birthday) pair has its own file, named after the hex of its account key.
+//! The file holds private note data and is written with owner-only permissions on Unix.
+//!
+//! Layout (all integers little-endian):
+//!
+//! | offset | size | field                                         |
+//! |--------|------|-----------------------------------------------|
+//! | 0      | 8    | magic `b"ZAIRSCAN"`                           |
+//! | 8      | 2    | format version                                |
+//! | 10     | 32   | account key, BLAKE2s-256 of UFVK and birthday |
+//! | 42     | n    | [`AccountNotesVisitor::write`] payload        |
+//! | 42 + n | 32   | BLAKE2s-256 of bytes `0..42 + n`              |
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tokio::io::AsyncWriteExt as _;
+use zcash_keys::keys::UnifiedFullViewingKey;
+use zcash_protocol::consensus::Network;
+
+use crate::scanner::AccountNotesVisitor;
+
+/// Cache file magic bytes.
+const SCAN_CACHE_MAGIC: [u8; 8] = *b"ZAIRSCAN";
+/// Current cache format version.
+const SCAN_CACHE_VERSION: u16 = 1;
+const HEADER_LEN: usize = 42;
+const CHECKSUM_LEN: usize = 32;
+const KEY_PERSONALIZATION: &[u8; 8] = b"ZAIRscnK";
+const CHECKSUM_PERSONALIZATION: &[u8; 8] = b"ZAIRscnC";
+
+/// Scan cache file of one account.
+#[derive(Debug, Clone)]
+pub struct ScanCache {
+    path: PathBuf,
+    key: [u8; 32],
+}
+
+impl ScanCache {
+    /// Cache file in `dir` for the account of `ufvk` scanned from `birthday_height`
+    #[must_use]
+    pub fn new(
+        dir: &Path,
+        network: Network,
+        ufvk: &UnifiedFullViewingKey,
+        birthday_height: u64,
+    ) -> Self {
+        let mut state = blake2s(KEY_PERSONALIZATION);
+        state.update(ufvk.encode(&network).as_bytes());
+        state.update(&birthday_height.to_le_bytes());
+        let key = *state.finalize().as_array();
+        Self {
+            path: dir.join(format!("{}.scan", hex::encode(key))),
+            key,
+        }
+    }
+
+    /// Path of the cache file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the cached scan, or `None` if there is no cache file for this account
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, fails its checksum, or is malformed
+    pub async fn load(&self) -> io::Result<Option<AccountNotesVisitor>> {
+        let bytes = match tokio::fs::read(&self.path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let (contents, checksum) = bytes
+            .split_last_chunk::<CHECKSUM_LEN>()
+            .ok_or_else(|| invalid_data("scan cache is truncated"))?;
+        if checksum_of(contents) != *checksum {
+            return Err(invalid_data("scan cache checksum mismatch"));
+        }
+        let (header, payload) = contents
+            .split_first_chunk::<HEADER_LEN>()
+            .ok_or_else(|| invalid_data("scan cache is truncated"))?;
+        let (magic, rest) = header.split_at(SCAN_CACHE_MAGIC.len());
+        let (version, key) = rest.split_at(2);
+        if magic != SCAN_CACHE_MAGIC {
+            return Err(invalid_data("not a scan cache file"));
+        }
+        let version = u16::from_le_bytes(version.try_into().map_err(invalid_data)?);
+        if version != SCAN_CACHE_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported scan cache version {version}"
+            )));
+        }
+        if key != self.key {
+            return Ok(None);
+        }
+
+        let mut reader = payload;
+        let visitor = AccountNotesVisitor::read(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(invalid_data("scan cache has trailing bytes"));
+        }
+        Ok(Some(visitor))
+    }
+
+    /// Store `visitor`, replacing the previous cache file atomically
+    ///
+    /// # Errors
+    /// Returns an error if the directory or file cannot be written
+    pub async fn store(&self, visitor: &AccountNotesVisitor) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN);
+        bytes.extend_from_slice(&SCAN_CACHE_MAGIC);
+        bytes.extend_from_slice(&SCAN_CACHE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.key);
+        visitor.write(&mut bytes)?;
+        let checksum = checksum_of(&bytes);
+        bytes.extend_from_slice(&checksum);
+
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        let staging = self.path.with_extension("scan.tmp");
+        let mut options = tokio::fs::OpenOptions::new();
+        options.create(true).truncate(true).write(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&staging).await?;
+        file.write_all(&bytes).await?;
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&staging, &self.path).await
+    }
+}
+
+fn blake2s(personalization: &[u8]) -> blake2s_simd::State {
+    blake2s_simd::Params::new()
+        .hash_length(32)
+        .personal(personalization)
+        .to_state()
+}
+
+fn checksum_of(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut state = blake2s(CHECKSUM_PERSONALIZATION);
+    state.update(bytes);
+    *state.finalize().as_array()
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use incrementalmerkletree::{Hashable as _, Marking, Retention};
+    use orchard::tree::MerkleHashOrchard;
+    use zcash_client_backend::data_api::BlockMetadata;
+    use zcash_client_backend::proto::service::TreeState;
+    use zcash_keys::keys::UnifiedSpendingKey;
+    use zcash_primitives::block::BlockHash;
+    use zcash_protocol::consensus::BlockHeight;
+    use zip32::AccountId;
+
+    use super::*;
+    use crate::scanner::ScanVisitor as _;
+
+    const SCANNED_HEIGHT: u32 = 1_001;
+
+    fn ufvk(seed: u8) -> UnifiedFullViewingKey {
+        UnifiedSpendingKey::from_seed(&Network::TestNetwork, &[seed; 32], AccountId::ZERO)
+            .expect("derive spending key")
+            .to_unified_full_viewing_key()
+    }
+
+    /// Visitor that scanned one block holding a Sapling and an Orchard commitment.
+    fn visitor() -> AccountNotesVisitor {
+        let mut visitor = AccountNotesVisitor::from_tree_state(&TreeState {
+            height: u64::from(SCANNED_HEIGHT - 1),
+            ..TreeState::default()
+        })
+        .expect("empty tree state");
+        let height = BlockHeight::from_u32(SCANNED_HEIGHT);
+        let retention = Retention::Checkpoint {
+            id: height,
+            marking: Marking::Marked,
+        };
+        visitor.on_sapling_commitment(sapling::Node::empty_leaf(), retention);
+        visitor.on_orchard_commitment(MerkleHashOrchard::empty_leaf(), retention);
+        visitor.on_block_scanned(
+            height,
+            &BlockMetadata::from_parts(height, BlockHash([7; 32]), Some(1), Some(1)),
+        );
+        visitor
+    }
+
+    #[tokio::test]
+    async fn store_load_roundtrip() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let cache = ScanCache::new(dir.path(), Network::TestNetwork, &ufvk(1), 1_001);
+        assert!(cache.load().await.expect("load").is_none());
+
+        let visitor = visitor();
+        cache.store(&visitor).await.expect("store");
+        let loaded = cache.load().await.expect("load").expect("cached visitor");
+
+        assert_eq!(
+            loaded.sapling_anchor().expect("anchor"),
+            visitor.sapling_anchor().expect("anchor")
+        );
+        assert_eq!(
+            loaded.orchard_anchor().expect("anchor"),
+            visitor.orchard_anchor().expect("anchor")
+        );
+        assert_eq!(
+            loaded.sapling_witness(0).expect("witness"),
+            visitor.sapling_witness(0).expect("witness")
+        );
+        assert_eq!(
+            loaded.latest_metadata().map(BlockMetadata::block_hash),
+            Some(BlockHash([7; 32]))
+        );
+        assert_eq!(
+            loaded.latest_height(),
+            Some(BlockHeight::from_u32(SCANNED_HEIGHT))
+        );
+    }
+
+    #[tokio::test]
+    async fn accounts_do_not_share_files() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let cache = ScanCache::new(dir.path(), Network::TestNetwork, &ufvk(1), 1_001);
+        cache.store(&visitor()).await.expect("store");
+
+        for other in [
+            ScanCache::new(dir.path(), Network::TestNetwork, &ufvk(2), 1_001),
+            ScanCache::new(dir.path(), Network::TestNetwork, &ufvk(1), 1_002),
+        ] {
+            assert_ne!(other.path(), cache.path());
+            assert!(other.load().await.expect("load").is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn load_rejects_corrupted_file() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let cache = ScanCache::new(dir.path(), Network::TestNetwork, &ufvk(1), 1_001);
+        cache.store(&visitor()).await.expect("store");
+
+        let mut bytes = std::fs::read(cache.path()).expect("read cache");
+        if let Some(byte) = bytes.get_mut(HEADER_LEN) {
+            *byte ^= 1;
+        }
+        std::fs::write(cache.path(), bytes).expect("write cache");
+
+        let result = cache.load().await;
+        assert!(
+            matches!(result, Err(e) if e.kind() == io::ErrorKind::InvalidData),
+            "Expected checksum failure"
+        );
+    }
+}
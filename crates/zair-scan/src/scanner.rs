@@ -2,11 +2,15 @@
 
 mod block_scanner;
 mod error;
+mod notes;
+mod store;
 mod trees;
 mod visitor;
 
 pub use block_scanner::BlockScanner;
 pub use error::ScannerError;
+pub use notes::AccountNotes;
+pub use store::PersistentShardStore;
 pub use trees::CommitmentTrees;
 pub use visitor::ScanVisitor;
 pub use visitor::account_notes::AccountNotesVisitor;
@@ -0,0 +1,52 @@
+//! Source-independent access to an account's notes and their witnesses
+
+use super::{AccountNotesVisitor, ScannerError};
+use crate::user_nullifiers::{FoundNote, SaplingNote};
+
+/// Notes of an account together with note commitment witnesses at the snapshot
+///
+/// Implemented by [`AccountNotesVisitor`] for scanned notes and by
+/// [`ImportedNotes`](crate::wallet_db::ImportedNotes) for notes read from a wallet database.
+pub trait AccountNotes: Send + Sync {
+    /// Account's Sapling notes
+    fn sapling_notes(&self) -> &[FoundNote<SaplingNote>];
+
+    /// Account's Orchard notes
+    fn orchard_notes(&self) -> &[FoundNote<orchard::Note>];
+
+    /// Sapling witness for a note position
+    ///
+    /// # Errors
+    /// Returns `ScannerError` if the witness cannot be retrieved
+    fn sapling_witness(&self, position: u64) -> Result<Option<sapling::MerklePath>, ScannerError>;
+
+    /// Orchard witness for a note position
+    ///
+    /// # Errors
+    /// Returns `ScannerError` if the witness cannot be retrieved
+    fn orchard_witness(
+        &self,
+        position: u64,
+    ) -> Result<Option<orchard::tree::MerklePath>, ScannerError>;
+}
+
+impl AccountNotes for AccountNotesVisitor {
+    fn sapling_notes(&self) -> &[FoundNote<SaplingNote>] {
+        Self::sapling_notes(self)
+    }
+
+    fn orchard_notes(&self) -> &[FoundNote<orchard::Note>] {
+        Self::orchard_notes(self)
+    }
+
+    fn sapling_witness(&self, position: u64) -> Result<Option<sapling::MerklePath>, ScannerError> {
+        Self::sapling_witness(self, position)
+    }
+
+    fn orchard_witness(
+        &self,
+        position: u64,
+    ) -> Result<Option<orchard::tree::MerklePath>, ScannerError> {
+        Self::orchard_witness(self, position)
+    }
+}
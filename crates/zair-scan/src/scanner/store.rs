@@ -0,0 +1,330 @@
+//! `ShardStore` that can be saved to and restored from a byte stream
+//!
+//! Shards are kept in memory while scanning; [`PersistentShardStore::write`] and
+//! [`PersistentShardStore::read`] move the whole store, checkpoints included, to and from disk.
+//!
+//! Layout (all integers little-endian):
+//!
+//! | field        | encoding                                                              |
+//! |--------------|-----------------------------------------------------------------------|
+//! | cap          | `write_shard`                                                         |
+//! | shards       | `u64` count, then per shard: root level `u8`, root index `u64`, `write_shard` |
+//! | checkpoints  | `u64` count, then per checkpoint: id `u32`, tree state, removed marks |
+//!
+//! A tree state is a `0` tag for the empty tree or a `1` tag followed by the `u64` position.
+//! Removed marks are a `u64` count followed by `u64` positions.
+
+use std::collections::BTreeSet;
+use std::convert::Infallible;
+use std::io::{self, Read, Write};
+
+use incrementalmerkletree::{Address, Level, Position};
+use shardtree::store::memory::MemoryShardStore;
+use shardtree::store::{Checkpoint, ShardStore, TreeState};
+use shardtree::{LocatedPrunableTree, LocatedTree, PrunableTree};
+use zcash_client_backend::serialization::shardtree::{read_shard, write_shard};
+use zcash_primitives::merkle_tree::HashSer;
+use zcash_protocol::consensus::BlockHeight;
+
+const TREE_EMPTY_TAG: u8 = 0;
+const TREE_AT_POSITION_TAG: u8 = 1;
+
+/// In-memory shard store with a binary encoding for persistence between runs.
+#[derive(Debug)]
+pub struct PersistentShardStore<H> {
+    inner: MemoryShardStore<H, BlockHeight>,
+}
+
+impl<H: Clone> PersistentShardStore<H> {
+    /// Creates an empty store
+    #[must_use]
+    pub fn empty() -> Self {
+        Self {
+            inner: MemoryShardStore::empty(),
+        }
+    }
+}
+
+impl<H: Clone + HashSer> PersistentShardStore<H> {
+    /// Writes the cap, shards and checkpoints of the store
+    ///
+    /// # Errors
+    /// Returns an error if writing fails
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let Ok(cap) = self.inner.get_cap();
+        write_shard(&mut writer, &cap)?;
+
+        let Ok(roots) = self.inner.get_shard_roots();
+        write_len(&mut writer, roots.len())?;
+        for root in roots {
+            let Ok(Some(shard)) = self.inner.get_shard(root) else {
+                return Err(invalid_data(format!("missing shard at {root:?}")));
+            };
+            writer.write_all(&[u8::from(root.level())])?;
+            writer.write_all(&root.index().to_le_bytes())?;
+            write_shard(&mut writer, shard.root())?;
+        }
+
+        let mut checkpoints = Vec::new();
+        let Ok(()) = self
+            .inner
+            .for_each_checkpoint(usize::MAX, |id, checkpoint| {
+                checkpoints.push((*id, checkpoint.clone()));
+                Ok(())
+            });
+        write_len(&mut writer, checkpoints.len())?;
+        for (id, checkpoint) in checkpoints {
+            writer.write_all(&u32::from(id).to_le_bytes())?;
+            match checkpoint.tree_state() {
+                TreeState::Empty => writer.write_all(&[TREE_EMPTY_TAG])?,
+                TreeState::AtPosition(position) => {
+                    writer.write_all(&[TREE_AT_POSITION_TAG])?;
+                    writer.write_all(&u64::from(position).to_le_bytes())?;
+                }
+            }
+            write_len(&mut writer, checkpoint.marks_removed().len())?;
+            for position in checkpoint.marks_removed() {
+                writer.write_all(&u64::from(*position).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a store written by [`Self::write`]
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or the data is malformed
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut store = Self::empty();
+
+        let cap: PrunableTree<H> = read_shard(&mut reader)?;
+        let Ok(()) = store.inner.put_cap(cap);
+
+        for _ in 0..read_u64(&mut reader)? {
+            let [level] = read_array(&mut reader)?;
+            let index = read_u64(&mut reader)?;
+            let root = Address::from_parts(Level::from(level), index);
+            let tree = LocatedTree::from_parts(root, read_shard(&mut reader)?)
+                .map_err(|addr| invalid_data(format!("malformed shard at {addr:?}")))?;
+            let Ok(()) = store.inner.put_shard(tree);
+        }
+
+        for _ in 0..read_u64(&mut reader)? {
+            let id = BlockHeight::from_u32(u32::from_le_bytes(read_array(&mut reader)?));
+            let tree_state = match read_array(&mut reader)? {
+                [TREE_EMPTY_TAG] => TreeState::Empty,
+                [TREE_AT_POSITION_TAG] => {
+                    TreeState::AtPosition(Position::from(read_u64(&mut reader)?))
+                }
+                [tag] => return Err(invalid_data(format!("unknown tree state tag {tag}"))),
+            };
+            let mut marks_removed = BTreeSet::new();
+            for _ in 0..read_u64(&mut reader)? {
+                marks_removed.insert(Position::from(read_u64(&mut reader)?));
+            }
+            let Ok(()) = store
+                .inner
+                .add_checkpoint(id, Checkpoint::from_parts(tree_state, marks_removed));
+        }
+
+        Ok(store)
+    }
+}
+
+impl<H: Clone> ShardStore for PersistentShardStore<H> {
+    type H = H;
+    type CheckpointId = BlockHeight;
+    type Error = Infallible;
+
+    fn get_shard(&self, shard_root: Address) -> Result<Option<LocatedPrunableTree<H>>, Infallible> {
+        self.inner.get_shard(shard_root)
+    }
+
+    fn last_shard(&self) -> Result<Option<LocatedPrunableTree<H>>, Infallible> {
+        self.inner.last_shard()
+    }
+
+    fn put_shard(&mut self, subtree: LocatedPrunableTree<H>) -> Result<(), Infallible> {
+        self.inner.put_shard(subtree)
+    }
+
+    fn get_shard_roots(&self) -> Result<Vec<Address>, Infallible> {
+        self.inner.get_shard_roots()
+    }
+
+    fn truncate_shards(&mut self, shard_index: u64) -> Result<(), Infallible> {
+        self.inner.truncate_shards(shard_index)
+    }
+
+    fn get_cap(&self) -> Result<PrunableTree<H>, Infallible> {
+        self.inner.get_cap()
+    }
+
+    fn put_cap(&mut self, cap: PrunableTree<H>) -> Result<(), Infallible> {
+        self.inner.put_cap(cap)
+    }
+
+    fn min_checkpoint_id(&self) -> Result<Option<BlockHeight>, Infallible> {
+        self.inner.min_checkpoint_id()
+    }
+
+    fn max_checkpoint_id(&self) -> Result<Option<BlockHeight>, Infallible> {
+        self.inner.max_checkpoint_id()
+    }
+
+    fn add_checkpoint(
+        &mut self,
+        checkpoint_id: BlockHeight,
+        checkpoint: Checkpoint,
+    ) -> Result<(), Infallible> {
+        self.inner.add_checkpoint(checkpoint_id, checkpoint)
+    }
+
+    fn checkpoint_count(&self) -> Result<usize, Infallible> {
+        self.inner.checkpoint_count()
+    }
+
+    fn get_checkpoint_at_depth(
+        &self,
+        checkpoint_depth: usize,
+    ) -> Result<Option<(BlockHeight, Checkpoint)>, Infallible> {
+        self.inner.get_checkpoint_at_depth(checkpoint_depth)
+    }
+
+    fn get_checkpoint(
+        &self,
+        checkpoint_id: &BlockHeight,
+    ) -> Result<Option<Checkpoint>, Infallible> {
+        self.inner.get_checkpoint(checkpoint_id)
+    }
+
+    fn with_checkpoints<F>(&mut self, limit: usize, callback: F) -> Result<(), Infallible>
+    where
+        F: FnMut(&BlockHeight, &Checkpoint) -> Result<(), Infallible>,
+    {
+        self.inner.with_checkpoints(limit, callback)
+    }
+
+    fn for_each_checkpoint<F>(&self, limit: usize, callback: F) -> Result<(), Infallible>
+    where
+        F: FnMut(&BlockHeight, &Checkpoint) -> Result<(), Infallible>,
+    {
+        self.inner.for_each_checkpoint(limit, callback)
+    }
+
+    fn update_checkpoint_with<F>(
+        &mut self,
+        checkpoint_id: &BlockHeight,
+        update: F,
+    ) -> Result<bool, Infallible>
+    where
+        F: Fn(&mut Checkpoint) -> Result<(), Infallible>,
+    {
+        self.inner.update_checkpoint_with(checkpoint_id, update)
+    }
+
+    fn remove_checkpoint(&mut self, checkpoint_id: &BlockHeight) -> Result<(), Infallible> {
+        self.inner.remove_checkpoint(checkpoint_id)
+    }
+
+    fn truncate_checkpoints_retaining(
+        &mut self,
+        checkpoint_id: &BlockHeight,
+    ) -> Result<(), Infallible> {
+        self.inner.truncate_checkpoints_retaining(checkpoint_id)
+    }
+}
+
+pub(super) fn invalid_data(
+    error: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+pub(super) fn read_array<const N: usize, R: Read>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0_u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub(super) fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    read_array(reader).map(u64::from_le_bytes)
+}
+
+pub(super) fn write_len<W: Write>(writer: &mut W, len: usize) -> io::Result<()> {
+    let len = u64::try_from(len).map_err(invalid_data)?;
+    writer.write_all(&len.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use incrementalmerkletree::{Marking, Retention};
+    use shardtree::ShardTree;
+
+    use super::*;
+
+    type TestTree = ShardTree<PersistentShardStore<sapling::Node>, 32, 16>;
+
+    fn node(byte: u8) -> sapling::Node {
+        let mut repr = [0_u8; 32];
+        if let Some(first) = repr.first_mut() {
+            *first = byte;
+        }
+        Option::from(sapling::Node::from_bytes(repr)).expect("valid node")
+    }
+
+    #[test]
+    fn roundtrip_preserves_witnesses_and_checkpoints() {
+        let mut tree = TestTree::new(PersistentShardStore::empty(), 10);
+        for (byte, retention) in [
+            (1, Retention::Ephemeral),
+            (2, Retention::Marked),
+            (
+                3,
+                Retention::Checkpoint {
+                    id: BlockHeight::from_u32(10),
+                    marking: Marking::None,
+                },
+            ),
+            (4, Retention::Ephemeral),
+        ] {
+            tree.append(node(byte), retention).expect("append");
+        }
+        tree.checkpoint(BlockHeight::from_u32(11))
+            .expect("checkpoint");
+
+        let mut bytes = Vec::new();
+        tree.store().write(&mut bytes).expect("write");
+        let restored = TestTree::new(
+            PersistentShardStore::read(bytes.as_slice()).expect("read"),
+            10,
+        );
+
+        for checkpoint in [10, 11].map(BlockHeight::from_u32) {
+            assert_eq!(
+                restored.root_at_checkpoint_id(&checkpoint).expect("root"),
+                tree.root_at_checkpoint_id(&checkpoint).expect("root"),
+            );
+            assert_eq!(
+                restored
+                    .witness_at_checkpoint_id(Position::from(1), &checkpoint)
+                    .expect("witness"),
+                tree.witness_at_checkpoint_id(Position::from(1), &checkpoint)
+                    .expect("witness"),
+            );
+        }
+    }
+
+    #[test]
+    fn read_rejects_truncated_input() {
+        let mut tree = TestTree::new(PersistentShardStore::empty(), 10);
+        tree.append(node(1), Retention::Marked).expect("append");
+        tree.checkpoint(BlockHeight::from_u32(1))
+            .expect("checkpoint");
+        let mut bytes = Vec::new();
+        tree.store().write(&mut bytes).expect("write");
+        bytes.pop();
+
+        assert!(PersistentShardStore::<sapling::Node>::read(bytes.as_slice()).is_err());
+    }
+}
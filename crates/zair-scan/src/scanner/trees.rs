@@ -1,14 +1,15 @@
 //! Commitment tree management using `ShardTree`
 
+use std::io::{self, Read, Write};
+
 use incrementalmerkletree::frontier::Frontier;
 use incrementalmerkletree::{Hashable, Marking, MerklePath, Position, Retention};
 use orchard::tree::MerkleHashOrchard;
 use shardtree::ShardTree;
 use shardtree::store::ShardStore;
-use shardtree::store::memory::MemoryShardStore;
 use zcash_protocol::consensus::BlockHeight;
 
-use super::ScannerError;
+use super::{PersistentShardStore, ScannerError};
 
 const SAPLING_SHARD_HEIGHT: u8 = sapling::NOTE_COMMITMENT_TREE_DEPTH / 2;
 // Hardcoded to avoid usize->u8 cast; assertion ensures it matches the library
@@ -18,16 +19,13 @@ const ORCHARD_SHARD_HEIGHT: u8 = ORCHARD_TREE_DEPTH / 2;
 const MAX_CHECKPOINTS: usize = 100;
 
 type SaplingTree = ShardTree<
-    MemoryShardStore<sapling::Node, BlockHeight>,
+    PersistentShardStore<sapling::Node>,
     { sapling::NOTE_COMMITMENT_TREE_DEPTH },
     SAPLING_SHARD_HEIGHT,
 >;
 
-type OrchardTree = ShardTree<
-    MemoryShardStore<MerkleHashOrchard, BlockHeight>,
-    ORCHARD_TREE_DEPTH,
-    ORCHARD_SHARD_HEIGHT,
->;
+type OrchardTree =
+    ShardTree<PersistentShardStore<MerkleHashOrchard>, ORCHARD_TREE_DEPTH, ORCHARD_SHARD_HEIGHT>;
 
 /// Manages Sapling and Orchard commitment trees
 #[derive(Debug)]
@@ -46,7 +44,7 @@ impl CommitmentTrees {
         orchard_frontier: &Frontier<MerkleHashOrchard, ORCHARD_TREE_DEPTH>,
         checkpoint_height: BlockHeight,
     ) -> Result<Self, ScannerError> {
-        let mut sapling_tree = ShardTree::new(MemoryShardStore::empty(), MAX_CHECKPOINTS);
+        let mut sapling_tree = ShardTree::new(PersistentShardStore::empty(), MAX_CHECKPOINTS);
         init_frontier(
             &mut sapling_tree,
             sapling_frontier,
@@ -54,7 +52,7 @@ impl CommitmentTrees {
             "Sapling",
         )?;
 
-        let mut orchard_tree = ShardTree::new(MemoryShardStore::empty(), MAX_CHECKPOINTS);
+        let mut orchard_tree = ShardTree::new(PersistentShardStore::empty(), MAX_CHECKPOINTS);
         init_frontier(
             &mut orchard_tree,
             orchard_frontier,
@@ -68,6 +66,66 @@ impl CommitmentTrees {
         })
     }
 
+    /// Reads trees written by [`Self::write`]
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or the data is malformed
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let sapling = PersistentShardStore::read(&mut reader)?;
+        let orchard = PersistentShardStore::read(&mut reader)?;
+        Ok(Self {
+            sapling: ShardTree::new(sapling, MAX_CHECKPOINTS),
+            orchard: ShardTree::new(orchard, MAX_CHECKPOINTS),
+        })
+    }
+
+    /// Writes the Sapling and then the Orchard tree, checkpoints included
+    ///
+    /// # Errors
+    /// Returns an error if writing fails
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.sapling.store().write(&mut writer)?;
+        self.orchard.store().write(&mut writer)
+    }
+
+    /// Checkpoints both trees at `height`, so blocks without commitments can be rewound to
+    ///
+    /// Heights at or below the latest checkpoint are ignored.
+    ///
+    /// # Errors
+    /// Returns `ScannerError` if checkpointing fails
+    pub fn checkpoint(&mut self, height: BlockHeight) -> Result<(), ScannerError> {
+        self.sapling
+            .checkpoint(height)
+            .map_err(|e| ScannerError::TreeError(format!("Sapling checkpoint: {e:?}")))?;
+        self.orchard
+            .checkpoint(height)
+            .map_err(|e| ScannerError::TreeError(format!("Orchard checkpoint: {e:?}")))?;
+        Ok(())
+    }
+
+    /// Truncates both trees to their checkpoint at `height`
+    ///
+    /// Returns `false`, leaving the trees untouched, if either tree no longer retains that
+    /// checkpoint.
+    ///
+    /// # Errors
+    /// Returns `ScannerError` if truncation fails
+    pub fn truncate_to_checkpoint(&mut self, height: BlockHeight) -> Result<bool, ScannerError> {
+        let Ok(sapling_checkpoint) = self.sapling.store().get_checkpoint(&height);
+        let Ok(orchard_checkpoint) = self.orchard.store().get_checkpoint(&height);
+        if sapling_checkpoint.is_none() || orchard_checkpoint.is_none() {
+            return Ok(false);
+        }
+        self.sapling
+            .truncate_to_checkpoint(&height)
+            .map_err(|e| ScannerError::TreeError(format!("Sapling truncate: {e:?}")))?;
+        self.orchard
+            .truncate_to_checkpoint(&height)
+            .map_err(|e| ScannerError::TreeError(format!("Orchard truncate: {e:?}")))?;
+        Ok(true)
+    }
+
     /// Appends Sapling commitments to the Sapling note commitment merkle-tree
     ///
     /// # Errors
@@ -1,12 +1,22 @@
+use std::io::{self, Read, Write};
+
+use ff::PrimeField;
 use incrementalmerkletree::{Position, Retention};
 use orchard::tree::MerkleHashOrchard;
 use zcash_client_backend::data_api::BlockMetadata;
 use zcash_client_backend::proto::service::TreeState;
+use zcash_primitives::block::BlockHash;
+use zcash_protocol::TxId;
 use zcash_protocol::consensus::BlockHeight;
+use zip32::Scope;
 
 use super::ScanVisitor;
+use crate::scanner::store::{invalid_data, read_array, read_u64, write_len};
 use crate::scanner::{CommitmentTrees, ScannerError};
-use crate::user_nullifiers::{FoundNote, SaplingNote};
+use crate::user_nullifiers::{FoundNote, NoteMetadata, SaplingNote};
+
+const RSEED_BEFORE_ZIP212_TAG: u8 = 0;
+const RSEED_AFTER_ZIP212_TAG: u8 = 1;
 
 /// Account visitor tracking notes and commitment trees
 pub struct AccountNotesVisitor {
@@ -14,6 +24,7 @@ pub struct AccountNotesVisitor {
     sapling_notes: Vec<FoundNote<SaplingNote>>,
     orchard_notes: Vec<FoundNote<orchard::Note>>,
     latest_height: Option<BlockHeight>,
+    latest_metadata: Option<BlockMetadata>,
 }
 
 impl AccountNotesVisitor {
@@ -41,9 +52,80 @@ impl AccountNotesVisitor {
             sapling_notes: Vec::new(),
             orchard_notes: Vec::new(),
             latest_height: None,
+            latest_metadata: None,
+        })
+    }
+
+    /// Read a visitor written by [`Self::write`]
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or the data is malformed
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let latest_metadata = read_optional(&mut reader, read_block_metadata)?;
+        let trees = CommitmentTrees::read(&mut reader)?;
+        let sapling_notes = read_vec(&mut reader, read_sapling_note)?;
+        let orchard_notes = read_vec(&mut reader, read_orchard_note)?;
+        Ok(Self {
+            trees,
+            sapling_notes,
+            orchard_notes,
+            latest_height: latest_metadata.map(|metadata| metadata.block_height()),
+            latest_metadata,
         })
     }
 
+    /// Write the latest block metadata, commitment trees and found notes
+    ///
+    /// The latest visited height is only kept through its block metadata, so a rewound visitor
+    /// is read back without one.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write_optional(
+            &mut writer,
+            self.latest_metadata.as_ref(),
+            write_block_metadata,
+        )?;
+        self.trees.write(&mut writer)?;
+        write_len(&mut writer, self.sapling_notes.len())?;
+        for note in &self.sapling_notes {
+            write_sapling_note(&mut writer, note)?;
+        }
+        write_len(&mut writer, self.orchard_notes.len())?;
+        for note in &self.orchard_notes {
+            write_orchard_note(&mut writer, note)?;
+        }
+        Ok(())
+    }
+
+    /// Rewind to the state right after `height` was visited, dropping later notes
+    ///
+    /// Returns `false`, leaving the visitor untouched, if `height` was not visited or its
+    /// checkpoint has been pruned. Block metadata is only kept for the latest visited block, so
+    /// it is cleared by a rewind to an earlier height.
+    ///
+    /// # Errors
+    /// Returns `ScannerError` if the commitment trees cannot be truncated
+    pub fn rewind_to(&mut self, height: BlockHeight) -> Result<bool, ScannerError> {
+        match self.latest_height {
+            Some(latest) if latest == height => return Ok(true),
+            Some(latest) if latest > height => {}
+            _ => return Ok(false),
+        }
+        if !self.trees.truncate_to_checkpoint(height)? {
+            return Ok(false);
+        }
+        let cutoff = u64::from(height);
+        self.sapling_notes
+            .retain(|note| note.metadata.height <= cutoff);
+        self.orchard_notes
+            .retain(|note| note.metadata.height <= cutoff);
+        self.latest_height = Some(height);
+        self.latest_metadata = None;
+        Ok(true)
+    }
+
     /// Get account's Sapling notes
     #[must_use]
     pub fn sapling_notes(&self) -> &[FoundNote<SaplingNote>] {
@@ -62,6 +144,12 @@ impl AccountNotesVisitor {
         self.latest_height
     }
 
+    /// Get metadata of the latest visited block, the starting point for resuming a scan
+    #[must_use]
+    pub const fn latest_metadata(&self) -> Option<&BlockMetadata> {
+        self.latest_metadata.as_ref()
+    }
+
     /// Get Sapling witness for a note position
     ///
     /// # Errors
@@ -122,7 +210,229 @@ impl ScanVisitor for AccountNotesVisitor {
         let _ = self.trees.append_orchard(&[(node, retention)]);
     }
 
-    fn on_block_scanned(&mut self, height: BlockHeight, _metadata: &BlockMetadata) {
+    fn on_block_scanned(&mut self, height: BlockHeight, metadata: &BlockMetadata) {
+        let _ = self.trees.checkpoint(height);
         self.latest_height = Some(height);
+        self.latest_metadata = Some(*metadata);
+    }
+}
+
+fn read_optional<R: Read, T>(
+    reader: &mut R,
+    read: impl FnOnce(&mut R) -> io::Result<T>,
+) -> io::Result<Option<T>> {
+    match read_array(reader)? {
+        [0] => Ok(None),
+        [1] => read(reader).map(Some),
+        [tag] => Err(invalid_data(format!("unknown option tag {tag}"))),
+    }
+}
+
+fn write_optional<W: Write, T>(
+    writer: &mut W,
+    value: Option<&T>,
+    write: impl FnOnce(&mut W, &T) -> io::Result<()>,
+) -> io::Result<()> {
+    match value {
+        None => writer.write_all(&[0]),
+        Some(value) => {
+            writer.write_all(&[1])?;
+            write(writer, value)
+        }
+    }
+}
+
+fn read_vec<R: Read, T>(
+    reader: &mut R,
+    read: impl Fn(&mut R) -> io::Result<T>,
+) -> io::Result<Vec<T>> {
+    (0..read_u64(reader)?).map(|_| read(reader)).collect()
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    read_array(reader).map(u32::from_le_bytes)
+}
+
+fn read_block_metadata<R: Read>(reader: &mut R) -> io::Result<BlockMetadata> {
+    let height = BlockHeight::from_u32(read_u32(reader)?);
+    let hash = BlockHash(read_array(reader)?);
+    let sapling_tree_size = read_optional(reader, read_u32)?;
+    let orchard_tree_size = read_optional(reader, read_u32)?;
+    Ok(BlockMetadata::from_parts(
+        height,
+        hash,
+        sapling_tree_size,
+        orchard_tree_size,
+    ))
+}
+
+fn write_block_metadata<W: Write>(writer: &mut W, metadata: &BlockMetadata) -> io::Result<()> {
+    let write_u32 = |writer: &mut W, value: &u32| writer.write_all(&value.to_le_bytes());
+    writer.write_all(&u32::from(metadata.block_height()).to_le_bytes())?;
+    writer.write_all(&metadata.block_hash().0)?;
+    write_optional(writer, metadata.sapling_tree_size().as_ref(), write_u32)?;
+    write_optional(writer, metadata.orchard_tree_size().as_ref(), write_u32)
+}
+
+const fn scope_tag(scope: Scope) -> u8 {
+    match scope {
+        Scope::External => 0,
+        Scope::Internal => 1,
+    }
+}
+
+fn read_scope<R: Read>(reader: &mut R) -> io::Result<Scope> {
+    match read_array(reader)? {
+        [0] => Ok(Scope::External),
+        [1] => Ok(Scope::Internal),
+        [tag] => Err(invalid_data(format!("unknown scope tag {tag}"))),
+    }
+}
+
+fn read_note_metadata<R: Read>(reader: &mut R) -> io::Result<NoteMetadata> {
+    Ok(NoteMetadata {
+        height: read_u64(reader)?,
+        txid: TxId::from_bytes(read_array(reader)?),
+        scope: read_scope(reader)?,
+        position: read_u64(reader)?,
+    })
+}
+
+fn write_note_metadata<W: Write>(writer: &mut W, metadata: &NoteMetadata) -> io::Result<()> {
+    writer.write_all(&metadata.height.to_le_bytes())?;
+    writer.write_all(metadata.txid.as_ref())?;
+    writer.write_all(&[scope_tag(metadata.scope)])?;
+    writer.write_all(&metadata.position.to_le_bytes())
+}
+
+fn read_sapling_note<R: Read>(reader: &mut R) -> io::Result<FoundNote<SaplingNote>> {
+    let recipient = sapling::PaymentAddress::from_bytes(&read_array(reader)?)
+        .ok_or_else(|| invalid_data("invalid Sapling recipient"))?;
+    let value = sapling::value::NoteValue::from_raw(read_u64(reader)?);
+    let rseed = match read_array(reader)? {
+        [RSEED_BEFORE_ZIP212_TAG] => sapling::Rseed::BeforeZip212(
+            Option::from(PrimeField::from_repr(read_array(reader)?))
+                .ok_or_else(|| invalid_data("invalid Sapling rcm"))?,
+        ),
+        [RSEED_AFTER_ZIP212_TAG] => sapling::Rseed::AfterZip212(read_array(reader)?),
+        [tag] => return Err(invalid_data(format!("unknown Sapling rseed tag {tag}"))),
+    };
+    let position = read_u64(reader)?;
+    let scope = read_scope(reader)?;
+    Ok(FoundNote {
+        note: SaplingNote {
+            note: sapling::Note::from_parts(recipient, value, rseed),
+            position,
+            scope,
+        },
+        metadata: read_note_metadata(reader)?,
+    })
+}
+
+fn write_sapling_note<W: Write>(writer: &mut W, note: &FoundNote<SaplingNote>) -> io::Result<()> {
+    let sapling_note = &note.note.note;
+    writer.write_all(&sapling_note.recipient().to_bytes())?;
+    writer.write_all(&sapling_note.value().inner().to_le_bytes())?;
+    match sapling_note.rseed() {
+        sapling::Rseed::BeforeZip212(rcm) => {
+            writer.write_all(&[RSEED_BEFORE_ZIP212_TAG])?;
+            writer.write_all(&rcm.to_repr())?;
+        }
+        sapling::Rseed::AfterZip212(rseed) => {
+            writer.write_all(&[RSEED_AFTER_ZIP212_TAG])?;
+            writer.write_all(rseed)?;
+        }
+    }
+    writer.write_all(&note.note.position.to_le_bytes())?;
+    writer.write_all(&[scope_tag(note.note.scope)])?;
+    write_note_metadata(writer, &note.metadata)
+}
+
+fn read_orchard_note<R: Read>(reader: &mut R) -> io::Result<FoundNote<orchard::Note>> {
+    let recipient: Option<orchard::Address> =
+        orchard::Address::from_raw_address_bytes(&read_array(reader)?).into();
+    let value = orchard::value::NoteValue::from_raw(read_u64(reader)?);
+    let rho: Option<orchard::note::Rho> =
+        orchard::note::Rho::from_bytes(&read_array(reader)?).into();
+    let rseed_bytes = read_array(reader)?;
+    let note = recipient.zip(rho).and_then(|(recipient, rho)| {
+        let rseed: Option<orchard::note::RandomSeed> =
+            orchard::note::RandomSeed::from_bytes(rseed_bytes, &rho).into();
+        rseed.and_then(|rseed| orchard::Note::from_parts(recipient, value, rho, rseed).into())
+    });
+    Ok(FoundNote {
+        note: note.ok_or_else(|| invalid_data("invalid Orchard note"))?,
+        metadata: read_note_metadata(reader)?,
+    })
+}
+
+fn write_orchard_note<W: Write>(writer: &mut W, note: &FoundNote<orchard::Note>) -> io::Result<()> {
+    writer.write_all(&note.note.recipient().to_raw_address_bytes())?;
+    writer.write_all(&note.note.value().inner().to_le_bytes())?;
+    writer.write_all(&note.note.rho().to_bytes())?;
+    writer.write_all(note.note.rseed().as_bytes())?;
+    write_note_metadata(writer, &note.metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use incrementalmerkletree::Marking;
+
+    use super::*;
+
+    fn node(byte: u8) -> sapling::Node {
+        let mut repr = [0_u8; 32];
+        if let Some(first) = repr.first_mut() {
+            *first = byte;
+        }
+        Option::from(sapling::Node::from_bytes(repr)).expect("valid node")
+    }
+
+    fn scan_block(visitor: &mut AccountNotesVisitor, height: u32, commitments: u8) {
+        let height = BlockHeight::from_u32(height);
+        for index in 1..=commitments {
+            let retention = if index == commitments {
+                Retention::Checkpoint {
+                    id: height,
+                    marking: Marking::None,
+                }
+            } else {
+                Retention::Ephemeral
+            };
+            visitor.on_sapling_commitment(node(index), retention);
+        }
+        visitor.on_block_scanned(
+            height,
+            &BlockMetadata::from_parts(height, BlockHash([0; 32]), None, None),
+        );
+    }
+
+    #[test]
+    fn rewind_restores_earlier_anchor() {
+        let mut visitor = AccountNotesVisitor::from_tree_state(&TreeState {
+            height: 99,
+            ..TreeState::default()
+        })
+        .expect("empty tree state");
+        scan_block(&mut visitor, 100, 2);
+        let anchor = visitor.sapling_anchor().expect("anchor");
+        // A block without commitments is still a rewind target.
+        scan_block(&mut visitor, 101, 0);
+        scan_block(&mut visitor, 102, 3);
+        assert_ne!(visitor.sapling_anchor().expect("anchor"), anchor);
+
+        assert!(
+            !visitor
+                .rewind_to(BlockHeight::from_u32(103))
+                .expect("rewind")
+        );
+        assert!(
+            visitor
+                .rewind_to(BlockHeight::from_u32(101))
+                .expect("rewind")
+        );
+        assert_eq!(visitor.sapling_anchor().expect("anchor"), anchor);
+        assert_eq!(visitor.latest_height(), Some(BlockHeight::from_u32(101)));
+        assert!(visitor.latest_metadata().is_none());
     }
 }
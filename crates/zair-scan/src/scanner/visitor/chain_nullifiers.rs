@@ -9,6 +9,12 @@ pub struct ChainNullifiersVisitor {
 }
 
 impl ChainNullifiersVisitor {
+    /// Number of Sapling and Orchard nullifiers collected, duplicates included
+    #[must_use]
+    pub const fn collected(&self) -> (usize, usize) {
+        (self.sapling_nullifiers.len(), self.orchard_nullifiers.len())
+    }
+
     /// Get collected Sapling nullifiers
     #[must_use]
     pub fn sanitise_nullifiers(self) -> (SanitiseNullifiers, SanitiseNullifiers) {
@@ -0,0 +1,819 @@
+//! Import of notes and witnesses from a `zcash_client_sqlite` wallet database.
+//!
+//! A synced wallet already holds the account's received notes, their commitment tree positions
+//! and the note commitment trees, so claims can be prepared without scanning the chain. The
+//! importer reads the notes mined up to the snapshot height from the
+//! `{sapling,orchard}_received_notes` tables and loads the `{pool}_tree_*` shard tables into a
+//! [`PersistentShardStore`].
+//!
+//! Wallets only keep checkpoints near their tip, so witnesses are taken at the latest wallet
+//! checkpoint and rewound to the snapshot with the snapshot note commitment frontier: path nodes
+//! left of the frontier do not change after the snapshot, the node holding its tip is recomputed
+//! from the frontier and nodes right of it are empty. Every path is checked against the snapshot
+//! root before it is returned.
+//!
+//! Notes are rebuilt from their stored `rcm`, which commits to the same note as its ZIP 212 seed.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use ff::PrimeField;
+use incrementalmerkletree::frontier::{Frontier, NonEmptyFrontier};
+use incrementalmerkletree::{Address, Hashable, Level, MerklePath, Position};
+use orchard::tree::MerkleHashOrchard;
+use rusqlite::{Connection, OpenFlags, OptionalExtension as _, params};
+use shardtree::store::{Checkpoint, ShardStore as _, TreeState as CheckpointState};
+use shardtree::{LocatedTree, ShardTree};
+use zcash_client_backend::proto::service::TreeState;
+use zcash_client_backend::serialization::shardtree::read_shard;
+use zcash_keys::keys::UnifiedFullViewingKey;
+use zcash_primitives::merkle_tree::HashSer;
+use zcash_protocol::TxId;
+use zcash_protocol::consensus::Network;
+use zip32::Scope;
+
+use crate::Pool;
+use crate::scanner::{AccountNotes, PersistentShardStore, ScannerError};
+use crate::user_nullifiers::{FoundNote, NoteMetadata, SaplingNote};
+
+/// Shard height used by `zcash_client_sqlite` for both note commitment trees.
+const SHARD_HEIGHT: u8 = 16;
+const ORCHARD_TREE_DEPTH: u8 = 32;
+const _: () = assert!(orchard::NOTE_COMMITMENT_TREE_DEPTH == 32);
+/// Imported trees are only read, so checkpoints are never pruned.
+const MAX_CHECKPOINTS: usize = usize::MAX;
+/// `recipient_key_scope` values of `zcash_client_sqlite`.
+const EXTERNAL_SCOPE: i64 = 0;
+const INTERNAL_SCOPE: i64 = 1;
+
+type WalletTree<H, const DEPTH: u8> = ShardTree<PersistentShardStore<H>, DEPTH, SHARD_HEIGHT>;
+
+/// Errors that can occur while importing from a wallet database
+#[derive(Debug, thiserror::Error)]
+pub enum WalletDbError {
+    /// The database could not be opened or queried
+    #[error("Wallet database error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    /// The wallet has no account with the viewing key
+    #[error("The wallet database has no account with this viewing key")]
+    AccountNotFound,
+
+    /// The pool has no notes or note commitment tree
+    #[error("The {0} pool has no shielded notes")]
+    NotShielded(Pool),
+
+    /// A received note row cannot be turned into a note
+    #[error("Invalid {pool} note {id} in the wallet database: {reason}")]
+    InvalidNote {
+        /// Pool of the note
+        pool: Pool,
+        /// Row id of the note
+        id: i64,
+        /// What is wrong with the row
+        reason: String,
+    },
+
+    /// The snapshot tree state or a wallet tree table cannot be decoded
+    #[error("Invalid {pool} note commitment tree: {reason}")]
+    InvalidTree {
+        /// Pool of the tree
+        pool: Pool,
+        /// What is wrong with the tree
+        reason: String,
+    },
+
+    /// The wallet has not scanned up to the snapshot height
+    #[error("The wallet {pool} tree has not been scanned up to snapshot height {snapshot_height}")]
+    NotSynced {
+        /// Pool of the tree
+        pool: Pool,
+        /// Snapshot height
+        snapshot_height: u64,
+    },
+
+    /// The wallet tree cannot witness a note
+    #[error("Cannot witness the {pool} note at position {position}: {reason}")]
+    MissingWitness {
+        /// Pool of the note
+        pool: Pool,
+        /// Note commitment tree position
+        position: u64,
+        /// Why no witness is available
+        reason: String,
+    },
+
+    /// A rewound witness does not lead to the snapshot root
+    #[error("The {pool} witness for position {position} does not match the snapshot root")]
+    RootMismatch {
+        /// Pool of the note
+        pool: Pool,
+        /// Note commitment tree position
+        position: u64,
+    },
+}
+
+/// Notes imported from a wallet database, with their witnesses at the snapshot
+#[derive(Debug, Default)]
+pub struct ImportedNotes {
+    sapling_notes: Vec<FoundNote<SaplingNote>>,
+    orchard_notes: Vec<FoundNote<orchard::Note>>,
+    sapling_witnesses: BTreeMap<u64, sapling::MerklePath>,
+    orchard_witnesses: BTreeMap<u64, orchard::tree::MerklePath>,
+}
+
+impl AccountNotes for ImportedNotes {
+    fn sapling_notes(&self) -> &[FoundNote<SaplingNote>] {
+        &self.sapling_notes
+    }
+
+    fn orchard_notes(&self) -> &[FoundNote<orchard::Note>] {
+        &self.orchard_notes
+    }
+
+    fn sapling_witness(&self, position: u64) -> Result<Option<sapling::MerklePath>, ScannerError> {
+        Ok(self.sapling_witnesses.get(&position).cloned())
+    }
+
+    fn orchard_witness(
+        &self,
+        position: u64,
+    ) -> Result<Option<orchard::tree::MerklePath>, ScannerError> {
+        Ok(self.orchard_witnesses.get(&position).cloned())
+    }
+}
+
+/// Read-only connection to a `zcash_client_sqlite` wallet database
+#[derive(Debug)]
+pub struct WalletDb {
+    connection: Connection,
+}
+
+/// Received note row with the pool-independent columns decoded
+struct ReceivedNote {
+    id: i64,
+    diversifier: [u8; 11],
+    value: u64,
+    metadata: NoteMetadata,
+    /// `rcm` for Sapling, `rho` and `rseed` for Orchard
+    randomness: Vec<[u8; 32]>,
+}
+
+impl WalletDb {
+    /// Open the wallet database at `path` read-only
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be opened
+    pub fn open(path: &Path) -> Result<Self, WalletDbError> {
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(Self { connection })
+    }
+
+    /// Import the notes of the `ufvk` account mined up to the height of `snapshot`
+    ///
+    /// `snapshot` is the chain tree state at the snapshot height; its frontiers are used to
+    /// rewind the wallet witnesses. Pools without a viewing key in `ufvk` are skipped.
+    ///
+    /// # Errors
+    /// Returns an error if the account is missing, a row or tree cannot be decoded, or a note
+    /// cannot be witnessed at the snapshot
+    pub fn import_notes(
+        &self,
+        network: Network,
+        ufvk: &UnifiedFullViewingKey,
+        snapshot: &TreeState,
+    ) -> Result<ImportedNotes, WalletDbError> {
+        let account_id = self
+            .connection
+            .query_row(
+                "SELECT id FROM accounts WHERE ufvk = ?1",
+                [ufvk.encode(&network)],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?
+            .ok_or(WalletDbError::AccountNotFound)?;
+        let mut imported = ImportedNotes::default();
+
+        if let Some(dfvk) = ufvk.sapling() {
+            let pool = Pool::Sapling;
+            let frontier = snapshot
+                .sapling_tree()
+                .map_err(|e| invalid_tree(pool, &e))?
+                .to_frontier();
+            let rows = self.received_notes(pool, account_id, snapshot.height)?;
+            if !rows.is_empty() {
+                let tree = self.load_tree(pool, snapshot.height)?;
+                for row in rows {
+                    let note = sapling_note(dfvk, row)?;
+                    let leaf = sapling::Node::from_cmu(&note.note.note.cmu());
+                    let path = snapshot_witness(&tree, &frontier, pool, note.note.position, leaf)?;
+                    imported.sapling_witnesses.insert(note.note.position, path);
+                    imported.sapling_notes.push(note);
+                }
+            }
+        }
+
+        if let Some(fvk) = ufvk.orchard() {
+            let pool = Pool::Orchard;
+            let frontier = snapshot
+                .orchard_tree()
+                .map_err(|e| invalid_tree(pool, &e))?
+                .to_frontier();
+            let rows = self.received_notes(pool, account_id, snapshot.height)?;
+            if !rows.is_empty() {
+                let tree = self.load_tree::<_, ORCHARD_TREE_DEPTH>(pool, snapshot.height)?;
+                for row in rows {
+                    let note = orchard_note(fvk, row)?;
+                    let leaf = MerkleHashOrchard::from_cmx(&note.note.commitment().into());
+                    let position = note.metadata.position;
+                    let path = snapshot_witness(&tree, &frontier, pool, position, leaf)?;
+                    imported.orchard_witnesses.insert(position, path.into());
+                    imported.orchard_notes.push(note);
+                }
+            }
+        }
+
+        Ok(imported)
+    }
+
+    fn received_notes(
+        &self,
+        pool: Pool,
+        account_id: i64,
+        snapshot_height: u64,
+    ) -> Result<Vec<ReceivedNote>, WalletDbError> {
+        let (table, randomness) = match pool {
+            Pool::Sapling => ("sapling_received_notes", ["rn.rcm"].as_slice()),
+            Pool::Orchard => ("orchard_received_notes", ["rn.rho", "rn.rseed"].as_slice()),
+            Pool::Transparent => return Err(WalletDbError::NotShielded(pool)),
+        };
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT rn.id, rn.diversifier, rn.value, rn.commitment_tree_position,
+                    rn.recipient_key_scope, t.txid, t.mined_height, {}
+             FROM {table} rn
+             JOIN transactions t ON t.id_tx = rn.transaction_id
+             WHERE rn.account_id = ?1
+               AND t.mined_height <= ?2
+               AND rn.commitment_tree_position IS NOT NULL
+             ORDER BY rn.commitment_tree_position",
+            randomness.join(", ")
+        ))?;
+        let snapshot_height = i64::try_from(snapshot_height).map_err(|e| invalid_tree(pool, &e))?;
+        let mut rows = statement.query(params![account_id, snapshot_height])?;
+
+        let mut notes = Vec::new();
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let invalid = |reason: String| WalletDbError::InvalidNote { pool, id, reason };
+            let blob = |index: usize| row.get::<_, Vec<u8>>(index);
+            let integer = |index: usize, column: &str| -> Result<u64, WalletDbError> {
+                u64::try_from(row.get::<_, i64>(index)?)
+                    .map_err(|_| invalid(format!("negative {column}")))
+            };
+
+            let diversifier = blob(1)?
+                .try_into()
+                .map_err(|_| invalid("diversifier is not 11 bytes".into()))?;
+            let scope = match row.get::<_, Option<i64>>(4)? {
+                None | Some(EXTERNAL_SCOPE) => Scope::External,
+                Some(INTERNAL_SCOPE) => Scope::Internal,
+                Some(other) => return Err(invalid(format!("unsupported key scope {other}"))),
+            };
+            let txid = blob(5)?
+                .try_into()
+                .map_err(|_| invalid("txid is not 32 bytes".into()))?;
+            let randomness = (7..)
+                .zip(randomness)
+                .map(|(index, column)| {
+                    blob(index)?
+                        .try_into()
+                        .map_err(|_| invalid(format!("{column} is not 32 bytes")))
+                })
+                .collect::<Result<_, _>>()?;
+
+            notes.push(ReceivedNote {
+                id,
+                diversifier,
+                value: integer(2, "value")?,
+                metadata: NoteMetadata {
+                    height: integer(6, "mined height")?,
+                    txid: TxId::from_bytes(txid),
+                    scope,
+                    position: integer(3, "commitment tree position")?,
+                },
+                randomness,
+            });
+        }
+        Ok(notes)
+    }
+
+    /// Load the shard tree tables of `pool`, requiring a checkpoint at or past the snapshot
+    fn load_tree<H, const DEPTH: u8>(
+        &self,
+        pool: Pool,
+        snapshot_height: u64,
+    ) -> Result<WalletTree<H, DEPTH>, WalletDbError>
+    where
+        H: Hashable + HashSer + Clone + PartialEq,
+    {
+        let prefix = match pool {
+            Pool::Sapling => "sapling",
+            Pool::Orchard => "orchard",
+            Pool::Transparent => return Err(WalletDbError::NotShielded(pool)),
+        };
+        let invalid = |e: io::Error| invalid_tree(pool, &e);
+        let mut store = PersistentShardStore::empty();
+
+        let cap = self
+            .connection
+            .query_row(
+                &format!("SELECT cap_data FROM {prefix}_tree_cap WHERE cap_id = 0"),
+                [],
+                |row| row.get::<_, Vec<u8>>(0),
+            )
+            .optional()?;
+        if let Some(cap) = cap {
+            let Ok(()) = store.put_cap(read_shard(cap.as_slice()).map_err(invalid)?);
+        }
+
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT shard_index, shard_data, root_hash FROM {prefix}_tree_shards"
+        ))?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let index = u64::try_from(row.get::<_, i64>(0)?).map_err(|e| invalid_tree(pool, &e))?;
+            let shard_data: Vec<u8> = row.get(1)?;
+            let root_hash: Option<Vec<u8>> = row.get(2)?;
+            let address = Address::from_parts(Level::from(SHARD_HEIGHT), index);
+            let mut shard = LocatedTree::from_parts(
+                address,
+                read_shard(shard_data.as_slice()).map_err(invalid)?,
+            )
+            .map_err(|addr| invalid_tree(pool, &format!("malformed shard at {addr:?}")))?;
+            if let Some(root_hash) = root_hash {
+                let root = H::read(root_hash.as_slice()).map_err(invalid)?;
+                shard = shard.reannotate_root(Some(Arc::new(root)));
+            }
+            let Ok(()) = store.put_shard(shard);
+        }
+
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT checkpoint_id, position FROM {prefix}_tree_checkpoints"
+        ))?;
+        let mut marks_statement = self.connection.prepare(&format!(
+            "SELECT mark_removed_position FROM {prefix}_tree_checkpoint_marks_removed
+             WHERE checkpoint_id = ?1"
+        ))?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let id: u32 = row.get(0)?;
+            let state = match row.get::<_, Option<i64>>(1)? {
+                None => CheckpointState::Empty,
+                Some(position) => CheckpointState::AtPosition(position_from(pool, position)?),
+            };
+            let marks_removed = marks_statement
+                .query_map([id], |row| row.get::<_, i64>(0))?
+                .map(|position| position_from(pool, position?))
+                .collect::<Result<BTreeSet<_>, _>>()?;
+            let Ok(()) =
+                store.add_checkpoint(id.into(), Checkpoint::from_parts(state, marks_removed));
+        }
+
+        let Ok(latest) = store.max_checkpoint_id();
+        if latest.is_none_or(|latest| u64::from(latest) < snapshot_height) {
+            return Err(WalletDbError::NotSynced {
+                pool,
+                snapshot_height,
+            });
+        }
+        Ok(ShardTree::new(store, MAX_CHECKPOINTS))
+    }
+}
+
+fn sapling_note(
+    dfvk: &sapling::zip32::DiversifiableFullViewingKey,
+    row: ReceivedNote,
+) -> Result<FoundNote<SaplingNote>, WalletDbError> {
+    let invalid = |reason: &str| WalletDbError::InvalidNote {
+        pool: Pool::Sapling,
+        id: row.id,
+        reason: reason.into(),
+    };
+    let diversifier = sapling::Diversifier(row.diversifier);
+    let recipient = match row.metadata.scope {
+        Scope::External => dfvk.diversified_address(diversifier),
+        Scope::Internal => dfvk.diversified_change_address(diversifier),
+    }
+    .ok_or_else(|| invalid("diversifier is invalid"))?;
+    let [rcm] = row.randomness.as_slice() else {
+        return Err(invalid("missing rcm"));
+    };
+    let rcm = Option::from(PrimeField::from_repr(*rcm)).ok_or_else(|| invalid("invalid rcm"))?;
+    let note = sapling::Note::from_parts(
+        recipient,
+        sapling::value::NoteValue::from_raw(row.value),
+        sapling::Rseed::BeforeZip212(rcm),
+    );
+    Ok(FoundNote {
+        note: SaplingNote {
+            note,
+            position: row.metadata.position,
+            scope: row.metadata.scope,
+        },
+        metadata: row.metadata,
+    })
+}
+
+fn orchard_note(
+    fvk: &orchard::keys::FullViewingKey,
+    row: ReceivedNote,
+) -> Result<FoundNote<orchard::Note>, WalletDbError> {
+    let invalid = || WalletDbError::InvalidNote {
+        pool: Pool::Orchard,
+        id: row.id,
+        reason: "invalid rho, rseed or diversifier".into(),
+    };
+    let [rho, rseed] = row.randomness.as_slice() else {
+        return Err(invalid());
+    };
+    let recipient = fvk.address(
+        orchard::keys::Diversifier::from_bytes(row.diversifier),
+        row.metadata.scope,
+    );
+    let rho: Option<orchard::note::Rho> = orchard::note::Rho::from_bytes(rho).into();
+    let note = rho.and_then(|rho| {
+        let rseed: Option<orchard::note::RandomSeed> =
+            orchard::note::RandomSeed::from_bytes(*rseed, &rho).into();
+        rseed.and_then(|rseed| {
+            orchard::Note::from_parts(
+                recipient,
+                orchard::value::NoteValue::from_raw(row.value),
+                rho,
+                rseed,
+            )
+            .into()
+        })
+    });
+    Ok(FoundNote {
+        note: note.ok_or_else(invalid)?,
+        metadata: row.metadata,
+    })
+}
+
+/// Witness of the leaf at `position` as of the `snapshot` frontier
+fn snapshot_witness<H, const DEPTH: u8>(
+    tree: &WalletTree<H, DEPTH>,
+    snapshot: &Frontier<H, DEPTH>,
+    pool: Pool,
+    position: u64,
+    leaf: H,
+) -> Result<MerklePath<H, DEPTH>, WalletDbError>
+where
+    H: Hashable + Clone + PartialEq,
+{
+    let missing = |reason: String| WalletDbError::MissingWitness {
+        pool,
+        position,
+        reason,
+    };
+    let frontier = snapshot
+        .value()
+        .ok_or_else(|| missing("the snapshot tree is empty".into()))?;
+    let tip_path = tree
+        .witness_at_checkpoint_depth(Position::from(position), 0)
+        .map_err(|e| missing(format!("{e:?}")))?
+        .ok_or_else(|| missing("the wallet tree has no checkpoint".into()))?;
+    let path = rewind_path(&tip_path, frontier)
+        .ok_or_else(|| missing("the note is past the snapshot frontier".into()))?;
+    if path.root(leaf) != snapshot.root() {
+        return Err(WalletDbError::RootMismatch { pool, position });
+    }
+    Ok(path)
+}
+
+/// Rewind a path taken at a later tree state to the tree ending at `frontier`
+///
+/// Returns `None` if the path position is past the frontier.
+fn rewind_path<H, const DEPTH: u8>(
+    path: &MerklePath<H, DEPTH>,
+    frontier: &NonEmptyFrontier<H>,
+) -> Option<MerklePath<H, DEPTH>>
+where
+    H: Hashable + Clone,
+{
+    let position = path.position();
+    let tip = frontier.position();
+    if position > tip {
+        return None;
+    }
+    let elems = path
+        .path_elems()
+        .iter()
+        .zip(0_u8..)
+        .map(|(node, level)| {
+            let level = Level::from(level);
+            let sibling = Address::above_position(level, position).sibling();
+            if sibling.position_range_start() > tip {
+                H::empty_root(level)
+            } else if sibling.position_range().contains(&tip) {
+                frontier.root(Some(level))
+            } else {
+                node.clone()
+            }
+        })
+        .collect();
+    MerklePath::from_parts(elems, position).ok()
+}
+
+fn position_from(pool: Pool, position: i64) -> Result<Position, WalletDbError> {
+    u64::try_from(position)
+        .map(Position::from)
+        .map_err(|e| invalid_tree(pool, &e))
+}
+
+fn invalid_tree(pool: Pool, reason: &impl ToString) -> WalletDbError {
+    WalletDbError::InvalidTree {
+        pool,
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use incrementalmerkletree::frontier::CommitmentTree;
+    use incrementalmerkletree::{Marking, Retention};
+    use zcash_client_backend::serialization::shardtree::write_shard;
+    use zcash_keys::keys::UnifiedSpendingKey;
+    use zcash_primitives::merkle_tree::write_commitment_tree;
+    use zcash_protocol::consensus::BlockHeight;
+    use zip32::AccountId;
+
+    use super::*;
+
+    const SNAPSHOT_HEIGHT: u32 = 100;
+    const WALLET_TIP: u32 = 101;
+    const NOTE_POSITION: u64 = 1;
+    const NOTE_VALUE: u64 = 5_000;
+
+    /// The parts of the `zcash_client_sqlite` schema read by the importer.
+    const SCHEMA: &str = "
+        CREATE TABLE accounts (id INTEGER PRIMARY KEY, ufvk TEXT);
+        CREATE TABLE transactions (id_tx INTEGER PRIMARY KEY, txid BLOB NOT NULL, mined_height INTEGER);
+        CREATE TABLE sapling_received_notes (
+            id INTEGER PRIMARY KEY, transaction_id INTEGER NOT NULL, account_id INTEGER NOT NULL,
+            diversifier BLOB NOT NULL, value INTEGER NOT NULL, rcm BLOB NOT NULL,
+            commitment_tree_position INTEGER, recipient_key_scope INTEGER
+        );
+        CREATE TABLE orchard_received_notes (
+            id INTEGER PRIMARY KEY, transaction_id INTEGER NOT NULL, account_id INTEGER NOT NULL,
+            diversifier BLOB NOT NULL, value INTEGER NOT NULL, rho BLOB NOT NULL,
+            rseed BLOB NOT NULL, commitment_tree_position INTEGER, recipient_key_scope INTEGER
+        );
+        CREATE TABLE sapling_tree_shards (
+            shard_index INTEGER PRIMARY KEY, subtree_end_height INTEGER, root_hash BLOB,
+            shard_data BLOB, contains_marked INTEGER
+        );
+        CREATE TABLE sapling_tree_cap (cap_id INTEGER PRIMARY KEY, cap_data BLOB NOT NULL);
+        CREATE TABLE sapling_tree_checkpoints (checkpoint_id INTEGER PRIMARY KEY, position INTEGER);
+        CREATE TABLE sapling_tree_checkpoint_marks_removed (
+            checkpoint_id INTEGER NOT NULL, mark_removed_position INTEGER NOT NULL
+        );
+    ";
+
+    type TestTree = WalletTree<sapling::Node, { sapling::NOTE_COMMITMENT_TREE_DEPTH }>;
+
+    fn ufvk(seed: u8) -> UnifiedFullViewingKey {
+        UnifiedSpendingKey::from_seed(&Network::TestNetwork, &[seed; 32], AccountId::ZERO)
+            .expect("derive spending key")
+            .to_unified_full_viewing_key()
+    }
+
+    fn node(byte: u8) -> sapling::Node {
+        let mut repr = [0_u8; 32];
+        if let Some(first) = repr.first_mut() {
+            *first = byte;
+        }
+        Option::from(sapling::Node::from_bytes(repr)).expect("valid node")
+    }
+
+    fn note(ufvk: &UnifiedFullViewingKey) -> sapling::Note {
+        let (_, recipient) = ufvk.sapling().expect("sapling key").default_address();
+        sapling::Note::from_parts(
+            recipient,
+            sapling::value::NoteValue::from_raw(NOTE_VALUE),
+            sapling::Rseed::AfterZip212([9; 32]),
+        )
+    }
+
+    /// Leaves of the Sapling tree; the account note is at `NOTE_POSITION`.
+    fn leaves(note: &sapling::Note) -> [sapling::Node; 5] {
+        [
+            node(1),
+            sapling::Node::from_cmu(&note.cmu()),
+            node(2),
+            node(3),
+            node(4),
+        ]
+    }
+
+    /// Snapshot tree state covering the first three leaves.
+    fn snapshot(note: &sapling::Note, height: u32) -> TreeState {
+        let mut tree = CommitmentTree::<sapling::Node, 32>::empty();
+        for leaf in leaves(note).into_iter().take(3) {
+            tree.append(leaf).expect("append");
+        }
+        let mut bytes = Vec::new();
+        write_commitment_tree(&tree, &mut bytes).expect("write tree");
+        TreeState {
+            height: u64::from(height),
+            sapling_tree: hex::encode(bytes),
+            ..TreeState::default()
+        }
+    }
+
+    /// Wallet tree holding all leaves, checkpointed only at its tip.
+    fn wallet_tree(note: &sapling::Note) -> TestTree {
+        let mut tree = TestTree::new(PersistentShardStore::empty(), 10);
+        for (position, leaf) in (0..).zip(leaves(note)) {
+            let retention = match position {
+                NOTE_POSITION => Retention::Marked,
+                4 => Retention::Checkpoint {
+                    id: BlockHeight::from_u32(WALLET_TIP),
+                    marking: Marking::None,
+                },
+                _ => Retention::Ephemeral,
+            };
+            tree.append(leaf, retention).expect("append");
+        }
+        tree
+    }
+
+    fn insert_tree(connection: &Connection, tree: &TestTree) {
+        let store = tree.store();
+        let Ok(cap) = store.get_cap();
+        let mut cap_data = Vec::new();
+        write_shard(&mut cap_data, &cap).expect("write cap");
+        connection
+            .execute(
+                "INSERT INTO sapling_tree_cap (cap_id, cap_data) VALUES (0, ?1)",
+                [cap_data],
+            )
+            .expect("insert cap");
+
+        let Ok(roots) = store.get_shard_roots();
+        for root in roots {
+            let Ok(Some(shard)) = store.get_shard(root) else {
+                panic!("missing shard at {root:?}");
+            };
+            let mut shard_data = Vec::new();
+            write_shard(&mut shard_data, shard.root()).expect("write shard");
+            connection
+                .execute(
+                    "INSERT INTO sapling_tree_shards (shard_index, shard_data) VALUES (?1, ?2)",
+                    params![
+                        i64::try_from(root.index()).expect("shard index"),
+                        shard_data
+                    ],
+                )
+                .expect("insert shard");
+        }
+
+        let mut checkpoints = Vec::new();
+        let Ok(()) = store.for_each_checkpoint(usize::MAX, |id, checkpoint| {
+            checkpoints.push((u32::from(*id), checkpoint.position()));
+            Ok(())
+        });
+        for (id, position) in checkpoints {
+            let position = position.map(|p| i64::try_from(u64::from(p)).expect("position"));
+            connection
+                .execute(
+                    "INSERT INTO sapling_tree_checkpoints (checkpoint_id, position) VALUES (?1, ?2)",
+                    params![id, position],
+                )
+                .expect("insert checkpoint");
+        }
+    }
+
+    /// Wallet of `ufvk(1)` with the account note mined before the snapshot and a later note
+    fn wallet_db(dir: &Path) -> WalletDb {
+        let owner = ufvk(1);
+        let note = note(&owner);
+        let path = dir.join("wallet.sqlite");
+        let connection = Connection::open(&path).expect("create database");
+        connection.execute_batch(SCHEMA).expect("create schema");
+        connection
+            .execute(
+                "INSERT INTO accounts (id, ufvk) VALUES (1, ?1)",
+                [owner.encode(&Network::TestNetwork)],
+            )
+            .expect("insert account");
+        for (id_tx, mined_height) in [(1, 90), (2, WALLET_TIP)] {
+            connection
+                .execute(
+                    "INSERT INTO transactions (id_tx, txid, mined_height) VALUES (?1, ?2, ?3)",
+                    params![
+                        id_tx,
+                        [u8::try_from(id_tx).expect("txid")].repeat(32),
+                        mined_height
+                    ],
+                )
+                .expect("insert transaction");
+        }
+        for (transaction_id, position) in [(1, NOTE_POSITION), (2, 4)] {
+            connection
+                .execute(
+                    "INSERT INTO sapling_received_notes (
+                        transaction_id, account_id, diversifier, value, rcm,
+                        commitment_tree_position, recipient_key_scope
+                    ) VALUES (?1, 1, ?2, ?3, ?4, ?5, 0)",
+                    params![
+                        transaction_id,
+                        note.recipient().diversifier().0.to_vec(),
+                        i64::try_from(NOTE_VALUE).expect("value"),
+                        note.rcm().to_repr().to_vec(),
+                        i64::try_from(position).expect("position"),
+                    ],
+                )
+                .expect("insert note");
+        }
+        insert_tree(&connection, &wallet_tree(&note));
+        drop(connection);
+        WalletDb::open(&path).expect("open wallet")
+    }
+
+    #[test]
+    fn import_rewinds_witness_to_snapshot() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let owner = ufvk(1);
+        let note = note(&owner);
+        let imported = wallet_db(dir.path())
+            .import_notes(
+                Network::TestNetwork,
+                &owner,
+                &snapshot(&note, SNAPSHOT_HEIGHT),
+            )
+            .expect("import");
+
+        let [found] = imported.sapling_notes() else {
+            panic!("expected one note before the snapshot");
+        };
+        assert_eq!(found.note.position, NOTE_POSITION);
+        assert_eq!(found.note.note.cmu(), note.cmu());
+        assert_eq!(found.metadata.height, 90);
+        assert!(imported.orchard_notes().is_empty());
+
+        // The same tree built only up to the snapshot.
+        let mut expected = TestTree::new(PersistentShardStore::empty(), 10);
+        for (position, leaf) in (0..).zip(leaves(&note)).take(3) {
+            let retention = match position {
+                NOTE_POSITION => Retention::Marked,
+                2 => Retention::Checkpoint {
+                    id: BlockHeight::from_u32(SNAPSHOT_HEIGHT),
+                    marking: Marking::None,
+                },
+                _ => Retention::Ephemeral,
+            };
+            expected.append(leaf, retention).expect("append");
+        }
+        assert_eq!(
+            imported.sapling_witness(NOTE_POSITION).expect("witness"),
+            expected
+                .witness_at_checkpoint_depth(Position::from(NOTE_POSITION), 0)
+                .expect("witness"),
+        );
+    }
+
+    #[test]
+    fn import_rejects_unknown_account() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let result = wallet_db(dir.path()).import_notes(
+            Network::TestNetwork,
+            &ufvk(2),
+            &snapshot(&note(&ufvk(1)), SNAPSHOT_HEIGHT),
+        );
+        assert!(matches!(result, Err(WalletDbError::AccountNotFound)));
+    }
+
+    #[test]
+    fn import_requires_wallet_synced_past_snapshot() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let owner = ufvk(1);
+        let result = wallet_db(dir.path()).import_notes(
+            Network::TestNetwork,
+            &owner,
+            &snapshot(&note(&owner), WALLET_TIP + 1),
+        );
+        assert!(matches!(
+            result,
+            Err(WalletDbError::NotSynced {
+                pool: Pool::Sapling,
+                ..
+            })
+        ));
+    }
+}
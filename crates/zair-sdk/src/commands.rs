@@ -1,40 +1,164 @@
 //! Application command implementations.
 //!
-//! This module contains the core logic for each CLI subcommand.
+//! This module contains the core logic for each CLI subcommand. Embedders should prefer
+//! `ClaimSession`, which runs the claim steps on typed values instead of files, and
+//! `AirdropConfigurationBuilder`, which builds a configuration from in-memory nullifiers. The
+//! in-memory building blocks ([`ClaimKeys`], [`prove_claims`], [`sign_claim_proofs`],
+//! [`sign_transparent_claims`], [`SubmissionVerifier`]) do no I/O and are available without
+//! the `native` feature.
 
+#[cfg(feature = "native")]
 mod airdrop_claim;
+#[cfg(feature = "native")]
 mod airdrop_configuration;
+#[cfg(feature = "native")]
+mod claim_export;
+mod claim_keys;
+mod claim_message;
 mod claim_proofs;
-#[cfg(feature = "prove")]
+#[cfg(all(feature = "native", feature = "prove"))]
 mod claim_proofs_prove;
+#[cfg(feature = "native")]
+mod claim_proofs_verify;
+#[cfg(feature = "prove")]
+mod claim_prover;
+#[cfg(feature = "native")]
+mod claim_registry;
+#[cfg(feature = "native")]
+mod claim_session;
+mod claim_signer;
+#[cfg(feature = "native")]
 mod claim_submission_sign;
+#[cfg(feature = "native")]
 mod claim_submission_verify;
+#[cfg(feature = "native")]
+mod claim_transparent_sign;
+mod claim_verifier;
+#[cfg(feature = "native")]
+mod config_audit;
+mod config_signature;
+#[cfg(feature = "native")]
+mod configuration_builder;
+#[cfg(feature = "native")]
+mod error_class;
+#[cfg(feature = "native")]
+mod exclusion_report;
+#[cfg(feature = "native")]
+mod gap_shards;
+#[cfg(feature = "native")]
 mod key;
+#[cfg(feature = "native")]
 mod note_metadata;
 mod nullifier_uniqueness;
+#[cfg(feature = "native")]
 mod orchard_params;
-#[cfg(feature = "prove")]
+#[cfg(all(feature = "native", feature = "prove"))]
 mod orchard_setup;
+#[cfg(feature = "native")]
 mod pool_processor;
+#[cfg(feature = "native")]
 mod sensitive_output;
 mod signature_digest;
 mod submission_auth;
 mod submission_messages;
+#[cfg(feature = "native")]
+mod transparent_balances;
+mod transparent_signer;
+mod verification_report;
+#[cfg(feature = "native")]
+mod verify_server;
+#[cfg(feature = "native")]
+mod witness_server;
+#[cfg(feature = "native")]
 mod workflows;
 
+#[cfg(feature = "native")]
 pub use airdrop_claim::{GapTreeMode, airdrop_claim};
+#[cfg(feature = "native")]
 pub use airdrop_configuration::build_airdrop_configuration;
-pub use claim_proofs::verify_claim_proofs;
-#[cfg(feature = "prove")]
+#[cfg(feature = "native")]
+pub use claim_export::{ExportFormat, ExportedClaim, export_claim_submission};
+pub use claim_keys::ClaimKeys;
+#[cfg(feature = "native")]
+pub use claim_message::write_claim_message;
+pub use claim_message::{AddressError, AddressValidator, AddressValidators, InvalidClaimMessage};
+pub use claim_proofs::{
+    ClaimProofsOutput, ClaimSecretsOutput, OrchardClaimProofResult, OrchardClaimSecretResult,
+    SaplingClaimProofResult, SaplingClaimSecretResult,
+};
+#[cfg(all(feature = "native", feature = "prove"))]
 pub use claim_proofs_prove::{generate_claim_params, generate_claim_proofs};
+#[cfg(feature = "native")]
+pub use claim_proofs_verify::verify_claim_proofs;
+#[cfg(feature = "prove")]
+pub use claim_prover::prove_claims;
+#[cfg(feature = "native")]
+pub use claim_registry::{
+    AlreadyClaimed, ClaimRegistry, RegistryEntry, registry_check, registry_export, registry_import,
+    submission_entries,
+};
+#[cfg(feature = "native")]
+pub use claim_session::{ClaimSession, ClaimSessionBuilder, SnapshotSource};
+pub use claim_signer::sign_claim_proofs;
+#[cfg(feature = "native")]
 pub use claim_submission_sign::sign_claim_submission;
+#[cfg(feature = "native")]
 pub use claim_submission_verify::verify_claim_submission_signature;
-pub use key::{MnemonicSource, key_derive_seed, key_derive_ufvk};
+#[cfg(feature = "native")]
+pub use claim_transparent_sign::sign_transparent_claim_submission;
+pub use claim_verifier::{
+    OrchardClaimVerifier, SaplingClaimVerifier, SubmissionVerifier, TransparentClaimVerifier,
+    TransparentInclusionError, VerifyRequestError,
+};
+#[cfg(feature = "native")]
+pub use config_audit::{
+    AuditFailed, CountCheck, FileDigest, GapTreeAudit, PoolAudit, RescanAudit, RootCheck,
+    ScanRange, SnapshotAudit, TransparentAudit, audit_airdrop_configuration,
+};
+#[cfg(feature = "native")]
+pub use config_signature::{TrustedOrganizer, sign_airdrop_configuration};
+pub use config_signature::{
+    UntrustedConfiguration, organizer_public_key, parse_organizer_public_key,
+    parse_signed_configuration, sign_configuration, verify_configuration,
+};
+#[cfg(feature = "native")]
+pub use configuration_builder::{
+    AirdropConfigurationBuilder, AirdropSnapshot, PoolGapTree, PoolSnapshot,
+};
+#[cfg(feature = "native")]
+pub use error_class::ErrorClass;
+#[cfg(feature = "native")]
+pub use exclusion_report::{
+    ExclusionReason, ExclusionReport, PoolCoverage, ValuePool, exclusion_report,
+    read_exclusion_report,
+};
+#[cfg(feature = "native")]
+pub use key::{MnemonicSource, key_derive_seed, key_derive_ufvk, key_generate_organizer};
+#[cfg(feature = "native")]
 pub use orchard_params::{
     OrchardParamsMode, generate_orchard_params_file, load_or_prepare_orchard_params,
 };
-#[cfg(feature = "prove")]
+#[cfg(all(feature = "native", feature = "prove"))]
 pub use orchard_setup::generate_orchard_params;
-#[cfg(feature = "prove")]
-pub use workflows::claim_run;
+pub use signature_digest::hash_message;
+pub use submission_messages::ResolvedMessageHashes;
+#[cfg(feature = "native")]
+pub use transparent_balances::{parse_transparent_balances, read_transparent_balances};
+pub use transparent_signer::{DEFAULT_TRANSPARENT_GAP_LIMIT, sign_transparent_claims};
+pub use verification_report::{
+    CheckOutcome, ClaimVerdict, FailureReason, VerificationFailed, VerificationReport,
+    VerifyResponse,
+};
+#[cfg(feature = "native")]
+pub use verify_server::{
+    DEFAULT_MAX_CONCURRENT_VERIFICATIONS, MAX_VERIFY_BATCH, VerifyService, serve_verifier,
+    verify_router,
+};
+#[cfg(feature = "native")]
+pub use witness_server::{
+    MAX_WITNESS_BATCH, RemoteWitnesses, WitnessService, serve_witnesses, witness_router,
+};
+#[cfg(feature = "native")]
 pub use workflows::verify_run;
+#[cfg(all(feature = "native", feature = "prove"))]
+pub use workflows::{ClaimRunOutput, claim_run};
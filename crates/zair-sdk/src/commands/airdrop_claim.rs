@@ -7,6 +7,7 @@ use std::collections::HashMap;
 use std::ops::RangeInclusive;
 use std::path::{Path, PathBuf};
 use std::str::FromStr as _;
+use std::sync::Arc;
 
 use eyre::{Context as _, ensure};
 use http::Uri;
@@ -14,42 +15,63 @@ use tokio::fs::File;
 use tokio::io::BufReader;
 use tracing::{debug, info, instrument, warn};
 use zair_core::base::{Nullifier, Pool, SanitiseNullifiers};
-use zair_core::schema::config::AirdropConfiguration;
+use zair_core::container::{ContainerHeader, ContainerKind, ContainerOptions};
+use zair_core::schema::config::{AirdropConfiguration, OrchardGapTreeHash};
 use zair_core::schema::proof_inputs::{AirdropClaimInputs, ClaimInput, PublicInputs};
 use zair_nonmembership::{
-    MerklePathError, NonMembershipTree, OrchardGapTree, OrchardNonMembershipTree, SaplingGapTree,
-    TreePosition, map_orchard_user_positions, map_sapling_user_positions,
+    MerklePathError, NonMembershipTree, OrchardGapTree, OrchardNonMembershipTree,
+    OrchardPoseidonGapTree, OrchardPoseidonNonMembershipTree, SaplingGapTree, TreePosition,
+    map_orchard_user_positions, map_sapling_user_positions,
 };
 use zair_scan::ViewingKeys;
-use zair_scan::light_walletd::LightWalletd;
-use zair_scan::scanner::{AccountNotesVisitor, BlockScanner};
+use zair_scan::light_walletd::{LightWalletd, LightWalletdError};
+use zair_scan::scan_cache::ScanCache;
+use zair_scan::scanner::{AccountNotes, AccountNotesVisitor, BlockScanner};
+use zair_scan::wallet_db::{ImportedNotes, WalletDb};
 use zcash_keys::keys::UnifiedFullViewingKey;
-use zcash_protocol::consensus::Network;
+use zcash_protocol::consensus::{BlockHeight, Network};
 
+use super::airdrop_configuration::read_airdrop_configuration;
+use super::claim_session::{ClaimSession, SnapshotSource};
+use super::config_signature::TrustedOrganizer;
+use super::gap_shards::fetch_sharded_pool_data;
 use super::note_metadata::NoteMetadata;
-use super::pool_processor::{OrchardPool, PoolClaimResult, PoolProcessor, SaplingPool};
+use super::pool_processor::{PoolClaimResult, PoolProcessor};
 use super::sensitive_output::write_sensitive_output;
-use crate::common::{resolve_lightwalletd_url, to_zcash_network};
+use super::witness_server::{RemoteWitnesses, fetch_remote_pool_data};
+use crate::common::to_zcash_network;
+use crate::progress::{Cancelled, Phase, Progress};
 /// 1 MiB buffer for file I/O.
 const FILE_BUF_SIZE: usize = 1024 * 1024;
 /// Default Sapling snapshot path used by claim flows.
-const DEFAULT_SAPLING_SNAPSHOT_FILE: &str = "snapshot-sapling.bin";
+pub(super) const DEFAULT_SAPLING_SNAPSHOT_FILE: &str = "snapshot-sapling.bin";
 /// Default Orchard snapshot path used by claim flows.
-const DEFAULT_ORCHARD_SNAPSHOT_FILE: &str = "snapshot-orchard.bin";
+pub(super) const DEFAULT_ORCHARD_SNAPSHOT_FILE: &str = "snapshot-orchard.bin";
 /// Default Sapling gap-tree path used by claim flows.
-const DEFAULT_SAPLING_GAP_TREE_FILE: &str = "gaptree-sapling.bin";
+pub(super) const DEFAULT_SAPLING_GAP_TREE_FILE: &str = "gaptree-sapling.bin";
 /// Default Orchard gap-tree path used by claim flows.
-const DEFAULT_ORCHARD_GAP_TREE_FILE: &str = "gaptree-orchard.bin";
+pub(super) const DEFAULT_ORCHARD_GAP_TREE_FILE: &str = "gaptree-orchard.bin";
 
 /// Gap-tree handling mode for claim prepare/run.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GapTreeMode {
-    /// Require precomputed `gaptree-*.bin` files and fail if missing/invalid.
+    /// Memory-map precomputed `gaptree-*.bin` files and fail if missing/invalid.
     None,
     /// Rebuild gap trees from snapshots and persist to `gaptree-*.bin`.
     Rebuild,
     /// Build sparse in-memory trees directly from snapshots; do not read/write gap-tree files.
     Sparse,
+    /// Fetch and verify gap witnesses from a witness server; no snapshot or gap-tree files.
+    Remote,
+    /// Fetch only the gap-tree shards covering the user's nullifiers and splice witnesses.
+    Sharded,
+}
+
+impl GapTreeMode {
+    /// Whether witnesses come from elsewhere instead of local snapshot files.
+    const fn is_fetched(self) -> bool {
+        matches!(self, Self::Remote | Self::Sharded)
+    }
 }
 
 fn resolve_snapshot_path_if_enabled(
@@ -57,8 +79,9 @@ fn resolve_snapshot_path_if_enabled(
     provided_path: Option<PathBuf>,
     default_path: &str,
     pool: Pool,
+    gap_tree_mode: GapTreeMode,
 ) -> Option<PathBuf> {
-    if !enabled {
+    if !enabled || gap_tree_mode.is_fetched() {
         return None;
     }
 
@@ -80,7 +103,7 @@ fn resolve_gap_tree_path_if_enabled(
     pool: Pool,
     gap_tree_mode: GapTreeMode,
 ) -> Option<PathBuf> {
-    if !enabled || gap_tree_mode == GapTreeMode::Sparse {
+    if !enabled || gap_tree_mode == GapTreeMode::Sparse || gap_tree_mode.is_fetched() {
         return None;
     }
 
@@ -101,7 +124,17 @@ fn resolve_gap_tree_path_if_enabled(
 /// and output them to the specified file. The function scans the blockchain
 /// for notes associated with the UFVK, constructs non-membership Merkle trees
 /// for the provided snapshot nullifiers, and generates non-membership proofs
-/// for the user's notes.
+/// for the user's notes. In [`GapTreeMode::Remote`] the witnesses are fetched from
+/// `witness_server_url` instead, and in [`GapTreeMode::Sharded`] they are spliced from the
+/// gap-tree shards at `gap_tree_shards`; both are verified against the configured roots.
+/// With `scan_cache_dir`, the note scan is cached there and reused by later claims. With
+/// `wallet_db`, notes are imported from that `zcash_client_sqlite` wallet database instead and
+/// `birthday_height` is not needed. With `trusted_organizer`, a configuration the organizer
+/// did not sign is refused. Scan and gap-tree progress is reported to `progress`, and the claim
+/// stops once it is cancelled.
+///
+/// This is a file-based wrapper around [`ClaimSession::prepare`]; it also returns the
+/// claim inputs it wrote.
 ///
 /// # Errors
 /// Returns error if any step in the process fails,
@@ -119,24 +152,36 @@ pub async fn airdrop_claim(
     sapling_gap_tree_file: Option<PathBuf>,
     orchard_gap_tree_file: Option<PathBuf>,
     gap_tree_mode: GapTreeMode,
+    witness_server_url: Option<String>,
+    gap_tree_shards: Option<String>,
     unified_full_viewing_key: String,
-    birthday_height: u64,
+    birthday_height: Option<u64>,
+    scan_cache_dir: Option<PathBuf>,
+    wallet_db: Option<PathBuf>,
     airdrop_claims_output_file: PathBuf,
     airdrop_configuration_file: PathBuf,
-) -> eyre::Result<()> {
-    let airdrop_config: AirdropConfiguration =
-        serde_json::from_str(&tokio::fs::read_to_string(airdrop_configuration_file).await?)?;
+    trusted_organizer: Option<TrustedOrganizer>,
+    progress: &Progress,
+) -> eyre::Result<AirdropClaimInputs> {
+    let airdrop_config = read_airdrop_configuration(
+        &airdrop_configuration_file,
+        None,
+        trusted_organizer.as_ref(),
+    )
+    .await?;
     let sapling_snapshot_nullifiers = resolve_snapshot_path_if_enabled(
         airdrop_config.sapling.is_some(),
         sapling_snapshot_nullifiers,
         DEFAULT_SAPLING_SNAPSHOT_FILE,
         Pool::Sapling,
+        gap_tree_mode,
     );
     let orchard_snapshot_nullifiers = resolve_snapshot_path_if_enabled(
         airdrop_config.orchard.is_some(),
         orchard_snapshot_nullifiers,
         DEFAULT_ORCHARD_SNAPSHOT_FILE,
         Pool::Orchard,
+        gap_tree_mode,
     );
     let sapling_gap_tree_file = resolve_gap_tree_path_if_enabled(
         airdrop_config.sapling.is_some(),
@@ -152,63 +197,52 @@ pub async fn airdrop_claim(
         Pool::Orchard,
         gap_tree_mode,
     );
-    validate_pool_inputs(
-        &airdrop_config,
-        sapling_snapshot_nullifiers.as_ref(),
-        orchard_snapshot_nullifiers.as_ref(),
-        sapling_gap_tree_file.as_ref(),
-        orchard_gap_tree_file.as_ref(),
-        gap_tree_mode,
-    )?;
 
     let network = to_zcash_network(airdrop_config.network);
-    let lightwalletd_url = resolve_lightwalletd_url(network, lightwalletd_url.as_deref());
     let ufvk = UnifiedFullViewingKey::decode(&network, &unified_full_viewing_key)
         .map_err(|e| eyre::eyre!("Failed to decode Unified Full Viewing Key: {e:?}"))?;
-    debug!(birthday_height, "Using user-provided birthday height");
-
-    let account_notes = find_user_notes(
-        &lightwalletd_url,
-        network,
-        airdrop_config.snapshot_height,
-        ufvk.clone(),
-        birthday_height,
-    )
-    .await?;
 
-    let viewing_keys = ViewingKeys::new(&ufvk);
-
-    // Process pools in parallel
-    let (sapling_result, orchard_result) = tokio::try_join!(
-        process_pool_claims::<SaplingPool>(
-            airdrop_config.sapling.is_some(),
-            &account_notes,
-            &viewing_keys,
-            &airdrop_config,
-            sapling_snapshot_nullifiers,
-            sapling_gap_tree_file,
-            gap_tree_mode,
-        ),
-        process_pool_claims::<OrchardPool>(
-            airdrop_config.orchard.is_some(),
-            &account_notes,
-            &viewing_keys,
-            &airdrop_config,
-            orchard_snapshot_nullifiers,
-            orchard_gap_tree_file,
-            gap_tree_mode,
-        ),
-    )?;
+    let mut session = ClaimSession::builder()
+        .config(airdrop_config)
+        .ufvk(ufvk)
+        .gap_tree_mode(gap_tree_mode)
+        .progress(progress.clone());
+    if let Some(height) = birthday_height {
+        session = session.birthday_height(height);
+    }
+    if let Some(url) = lightwalletd_url {
+        session = session.lightwalletd_url(url);
+    }
+    if let Some(dir) = scan_cache_dir {
+        session = session.scan_cache(dir);
+    }
+    if let Some(path) = wallet_db {
+        session = session.wallet_db(path);
+    }
+    if let Some(path) = sapling_snapshot_nullifiers {
+        session = session.snapshot(Pool::Sapling, path);
+    }
+    if let Some(path) = orchard_snapshot_nullifiers {
+        session = session.snapshot(Pool::Orchard, path);
+    }
+    if let Some(path) = sapling_gap_tree_file {
+        session = session.gap_tree_file(Pool::Sapling, path);
+    }
+    if let Some(path) = orchard_gap_tree_file {
+        session = session.gap_tree_file(Pool::Orchard, path);
+    }
+    if let Some(url) = witness_server_url {
+        session = session.witness_server(url);
+    }
+    if let Some(source) = gap_tree_shards {
+        session = session.gap_tree_shards(source);
+    }
 
-    let total_claims = sapling_result
-        .claims
+    let user_proofs = session.build()?.prepare().await?;
+    let total_claims = user_proofs
+        .sapling_claim_input
         .len()
-        .checked_add(orchard_result.claims.len());
-
-    let user_proofs = AirdropClaimInputs {
-        sapling_claim_input: sapling_result.claims,
-        orchard_claim_input: orchard_result.claims,
-    };
+        .checked_add(user_proofs.orchard_claim_input.len());
 
     let json = serde_json::to_string_pretty(&user_proofs)?;
     write_sensitive_output(&airdrop_claims_output_file, &json).await?;
@@ -219,16 +253,18 @@ pub async fn airdrop_claim(
         "airdrop claims written"
     );
 
-    Ok(())
+    Ok(user_proofs)
 }
 
-fn validate_pool_inputs(
+/// Check that every configured pool has a witness source for `gap_tree_mode`.
+pub(super) fn validate_pool_inputs(
     airdrop_config: &AirdropConfiguration,
-    sapling_snapshot_nullifiers: Option<&PathBuf>,
-    orchard_snapshot_nullifiers: Option<&PathBuf>,
+    sapling_snapshot_nullifiers: Option<&SnapshotSource>,
+    orchard_snapshot_nullifiers: Option<&SnapshotSource>,
     sapling_gap_tree_file: Option<&PathBuf>,
     orchard_gap_tree_file: Option<&PathBuf>,
     gap_tree_mode: GapTreeMode,
+    remote_source: Option<&str>,
 ) -> eyre::Result<()> {
     let config_has_sapling = airdrop_config.sapling.is_some();
     let config_has_orchard = airdrop_config.orchard.is_some();
@@ -238,6 +274,24 @@ fn validate_pool_inputs(
         "Airdrop configuration must enable at least one pool (sapling/orchard)"
     );
 
+    match gap_tree_mode {
+        GapTreeMode::Remote => {
+            ensure!(
+                remote_source.is_some(),
+                "--gap-tree-mode remote requires --witness-server"
+            );
+            return Ok(());
+        }
+        GapTreeMode::Sharded => {
+            ensure!(
+                remote_source.is_some(),
+                "--gap-tree-mode sharded requires --gap-tree-shards"
+            );
+            return Ok(());
+        }
+        GapTreeMode::None | GapTreeMode::Rebuild | GapTreeMode::Sparse => {}
+    }
+
     ensure!(
         !(config_has_sapling && sapling_snapshot_nullifiers.is_none()),
         "Airdrop configuration enables Sapling, but --snapshot-sapling is missing"
@@ -261,40 +315,95 @@ fn validate_pool_inputs(
 }
 
 /// Scan the blockchain for user notes within the snapshot range.
+///
+/// With `scan_cache_dir`, the scan state of the account is loaded from and saved to a
+/// [`ScanCache`] there: a cache at or past the snapshot is used without scanning while its
+/// snapshot checkpoint is retained, and an older one is resumed from its last block. A cache
+/// that cannot be read or resumed falls back to a full scan from the birthday.
 #[instrument(level = "debug", skip_all)]
-async fn find_user_notes(
+pub(super) async fn find_user_notes(
     lightwalletd_url: &str,
     network: Network,
     snapshot_height: u64,
     ufvk: UnifiedFullViewingKey,
     birthday_height: u64,
+    scan_cache_dir: Option<&Path>,
+    progress: &Progress,
 ) -> eyre::Result<AccountNotesVisitor> {
     ensure!(
         birthday_height <= snapshot_height,
         "Birthday height cannot be past snapshot height"
     );
+    let snapshot = BlockHeight::from_u32(u32::try_from(snapshot_height)?);
+
+    let scan_cache = scan_cache_dir.map(|dir| ScanCache::new(dir, network, &ufvk, birthday_height));
+    let mut resume = None;
+    if let Some(cache) = &scan_cache {
+        match cache.load().await {
+            Ok(Some(mut cached)) => match cached.latest_height() {
+                Some(latest) if latest >= snapshot && cached.rewind_to(snapshot)? => {
+                    info!(file = ?cache.path(), "Using cached note scan");
+                    return Ok(cached);
+                }
+                Some(latest) if latest < snapshot && cached.latest_metadata().is_some() => {
+                    resume = Some(cached);
+                }
+                _ => debug!(file = ?cache.path(), "Cached note scan cannot serve the snapshot"),
+            },
+            Ok(None) => {}
+            Err(e) => warn!(file = ?cache.path(), error = %e, "Ignoring unreadable scan cache"),
+        }
+    }
 
     let lightwalletd_url =
         Uri::from_str(lightwalletd_url).context("lightwalletd URL is required")?;
     let lightwalletd = LightWalletd::connect(lightwalletd_url).await?;
 
-    // NOTE: We are interested at tree state from the point that the account could have notes
-    let start_block = birthday_height;
-    let tree_state_height = start_block.saturating_sub(1);
-    let tree_state = lightwalletd.get_tree_state(tree_state_height).await?;
-
-    // Initialize visitor from tree state
-    let visitor = AccountNotesVisitor::from_tree_state(&tree_state)?;
-
-    let scan_range = RangeInclusive::new(birthday_height, snapshot_height);
-
-    info!("Scanning for user notes");
-    let initial_metadata = BlockScanner::parse_tree_state(&tree_state)?;
-
-    // Use channel-based scanning to keep non-Send BlockScanner off async tasks
-    let (visitor, _final_metadata) = lightwalletd
-        .scan_blocks_spawned(ufvk, network, visitor, &scan_range, Some(initial_metadata))
-        .await?;
+    let visitor = loop {
+        let resumed = resume.is_some();
+        let (visitor, initial_metadata) = if let Some(cached) = resume.take() {
+            let initial_metadata = cached.latest_metadata().copied();
+            (cached, initial_metadata)
+        } else {
+            // NOTE: We are interested at tree state from the point that the account could have
+            // notes
+            let tree_state_height = birthday_height.saturating_sub(1);
+            let tree_state = lightwalletd.get_tree_state(tree_state_height).await?;
+            (
+                AccountNotesVisitor::from_tree_state(&tree_state)?,
+                Some(BlockScanner::parse_tree_state(&tree_state)?),
+            )
+        };
+        let scan_start = initial_metadata.map_or(birthday_height, |metadata| {
+            u64::from(metadata.block_height()).saturating_add(1)
+        });
+        let scan_range = RangeInclusive::new(scan_start, snapshot_height);
+
+        info!(from = scan_start, "Scanning for user notes");
+        // Use channel-based scanning to keep non-Send BlockScanner off async tasks
+        let scan_progress = progress.clone();
+        match lightwalletd
+            .scan_blocks_spawned_with_progress(
+                ufvk.clone(),
+                network,
+                visitor,
+                &scan_range,
+                initial_metadata,
+                move |_height, scanned, total| {
+                    scan_progress.report_count(Phase::ScanNotes, None, scanned, total);
+                },
+                progress.cancellation().clone(),
+            )
+            .await
+        {
+            Ok((visitor, _final_metadata)) => break visitor,
+            Err(LightWalletdError::Cancelled) => return Err(eyre::Report::new(Cancelled)),
+            Err(e) if resumed => {
+                warn!(error = %e, "Cached note scan does not continue the chain; rescanning");
+            }
+            Err(e) => return Err(eyre::Report::new(e)),
+        }
+    };
 
     info!(
         total = visitor
@@ -304,9 +413,50 @@ async fn find_user_notes(
         "Scan complete"
     );
 
+    if let Some(cache) = &scan_cache {
+        match cache.store(&visitor).await {
+            Ok(()) => debug!(file = ?cache.path(), "Note scan cached"),
+            Err(e) => warn!(file = ?cache.path(), error = %e, "Failed to write scan cache"),
+        }
+    }
+
     Ok(visitor)
 }
 
+/// Import the user's notes at the snapshot from a `zcash_client_sqlite` wallet database.
+///
+/// Only the snapshot tree state is fetched from lightwalletd; it rewinds the wallet's witnesses
+/// to the snapshot.
+#[instrument(level = "debug", skip_all)]
+pub(super) async fn import_user_notes(
+    lightwalletd_url: &str,
+    network: Network,
+    snapshot_height: u64,
+    ufvk: UnifiedFullViewingKey,
+    wallet_db: &Path,
+) -> eyre::Result<ImportedNotes> {
+    let lightwalletd_url =
+        Uri::from_str(lightwalletd_url).context("lightwalletd URL is required")?;
+    let lightwalletd = LightWalletd::connect(lightwalletd_url).await?;
+    let tree_state = lightwalletd.get_tree_state(snapshot_height).await?;
+
+    info!(file = ?wallet_db, "Importing notes from wallet database");
+    let wallet_db = wallet_db.to_path_buf();
+    let notes = tokio::task::spawn_blocking(move || {
+        WalletDb::open(&wallet_db)?.import_notes(network, &ufvk, &tree_state)
+    })
+    .await??;
+
+    info!(
+        total = notes
+            .sapling_notes()
+            .len()
+            .checked_add(notes.orchard_notes().len()),
+        "Import complete"
+    );
+    Ok(notes)
+}
+
 /// Loaded pool data including the non-membership merkle-tree and user's nullifier positions.
 pub struct LoadedPoolData {
     /// The non-membership merkle tree for the pool.
@@ -319,36 +469,172 @@ pub struct LoadedPoolData {
 pub enum PoolMerkleTree {
     Sapling(SaplingGapTree),
     Orchard(OrchardGapTree),
+    OrchardPoseidon(OrchardPoseidonGapTree),
     SaplingSparse(NonMembershipTree),
     OrchardSparse(OrchardNonMembershipTree),
+    OrchardPoseidonSparse(OrchardPoseidonNonMembershipTree),
+    Remote(RemoteWitnesses),
 }
 
 impl PoolMerkleTree {
-    fn root_bytes(&self) -> [u8; 32] {
+    pub(super) fn root_bytes(&self) -> [u8; 32] {
         match self {
             Self::Sapling(tree) => tree.root_bytes(),
             Self::Orchard(tree) => tree.root_bytes(),
+            Self::OrchardPoseidon(tree) => tree.root_bytes(),
             Self::SaplingSparse(tree) => tree.root().to_bytes(),
             Self::OrchardSparse(tree) => tree.root_bytes(),
+            Self::OrchardPoseidonSparse(tree) => tree.root_bytes(),
+            Self::Remote(witnesses) => witnesses.root_bytes(),
         }
     }
 
-    fn witness_bytes(&self, position: u64) -> Result<Vec<[u8; 32]>, MerklePathError> {
+    pub(super) fn witness_bytes(&self, position: u64) -> Result<Vec<[u8; 32]>, MerklePathError> {
         match self {
             Self::Sapling(tree) => tree.witness_bytes(position),
             Self::Orchard(tree) => tree.witness_bytes(position),
+            Self::OrchardPoseidon(tree) => tree.witness_bytes(position),
             Self::SaplingSparse(tree) => tree
                 .witness(position.into())
                 .map(|path| path.into_iter().map(|node| node.to_bytes()).collect()),
             Self::OrchardSparse(tree) => tree.witness_bytes(position.into()),
+            Self::OrchardPoseidonSparse(tree) => tree.witness_bytes(position.into()),
+            Self::Remote(witnesses) => witnesses.witness_bytes(position),
+        }
+    }
+
+    fn to_bytes(&self, options: &ContainerOptions) -> Option<Result<Vec<u8>, MerklePathError>> {
+        match self {
+            Self::Sapling(tree) => Some(tree.to_bytes(options)),
+            Self::Orchard(tree) => Some(tree.to_bytes(options)),
+            Self::OrchardPoseidon(tree) => Some(tree.to_bytes(options)),
+            Self::SaplingSparse(_) |
+            Self::OrchardSparse(_) |
+            Self::OrchardPoseidonSparse(_) |
+            Self::Remote(_) => None,
+        }
+    }
+
+    pub(super) const fn header(&self) -> Option<&ContainerHeader> {
+        match self {
+            Self::Sapling(tree) => tree.header(),
+            Self::Orchard(tree) => tree.header(),
+            Self::OrchardPoseidon(tree) => tree.header(),
+            Self::SaplingSparse(_) |
+            Self::OrchardSparse(_) |
+            Self::OrchardPoseidonSparse(_) |
+            Self::Remote(_) => None,
+        }
+    }
+}
+
+/// Log tree-build progress in 10% steps and report every update to `progress`.
+fn tree_progress_logger(pool: Pool, progress: &Progress) -> impl FnMut(usize, usize) {
+    let mut last_progress_pct = 0_usize;
+    let progress = progress.clone();
+    move |current, total| {
+        progress.report_count(Phase::BuildGapTree, Some(pool), current, total);
+        if total == 0 {
+            return;
+        }
+        #[allow(
+            clippy::arithmetic_side_effects,
+            reason = "Progress percentage uses saturating operations and is guarded against total=0"
+        )]
+        let pct = current.saturating_mul(100).saturating_div(total);
+        if pct == 100 || pct >= last_progress_pct.saturating_add(10) {
+            last_progress_pct = pct;
+            info!(%pool, progress = %format!("{pct}%"), "Building non-membership tree");
         }
     }
 }
 
+/// Build a sparse in-memory tree and mark the user's gap positions.
+fn build_sparse_tree(
+    chain_nullifiers: &SanitiseNullifiers,
+    user_nullifiers: &SanitiseNullifiers,
+    pool: Pool,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    progress: &Progress,
+) -> Result<(PoolMerkleTree, Vec<TreePosition>), MerklePathError> {
+    let on_progress = tree_progress_logger(pool, progress);
+    match (pool, orchard_gap_tree_hash) {
+        (Pool::Sapling, _) => NonMembershipTree::from_chain_and_user_nullifiers_with_progress(
+            chain_nullifiers,
+            user_nullifiers,
+            on_progress,
+        )
+        .map(|(tree, positions)| (PoolMerkleTree::SaplingSparse(tree), positions)),
+        (Pool::Orchard, OrchardGapTreeHash::Sinsemilla) => {
+            OrchardNonMembershipTree::from_chain_and_user_nullifiers_with_progress(
+                chain_nullifiers,
+                user_nullifiers,
+                on_progress,
+            )
+            .map(|(tree, positions)| (PoolMerkleTree::OrchardSparse(tree), positions))
+        }
+        (Pool::Orchard, OrchardGapTreeHash::Poseidon) => {
+            OrchardPoseidonNonMembershipTree::from_chain_and_user_nullifiers_with_progress(
+                chain_nullifiers,
+                user_nullifiers,
+                on_progress,
+            )
+            .map(|(tree, positions)| (PoolMerkleTree::OrchardPoseidonSparse(tree), positions))
+        }
+        (Pool::Transparent, _) => Err(MerklePathError::NoGapTree(pool)),
+    }
+}
+
+/// Build a dense gap tree from the full chain nullifier set.
+fn build_dense_tree(
+    chain_nullifiers: &SanitiseNullifiers,
+    pool: Pool,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    progress: &Progress,
+) -> Result<PoolMerkleTree, MerklePathError> {
+    let on_progress = tree_progress_logger(pool, progress);
+    match (pool, orchard_gap_tree_hash) {
+        (Pool::Sapling, _) => {
+            SaplingGapTree::from_nullifiers_with_progress(chain_nullifiers, on_progress)
+                .map(PoolMerkleTree::Sapling)
+        }
+        (Pool::Orchard, OrchardGapTreeHash::Sinsemilla) => {
+            OrchardGapTree::from_nullifiers_with_progress(chain_nullifiers, on_progress)
+                .map(PoolMerkleTree::Orchard)
+        }
+        (Pool::Orchard, OrchardGapTreeHash::Poseidon) => {
+            OrchardPoseidonGapTree::from_nullifiers_with_progress(chain_nullifiers, on_progress)
+                .map(PoolMerkleTree::OrchardPoseidon)
+        }
+        (Pool::Transparent, _) => Err(MerklePathError::NoGapTree(pool)),
+    }
+}
+
+/// Memory-map a persisted dense gap tree; witnesses are read from the file on demand.
+pub(super) fn map_dense_tree(
+    path: &Path,
+    pool: Pool,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+) -> Result<PoolMerkleTree, MerklePathError> {
+    match (pool, orchard_gap_tree_hash) {
+        (Pool::Sapling, _) => SaplingGapTree::map_file(path).map(PoolMerkleTree::Sapling),
+        (Pool::Orchard, OrchardGapTreeHash::Sinsemilla) => {
+            OrchardGapTree::map_file(path).map(PoolMerkleTree::Orchard)
+        }
+        (Pool::Orchard, OrchardGapTreeHash::Poseidon) => {
+            OrchardPoseidonGapTree::map_file(path).map(PoolMerkleTree::OrchardPoseidon)
+        }
+        (Pool::Transparent, _) => Err(MerklePathError::NoGapTree(pool)),
+    }
+}
+
 /// Build the non-membership merkle tree for a pool.
+///
+/// `orchard_gap_tree_hash` selects the Orchard tree variant and is ignored for Sapling.
+/// Versioned snapshot and gap-tree files must match `options`; legacy files are accepted.
 #[allow(
-    clippy::too_many_lines,
-    reason = "Mode-specific sparse/dense cache handling is intentionally kept in one dispatch function"
+    clippy::too_many_arguments,
+    reason = "Pool inputs are passed through explicitly"
 )]
 async fn build_pool_merkle_tree(
     snapshot_nullifiers_path: &Path,
@@ -356,9 +642,12 @@ async fn build_pool_merkle_tree(
     user_nullifiers: SanitiseNullifiers,
     pool: Pool,
     gap_tree_mode: GapTreeMode,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    options: &ContainerOptions,
+    progress: &Progress,
 ) -> eyre::Result<LoadedPoolData> {
-    let use_orchard_tree = pool == Pool::Orchard;
-    let chain_nullifiers = load_nullifiers_from_file(snapshot_nullifiers_path).await?;
+    let chain_nullifiers =
+        load_nullifiers_from_file(snapshot_nullifiers_path, pool, options).await?;
 
     info!(
         count = chain_nullifiers.len(),
@@ -366,6 +655,38 @@ async fn build_pool_merkle_tree(
         "Loaded chain nullifiers"
     );
 
+    build_pool_merkle_tree_from_nullifiers(
+        Arc::new(chain_nullifiers),
+        gap_tree_path,
+        user_nullifiers,
+        pool,
+        gap_tree_mode,
+        orchard_gap_tree_hash,
+        options,
+        progress,
+    )
+    .await
+}
+
+/// Build the non-membership merkle tree for a pool from snapshot nullifiers already in memory.
+///
+/// Cancellation is checked before and after the tree build, not while it runs.
+#[allow(
+    clippy::too_many_arguments,
+    clippy::too_many_lines,
+    reason = "Pool inputs are passed through explicitly; one flow per gap-tree mode"
+)]
+async fn build_pool_merkle_tree_from_nullifiers(
+    chain_nullifiers: Arc<SanitiseNullifiers>,
+    gap_tree_path: Option<&Path>,
+    user_nullifiers: SanitiseNullifiers,
+    pool: Pool,
+    gap_tree_mode: GapTreeMode,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    options: &ContainerOptions,
+    progress: &Progress,
+) -> eyre::Result<LoadedPoolData> {
+    progress.ensure_not_cancelled()?;
     match gap_tree_mode {
         GapTreeMode::Sparse => {
             info!(
@@ -377,53 +698,18 @@ async fn build_pool_merkle_tree(
                 progress = "0%",
                 "Building non-membership tree"
             );
-            let chain_for_build = chain_nullifiers;
-            let user_for_build = user_nullifiers;
+            let tree_progress = progress.clone();
             let (tree, user_positions) = tokio::task::spawn_blocking(move || {
-                let mut last_progress_pct = 0_usize;
-                if use_orchard_tree {
-                    OrchardNonMembershipTree::from_chain_and_user_nullifiers_with_progress(
-                        &chain_for_build,
-                        &user_for_build,
-                        |current, total| {
-                            if total == 0 {
-                                return;
-                            }
-                            #[allow(
-                                clippy::arithmetic_side_effects,
-                                reason = "Progress percentage uses saturating operations and is guarded against total=0"
-                            )]
-                            let pct = current.saturating_mul(100).saturating_div(total);
-                            if pct >= last_progress_pct.saturating_add(10) {
-                                last_progress_pct = pct;
-                                info!(%pool, progress = %format!("{pct}%"), "Building non-membership tree");
-                            }
-                        },
-                    )
-                    .map(|(tree, positions)| (PoolMerkleTree::OrchardSparse(tree), positions))
-                } else {
-                    NonMembershipTree::from_chain_and_user_nullifiers_with_progress(
-                        &chain_for_build,
-                        &user_for_build,
-                        |current, total| {
-                            if total == 0 {
-                                return;
-                            }
-                            #[allow(
-                                clippy::arithmetic_side_effects,
-                                reason = "Progress percentage uses saturating operations and is guarded against total=0"
-                            )]
-                            let pct = current.saturating_mul(100).saturating_div(total);
-                            if pct >= last_progress_pct.saturating_add(10) {
-                                last_progress_pct = pct;
-                                info!(%pool, progress = %format!("{pct}%"), "Building non-membership tree");
-                            }
-                        },
-                    )
-                    .map(|(tree, positions)| (PoolMerkleTree::SaplingSparse(tree), positions))
-                }
+                build_sparse_tree(
+                    &chain_nullifiers,
+                    &user_nullifiers,
+                    pool,
+                    orchard_gap_tree_hash,
+                    &tree_progress,
+                )
             })
             .await??;
+            progress.ensure_not_cancelled()?;
 
             info!(%pool, "Non-membership tree ready");
             Ok(LoadedPoolData {
@@ -431,13 +717,16 @@ async fn build_pool_merkle_tree(
                 user_nullifiers: user_positions,
             })
         }
+        GapTreeMode::Remote | GapTreeMode::Sharded => Err(eyre::eyre!(
+            "{pool} witnesses are fetched instead of built in {gap_tree_mode:?} mode"
+        )),
         GapTreeMode::Rebuild | GapTreeMode::None => {
-            let user_positions = if use_orchard_tree {
-                map_orchard_user_positions(&chain_nullifiers, &user_nullifiers)
-                    .map_err(|e| eyre::eyre!("Failed to map Orchard user nullifiers: {e}"))?
-            } else {
-                map_sapling_user_positions(&chain_nullifiers, &user_nullifiers)
-                    .map_err(|e| eyre::eyre!("Failed to map Sapling user nullifiers: {e}"))?
+            let user_positions = match pool {
+                Pool::Orchard => map_orchard_user_positions(&chain_nullifiers, &user_nullifiers)
+                    .map_err(|e| eyre::eyre!("Failed to map Orchard user nullifiers: {e}"))?,
+                Pool::Sapling => map_sapling_user_positions(&chain_nullifiers, &user_nullifiers)
+                    .map_err(|e| eyre::eyre!("Failed to map Sapling user nullifiers: {e}"))?,
+                Pool::Transparent => return Err(MerklePathError::NoGapTree(pool).into()),
             };
             let gap_tree_path = gap_tree_path.ok_or_else(|| {
                 eyre::eyre!(
@@ -451,50 +740,20 @@ async fn build_pool_merkle_tree(
                     %pool,
                     "Rebuilding gap-tree from snapshot nullifiers..."
                 );
-                let chain_nullifiers_for_build = chain_nullifiers;
+                let tree_progress = progress.clone();
                 let built_tree = tokio::task::spawn_blocking(move || {
-                    if use_orchard_tree {
-                        OrchardGapTree::from_nullifiers_with_progress(
-                            &chain_nullifiers_for_build,
-                            |current, total| {
-                                if total == 0 {
-                                    return;
-                                }
-                                #[allow(
-                                    clippy::arithmetic_side_effects,
-                                    reason = "Progress percentage uses saturating operations and is guarded against total=0"
-                                )]
-                                let pct = current.saturating_mul(100).saturating_div(total);
-                                info!(%pool, progress = %format!("{pct}%"), "Building non-membership tree");
-                            },
-                        )
-                        .map(PoolMerkleTree::Orchard)
-                    } else {
-                        SaplingGapTree::from_nullifiers_with_progress(
-                            &chain_nullifiers_for_build,
-                            |current, total| {
-                                if total == 0 {
-                                    return;
-                                }
-                                #[allow(
-                                    clippy::arithmetic_side_effects,
-                                    reason = "Progress percentage uses saturating operations and is guarded against total=0"
-                                )]
-                                let pct = current.saturating_mul(100).saturating_div(total);
-                                info!(%pool, progress = %format!("{pct}%"), "Building non-membership tree");
-                            },
-                        )
-                        .map(PoolMerkleTree::Sapling)
-                    }
+                    build_dense_tree(
+                        &chain_nullifiers,
+                        pool,
+                        orchard_gap_tree_hash,
+                        &tree_progress,
+                    )
                 })
                 .await??;
-                let serialized = match &built_tree {
-                    PoolMerkleTree::Sapling(tree) => tree.to_bytes(),
-                    PoolMerkleTree::Orchard(tree) => tree.to_bytes(),
-                    PoolMerkleTree::SaplingSparse(_) | PoolMerkleTree::OrchardSparse(_) => {
-                        unreachable!("sparse variants are not persisted in rebuild mode")
-                    }
-                };
+                progress.ensure_not_cancelled()?;
+                let serialized = built_tree.to_bytes(options).ok_or_else(|| {
+                    eyre::eyre!("Sparse trees are not persisted in rebuild mode")
+                })??;
                 tokio::fs::write(gap_tree_path, serialized)
                     .await
                     .with_context(|| {
@@ -502,32 +761,26 @@ async fn build_pool_merkle_tree(
                     })?;
                 built_tree
             } else {
-                let bytes = tokio::fs::read(gap_tree_path).await.with_context(|| {
+                map_dense_tree(gap_tree_path, pool, orchard_gap_tree_hash).map_err(|e| {
+                    let action = if matches!(e, MerklePathError::Io(_)) {
+                        "read"
+                    } else {
+                        "parse"
+                    };
+                    eyre::eyre!(e).wrap_err(format!(
+                        "Failed to {action} {pool} gap-tree {}. Retry with --gap-tree-mode rebuild",
+                        gap_tree_path.display()
+                    ))
+                })?
+            };
+            if let Some(header) = tree.header() {
+                header.ensure_snapshot(options).with_context(|| {
                     format!(
-                        "Failed to read gap-tree from {}. Retry with --gap-tree-mode rebuild",
+                        "{pool} gap-tree {} does not match the airdrop configuration. Retry with --gap-tree-mode rebuild",
                         gap_tree_path.display()
                     )
                 })?;
-                if use_orchard_tree {
-                    PoolMerkleTree::Orchard(OrchardGapTree::from_bytes(&bytes).with_context(
-                        || {
-                            format!(
-                                "Failed to parse Orchard gap-tree {}. Retry with --gap-tree-mode rebuild",
-                                gap_tree_path.display()
-                            )
-                        },
-                    )?)
-                } else {
-                    PoolMerkleTree::Sapling(SaplingGapTree::from_bytes(&bytes).with_context(
-                        || {
-                            format!(
-                                "Failed to parse Sapling gap-tree {}. Retry with --gap-tree-mode rebuild",
-                                gap_tree_path.display()
-                            )
-                        },
-                    )?)
-                }
-            };
+            }
 
             info!(%pool, "Non-membership tree ready");
 
@@ -577,6 +830,7 @@ fn generate_claims<M: NoteMetadata>(
             Ok(ClaimInput {
                 public_inputs: PublicInputs {
                     airdrop_nullifier: metadata.hiding_nullifier(),
+                    target_nullifiers: metadata.target_nullifiers().clone(),
                 },
                 private_inputs,
             })
@@ -584,60 +838,152 @@ fn generate_claims<M: NoteMetadata>(
         .collect()
 }
 
+/// Where a pool's gap witnesses come from.
+enum WitnessSource<'a> {
+    /// Local snapshot (plus gap-tree file unless sparse).
+    Local(&'a SnapshotSource),
+    /// Witness server URL.
+    Remote(&'a str),
+    /// Base URL or directory of the published gap-tree shards.
+    Sharded(&'a str),
+}
+
+impl<'a> WitnessSource<'a> {
+    /// Pick the witness source for `pool` and check its inputs are present.
+    fn resolve(
+        pool: Pool,
+        snapshot_nullifiers: Option<&'a SnapshotSource>,
+        has_gap_tree_file: bool,
+        gap_tree_mode: GapTreeMode,
+        remote_source: Option<&'a str>,
+    ) -> eyre::Result<Self> {
+        if gap_tree_mode.is_fetched() {
+            let source = remote_source.ok_or_else(|| {
+                eyre::eyre!("{pool} witness source is required in {gap_tree_mode:?} mode")
+            })?;
+            return Ok(if gap_tree_mode == GapTreeMode::Sharded {
+                Self::Sharded(source)
+            } else {
+                Self::Remote(source)
+            });
+        }
+        let Some(snapshot_nullifiers) = snapshot_nullifiers else {
+            return Err(eyre::eyre!(
+                "{pool} snapshot nullifiers are required by the airdrop configuration"
+            ));
+        };
+        ensure!(
+            gap_tree_mode == GapTreeMode::Sparse || has_gap_tree_file,
+            "{pool} gap-tree path is required by the airdrop configuration"
+        );
+        Ok(Self::Local(snapshot_nullifiers))
+    }
+}
+
 /// Generic pool claim processor.
 ///
 /// Processes claims for any pool type implementing `PoolProcessor`.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "Pool inputs are passed through explicitly"
+)]
 #[instrument(level = "debug", skip_all, fields(pool = %P::POOL))]
-async fn process_pool_claims<P: PoolProcessor>(
+pub(super) async fn process_pool_claims<P: PoolProcessor>(
     pool_enabled_in_config: bool,
-    visitor: &AccountNotesVisitor,
+    account_notes: &dyn AccountNotes,
     viewing_keys: &ViewingKeys,
     airdrop_config: &AirdropConfiguration,
-    snapshot_nullifiers: Option<PathBuf>,
-    gap_tree_file: Option<PathBuf>,
+    snapshot_nullifiers: Option<&SnapshotSource>,
+    gap_tree_file: Option<&Path>,
     gap_tree_mode: GapTreeMode,
+    remote_source: Option<&str>,
+    progress: &Progress,
 ) -> eyre::Result<PoolClaimResult<P::PrivateInputs>> {
     if !pool_enabled_in_config {
         return Ok(PoolClaimResult::empty());
     }
 
-    let Some(snapshot_nullifiers) = snapshot_nullifiers else {
+    let Some(expected_root) = P::expected_root(airdrop_config) else {
         return Err(eyre::eyre!(
-            "{} snapshot nullifiers path is required by the airdrop configuration",
+            "{} pool is unexpectedly missing in the airdrop configuration",
             P::POOL
         ));
     };
-    if gap_tree_mode != GapTreeMode::Sparse && gap_tree_file.is_none() {
-        return Err(eyre::eyre!(
-            "{} gap-tree path is required by the airdrop configuration",
-            P::POOL
-        ));
-    }
+    let witness_source = WitnessSource::resolve(
+        P::POOL,
+        snapshot_nullifiers,
+        gap_tree_file.is_some(),
+        gap_tree_mode,
+        remote_source,
+    )?;
 
-    let Some(notes) = P::collect_notes(visitor, viewing_keys, airdrop_config)? else {
+    let Some(notes) = P::collect_notes(account_notes, viewing_keys, airdrop_config)? else {
         warn!("UFVK has no {} viewing key; skipping", P::POOL);
         return Ok(PoolClaimResult::empty());
     };
 
     // Build merkle tree
     let user_nullifiers = SanitiseNullifiers::new(notes.keys().copied().collect());
-    let pool_data = build_pool_merkle_tree(
-        &snapshot_nullifiers,
-        gap_tree_file.as_deref(),
-        user_nullifiers,
-        P::POOL,
-        gap_tree_mode,
-    )
-    .await?;
+    let orchard_gap_tree_hash = airdrop_config
+        .orchard
+        .as_ref()
+        .map(|orchard| orchard.nullifier_gap_tree_hash)
+        .unwrap_or_default();
+    let options = ContainerOptions::new(airdrop_config.network, airdrop_config.snapshot_height);
+    let pool_data = match witness_source {
+        WitnessSource::Remote(url) => {
+            progress
+                .run_cancellable(fetch_remote_pool_data(
+                    url,
+                    P::POOL,
+                    orchard_gap_tree_hash,
+                    expected_root,
+                    &user_nullifiers,
+                ))
+                .await?
+        }
+        WitnessSource::Sharded(source) => {
+            progress
+                .run_cancellable(fetch_sharded_pool_data(
+                    source,
+                    P::POOL,
+                    orchard_gap_tree_hash,
+                    expected_root,
+                    &options,
+                    &user_nullifiers,
+                ))
+                .await?
+        }
+        WitnessSource::Local(SnapshotSource::File(snapshot_nullifiers)) => {
+            build_pool_merkle_tree(
+                snapshot_nullifiers,
+                gap_tree_file,
+                user_nullifiers,
+                P::POOL,
+                gap_tree_mode,
+                orchard_gap_tree_hash,
+                &options,
+                progress,
+            )
+            .await?
+        }
+        WitnessSource::Local(SnapshotSource::Nullifiers(chain_nullifiers)) => {
+            build_pool_merkle_tree_from_nullifiers(
+                Arc::clone(chain_nullifiers),
+                gap_tree_file,
+                user_nullifiers,
+                P::POOL,
+                gap_tree_mode,
+                orchard_gap_tree_hash,
+                &options,
+                progress,
+            )
+            .await?
+        }
+    };
 
     // Verify merkle root
     let anchor = pool_data.tree.root_bytes();
-    let Some(expected_root) = P::expected_root(airdrop_config) else {
-        return Err(eyre::eyre!(
-            "{} pool is unexpectedly missing in the airdrop configuration",
-            P::POOL
-        ));
-    };
     ensure!(
         expected_root == anchor,
         "{} merkle root mismatch with airdrop configuration",
@@ -659,18 +1005,38 @@ async fn process_pool_claims<P: PoolProcessor>(
     Ok(PoolClaimResult { claims })
 }
 
-/// Load nullifiers from a file.
-#[instrument(level = "debug", fields(path))]
-pub async fn load_nullifiers_from_file(path: &Path) -> eyre::Result<SanitiseNullifiers> {
+/// Load nullifiers from a snapshot file.
+///
+/// A versioned snapshot must be a `pool` snapshot taken for `options`; legacy files are
+/// accepted as-is.
+#[instrument(level = "debug", skip(options), fields(path))]
+pub async fn load_nullifiers_from_file(
+    path: &Path,
+    pool: Pool,
+    options: &ContainerOptions,
+) -> eyre::Result<SanitiseNullifiers> {
     debug!("Loading nullifiers from file");
 
     let file = File::open(path).await?;
     let reader = BufReader::with_capacity(FILE_BUF_SIZE, file);
 
-    let nullifiers = zair_scan::read_nullifiers(reader)
+    let snapshot = zair_scan::read_snapshot(reader)
         .await
         .context(format!("Failed to read {}", path.display()))?;
-    let sanitised_nullifiers = SanitiseNullifiers::new(nullifiers);
+    if let Some(header) = snapshot.header {
+        header
+            .ensure_content(ContainerKind::Snapshot, pool)
+            .and_then(|()| header.ensure_snapshot(options))
+            .with_context(|| {
+                format!(
+                    "Snapshot {} does not match the airdrop configuration",
+                    path.display()
+                )
+            })?;
+    } else {
+        warn!(file = %path.display(), "Snapshot file has no container header");
+    }
+    let sanitised_nullifiers = SanitiseNullifiers::new(snapshot.nullifiers);
 
     info!(file = %path.display(), "Read {} nullifiers from disk", sanitised_nullifiers.len());
 
@@ -717,12 +1083,14 @@ mod tests {
         }
     }
 
-    async fn write_snapshot_file(path: &Path, nullifiers: &SanitiseNullifiers) {
+    const OPTIONS: ContainerOptions = ContainerOptions::new(AirdropNetwork::Testnet, 3_839_800);
+
+    async fn write_snapshot_file(path: &Path, pool: Pool, nullifiers: &SanitiseNullifiers) {
         let file = File::create(path)
             .await
             .expect("snapshot file should be created");
         let mut writer = BufWriter::with_capacity(FILE_BUF_SIZE, file);
-        write_nullifiers(nullifiers, &mut writer)
+        write_nullifiers(nullifiers, pool, [0; 32], &OPTIONS, &mut writer)
             .await
             .expect("snapshot nullifiers should be written");
         writer.flush().await.expect("snapshot writer should flush");
@@ -749,6 +1117,7 @@ mod tests {
                 nullifier_gap_root: [4_u8; 32],
                 target_id: "ZAIRTEST:O".to_string(),
                 value_commitment_scheme: ValueCommitmentScheme::Native,
+                nullifier_gap_tree_hash: OrchardGapTreeHash::Sinsemilla,
             }),
         )
     }
@@ -759,7 +1128,7 @@ mod tests {
             let snapshot_path = unique_temp_path("snapshot");
             let gaptree_path = unique_temp_path("gaptree");
             let chain = chain_nullifiers(pool);
-            write_snapshot_file(&snapshot_path, &chain).await;
+            write_snapshot_file(&snapshot_path, pool, &chain).await;
             tokio::fs::write(&gaptree_path, [0_u8, 1_u8, 2_u8])
                 .await
                 .expect("corrupt cache bytes should be written");
@@ -770,6 +1139,9 @@ mod tests {
                 SanitiseNullifiers::new(vec![]),
                 pool,
                 GapTreeMode::None,
+                OrchardGapTreeHash::Sinsemilla,
+                &OPTIONS,
+                &Progress::default(),
             )
             .await;
 
@@ -791,7 +1163,7 @@ mod tests {
             let snapshot_path = unique_temp_path("snapshot");
             let gaptree_path = unique_temp_path("gaptree");
             let chain = chain_nullifiers(pool);
-            write_snapshot_file(&snapshot_path, &chain).await;
+            write_snapshot_file(&snapshot_path, pool, &chain).await;
             tokio::fs::write(&gaptree_path, [0_u8, 1_u8, 2_u8])
                 .await
                 .expect("corrupt cache bytes should be written");
@@ -802,6 +1174,9 @@ mod tests {
                 SanitiseNullifiers::new(vec![]),
                 pool,
                 GapTreeMode::Rebuild,
+                OrchardGapTreeHash::Sinsemilla,
+                &OPTIONS,
+                &Progress::default(),
             )
             .await
             .expect("rebuild should recover from corrupt gap-tree");
@@ -833,12 +1208,67 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn snapshot_or_gap_tree_for_other_height_is_rejected() {
+        let pool = Pool::Sapling;
+        let snapshot_path = unique_temp_path("snapshot");
+        let gaptree_path = unique_temp_path("gaptree");
+        let chain = chain_nullifiers(pool);
+        write_snapshot_file(&snapshot_path, pool, &chain).await;
+        let other = ContainerOptions::new(AirdropNetwork::Testnet, 1);
+
+        let snapshot_result = build_pool_merkle_tree(
+            &snapshot_path,
+            None,
+            SanitiseNullifiers::new(vec![]),
+            pool,
+            GapTreeMode::Sparse,
+            OrchardGapTreeHash::Sinsemilla,
+            &other,
+            &Progress::default(),
+        )
+        .await;
+
+        let tree = SaplingGapTree::from_nullifiers(&chain).expect("gap-tree should build");
+        tokio::fs::write(
+            &gaptree_path,
+            tree.to_bytes(&other).expect("gap-tree should encode"),
+        )
+        .await
+        .expect("gap-tree should be written");
+        let gap_tree_result = build_pool_merkle_tree(
+            &snapshot_path,
+            Some(&gaptree_path),
+            SanitiseNullifiers::new(vec![]),
+            pool,
+            GapTreeMode::None,
+            OrchardGapTreeHash::Sinsemilla,
+            &OPTIONS,
+            &Progress::default(),
+        )
+        .await;
+
+        cleanup(&[snapshot_path.as_path(), gaptree_path.as_path()]).await;
+
+        let err = snapshot_result
+            .err()
+            .expect("snapshot for another height should be rejected");
+        assert!(
+            err.to_string()
+                .contains("does not match the airdrop configuration")
+        );
+        let err = gap_tree_result
+            .err()
+            .expect("gap-tree for another height should be rejected");
+        assert!(err.to_string().contains("gap-tree"));
+    }
+
     #[tokio::test]
     async fn sparse_mode_builds_without_gap_tree_file() {
         for pool in POOLS {
             let snapshot_path = unique_temp_path("snapshot");
             let chain = chain_nullifiers(pool);
-            write_snapshot_file(&snapshot_path, &chain).await;
+            write_snapshot_file(&snapshot_path, pool, &chain).await;
 
             let pool_data = build_pool_merkle_tree(
                 &snapshot_path,
@@ -846,6 +1276,9 @@ mod tests {
                 SanitiseNullifiers::new(vec![]),
                 pool,
                 GapTreeMode::Sparse,
+                OrchardGapTreeHash::Sinsemilla,
+                &OPTIONS,
+                &Progress::default(),
             )
             .await
             .expect("sparse mode should build without gap-tree file");
@@ -860,6 +1293,68 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn poseidon_orchard_rebuild_matches_sparse_root() {
+        let snapshot_path = unique_temp_path("snapshot");
+        let gaptree_path = unique_temp_path("gaptree");
+        let chain = chain_nullifiers(Pool::Orchard);
+        write_snapshot_file(&snapshot_path, Pool::Orchard, &chain).await;
+
+        let rebuilt = build_pool_merkle_tree(
+            &snapshot_path,
+            Some(&gaptree_path),
+            SanitiseNullifiers::new(vec![]),
+            Pool::Orchard,
+            GapTreeMode::Rebuild,
+            OrchardGapTreeHash::Poseidon,
+            &OPTIONS,
+            &Progress::default(),
+        )
+        .await
+        .expect("poseidon rebuild should succeed");
+        let loaded = build_pool_merkle_tree(
+            &snapshot_path,
+            Some(&gaptree_path),
+            SanitiseNullifiers::new(vec![]),
+            Pool::Orchard,
+            GapTreeMode::None,
+            OrchardGapTreeHash::Poseidon,
+            &OPTIONS,
+            &Progress::default(),
+        )
+        .await
+        .expect("persisted poseidon gap-tree should load");
+        let sparse = build_pool_merkle_tree(
+            &snapshot_path,
+            None,
+            SanitiseNullifiers::new(vec![]),
+            Pool::Orchard,
+            GapTreeMode::Sparse,
+            OrchardGapTreeHash::Poseidon,
+            &OPTIONS,
+            &Progress::default(),
+        )
+        .await
+        .expect("poseidon sparse tree should build");
+
+        cleanup(&[snapshot_path.as_path(), gaptree_path.as_path()]).await;
+
+        assert!(matches!(rebuilt.tree, PoolMerkleTree::OrchardPoseidon(_)));
+        assert!(matches!(
+            sparse.tree,
+            PoolMerkleTree::OrchardPoseidonSparse(_)
+        ));
+        assert_eq!(rebuilt.tree.root_bytes(), loaded.tree.root_bytes());
+        assert_eq!(rebuilt.tree.root_bytes(), sparse.tree.root_bytes());
+        assert_ne!(
+            rebuilt.tree.root_bytes(),
+            OrchardGapTree::from_nullifiers_with_progress(&chain, |_, _| {})
+                .expect("sinsemilla gap-tree should build")
+                .root_bytes(),
+            "poseidon and sinsemilla roots should differ"
+        );
+    }
+
     #[test]
     fn sparse_mode_discards_gap_tree_paths() {
         let resolved = resolve_gap_tree_path_if_enabled(
@@ -881,12 +1376,41 @@ mod tests {
         let err = validate_pool_inputs(
             &config,
             None,
-            Some(&PathBuf::from("snapshot-orchard.bin")),
+            Some(&SnapshotSource::from(PathBuf::from("snapshot-orchard.bin"))),
             None,
             None,
             GapTreeMode::None,
+            None,
         )
         .expect_err("none mode must require gap-tree path for enabled pools");
         assert!(err.to_string().contains("--gap-tree-orchard is missing"));
     }
+
+    #[test]
+    fn remote_mode_requires_witness_server_but_no_files() {
+        let config = test_config(true, true);
+        let err = validate_pool_inputs(&config, None, None, None, None, GapTreeMode::Remote, None)
+            .expect_err("remote mode must require a witness server");
+        assert!(err.to_string().contains("--witness-server"));
+        validate_pool_inputs(
+            &config,
+            None,
+            None,
+            None,
+            None,
+            GapTreeMode::Remote,
+            Some("http://127.0.0.1:8080"),
+        )
+        .expect("remote mode should not require snapshot or gap-tree files");
+        assert!(
+            resolve_snapshot_path_if_enabled(
+                true,
+                Some(PathBuf::from("snapshot-sapling.bin")),
+                DEFAULT_SAPLING_SNAPSHOT_FILE,
+                Pool::Sapling,
+                GapTreeMode::Remote,
+            )
+            .is_none()
+        );
+    }
 }
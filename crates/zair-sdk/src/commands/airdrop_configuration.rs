@@ -1,26 +1,36 @@
 use std::ops::RangeInclusive;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr as _;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use eyre::{Context as _, ContextCompat as _, ensure};
 use http::Uri;
 use tokio::fs::File;
 use tokio::io::BufWriter;
-use tracing::{info, instrument, warn};
-use zair_core::base::{Pool, SanitiseNullifiers};
+use tracing::{info, instrument};
+use zair_core::base::Pool;
+use zair_core::container::ContainerOptions;
 use zair_core::schema::config::{
-    AirdropConfiguration, OrchardSnapshot, SaplingSnapshot, ValueCommitmentScheme,
+    AirdropConfiguration, ChainClock, OrchardGapTreeHash, ValueCommitmentScheme,
 };
-use zair_nonmembership::{OrchardGapTree, SaplingGapTree};
+use zair_nonmembership::MerklePathError;
 use zair_scan::light_walletd::LightWalletd;
 use zair_scan::scanner::ChainNullifiersVisitor;
 use zair_scan::write_nullifiers;
 use zcash_protocol::consensus::BlockHeight;
 
+use super::config_signature::{TrustedOrganizer, parse_trusted_configuration};
+use super::configuration_builder::{
+    AirdropConfigurationBuilder, AirdropSnapshot, PoolSnapshot, validate_target_ids,
+};
+use super::exclusion_report::{log_exclusions, read_exclusion_report};
+use super::gap_shards::{encode_gap_shards, write_gap_shards};
+use super::transparent_balances::read_transparent_balances;
 use crate::common::{CommonConfig, PoolSelection, resolve_lightwalletd_url, to_airdrop_network};
 use crate::network_params::{
     orchard_activation_height, sapling_activation_height, scan_start_height,
 };
+use crate::progress::{Phase, Progress};
 
 /// 1 MiB buffer for file I/O.
 const FILE_BUF_SIZE: usize = 1024 * 1024;
@@ -28,8 +38,25 @@ const FILE_BUF_SIZE: usize = 1024 * 1024;
 /// Build the airdrop configuration by fetching nullifiers from lightwalletd,
 /// computing the non-membership roots, and exporting snapshot metadata.
 ///
+/// This fetches the inputs of an [`AirdropConfigurationBuilder`] and writes its outputs; embedders
+/// holding the nullifiers in memory can use the builder directly.
+///
+/// Returns the configuration written to `configuration_output_file`.
+///
+/// With `gap_tree_shard_depth`, each gap tree is also split into shards of
+/// `2^gap_tree_shard_depth` gaps under `gap_tree_shards_dir` for sharded claim preparation.
+///
+/// With `transparent_balances`, the configuration also commits to the balance root of that
+/// `address,zatoshis` listing so its P2PKH holders can claim under `transparent_target_id`.
+///
+/// With `value_pools`, a full node's `getblock <height> 1` output, the value of every pool
+/// that cannot claim (always including Sprout) is written to `exclusion_report_file`.
+///
+/// Fetch and gap-tree progress is reported to `progress`, which can also cancel the build.
+///
 /// # Errors
-/// Returns an error if fetching nullifiers, validating inputs, or writing files fails.
+/// Returns an error if fetching nullifiers, validating inputs, or writing files fails, or
+/// [`Cancelled`](crate::progress::Cancelled) if `progress` is cancelled.
 #[instrument(level = "debug", skip_all, fields(snapshot_height = config.snapshot_height, ?pool))]
 #[allow(
     clippy::too_many_lines,
@@ -45,12 +72,50 @@ pub async fn build_airdrop_configuration(
     sapling_gap_tree_file: PathBuf,
     orchard_gap_tree_file: PathBuf,
     no_gap_tree: bool,
+    gap_tree_shards_dir: PathBuf,
+    gap_tree_shard_depth: Option<u8>,
     sapling_target_id: String,
     sapling_value_commitment_scheme: ValueCommitmentScheme,
     orchard_target_id: String,
     orchard_value_commitment_scheme: ValueCommitmentScheme,
-) -> eyre::Result<()> {
-    validate_target_ids(pool, &sapling_target_id, &orchard_target_id)?;
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    transparent_balances: Option<PathBuf>,
+    transparent_target_id: String,
+    value_pools: Option<PathBuf>,
+    exclusion_report_file: PathBuf,
+    progress: &Progress,
+) -> eyre::Result<AirdropConfiguration> {
+    validate_target_ids(
+        pool.includes_sapling(),
+        &sapling_target_id,
+        pool.includes_orchard(),
+        &orchard_target_id,
+    )?;
+    ensure!(
+        transparent_target_id.len() <= 32,
+        "Transparent target_id must be at most 32 bytes"
+    );
+    let network = to_airdrop_network(config.network);
+    let mut builder = AirdropConfigurationBuilder::new(network, config.snapshot_height)
+        .target_id(Pool::Sapling, sapling_target_id)
+        .target_id(Pool::Orchard, orchard_target_id)
+        .target_id(Pool::Transparent, transparent_target_id)
+        .value_commitment_scheme(Pool::Sapling, sapling_value_commitment_scheme)
+        .value_commitment_scheme(Pool::Orchard, orchard_value_commitment_scheme)
+        .orchard_gap_tree_hash(orchard_gap_tree_hash)
+        .progress(progress.clone());
+    let transparent = transparent_balances.is_some();
+    // Parse the listing before the long nullifier fetch so a bad file fails fast.
+    if let Some(path) = transparent_balances {
+        builder = builder.transparent_balances(read_transparent_balances(network, &path).await?);
+    }
+    let exclusions = match value_pools {
+        Some(path) => Some(
+            read_exclusion_report(network, config.snapshot_height, &path, pool, transparent)
+                .await?,
+        ),
+        None => None,
+    };
 
     let scan_range = resolve_snapshot_scan_range(config.network, pool, config.snapshot_height)?;
     let lightwalletd_url =
@@ -63,59 +128,36 @@ pub async fn build_airdrop_configuration(
     let mut visitor = ChainNullifiersVisitor::default();
     let mut last_fetch_pct = 0_usize;
     info!(progress = "0%", "Fetching nullifiers");
-    lightwalletd
-        .scan_nullifiers_with_progress(
-            &mut visitor,
-            &scan_range,
-            |height, scanned, total| {
-                if total == 0 {
-                    return;
-                }
-                #[allow(
-                    clippy::arithmetic_side_effects,
-                    reason = "Fetch progress percentage uses saturating operations and is guarded against total=0"
-                )]
-                let pct = scanned.saturating_mul(100).saturating_div(total);
-                if pct >= last_fetch_pct.saturating_add(10) {
-                    last_fetch_pct = pct;
-                    info!(
-                        progress = %format!("{pct}%"),
-                        current_height = height,
-                        scanned_blocks = scanned,
-                        total_blocks = total,
-                        "Fetching nullifiers"
-                    );
-                }
-            },
-        )
+    let scan = lightwalletd.scan_nullifiers_with_progress(
+        &mut visitor,
+        &scan_range,
+        |height, scanned, total| {
+            progress.report_count(Phase::FetchNullifiers, None, scanned, total);
+            if total == 0 {
+                return;
+            }
+            #[allow(
+                clippy::arithmetic_side_effects,
+                reason = "Fetch progress percentage uses saturating operations and is guarded against total=0"
+            )]
+            let pct = scanned.saturating_mul(100).saturating_div(total);
+            if pct >= last_fetch_pct.saturating_add(10) {
+                last_fetch_pct = pct;
+                info!(
+                    progress = %format!("{pct}%"),
+                    current_height = height,
+                    scanned_blocks = scanned,
+                    total_blocks = total,
+                    "Fetching nullifiers"
+                );
+            }
+        },
+    );
+    progress
+        .run_cancellable(async { scan.await.map_err(Into::into) })
         .await?;
     let (sapling_nullifiers, orchard_nullifiers) = visitor.sanitise_nullifiers();
 
-    let sapling_handle = tokio::spawn(process_pool(
-        pool.includes_sapling(),
-        Pool::Sapling,
-        sapling_nullifiers,
-        sapling_snapshot_nullifiers,
-        if no_gap_tree {
-            None
-        } else {
-            Some(sapling_gap_tree_file)
-        },
-    ));
-    let orchard_handle = tokio::spawn(process_pool(
-        pool.includes_orchard(),
-        Pool::Orchard,
-        orchard_nullifiers,
-        orchard_snapshot_nullifiers,
-        if no_gap_tree {
-            None
-        } else {
-            Some(orchard_gap_tree_file)
-        },
-    ));
-
-    let (sapling_nf_root, orchard_nf_root) = tokio::try_join!(sapling_handle, orchard_handle)?;
-
     // Note commitment roots needed for proving note existence.
     let upper_limit: u32 = config
         .snapshot_height
@@ -130,71 +172,66 @@ pub async fn build_airdrop_configuration(
         .await
         .context("Failed to fetch commitment tree roots from lightwalletd")?;
 
-    let sapling = if pool.includes_sapling() {
-        let sapling_nf_root = sapling_nf_root?
-            .context("Sapling pool enabled but nullifier gap root was not produced")?;
-        Some(SaplingSnapshot {
-            note_commitment_root: note_commitment_roots.sapling,
-            nullifier_gap_root: sapling_nf_root,
-            target_id: sapling_target_id,
-            value_commitment_scheme: sapling_value_commitment_scheme,
-        })
-    } else {
-        None
-    };
-
-    let orchard = if pool.includes_orchard() {
-        let orchard_nf_root = orchard_nf_root?
-            .context("Orchard pool enabled but nullifier gap root was not produced")?;
-        Some(OrchardSnapshot {
-            note_commitment_root: note_commitment_roots.orchard,
-            nullifier_gap_root: orchard_nf_root,
-            target_id: orchard_target_id,
-            value_commitment_scheme: orchard_value_commitment_scheme,
-        })
-    } else {
-        None
-    };
+    if pool.includes_sapling() {
+        builder = builder.pool(
+            Pool::Sapling,
+            sapling_nullifiers,
+            note_commitment_roots.sapling,
+        );
+    }
+    if pool.includes_orchard() {
+        builder = builder.pool(
+            Pool::Orchard,
+            orchard_nullifiers,
+            note_commitment_roots.orchard,
+        );
+    }
+    let AirdropSnapshot {
+        config: config_out,
+        sapling,
+        orchard,
+    } = builder.build().await?;
 
-    let config_out = AirdropConfiguration::new(
-        to_airdrop_network(config.network),
-        config.snapshot_height,
+    let container_options = ContainerOptions::new(network, config.snapshot_height);
+    let sapling_handle = tokio::spawn(write_pool_snapshot(
+        Pool::Sapling,
         sapling,
+        sapling_snapshot_nullifiers,
+        (!no_gap_tree).then_some(sapling_gap_tree_file),
+        gap_tree_shard_depth.map(|depth| (gap_tree_shards_dir.clone(), depth)),
+        container_options,
+    ));
+    let orchard_handle = tokio::spawn(write_pool_snapshot(
+        Pool::Orchard,
         orchard,
-    );
+        orchard_snapshot_nullifiers,
+        (!no_gap_tree).then_some(orchard_gap_tree_file),
+        gap_tree_shard_depth.map(|depth| (gap_tree_shards_dir, depth)),
+        container_options,
+    ));
+    let (sapling_written, orchard_written) = tokio::try_join!(sapling_handle, orchard_handle)?;
+    sapling_written?;
+    orchard_written?;
 
     let json = serde_json::to_string_pretty(&config_out)?;
     tokio::fs::write(&configuration_output_file, json).await?;
 
     info!(file = ?configuration_output_file, "Exported configuration");
-    Ok(())
-}
 
-fn validate_target_ids(
-    pool: PoolSelection,
-    sapling_target_id: &str,
-    orchard_target_id: &str,
-) -> eyre::Result<()> {
-    if pool.includes_sapling() {
-        ensure!(
-            sapling_target_id.len() == 8,
-            "Sapling target_id must be exactly 8 bytes"
-        );
-    }
-    if pool.includes_orchard() {
-        ensure!(
-            orchard_target_id.len() <= 32,
-            "Orchard target_id must be at most 32 bytes"
-        );
+    if let Some(exclusions) = exclusions {
+        log_exclusions(&exclusions);
+        let json = serde_json::to_string_pretty(&exclusions)?;
+        tokio::fs::write(&exclusion_report_file, json).await?;
+        info!(file = ?exclusion_report_file, "Exported exclusion report");
     }
-    Ok(())
+    Ok(config_out)
 }
 
 /// Resolve the scan range for collecting nullifiers for a snapshot.
 ///
 /// For `Both`, scanning starts at min(Sapling start, Orchard start), so one chain
 /// pass covers both pools.
-fn resolve_snapshot_scan_range(
+pub(super) fn resolve_snapshot_scan_range(
     network: zcash_protocol::consensus::Network,
     pool: PoolSelection,
     snapshot_height: u64,
@@ -223,89 +260,119 @@ fn resolve_snapshot_scan_range(
     Ok(scan_start..=snapshot_height)
 }
 
+/// Read an airdrop configuration file, resolved to the airdrop `target` when one is named.
+///
+/// With `trusted_organizer`, the file is refused unless the organizer's detached signature
+/// covers its bytes. The file is read once, so the configuration returned is the one whose
+/// signature was checked.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed, its targets repeat a name or target
+/// id, the target cannot be resolved, or
+/// [`UntrustedConfiguration`](super::UntrustedConfiguration) if the configuration is not signed
+/// by the trusted organizer.
+pub(super) async fn read_airdrop_configuration(
+    airdrop_configuration_file: &Path,
+    target: Option<&str>,
+    trusted_organizer: Option<&TrustedOrganizer>,
+) -> eyre::Result<AirdropConfiguration> {
+    let config_bytes = tokio::fs::read(airdrop_configuration_file).await?;
+    let config: AirdropConfiguration = match trusted_organizer {
+        Some(organizer) => {
+            parse_trusted_configuration(airdrop_configuration_file, &config_bytes, organizer)
+                .await?
+        }
+        None => serde_json::from_slice(&config_bytes)
+            .context("Failed to parse airdrop configuration JSON")?,
+    };
+    config.validate_targets()?;
+    let Some(target) = target else {
+        return Ok(config);
+    };
+    let config = config.for_target(target)?;
+    info!(target, "Using airdrop target");
+    Ok(config)
+}
+
+/// Target-chain position at which to check a claim window.
+///
+/// The time is read from the system clock unless `timestamp` is given.
+pub(super) fn chain_clock(height: Option<u64>, timestamp: Option<u64>) -> ChainClock {
+    ChainClock {
+        height,
+        timestamp: timestamp.or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|elapsed| elapsed.as_secs())
+        }),
+    }
+}
+
+/// Write the snapshot nullifiers of `pool` to `store`, and its gap tree and shards when asked.
 #[instrument(level = "debug", skip_all, fields(pool = ?pool, store = %store.display()))]
-async fn process_pool(
-    enabled: bool,
+async fn write_pool_snapshot(
     pool: Pool,
-    nullifiers: SanitiseNullifiers,
+    snapshot: Option<PoolSnapshot>,
     store: PathBuf,
     gap_tree_store: Option<PathBuf>,
-) -> eyre::Result<Option<[u8; 32]>> {
-    if !enabled {
-        return Ok(None);
-    }
+    gap_tree_shards: Option<(PathBuf, u8)>,
+    options: ContainerOptions,
+) -> eyre::Result<()> {
+    let Some(snapshot) = snapshot else {
+        return Ok(());
+    };
 
-    if nullifiers.is_empty() {
-        warn!("No nullifiers collected; using canonical empty-gap root");
-    } else {
-        info!(count = nullifiers.len(), "Collected nullifiers");
-    }
+    let persist_gap_tree = gap_tree_store.is_some();
+    let shard_depth = gap_tree_shards.as_ref().map(|(_, depth)| *depth);
+    let (snapshot, tree_bytes, shard_files) = tokio::task::spawn_blocking(move || {
+        let tree_bytes = persist_gap_tree
+            .then(|| snapshot.gap_tree.to_bytes(&options))
+            .transpose()?;
+        let shard_files = shard_depth
+            .map(|depth| {
+                snapshot
+                    .gap_tree
+                    .shards(&snapshot.nullifiers, depth)
+                    .and_then(|shards| encode_gap_shards(&shards, &options))
+            })
+            .transpose()?;
+        Ok::<_, MerklePathError>((snapshot, tree_bytes, shard_files))
+    })
+    .await??;
 
     let file = File::create(&store).await?;
     let mut writer = BufWriter::with_capacity(FILE_BUF_SIZE, file);
-    write_nullifiers(&nullifiers, &mut writer).await?;
+    write_nullifiers(
+        &snapshot.nullifiers,
+        pool,
+        snapshot.gap_tree.root_bytes(),
+        &options,
+        &mut writer,
+    )
+    .await?;
     info!(file = ?store, pool = ?pool, "Saved nullifiers");
 
-    let merkle_root = match pool {
-        Pool::Sapling => {
-            info!(pool = ?pool, progress = "0%", "Building non-membership tree");
-            let sapling_tree = tokio::task::spawn_blocking(move || {
-                SaplingGapTree::from_nullifiers_with_progress(&nullifiers, |current, total| {
-                    if total == 0 {
-                        return;
-                    }
-                    #[allow(
-                        clippy::arithmetic_side_effects,
-                        reason = "Tree build progress percentage uses saturating operations and is guarded against total=0"
-                    )]
-                    let pct = current.saturating_mul(100).saturating_div(total);
-                    info!(pool = ?pool, progress = %format!("{pct}%"), "Building non-membership tree");
-                })
-            })
-            .await??;
-            let root = sapling_tree.root_bytes();
-            if let Some(path) = gap_tree_store {
-                tokio::fs::write(&path, sapling_tree.to_bytes()).await?;
-                info!(pool = ?pool, file = %path.display(), "Saved gap-tree");
-            }
-            root
-        }
-        Pool::Orchard => {
-            info!(pool = ?pool, progress = "0%", "Building non-membership tree");
-            let orchard_tree = tokio::task::spawn_blocking(move || {
-                OrchardGapTree::from_nullifiers_with_progress(&nullifiers, |current, total| {
-                    if total == 0 {
-                        return;
-                    }
-                    #[allow(
-                        clippy::arithmetic_side_effects,
-                        reason = "Tree build progress percentage uses saturating operations and is guarded against total=0"
-                    )]
-                    let pct = current.saturating_mul(100).saturating_div(total);
-                    info!(pool = ?pool, progress = %format!("{pct}%"), "Building non-membership tree");
-                })
-            })
-            .await??;
-            let root = orchard_tree.root_bytes();
-            if let Some(path) = gap_tree_store {
-                tokio::fs::write(&path, orchard_tree.to_bytes()).await?;
-                info!(pool = ?pool, file = %path.display(), "Saved gap-tree");
-            }
-            root
-        }
-    };
-
-    Ok(Some(merkle_root))
+    if let (Some(path), Some(bytes)) = (gap_tree_store, tree_bytes) {
+        tokio::fs::write(&path, bytes).await?;
+        info!(pool = ?pool, file = %path.display(), "Saved gap-tree");
+    }
+    if let (Some((dir, _)), Some(files)) = (gap_tree_shards, shard_files) {
+        write_gap_shards(Path::new(&dir), pool, files).await?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use std::time::{SystemTime, UNIX_EPOCH};
-
+    use zair_core::base::SanitiseNullifiers;
     use zair_core::schema::config::{AirdropNetwork, OrchardSnapshot, SaplingSnapshot};
+    use zair_nonmembership::{OrchardPoseidonGapTree, SaplingGapTree};
 
     use super::*;
 
+    const OPTIONS: ContainerOptions = ContainerOptions::new(AirdropNetwork::Testnet, 200);
+
     #[test]
     fn deserialize_json_format() {
         // Documents the expected JSON format for consumers.
@@ -343,48 +410,136 @@ mod tests {
                 nullifier_gap_root: [6_u8; 32],
                 target_id: "ZAIRTEST:O".to_string(),
                 value_commitment_scheme: ValueCommitmentScheme::Sha256,
+                nullifier_gap_tree_hash: OrchardGapTreeHash::Sinsemilla,
             }),
         );
 
         assert_eq!(json_config, expected_config);
     }
 
-    #[test]
-    fn orchard_allows_target_id_up_to_32_bytes() {
-        let target = "a".repeat(32);
-        validate_target_ids(PoolSelection::Orchard, "ZAIRTEST", &target)
-            .expect("Orchard target_id should be allowed up to 32 bytes");
-    }
-
     #[tokio::test]
-    async fn process_pool_empty_nullifiers_uses_canonical_root() {
-        let nullifiers = SanitiseNullifiers::new(vec![]);
-
-        let unique = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("clock should be after unix epoch")
-            .as_nanos();
-        let path = std::env::temp_dir().join(format!(
-            "zair-empty-nullifiers-{}-{unique}.bin",
-            std::process::id()
-        ));
-
-        let root = process_pool(true, Pool::Sapling, nullifiers, path.clone(), None)
+    async fn empty_snapshot_is_written_with_the_canonical_root() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("snapshot-sapling.bin");
+        let snapshot = AirdropConfigurationBuilder::new(AirdropNetwork::Testnet, 200)
+            .pool(Pool::Sapling, SanitiseNullifiers::new(vec![]), [0_u8; 32])
+            .build()
             .await
-            .expect("processing should succeed")
-            .expect("enabled pool should produce a root");
+            .expect("build configuration");
+
+        write_pool_snapshot(
+            Pool::Sapling,
+            snapshot.sapling,
+            path.clone(),
+            None,
+            None,
+            OPTIONS,
+        )
+        .await
+        .expect("writing should succeed");
 
-        let expected_nullifiers = SanitiseNullifiers::new(vec![]);
-        let expected_root = SaplingGapTree::from_nullifiers(&expected_nullifiers)
+        let expected_root = SaplingGapTree::from_nullifiers(&SanitiseNullifiers::new(vec![]))
             .expect("empty nullifiers should produce canonical tree")
             .root_bytes();
-        assert_eq!(root, expected_root);
+        let bytes = std::fs::read(&path).expect("snapshot file must exist");
+        let (header, payload) =
+            zair_core::container::decode_header(&bytes).expect("snapshot should be a container");
+        assert_eq!(header.item_count, 0);
+        assert_eq!(header.root, expected_root);
+        assert!(payload.is_empty(), "empty snapshot should have no payload");
+    }
+
+    #[tokio::test]
+    async fn poseidon_orchard_gap_tree_and_shards_are_written() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let snapshot_path = dir.path().join("snapshot-orchard.bin");
+        let gap_tree_path = dir.path().join("gaptree-orchard.bin");
+        let snapshot = AirdropConfigurationBuilder::new(AirdropNetwork::Testnet, 200)
+            .pool(Pool::Orchard, SanitiseNullifiers::new(vec![]), [0_u8; 32])
+            .target_id(Pool::Orchard, "ZAIRTEST:O")
+            .orchard_gap_tree_hash(OrchardGapTreeHash::Poseidon)
+            .build()
+            .await
+            .expect("build configuration");
+        let root = snapshot
+            .config
+            .orchard
+            .as_ref()
+            .expect("orchard pool")
+            .nullifier_gap_root;
+
+        write_pool_snapshot(
+            Pool::Orchard,
+            snapshot.orchard,
+            snapshot_path,
+            Some(gap_tree_path.clone()),
+            Some((dir.path().join("shards"), 4)),
+            OPTIONS,
+        )
+        .await
+        .expect("writing should succeed");
+
+        let persisted = std::fs::read(&gap_tree_path).expect("gap-tree file must exist");
+        let tree = OrchardPoseidonGapTree::from_bytes(&persisted)
+            .expect("persisted poseidon gap-tree should decode");
+        assert_eq!(tree.root_bytes(), root);
+        assert_eq!(tree.header().map(|header| header.pool), Some(Pool::Orchard));
+
+        let index = std::fs::read(dir.path().join("shards/orchard/index.bin"))
+            .expect("shard index must exist");
+        let index = zair_nonmembership::GapShardIndex::from_bytes(&index, Pool::Orchard)
+            .expect("shard index should decode");
+        assert_eq!(index.root_bytes(), root);
+        assert_eq!(index.shard_count(), 1);
+        assert!(dir.path().join("shards/orchard/shard-000000.bin").exists());
+    }
+
+    #[tokio::test]
+    async fn trusted_configuration_is_parsed_from_the_signed_bytes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let config_path = dir.path().join("config.json");
+        let signature_path = dir.path().join("config.sig.json");
+        let config = br#"{"network":"testnet","snapshot_height":200}"#;
+        std::fs::write(&config_path, config).expect("write config");
+        let secret = [5_u8; 32];
+        let signature = super::super::sign_configuration(config, &secret);
+        std::fs::write(
+            &signature_path,
+            serde_json::to_string(&signature).expect("serialize signature"),
+        )
+        .expect("write signature");
+        let organizer = TrustedOrganizer {
+            public_key: super::super::organizer_public_key(&secret),
+            signature_file: signature_path.clone(),
+        };
 
-        let size = std::fs::metadata(&path)
-            .expect("snapshot file must exist")
-            .len();
-        assert_eq!(size, 0, "empty snapshot file should contain zero bytes");
+        let trusted = read_airdrop_configuration(&config_path, None, Some(&organizer))
+            .await
+            .expect("signed configuration is accepted");
+        assert_eq!(trusted.snapshot_height, 200);
 
-        std::fs::remove_file(path).expect("temporary snapshot file should be removable");
+        std::fs::write(
+            &config_path,
+            br#"{"network":"testnet","snapshot_height":201}"#,
+        )
+        .expect("tamper config");
+        let err = read_airdrop_configuration(&config_path, None, Some(&organizer))
+            .await
+            .expect_err("tampered configuration must be refused");
+        assert!(
+            err.downcast_ref::<super::super::UntrustedConfiguration>()
+                .is_some(),
+            "{err:#}"
+        );
+
+        std::fs::remove_file(&signature_path).expect("remove signature");
+        let err = read_airdrop_configuration(&config_path, None, Some(&organizer))
+            .await
+            .expect_err("missing signature must be refused");
+        assert!(
+            err.chain()
+                .any(|cause| cause.downcast_ref::<std::io::Error>().is_some()),
+            "{err:#}"
+        );
     }
 }
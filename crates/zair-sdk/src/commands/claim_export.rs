@@ -0,0 +1,74 @@
+//! Export signed claim submissions to target-chain payload formats.
+
+use std::path::PathBuf;
+
+use eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use serde_with::hex::Hex;
+use serde_with::serde_as;
+use tracing::info;
+use zair_core::base::{Nullifier, Pool};
+use zair_core::schema::submission::ClaimSubmission;
+
+use crate::namada::submission_payloads;
+
+/// Target format for `claim export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Borsh-encoded Namada claim transaction payloads.
+    Namada,
+}
+
+/// One exported claim payload.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedClaim {
+    /// Pool the claim was made from.
+    pub pool: Pool,
+    /// Airdrop nullifier of the claim.
+    pub airdrop_nullifier: Nullifier,
+    /// Encoded payload bytes.
+    #[serde_as(as = "Hex")]
+    pub payload: Vec<u8>,
+}
+
+/// Convert every claim of a signed submission into target-chain payloads.
+///
+/// The output is a JSON array with one hex-encoded payload per claim, Sapling first.
+///
+/// # Errors
+/// Returns an error if the submission cannot be parsed, a claim cannot be encoded, or the
+/// output cannot be written.
+pub async fn export_claim_submission(
+    submission_file: PathBuf,
+    format: ExportFormat,
+    output_file: PathBuf,
+) -> eyre::Result<()> {
+    let submission: ClaimSubmission =
+        serde_json::from_str(&tokio::fs::read_to_string(&submission_file).await?)
+            .context("Failed to parse submission JSON")?;
+
+    let exported = match format {
+        ExportFormat::Namada => submission_payloads(&submission)?
+            .into_iter()
+            .map(|claim| {
+                Ok(ExportedClaim {
+                    pool: claim.pool(),
+                    airdrop_nullifier: claim.airdrop_nullifier(),
+                    payload: claim.to_bytes()?,
+                })
+            })
+            .collect::<eyre::Result<Vec<_>>>()?,
+    };
+
+    tokio::fs::write(&output_file, serde_json::to_vec_pretty(&exported)?)
+        .await
+        .with_context(|| format!("Failed to write {}", output_file.display()))?;
+    info!(
+        ?format,
+        claims = exported.len(),
+        file = %output_file.display(),
+        "Exported claim payloads"
+    );
+    Ok(())
+}
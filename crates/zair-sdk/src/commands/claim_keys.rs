@@ -0,0 +1,37 @@
+//! Seed-derived key material for proving and signing claims.
+
+use zair_core::schema::config::AirdropNetwork;
+use zcash_keys::keys::UnifiedSpendingKey;
+use zip32::AccountId;
+
+use crate::common::to_zcash_network;
+
+/// Spending keys of one ZIP-32 account, derived from a wallet seed.
+pub struct ClaimKeys {
+    usk: UnifiedSpendingKey,
+}
+
+impl ClaimKeys {
+    /// Derive the spending keys of `account_id` from a 64-byte seed.
+    ///
+    /// # Errors
+    /// Returns an error if `account_id` is not a valid ZIP-32 account index or key derivation
+    /// fails.
+    pub fn from_seed(
+        network: AirdropNetwork,
+        seed: &[u8; 64],
+        account_id: u32,
+    ) -> eyre::Result<Self> {
+        let account_id =
+            AccountId::try_from(account_id).map_err(|_| eyre::eyre!("Invalid account-id"))?;
+        let usk = UnifiedSpendingKey::from_seed(&to_zcash_network(network), seed, account_id)
+            .map_err(|e| eyre::eyre!("Failed to derive spending key: {e:?}"))?;
+        Ok(Self { usk })
+    }
+
+    /// The account's unified spending key.
+    #[must_use]
+    pub const fn spending_key(&self) -> &UnifiedSpendingKey {
+        &self.usk
+    }
+}
@@ -0,0 +1,320 @@
+//! Structured claim messages and recipient address validation.
+//!
+//! Claim messages are opaque bytes to the signature scheme, so a typo in a recipient address is
+//! signed as readily as a valid one. Messages following the [`StructuredClaimMessage`] schema are
+//! checked before they are hashed: the recipient must pass the [`AddressValidator`] registered
+//! for its `address_format`.
+
+use std::collections::BTreeMap;
+#[cfg(feature = "native")]
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(feature = "native")]
+use eyre::Context as _;
+#[cfg(feature = "native")]
+use tracing::info;
+use zair_core::schema::message::{
+    CLAIM_MESSAGE_SCHEMA, CLAIM_MESSAGE_SCHEMA_PREFIX, StructuredClaimMessage,
+};
+
+use crate::namada::{NAMADA_ADDRESS_FORMAT, NamadaAddressValidator};
+
+/// Why a recipient address is malformed.
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
+pub enum AddressError {
+    /// The address is not a valid encoding, for example because its checksum fails.
+    #[error("{0}")]
+    Encoding(String),
+    /// The address prefix does not belong to the address format.
+    #[error("prefix {found:?} is not one of {expected}")]
+    UnexpectedPrefix {
+        /// Prefix of the address.
+        found: String,
+        /// Comma-separated accepted prefixes.
+        expected: String,
+    },
+    /// The address payload has the wrong length for its prefix.
+    #[error("{prefix} addresses carry {expected} bytes, got {found}")]
+    UnexpectedLength {
+        /// Prefix of the address.
+        prefix: String,
+        /// Payload length of the prefix.
+        expected: usize,
+        /// Payload length of the address.
+        found: usize,
+    },
+}
+
+/// Why a structured claim message is refused.
+#[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
+pub enum InvalidClaimMessage {
+    /// The message names the structured schema but does not follow it.
+    #[error("claim message does not follow the {CLAIM_MESSAGE_SCHEMA} schema: {0}")]
+    Malformed(String),
+    /// The message uses a schema version this build does not know.
+    #[error("unsupported claim message schema {0:?}, expected {CLAIM_MESSAGE_SCHEMA:?}")]
+    UnsupportedSchema(String),
+    /// The message does not name its target chain.
+    #[error("claim message has an empty chain_id")]
+    EmptyChainId,
+    /// No validator is registered for the recipient's address format.
+    #[error("no validator for address format {format:?} (known: {known})")]
+    UnknownAddressFormat {
+        /// Address format of the message.
+        format: String,
+        /// Comma-separated registered address formats.
+        known: String,
+    },
+    /// The recipient is not a valid address of its format.
+    #[error("invalid {format} recipient {recipient:?}: {reason}")]
+    InvalidRecipient {
+        /// Address format of the message.
+        format: String,
+        /// Recipient address of the message.
+        recipient: String,
+        /// Why the address is malformed.
+        reason: AddressError,
+    },
+}
+
+/// Checks recipient addresses of one target-chain address format.
+pub trait AddressValidator: Send + Sync {
+    /// Check that `address` is a well-formed recipient on the chain `chain_id`.
+    ///
+    /// # Errors
+    /// Returns why the address is malformed.
+    fn validate(&self, address: &str, chain_id: &str) -> Result<(), AddressError>;
+}
+
+/// Address validators by the `address_format` they check.
+///
+/// The default registry validates Namada addresses; embedders add validators for other target
+/// chains with [`Self::with`].
+#[derive(Clone)]
+pub struct AddressValidators {
+    by_format: BTreeMap<String, Arc<dyn AddressValidator>>,
+}
+
+impl std::fmt::Debug for AddressValidators {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.by_format.keys()).finish()
+    }
+}
+
+impl Default for AddressValidators {
+    fn default() -> Self {
+        Self::empty().with(NAMADA_ADDRESS_FORMAT, NamadaAddressValidator)
+    }
+}
+
+impl AddressValidators {
+    /// A registry without validators, refusing every structured message.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self {
+            by_format: BTreeMap::new(),
+        }
+    }
+
+    /// Register `validator` for `address_format`, replacing any previous one.
+    #[must_use]
+    pub fn with(
+        mut self,
+        address_format: impl Into<String>,
+        validator: impl AddressValidator + 'static,
+    ) -> Self {
+        self.by_format
+            .insert(address_format.into(), Arc::new(validator));
+        self
+    }
+
+    /// Check the chain id and recipient of `message`.
+    ///
+    /// # Errors
+    /// Returns [`InvalidClaimMessage`] if the schema is unknown, the chain id is empty, or the
+    /// recipient fails its address format's validator.
+    pub fn validate(&self, message: &StructuredClaimMessage) -> Result<(), InvalidClaimMessage> {
+        if message.schema != CLAIM_MESSAGE_SCHEMA {
+            return Err(InvalidClaimMessage::UnsupportedSchema(
+                message.schema.clone(),
+            ));
+        }
+        if message.chain_id.trim().is_empty() {
+            return Err(InvalidClaimMessage::EmptyChainId);
+        }
+        let validator = self.by_format.get(&message.address_format).ok_or_else(|| {
+            InvalidClaimMessage::UnknownAddressFormat {
+                format: message.address_format.clone(),
+                known: self
+                    .by_format
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            }
+        })?;
+        validator
+            .validate(&message.recipient, &message.chain_id)
+            .map_err(|reason| InvalidClaimMessage::InvalidRecipient {
+                format: message.address_format.clone(),
+                recipient: message.recipient.clone(),
+                reason,
+            })
+    }
+
+    /// Validate `message` bytes if they are a structured claim message.
+    ///
+    /// Returns the parsed message, or `None` for bytes that are not JSON naming a
+    /// `zair-claim-message` schema; those are signed as they are. Bytes that are not JSON but
+    /// mention the schema prefix are taken for a broken structured message, not a plain one.
+    ///
+    /// # Errors
+    /// Returns [`InvalidClaimMessage`] if the bytes name the schema but are malformed or fail
+    /// [`Self::validate`].
+    pub fn check_message(
+        &self,
+        message: &[u8],
+    ) -> Result<Option<StructuredClaimMessage>, InvalidClaimMessage> {
+        let value = match serde_json::from_slice::<serde_json::Value>(message) {
+            Ok(value) => value,
+            Err(err) if mentions_schema(message) => {
+                return Err(InvalidClaimMessage::Malformed(err.to_string()));
+            }
+            Err(_) => return Ok(None),
+        };
+        let Some(schema) = value
+            .get("schema")
+            .and_then(serde_json::Value::as_str)
+            .filter(|schema| schema.starts_with(CLAIM_MESSAGE_SCHEMA_PREFIX))
+        else {
+            return Ok(None);
+        };
+        if schema != CLAIM_MESSAGE_SCHEMA {
+            return Err(InvalidClaimMessage::UnsupportedSchema(schema.to_owned()));
+        }
+        let message: StructuredClaimMessage = serde_json::from_value(value)
+            .map_err(|err| InvalidClaimMessage::Malformed(err.to_string()))?;
+        self.validate(&message)?;
+        Ok(Some(message))
+    }
+}
+
+/// Whether `message` contains the structured claim message schema prefix.
+fn mentions_schema(message: &[u8]) -> bool {
+    message
+        .windows(CLAIM_MESSAGE_SCHEMA_PREFIX.len())
+        .any(|window| window == CLAIM_MESSAGE_SCHEMA_PREFIX.as_bytes())
+}
+
+/// Write a structured claim message for `recipient` on `chain_id` to `output`.
+///
+/// The recipient is checked with the default [`AddressValidators`] before anything is written.
+///
+/// # Errors
+/// Returns [`InvalidClaimMessage`] if the message is refused, and an error if `output` cannot
+/// be written.
+#[cfg(feature = "native")]
+pub async fn write_claim_message(
+    chain_id: String,
+    address_format: String,
+    recipient: String,
+    memo: Option<String>,
+    output: PathBuf,
+) -> eyre::Result<StructuredClaimMessage> {
+    let message = StructuredClaimMessage::new(chain_id, address_format, recipient, memo);
+    AddressValidators::default().validate(&message)?;
+    tokio::fs::write(&output, serde_json::to_vec_pretty(&message)?)
+        .await
+        .with_context(|| format!("Failed to write claim message {}", output.display()))?;
+    info!(
+        file = ?output,
+        recipient = %message.recipient,
+        chain_id = %message.chain_id,
+        "Claim message written"
+    );
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct AnyAddress;
+
+    impl AddressValidator for AnyAddress {
+        fn validate(&self, _address: &str, _chain_id: &str) -> Result<(), AddressError> {
+            Ok(())
+        }
+    }
+
+    fn message(address_format: &str, recipient: &str) -> StructuredClaimMessage {
+        StructuredClaimMessage::new(
+            "namada.5f5de2dd1b88cba30586420".to_owned(),
+            address_format.to_owned(),
+            recipient.to_owned(),
+            None,
+        )
+    }
+
+    #[test]
+    fn plain_messages_are_not_checked() {
+        let validators = AddressValidators::default();
+        assert_eq!(validators.check_message(b"claim to anything"), Ok(None));
+        assert_eq!(validators.check_message(br#"{"to": "tnam1"}"#), Ok(None));
+    }
+
+    #[test]
+    fn structured_messages_need_a_known_schema_and_format() {
+        let validators = AddressValidators::default();
+        let future = br#"{"schema": "zair-claim-message/v9", "chain_id": "x"}"#;
+        assert_eq!(
+            validators.check_message(future),
+            Err(InvalidClaimMessage::UnsupportedSchema(
+                "zair-claim-message/v9".to_owned()
+            ))
+        );
+        assert!(matches!(
+            validators.check_message(br#"{"schema": "zair-claim-message/v1"}"#),
+            Err(InvalidClaimMessage::Malformed(_))
+        ));
+        let truncated = br#"{"schema": "zair-claim-message/v1", "recipient": "tnam1"#;
+        assert!(matches!(
+            validators.check_message(truncated),
+            Err(InvalidClaimMessage::Malformed(_))
+        ));
+        assert_eq!(
+            validators.validate(&message("cosmos", "cosmos1abc")),
+            Err(InvalidClaimMessage::UnknownAddressFormat {
+                format: "cosmos".to_owned(),
+                known: "namada".to_owned(),
+            })
+        );
+
+        let validators = validators.with("cosmos", AnyAddress);
+        assert_eq!(
+            validators.validate(&message("cosmos", "cosmos1abc")),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn structured_messages_round_trip_through_check() {
+        let validators = AddressValidators::empty().with("any", AnyAddress);
+        let message = StructuredClaimMessage {
+            memo: Some("season 2".to_owned()),
+            ..message("any", "somewhere")
+        };
+        let bytes = serde_json::to_vec_pretty(&message).expect("serialize message");
+        assert_eq!(validators.check_message(&bytes), Ok(Some(message.clone())));
+
+        let empty_chain = StructuredClaimMessage {
+            chain_id: " ".to_owned(),
+            ..message
+        };
+        assert_eq!(
+            validators.validate(&empty_chain),
+            Err(InvalidClaimMessage::EmptyChainId)
+        );
+    }
+}
@@ -0,0 +1,483 @@
+//! In-memory claim proof generation.
+
+use bellman::groth16::PreparedVerifyingKey;
+use bls12_381::Bls12;
+use eyre::{ContextCompat as _, ensure};
+use group::GroupEncoding as _;
+use group::ff::{Field as _, FromUniformBytes as _, PrimeField as _};
+use halo2_proofs::poly::commitment::Params;
+use orchard::keys::{
+    FullViewingKey as OrchardFullViewingKey, SpendAuthorizingKey, SpendValidatingKey,
+};
+use orchard::value::ValueCommitTrapdoor as OrchardValueCommitTrapdoor;
+use pasta_curves::{pallas, vesta};
+use rand_core::{CryptoRng, RngCore};
+use tracing::info;
+use zair_core::base::Nullifier;
+use zair_core::schema::config::AirdropConfiguration;
+use zair_core::schema::proof_inputs::{
+    AirdropClaimInputs, ClaimInput, OrchardPrivateInputs, SaplingPrivateInputs, SerializableScope,
+};
+use zair_orchard_proofs::{
+    ClaimProofInputs as OrchardClaimProofInputs, GapTreeHash as OrchardGapTreeHash,
+    ValueCommitmentScheme as OrchardValueCommitmentScheme,
+    generate_claim_proof_with_rng as generate_orchard_claim_proof,
+    verify_claim_proof_output as verify_orchard_claim_proof_output,
+};
+use zair_sapling_proofs::prover::{
+    ClaimParameters, ClaimProofInputs, ValueCommitmentScheme as SaplingValueCommitmentScheme,
+    generate_claim_proof_with_rng,
+};
+use zair_sapling_proofs::verifier::{ClaimProofOutput, verify_claim_proof_output};
+use zcash_keys::keys::UnifiedSpendingKey;
+use zcash_spec::PrfExpand;
+
+use super::claim_keys::ClaimKeys;
+use super::claim_proofs::{
+    ClaimProofsOutput, ClaimSecretsOutput, OrchardClaimProofResult, OrchardClaimSecretResult,
+    SaplingClaimProofResult, SaplingClaimSecretResult,
+};
+
+/// Prove every claim of `inputs` one after another, drawing witness and proof randomness from
+/// `rng`.
+///
+/// `inputs` is first narrowed to the pools and target ids of `airdrop_config`, see
+/// [`AirdropClaimInputs::for_configuration`]. `sapling_params` and `orchard_params` are only
+/// required when `inputs` has claims in that pool. Every proof is self-verified before it is
+/// returned.
+///
+/// # Errors
+/// Returns an error if the claims were built for a different seed, a pool or its parameters are
+/// missing, or proving fails.
+pub fn prove_claims<R: RngCore + CryptoRng>(
+    inputs: &AirdropClaimInputs,
+    airdrop_config: &AirdropConfiguration,
+    keys: &ClaimKeys,
+    sapling_params: Option<&ClaimParameters>,
+    orchard_params: Option<&Params<vesta::Affine>>,
+    rng: &mut R,
+) -> eyre::Result<(ClaimProofsOutput, ClaimSecretsOutput)> {
+    let inputs = &inputs.clone().for_configuration(airdrop_config)?;
+    let sapling_keys = derive_sapling_proof_generation_keys(keys);
+    ensure!(
+        inputs
+            .sapling_claim_input
+            .iter()
+            .all(|claim| claim_matches_seed_keys(claim, &sapling_keys)),
+        "Seed mismatch: seed-derived Sapling keys do not match claim file"
+    );
+
+    let mut sapling_proofs = Vec::with_capacity(inputs.sapling_claim_input.len());
+    let mut sapling_secrets = Vec::with_capacity(inputs.sapling_claim_input.len());
+    if !inputs.sapling_claim_input.is_empty() {
+        let sapling = airdrop_config
+            .sapling
+            .as_ref()
+            .context("Sapling claims present but airdrop configuration has no sapling pool")?;
+        let params = sapling_params.context("Sapling claims present but no proving key given")?;
+        let pvk = params.prepared_verifying_key();
+        for claim_input in &inputs.sapling_claim_input {
+            let (proof, secret) = generate_single_sapling_proof(
+                claim_input,
+                params,
+                &pvk,
+                &sapling_keys,
+                sapling.note_commitment_root,
+                sapling.nullifier_gap_root,
+                sapling.value_commitment_scheme.into(),
+                rng,
+            )?;
+            sapling_proofs.push(proof);
+            sapling_secrets.push(secret);
+        }
+    }
+
+    let mut orchard_proofs = Vec::with_capacity(inputs.orchard_claim_input.len());
+    let mut orchard_secrets = Vec::with_capacity(inputs.orchard_claim_input.len());
+    if !inputs.orchard_claim_input.is_empty() {
+        let orchard = airdrop_config
+            .orchard
+            .as_ref()
+            .context("Orchard claims present but airdrop configuration has no orchard pool")?;
+        let params =
+            orchard_params.context("Orchard claims present but no Orchard params given")?;
+        for claim_input in &inputs.orchard_claim_input {
+            let (proof, secret) = generate_single_orchard_proof(
+                params,
+                claim_input,
+                keys,
+                orchard.note_commitment_root,
+                orchard.nullifier_gap_root,
+                &orchard.target_id,
+                orchard.value_commitment_scheme.into(),
+                orchard.nullifier_gap_tree_hash.into(),
+                rng,
+            )?;
+            orchard_proofs.push(proof);
+            orchard_secrets.push(secret);
+        }
+    }
+
+    Ok((
+        ClaimProofsOutput {
+            sapling_proofs,
+            orchard_proofs,
+        },
+        ClaimSecretsOutput {
+            sapling: sapling_secrets,
+            orchard: orchard_secrets,
+        },
+    ))
+}
+
+/// Sapling proof generation keys for both external and internal scopes.
+pub(super) struct SaplingProofGenerationKeys {
+    external: sapling::ProofGenerationKey,
+    internal: sapling::ProofGenerationKey,
+}
+
+/// Derive Sapling proof generation keys from the account's spending keys.
+pub(super) fn derive_sapling_proof_generation_keys(keys: &ClaimKeys) -> SaplingProofGenerationKeys {
+    let extsk = keys.spending_key().sapling();
+    SaplingProofGenerationKeys {
+        external: extsk.expsk.proof_generation_key(),
+        internal: extsk.derive_internal().expsk.proof_generation_key(),
+    }
+}
+
+/// Returns true when claim key material matches seed-derived key material for its scope.
+#[allow(clippy::similar_names)]
+pub(super) fn claim_matches_seed_keys(
+    claim_input: &ClaimInput<SaplingPrivateInputs>,
+    keys: &SaplingProofGenerationKeys,
+) -> bool {
+    let proof_generation_key = match claim_input.private_inputs.scope {
+        SerializableScope::External => &keys.external,
+        SerializableScope::Internal => &keys.internal,
+    };
+
+    let seed_ak = proof_generation_key.ak.to_bytes();
+    let seed_nk = proof_generation_key.to_viewing_key().nk.0.to_bytes();
+
+    claim_input.private_inputs.ak == seed_ak && claim_input.private_inputs.nk == seed_nk
+}
+
+/// Generate and verify a single Sapling claim proof.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "Per-claim Sapling proving needs explicit material"
+)]
+pub(super) fn generate_single_sapling_proof<R: RngCore + CryptoRng>(
+    claim_input: &ClaimInput<SaplingPrivateInputs>,
+    params: &ClaimParameters,
+    pvk: &PreparedVerifyingKey<Bls12>,
+    keys: &SaplingProofGenerationKeys,
+    note_commitment_root: [u8; 32],
+    nullifier_gap_root: [u8; 32],
+    value_commitment_scheme: SaplingValueCommitmentScheme,
+    rng: &mut R,
+) -> eyre::Result<(SaplingClaimProofResult, SaplingClaimSecretResult)> {
+    info!(
+        value = claim_input.private_inputs.value,
+        "Generating claim proof..."
+    );
+
+    let proof_generation_key = match claim_input.private_inputs.scope {
+        SerializableScope::External => keys.external.clone(),
+        SerializableScope::Internal => keys.internal.clone(),
+    };
+
+    // Caller-generated witness randomness (Sapling-style).
+    let alpha = jubjub::Fr::random(&mut *rng);
+    let alpha_bytes = alpha.to_repr();
+
+    let rcv = sapling::value::ValueCommitTrapdoor::random(&mut *rng);
+    let rcv_bytes = rcv.inner().to_repr();
+
+    let rcv_sha256 = match value_commitment_scheme {
+        SaplingValueCommitmentScheme::Native => None,
+        SaplingValueCommitmentScheme::Sha256 => {
+            let mut rcv_sha256 = [0_u8; 32];
+            rng.fill_bytes(&mut rcv_sha256);
+            Some(rcv_sha256)
+        }
+    };
+
+    let airdrop_nullifier: [u8; 32] = claim_input.public_inputs.airdrop_nullifier.into();
+    let claim_inputs = to_claim_proof_inputs(
+        &claim_input.private_inputs,
+        airdrop_nullifier,
+        note_commitment_root,
+        nullifier_gap_root,
+        value_commitment_scheme,
+        alpha_bytes,
+        rcv_bytes,
+        rcv_sha256,
+    );
+
+    let proof_output =
+        generate_claim_proof_with_rng(params, &claim_inputs, &proof_generation_key, rng)
+            .map_err(|e| eyre::eyre!("Failed to generate Sapling proof: {e}"))?;
+
+    verify_claim_proof_output(
+        &proof_output,
+        pvk,
+        value_commitment_scheme,
+        &note_commitment_root,
+        &nullifier_gap_root,
+    )
+    .map_err(|e| eyre::eyre!("Generated Sapling proof failed self-verification: {e}"))?;
+
+    info!("Proof generated and verified successfully");
+    Ok((
+        to_proof_result(&proof_output, claim_input.public_inputs.airdrop_nullifier),
+        SaplingClaimSecretResult {
+            airdrop_nullifier: claim_input.public_inputs.airdrop_nullifier,
+            alpha: alpha_bytes,
+            rcv: match value_commitment_scheme {
+                SaplingValueCommitmentScheme::Native => Some(rcv_bytes),
+                SaplingValueCommitmentScheme::Sha256 => None,
+            },
+            rcv_sha256,
+        },
+    ))
+}
+
+fn vec_to_orchard_depth_array(
+    path: &[[u8; 32]],
+) -> eyre::Result<[[u8; 32]; orchard::NOTE_COMMITMENT_TREE_DEPTH]> {
+    ensure!(
+        path.len() == orchard::NOTE_COMMITMENT_TREE_DEPTH,
+        "Expected Orchard Merkle path length {}, got {}",
+        orchard::NOTE_COMMITMENT_TREE_DEPTH,
+        path.len()
+    );
+    path.to_owned()
+        .try_into()
+        .map_err(|_| eyre::eyre!("Failed to convert Orchard merkle path to fixed array"))
+}
+
+fn derive_orchard_key_material_bytes(
+    usk: &UnifiedSpendingKey,
+    scope: SerializableScope,
+) -> eyre::Result<([u8; 32], [u8; 32], [u8; 32])> {
+    let sk = usk.orchard();
+    let fvk: OrchardFullViewingKey = sk.into();
+    let fvk_bytes = fvk.to_bytes();
+
+    let ak: [u8; 32] = fvk_bytes
+        .get(0..32)
+        .and_then(|s| s.try_into().ok())
+        .ok_or_else(|| eyre::eyre!("Invalid Orchard FVK encoding (ak)"))?;
+    let nk: [u8; 32] = fvk_bytes
+        .get(32..64)
+        .and_then(|s| s.try_into().ok())
+        .ok_or_else(|| eyre::eyre!("Invalid Orchard FVK encoding (nk)"))?;
+    let rivk_external: [u8; 32] = fvk_bytes
+        .get(64..96)
+        .and_then(|s| s.try_into().ok())
+        .ok_or_else(|| eyre::eyre!("Invalid Orchard FVK encoding (rivk)"))?;
+
+    let rivk = match scope {
+        SerializableScope::External => rivk_external,
+        SerializableScope::Internal => {
+            let prf_out = PrfExpand::ORCHARD_RIVK_INTERNAL.with(&rivk_external, &ak, &nk);
+            pallas::Scalar::from_uniform_bytes(&prf_out).to_repr()
+        }
+    };
+
+    Ok((ak, nk, rivk))
+}
+
+fn orchard_target_id_bytes(target_id: &str) -> eyre::Result<([u8; 32], u8)> {
+    ensure!(
+        target_id.len() <= 32,
+        "Orchard target_id must be at most 32 bytes"
+    );
+    let mut bytes = [0_u8; 32];
+    let prefix = bytes
+        .get_mut(..target_id.len())
+        .context("Orchard target_id length exceeds byte buffer")?;
+    prefix.copy_from_slice(target_id.as_bytes());
+    let len = u8::try_from(target_id.len())
+        .map_err(|_| eyre::eyre!("Orchard target_id length does not fit in u8"))?;
+    Ok((bytes, len))
+}
+
+#[allow(
+    clippy::too_many_lines,
+    clippy::too_many_arguments,
+    reason = "Per-claim Orchard proving needs explicit material"
+)]
+pub(super) fn generate_single_orchard_proof<R: RngCore + CryptoRng>(
+    params: &Params<vesta::Affine>,
+    claim_input: &ClaimInput<OrchardPrivateInputs>,
+    keys: &ClaimKeys,
+    orchard_note_root: [u8; 32],
+    orchard_gap_root: [u8; 32],
+    orchard_target_id: &str,
+    orchard_scheme: OrchardValueCommitmentScheme,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    rng: &mut R,
+) -> eyre::Result<(OrchardClaimProofResult, OrchardClaimSecretResult)> {
+    let usk = keys.spending_key();
+    let alpha = pallas::Scalar::random(&mut *rng);
+    let alpha_bytes = alpha.to_repr();
+
+    let rcv_bytes = loop {
+        let mut b = [0_u8; 32];
+        rng.fill_bytes(&mut b);
+        if Option::<OrchardValueCommitTrapdoor>::from(OrchardValueCommitTrapdoor::from_bytes(b))
+            .is_some()
+        {
+            break b;
+        }
+    };
+
+    let ask = SpendAuthorizingKey::from(usk.orchard());
+    let ak = SpendValidatingKey::from(&ask);
+    let ak_p_bytes = pallas::Point::from(&ak).to_bytes();
+
+    let rcv_sha256 = match orchard_scheme {
+        OrchardValueCommitmentScheme::Native => None,
+        OrchardValueCommitmentScheme::Sha256 => {
+            let mut bytes = [0_u8; 32];
+            rng.fill_bytes(&mut bytes);
+            Some(bytes)
+        }
+    };
+
+    let cm_merkle_path =
+        vec_to_orchard_depth_array(&claim_input.private_inputs.note_commitment_merkle_path)?;
+    let nf_merkle_path =
+        vec_to_orchard_depth_array(&claim_input.private_inputs.nullifier_gap_merkle_path)?;
+    let cm_note_position = u32::try_from(claim_input.private_inputs.note_commitment_position)
+        .map_err(|_| eyre::eyre!("Orchard note position does not fit in u32"))?;
+    let nf_leaf_position = u32::try_from(claim_input.private_inputs.nullifier_gap_position)
+        .map_err(|_| eyre::eyre!("Orchard non-membership leaf position does not fit in u32"))?;
+    let (_ak_bytes, nk_bytes, rivk_bytes) =
+        derive_orchard_key_material_bytes(usk, claim_input.private_inputs.scope)?;
+
+    let (target_id, target_id_len) = orchard_target_id_bytes(orchard_target_id)?;
+
+    let inputs = OrchardClaimProofInputs {
+        target_id,
+        target_id_len,
+        airdrop_nullifier: claim_input.public_inputs.airdrop_nullifier.into(),
+        note_commitment_root: orchard_note_root,
+        nullifier_gap_root: orchard_gap_root,
+        value_commitment_scheme: orchard_scheme,
+        rcv_sha256,
+        rho: claim_input.private_inputs.rho,
+        rseed: claim_input.private_inputs.rseed,
+        g_d: claim_input.private_inputs.g_d,
+        pk_d: claim_input.private_inputs.pk_d,
+        value: claim_input.private_inputs.value,
+        cm_note_position,
+        cm_merkle_path,
+        alpha: alpha_bytes,
+        ak_p: ak_p_bytes,
+        nk: nk_bytes,
+        rivk: rivk_bytes,
+        rcv: rcv_bytes,
+        left: claim_input.private_inputs.nullifier_gap_left_bound.into(),
+        right: claim_input.private_inputs.nullifier_gap_right_bound.into(),
+        gap_tree_hash: orchard_gap_tree_hash,
+        nf_leaf_position,
+        nf_merkle_path,
+    };
+
+    let proof_output = generate_orchard_claim_proof(params, &inputs, rng)?;
+    let target_id_slice = target_id
+        .get(..usize::from(target_id_len))
+        .context("Orchard target_id length exceeds padded target_id buffer")?;
+    verify_orchard_claim_proof_output(
+        params,
+        &proof_output,
+        orchard_note_root,
+        orchard_gap_root,
+        orchard_scheme,
+        orchard_gap_tree_hash,
+        target_id_slice,
+    )
+    .map_err(|e| eyre::eyre!("Generated Orchard proof failed self-verification: {e}"))?;
+
+    let proof = OrchardClaimProofResult {
+        zkproof: proof_output.zkproof,
+        rk: proof_output.rk,
+        cv: proof_output.cv,
+        cv_sha256: proof_output.cv_sha256,
+        airdrop_nullifier: claim_input.public_inputs.airdrop_nullifier,
+    };
+    let secret = OrchardClaimSecretResult {
+        airdrop_nullifier: claim_input.public_inputs.airdrop_nullifier,
+        alpha: alpha_bytes,
+        rcv: match orchard_scheme {
+            OrchardValueCommitmentScheme::Native => Some(rcv_bytes),
+            OrchardValueCommitmentScheme::Sha256 => None,
+        },
+        rcv_sha256,
+    };
+    Ok((proof, secret))
+}
+
+/// Convert `SaplingPrivateInputs` to `ClaimProofInputs`.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "Witness assembly requires all inputs"
+)]
+fn to_claim_proof_inputs(
+    private: &SaplingPrivateInputs,
+    airdrop_nullifier: [u8; 32],
+    note_commitment_root: [u8; 32],
+    nullifier_gap_root: [u8; 32],
+    value_commitment_scheme: SaplingValueCommitmentScheme,
+    alpha: [u8; 32],
+    rcv: [u8; 32],
+    rcv_sha256: Option<[u8; 32]>,
+) -> ClaimProofInputs {
+    // Convert the non-membership merkle path from Vec<[u8; 32]> to Vec<([u8; 32], bool)>
+    // The bool indicates if the current node is on the right side
+    let nm_merkle_path: Vec<([u8; 32], bool)> = private
+        .nullifier_gap_merkle_path
+        .iter()
+        .enumerate()
+        .map(|(i, sibling)| {
+            let is_right = (private.nullifier_gap_position >> i) & 1 == 1;
+            (*sibling, is_right)
+        })
+        .collect();
+
+    ClaimProofInputs {
+        diversifier: private.diversifier,
+        pk_d: private.pk_d,
+        value: private.value,
+        rcm: private.rcm,
+        position: private.note_commitment_position,
+        merkle_path: private.note_commitment_merkle_path.clone(),
+        note_commitment_root,
+        airdrop_nullifier,
+        nm_left_nf: private.nullifier_gap_left_bound.into(),
+        nm_right_nf: private.nullifier_gap_right_bound.into(),
+        nm_merkle_path,
+        nullifier_gap_root,
+        value_commitment_scheme,
+        alpha,
+        rcv,
+        rcv_sha256,
+    }
+}
+
+/// Convert `ClaimProofOutput` to `SaplingClaimProofResult`.
+const fn to_proof_result(
+    output: &ClaimProofOutput,
+    airdrop_nullifier: Nullifier,
+) -> SaplingClaimProofResult {
+    SaplingClaimProofResult {
+        zkproof: output.zkproof,
+        rk: output.rk,
+        cv: output.cv,
+        cv_sha256: output.cv_sha256,
+        airdrop_nullifier,
+    }
+}
@@ -0,0 +1,586 @@
+//! Persistent registry of claimed airdrop nullifiers.
+//!
+//! `ensure_unique_airdrop_nullifiers` only rejects duplicates inside one submission. The
+//! registry records every accepted `airdrop_nullifier` in a sled database, one tree per pool,
+//! so a claim replayed in a later submission is rejected as well.
+//!
+//! Records are keyed by the airdrop nullifier followed by the pool's `target_id`, so one
+//! registry can serve several airdrop targets of a configuration without their claims
+//! colliding.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use eyre::{Context as _, ContextCompat as _, ensure};
+use serde::{Deserialize, Serialize};
+use serde_with::hex::Hex;
+use serde_with::serde_as;
+use sled::transaction::{ConflictableTransactionError, TransactionError};
+use sled::{Transactional as _, Tree};
+use tracing::{info, warn};
+use zair_core::base::{Nullifier, Pool};
+use zair_core::schema::config::AirdropConfiguration;
+use zair_core::schema::submission::ClaimSubmission;
+
+use super::airdrop_configuration::read_airdrop_configuration;
+use super::config_signature::TrustedOrganizer;
+use super::nullifier_uniqueness::ensure_unique_airdrop_nullifiers;
+use super::verification_report::{ClaimVerdict, FailureReason, VerificationReport};
+
+/// A claim accepted by the verifier.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    /// Airdrop target the claim was made for, the pool's `target_id` in the configuration.
+    pub target_id: String,
+    /// Pool the claim was made from.
+    pub pool: Pool,
+    /// Airdrop nullifier of the claim.
+    pub airdrop_nullifier: Nullifier,
+    /// Hash of the claim's unsigned proof fields.
+    #[serde_as(as = "Hex")]
+    pub proof_hash: [u8; 32],
+    /// Hash of the claim's external message payload.
+    #[serde_as(as = "Hex")]
+    pub message_hash: [u8; 32],
+    /// Unix timestamp (seconds) at which the claim was recorded.
+    pub recorded_at: u64,
+}
+
+/// Registry entries for every claim in a submission, stamped with `recorded_at`.
+///
+/// Each entry takes the `target_id` of its pool in `airdrop_config`, which must already be
+/// resolved to the airdrop target the submission was verified for.
+///
+/// # Errors
+/// Returns an error if the submission has claims for a pool the configuration does not have.
+pub fn submission_entries(
+    submission: &ClaimSubmission,
+    airdrop_config: &AirdropConfiguration,
+    recorded_at: u64,
+) -> eyre::Result<Vec<RegistryEntry>> {
+    let target_id = |pool: Pool| -> eyre::Result<String> {
+        let target_id = match pool {
+            Pool::Sapling => airdrop_config.sapling.as_ref().map(|pool| &pool.target_id),
+            Pool::Orchard => airdrop_config.orchard.as_ref().map(|pool| &pool.target_id),
+            Pool::Transparent => airdrop_config
+                .transparent
+                .as_ref()
+                .map(|pool| &pool.target_id),
+        };
+        target_id
+            .cloned()
+            .with_context(|| format!("Airdrop configuration has no {pool} pool"))
+    };
+    let mut entries = Vec::new();
+    if !submission.sapling.is_empty() {
+        let target_id = target_id(Pool::Sapling)?;
+        entries.extend(submission.sapling.iter().map(|claim| RegistryEntry {
+            target_id: target_id.clone(),
+            pool: Pool::Sapling,
+            airdrop_nullifier: claim.airdrop_nullifier,
+            proof_hash: claim.proof_hash,
+            message_hash: claim.message_hash,
+            recorded_at,
+        }));
+    }
+    if !submission.orchard.is_empty() {
+        let target_id = target_id(Pool::Orchard)?;
+        entries.extend(submission.orchard.iter().map(|claim| RegistryEntry {
+            target_id: target_id.clone(),
+            pool: Pool::Orchard,
+            airdrop_nullifier: claim.airdrop_nullifier,
+            proof_hash: claim.proof_hash,
+            message_hash: claim.message_hash,
+            recorded_at,
+        }));
+    }
+    if !submission.transparent.is_empty() {
+        let target_id = target_id(Pool::Transparent)?;
+        entries.extend(submission.transparent.iter().map(|claim| RegistryEntry {
+            target_id: target_id.clone(),
+            pool: Pool::Transparent,
+            airdrop_nullifier: claim.airdrop_nullifier,
+            proof_hash: claim.proof_hash,
+            message_hash: claim.message_hash,
+            recorded_at,
+        }));
+    }
+    Ok(entries)
+}
+
+/// Sled key of a claim: the airdrop nullifier, then the target id.
+///
+/// The nullifier has a fixed length, so keys of different targets cannot collide.
+fn registry_key(target_id: &str, airdrop_nullifier: &Nullifier) -> Vec<u8> {
+    let mut key = AsRef::<[u8]>::as_ref(airdrop_nullifier).to_vec();
+    key.extend_from_slice(target_id.as_bytes());
+    key
+}
+
+/// Error returned when claims of a submission are already recorded in the registry.
+#[derive(Debug, thiserror::Error)]
+#[error("{} of {total} claims were already recorded in the registry", .claimed.len())]
+pub struct AlreadyClaimed {
+    /// The previously recorded entries of the replayed claims.
+    pub claimed: Vec<RegistryEntry>,
+    /// Number of claims in the submission.
+    pub total: usize,
+}
+
+/// Sled-backed set of claimed airdrop nullifiers.
+pub struct ClaimRegistry {
+    db: sled::Db,
+    sapling: Tree,
+    orchard: Tree,
+    transparent: Tree,
+}
+
+impl ClaimRegistry {
+    /// Open the registry at `path`, creating it if it does not exist.
+    ///
+    /// Writes are flushed by [`Self::record`], so sled's background flusher is disabled and the
+    /// database lock is released as soon as the registry is dropped.
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be opened.
+    pub fn open(path: &Path) -> eyre::Result<Self> {
+        let db = sled::Config::new()
+            .path(path)
+            .flush_every_ms(None)
+            .open()
+            .with_context(|| format!("Failed to open claim registry {}", path.display()))?;
+        let sapling = db.open_tree("sapling")?;
+        let orchard = db.open_tree("orchard")?;
+        let transparent = db.open_tree("transparent")?;
+        Ok(Self {
+            db,
+            sapling,
+            orchard,
+            transparent,
+        })
+    }
+
+    const fn tree(&self, pool: Pool) -> &Tree {
+        match pool {
+            Pool::Sapling => &self.sapling,
+            Pool::Orchard => &self.orchard,
+            Pool::Transparent => &self.transparent,
+        }
+    }
+
+    /// Look up the recorded claim for an airdrop nullifier of the airdrop target `target_id`.
+    ///
+    /// # Errors
+    /// Returns an error if the database read fails or the stored entry is corrupt.
+    pub fn get(
+        &self,
+        target_id: &str,
+        pool: Pool,
+        airdrop_nullifier: &Nullifier,
+    ) -> eyre::Result<Option<RegistryEntry>> {
+        self.tree(pool)
+            .get(registry_key(target_id, airdrop_nullifier))?
+            .map(|value| serde_json::from_slice(&value).context("Corrupt claim registry entry"))
+            .transpose()
+    }
+
+    /// Recorded entries that collide with the given claims.
+    ///
+    /// # Errors
+    /// Returns an error if a database read fails.
+    pub fn claimed(&self, entries: &[RegistryEntry]) -> eyre::Result<Vec<RegistryEntry>> {
+        let mut claimed = Vec::new();
+        for entry in entries {
+            if let Some(existing) =
+                self.get(&entry.target_id, entry.pool, &entry.airdrop_nullifier)?
+            {
+                claimed.push(existing);
+            }
+        }
+        Ok(claimed)
+    }
+
+    /// Record claims atomically: if any airdrop nullifier is already present, nothing is written.
+    ///
+    /// # Errors
+    /// Returns [`AlreadyClaimed`] with the recorded entry of the first replayed claim, or an
+    /// error if the database write fails.
+    pub fn record(&self, entries: &[RegistryEntry]) -> eyre::Result<()> {
+        let encoded = entries
+            .iter()
+            .map(|entry| {
+                Ok((
+                    entry.pool,
+                    registry_key(&entry.target_id, &entry.airdrop_nullifier),
+                    serde_json::to_vec(entry)?,
+                ))
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        (&self.sapling, &self.orchard, &self.transparent)
+            .transaction(|(sapling, orchard, transparent)| {
+                for (pool, key, value) in &encoded {
+                    let tree = match pool {
+                        Pool::Sapling => sapling,
+                        Pool::Orchard => orchard,
+                        Pool::Transparent => transparent,
+                    };
+                    if let Some(existing) = tree.get(key.as_slice())? {
+                        return Err(ConflictableTransactionError::Abort(existing));
+                    }
+                    tree.insert(key.as_slice(), value.as_slice())?;
+                }
+                Ok(())
+            })
+            .map_err(|err| match err {
+                TransactionError::Abort(existing) => {
+                    match serde_json::from_slice::<RegistryEntry>(&existing) {
+                        Ok(existing) => AlreadyClaimed {
+                            claimed: vec![existing],
+                            total: entries.len(),
+                        }
+                        .into(),
+                        Err(err) => eyre::Report::new(err).wrap_err("Corrupt claim registry entry"),
+                    }
+                }
+                TransactionError::Storage(err) => err.into(),
+            })?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// All recorded claims, Sapling, then Orchard, then transparent, each pool in nullifier byte
+    /// order and then by target id.
+    ///
+    /// # Errors
+    /// Returns an error if the database read fails or an entry is corrupt.
+    pub fn entries(&self) -> eyre::Result<Vec<RegistryEntry>> {
+        self.sapling
+            .iter()
+            .chain(self.orchard.iter())
+            .chain(self.transparent.iter())
+            .map(|item| {
+                let (_, value) = item?;
+                serde_json::from_slice(&value).context("Corrupt claim registry entry")
+            })
+            .collect()
+    }
+}
+
+fn now_unix_seconds() -> eyre::Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+async fn load_submission_entries(
+    submission_file: &Path,
+    airdrop_configuration_file: &Path,
+    target: Option<&str>,
+    trusted_organizer: Option<&TrustedOrganizer>,
+) -> eyre::Result<Vec<RegistryEntry>> {
+    let submission: ClaimSubmission =
+        serde_json::from_str(&tokio::fs::read_to_string(submission_file).await?)
+            .context("Failed to parse submission JSON")?;
+    ensure_unique_airdrop_nullifiers(
+        submission
+            .sapling
+            .iter()
+            .map(|entry| entry.airdrop_nullifier),
+        "Sapling signed claim",
+    )?;
+    ensure_unique_airdrop_nullifiers(
+        submission
+            .orchard
+            .iter()
+            .map(|entry| entry.airdrop_nullifier),
+        "Orchard signed claim",
+    )?;
+    ensure_unique_airdrop_nullifiers(
+        submission
+            .transparent
+            .iter()
+            .map(|entry| entry.airdrop_nullifier),
+        "Transparent signed claim",
+    )?;
+    let airdrop_config =
+        read_airdrop_configuration(airdrop_configuration_file, target, trusted_organizer).await?;
+    submission_entries(&submission, &airdrop_config, now_unix_seconds()?)
+}
+
+fn warn_replay(entry: &RegistryEntry) {
+    warn!(
+        target_id = %entry.target_id,
+        pool = %entry.pool,
+        airdrop_nullifier = %entry.airdrop_nullifier,
+        recorded_at = entry.recorded_at,
+        "Airdrop nullifier already claimed"
+    );
+}
+
+/// Registry verdict of every claim in `entries`, which must be in submission order as
+/// [`submission_entries`] returns them. A claim already recorded fails with
+/// [`FailureReason::AlreadyClaimed`].
+///
+/// Returns the verdicts and the recorded entries of the replayed claims, logging every replay.
+///
+/// # Errors
+/// Returns an error if a database read fails.
+pub(super) fn registry_verdicts(
+    registry: &ClaimRegistry,
+    entries: &[RegistryEntry],
+) -> eyre::Result<(VerificationReport, Vec<RegistryEntry>)> {
+    let mut next_index = HashMap::<Pool, usize>::new();
+    let mut report = VerificationReport::default();
+    let mut claimed = Vec::new();
+    for entry in entries {
+        let index = next_index.entry(entry.pool).or_default();
+        let existing = registry.get(&entry.target_id, entry.pool, &entry.airdrop_nullifier)?;
+        let outcome = existing.as_ref().map_or(Ok(()), |existing| {
+            warn_replay(existing);
+            Err(FailureReason::AlreadyClaimed {
+                recorded_at: existing.recorded_at,
+            })
+        });
+        report.claims.push(ClaimVerdict {
+            registry: Some(outcome.into()),
+            ..ClaimVerdict::new(entry.pool, *index, entry.airdrop_nullifier)
+        });
+        *index = index.saturating_add(1);
+        claimed.extend(existing);
+    }
+    Ok((report, claimed))
+}
+
+/// Fail if any claim in `entries` is already recorded, logging every replay.
+pub(super) fn ensure_unclaimed(
+    registry: &ClaimRegistry,
+    entries: &[RegistryEntry],
+) -> eyre::Result<()> {
+    let claimed = registry.claimed(entries)?;
+    claimed.iter().for_each(warn_replay);
+    if claimed.is_empty() {
+        Ok(())
+    } else {
+        Err(AlreadyClaimed {
+            claimed,
+            total: entries.len(),
+        }
+        .into())
+    }
+}
+
+/// Record an already verified submission's claims in the registry.
+///
+/// The submission is not verified here, so `unverified` must be set to acknowledge that it was
+/// verified by other means. Use `verify run --registry` to verify and record in one step.
+///
+/// The claims are keyed by the target ids of the configuration, resolved to `target` when one
+/// is named. With `trusted_organizer`, a configuration the organizer did not sign is refused.
+///
+/// Returns the recorded entries.
+///
+/// # Errors
+/// Returns an error if `unverified` is not set, the submission or configuration cannot be
+/// parsed, or [`AlreadyClaimed`] if any claim was already recorded.
+pub async fn registry_import(
+    registry_dir: PathBuf,
+    submission_file: PathBuf,
+    airdrop_configuration_file: PathBuf,
+    target: Option<String>,
+    trusted_organizer: Option<TrustedOrganizer>,
+    unverified: bool,
+) -> eyre::Result<Vec<RegistryEntry>> {
+    ensure!(
+        unverified,
+        "registry import records claims without verifying them; use `verify run --registry` to \
+         verify and record a submission, or pass --unverified for a submission verified \
+         elsewhere"
+    );
+    let entries = load_submission_entries(
+        &submission_file,
+        &airdrop_configuration_file,
+        target.as_deref(),
+        trusted_organizer.as_ref(),
+    )
+    .await?;
+    let registry = ClaimRegistry::open(&registry_dir)?;
+    ensure_unclaimed(&registry, &entries)?;
+    registry.record(&entries)?;
+    info!(claims = entries.len(), registry = %registry_dir.display(), "Recorded claims");
+    Ok(entries)
+}
+
+/// Check that none of a submission's claims are recorded in the registry.
+///
+/// Returns the submission's entries as they would be recorded for the configuration, resolved
+/// to `target` when one is named. With `trusted_organizer`, a configuration the organizer did
+/// not sign is refused.
+///
+/// # Errors
+/// Returns an error if the submission or configuration cannot be parsed, or [`AlreadyClaimed`]
+/// if any claim was already recorded.
+pub async fn registry_check(
+    registry_dir: PathBuf,
+    submission_file: PathBuf,
+    airdrop_configuration_file: PathBuf,
+    target: Option<String>,
+    trusted_organizer: Option<TrustedOrganizer>,
+) -> eyre::Result<Vec<RegistryEntry>> {
+    let entries = load_submission_entries(
+        &submission_file,
+        &airdrop_configuration_file,
+        target.as_deref(),
+        trusted_organizer.as_ref(),
+    )
+    .await?;
+    let registry = ClaimRegistry::open(&registry_dir)?;
+    ensure_unclaimed(&registry, &entries)?;
+    info!(
+        claims = entries.len(),
+        "No claims are recorded in the registry"
+    );
+    Ok(entries)
+}
+
+/// Export every recorded claim as a JSON array and return the exported entries.
+///
+/// # Errors
+/// Returns an error if the registry cannot be read or the output cannot be written.
+pub async fn registry_export(
+    registry_dir: PathBuf,
+    output_file: PathBuf,
+) -> eyre::Result<Vec<RegistryEntry>> {
+    let registry = ClaimRegistry::open(&registry_dir)?;
+    let entries = registry.entries()?;
+    tokio::fs::write(&output_file, serde_json::to_vec_pretty(&entries)?)
+        .await
+        .with_context(|| format!("Failed to write {}", output_file.display()))?;
+    info!(claims = entries.len(), file = %output_file.display(), "Exported claim registry");
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::super::error_class::ErrorClass;
+    use super::*;
+
+    fn entry(pool: Pool, byte: u8) -> RegistryEntry {
+        target_entry("ZAIRTEST", pool, byte)
+    }
+
+    fn target_entry(target_id: &str, pool: Pool, byte: u8) -> RegistryEntry {
+        RegistryEntry {
+            target_id: target_id.to_owned(),
+            pool,
+            airdrop_nullifier: Nullifier::from([byte; 32]),
+            proof_hash: [byte.wrapping_add(1); 32],
+            message_hash: [byte.wrapping_add(2); 32],
+            recorded_at: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn replayed_claim_is_rejected() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let registry = ClaimRegistry::open(dir.path()).expect("registry should open");
+        let first = [entry(Pool::Sapling, 1), entry(Pool::Orchard, 1)];
+        registry.record(&first).expect("fresh claims should record");
+
+        let err = registry
+            .record(&[entry(Pool::Sapling, 2), entry(Pool::Sapling, 1)])
+            .expect_err("replay must be rejected");
+        let replayed = err
+            .downcast_ref::<AlreadyClaimed>()
+            .expect("a replay is reported as AlreadyClaimed");
+        assert_eq!(replayed.claimed, vec![entry(Pool::Sapling, 1)]);
+        assert_eq!(replayed.total, 2);
+        assert_eq!(ErrorClass::of(&err), ErrorClass::AlreadyClaimed);
+        assert!(
+            registry
+                .get("ZAIRTEST", Pool::Sapling, &Nullifier::from([2_u8; 32]))
+                .expect("read")
+                .is_none(),
+            "a rejected batch records nothing"
+        );
+        assert!(ensure_unclaimed(&registry, &[entry(Pool::Orchard, 1)]).is_err());
+        assert!(ensure_unclaimed(&registry, &[entry(Pool::Orchard, 2)]).is_ok());
+    }
+
+    #[test]
+    fn registry_verdicts_fail_only_the_replayed_claims() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let registry = ClaimRegistry::open(dir.path()).expect("registry should open");
+        registry
+            .record(&[entry(Pool::Sapling, 7)])
+            .expect("fresh claims should record");
+
+        let entries = [
+            entry(Pool::Orchard, 7),
+            entry(Pool::Sapling, 8),
+            entry(Pool::Sapling, 7),
+        ];
+        let (report, claimed) = registry_verdicts(&registry, &entries).expect("verdicts");
+        assert_eq!(claimed, vec![entry(Pool::Sapling, 7)]);
+        assert!(report.only_replays_failed());
+        assert_eq!(report.valid_indices(Pool::Orchard), HashSet::from([0]));
+        assert_eq!(report.valid_indices(Pool::Sapling), HashSet::from([0]));
+        assert_eq!(
+            report
+                .claims
+                .iter()
+                .flat_map(ClaimVerdict::failures)
+                .collect::<Vec<_>>(),
+            vec![&FailureReason::AlreadyClaimed {
+                recorded_at: 1_700_000_000
+            }]
+        );
+    }
+
+    #[test]
+    fn claims_are_scoped_to_their_target() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let registry = ClaimRegistry::open(dir.path()).expect("registry should open");
+        registry
+            .record(&[entry(Pool::Orchard, 5)])
+            .expect("fresh claims should record");
+
+        let other_target = target_entry("SEASON2:O", Pool::Orchard, 5);
+        assert!(ensure_unclaimed(&registry, std::slice::from_ref(&other_target)).is_ok());
+        registry
+            .record(std::slice::from_ref(&other_target))
+            .expect("the same nullifier of another target should record");
+        assert!(registry.record(&[other_target]).is_err());
+        assert_eq!(registry.entries().expect("entries").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn import_requires_the_unverified_flag() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let err = registry_import(
+            dir.path().join("registry"),
+            dir.path().join("submission.json"),
+            dir.path().join("config.json"),
+            None,
+            None,
+            false,
+        )
+        .await
+        .expect_err("import without --unverified must be refused");
+        assert!(err.to_string().contains("--unverified"), "{err:?}");
+        assert!(!dir.path().join("registry").exists());
+    }
+
+    #[test]
+    fn registry_persists_across_reopen() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let entries = vec![entry(Pool::Sapling, 3), entry(Pool::Orchard, 4)];
+        {
+            let registry = ClaimRegistry::open(dir.path()).expect("registry should open");
+            registry.record(&entries).expect("claims should record");
+        }
+        let registry = ClaimRegistry::open(dir.path()).expect("registry should reopen");
+        assert_eq!(registry.entries().expect("entries"), entries);
+    }
+}
@@ -0,0 +1,533 @@
+//! In-memory claim pipeline.
+//!
+//! A [`ClaimSession`] binds an airdrop configuration to the claimant's keys and witness
+//! sources, and runs `prepare`, `prove` and `sign` on typed values. The file-based commands
+//! ([`super::airdrop_claim`], [`super::generate_claim_proofs`], [`super::sign_claim_submission`])
+//! are thin wrappers around it.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use eyre::ContextCompat as _;
+#[cfg(feature = "prove")]
+use halo2_proofs::poly::commitment::Params;
+#[cfg(feature = "prove")]
+use pasta_curves::vesta;
+use tracing::debug;
+use zair_core::base::{Pool, SanitiseNullifiers};
+use zair_core::schema::config::AirdropConfiguration;
+use zair_core::schema::proof_inputs::AirdropClaimInputs;
+use zair_core::schema::submission::ClaimSubmission;
+#[cfg(feature = "prove")]
+use zair_sapling_proofs::prover::ClaimParameters;
+use zair_scan::ViewingKeys;
+use zair_scan::scanner::AccountNotes;
+use zcash_keys::keys::UnifiedFullViewingKey;
+
+use super::airdrop_claim::{
+    GapTreeMode, find_user_notes, import_user_notes, process_pool_claims, validate_pool_inputs,
+};
+use super::claim_keys::ClaimKeys;
+use super::claim_proofs::{ClaimProofsOutput, ClaimSecretsOutput};
+#[cfg(feature = "prove")]
+use super::claim_proofs_prove::prove_claim_inputs;
+use super::claim_signer::sign_claim_proofs;
+use super::pool_processor::{OrchardPool, SaplingPool};
+use super::submission_messages::ResolvedMessageHashes;
+use crate::common::{resolve_lightwalletd_url, to_zcash_network};
+use crate::progress::Progress;
+
+/// Snapshot nullifiers of one pool.
+#[derive(Debug, Clone)]
+pub enum SnapshotSource {
+    /// A snapshot file, read when claims are prepared.
+    File(PathBuf),
+    /// Nullifiers already in memory.
+    Nullifiers(Arc<SanitiseNullifiers>),
+}
+
+impl From<PathBuf> for SnapshotSource {
+    fn from(path: PathBuf) -> Self {
+        Self::File(path)
+    }
+}
+
+impl From<SanitiseNullifiers> for SnapshotSource {
+    fn from(nullifiers: SanitiseNullifiers) -> Self {
+        Self::Nullifiers(Arc::new(nullifiers))
+    }
+}
+
+/// Builder for a [`ClaimSession`]; only the airdrop configuration is required.
+#[derive(Default)]
+pub struct ClaimSessionBuilder {
+    config: Option<AirdropConfiguration>,
+    lightwalletd_url: Option<String>,
+    ufvk: Option<UnifiedFullViewingKey>,
+    birthday_height: Option<u64>,
+    scan_cache_dir: Option<PathBuf>,
+    wallet_db: Option<PathBuf>,
+    sapling_snapshot: Option<SnapshotSource>,
+    orchard_snapshot: Option<SnapshotSource>,
+    sapling_gap_tree: Option<PathBuf>,
+    orchard_gap_tree: Option<PathBuf>,
+    gap_tree_mode: Option<GapTreeMode>,
+    witness_server_url: Option<String>,
+    gap_tree_shards: Option<String>,
+    keys: Option<ClaimKeys>,
+    progress: Progress,
+    #[cfg(feature = "prove")]
+    sapling_params: Option<Arc<ClaimParameters>>,
+    #[cfg(feature = "prove")]
+    orchard_params: Option<Arc<Params<vesta::Affine>>>,
+}
+
+/// An airdrop configuration bound to one claimant's keys and witness sources.
+pub struct ClaimSession {
+    config: AirdropConfiguration,
+    lightwalletd_url: Option<String>,
+    ufvk: Option<UnifiedFullViewingKey>,
+    birthday_height: Option<u64>,
+    scan_cache_dir: Option<PathBuf>,
+    wallet_db: Option<PathBuf>,
+    sapling_snapshot: Option<SnapshotSource>,
+    orchard_snapshot: Option<SnapshotSource>,
+    sapling_gap_tree: Option<PathBuf>,
+    orchard_gap_tree: Option<PathBuf>,
+    gap_tree_mode: GapTreeMode,
+    witness_server_url: Option<String>,
+    gap_tree_shards: Option<String>,
+    keys: Option<ClaimKeys>,
+    progress: Progress,
+    #[cfg(feature = "prove")]
+    sapling_params: Option<Arc<ClaimParameters>>,
+    #[cfg(feature = "prove")]
+    orchard_params: Option<Arc<Params<vesta::Affine>>>,
+}
+
+impl ClaimSessionBuilder {
+    /// Airdrop configuration to claim against.
+    #[must_use]
+    pub fn config(mut self, config: AirdropConfiguration) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Lightwalletd endpoint used to scan for notes; defaults to the network's endpoint.
+    #[must_use]
+    pub fn lightwalletd_url(mut self, url: impl Into<String>) -> Self {
+        self.lightwalletd_url = Some(url.into());
+        self
+    }
+
+    /// Viewing key to scan with; derived from [`Self::keys`] when not set.
+    #[must_use]
+    pub fn ufvk(mut self, ufvk: UnifiedFullViewingKey) -> Self {
+        self.ufvk = Some(ufvk);
+        self
+    }
+
+    /// Wallet birthday height; scanning starts here.
+    #[must_use]
+    pub const fn birthday_height(mut self, height: u64) -> Self {
+        self.birthday_height = Some(height);
+        self
+    }
+
+    /// Directory holding per-account scan caches, so repeated `prepare` calls only scan blocks
+    /// not seen before.
+    #[must_use]
+    pub fn scan_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.scan_cache_dir = Some(dir.into());
+        self
+    }
+
+    /// `zcash_client_sqlite` wallet database to import notes and witnesses from instead of
+    /// scanning; the wallet must have scanned past the snapshot height.
+    #[must_use]
+    pub fn wallet_db(mut self, path: impl Into<PathBuf>) -> Self {
+        self.wallet_db = Some(path.into());
+        self
+    }
+
+    /// Snapshot nullifiers of `pool`, as a file path or in-memory nullifiers.
+    ///
+    /// Ignored for [`Pool::Transparent`], which has no nullifiers.
+    #[must_use]
+    pub fn snapshot(mut self, pool: Pool, source: impl Into<SnapshotSource>) -> Self {
+        match pool {
+            Pool::Sapling => self.sapling_snapshot = Some(source.into()),
+            Pool::Orchard => self.orchard_snapshot = Some(source.into()),
+            Pool::Transparent => {}
+        }
+        self
+    }
+
+    /// Gap-tree file of `pool`, mapped in [`GapTreeMode::None`] and written in
+    /// [`GapTreeMode::Rebuild`]. Ignored for [`Pool::Transparent`].
+    #[must_use]
+    pub fn gap_tree_file(mut self, pool: Pool, path: PathBuf) -> Self {
+        match pool {
+            Pool::Sapling => self.sapling_gap_tree = Some(path),
+            Pool::Orchard => self.orchard_gap_tree = Some(path),
+            Pool::Transparent => {}
+        }
+        self
+    }
+
+    /// How gap witnesses are obtained; defaults to [`GapTreeMode::Sparse`], which needs no
+    /// gap-tree files.
+    #[must_use]
+    pub const fn gap_tree_mode(mut self, mode: GapTreeMode) -> Self {
+        self.gap_tree_mode = Some(mode);
+        self
+    }
+
+    /// Witness server URL for [`GapTreeMode::Remote`].
+    #[must_use]
+    pub fn witness_server(mut self, url: impl Into<String>) -> Self {
+        self.witness_server_url = Some(url.into());
+        self
+    }
+
+    /// Base URL or directory of the published gap-tree shards for [`GapTreeMode::Sharded`].
+    #[must_use]
+    pub fn gap_tree_shards(mut self, source: impl Into<String>) -> Self {
+        self.gap_tree_shards = Some(source.into());
+        self
+    }
+
+    /// Spending keys used to prove and sign.
+    #[must_use]
+    pub const fn keys(mut self, keys: ClaimKeys) -> Self {
+        self.keys = Some(keys);
+        self
+    }
+
+    /// Progress sink and cancellation token for `prepare` and `prove`.
+    #[must_use]
+    pub fn progress(mut self, progress: Progress) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Sapling proving key, required to prove Sapling claims.
+    #[cfg(feature = "prove")]
+    #[must_use]
+    pub fn sapling_proving_key(mut self, params: Arc<ClaimParameters>) -> Self {
+        self.sapling_params = Some(params);
+        self
+    }
+
+    /// Orchard Halo2 params, required to prove Orchard claims.
+    #[cfg(feature = "prove")]
+    #[must_use]
+    pub fn orchard_params(mut self, params: Arc<Params<vesta::Affine>>) -> Self {
+        self.orchard_params = Some(params);
+        self
+    }
+
+    /// Finish the session.
+    ///
+    /// # Errors
+    /// Returns an error if no airdrop configuration was given or its targets repeat a name or
+    /// target id.
+    pub fn build(self) -> eyre::Result<ClaimSession> {
+        let config = self
+            .config
+            .context("Claim session requires an airdrop configuration")?;
+        config.validate_targets()?;
+        Ok(ClaimSession {
+            config,
+            lightwalletd_url: self.lightwalletd_url,
+            ufvk: self.ufvk,
+            birthday_height: self.birthday_height,
+            scan_cache_dir: self.scan_cache_dir,
+            wallet_db: self.wallet_db,
+            sapling_snapshot: self.sapling_snapshot,
+            orchard_snapshot: self.orchard_snapshot,
+            sapling_gap_tree: self.sapling_gap_tree,
+            orchard_gap_tree: self.orchard_gap_tree,
+            gap_tree_mode: self.gap_tree_mode.unwrap_or(GapTreeMode::Sparse),
+            witness_server_url: self.witness_server_url,
+            gap_tree_shards: self.gap_tree_shards,
+            keys: self.keys,
+            progress: self.progress,
+            #[cfg(feature = "prove")]
+            sapling_params: self.sapling_params,
+            #[cfg(feature = "prove")]
+            orchard_params: self.orchard_params,
+        })
+    }
+}
+
+impl ClaimSession {
+    /// Start building a session.
+    #[must_use]
+    pub fn builder() -> ClaimSessionBuilder {
+        ClaimSessionBuilder::default()
+    }
+
+    /// The airdrop configuration this session claims against.
+    #[must_use]
+    pub const fn config(&self) -> &AirdropConfiguration {
+        &self.config
+    }
+
+    /// Scan for the claimant's unspent notes at the snapshot and build their claim inputs.
+    ///
+    /// With [`ClaimSessionBuilder::wallet_db`] the notes are imported from the wallet database
+    /// instead of scanned.
+    ///
+    /// # Errors
+    /// Returns an error if the gap-tree mode lacks the witness sources it needs, no viewing
+    /// key or spending keys were given, neither a birthday height nor a wallet database was
+    /// given, or scanning, importing, loading snapshots or building witnesses fails.
+    pub async fn prepare(&self) -> eyre::Result<AirdropClaimInputs> {
+        let remote_source = self.remote_source();
+        validate_pool_inputs(
+            &self.config,
+            self.sapling_snapshot.as_ref(),
+            self.orchard_snapshot.as_ref(),
+            self.sapling_gap_tree.as_ref(),
+            self.orchard_gap_tree.as_ref(),
+            self.gap_tree_mode,
+            remote_source,
+        )?;
+        let ufvk = match (&self.ufvk, &self.keys) {
+            (Some(ufvk), _) => ufvk.clone(),
+            (None, Some(keys)) => keys.spending_key().to_unified_full_viewing_key(),
+            (None, None) => {
+                return Err(eyre::eyre!(
+                    "Preparing claims requires a viewing key or spending keys"
+                ));
+            }
+        };
+        let network = to_zcash_network(self.config.network);
+        let lightwalletd_url = resolve_lightwalletd_url(network, self.lightwalletd_url.as_deref());
+
+        let account_notes: Box<dyn AccountNotes> = if let Some(wallet_db) = &self.wallet_db {
+            Box::new(
+                import_user_notes(
+                    &lightwalletd_url,
+                    network,
+                    self.config.snapshot_height,
+                    ufvk.clone(),
+                    wallet_db,
+                )
+                .await?,
+            )
+        } else {
+            let birthday_height = self
+                .birthday_height
+                .context("Preparing claims requires a birthday height or a wallet database")?;
+            debug!(birthday_height, "Using user-provided birthday height");
+            Box::new(
+                find_user_notes(
+                    &lightwalletd_url,
+                    network,
+                    self.config.snapshot_height,
+                    ufvk.clone(),
+                    birthday_height,
+                    self.scan_cache_dir.as_deref(),
+                    &self.progress,
+                )
+                .await?,
+            )
+        };
+
+        let viewing_keys = ViewingKeys::new(&ufvk);
+
+        // Process pools in parallel
+        let (sapling_result, orchard_result) = tokio::try_join!(
+            process_pool_claims::<SaplingPool>(
+                self.config.sapling.is_some(),
+                account_notes.as_ref(),
+                &viewing_keys,
+                &self.config,
+                self.sapling_snapshot.as_ref(),
+                self.sapling_gap_tree.as_deref(),
+                self.gap_tree_mode,
+                remote_source,
+                &self.progress,
+            ),
+            process_pool_claims::<OrchardPool>(
+                self.config.orchard.is_some(),
+                account_notes.as_ref(),
+                &viewing_keys,
+                &self.config,
+                self.orchard_snapshot.as_ref(),
+                self.orchard_gap_tree.as_deref(),
+                self.gap_tree_mode,
+                remote_source,
+                &self.progress,
+            ),
+        )?;
+
+        Ok(AirdropClaimInputs {
+            sapling_claim_input: sapling_result.claims,
+            orchard_claim_input: orchard_result.claims,
+        })
+    }
+
+    /// Prove every claim of `inputs`; every proof is self-verified before it is returned.
+    ///
+    /// # Errors
+    /// Returns an error if no spending keys were given, the claims were built for other keys,
+    /// a pool's params are missing, or proving fails.
+    #[cfg(feature = "prove")]
+    pub async fn prove(
+        &self,
+        inputs: AirdropClaimInputs,
+    ) -> eyre::Result<(ClaimProofsOutput, ClaimSecretsOutput)> {
+        prove_claim_inputs(
+            inputs,
+            &self.config,
+            self.keys()?,
+            self.sapling_params.clone(),
+            self.orchard_params.as_deref(),
+            &self.progress,
+        )
+        .await
+    }
+
+    /// Sign proofs into a submission.
+    ///
+    /// # Errors
+    /// Returns an error if no spending keys were given or signing fails, see
+    /// [`super::sign_claim_proofs`].
+    pub fn sign(
+        &self,
+        proofs: &ClaimProofsOutput,
+        secrets: ClaimSecretsOutput,
+        message_hashes: &ResolvedMessageHashes,
+    ) -> eyre::Result<ClaimSubmission> {
+        sign_claim_proofs(
+            proofs,
+            secrets,
+            self.keys()?,
+            &self.config,
+            message_hashes,
+            &mut rand_core::OsRng,
+        )
+    }
+
+    fn keys(&self) -> eyre::Result<&ClaimKeys> {
+        self.keys
+            .as_ref()
+            .context("Claim session requires spending keys to prove and sign")
+    }
+
+    fn remote_source(&self) -> Option<&str> {
+        match self.gap_tree_mode {
+            GapTreeMode::Remote => self.witness_server_url.as_deref(),
+            GapTreeMode::Sharded => self.gap_tree_shards.as_deref(),
+            GapTreeMode::None | GapTreeMode::Rebuild | GapTreeMode::Sparse => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zair_core::schema::config::{
+        AirdropNetwork, MessagePolicy, OrchardGapTreeHash, OrchardSnapshot, ValueCommitmentScheme,
+    };
+
+    use super::*;
+
+    fn orchard_config() -> AirdropConfiguration {
+        AirdropConfiguration {
+            network: AirdropNetwork::Testnet,
+            snapshot_height: 1,
+            sapling: None,
+            orchard: Some(OrchardSnapshot {
+                note_commitment_root: [0_u8; 32],
+                nullifier_gap_root: [0_u8; 32],
+                target_id: "ZAIRTEST:O".to_owned(),
+                value_commitment_scheme: ValueCommitmentScheme::Native,
+                nullifier_gap_tree_hash: OrchardGapTreeHash::default(),
+            }),
+            transparent: None,
+            message_policy: MessagePolicy::default(),
+            claim_window: None,
+            targets: Vec::new(),
+        }
+    }
+
+    fn keys() -> ClaimKeys {
+        ClaimKeys::from_seed(AirdropNetwork::Testnet, &[7_u8; 64], 0).expect("derive keys")
+    }
+
+    #[test]
+    fn build_requires_config() {
+        let err = ClaimSession::builder()
+            .build()
+            .err()
+            .expect("session without config must fail");
+        assert!(err.to_string().contains("airdrop configuration"));
+    }
+
+    #[tokio::test]
+    async fn none_mode_requires_gap_tree_file() {
+        let session = ClaimSession::builder()
+            .config(orchard_config())
+            .snapshot(Pool::Orchard, SanitiseNullifiers::new(Vec::new()))
+            .gap_tree_mode(GapTreeMode::None)
+            .keys(keys())
+            .birthday_height(1)
+            .build()
+            .expect("build session");
+        let err = session
+            .prepare()
+            .await
+            .expect_err("none mode must require a gap-tree file");
+        assert!(err.to_string().contains("gap-tree"));
+    }
+
+    #[tokio::test]
+    async fn prepare_requires_a_key() {
+        let session = ClaimSession::builder()
+            .config(orchard_config())
+            .snapshot(Pool::Orchard, SanitiseNullifiers::new(Vec::new()))
+            .birthday_height(1)
+            .build()
+            .expect("build session");
+        let err = session
+            .prepare()
+            .await
+            .expect_err("prepare without keys must fail");
+        assert!(err.to_string().contains("viewing key or spending keys"));
+    }
+
+    #[test]
+    fn sign_requires_keys_and_proofs() {
+        let proofs = ClaimProofsOutput {
+            sapling_proofs: Vec::new(),
+            orchard_proofs: Vec::new(),
+        };
+        let secrets = || ClaimSecretsOutput {
+            sapling: Vec::new(),
+            orchard: Vec::new(),
+        };
+        let messages = ResolvedMessageHashes::default();
+
+        let without_keys = ClaimSession::builder()
+            .config(orchard_config())
+            .build()
+            .expect("build session");
+        let err = without_keys
+            .sign(&proofs, secrets(), &messages)
+            .expect_err("signing without keys must fail");
+        assert!(err.to_string().contains("spending keys"));
+
+        let with_keys = ClaimSession::builder()
+            .config(orchard_config())
+            .keys(keys())
+            .build()
+            .expect("build session");
+        let err = with_keys
+            .sign(&proofs, secrets(), &messages)
+            .expect_err("signing without proofs must fail");
+        assert!(err.to_string().contains("No proofs found"));
+    }
+}
@@ -0,0 +1,197 @@
+//! In-memory claim submission signing.
+
+use std::collections::BTreeMap;
+
+use eyre::{ContextCompat as _, ensure};
+use rand_core::{CryptoRng, RngCore};
+use zair_core::base::Pool;
+use zair_core::schema::config::AirdropConfiguration;
+use zair_core::schema::submission::{ClaimSubmission, OrchardSignedClaim, SaplingSignedClaim};
+
+use super::claim_keys::ClaimKeys;
+use super::claim_proofs::{ClaimProofsOutput, ClaimSecretsOutput};
+use super::nullifier_uniqueness::ensure_unique_airdrop_nullifiers;
+use super::signature_digest::{hash_orchard_proof, hash_sapling_proof, signature_digest};
+use super::submission_auth::{orchard, sapling};
+use super::submission_messages::ResolvedMessageHashes;
+
+/// Sign claim proofs into a submission, drawing signature randomness from `rng`.
+///
+/// Every claim is signed with its re-randomized spend-authorizing key over a digest binding the
+/// pool's `target_id`, the proof hash and the claim's message hash.
+///
+/// # Errors
+/// Returns an error if proofs and secrets do not pair up, a pool is missing from
+/// `airdrop_config`, a claim has no message, or a proof's `rk` does not match `keys`.
+#[allow(
+    clippy::too_many_lines,
+    reason = "Pairs, validates and signs both pools in one pass"
+)]
+pub fn sign_claim_proofs<R: RngCore + CryptoRng>(
+    proofs: &ClaimProofsOutput,
+    secrets: ClaimSecretsOutput,
+    keys: &ClaimKeys,
+    airdrop_config: &AirdropConfiguration,
+    message_hashes: &ResolvedMessageHashes,
+    rng: &mut R,
+) -> eyre::Result<ClaimSubmission> {
+    ensure!(
+        !(proofs.sapling_proofs.is_empty() && proofs.orchard_proofs.is_empty()),
+        "No proofs found to sign"
+    );
+    ensure!(
+        !proofs.sapling_proofs.is_empty() || secrets.sapling.is_empty(),
+        "Sapling secrets provided without Sapling proofs"
+    );
+    ensure!(
+        !proofs.orchard_proofs.is_empty() || secrets.orchard.is_empty(),
+        "Orchard secrets provided without Orchard proofs"
+    );
+    ensure!(
+        proofs.sapling_proofs.len() == secrets.sapling.len(),
+        "Proof/secret count mismatch for Sapling entries"
+    );
+    ensure!(
+        proofs.orchard_proofs.len() == secrets.orchard.len(),
+        "Proof/secret count mismatch for Orchard entries"
+    );
+    ensure_unique_airdrop_nullifiers(
+        proofs
+            .sapling_proofs
+            .iter()
+            .map(|proof| proof.airdrop_nullifier),
+        "Sapling proof",
+    )?;
+    ensure_unique_airdrop_nullifiers(
+        proofs
+            .orchard_proofs
+            .iter()
+            .map(|proof| proof.airdrop_nullifier),
+        "Orchard proof",
+    )?;
+
+    let sapling_target_id = if proofs.sapling_proofs.is_empty() {
+        None
+    } else {
+        Some(
+            airdrop_config
+                .sapling
+                .as_ref()
+                .context("Sapling proofs provided, but airdrop configuration has no sapling pool")?
+                .target_id
+                .as_str(),
+        )
+    };
+    let orchard_target_id = if proofs.orchard_proofs.is_empty() {
+        None
+    } else {
+        Some(
+            airdrop_config
+                .orchard
+                .as_ref()
+                .context("Orchard proofs provided, but airdrop configuration has no orchard pool")?
+                .target_id
+                .as_str(),
+        )
+    };
+
+    let mut sapling_secret_by_nf = BTreeMap::new();
+    for secret in secrets.sapling {
+        let existing = sapling_secret_by_nf.insert(secret.airdrop_nullifier, secret);
+        ensure!(
+            existing.is_none(),
+            "Duplicate Sapling secret entry for airdrop nullifier"
+        );
+    }
+
+    let sapling_keys = sapling::derive_spend_auth_keys(keys);
+    let mut sapling = Vec::with_capacity(proofs.sapling_proofs.len());
+    for proof in &proofs.sapling_proofs {
+        let secret = sapling_secret_by_nf
+            .get(&proof.airdrop_nullifier)
+            .context("Missing secret material for Sapling proof entry")?;
+        let target_id =
+            sapling_target_id.context("Sapling target_id must be present for Sapling signing")?;
+        let message_hash = message_hashes
+            .sapling_hash(proof.airdrop_nullifier)
+            .with_context(|| {
+                format!(
+                    "No message provided for Sapling claim with airdrop nullifier {}. Provide --message or --messages entry",
+                    proof.airdrop_nullifier
+                )
+            })?;
+        let proof_hash = hash_sapling_proof(proof);
+        let digest = signature_digest(
+            Pool::Sapling,
+            target_id,
+            airdrop_config.claim_window.as_ref(),
+            &proof_hash,
+            &message_hash,
+        )?;
+
+        let spend_auth_sig = sapling::sign_claim(proof, secret, &sapling_keys, &digest, &mut *rng)?;
+        sapling.push(SaplingSignedClaim {
+            zkproof: proof.zkproof,
+            rk: proof.rk,
+            cv: proof.cv,
+            cv_sha256: proof.cv_sha256,
+            airdrop_nullifier: proof.airdrop_nullifier,
+            proof_hash,
+            message_hash,
+            spend_auth_sig,
+        });
+    }
+
+    let mut orchard_secret_by_nf = BTreeMap::new();
+    for secret in secrets.orchard {
+        let existing = orchard_secret_by_nf.insert(secret.airdrop_nullifier, secret);
+        ensure!(
+            existing.is_none(),
+            "Duplicate Orchard secret entry for airdrop nullifier"
+        );
+    }
+
+    let orchard_key = orchard::derive_spend_auth_key(keys);
+    let mut orchard = Vec::with_capacity(proofs.orchard_proofs.len());
+    for proof in &proofs.orchard_proofs {
+        let secret = orchard_secret_by_nf
+            .get(&proof.airdrop_nullifier)
+            .context("Missing secret material for Orchard proof entry")?;
+        let target_id =
+            orchard_target_id.context("Orchard target_id must be present for Orchard signing")?;
+        let message_hash = message_hashes
+            .orchard_hash(proof.airdrop_nullifier)
+            .with_context(|| {
+                format!(
+                    "No message provided for Orchard claim with airdrop nullifier {}. Provide --message or --messages entry",
+                    proof.airdrop_nullifier
+                )
+            })?;
+        let proof_hash = hash_orchard_proof(proof)?;
+        let digest = signature_digest(
+            Pool::Orchard,
+            target_id,
+            airdrop_config.claim_window.as_ref(),
+            &proof_hash,
+            &message_hash,
+        )?;
+
+        let spend_auth_sig = orchard::sign_claim(proof, secret, &orchard_key, &digest, &mut *rng)?;
+        orchard.push(OrchardSignedClaim {
+            zkproof: proof.zkproof.clone(),
+            rk: proof.rk,
+            cv: proof.cv,
+            cv_sha256: proof.cv_sha256,
+            airdrop_nullifier: proof.airdrop_nullifier,
+            proof_hash,
+            message_hash,
+            spend_auth_sig,
+        });
+    }
+
+    Ok(ClaimSubmission {
+        sapling,
+        orchard,
+        transparent: Vec::new(),
+    })
+}
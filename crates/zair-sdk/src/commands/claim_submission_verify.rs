@@ -2,42 +2,133 @@
 
 use std::path::PathBuf;
 
-use eyre::{Context as _, ContextCompat as _, ensure};
+use eyre::{Context as _, ensure};
 use tracing::{info, warn};
-use zair_core::base::Pool;
-use zair_core::schema::config::AirdropConfiguration;
+use zair_core::schema::config::{AirdropConfiguration, ChainClock};
 use zair_core::schema::submission::ClaimSubmission;
 
-use super::nullifier_uniqueness::ensure_unique_airdrop_nullifiers;
-use super::signature_digest::{
-    hash_orchard_signed_claim_proof, hash_sapling_signed_claim_proof, signature_digest,
+use super::airdrop_configuration::{chain_clock, read_airdrop_configuration};
+use super::claim_message::AddressValidators;
+use super::claim_verifier::{
+    OrchardClaimVerifier, SaplingClaimVerifier, SubmissionVerifier, TransparentClaimVerifier,
 };
-use super::submission_auth::{orchard, sapling};
-use super::submission_messages::resolve_message_hashes;
+use super::config_signature::TrustedOrganizer;
+use super::nullifier_uniqueness::ensure_unique_airdrop_nullifiers;
+use super::submission_messages::{ResolvedMessageHashes, resolve_message_hashes};
+use super::verification_report::{ClaimVerdict, VerificationReport};
 
-/// Verify spend-auth signatures in a submission package.
+/// Verify spend-auth signatures in a submission package and report a verdict per claim.
+///
+/// Every claim is checked for `proof_hash` consistency, its message hash and its signature;
+/// transparent claims are also checked for inclusion in the balance tree. A failing claim does
+/// not stop verification of the others. With `trusted_organizer`, a configuration the
+/// organizer did not sign is refused.
 ///
 /// # Errors
-/// Returns an error if parsing fails, digest mismatches are found, config-binding checks fail,
-/// or any signature is invalid.
+/// Returns an error if parsing fails or config-binding checks fail,
+/// [`ClaimWindowError`](zair_core::schema::config::ClaimWindowError) if `current_height` or
+/// `current_time` is outside the airdrop's claim window, or
+/// [`VerificationFailed`](super::VerificationFailed) with the per-claim report if any claim fails a
+/// check.
 #[allow(
-    clippy::too_many_lines,
+    clippy::too_many_arguments,
     clippy::similar_names,
-    reason = "Verification entrypoint intentionally keeps all pool/message checks in one flow"
+    reason = "CLI entrypoint parameters"
 )]
 pub async fn verify_claim_submission_signature(
     submission_file: PathBuf,
     message_file: Option<PathBuf>,
     messages_file: Option<PathBuf>,
     airdrop_configuration_file: PathBuf,
-) -> eyre::Result<()> {
+    target: Option<String>,
+    trusted_organizer: Option<TrustedOrganizer>,
+    current_height: Option<u64>,
+    current_time: Option<u64>,
+) -> eyre::Result<VerificationReport> {
+    let LoadedSubmission {
+        submission,
+        airdrop_config,
+        messages,
+    } = load_submission(
+        submission_file,
+        message_file,
+        messages_file,
+        airdrop_configuration_file,
+        target,
+        trusted_organizer,
+        chain_clock(current_height, current_time),
+    )
+    .await?;
+
+    let verifier = SubmissionVerifier::new(
+        airdrop_config
+            .sapling
+            .as_ref()
+            .map(SaplingClaimVerifier::signatures_only),
+        airdrop_config
+            .orchard
+            .as_ref()
+            .map(OrchardClaimVerifier::signatures_only),
+        airdrop_config
+            .transparent
+            .as_ref()
+            .map(TransparentClaimVerifier::new),
+    )
+    .with_claim_window(airdrop_config.claim_window);
+    let report =
+        verifier.check_submission(&submission, &messages, |_, _, _| Ok::<_, eyre::Report>(()))?;
+    report
+        .claims
+        .iter()
+        .for_each(|verdict| log_verdict(verdict, "Signature"));
+    let report = report.into_result("submission signatures")?;
+
+    info!(
+        sapling_count = submission.sapling.len(),
+        orchard_count = submission.orchard.len(),
+        transparent_count = submission.transparent.len(),
+        "All submission signatures are VALID"
+    );
+    Ok(report)
+}
+
+/// A signed submission with the configuration and message hashes it is verified against.
+pub(super) struct LoadedSubmission {
+    /// The signed submission.
+    pub(super) submission: ClaimSubmission,
+    /// The configuration, resolved to the airdrop target the claims were made for.
+    pub(super) airdrop_config: AirdropConfiguration,
+    /// Message hashes of the claims.
+    pub(super) messages: ResolvedMessageHashes,
+}
+
+/// Load a submission for verification, with its configuration and message hashes.
+///
+/// # Errors
+/// Returns an error if parsing fails, the submission is empty, airdrop nullifiers repeat, a
+/// pool is not configured or `clock` is outside the claim window.
+#[allow(
+    clippy::similar_names,
+    reason = "Message and messages files are both CLI inputs"
+)]
+pub(super) async fn load_submission(
+    submission_file: PathBuf,
+    message_file: Option<PathBuf>,
+    messages_file: Option<PathBuf>,
+    airdrop_configuration_file: PathBuf,
+    target: Option<String>,
+    trusted_organizer: Option<TrustedOrganizer>,
+    clock: ChainClock,
+) -> eyre::Result<LoadedSubmission> {
     info!(file = ?submission_file, "Loading signed submission...");
     let submission: ClaimSubmission =
         serde_json::from_str(&tokio::fs::read_to_string(&submission_file).await?)
             .context("Failed to parse submission JSON")?;
 
     ensure!(
-        !(submission.sapling.is_empty() && submission.orchard.is_empty()),
+        !(submission.sapling.is_empty() &&
+            submission.orchard.is_empty() &&
+            submission.transparent.is_empty()),
         "Submission contains no signed claims"
     );
     ensure_unique_airdrop_nullifiers(
@@ -54,151 +145,76 @@ pub async fn verify_claim_submission_signature(
             .map(|entry| entry.airdrop_nullifier),
         "Orchard signed claim",
     )?;
+    ensure_unique_airdrop_nullifiers(
+        submission
+            .transparent
+            .iter()
+            .map(|entry| entry.airdrop_nullifier),
+        "Transparent signed claim",
+    )?;
 
-    let airdrop_config: AirdropConfiguration =
-        serde_json::from_str(&tokio::fs::read_to_string(&airdrop_configuration_file).await?)
-            .context("Failed to parse airdrop configuration JSON")?;
-    let sapling_target_id = if submission.sapling.is_empty() {
-        None
-    } else {
-        Some(
-            airdrop_config
-                .sapling
-                .as_ref()
-                .context(
-                    "Sapling signed claims provided, but airdrop configuration has no sapling pool",
-                )?
-                .target_id
-                .clone(),
-        )
-    };
-    let orchard_target_id = if submission.orchard.is_empty() {
-        None
-    } else {
-        Some(
-            airdrop_config
-                .orchard
-                .as_ref()
-                .context(
-                    "Orchard signed claims provided, but airdrop configuration has no orchard pool",
-                )?
-                .target_id
-                .clone(),
-        )
-    };
-
-    let message_hashes =
-        resolve_message_hashes(message_file.as_ref(), messages_file.as_ref()).await?;
-
-    let mut invalid_count = 0_usize;
-
-    for (idx, entry) in submission.sapling.iter().enumerate() {
-        let expected_proof_hash = hash_sapling_signed_claim_proof(entry);
-        ensure!(
-            expected_proof_hash == entry.proof_hash,
-            "Sapling proof hash mismatch at index {idx}"
-        );
-
-        let expected_message_hash = message_hashes
-            .sapling_hash(entry.airdrop_nullifier)
-            .with_context(|| {
-                format!(
-                    "No message provided for Sapling claim with airdrop nullifier {}. Provide --message or --messages entry",
-                    entry.airdrop_nullifier
-                )
-            })?;
-        ensure!(
-            expected_message_hash == entry.message_hash,
-            "Sapling message hash mismatch at index {idx}"
-        );
-
-        let target_id = sapling_target_id
-            .as_deref()
-            .context("Sapling target_id must be present for Sapling signature verification")?;
-        let digest = signature_digest(
-            Pool::Sapling,
-            target_id,
-            &entry.proof_hash,
-            &entry.message_hash,
-        )?;
-
-        let is_valid = sapling::verify_signature(entry.rk, entry.spend_auth_sig, &digest)
-            .with_context(|| format!("Invalid Sapling signature encoding at index {idx}"))?;
-        if is_valid {
-            info!(
-                index = idx,
-                airdrop_nullifier = %entry.airdrop_nullifier,
-                "Sapling signature VALID"
-            );
-        } else {
-            invalid_count = invalid_count.saturating_add(1);
-            warn!(
-                index = idx,
-                airdrop_nullifier = %entry.airdrop_nullifier,
-                "Sapling signature INVALID"
-            );
-        }
+    let airdrop_config = read_airdrop_configuration(
+        &airdrop_configuration_file,
+        target.as_deref(),
+        trusted_organizer.as_ref(),
+    )
+    .await?;
+    if let Some(claim_window) = &airdrop_config.claim_window {
+        claim_window
+            .check(&clock)
+            .context("Submission is outside the airdrop claim window")?;
     }
+    ensure!(
+        submission.sapling.is_empty() || airdrop_config.sapling.is_some(),
+        "Sapling signed claims provided, but airdrop configuration has no sapling pool"
+    );
+    ensure!(
+        submission.orchard.is_empty() || airdrop_config.orchard.is_some(),
+        "Orchard signed claims provided, but airdrop configuration has no orchard pool"
+    );
+    ensure!(
+        submission.transparent.is_empty() || airdrop_config.transparent.is_some(),
+        "Transparent signed claims provided, but airdrop configuration has no transparent pool"
+    );
 
-    for (idx, entry) in submission.orchard.iter().enumerate() {
-        let expected_proof_hash = hash_orchard_signed_claim_proof(entry)?;
-        ensure!(
-            expected_proof_hash == entry.proof_hash,
-            "Orchard proof hash mismatch at index {idx}"
-        );
+    let messages = resolve_message_hashes(
+        message_file.as_ref(),
+        messages_file.as_ref(),
+        &airdrop_config.message_policy,
+        &AddressValidators::default(),
+    )
+    .await?;
+
+    Ok(LoadedSubmission {
+        submission,
+        airdrop_config,
+        messages,
+    })
+}
 
-        let expected_message_hash = message_hashes
-            .orchard_hash(entry.airdrop_nullifier)
-            .with_context(|| {
-                format!(
-                    "No message provided for Orchard claim with airdrop nullifier {}. Provide --message or --messages entry",
-                    entry.airdrop_nullifier
-                )
-            })?;
-        ensure!(
-            expected_message_hash == entry.message_hash,
-            "Orchard message hash mismatch at index {idx}"
+/// Log the verdict of one claim; `checks` names what was checked, e.g. `Signature`.
+pub(super) fn log_verdict(verdict: &ClaimVerdict, checks: &str) {
+    if verdict.is_valid() {
+        info!(
+            pool = %verdict.pool,
+            index = verdict.index,
+            airdrop_nullifier = %verdict.airdrop_nullifier,
+            "{checks} VALID"
+        );
+    } else {
+        let reasons = verdict
+            .failures()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ");
+        warn!(
+            pool = %verdict.pool,
+            index = verdict.index,
+            airdrop_nullifier = %verdict.airdrop_nullifier,
+            %reasons,
+            "{checks} INVALID"
         );
-
-        let target_id = orchard_target_id
-            .as_deref()
-            .context("Orchard target_id must be present for Orchard signature verification")?;
-        let digest = signature_digest(
-            Pool::Orchard,
-            target_id,
-            &entry.proof_hash,
-            &entry.message_hash,
-        )?;
-
-        let is_valid = orchard::verify_signature(entry.rk, entry.spend_auth_sig, &digest)
-            .with_context(|| format!("Invalid Orchard signature encoding at index {idx}"))?;
-        if is_valid {
-            info!(
-                index = idx,
-                airdrop_nullifier = %entry.airdrop_nullifier,
-                "Orchard signature VALID"
-            );
-        } else {
-            invalid_count = invalid_count.saturating_add(1);
-            warn!(
-                index = idx,
-                airdrop_nullifier = %entry.airdrop_nullifier,
-                "Orchard signature INVALID"
-            );
-        }
     }
-
-    ensure!(
-        invalid_count == 0,
-        "{invalid_count} submission signatures failed verification"
-    );
-
-    info!(
-        sapling_count = submission.sapling.len(),
-        orchard_count = submission.orchard.len(),
-        "All submission signatures are VALID"
-    );
-    Ok(())
 }
 
 #[cfg(test)]
@@ -209,12 +225,15 @@ mod tests {
     use tempfile::tempdir;
     use zair_core::base::Nullifier;
     use zair_core::schema::config::{
-        AirdropConfiguration, AirdropNetwork, SaplingSnapshot, ValueCommitmentScheme,
+        AirdropConfiguration, AirdropNetwork, ClaimWindow, ClaimWindowError, ClaimWindowUnit,
+        MessagePolicy, SaplingSnapshot, ValueCommitmentScheme,
     };
     use zair_core::schema::submission::{ClaimSubmission, OrchardSignedClaim, SaplingSignedClaim};
 
     use super::*;
+    use crate::commands::VerificationFailed;
     use crate::commands::signature_digest::{hash_message, hash_sapling_signed_claim_proof};
+    use crate::commands::verification_report::{CheckOutcome, FailureReason};
 
     fn write_json<T: Serialize>(path: &Path, value: &T) {
         let bytes = serde_json::to_vec_pretty(value).expect("serialize json");
@@ -232,6 +251,10 @@ mod tests {
                 value_commitment_scheme: ValueCommitmentScheme::Native,
             }),
             orchard: None,
+            transparent: None,
+            message_policy: MessagePolicy::default(),
+            claim_window: None,
+            targets: Vec::new(),
         }
     }
 
@@ -250,8 +273,70 @@ mod tests {
         claim
     }
 
+    fn failed_report(err: &eyre::Report) -> &VerificationReport {
+        &err.downcast_ref::<VerificationFailed>()
+            .expect("verification failure carries a report")
+            .report
+    }
+
+    #[tokio::test]
+    async fn verify_rejects_submissions_outside_the_claim_window() {
+        let dir = tempdir().expect("tempdir");
+        let submission_path = dir.path().join("submission.json");
+        let config_path = dir.path().join("config.json");
+
+        write_json(
+            &submission_path,
+            &ClaimSubmission {
+                sapling: vec![sample_sapling_claim()],
+                orchard: vec![],
+                transparent: vec![],
+            },
+        );
+        write_json(
+            &config_path,
+            &AirdropConfiguration {
+                claim_window: Some(ClaimWindow {
+                    unit: ClaimWindowUnit::Height,
+                    start: Some(100),
+                    end: Some(200),
+                }),
+                ..sapling_config()
+            },
+        );
+
+        for (current_height, expected) in [
+            (
+                Some(201),
+                ClaimWindowError::Closed {
+                    unit: ClaimWindowUnit::Height,
+                    end: 200,
+                    current: 201,
+                },
+            ),
+            (
+                None,
+                ClaimWindowError::UnknownPosition(ClaimWindowUnit::Height),
+            ),
+        ] {
+            let err = verify_claim_submission_signature(
+                submission_path.clone(),
+                None,
+                None,
+                config_path.clone(),
+                None,
+                None,
+                current_height,
+                None,
+            )
+            .await
+            .expect_err("submission outside the window must be rejected");
+            assert_eq!(err.downcast_ref::<ClaimWindowError>(), Some(&expected));
+        }
+    }
+
     #[tokio::test]
-    async fn verify_rejects_missing_message_for_sapling_claim() {
+    async fn verify_reports_missing_message_for_sapling_claim() {
         let dir = tempdir().expect("tempdir");
         let submission_path = dir.path().join("submission.json");
         let config_path = dir.path().join("config.json");
@@ -259,36 +344,56 @@ mod tests {
         let submission = ClaimSubmission {
             sapling: vec![sample_sapling_claim()],
             orchard: vec![],
+            transparent: vec![],
         };
         write_json(&submission_path, &submission);
         write_json(&config_path, &sapling_config());
 
-        let err = verify_claim_submission_signature(submission_path, None, None, config_path)
-            .await
-            .expect_err("verification must fail without a message");
+        let err = verify_claim_submission_signature(
+            submission_path,
+            None,
+            None,
+            config_path,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect_err("verification must fail without a message");
 
+        let verdict = failed_report(&err).claims.first().expect("sapling verdict");
+        assert_eq!(
+            verdict.message_hash.as_ref().and_then(CheckOutcome::reason),
+            Some(&FailureReason::MissingMessage)
+        );
         assert!(
-            err.to_string()
-                .contains("No message provided for Sapling claim"),
-            "{err:?}"
+            verdict
+                .proof_hash
+                .as_ref()
+                .is_some_and(CheckOutcome::is_valid)
         );
     }
 
     #[tokio::test]
-    async fn verify_rejects_sapling_proof_hash_mismatch_before_signature_check() {
+    async fn verify_checks_every_claim_after_a_proof_hash_mismatch() {
         let dir = tempdir().expect("tempdir");
         let submission_path = dir.path().join("submission.json");
         let config_path = dir.path().join("config.json");
         let message_path = dir.path().join("message.bin");
         std::fs::write(&message_path, b"test-message").expect("write message file");
 
-        let mut claim = sample_sapling_claim();
-        claim.proof_hash = [99_u8; 32];
-        claim.message_hash = hash_message(b"test-message");
+        let mut bad_hash = sample_sapling_claim();
+        bad_hash.proof_hash = [99_u8; 32];
+        bad_hash.message_hash = hash_message(b"test-message");
+        let mut other = sample_sapling_claim();
+        other.airdrop_nullifier = Nullifier::from([45_u8; 32]);
+        other.proof_hash = hash_sapling_signed_claim_proof(&other);
 
         let submission = ClaimSubmission {
-            sapling: vec![claim],
+            sapling: vec![bad_hash, other],
             orchard: vec![],
+            transparent: vec![],
         };
         write_json(&submission_path, &submission);
         write_json(&config_path, &sapling_config());
@@ -298,14 +403,43 @@ mod tests {
             Some(message_path),
             None,
             config_path,
+            None,
+            None,
+            None,
+            None,
         )
         .await
         .expect_err("verification must fail for proof hash mismatch");
 
+        let report = failed_report(&err);
+        assert_eq!(report.claims.len(), 2);
+        let [bad_hash, other] = [0, 1].map(|index| {
+            report
+                .claims
+                .iter()
+                .find(|verdict| verdict.index == index)
+                .expect("verdict per claim")
+        });
+        assert_eq!(
+            bad_hash.proof_hash.as_ref().and_then(CheckOutcome::reason),
+            Some(&FailureReason::ProofHashMismatch)
+        );
         assert!(
-            err.to_string()
-                .contains("Sapling proof hash mismatch at index 0"),
-            "{err:?}"
+            bad_hash
+                .message_hash
+                .as_ref()
+                .is_some_and(CheckOutcome::is_valid)
+        );
+        assert!(bad_hash.signature.is_some());
+        assert!(
+            other
+                .proof_hash
+                .as_ref()
+                .is_some_and(CheckOutcome::is_valid)
+        );
+        assert_eq!(
+            other.message_hash.as_ref().and_then(CheckOutcome::reason),
+            Some(&FailureReason::MessageHashMismatch)
         );
     }
 
@@ -327,13 +461,23 @@ mod tests {
                 message_hash: [7_u8; 32],
                 spend_auth_sig: [8_u8; 64],
             }],
+            transparent: vec![],
         };
         write_json(&submission_path, &submission);
         write_json(&config_path, &sapling_config());
 
-        let err = verify_claim_submission_signature(submission_path, None, None, config_path)
-            .await
-            .expect_err("verification must fail when orchard config is missing");
+        let err = verify_claim_submission_signature(
+            submission_path,
+            None,
+            None,
+            config_path,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect_err("verification must fail when orchard config is missing");
 
         assert!(
             err.to_string().contains(
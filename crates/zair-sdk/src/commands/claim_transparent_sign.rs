@@ -0,0 +1,92 @@
+//! Transparent claim signing command implementation.
+
+use std::path::PathBuf;
+
+use eyre::ensure;
+use secrecy::ExposeSecret;
+use tracing::info;
+use zair_core::schema::submission::ClaimSubmission;
+
+use super::airdrop_configuration::read_airdrop_configuration;
+use super::claim_keys::ClaimKeys;
+use super::claim_message::AddressValidators;
+use super::config_signature::TrustedOrganizer;
+use super::submission_messages::resolve_message_hashes;
+use super::transparent_balances::read_transparent_balances;
+use super::transparent_signer::sign_transparent_claims;
+use crate::seed::read_seed_file;
+
+/// Sign a claim for every funded transparent address of an account into a submission package.
+///
+/// This is a file-based wrapper around [`sign_transparent_claims`]; `balances_file` must be the
+/// listing the configuration's balance root was built from. With `target`, the claims are
+/// signed for that airdrop target of the configuration. With `trusted_organizer`, a
+/// configuration the organizer did not sign is refused.
+///
+/// # Errors
+/// Returns an error if inputs are invalid, the listing does not match the configuration, no
+/// address of the account is funded, or signing fails.
+#[allow(
+    clippy::too_many_arguments,
+    clippy::similar_names,
+    reason = "CLI entrypoint parameters"
+)]
+pub async fn sign_transparent_claim_submission(
+    airdrop_configuration_file: PathBuf,
+    target: Option<String>,
+    trusted_organizer: Option<TrustedOrganizer>,
+    balances_file: PathBuf,
+    seed_file: PathBuf,
+    account_id: u32,
+    gap_limit: u32,
+    message_file: Option<PathBuf>,
+    messages_file: Option<PathBuf>,
+    submission_output_file: PathBuf,
+) -> eyre::Result<ClaimSubmission> {
+    let airdrop_config = read_airdrop_configuration(
+        &airdrop_configuration_file,
+        target.as_deref(),
+        trusted_organizer.as_ref(),
+    )
+    .await?;
+    let balances = read_transparent_balances(airdrop_config.network, &balances_file).await?;
+
+    info!(file = ?seed_file, "Reading seed from file...");
+    let seed = read_seed_file(&seed_file).await?;
+    let keys = ClaimKeys::from_seed(airdrop_config.network, seed.expose_secret(), account_id)?;
+
+    let message_hashes = resolve_message_hashes(
+        message_file.as_ref(),
+        messages_file.as_ref(),
+        &airdrop_config.message_policy,
+        &AddressValidators::default(),
+    )
+    .await?;
+
+    let transparent = sign_transparent_claims(
+        &balances,
+        &keys,
+        &airdrop_config,
+        &message_hashes,
+        gap_limit,
+    )?;
+    ensure!(
+        !transparent.is_empty(),
+        "No transparent address of account {account_id} has a snapshot balance (gap limit {gap_limit})"
+    );
+
+    let submission = ClaimSubmission {
+        sapling: Vec::new(),
+        orchard: Vec::new(),
+        transparent,
+    };
+    let json = serde_json::to_string_pretty(&submission)?;
+    tokio::fs::write(&submission_output_file, json).await?;
+    info!(
+        file = ?submission_output_file,
+        transparent_count = submission.transparent.len(),
+        "Signed transparent claim submission written"
+    );
+
+    Ok(submission)
+}
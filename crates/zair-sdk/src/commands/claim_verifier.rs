@@ -0,0 +1,820 @@
+//! In-memory verification of signed claim submissions.
+//!
+//! Every claim of a submission is checked for its `proof_hash`, message hash, spend-auth
+//! signature and proof, and gets its own [`ClaimVerdict`] with a typed [`FailureReason`] per
+//! failed check. The `verify` commands, the verification service and the wasm bindings all
+//! check claims here. Transparent claims prove inclusion in the balance tree instead of
+//! carrying a zero-knowledge proof. Messages sent as bytes are checked
+//! like the CLI checks message files: they must fit the airdrop's message policy, and a
+//! structured claim message must name a valid recipient.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use bellman::groth16::{PreparedVerifyingKey, VerifyingKey};
+use bls12_381::Bls12;
+use halo2_proofs::poly::commitment::Params;
+use pasta_curves::vesta;
+use zair_core::base::{Nullifier, Pool};
+use zair_core::schema::config::{
+    ChainClock, ClaimWindow, ClaimWindowError, MessagePolicy, OrchardSnapshot, SaplingSnapshot,
+    TransparentSnapshot,
+};
+use zair_core::schema::submission::{
+    ClaimSubmission, OrchardSignedClaim, SaplingSignedClaim, TransparentSignedClaim,
+};
+use zair_core::schema::verification::{ClaimMessage, ClaimMessageEntry, VerifyRequest};
+use zair_core::transparent::{TransparentBalance, airdrop_nullifier, root_from_path};
+use zair_orchard_proofs::{
+    ClaimProofOutput as OrchardClaimProofOutput, GapTreeHash,
+    ValueCommitmentScheme as OrchardValueCommitmentScheme,
+    verify_claim_proof_output as verify_orchard_claim_proof_output,
+};
+use zair_sapling_proofs::verifier::{
+    ValueCommitmentScheme as SaplingValueCommitmentScheme, verify_claim_proof_bytes,
+};
+
+use super::claim_message::{AddressValidators, InvalidClaimMessage};
+use super::claim_proofs::{OrchardClaimProofResult, SaplingClaimProofResult};
+use super::nullifier_uniqueness::ensure_unique_airdrop_nullifiers;
+use super::signature_digest::{
+    hash_message, hash_orchard_signed_claim_proof, hash_sapling_signed_claim_proof,
+    hash_transparent_signed_claim_proof, signature_digest,
+};
+use super::submission_auth::{orchard, sapling, transparent};
+use super::submission_messages::ResolvedMessageHashes;
+use super::verification_report::{
+    CheckOutcome, ClaimVerdict, FailureReason, VerificationReport, VerifyResponse,
+};
+
+/// Requests rejected as a whole, before any claim is verified.
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyRequestError {
+    /// The submission holds no claims.
+    #[error("submission contains no signed claims")]
+    EmptySubmission,
+    /// The submission has claims for a pool the airdrop does not enable.
+    #[error("{0} claims provided, but the airdrop configuration has no {0} pool")]
+    PoolNotConfigured(Pool),
+    /// The submission or its messages are malformed.
+    #[error("{0}")]
+    InvalidSubmission(String),
+    /// The airdrop's claim window is not open.
+    #[error(transparent)]
+    OutsideClaimWindow(#[from] ClaimWindowError),
+}
+
+/// Sapling verification context bound to the airdrop configuration.
+pub struct SaplingClaimVerifier {
+    pvk: Option<PreparedVerifyingKey<Bls12>>,
+    scheme: SaplingValueCommitmentScheme,
+    note_commitment_root: [u8; 32],
+    nullifier_gap_root: [u8; 32],
+    target_id: String,
+    claim_window: Option<ClaimWindow>,
+}
+
+/// Orchard verification context bound to the airdrop configuration.
+pub struct OrchardClaimVerifier {
+    params: OrchardProofParams,
+    scheme: OrchardValueCommitmentScheme,
+    gap_tree_hash: GapTreeHash,
+    note_commitment_root: [u8; 32],
+    nullifier_gap_root: [u8; 32],
+    target_id: String,
+    claim_window: Option<ClaimWindow>,
+}
+
+/// Halo2 params an Orchard verifier checks proofs with.
+enum OrchardProofParams {
+    /// Proofs are not checked.
+    Unchecked,
+    /// Proofs are checked, but no params were loaded.
+    #[cfg_attr(
+        not(feature = "native"),
+        allow(
+            dead_code,
+            reason = "Only the native proof commands check proofs without params"
+        )
+    )]
+    Missing,
+    /// Proofs are checked with these params.
+    Loaded(Arc<Params<vesta::Affine>>),
+}
+
+/// Transparent verification context bound to the airdrop configuration.
+pub struct TransparentClaimVerifier {
+    snapshot: TransparentSnapshot,
+    claim_window: Option<ClaimWindow>,
+}
+
+/// Why a transparent claim is not backed by the balance snapshot.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum TransparentInclusionError {
+    /// The public key or Merkle path cannot be decoded.
+    #[error("{0}")]
+    Malformed(String),
+    /// The airdrop nullifier is not derived from the claimed address.
+    #[error("airdrop nullifier does not match the claim's address")]
+    NullifierMismatch,
+    /// The address and balance are not a leaf of the balance tree.
+    #[error("address balance is not in the transparent snapshot")]
+    NotInSnapshot,
+}
+
+impl From<TransparentInclusionError> for FailureReason {
+    fn from(error: TransparentInclusionError) -> Self {
+        match error {
+            TransparentInclusionError::Malformed(detail) => Self::Malformed { detail },
+            TransparentInclusionError::NullifierMismatch => Self::NullifierMismatch,
+            TransparentInclusionError::NotInSnapshot => Self::NotInSnapshot,
+        }
+    }
+}
+
+/// Verifies signed submissions against the pools of one airdrop configuration.
+pub struct SubmissionVerifier {
+    sapling: Option<SaplingClaimVerifier>,
+    orchard: Option<OrchardClaimVerifier>,
+    transparent: Option<TransparentClaimVerifier>,
+    claim_window: Option<ClaimWindow>,
+    message_policy: MessagePolicy,
+    validators: AddressValidators,
+}
+
+impl SaplingClaimVerifier {
+    /// Bind the Sapling verifying key to the configured Sapling pool.
+    #[must_use]
+    pub fn new(config: &SaplingSnapshot, vk: &VerifyingKey<Bls12>) -> Self {
+        Self {
+            pvk: Some(bellman::groth16::prepare_verifying_key(vk)),
+            ..Self::signatures_only(config)
+        }
+    }
+
+    /// Check everything but the proofs of the configured Sapling pool's claims.
+    #[must_use]
+    pub fn signatures_only(config: &SaplingSnapshot) -> Self {
+        Self {
+            pvk: None,
+            scheme: config.value_commitment_scheme.into(),
+            note_commitment_root: config.note_commitment_root,
+            nullifier_gap_root: config.nullifier_gap_root,
+            target_id: config.target_id.clone(),
+            claim_window: None,
+        }
+    }
+
+    /// Bind the airdrop's claim window, which claims are signed over.
+    #[must_use]
+    pub const fn with_claim_window(mut self, claim_window: Option<ClaimWindow>) -> Self {
+        self.claim_window = claim_window;
+        self
+    }
+
+    /// Check one signed claim against the hash of the message it must be signed over.
+    ///
+    /// The proof is only checked if the verifier has a verifying key.
+    #[must_use]
+    pub fn check(
+        &self,
+        index: usize,
+        claim: &SaplingSignedClaim,
+        message_hash: Option<[u8; 32]>,
+    ) -> ClaimVerdict {
+        let digest = signature_digest(
+            Pool::Sapling,
+            &self.target_id,
+            self.claim_window.as_ref(),
+            &claim.proof_hash,
+            &claim.message_hash,
+        );
+        ClaimVerdict {
+            proof_hash: Some(proof_hash_outcome(
+                Ok(hash_sapling_signed_claim_proof(claim)),
+                &claim.proof_hash,
+            )),
+            message_hash: Some(message_hash_outcome(message_hash, &claim.message_hash)),
+            signature: Some(signature_outcome(digest.and_then(|digest| {
+                sapling::verify_signature(claim.rk, claim.spend_auth_sig, &digest)
+            }))),
+            proof: self.check_proof(&claim.into()),
+            ..ClaimVerdict::new(Pool::Sapling, index, claim.airdrop_nullifier)
+        }
+    }
+
+    /// Check one claim proof, or `None` if the verifier has no verifying key.
+    #[must_use]
+    pub fn check_proof(&self, proof: &SaplingClaimProofResult) -> Option<CheckOutcome> {
+        let pvk = self.pvk.as_ref()?;
+        let result = verify_claim_proof_bytes(
+            pvk,
+            &proof.zkproof,
+            self.scheme,
+            &proof.rk,
+            proof.cv.as_ref(),
+            proof.cv_sha256.as_ref(),
+            &self.note_commitment_root,
+            &proof.airdrop_nullifier.into(),
+            &self.nullifier_gap_root,
+        )
+        .map_err(|error| FailureReason::InvalidProof {
+            detail: error.to_string(),
+        });
+        Some(result.into())
+    }
+}
+
+impl OrchardClaimVerifier {
+    /// Bind Orchard Halo2 params to the configured Orchard pool.
+    #[must_use]
+    pub fn new(config: &OrchardSnapshot, params: Arc<Params<vesta::Affine>>) -> Self {
+        Self {
+            params: OrchardProofParams::Loaded(params),
+            ..Self::signatures_only(config)
+        }
+    }
+
+    /// Check everything but the proofs of the configured Orchard pool's claims.
+    #[must_use]
+    pub fn signatures_only(config: &OrchardSnapshot) -> Self {
+        Self {
+            params: OrchardProofParams::Unchecked,
+            scheme: config.value_commitment_scheme.into(),
+            gap_tree_hash: config.nullifier_gap_tree_hash.into(),
+            note_commitment_root: config.note_commitment_root,
+            nullifier_gap_root: config.nullifier_gap_root,
+            target_id: config.target_id.clone(),
+            claim_window: None,
+        }
+    }
+
+    /// Check proofs without Halo2 params, for claims whose commitment fields are known not to
+    /// match the configured scheme; a proof that does match fails with
+    /// [`FailureReason::MissingOrchardParams`].
+    #[cfg(feature = "native")]
+    pub(super) fn without_params(config: &OrchardSnapshot) -> Self {
+        Self {
+            params: OrchardProofParams::Missing,
+            ..Self::signatures_only(config)
+        }
+    }
+
+    /// Bind the airdrop's claim window, which claims are signed over.
+    #[must_use]
+    pub const fn with_claim_window(mut self, claim_window: Option<ClaimWindow>) -> Self {
+        self.claim_window = claim_window;
+        self
+    }
+
+    /// Check one signed claim against the hash of the message it must be signed over.
+    ///
+    /// The proof is only checked if the verifier was built with params.
+    #[must_use]
+    pub fn check(
+        &self,
+        index: usize,
+        claim: &OrchardSignedClaim,
+        message_hash: Option<[u8; 32]>,
+    ) -> ClaimVerdict {
+        let digest = signature_digest(
+            Pool::Orchard,
+            &self.target_id,
+            self.claim_window.as_ref(),
+            &claim.proof_hash,
+            &claim.message_hash,
+        );
+        ClaimVerdict {
+            proof_hash: Some(proof_hash_outcome(
+                hash_orchard_signed_claim_proof(claim),
+                &claim.proof_hash,
+            )),
+            message_hash: Some(message_hash_outcome(message_hash, &claim.message_hash)),
+            signature: Some(signature_outcome(digest.and_then(|digest| {
+                orchard::verify_signature(claim.rk, claim.spend_auth_sig, &digest)
+            }))),
+            proof: self.check_proof(&claim.into()),
+            ..ClaimVerdict::new(Pool::Orchard, index, claim.airdrop_nullifier)
+        }
+    }
+
+    /// Check one claim proof, or `None` if the verifier does not check proofs.
+    #[must_use]
+    pub fn check_proof(&self, proof: &OrchardClaimProofResult) -> Option<CheckOutcome> {
+        let params = match &self.params {
+            OrchardProofParams::Unchecked => return None,
+            OrchardProofParams::Missing => None,
+            OrchardProofParams::Loaded(params) => Some(params.as_ref()),
+        };
+        Some(self.verify_proof(proof, params).into())
+    }
+
+    fn verify_proof(
+        &self,
+        proof: &OrchardClaimProofResult,
+        params: Option<&Params<vesta::Affine>>,
+    ) -> Result<(), FailureReason> {
+        if !commitments_match_scheme(self.scheme, proof) {
+            return Err(FailureReason::SchemeMismatch);
+        }
+        let params = params.ok_or(FailureReason::MissingOrchardParams)?;
+        let output = OrchardClaimProofOutput {
+            zkproof: proof.zkproof.clone(),
+            rk: proof.rk,
+            cv: proof.cv,
+            cv_sha256: proof.cv_sha256,
+            airdrop_nullifier: proof.airdrop_nullifier.into(),
+        };
+        verify_orchard_claim_proof_output(
+            params,
+            &output,
+            self.note_commitment_root,
+            self.nullifier_gap_root,
+            self.scheme,
+            self.gap_tree_hash,
+            self.target_id.as_bytes(),
+        )
+        .map_err(|error| FailureReason::InvalidProof {
+            detail: error.to_string(),
+        })
+    }
+}
+
+/// Whether an Orchard proof carries exactly the value commitment of `scheme`, so that checking
+/// it needs the Halo2 params.
+pub(super) const fn commitments_match_scheme(
+    scheme: OrchardValueCommitmentScheme,
+    proof: &OrchardClaimProofResult,
+) -> bool {
+    match scheme {
+        OrchardValueCommitmentScheme::Native => proof.cv.is_some() && proof.cv_sha256.is_none(),
+        OrchardValueCommitmentScheme::Sha256 => proof.cv.is_none() && proof.cv_sha256.is_some(),
+    }
+}
+
+impl TransparentClaimVerifier {
+    /// Bind the configured transparent balance snapshot.
+    #[must_use]
+    pub fn new(config: &TransparentSnapshot) -> Self {
+        Self {
+            snapshot: config.clone(),
+            claim_window: None,
+        }
+    }
+
+    /// Bind the airdrop's claim window, which claims are signed over.
+    #[must_use]
+    pub const fn with_claim_window(mut self, claim_window: Option<ClaimWindow>) -> Self {
+        self.claim_window = claim_window;
+        self
+    }
+
+    /// Check one signed claim against the hash of the message it must be signed over.
+    ///
+    /// The claim's inclusion proof takes the place of a zero-knowledge proof.
+    #[must_use]
+    pub fn check(
+        &self,
+        index: usize,
+        claim: &TransparentSignedClaim,
+        message_hash: Option<[u8; 32]>,
+    ) -> ClaimVerdict {
+        let digest = signature_digest(
+            Pool::Transparent,
+            &self.snapshot.target_id,
+            self.claim_window.as_ref(),
+            &claim.proof_hash,
+            &claim.message_hash,
+        );
+        ClaimVerdict {
+            proof_hash: Some(proof_hash_outcome(
+                hash_transparent_signed_claim_proof(claim),
+                &claim.proof_hash,
+            )),
+            message_hash: Some(message_hash_outcome(message_hash, &claim.message_hash)),
+            signature: Some(signature_outcome(digest.and_then(|digest| {
+                transparent::verify_signature(&claim.pubkey, &claim.signature, &digest)
+            }))),
+            proof: Some(
+                check_transparent_inclusion(claim, &self.snapshot)
+                    .map_err(FailureReason::from)
+                    .into(),
+            ),
+            ..ClaimVerdict::new(Pool::Transparent, index, claim.airdrop_nullifier)
+        }
+    }
+}
+
+fn check_outcome(matches: bool, reason: FailureReason) -> CheckOutcome {
+    if matches {
+        CheckOutcome::Valid
+    } else {
+        CheckOutcome::Invalid { reason }
+    }
+}
+
+fn proof_hash_outcome(computed: eyre::Result<[u8; 32]>, proof_hash: &[u8; 32]) -> CheckOutcome {
+    match computed {
+        Ok(computed) => check_outcome(&computed == proof_hash, FailureReason::ProofHashMismatch),
+        Err(error) => malformed(&error),
+    }
+}
+
+fn message_hash_outcome(expected: Option<[u8; 32]>, message_hash: &[u8; 32]) -> CheckOutcome {
+    expected.map_or(
+        CheckOutcome::Invalid {
+            reason: FailureReason::MissingMessage,
+        },
+        |expected| {
+            check_outcome(
+                &expected == message_hash,
+                FailureReason::MessageHashMismatch,
+            )
+        },
+    )
+}
+
+fn signature_outcome(result: eyre::Result<bool>) -> CheckOutcome {
+    match result {
+        Ok(valid) => check_outcome(valid, FailureReason::InvalidSignature),
+        Err(error) => malformed(&error),
+    }
+}
+
+fn malformed(error: &eyre::Report) -> CheckOutcome {
+    CheckOutcome::Invalid {
+        reason: FailureReason::Malformed {
+            detail: format!("{error:#}"),
+        },
+    }
+}
+
+/// Check that a transparent claim's address and balance are a leaf of the snapshot's balance
+/// tree and that its airdrop nullifier belongs to that address.
+///
+/// # Errors
+/// Returns why the claim is not backed by the snapshot.
+pub(super) fn check_transparent_inclusion(
+    claim: &TransparentSignedClaim,
+    snapshot: &TransparentSnapshot,
+) -> Result<(), TransparentInclusionError> {
+    let pubkey_hash = transparent::pubkey_hash(&claim.pubkey)
+        .map_err(|err| TransparentInclusionError::Malformed(err.to_string()))?;
+    if airdrop_nullifier(snapshot.target_id.as_bytes(), &pubkey_hash) != claim.airdrop_nullifier {
+        return Err(TransparentInclusionError::NullifierMismatch);
+    }
+    let leaf = TransparentBalance {
+        pubkey_hash,
+        balance: claim.balance,
+    };
+    let root = root_from_path(leaf.leaf_hash(), claim.leaf_index, &claim.merkle_path).ok_or_else(
+        || {
+            TransparentInclusionError::Malformed(
+                "Merkle path does not fit the leaf index".to_owned(),
+            )
+        },
+    )?;
+    if root == snapshot.balance_root {
+        Ok(())
+    } else {
+        Err(TransparentInclusionError::NotInSnapshot)
+    }
+}
+
+impl SubmissionVerifier {
+    /// Verifier for the pools that have verifying material.
+    ///
+    /// Structured claim messages are checked with the default [`AddressValidators`].
+    #[must_use]
+    pub fn new(
+        sapling: Option<SaplingClaimVerifier>,
+        orchard: Option<OrchardClaimVerifier>,
+        transparent: Option<TransparentClaimVerifier>,
+    ) -> Self {
+        Self {
+            sapling,
+            orchard,
+            transparent,
+            claim_window: None,
+            message_policy: MessagePolicy::default(),
+            validators: AddressValidators::default(),
+        }
+    }
+
+    /// Refuse message bytes the airdrop's message `policy` does not accept.
+    #[must_use]
+    pub const fn with_message_policy(mut self, policy: MessagePolicy) -> Self {
+        self.message_policy = policy;
+        self
+    }
+
+    /// Check the recipients of structured claim messages with `validators`.
+    #[must_use]
+    pub fn with_address_validators(mut self, validators: AddressValidators) -> Self {
+        self.validators = validators;
+        self
+    }
+
+    /// Enforce the airdrop's claim window and bind it into every pool's signature checks.
+    #[must_use]
+    pub fn with_claim_window(mut self, claim_window: Option<ClaimWindow>) -> Self {
+        self.sapling = self
+            .sapling
+            .map(|verifier| verifier.with_claim_window(claim_window));
+        self.orchard = self
+            .orchard
+            .map(|verifier| verifier.with_claim_window(claim_window));
+        self.transparent = self
+            .transparent
+            .map(|verifier| verifier.with_claim_window(claim_window));
+        self.claim_window = claim_window;
+        self
+    }
+
+    /// Whether no pool can be verified.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.sapling.is_none() && self.orchard.is_none() && self.transparent.is_none()
+    }
+
+    /// Verify every claim of a submission at the target-chain position `clock`.
+    ///
+    /// Failing claims are reported in the response; only requests that cannot be verified at
+    /// all are rejected. A claim fails with [`FailureReason::InvalidMessage`] if its message
+    /// bytes exceed the message policy or are a structured claim message refused by the address
+    /// validators.
+    ///
+    /// # Errors
+    /// Returns an error if the submission is empty, has duplicate airdrop nullifiers or
+    /// messages, has claims for a pool without a verifier, or `clock` is outside the claim
+    /// window.
+    pub fn verify(
+        &self,
+        request: &VerifyRequest,
+        clock: &ChainClock,
+    ) -> Result<VerifyResponse, VerifyRequestError> {
+        let submission = &request.submission;
+        if submission.sapling.is_empty() &&
+            submission.orchard.is_empty() &&
+            submission.transparent.is_empty()
+        {
+            return Err(VerifyRequestError::EmptySubmission);
+        }
+        if let Some(claim_window) = &self.claim_window {
+            claim_window.check(clock)?;
+        }
+        ensure_unique_airdrop_nullifiers(
+            submission
+                .sapling
+                .iter()
+                .map(|claim| claim.airdrop_nullifier),
+            "Sapling signed claim",
+        )
+        .and_then(|()| {
+            ensure_unique_airdrop_nullifiers(
+                submission
+                    .orchard
+                    .iter()
+                    .map(|claim| claim.airdrop_nullifier),
+                "Orchard signed claim",
+            )
+        })
+        .and_then(|()| {
+            ensure_unique_airdrop_nullifiers(
+                submission
+                    .transparent
+                    .iter()
+                    .map(|claim| claim.airdrop_nullifier),
+                "Transparent signed claim",
+            )
+        })
+        .map_err(|err| VerifyRequestError::InvalidSubmission(err.to_string()))?;
+        let messages = self.request_messages(request)?;
+
+        let report = self.check_claims(
+            submission,
+            |pool, airdrop_nullifier| messages.resolve(pool, airdrop_nullifier),
+            |_, _, _| Ok::<_, VerifyRequestError>(()),
+        )?;
+        Ok(report.into())
+    }
+
+    /// Check every claim of a submission whose message hashes are already resolved.
+    ///
+    /// Unlike [`Self::verify`], the submission is not validated as a whole and the claim
+    /// window is not checked. `progress` is called with the pool, the number of its claims
+    /// checked so far and their total, before each claim and once the pool is done; an error it
+    /// returns stops the checks.
+    ///
+    /// # Errors
+    /// Returns [`VerifyRequestError::PoolNotConfigured`] if the submission has claims for a
+    /// pool without a verifier, or the error returned by `progress`.
+    pub fn check_submission<E: From<VerifyRequestError>>(
+        &self,
+        submission: &ClaimSubmission,
+        messages: &ResolvedMessageHashes,
+        progress: impl FnMut(Pool, usize, usize) -> Result<(), E>,
+    ) -> Result<VerificationReport, E> {
+        self.check_claims(
+            submission,
+            |pool, airdrop_nullifier| {
+                Ok(match pool {
+                    Pool::Sapling => messages.sapling_hash(airdrop_nullifier),
+                    Pool::Orchard => messages.orchard_hash(airdrop_nullifier),
+                    Pool::Transparent => messages.transparent_hash(airdrop_nullifier),
+                })
+            },
+            progress,
+        )
+    }
+
+    fn check_claims<E: From<VerifyRequestError>>(
+        &self,
+        submission: &ClaimSubmission,
+        message: impl Fn(Pool, Nullifier) -> Result<Option<[u8; 32]>, FailureReason>,
+        mut progress: impl FnMut(Pool, usize, usize) -> Result<(), E>,
+    ) -> Result<VerificationReport, E> {
+        let sapling = configured(self.sapling.as_ref(), &submission.sapling, Pool::Sapling)?;
+        let orchard = configured(self.orchard.as_ref(), &submission.orchard, Pool::Orchard)?;
+        let transparent = configured(
+            self.transparent.as_ref(),
+            &submission.transparent,
+            Pool::Transparent,
+        )?;
+
+        let mut report = VerificationReport::default();
+        if let Some(verifier) = sapling {
+            check_pool(
+                Pool::Sapling,
+                &submission.sapling,
+                |index, claim, message_hash| verifier.check(index, claim, message_hash),
+                |claim| message(Pool::Sapling, claim.airdrop_nullifier),
+                &mut progress,
+                &mut report,
+            )?;
+        }
+        if let Some(verifier) = orchard {
+            check_pool(
+                Pool::Orchard,
+                &submission.orchard,
+                |index, claim, message_hash| verifier.check(index, claim, message_hash),
+                |claim| message(Pool::Orchard, claim.airdrop_nullifier),
+                &mut progress,
+                &mut report,
+            )?;
+        }
+        if let Some(verifier) = transparent {
+            check_pool(
+                Pool::Transparent,
+                &submission.transparent,
+                |index, claim, message_hash| verifier.check(index, claim, message_hash),
+                |claim| message(Pool::Transparent, claim.airdrop_nullifier),
+                &mut progress,
+                &mut report,
+            )?;
+        }
+        Ok(report)
+    }
+}
+
+/// The verifier of `pool` if the submission has `claims` for it.
+fn configured<'a, V, C>(
+    verifier: Option<&'a V>,
+    claims: &[C],
+    pool: Pool,
+) -> Result<Option<&'a V>, VerifyRequestError> {
+    if claims.is_empty() {
+        Ok(None)
+    } else {
+        verifier
+            .map(Some)
+            .ok_or(VerifyRequestError::PoolNotConfigured(pool))
+    }
+}
+
+/// Check the claims of one pool in order, reporting progress before each claim and at the end.
+fn check_pool<C, E>(
+    pool: Pool,
+    claims: &[C],
+    check: impl Fn(usize, &C, Option<[u8; 32]>) -> ClaimVerdict,
+    message: impl Fn(&C) -> Result<Option<[u8; 32]>, FailureReason>,
+    progress: &mut impl FnMut(Pool, usize, usize) -> Result<(), E>,
+    report: &mut VerificationReport,
+) -> Result<(), E> {
+    let total = claims.len();
+    for (index, claim) in claims.iter().enumerate() {
+        progress(pool, index, total)?;
+        report.claims.push(match message(claim) {
+            Ok(message_hash) => check(index, claim, message_hash),
+            Err(reason) => ClaimVerdict {
+                message_hash: Some(CheckOutcome::Invalid { reason }),
+                ..check(index, claim, None)
+            },
+        });
+    }
+    progress(pool, total, total)
+}
+
+impl SubmissionVerifier {
+    /// Hash a claim message, refusing message bytes above the message policy's limit or that
+    /// are an invalid structured claim message.
+    fn check_message(&self, message: &ClaimMessage) -> MessageCheck {
+        match message {
+            ClaimMessage::Bytes(bytes) => {
+                if !self.message_policy.accepts(bytes) {
+                    return Err(RefusedMessage::TooLong {
+                        len: bytes.len(),
+                        max_bytes: self.message_policy.max_bytes.unwrap_or_default(),
+                    });
+                }
+                self.validators.check_message(bytes)?;
+                Ok(hash_message(bytes))
+            }
+            ClaimMessage::Hash(hash) => Ok(*hash),
+        }
+    }
+
+    fn message_map(
+        &self,
+        entries: &[ClaimMessageEntry],
+        pool: Pool,
+    ) -> Result<BTreeMap<Nullifier, MessageCheck>, VerifyRequestError> {
+        let mut by_nullifier = BTreeMap::new();
+        for entry in entries {
+            if by_nullifier
+                .insert(entry.airdrop_nullifier, self.check_message(&entry.message))
+                .is_some()
+            {
+                return Err(VerifyRequestError::InvalidSubmission(format!(
+                    "Duplicate {pool} message for airdrop nullifier {}",
+                    entry.airdrop_nullifier
+                )));
+            }
+        }
+        Ok(by_nullifier)
+    }
+
+    fn request_messages(
+        &self,
+        request: &VerifyRequest,
+    ) -> Result<RequestMessages, VerifyRequestError> {
+        Ok(RequestMessages {
+            shared: request
+                .message
+                .as_ref()
+                .map(|message| self.check_message(message)),
+            sapling: self.message_map(&request.sapling_messages, Pool::Sapling)?,
+            orchard: self.message_map(&request.orchard_messages, Pool::Orchard)?,
+            transparent: self.message_map(&request.transparent_messages, Pool::Transparent)?,
+        })
+    }
+}
+
+/// Why a claim message sent as bytes is refused.
+#[derive(Debug, thiserror::Error)]
+enum RefusedMessage {
+    /// The message is longer than the airdrop's message policy allows.
+    #[error(
+        "message is {len} bytes, above the {max_bytes} byte limit of the airdrop configuration"
+    )]
+    TooLong { len: usize, max_bytes: u64 },
+    /// The message is an invalid structured claim message.
+    #[error(transparent)]
+    Invalid(#[from] InvalidClaimMessage),
+}
+
+/// Hash of a claim message, or why the message is refused.
+type MessageCheck = Result<[u8; 32], RefusedMessage>;
+
+/// Checked messages of a request; per-claim messages override the shared one.
+struct RequestMessages {
+    shared: Option<MessageCheck>,
+    sapling: BTreeMap<Nullifier, MessageCheck>,
+    orchard: BTreeMap<Nullifier, MessageCheck>,
+    transparent: BTreeMap<Nullifier, MessageCheck>,
+}
+
+impl RequestMessages {
+    /// Hash of the message of the `pool` claim with `airdrop_nullifier`, if it has one.
+    fn resolve(
+        &self,
+        pool: Pool,
+        airdrop_nullifier: Nullifier,
+    ) -> Result<Option<[u8; 32]>, FailureReason> {
+        let by_nullifier = match pool {
+            Pool::Sapling => &self.sapling,
+            Pool::Orchard => &self.orchard,
+            Pool::Transparent => &self.transparent,
+        };
+        match by_nullifier
+            .get(&airdrop_nullifier)
+            .or(self.shared.as_ref())
+        {
+            None => Ok(None),
+            Some(Ok(hash)) => Ok(Some(*hash)),
+            Some(Err(err)) => Err(FailureReason::InvalidMessage {
+                detail: err.to_string(),
+            }),
+        }
+    }
+}
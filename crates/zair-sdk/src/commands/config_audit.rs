@@ -0,0 +1,934 @@
+//! Independent audit of a published airdrop snapshot.
+//!
+//! The audit recomputes what `config build` committed to from the published artifacts: each
+//! nullifier gap root from the snapshot file, the root of each gap-tree file, the transparent
+//! balance root from the balance listing, and (online) the note commitment roots from
+//! lightwalletd. With a rescan it also re-fetches the nullifiers
+//! from the chain, so a third party can reproduce the snapshot end to end. The JSON report
+//! and its Markdown rendering name the SHA-256 of every input, so an auditor can sign off on
+//! exactly the files they checked.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::str::FromStr as _;
+
+use eyre::{Context as _, ContextCompat as _};
+use http::Uri;
+use serde::Serialize;
+use sha2::{Digest as _, Sha256};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt as _, BufReader};
+use tracing::{info, warn};
+use zair_core::base::{Pool, SanitiseNullifiers};
+use zair_core::container::{ContainerKind, ContainerOptions};
+use zair_core::schema::config::{AirdropConfiguration, AirdropNetwork, OrchardGapTreeHash};
+use zair_nonmembership::{MerklePathError, OrchardGapTree, OrchardPoseidonGapTree, SaplingGapTree};
+use zair_scan::light_walletd::LightWalletd;
+use zair_scan::scanner::ChainNullifiersVisitor;
+use zcash_protocol::consensus::BlockHeight;
+
+use super::airdrop_configuration::resolve_snapshot_scan_range;
+use super::transparent_balances::read_transparent_balances;
+use crate::common::{PoolSelection, resolve_lightwalletd_url, to_zcash_network};
+
+/// 1 MiB buffer for file I/O.
+const FILE_BUF_SIZE: usize = 1024 * 1024;
+
+/// A published root and the root recomputed from the artifacts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RootCheck {
+    /// Root committed to by the configuration, as hex.
+    pub expected: String,
+    /// Recomputed root, as hex.
+    pub computed: String,
+    /// Whether the roots are equal.
+    pub matches: bool,
+}
+
+impl RootCheck {
+    fn new(expected: [u8; 32], computed: [u8; 32]) -> Self {
+        Self {
+            expected: hex::encode(expected),
+            computed: hex::encode(computed),
+            matches: expected == computed,
+        }
+    }
+}
+
+/// A published count and the count recomputed from the artifacts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CountCheck {
+    /// Count committed to by the configuration.
+    pub expected: u64,
+    /// Recomputed count.
+    pub computed: u64,
+    /// Whether the counts are equal.
+    pub matches: bool,
+}
+
+impl CountCheck {
+    const fn new(expected: u64, computed: u64) -> Self {
+        Self {
+            expected,
+            computed,
+            matches: expected == computed,
+        }
+    }
+}
+
+/// An audited input file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDigest {
+    /// Path the file was read from.
+    pub path: PathBuf,
+    /// SHA-256 of the file contents, as hex.
+    pub sha256: String,
+}
+
+/// Audit of a published gap-tree file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GapTreeAudit {
+    /// Gap-tree file.
+    pub file: FileDigest,
+    /// Root of the gap tree against the configured gap root.
+    pub root: RootCheck,
+}
+
+/// Nullifiers re-fetched from the chain for one pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RescanAudit {
+    /// Nullifiers seen on chain in the scan range, duplicates included.
+    pub collected: u64,
+    /// Duplicates removed when sanitising the collected nullifiers.
+    pub duplicates_removed: u64,
+    /// Whether the sanitised nullifiers equal the snapshot file.
+    pub matches_snapshot: bool,
+}
+
+/// Audit of one shielded pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PoolAudit {
+    /// Audited pool.
+    pub pool: Pool,
+    /// Snapshot nullifiers file.
+    pub snapshot: FileDigest,
+    /// Distinct nullifiers in the snapshot file.
+    pub nullifier_count: u64,
+    /// Duplicate nullifiers in the snapshot file; `config build` writes none.
+    pub duplicates_in_file: u64,
+    /// Root stored in the snapshot file header, if the file has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_header_root: Option<RootCheck>,
+    /// Gap root recomputed from the snapshot nullifiers.
+    pub nullifier_gap_root: RootCheck,
+    /// Published gap-tree file, if one was audited.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap_tree: Option<GapTreeAudit>,
+    /// Note commitment root re-queried from lightwalletd, or `None` offline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note_commitment_root: Option<RootCheck>,
+    /// Nullifiers re-fetched from the chain, if rescanned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rescan: Option<RescanAudit>,
+}
+
+impl PoolAudit {
+    /// Whether every check run on the pool passed.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.nullifier_gap_root.matches &&
+            self.snapshot_header_root
+                .as_ref()
+                .is_none_or(|check| check.matches) &&
+            self.gap_tree.as_ref().is_none_or(|tree| tree.root.matches) &&
+            self.note_commitment_root
+                .as_ref()
+                .is_none_or(|check| check.matches) &&
+            self.rescan
+                .as_ref()
+                .is_none_or(|rescan| rescan.matches_snapshot)
+    }
+}
+
+/// Audit of the transparent balance snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransparentAudit {
+    /// Transparent balance listing.
+    pub balances: FileDigest,
+    /// Balance root recomputed from the listing.
+    pub balance_root: RootCheck,
+    /// Funded P2PKH addresses in the listing.
+    pub address_count: CountCheck,
+    /// Sum of the listed balances, in zatoshis.
+    pub total_balance: CountCheck,
+}
+
+impl TransparentAudit {
+    /// Whether every transparent check passed.
+    #[must_use]
+    pub const fn passed(&self) -> bool {
+        self.balance_root.matches && self.address_count.matches && self.total_balance.matches
+    }
+}
+
+/// Block range scanned for snapshot nullifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ScanRange {
+    /// First scanned height.
+    pub start: u64,
+    /// Last scanned height, the snapshot height.
+    pub end: u64,
+}
+
+impl From<&RangeInclusive<u64>> for ScanRange {
+    fn from(range: &RangeInclusive<u64>) -> Self {
+        Self {
+            start: *range.start(),
+            end: *range.end(),
+        }
+    }
+}
+
+/// Audit report of an airdrop snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SnapshotAudit {
+    /// Version of the tool that produced the report.
+    pub zair_version: String,
+    /// Audited configuration file.
+    pub config: FileDigest,
+    /// Network of the snapshot.
+    pub network: AirdropNetwork,
+    /// Snapshot height.
+    pub snapshot_height: u64,
+    /// Block range the snapshot nullifiers are collected from.
+    pub scan_range: ScanRange,
+    /// Whether the note commitment roots were re-queried from lightwalletd.
+    pub online: bool,
+    /// Per-pool results.
+    pub pools: Vec<PoolAudit>,
+    /// Transparent balance snapshot results, if the configuration has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transparent: Option<TransparentAudit>,
+    /// Whether every check passed.
+    pub passed: bool,
+    /// Name of the auditor signing off on the report.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auditor: Option<String>,
+}
+
+impl SnapshotAudit {
+    /// Render the report as Markdown, with a sign-off naming `report_sha256`, the SHA-256 of
+    /// the JSON report.
+    #[must_use]
+    pub fn to_markdown(&self, report_sha256: &str) -> String {
+        Markdown {
+            report: self,
+            report_sha256,
+        }
+        .to_string()
+    }
+}
+
+/// Markdown rendering of a [`SnapshotAudit`].
+struct Markdown<'a> {
+    report: &'a SnapshotAudit,
+    report_sha256: &'a str,
+}
+
+impl fmt::Display for Markdown<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = self.report;
+        writeln!(f, "# ZAIR snapshot audit")?;
+        writeln!(f)?;
+        writeln!(f, "- Network: {:?}", report.network)?;
+        writeln!(f, "- Snapshot height: {}", report.snapshot_height)?;
+        writeln!(
+            f,
+            "- Scan range: {}..={}",
+            report.scan_range.start, report.scan_range.end
+        )?;
+        writeln!(
+            f,
+            "- Configuration: `{}` (SHA-256 `{}`)",
+            report.config.path.display(),
+            report.config.sha256
+        )?;
+        writeln!(f, "- Tool: zair {}", report.zair_version)?;
+        writeln!(
+            f,
+            "- Result: **{}**",
+            if report.passed { "PASSED" } else { "FAILED" }
+        )?;
+        if !report.online {
+            writeln!(
+                f,
+                "- Note commitment roots were not checked (offline audit)"
+            )?;
+        }
+        for pool in &report.pools {
+            write_pool(f, pool)?;
+        }
+        if let Some(transparent) = &report.transparent {
+            write_transparent(f, transparent)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "## Sign-off")?;
+        writeln!(f)?;
+        writeln!(f, "JSON report SHA-256: `{}`", self.report_sha256)?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Audited by: {}",
+            report.auditor.as_deref().unwrap_or("____________________")
+        )
+    }
+}
+
+fn write_pool(f: &mut fmt::Formatter<'_>, pool: &PoolAudit) -> fmt::Result {
+    let verdict = |matches: bool| if matches { "ok" } else { "MISMATCH" };
+    writeln!(f)?;
+    writeln!(f, "## {}", pool.pool)?;
+    writeln!(f)?;
+    writeln!(
+        f,
+        "- Snapshot: `{}` (SHA-256 `{}`)",
+        pool.snapshot.path.display(),
+        pool.snapshot.sha256
+    )?;
+    writeln!(
+        f,
+        "- Nullifiers: {} ({} duplicates in file)",
+        pool.nullifier_count, pool.duplicates_in_file
+    )?;
+    if let Some(tree) = &pool.gap_tree {
+        writeln!(
+            f,
+            "- Gap tree: `{}` (SHA-256 `{}`)",
+            tree.file.path.display(),
+            tree.file.sha256
+        )?;
+    }
+    if let Some(rescan) = &pool.rescan {
+        writeln!(
+            f,
+            "- Rescan: {} nullifiers on chain, {} duplicates removed",
+            rescan.collected, rescan.duplicates_removed
+        )?;
+    }
+    writeln!(f)?;
+    writeln!(f, "| Check | Expected | Computed | Result |")?;
+    writeln!(f, "| ----- | -------- | -------- | ------ |")?;
+    let checks = [
+        ("Nullifier gap root", Some(&pool.nullifier_gap_root)),
+        ("Snapshot header root", pool.snapshot_header_root.as_ref()),
+        (
+            "Gap-tree file root",
+            pool.gap_tree.as_ref().map(|tree| &tree.root),
+        ),
+        ("Note commitment root", pool.note_commitment_root.as_ref()),
+    ];
+    for (name, check) in checks {
+        if let Some(check) = check {
+            writeln!(
+                f,
+                "| {name} | `{}` | `{}` | {} |",
+                check.expected,
+                check.computed,
+                verdict(check.matches)
+            )?;
+        }
+    }
+    if let Some(rescan) = &pool.rescan {
+        writeln!(
+            f,
+            "| Rescanned nullifiers | snapshot file | chain | {} |",
+            verdict(rescan.matches_snapshot)
+        )?;
+    }
+    Ok(())
+}
+
+fn write_transparent(f: &mut fmt::Formatter<'_>, audit: &TransparentAudit) -> fmt::Result {
+    let verdict = |matches: bool| if matches { "ok" } else { "MISMATCH" };
+    writeln!(f)?;
+    writeln!(f, "## {}", Pool::Transparent)?;
+    writeln!(f)?;
+    writeln!(
+        f,
+        "- Balances: `{}` (SHA-256 `{}`)",
+        audit.balances.path.display(),
+        audit.balances.sha256
+    )?;
+    writeln!(f)?;
+    writeln!(f, "| Check | Expected | Computed | Result |")?;
+    writeln!(f, "| ----- | -------- | -------- | ------ |")?;
+    writeln!(
+        f,
+        "| Balance root | `{}` | `{}` | {} |",
+        audit.balance_root.expected,
+        audit.balance_root.computed,
+        verdict(audit.balance_root.matches)
+    )?;
+    for (name, check) in [
+        ("Address count", audit.address_count),
+        ("Total balance", audit.total_balance),
+    ] {
+        writeln!(
+            f,
+            "| {name} | {} | {} | {} |",
+            check.expected,
+            check.computed,
+            verdict(check.matches)
+        )?;
+    }
+    Ok(())
+}
+
+/// Error returned when at least one audit check fails; carries the full report.
+#[derive(Debug, thiserror::Error)]
+#[error("Snapshot audit failed; see the report for the mismatching checks")]
+pub struct AuditFailed {
+    /// Report with every check.
+    pub report: SnapshotAudit,
+}
+
+/// Per-pool inputs of the audit.
+struct PoolInputs<'a> {
+    pool: Pool,
+    note_commitment_root: [u8; 32],
+    nullifier_gap_root: [u8; 32],
+    gap_tree_hash: OrchardGapTreeHash,
+    snapshot_file: &'a Path,
+    gap_tree_file: Option<&'a Path>,
+}
+
+/// Audit a published airdrop configuration against its snapshot artifacts.
+///
+/// Writes the JSON report to `report_output_file` and its Markdown rendering to
+/// `markdown_output_file`, whether or not the audit passes. Unless `offline`, the note
+/// commitment roots are re-queried from lightwalletd; with `rescan`, the snapshot nullifiers
+/// are also re-fetched from the chain. A configuration with a transparent snapshot needs its
+/// `transparent_balances_file` listing, from which the balance tree is rebuilt.
+///
+/// # Errors
+/// Returns an error if an input cannot be read or does not belong to the configuration, the
+/// transparent listing is missing or not expected, lightwalletd fails, or [`AuditFailed`] with
+/// the report if any check fails.
+#[allow(
+    clippy::too_many_arguments,
+    clippy::too_many_lines,
+    reason = "CLI-facing command entrypoint mirrors explicit command arguments"
+)]
+pub async fn audit_airdrop_configuration(
+    airdrop_configuration_file: PathBuf,
+    sapling_snapshot_file: PathBuf,
+    orchard_snapshot_file: PathBuf,
+    sapling_gap_tree_file: Option<PathBuf>,
+    orchard_gap_tree_file: Option<PathBuf>,
+    transparent_balances_file: Option<PathBuf>,
+    lightwalletd_url: Option<String>,
+    offline: bool,
+    rescan: bool,
+    auditor: Option<String>,
+    report_output_file: PathBuf,
+    markdown_output_file: PathBuf,
+) -> eyre::Result<SnapshotAudit> {
+    eyre::ensure!(
+        !(offline && rescan),
+        "--rescan needs lightwalletd and cannot run offline"
+    );
+    let config_digest = file_digest(&airdrop_configuration_file).await?;
+    let config: AirdropConfiguration =
+        serde_json::from_str(&tokio::fs::read_to_string(&airdrop_configuration_file).await?)
+            .context("Failed to parse airdrop configuration JSON")?;
+
+    let selection = match (&config.sapling, &config.orchard) {
+        (Some(_), Some(_)) => PoolSelection::Both,
+        (Some(_), None) => PoolSelection::Sapling,
+        (None, Some(_)) => PoolSelection::Orchard,
+        (None, None) => {
+            eyre::bail!("Airdrop configuration has no shielded pool to audit");
+        }
+    };
+    let transparent_inputs = match (&config.transparent, transparent_balances_file) {
+        (Some(transparent), Some(path)) => Some((transparent, path)),
+        (None, None) => None,
+        (Some(_), None) => {
+            eyre::bail!(
+                "Airdrop configuration commits to transparent balances; the balance listing is \
+                 needed to audit them"
+            );
+        }
+        (None, Some(path)) => {
+            eyre::bail!(
+                "Airdrop configuration has no transparent snapshot to audit against {}",
+                path.display()
+            );
+        }
+    };
+    let network = to_zcash_network(config.network);
+    let scan_range = resolve_snapshot_scan_range(network, selection, config.snapshot_height)?;
+    let options = ContainerOptions::new(config.network, config.snapshot_height);
+
+    let mut inputs = Vec::new();
+    if let Some(sapling) = &config.sapling {
+        inputs.push(PoolInputs {
+            pool: Pool::Sapling,
+            note_commitment_root: sapling.note_commitment_root,
+            nullifier_gap_root: sapling.nullifier_gap_root,
+            gap_tree_hash: OrchardGapTreeHash::default(),
+            snapshot_file: &sapling_snapshot_file,
+            gap_tree_file: sapling_gap_tree_file.as_deref(),
+        });
+    }
+    if let Some(orchard) = &config.orchard {
+        inputs.push(PoolInputs {
+            pool: Pool::Orchard,
+            note_commitment_root: orchard.note_commitment_root,
+            nullifier_gap_root: orchard.nullifier_gap_root,
+            gap_tree_hash: orchard.nullifier_gap_tree_hash,
+            snapshot_file: &orchard_snapshot_file,
+            gap_tree_file: orchard_gap_tree_file.as_deref(),
+        });
+    }
+
+    let lightwalletd = if offline {
+        warn!("Offline audit: note commitment roots are not checked");
+        None
+    } else {
+        let url = resolve_lightwalletd_url(network, lightwalletd_url.as_deref());
+        let url = Uri::from_str(&url).context("Invalid lightwalletd URL")?;
+        Some(LightWalletd::connect(url).await?)
+    };
+    let anchors = match &lightwalletd {
+        Some(lightwalletd) => {
+            let upper_limit = u32::try_from(config.snapshot_height)
+                .context("Snapshot height too large")?
+                .checked_add(1)
+                .context("Snapshot height overflowed when adding 1")?;
+            Some(
+                lightwalletd
+                    .commitment_tree_anchors(BlockHeight::from_u32(upper_limit))
+                    .await
+                    .context("Failed to fetch commitment tree roots from lightwalletd")?,
+            )
+        }
+        None => None,
+    };
+    let rescanned = match (&lightwalletd, rescan) {
+        (Some(lightwalletd), true) => {
+            info!(?scan_range, "Re-fetching snapshot nullifiers");
+            let mut visitor = ChainNullifiersVisitor::default();
+            lightwalletd
+                .scan_nullifiers(&mut visitor, &scan_range)
+                .await?;
+            let (sapling_collected, orchard_collected) = visitor.collected();
+            let (sapling, orchard) = visitor.sanitise_nullifiers();
+            Some([(sapling_collected, sapling), (orchard_collected, orchard)])
+        }
+        _ => None,
+    };
+
+    let mut pools = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let snapshot = file_digest(input.snapshot_file).await?;
+        let (header_root, raw_count, nullifiers) =
+            load_snapshot(input.snapshot_file, input.pool, &options).await?;
+        let nullifier_count = nullifiers.len();
+        let duplicates_in_file = raw_count.saturating_sub(nullifier_count);
+        if duplicates_in_file > 0 {
+            warn!(pool = ?input.pool, duplicates_in_file, "Snapshot file has duplicate nullifiers");
+        }
+
+        info!(pool = ?input.pool, nullifiers = nullifier_count, "Recomputing nullifier gap root");
+        let (pool, hash) = (input.pool, input.gap_tree_hash);
+        let (nullifiers, computed_root) = tokio::task::spawn_blocking(move || {
+            gap_root(pool, hash, &nullifiers).map(|root| (nullifiers, root))
+        })
+        .await??;
+
+        let gap_tree = match input.gap_tree_file {
+            Some(path) => {
+                let file = file_digest(path).await?;
+                let owned = path.to_owned();
+                let root =
+                    tokio::task::spawn_blocking(move || gap_tree_file_root(pool, hash, &owned))
+                        .await?
+                        .with_context(|| format!("Failed to read gap tree {}", path.display()))?;
+                Some(GapTreeAudit {
+                    file,
+                    root: RootCheck::new(input.nullifier_gap_root, root),
+                })
+            }
+            None => None,
+        };
+
+        let note_commitment_root = match (&anchors, input.pool) {
+            (None, _) => None,
+            (Some(anchors), Pool::Sapling) => Some(anchors.sapling),
+            (Some(anchors), Pool::Orchard) => Some(anchors.orchard),
+            (Some(_), Pool::Transparent) => {
+                eyre::bail!("The transparent pool has no note commitment tree");
+            }
+        }
+        .map(|queried| RootCheck::new(input.note_commitment_root, queried));
+
+        let rescan = rescanned.as_ref().and_then(|rescanned| {
+            let index = usize::from(input.pool == Pool::Orchard);
+            rescanned.get(index).map(|(collected, chain)| RescanAudit {
+                collected: u64::try_from(*collected).unwrap_or(u64::MAX),
+                duplicates_removed: u64::try_from(collected.saturating_sub(chain.len()))
+                    .unwrap_or(u64::MAX),
+                matches_snapshot: *chain == nullifiers,
+            })
+        });
+
+        let audit = PoolAudit {
+            pool: input.pool,
+            snapshot,
+            nullifier_count: u64::try_from(nullifier_count)?,
+            duplicates_in_file: u64::try_from(duplicates_in_file)?,
+            snapshot_header_root: header_root
+                .map(|root| RootCheck::new(input.nullifier_gap_root, root)),
+            nullifier_gap_root: RootCheck::new(input.nullifier_gap_root, computed_root),
+            gap_tree,
+            note_commitment_root,
+            rescan,
+        };
+        info!(pool = ?audit.pool, passed = audit.passed(), "Audited pool");
+        pools.push(audit);
+    }
+
+    let transparent = match transparent_inputs {
+        Some((expected, path)) => {
+            let balances = file_digest(&path).await?;
+            let tree = read_transparent_balances(config.network, &path).await?;
+            let total_balance = tree
+                .total_balance()
+                .context("Total transparent balance overflows u64")?;
+            let audit = TransparentAudit {
+                balances,
+                balance_root: RootCheck::new(expected.balance_root, tree.root()),
+                address_count: CountCheck::new(
+                    expected.address_count,
+                    u64::try_from(tree.balances().len())?,
+                ),
+                total_balance: CountCheck::new(expected.total_balance, total_balance),
+            };
+            info!(passed = audit.passed(), "Audited transparent balances");
+            Some(audit)
+        }
+        None => None,
+    };
+
+    let passed = pools.iter().all(PoolAudit::passed) &&
+        transparent.as_ref().is_none_or(TransparentAudit::passed);
+    let report = SnapshotAudit {
+        zair_version: env!("CARGO_PKG_VERSION").to_owned(),
+        config: config_digest,
+        network: config.network,
+        snapshot_height: config.snapshot_height,
+        scan_range: ScanRange::from(&scan_range),
+        online: !offline,
+        pools,
+        transparent,
+        passed,
+        auditor,
+    };
+
+    let json = serde_json::to_string_pretty(&report)?;
+    let report_sha256 = hex::encode(Sha256::digest(json.as_bytes()));
+    tokio::fs::write(&report_output_file, &json).await?;
+    tokio::fs::write(&markdown_output_file, report.to_markdown(&report_sha256)).await?;
+    info!(
+        report = ?report_output_file,
+        markdown = ?markdown_output_file,
+        sha256 = %report_sha256,
+        passed,
+        "Exported snapshot audit"
+    );
+
+    if passed {
+        Ok(report)
+    } else {
+        Err(AuditFailed { report }.into())
+    }
+}
+
+/// Read a snapshot file, returning its header root, raw nullifier count and nullifiers.
+async fn load_snapshot(
+    path: &Path,
+    pool: Pool,
+    options: &ContainerOptions,
+) -> eyre::Result<(Option<[u8; 32]>, usize, SanitiseNullifiers)> {
+    let file = File::open(path)
+        .await
+        .with_context(|| format!("Failed to open snapshot {}", path.display()))?;
+    let snapshot = zair_scan::read_snapshot(BufReader::with_capacity(FILE_BUF_SIZE, file))
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let header_root = if let Some(header) = snapshot.header {
+        header
+            .ensure_content(ContainerKind::Snapshot, pool)
+            .and_then(|()| header.ensure_snapshot(options))
+            .with_context(|| {
+                format!(
+                    "Snapshot {} does not match the airdrop configuration",
+                    path.display()
+                )
+            })?;
+        Some(header.root)
+    } else {
+        warn!(file = %path.display(), "Snapshot file has no container header");
+        None
+    };
+    let raw_count = snapshot.nullifiers.len();
+    Ok((
+        header_root,
+        raw_count,
+        SanitiseNullifiers::new(snapshot.nullifiers),
+    ))
+}
+
+fn gap_root(
+    pool: Pool,
+    hash: OrchardGapTreeHash,
+    nullifiers: &SanitiseNullifiers,
+) -> Result<[u8; 32], MerklePathError> {
+    match (pool, hash) {
+        (Pool::Sapling, _) => Ok(SaplingGapTree::from_nullifiers(nullifiers)?.root_bytes()),
+        (Pool::Orchard, OrchardGapTreeHash::Sinsemilla) => {
+            Ok(OrchardGapTree::from_nullifiers_with_progress(nullifiers, |_, _| {})?.root_bytes())
+        }
+        (Pool::Orchard, OrchardGapTreeHash::Poseidon) => Ok(
+            OrchardPoseidonGapTree::from_nullifiers_with_progress(nullifiers, |_, _| {})?
+                .root_bytes(),
+        ),
+        (Pool::Transparent, _) => Err(MerklePathError::NoGapTree(pool)),
+    }
+}
+
+fn gap_tree_file_root(
+    pool: Pool,
+    hash: OrchardGapTreeHash,
+    path: &Path,
+) -> Result<[u8; 32], MerklePathError> {
+    match (pool, hash) {
+        (Pool::Sapling, _) => Ok(SaplingGapTree::map_file(path)?.root_bytes()),
+        (Pool::Orchard, OrchardGapTreeHash::Sinsemilla) => {
+            Ok(OrchardGapTree::map_file(path)?.root_bytes())
+        }
+        (Pool::Orchard, OrchardGapTreeHash::Poseidon) => {
+            Ok(OrchardPoseidonGapTree::map_file(path)?.root_bytes())
+        }
+        (Pool::Transparent, _) => Err(MerklePathError::NoGapTree(pool)),
+    }
+}
+
+async fn file_digest(path: &Path) -> eyre::Result<FileDigest> {
+    let mut file = File::open(path)
+        .await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0_u8; FILE_BUF_SIZE];
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(buf.get(..read).context("Read past the buffer")?);
+    }
+    Ok(FileDigest {
+        path: path.to_owned(),
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt as _;
+    use zair_core::base::Nullifier;
+    use zair_core::schema::config::{SaplingSnapshot, TransparentSnapshot, ValueCommitmentScheme};
+    use zair_core::transparent::PUBKEY_HASH_SIZE;
+    use zair_scan::write_nullifiers;
+    use zcash_protocol::consensus::NetworkType;
+    use zcash_transparent::address::TransparentAddress;
+
+    use super::*;
+
+    const HEIGHT: u64 = 3_000_000;
+
+    async fn write_snapshot(path: &Path, nullifiers: &SanitiseNullifiers, root: [u8; 32]) {
+        let options = ContainerOptions::new(AirdropNetwork::Testnet, HEIGHT);
+        let mut file = File::create(path).await.expect("create snapshot");
+        write_nullifiers(nullifiers, Pool::Sapling, root, &options, &mut file)
+            .await
+            .expect("write snapshot");
+        file.flush().await.expect("flush snapshot");
+    }
+
+    async fn write_config(path: &Path, gap_root: [u8; 32]) {
+        write_config_with(path, gap_root, None).await;
+    }
+
+    async fn write_config_with(
+        path: &Path,
+        gap_root: [u8; 32],
+        transparent: Option<TransparentSnapshot>,
+    ) {
+        let mut config = AirdropConfiguration::new(
+            AirdropNetwork::Testnet,
+            HEIGHT,
+            Some(SaplingSnapshot {
+                note_commitment_root: [1_u8; 32],
+                nullifier_gap_root: gap_root,
+                target_id: "ZAIRTEST".to_owned(),
+                value_commitment_scheme: ValueCommitmentScheme::Native,
+            }),
+            None,
+        );
+        if let Some(transparent) = transparent {
+            config = config.with_transparent(transparent);
+        }
+        tokio::fs::write(path, serde_json::to_string(&config).expect("config json"))
+            .await
+            .expect("write config");
+    }
+
+    #[tokio::test]
+    async fn offline_audit_recomputes_gap_roots() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let config_path = dir.path().join("config.json");
+        let snapshot_path = dir.path().join("snapshot-sapling.bin");
+        let report_path = dir.path().join("audit.json");
+        let markdown_path = dir.path().join("audit.md");
+        let nullifiers = SanitiseNullifiers::new(vec![
+            Nullifier::from([3_u8; 32]),
+            Nullifier::from([9_u8; 32]),
+        ]);
+        let root =
+            gap_root(Pool::Sapling, OrchardGapTreeHash::default(), &nullifiers).expect("gap root");
+        write_snapshot(&snapshot_path, &nullifiers, root).await;
+        write_config(&config_path, root).await;
+
+        let audit = |auditor: Option<&str>| {
+            audit_airdrop_configuration(
+                config_path.clone(),
+                snapshot_path.clone(),
+                dir.path().join("snapshot-orchard.bin"),
+                None,
+                None,
+                None,
+                None,
+                true,
+                false,
+                auditor.map(str::to_owned),
+                report_path.clone(),
+                markdown_path.clone(),
+            )
+        };
+
+        let report = audit(Some("Alice")).await.expect("audit passes");
+        assert!(report.passed);
+        let pool = report.pools.first().expect("sapling audit");
+        assert_eq!(pool.nullifier_count, 2);
+        assert_eq!(pool.duplicates_in_file, 0);
+        assert!(pool.note_commitment_root.is_none());
+        let markdown = tokio::fs::read_to_string(&markdown_path)
+            .await
+            .expect("markdown report");
+        let json = tokio::fs::read(&report_path).await.expect("json report");
+        assert!(markdown.contains(&hex::encode(Sha256::digest(&json))));
+        assert!(markdown.contains("Audited by: Alice"));
+
+        write_config(&config_path, [7_u8; 32]).await;
+        let err = audit(None).await.expect_err("tampered gap root");
+        let failed = err.downcast_ref::<AuditFailed>().expect("audit failure");
+        let pool = failed.report.pools.first().expect("sapling audit");
+        assert!(!pool.nullifier_gap_root.matches);
+        assert!(report_path.exists());
+    }
+
+    #[tokio::test]
+    async fn offline_audit_rebuilds_transparent_balances() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let config_path = dir.path().join("config.json");
+        let snapshot_path = dir.path().join("snapshot-sapling.bin");
+        let balances_path = dir.path().join("transparent-balances.csv");
+        let nullifiers = SanitiseNullifiers::new(vec![Nullifier::from([3_u8; 32])]);
+        let root =
+            gap_root(Pool::Sapling, OrchardGapTreeHash::default(), &nullifiers).expect("gap root");
+        write_snapshot(&snapshot_path, &nullifiers, root).await;
+        let address = |byte: u8| {
+            TransparentAddress::PublicKeyHash([byte; PUBKEY_HASH_SIZE])
+                .to_zcash_address(NetworkType::Test)
+                .encode()
+        };
+        let listing = format!("{},100\n{},20\n", address(1), address(2));
+        tokio::fs::write(&balances_path, &listing)
+            .await
+            .expect("write balances");
+        let tree = read_transparent_balances(AirdropNetwork::Testnet, &balances_path)
+            .await
+            .expect("balance tree");
+        let snapshot = TransparentSnapshot {
+            balance_root: tree.root(),
+            address_count: 2,
+            total_balance: 120,
+            target_id: "ZAIRTEST:T".to_owned(),
+        };
+
+        let audit = |balances: Option<PathBuf>| {
+            audit_airdrop_configuration(
+                config_path.clone(),
+                snapshot_path.clone(),
+                dir.path().join("snapshot-orchard.bin"),
+                None,
+                None,
+                balances,
+                None,
+                true,
+                false,
+                None,
+                dir.path().join("audit.json"),
+                dir.path().join("audit.md"),
+            )
+        };
+
+        write_config_with(&config_path, root, Some(snapshot.clone())).await;
+        let report = audit(Some(balances_path.clone()))
+            .await
+            .expect("audit passes");
+        let transparent = report.transparent.expect("transparent audit");
+        assert!(transparent.passed());
+        assert_eq!(transparent.total_balance, CountCheck::new(120, 120));
+        assert!(
+            audit(None).await.is_err(),
+            "a transparent snapshot needs its listing"
+        );
+
+        write_config_with(
+            &config_path,
+            root,
+            Some(TransparentSnapshot {
+                total_balance: 121,
+                ..snapshot
+            }),
+        )
+        .await;
+        let err = audit(Some(balances_path.clone()))
+            .await
+            .expect_err("tampered total balance");
+        let failed = err.downcast_ref::<AuditFailed>().expect("audit failure");
+        let transparent = failed.report.transparent.as_ref().expect("transparent");
+        assert!(transparent.balance_root.matches);
+        assert!(!transparent.total_balance.matches);
+
+        write_config(&config_path, root).await;
+        assert!(
+            audit(Some(balances_path)).await.is_err(),
+            "a listing without a transparent snapshot is refused"
+        );
+    }
+}
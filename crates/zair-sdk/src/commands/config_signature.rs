@@ -0,0 +1,280 @@
+//! Organizer signatures over airdrop configurations.
+//!
+//! A configuration is a plain JSON file, so nothing stops a phishing site from serving one
+//! with its own roots or `target_id`s. The organizer signs the exact bytes of the configuration
+//! file with an Ed25519 key, publishes the detached signature next to it and the public key
+//! through its own channels; claimants and verifiers pin that key and refuse configurations it
+//! did not sign.
+//!
+//! The signature covers the file bytes rather than a re-serialization of the parsed
+//! configuration, so it does not depend on how this build encodes the configuration: a field
+//! this build does not know, a reordered key or an escaped character all break it.
+
+#[cfg(feature = "native")]
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::{Signature, Signer as _, SigningKey, VerifyingKey};
+use eyre::Context as _;
+#[cfg(feature = "native")]
+use tracing::info;
+use zair_core::schema::config::{AirdropConfiguration, OrganizerSignature};
+#[cfg(feature = "native")]
+use zeroize::Zeroizing;
+
+/// Domain separator prefixed to the signed configuration file bytes.
+///
+/// Version 1 signed a JSON re-serialization of the parsed configuration; version 2 signs the
+/// file as it is.
+const CONFIGURATION_SIGNATURE_DOMAIN: &[u8] = b"ZAIR airdrop configuration v2\0";
+
+/// Why a configuration is not trusted.
+#[derive(Debug, thiserror::Error)]
+pub enum UntrustedConfiguration {
+    /// The configuration is signed by another key than the trusted one.
+    #[error(
+        "Airdrop configuration is signed by {signer}, not by the trusted organizer key {trusted}"
+    )]
+    UnknownSigner {
+        /// Hex public key of the signer.
+        signer: String,
+        /// Hex public key of the trusted organizer.
+        trusted: String,
+    },
+    /// The signature does not match the configuration.
+    #[error("Invalid organizer signature over the airdrop configuration")]
+    BadSignature,
+}
+
+/// Bytes signed by the organizer: the domain separator followed by the configuration file.
+fn signing_message(config_bytes: &[u8]) -> Vec<u8> {
+    [CONFIGURATION_SIGNATURE_DOMAIN, config_bytes].concat()
+}
+
+/// Ed25519 public key of an organizer secret key.
+#[must_use]
+pub fn organizer_public_key(secret_key: &[u8; 32]) -> [u8; 32] {
+    SigningKey::from_bytes(secret_key)
+        .verifying_key()
+        .to_bytes()
+}
+
+/// Sign the configuration file bytes `config_bytes` with the organizer's Ed25519 `secret_key`.
+#[must_use]
+pub fn sign_configuration(config_bytes: &[u8], secret_key: &[u8; 32]) -> OrganizerSignature {
+    let signing_key = SigningKey::from_bytes(secret_key);
+    let signature = signing_key.sign(&signing_message(config_bytes));
+    OrganizerSignature {
+        public_key: signing_key.verifying_key().to_bytes(),
+        signature: signature.to_bytes(),
+    }
+}
+
+/// Check that `signature` is a signature of the configuration file bytes `config_bytes` by the
+/// `trusted_key` organizer.
+///
+/// # Errors
+/// Returns [`UntrustedConfiguration`] if the signature is made by another key or invalid, and
+/// an error if `trusted_key` is not an Ed25519 public key.
+pub fn verify_configuration(
+    config_bytes: &[u8],
+    signature: &OrganizerSignature,
+    trusted_key: &[u8; 32],
+) -> eyre::Result<()> {
+    let trusted = VerifyingKey::from_bytes(trusted_key)
+        .map_err(|_| eyre::eyre!("Trusted organizer key is not an Ed25519 public key"))?;
+    if signature.public_key != *trusted_key {
+        return Err(UntrustedConfiguration::UnknownSigner {
+            signer: hex::encode(signature.public_key),
+            trusted: hex::encode(trusted_key),
+        }
+        .into());
+    }
+    trusted
+        .verify_strict(
+            &signing_message(config_bytes),
+            &Signature::from_bytes(&signature.signature),
+        )
+        .map_err(|_| UntrustedConfiguration::BadSignature)?;
+    Ok(())
+}
+
+/// Read an organizer secret key file holding 32 bytes as hex.
+#[cfg(feature = "native")]
+async fn read_organizer_key(path: &Path) -> eyre::Result<Zeroizing<[u8; 32]>> {
+    let text = Zeroizing::new(
+        tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read organizer key {}", path.display()))?,
+    );
+    let mut key = Zeroizing::new([0_u8; 32]);
+    hex::decode_to_slice(text.trim(), key.as_mut_slice())
+        .context("Organizer key must be 32 bytes (64 hex characters)")?;
+    Ok(key)
+}
+
+/// Sign an airdrop configuration file with the organizer key in `signing_key_file` and write
+/// the detached signature to `signature_out`.
+///
+/// The configuration file is signed as it is and left untouched; any later change to it, even
+/// reformatting, invalidates the signature.
+///
+/// # Errors
+/// Returns an error if a file cannot be read or written, the configuration does not parse or
+/// its targets repeat a name or target id, or the key file is malformed.
+#[cfg(feature = "native")]
+pub async fn sign_airdrop_configuration(
+    airdrop_configuration_file: PathBuf,
+    signing_key_file: PathBuf,
+    signature_out: PathBuf,
+) -> eyre::Result<OrganizerSignature> {
+    let config_bytes = tokio::fs::read(&airdrop_configuration_file)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to read airdrop configuration {}",
+                airdrop_configuration_file.display()
+            )
+        })?;
+    let invalid = || {
+        format!(
+            "Invalid airdrop configuration {}",
+            airdrop_configuration_file.display()
+        )
+    };
+    let config: AirdropConfiguration =
+        serde_json::from_slice(&config_bytes).with_context(invalid)?;
+    config.validate_targets().with_context(invalid)?;
+    let secret_key = read_organizer_key(&signing_key_file).await?;
+    let signature = sign_configuration(&config_bytes, &secret_key);
+
+    tokio::fs::write(&signature_out, serde_json::to_string_pretty(&signature)?).await?;
+    info!(
+        file = ?signature_out,
+        public_key = %hex::encode(signature.public_key),
+        "Signed airdrop configuration"
+    );
+    Ok(signature)
+}
+
+/// Organizer an airdrop configuration must be signed by, with the detached signature to check.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustedOrganizer {
+    /// Ed25519 public key of the organizer.
+    pub public_key: [u8; 32],
+    /// Detached signature file written by `config sign`.
+    pub signature_file: PathBuf,
+}
+
+/// Parse an organizer public key given as 64 hex characters.
+///
+/// # Errors
+/// Returns an error if `hex` is not 32 bytes of hex.
+pub fn parse_organizer_public_key(hex: &str) -> eyre::Result<[u8; 32]> {
+    let mut key = [0_u8; 32];
+    hex::decode_to_slice(hex.trim(), &mut key).map_err(|_| {
+        eyre::eyre!("Invalid organizer key: expected 32 bytes as 64 hex characters")
+    })?;
+    Ok(key)
+}
+
+/// Parse the configuration file bytes `config_bytes` once `signature` is checked to be a
+/// signature over them by the `trusted_key` organizer.
+///
+/// The configuration is parsed from the very bytes whose signature was checked.
+///
+/// # Errors
+/// Returns [`UntrustedConfiguration`] if the configuration is not signed by `trusted_key`,
+/// and an error if it does not parse or its targets repeat a name or target id.
+pub fn parse_signed_configuration(
+    config_bytes: &[u8],
+    signature: &OrganizerSignature,
+    trusted_key: &[u8; 32],
+) -> eyre::Result<AirdropConfiguration> {
+    verify_configuration(config_bytes, signature, trusted_key)?;
+    let config: AirdropConfiguration = serde_json::from_slice(config_bytes)
+        .context("Failed to parse airdrop configuration JSON")?;
+    config.validate_targets()?;
+    Ok(config)
+}
+
+/// Parse the bytes `config_bytes` of `airdrop_configuration_file` once the detached signature of
+/// `organizer` is checked over them.
+///
+/// # Errors
+/// Returns [`UntrustedConfiguration`] if the configuration is not signed by the organizer, and
+/// an error if the signature file cannot be read or either file does not parse.
+#[cfg(feature = "native")]
+pub(super) async fn parse_trusted_configuration(
+    airdrop_configuration_file: &Path,
+    config_bytes: &[u8],
+    organizer: &TrustedOrganizer,
+) -> eyre::Result<AirdropConfiguration> {
+    let signature_file = &organizer.signature_file;
+    let json = tokio::fs::read_to_string(signature_file)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to read configuration signature {}",
+                signature_file.display()
+            )
+        })?;
+    let signature: OrganizerSignature = serde_json::from_str(&json).with_context(|| {
+        format!(
+            "Invalid configuration signature {}",
+            signature_file.display()
+        )
+    })?;
+    let config = parse_signed_configuration(config_bytes, &signature, &organizer.public_key)
+        .with_context(|| {
+            format!(
+                "Refusing airdrop configuration {}",
+                airdrop_configuration_file.display()
+            )
+        })?;
+    info!(
+        organizer = %hex::encode(organizer.public_key),
+        "Airdrop configuration signed by the trusted organizer"
+    );
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: [u8; 32] = [7; 32];
+    const CONFIG: &[u8] = br#"{"network":"testnet","snapshot_height":42}"#;
+
+    #[test]
+    fn signature_verifies_over_the_file_bytes() {
+        let trusted = organizer_public_key(&SECRET);
+        let signature = sign_configuration(CONFIG, &SECRET);
+        verify_configuration(CONFIG, &signature, &trusted).expect("signature");
+    }
+
+    #[test]
+    fn rejects_tampered_reformatted_and_foreign_configurations() {
+        let trusted = organizer_public_key(&SECRET);
+        let signature = sign_configuration(CONFIG, &SECRET);
+
+        let foreign = sign_configuration(CONFIG, &[9; 32]);
+        let foreign = verify_configuration(CONFIG, &foreign, &trusted).expect_err("foreign");
+        assert!(matches!(
+            foreign.downcast_ref::<UntrustedConfiguration>(),
+            Some(UntrustedConfiguration::UnknownSigner { .. })
+        ));
+
+        for changed in [
+            &br#"{"network":"mainnet","snapshot_height":42}"#[..],
+            br#"{"snapshot_height":42,"network":"testnet"}"#,
+            br#"{"network":"testnet","snapshot_height":42,"unknown":1}"#,
+        ] {
+            let err = verify_configuration(changed, &signature, &trusted).expect_err("changed");
+            assert!(matches!(
+                err.downcast_ref::<UntrustedConfiguration>(),
+                Some(UntrustedConfiguration::BadSignature)
+            ));
+        }
+    }
+}
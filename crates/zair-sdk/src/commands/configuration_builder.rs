@@ -0,0 +1,485 @@
+//! In-memory airdrop configuration builder.
+//!
+//! An [`AirdropConfigurationBuilder`] takes the snapshot nullifiers and note commitment root of
+//! each shielded pool, builds their gap trees, and returns the [`AirdropConfiguration`] that
+//! commits to them together with the trees. [`super::build_airdrop_configuration`] is a thin
+//! wrapper that fetches the inputs from lightwalletd and writes the results to files.
+
+use eyre::{Context as _, ContextCompat as _, ensure};
+use tracing::{info, warn};
+use zair_core::base::{Pool, SAPLING_HIDING_NF_PERSONALIZATION, SanitiseNullifiers};
+use zair_core::container::ContainerOptions;
+use zair_core::schema::config::{
+    AirdropConfiguration, AirdropNetwork, OrchardGapTreeHash, OrchardSnapshot, SaplingSnapshot,
+    TransparentSnapshot, ValueCommitmentScheme,
+};
+use zair_core::transparent::BalanceTree;
+use zair_nonmembership::{
+    GapShards, MerklePathError, OrchardGapTree, OrchardPoseidonGapTree, SaplingGapTree,
+};
+
+use crate::progress::{Phase, Progress};
+
+/// Gap tree of one shielded pool.
+#[derive(Debug, Clone)]
+pub enum PoolGapTree {
+    /// Sapling gap tree.
+    Sapling(SaplingGapTree),
+    /// Orchard gap tree with Sinsemilla nodes.
+    Orchard(OrchardGapTree),
+    /// Orchard gap tree with Poseidon nodes.
+    OrchardPoseidon(OrchardPoseidonGapTree),
+}
+
+impl PoolGapTree {
+    /// Root committed to as the pool's `nullifier_gap_root`.
+    #[must_use]
+    pub const fn root_bytes(&self) -> [u8; 32] {
+        match self {
+            Self::Sapling(tree) => tree.root_bytes(),
+            Self::Orchard(tree) => tree.root_bytes(),
+            Self::OrchardPoseidon(tree) => tree.root_bytes(),
+        }
+    }
+
+    /// Serialize the tree as a versioned gap-tree container.
+    ///
+    /// # Errors
+    /// Returns an error if the tree cannot be encoded.
+    pub fn to_bytes(&self, options: &ContainerOptions) -> Result<Vec<u8>, MerklePathError> {
+        match self {
+            Self::Sapling(tree) => tree.to_bytes(options),
+            Self::Orchard(tree) => tree.to_bytes(options),
+            Self::OrchardPoseidon(tree) => tree.to_bytes(options),
+        }
+    }
+
+    /// Split the tree, built from `nullifiers`, into shards of `2^shard_depth` gaps.
+    ///
+    /// # Errors
+    /// Returns an error if `shard_depth` is out of range or `nullifiers` are not the ones the
+    /// tree was built from.
+    pub fn shards(
+        &self,
+        nullifiers: &SanitiseNullifiers,
+        shard_depth: u8,
+    ) -> Result<GapShards, MerklePathError> {
+        match self {
+            Self::Sapling(tree) => tree.shards(nullifiers, shard_depth),
+            Self::Orchard(tree) => tree.shards(nullifiers, shard_depth),
+            Self::OrchardPoseidon(tree) => tree.shards(nullifiers, shard_depth),
+        }
+    }
+}
+
+/// Snapshot nullifiers of one shielded pool and the gap tree built from them.
+#[derive(Debug)]
+pub struct PoolSnapshot {
+    /// Sorted, deduplicated nullifiers spent up to the snapshot height.
+    pub nullifiers: SanitiseNullifiers,
+    /// Gap tree over the nullifiers.
+    pub gap_tree: PoolGapTree,
+}
+
+/// Output of [`AirdropConfigurationBuilder::build`].
+#[derive(Debug)]
+pub struct AirdropSnapshot {
+    /// The airdrop configuration.
+    pub config: AirdropConfiguration,
+    /// Sapling snapshot, if the Sapling pool is included.
+    pub sapling: Option<PoolSnapshot>,
+    /// Orchard snapshot, if the Orchard pool is included.
+    pub orchard: Option<PoolSnapshot>,
+}
+
+/// Nullifiers and note commitment root of one shielded pool.
+struct PoolInput {
+    nullifiers: SanitiseNullifiers,
+    note_commitment_root: [u8; 32],
+}
+
+/// Builder for an airdrop configuration from in-memory snapshot data.
+///
+/// Only the pools given to [`Self::pool`] are included.
+pub struct AirdropConfigurationBuilder {
+    network: AirdropNetwork,
+    snapshot_height: u64,
+    sapling: Option<PoolInput>,
+    orchard: Option<PoolInput>,
+    sapling_target_id: Option<String>,
+    orchard_target_id: Option<String>,
+    transparent_target_id: Option<String>,
+    sapling_value_commitment_scheme: ValueCommitmentScheme,
+    orchard_value_commitment_scheme: ValueCommitmentScheme,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    transparent_balances: Option<BalanceTree>,
+    progress: Progress,
+}
+
+impl AirdropConfigurationBuilder {
+    /// Start a configuration for a snapshot of `network` at `snapshot_height`.
+    #[must_use]
+    pub fn new(network: AirdropNetwork, snapshot_height: u64) -> Self {
+        Self {
+            network,
+            snapshot_height,
+            sapling: None,
+            orchard: None,
+            sapling_target_id: None,
+            orchard_target_id: None,
+            transparent_target_id: None,
+            sapling_value_commitment_scheme: ValueCommitmentScheme::default(),
+            orchard_value_commitment_scheme: ValueCommitmentScheme::default(),
+            orchard_gap_tree_hash: OrchardGapTreeHash::default(),
+            transparent_balances: None,
+            progress: Progress::default(),
+        }
+    }
+
+    /// Include `pool` with the nullifiers spent up to the snapshot height and the note
+    /// commitment root at it.
+    ///
+    /// Ignored for [`Pool::Transparent`], see [`Self::transparent_balances`].
+    #[must_use]
+    pub fn pool(
+        mut self,
+        pool: Pool,
+        nullifiers: SanitiseNullifiers,
+        note_commitment_root: [u8; 32],
+    ) -> Self {
+        let input = PoolInput {
+            nullifiers,
+            note_commitment_root,
+        };
+        match pool {
+            Pool::Sapling => self.sapling = Some(input),
+            Pool::Orchard => self.orchard = Some(input),
+            Pool::Transparent => {}
+        }
+        self
+    }
+
+    /// Target id of `pool`, used for hiding nullifier derivation.
+    ///
+    /// The Sapling target id defaults to the personalization of the Sapling claim circuit, the
+    /// only one it can prove. The Orchard and transparent target ids are required for those
+    /// pools and must be at most 32 bytes.
+    #[must_use]
+    pub fn target_id(mut self, pool: Pool, target_id: impl Into<String>) -> Self {
+        let target_id = Some(target_id.into());
+        match pool {
+            Pool::Sapling => self.sapling_target_id = target_id,
+            Pool::Orchard => self.orchard_target_id = target_id,
+            Pool::Transparent => self.transparent_target_id = target_id,
+        }
+        self
+    }
+
+    /// Value commitment scheme of `pool`; ignored for [`Pool::Transparent`].
+    #[must_use]
+    pub const fn value_commitment_scheme(
+        mut self,
+        pool: Pool,
+        scheme: ValueCommitmentScheme,
+    ) -> Self {
+        match pool {
+            Pool::Sapling => self.sapling_value_commitment_scheme = scheme,
+            Pool::Orchard => self.orchard_value_commitment_scheme = scheme,
+            Pool::Transparent => {}
+        }
+        self
+    }
+
+    /// Hash of the Orchard gap-tree nodes; defaults to Sinsemilla.
+    #[must_use]
+    pub const fn orchard_gap_tree_hash(mut self, hash: OrchardGapTreeHash) -> Self {
+        self.orchard_gap_tree_hash = hash;
+        self
+    }
+
+    /// Transparent balances at the snapshot height, so their P2PKH holders can claim.
+    #[must_use]
+    pub fn transparent_balances(mut self, balances: BalanceTree) -> Self {
+        self.transparent_balances = Some(balances);
+        self
+    }
+
+    /// Progress sink and cancellation token for building the gap trees.
+    #[must_use]
+    pub fn progress(mut self, progress: Progress) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Build the gap trees and the configuration committing to them.
+    ///
+    /// # Errors
+    /// Returns an error if a target id is missing or invalid, no pool is included, a gap tree
+    /// cannot be built, or [`Cancelled`](crate::progress::Cancelled) if the progress is
+    /// cancelled.
+    pub async fn build(self) -> eyre::Result<AirdropSnapshot> {
+        let sapling_target_id = self.sapling_target_id.unwrap_or_else(|| {
+            String::from_utf8_lossy(&SAPLING_HIDING_NF_PERSONALIZATION).into_owned()
+        });
+        let orchard_target_id = match &self.orchard {
+            Some(_) => self
+                .orchard_target_id
+                .context("Orchard pool requires a target id")?,
+            None => String::new(),
+        };
+        validate_target_ids(
+            self.sapling.is_some(),
+            &sapling_target_id,
+            self.orchard.is_some(),
+            &orchard_target_id,
+        )?;
+        let transparent = match self.transparent_balances {
+            Some(tree) => {
+                let target_id = self
+                    .transparent_target_id
+                    .context("Transparent balances require a target id")?;
+                ensure!(
+                    target_id.len() <= 32,
+                    "Transparent target_id must be at most 32 bytes"
+                );
+                Some(TransparentSnapshot {
+                    balance_root: tree.root(),
+                    address_count: u64::try_from(tree.balances().len())?,
+                    total_balance: tree
+                        .total_balance()
+                        .context("Total transparent balance overflows u64")?,
+                    target_id,
+                })
+            }
+            None => None,
+        };
+        ensure!(
+            self.sapling.is_some() || self.orchard.is_some() || transparent.is_some(),
+            "Airdrop configuration requires at least one pool"
+        );
+
+        let sapling_handle = tokio::spawn(build_gap_tree(
+            Pool::Sapling,
+            self.sapling,
+            OrchardGapTreeHash::default(),
+            self.progress.clone(),
+        ));
+        let orchard_handle = tokio::spawn(build_gap_tree(
+            Pool::Orchard,
+            self.orchard,
+            self.orchard_gap_tree_hash,
+            self.progress.clone(),
+        ));
+        let (sapling, orchard) = tokio::try_join!(sapling_handle, orchard_handle)?;
+        let (sapling, orchard) = (sapling?, orchard?);
+        self.progress.ensure_not_cancelled()?;
+
+        let config = AirdropConfiguration::new(
+            self.network,
+            self.snapshot_height,
+            sapling
+                .as_ref()
+                .map(|(snapshot, note_commitment_root)| SaplingSnapshot {
+                    note_commitment_root: *note_commitment_root,
+                    nullifier_gap_root: snapshot.gap_tree.root_bytes(),
+                    target_id: sapling_target_id,
+                    value_commitment_scheme: self.sapling_value_commitment_scheme,
+                }),
+            orchard
+                .as_ref()
+                .map(|(snapshot, note_commitment_root)| OrchardSnapshot {
+                    note_commitment_root: *note_commitment_root,
+                    nullifier_gap_root: snapshot.gap_tree.root_bytes(),
+                    target_id: orchard_target_id,
+                    value_commitment_scheme: self.orchard_value_commitment_scheme,
+                    nullifier_gap_tree_hash: self.orchard_gap_tree_hash,
+                }),
+        );
+        let config = match transparent {
+            Some(transparent) => config.with_transparent(transparent),
+            None => config,
+        };
+
+        Ok(AirdropSnapshot {
+            config,
+            sapling: sapling.map(|(snapshot, _)| snapshot),
+            orchard: orchard.map(|(snapshot, _)| snapshot),
+        })
+    }
+}
+
+/// Check the target ids of the included shielded pools.
+pub(super) fn validate_target_ids(
+    sapling: bool,
+    sapling_target_id: &str,
+    orchard: bool,
+    orchard_target_id: &str,
+) -> eyre::Result<()> {
+    if sapling {
+        ensure!(
+            sapling_target_id.as_bytes() == SAPLING_HIDING_NF_PERSONALIZATION,
+            "Sapling target_id must be {:?}, the hiding nullifier personalization of the Sapling \
+             claim circuit",
+            String::from_utf8_lossy(&SAPLING_HIDING_NF_PERSONALIZATION)
+        );
+    }
+    if orchard {
+        ensure!(
+            orchard_target_id.len() <= 32,
+            "Orchard target_id must be at most 32 bytes"
+        );
+    }
+    Ok(())
+}
+
+/// Build the gap tree of `pool`, returned with the pool's note commitment root.
+async fn build_gap_tree(
+    pool: Pool,
+    input: Option<PoolInput>,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    progress: Progress,
+) -> eyre::Result<Option<(PoolSnapshot, [u8; 32])>> {
+    let Some(PoolInput {
+        nullifiers,
+        note_commitment_root,
+    }) = input
+    else {
+        return Ok(None);
+    };
+
+    if nullifiers.is_empty() {
+        warn!(pool = ?pool, "No nullifiers collected; using canonical empty-gap root");
+    } else {
+        info!(pool = ?pool, count = nullifiers.len(), "Collected nullifiers");
+    }
+
+    info!(
+        pool = ?pool,
+        hash = ?orchard_gap_tree_hash,
+        progress = "0%",
+        "Building non-membership tree"
+    );
+    progress.ensure_not_cancelled()?;
+    let snapshot = tokio::task::spawn_blocking(move || {
+        let on_progress = |current: usize, total: usize| {
+            progress.report_count(Phase::BuildGapTree, Some(pool), current, total);
+            if total == 0 {
+                return;
+            }
+            #[allow(
+                clippy::arithmetic_side_effects,
+                reason = "Tree build progress percentage uses saturating operations and is guarded against total=0"
+            )]
+            let pct = current.saturating_mul(100).saturating_div(total);
+            info!(pool = ?pool, progress = %format!("{pct}%"), "Building non-membership tree");
+        };
+        let gap_tree = match (pool, orchard_gap_tree_hash) {
+            (Pool::Sapling, _) => PoolGapTree::Sapling(
+                SaplingGapTree::from_nullifiers_with_progress(&nullifiers, on_progress)?,
+            ),
+            (Pool::Orchard, OrchardGapTreeHash::Sinsemilla) => PoolGapTree::Orchard(
+                OrchardGapTree::from_nullifiers_with_progress(&nullifiers, on_progress)?,
+            ),
+            (Pool::Orchard, OrchardGapTreeHash::Poseidon) => PoolGapTree::OrchardPoseidon(
+                OrchardPoseidonGapTree::from_nullifiers_with_progress(&nullifiers, on_progress)?,
+            ),
+            (Pool::Transparent, _) => return Err(MerklePathError::NoGapTree(pool)),
+        };
+        Ok::<_, MerklePathError>(PoolSnapshot {
+            nullifiers,
+            gap_tree,
+        })
+    })
+    .await?
+    .with_context(|| format!("Failed to build the {pool:?} gap tree"))?;
+
+    Ok(Some((snapshot, note_commitment_root)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn empty_pools_use_the_canonical_roots() {
+        let snapshot = AirdropConfigurationBuilder::new(AirdropNetwork::Testnet, 200)
+            .pool(Pool::Sapling, SanitiseNullifiers::new(vec![]), [1_u8; 32])
+            .pool(Pool::Orchard, SanitiseNullifiers::new(vec![]), [2_u8; 32])
+            .target_id(Pool::Orchard, "ZAIRTEST:O")
+            .build()
+            .await
+            .expect("build configuration");
+
+        let sapling_root = SaplingGapTree::from_nullifiers(&SanitiseNullifiers::new(vec![]))
+            .expect("empty nullifiers should produce canonical tree")
+            .root_bytes();
+        let sapling = snapshot.config.sapling.expect("sapling pool");
+        assert_eq!(sapling.nullifier_gap_root, sapling_root);
+        assert_eq!(sapling.note_commitment_root, [1_u8; 32]);
+        assert_eq!(
+            sapling.target_id.as_bytes(),
+            SAPLING_HIDING_NF_PERSONALIZATION
+        );
+
+        let orchard = snapshot.config.orchard.expect("orchard pool");
+        assert_eq!(orchard.note_commitment_root, [2_u8; 32]);
+        assert_eq!(
+            Some(orchard.nullifier_gap_root),
+            snapshot
+                .orchard
+                .map(|orchard| orchard.gap_tree.root_bytes())
+        );
+    }
+
+    #[tokio::test]
+    async fn orchard_gap_tree_hash_selects_the_tree() {
+        let build = |hash| {
+            AirdropConfigurationBuilder::new(AirdropNetwork::Testnet, 200)
+                .pool(Pool::Orchard, SanitiseNullifiers::new(vec![]), [0_u8; 32])
+                .target_id(Pool::Orchard, "ZAIRTEST:O")
+                .orchard_gap_tree_hash(hash)
+                .build()
+        };
+        let poseidon = build(OrchardGapTreeHash::Poseidon)
+            .await
+            .expect("build poseidon configuration");
+        let sinsemilla = build(OrchardGapTreeHash::Sinsemilla)
+            .await
+            .expect("build sinsemilla configuration");
+
+        let poseidon = poseidon.orchard.expect("orchard snapshot");
+        assert!(matches!(poseidon.gap_tree, PoolGapTree::OrchardPoseidon(_)));
+        assert_ne!(
+            Some(poseidon.gap_tree.root_bytes()),
+            sinsemilla
+                .orchard
+                .map(|orchard| orchard.gap_tree.root_bytes())
+        );
+        assert!(sinsemilla.sapling.is_none());
+    }
+
+    #[tokio::test]
+    async fn orchard_requires_a_target_id() {
+        let err = AirdropConfigurationBuilder::new(AirdropNetwork::Testnet, 200)
+            .pool(Pool::Orchard, SanitiseNullifiers::new(vec![]), [0_u8; 32])
+            .build()
+            .await
+            .expect_err("orchard without a target id must fail");
+        assert!(err.to_string().contains("target id"), "{err:#}");
+    }
+
+    #[test]
+    fn orchard_allows_target_id_up_to_32_bytes() {
+        let target = "a".repeat(32);
+        validate_target_ids(false, "ZAIRTEST", true, &target)
+            .expect("Orchard target_id should be allowed up to 32 bytes");
+    }
+
+    #[test]
+    fn sapling_target_id_must_be_the_circuit_personalization() {
+        assert!(
+            validate_target_ids(true, "SEASON02", true, "ZAIRTEST:O").is_err(),
+            "the Sapling circuit only proves the ZAIRTEST personalization"
+        );
+    }
+}
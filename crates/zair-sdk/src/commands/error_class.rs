@@ -0,0 +1,109 @@
+//! Failure classes of command errors and their process exit codes.
+
+use serde::Serialize;
+use zair_core::schema::config::ClaimWindowError;
+use zair_scan::light_walletd::LightWalletdError;
+
+use super::claim_message::InvalidClaimMessage;
+use super::claim_registry::AlreadyClaimed;
+use super::config_audit::AuditFailed;
+use super::config_signature::UntrustedConfiguration;
+use super::verification_report::VerificationFailed;
+use crate::progress::Cancelled;
+
+/// Broad class of a command failure, for scripts that branch on the cause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// Any failure not covered by a more specific class.
+    Failure,
+    /// An input file is missing, unreadable or malformed, or a claim message names an invalid
+    /// recipient.
+    Input,
+    /// lightwalletd, a witness server or a shard host could not be reached or failed.
+    Network,
+    /// At least one claim proof or signature, snapshot audit check or the organizer signature
+    /// of the configuration is invalid, or the claim window is not open.
+    VerificationFailed,
+    /// At least one claim is already recorded in the claim registry.
+    AlreadyClaimed,
+    /// The operation was cancelled.
+    Cancelled,
+}
+
+impl ErrorClass {
+    /// Classify `err` by the typed errors in its chain.
+    ///
+    /// Verdict classes win over transport classes, and transport classes over input classes,
+    /// so a network error wrapping an I/O error is still a network error.
+    #[must_use]
+    pub fn of(err: &eyre::Report) -> Self {
+        if chain_has::<Cancelled>(err) {
+            Self::Cancelled
+        } else if chain_has::<VerificationFailed>(err) ||
+            chain_has::<AuditFailed>(err) ||
+            chain_has::<UntrustedConfiguration>(err) ||
+            chain_has::<ClaimWindowError>(err)
+        {
+            Self::VerificationFailed
+        } else if chain_has::<AlreadyClaimed>(err) {
+            Self::AlreadyClaimed
+        } else if chain_has::<LightWalletdError>(err) || chain_has::<ureq::Error>(err) {
+            Self::Network
+        } else if chain_has::<std::io::Error>(err) ||
+            chain_has::<serde_json::Error>(err) ||
+            chain_has::<InvalidClaimMessage>(err)
+        {
+            Self::Input
+        } else {
+            Self::Failure
+        }
+    }
+
+    /// Process exit code of the class. `2` is left to command-line usage errors.
+    #[must_use]
+    pub const fn exit_code(self) -> i32 {
+        match self {
+            Self::Failure => 1,
+            Self::Input => 3,
+            Self::Network => 4,
+            Self::VerificationFailed => 5,
+            Self::AlreadyClaimed => 6,
+            Self::Cancelled => 130,
+        }
+    }
+}
+
+/// Whether any error in the chain of `err` is a `T`.
+fn chain_has<T: std::error::Error + 'static>(err: &eyre::Report) -> bool {
+    err.chain().any(<dyn std::error::Error>::is::<T>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::VerificationReport;
+
+    #[test]
+    fn classifies_typed_errors_through_context() {
+        let io = eyre::Report::new(std::io::Error::other("missing")).wrap_err("Failed to read");
+        assert_eq!(ErrorClass::of(&io), ErrorClass::Input);
+
+        let failed = eyre::Report::new(VerificationFailed {
+            report: VerificationReport::default(),
+            subject: "proofs",
+        })
+        .wrap_err("verify run");
+        assert_eq!(ErrorClass::of(&failed), ErrorClass::VerificationFailed);
+        assert_eq!(ErrorClass::of(&failed).exit_code(), 5);
+
+        assert_eq!(
+            ErrorClass::of(&eyre::eyre!("bad input")),
+            ErrorClass::Failure
+        );
+        assert_eq!(
+            ErrorClass::of(&eyre::Report::new(Cancelled)),
+            ErrorClass::Cancelled
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Value excluded from an airdrop snapshot.
+//!
+//! Compact blocks carry no Sprout data and there is no Sprout claim circuit, so Sprout notes
+//! can never claim. The report states how much value each unclaimable pool held at the
+//! snapshot height, from the `valuePools` of a full node's `getblock <height> 1` output, so
+//! organizers can publish what was left out and why.
+
+use std::fmt;
+use std::path::Path;
+
+use eyre::{Context as _, ContextCompat as _, ensure};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use zair_core::schema::config::AirdropNetwork;
+
+use crate::common::PoolSelection;
+
+/// Zcash value pool tracked by full nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValuePool {
+    /// Transparent UTXOs.
+    Transparent,
+    /// Sprout notes.
+    Sprout,
+    /// Sapling notes.
+    Sapling,
+    /// Orchard notes.
+    Orchard,
+}
+
+impl ValuePool {
+    const ALL: [Self; 4] = [
+        Self::Transparent,
+        Self::Sprout,
+        Self::Sapling,
+        Self::Orchard,
+    ];
+
+    const fn node_id(self) -> &'static str {
+        match self {
+            Self::Transparent => "transparent",
+            Self::Sprout => "sprout",
+            Self::Sapling => "sapling",
+            Self::Orchard => "orchard",
+        }
+    }
+}
+
+impl fmt::Display for ValuePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.node_id())
+    }
+}
+
+/// Why a pool's value cannot be claimed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusionReason {
+    /// There is no claim path for the pool; holders had to migrate their funds to Sapling or
+    /// Orchard before the snapshot height.
+    NoClaimPath,
+    /// The pool was left out of the configuration with `--pool`.
+    NotSelected,
+    /// No transparent balance listing was committed to.
+    NoBalanceSnapshot,
+}
+
+/// Value of one pool at the snapshot height and whether it can claim.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PoolCoverage {
+    /// Value pool.
+    pub pool: ValuePool,
+    /// Pool value in zatoshis.
+    pub value_zat: u64,
+    /// Why the pool cannot claim, or `None` if it can.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excluded: Option<ExclusionReason>,
+}
+
+/// Per-pool coverage of an airdrop snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExclusionReport {
+    /// Network of the snapshot.
+    pub network: AirdropNetwork,
+    /// Snapshot height.
+    pub snapshot_height: u64,
+    /// Hash of the snapshot block, as reported by the full node.
+    pub block_hash: String,
+    /// Value and coverage of every pool.
+    pub pools: Vec<PoolCoverage>,
+    /// Total value of the excluded pools in zatoshis.
+    pub excluded_value_zat: u64,
+}
+
+impl ExclusionReport {
+    /// Value of `pool` in the report.
+    #[must_use]
+    pub fn coverage(&self, pool: ValuePool) -> Option<&PoolCoverage> {
+        self.pools.iter().find(|coverage| coverage.pool == pool)
+    }
+}
+
+#[derive(Deserialize)]
+struct NodeBlock {
+    height: u64,
+    hash: String,
+    #[serde(rename = "valuePools")]
+    value_pools: Vec<NodeValuePool>,
+}
+
+#[derive(Deserialize)]
+struct NodeValuePool {
+    id: String,
+    #[serde(default)]
+    monitored: bool,
+    #[serde(rename = "chainValueZat")]
+    chain_value_zat: Option<u64>,
+}
+
+/// Build the exclusion report of a snapshot from a full node's `getblock <height> 1` JSON.
+///
+/// Sprout is always excluded. Sapling and Orchard are excluded unless selected by `pool`, and
+/// transparent value unless `transparent` balances are committed to.
+///
+/// # Errors
+/// Returns an error if the JSON is not a verbose block, the block is not at
+/// `snapshot_height`, or the node does not track the value of every pool.
+pub fn exclusion_report(
+    network: AirdropNetwork,
+    snapshot_height: u64,
+    block_json: &str,
+    pool: PoolSelection,
+    transparent: bool,
+) -> eyre::Result<ExclusionReport> {
+    let block: NodeBlock = serde_json::from_str(block_json)
+        .context("Expected the output of `getblock <height> 1` with `valuePools`")?;
+    ensure!(
+        block.height == snapshot_height,
+        "Block is at height {}, not at the snapshot height {snapshot_height}",
+        block.height
+    );
+
+    let mut pools = Vec::with_capacity(ValuePool::ALL.len());
+    let mut excluded_value_zat = 0_u64;
+    for value_pool in ValuePool::ALL {
+        let entry = block
+            .value_pools
+            .iter()
+            .find(|entry| entry.id == value_pool.node_id())
+            .with_context(|| format!("Block has no {value_pool} value pool"))?;
+        let value_zat = entry
+            .chain_value_zat
+            .filter(|_| entry.monitored)
+            .with_context(|| format!("Full node does not track the {value_pool} pool value"))?;
+        let excluded = match value_pool {
+            ValuePool::Sprout => Some(ExclusionReason::NoClaimPath),
+            ValuePool::Sapling if !pool.includes_sapling() => Some(ExclusionReason::NotSelected),
+            ValuePool::Orchard if !pool.includes_orchard() => Some(ExclusionReason::NotSelected),
+            ValuePool::Transparent if !transparent => Some(ExclusionReason::NoBalanceSnapshot),
+            ValuePool::Transparent | ValuePool::Sapling | ValuePool::Orchard => None,
+        };
+        if excluded.is_some() {
+            excluded_value_zat = excluded_value_zat
+                .checked_add(value_zat)
+                .context("Excluded value overflows u64")?;
+        }
+        pools.push(PoolCoverage {
+            pool: value_pool,
+            value_zat,
+            excluded,
+        });
+    }
+
+    Ok(ExclusionReport {
+        network,
+        snapshot_height,
+        block_hash: block.hash,
+        pools,
+        excluded_value_zat,
+    })
+}
+
+/// Read a full node block file and build its exclusion report.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or [`exclusion_report`] fails.
+pub async fn read_exclusion_report(
+    network: AirdropNetwork,
+    snapshot_height: u64,
+    block_file: &Path,
+    pool: PoolSelection,
+    transparent: bool,
+) -> eyre::Result<ExclusionReport> {
+    let block_json = tokio::fs::read_to_string(block_file)
+        .await
+        .with_context(|| format!("Failed to read value pools {}", block_file.display()))?;
+    exclusion_report(network, snapshot_height, &block_json, pool, transparent)
+        .with_context(|| format!("Invalid value pools {}", block_file.display()))
+}
+
+/// Log the excluded pools of a report.
+pub fn log_exclusions(report: &ExclusionReport) {
+    for coverage in &report.pools {
+        if let Some(reason) = coverage.excluded {
+            warn!(
+                pool = %coverage.pool,
+                value_zat = coverage.value_zat,
+                ?reason,
+                "Pool value excluded from the airdrop"
+            );
+        }
+    }
+    info!(
+        excluded_value_zat = report.excluded_value_zat,
+        "Built exclusion report"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(height: u64, sprout: &str) -> String {
+        format!(
+            r#"{{
+                "hash": "00ab",
+                "height": {height},
+                "valuePools": [
+                    {{ "id": "transparent", "monitored": true, "chainValueZat": 1000 }},
+                    {{ "id": "sprout", "monitored": true, {sprout} }},
+                    {{ "id": "sapling", "monitored": true, "chainValueZat": 300 }},
+                    {{ "id": "orchard", "monitored": true, "chainValueZat": 40 }},
+                    {{ "id": "lockbox", "monitored": true, "chainValueZat": 5 }}
+                ]
+            }}"#
+        )
+    }
+
+    #[test]
+    fn sprout_and_unselected_pools_are_excluded() {
+        let report = exclusion_report(
+            AirdropNetwork::Testnet,
+            10,
+            &block(10, r#""chainValueZat": 20"#),
+            PoolSelection::Orchard,
+            false,
+        )
+        .expect("report");
+
+        assert_eq!(report.block_hash, "00ab");
+        assert_eq!(
+            report
+                .coverage(ValuePool::Sprout)
+                .map(|c| (c.value_zat, c.excluded)),
+            Some((20, Some(ExclusionReason::NoClaimPath)))
+        );
+        assert_eq!(
+            report.coverage(ValuePool::Sapling).and_then(|c| c.excluded),
+            Some(ExclusionReason::NotSelected)
+        );
+        assert_eq!(
+            report.coverage(ValuePool::Orchard).and_then(|c| c.excluded),
+            None
+        );
+        assert_eq!(report.excluded_value_zat, 1_320);
+    }
+
+    #[test]
+    fn rejects_other_heights_and_untracked_pools() {
+        let wrong_height = exclusion_report(
+            AirdropNetwork::Testnet,
+            10,
+            &block(11, r#""chainValueZat": 20"#),
+            PoolSelection::Both,
+            true,
+        );
+        assert!(wrong_height.is_err());
+
+        let untracked = exclusion_report(
+            AirdropNetwork::Testnet,
+            10,
+            &block(10, r#""chainValue": 0"#),
+            PoolSelection::Both,
+            true,
+        )
+        .expect_err("sprout value is not tracked");
+        assert!(format!("{untracked:#}").contains("sprout"), "{untracked:#}");
+    }
+}
@@ -0,0 +1,293 @@
+//! Publishing and fetching sharded gap trees.
+//!
+//! `config build` can split each gap tree into fixed-size shards under
+//! `<dir>/<pool>/shard-NNNNNN.bin` plus a `<dir>/<pool>/index.bin` listing every shard's
+//! first bound and root. Claimants fetch the index and only the shards covering their
+//! nullifiers, from a URL or a local directory, and splice full witnesses from them. Every
+//! witness is still checked against `nullifier_gap_root` from the airdrop configuration.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use eyre::{Context as _, ensure};
+use tracing::info;
+use zair_core::base::{Nullifier, Pool, SanitiseNullifiers};
+use zair_core::container::ContainerOptions;
+use zair_core::schema::config::OrchardGapTreeHash;
+use zair_core::schema::witness::{NullifierWitness, WitnessResponse};
+use zair_nonmembership::{
+    GapShard, GapShardIndex, GapShards, MerklePathError, OrchardGapTree, OrchardPoseidonGapTree,
+    SaplingGapTree,
+};
+
+use super::airdrop_claim::LoadedPoolData;
+use super::witness_server::verify_remote_witnesses;
+
+/// File name of a pool's shard index.
+pub const GAP_SHARD_INDEX_FILE: &str = "index.bin";
+
+/// Upper bound on a downloaded shard or index file.
+const MAX_SHARD_FILE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Time allowed to connect to a shard host and receive the response headers.
+const SHARD_RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Time allowed for a whole shard or index download, generous enough for large shards on
+/// slow links while still ending a stalled transfer.
+const SHARD_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+const fn pool_dir(pool: Pool) -> &'static str {
+    match pool {
+        Pool::Sapling => "sapling",
+        Pool::Orchard => "orchard",
+        Pool::Transparent => "transparent",
+    }
+}
+
+fn shard_file_name(shard_index: u64) -> String {
+    format!("shard-{shard_index:06}.bin")
+}
+
+/// Encode the shard index and shards as `(file name, bytes)` pairs.
+pub(super) fn encode_gap_shards(
+    shards: &GapShards,
+    options: &ContainerOptions,
+) -> Result<Vec<(String, Vec<u8>)>, MerklePathError> {
+    let mut files = Vec::with_capacity(shards.shards.len().saturating_add(1));
+    files.push((
+        GAP_SHARD_INDEX_FILE.to_string(),
+        shards.index.to_bytes(options)?,
+    ));
+    for shard in &shards.shards {
+        files.push((
+            shard_file_name(shard.shard_index()),
+            shard.to_bytes(options)?,
+        ));
+    }
+    Ok(files)
+}
+
+/// Write encoded shard files to `<dir>/<pool>/`.
+pub(super) async fn write_gap_shards(
+    dir: &Path,
+    pool: Pool,
+    files: Vec<(String, Vec<u8>)>,
+) -> eyre::Result<()> {
+    let pool_dir = dir.join(pool_dir(pool));
+    tokio::fs::create_dir_all(&pool_dir)
+        .await
+        .with_context(|| format!("Failed to create {}", pool_dir.display()))?;
+    let count = files.len().saturating_sub(1);
+    for (name, bytes) in files {
+        tokio::fs::write(pool_dir.join(name), bytes).await?;
+    }
+    info!(%pool, dir = %pool_dir.display(), shards = count, "Saved gap-tree shards");
+    Ok(())
+}
+
+/// Read `<source>/<pool>/<name>` from an `http(s)://` base URL or a local directory.
+async fn fetch_shard_file(source: &str, pool: Pool, name: &str) -> eyre::Result<Vec<u8>> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let url = format!("{}/{}/{name}", source.trim_end_matches('/'), pool_dir(pool));
+        let bytes = tokio::task::spawn_blocking({
+            let url = url.clone();
+            move || {
+                ureq::get(&url)
+                    .config()
+                    .timeout_connect(Some(SHARD_RESPONSE_TIMEOUT))
+                    .timeout_recv_response(Some(SHARD_RESPONSE_TIMEOUT))
+                    .timeout_global(Some(SHARD_DOWNLOAD_TIMEOUT))
+                    .build()
+                    .call()?
+                    .body_mut()
+                    .with_config()
+                    .limit(MAX_SHARD_FILE_BYTES)
+                    .read_to_vec()
+            }
+        })
+        .await?
+        .with_context(|| format!("Failed to fetch {url}"))?;
+        Ok(bytes)
+    } else {
+        let path = Path::new(source).join(pool_dir(pool)).join(name);
+        tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+}
+
+/// Fetch the shards covering the user's nullifiers and splice verified witnesses from them.
+///
+/// Nullifiers that are a shard bound are spent in the snapshot and get no witness.
+pub(super) async fn fetch_sharded_pool_data(
+    source: &str,
+    pool: Pool,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    expected_root: [u8; 32],
+    options: &ContainerOptions,
+    user_nullifiers: &SanitiseNullifiers,
+) -> eyre::Result<LoadedPoolData> {
+    let index = GapShardIndex::from_bytes(
+        &fetch_shard_file(source, pool, GAP_SHARD_INDEX_FILE).await?,
+        pool,
+    )
+    .with_context(|| format!("Invalid {pool} gap-tree shard index"))?;
+    if let Some(header) = index.header() {
+        header
+            .ensure_snapshot(options)
+            .with_context(|| format!("{pool} gap-tree shard index is for another snapshot"))?;
+    }
+    ensure!(
+        index.root_bytes() == expected_root,
+        "{pool} gap-tree shard index root does not match the airdrop configuration"
+    );
+
+    let mut by_shard: BTreeMap<u64, Vec<Nullifier>> = BTreeMap::new();
+    for nullifier in user_nullifiers.iter() {
+        if let Some(shard_index) = index.shard_for(nullifier) {
+            by_shard.entry(shard_index).or_default().push(*nullifier);
+        }
+    }
+    info!(
+        %pool,
+        shards = by_shard.len(),
+        total = index.shard_count(),
+        "Fetching gap-tree shards"
+    );
+
+    let mut shards = Vec::with_capacity(by_shard.len());
+    for (shard_index, nullifiers) in by_shard {
+        let shard = GapShard::from_bytes(
+            &fetch_shard_file(source, pool, &shard_file_name(shard_index)).await?,
+            pool,
+        )
+        .with_context(|| format!("Invalid {pool} gap-tree shard {shard_index}"))?;
+        ensure!(
+            shard.shard_index() == shard_index,
+            "{pool} gap-tree shard file {shard_index} holds shard {}",
+            shard.shard_index()
+        );
+        shards.push((shard, nullifiers));
+    }
+
+    let shard_witnesses = match (pool, orchard_gap_tree_hash) {
+        (Pool::Sapling, _) => SaplingGapTree::shard_witnesses,
+        (Pool::Orchard, OrchardGapTreeHash::Sinsemilla) => OrchardGapTree::shard_witnesses,
+        (Pool::Orchard, OrchardGapTreeHash::Poseidon) => OrchardPoseidonGapTree::shard_witnesses,
+        (Pool::Transparent, _) => return Err(MerklePathError::NoGapTree(pool).into()),
+    };
+    let (witnesses, spent) = tokio::task::spawn_blocking(move || {
+        let mut witnesses = Vec::new();
+        let mut spent = Vec::new();
+        for (shard, nullifiers) in shards {
+            let mut unspent_positions = Vec::new();
+            let mut spent_positions = Vec::new();
+            for nullifier in nullifiers {
+                match shard.locate(nullifier)? {
+                    Some(position) => unspent_positions.push(position),
+                    None => spent_positions.extend(shard.locate_spent(nullifier)?),
+                }
+            }
+            for (positions, out) in [
+                (unspent_positions, &mut witnesses),
+                (spent_positions, &mut spent),
+            ] {
+                let leaf_positions: Vec<u64> = positions
+                    .iter()
+                    .map(|position| u64::from(position.leaf_position))
+                    .collect();
+                let paths = shard_witnesses(&index, &shard, &leaf_positions)?;
+                out.extend(positions.into_iter().zip(paths).map(|(position, path)| {
+                    NullifierWitness {
+                        nullifier: position.nullifier,
+                        left_bound: position.left_bound,
+                        right_bound: position.right_bound,
+                        leaf_position: u64::from(position.leaf_position),
+                        merkle_path: path,
+                    }
+                }));
+            }
+        }
+        Ok::<_, MerklePathError>((witnesses, spent))
+    })
+    .await??;
+
+    verify_remote_witnesses(
+        pool,
+        orchard_gap_tree_hash,
+        expected_root,
+        user_nullifiers,
+        vec![WitnessResponse {
+            pool,
+            root: expected_root,
+            witnesses,
+            spent,
+        }],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use zair_core::schema::config::AirdropNetwork;
+
+    use super::*;
+
+    const OPTIONS: ContainerOptions = ContainerOptions::new(AirdropNetwork::Testnet, 1);
+
+    #[tokio::test]
+    async fn sharded_witnesses_match_full_tree() {
+        let chain = SanitiseNullifiers::new(
+            (1_u8..=9)
+                .map(|byte| Nullifier::from([byte.saturating_mul(10); 32]))
+                .collect(),
+        );
+        let tree = SaplingGapTree::from_nullifiers(&chain).expect("sapling tree should build");
+        let shards = tree.shards(&chain, 2).expect("shards should build");
+        let dir = tempfile::tempdir().expect("tempdir");
+        let files = encode_gap_shards(&shards, &OPTIONS).expect("shards should encode");
+        write_gap_shards(dir.path(), Pool::Sapling, files)
+            .await
+            .expect("shards should be written");
+
+        let user = SanitiseNullifiers::new(vec![
+            Nullifier::from([15_u8; 32]),
+            Nullifier::from([50_u8; 32]),
+            Nullifier::from([85_u8; 32]),
+        ]);
+        let source = dir.path().to_str().expect("utf-8 path");
+        let data = fetch_sharded_pool_data(
+            source,
+            Pool::Sapling,
+            OrchardGapTreeHash::Sinsemilla,
+            tree.root_bytes(),
+            &OPTIONS,
+            &user,
+        )
+        .await
+        .expect("sharded witnesses should verify");
+
+        assert_eq!(data.user_nullifiers.len(), 2, "spent nullifier is omitted");
+        for position in &data.user_nullifiers {
+            let leaf_position = u64::from(position.leaf_position);
+            assert_eq!(
+                data.tree.witness_bytes(leaf_position),
+                tree.witness_bytes(leaf_position)
+            );
+        }
+
+        assert!(
+            fetch_sharded_pool_data(
+                source,
+                Pool::Sapling,
+                OrchardGapTreeHash::Sinsemilla,
+                [0_u8; 32],
+                &OPTIONS,
+                &user,
+            )
+            .await
+            .is_err(),
+            "wrong root should be rejected"
+        );
+    }
+}
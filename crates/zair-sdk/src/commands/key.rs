@@ -10,6 +10,7 @@ use zcash_keys::keys::UnifiedSpendingKey;
 use zcash_protocol::consensus::Network;
 use zip32::AccountId;
 
+use super::config_signature::organizer_public_key;
 use super::sensitive_output::write_sensitive_output;
 use crate::seed::read_seed_file;
 
@@ -140,3 +141,22 @@ pub async fn key_derive_ufvk(
     info!(file = ?output, "UFVK written");
     Ok(())
 }
+
+/// Generate a random Ed25519 organizer key, write it as hex to `output` and return its
+/// public key.
+///
+/// # Errors
+/// Returns an error if file I/O fails.
+pub async fn key_generate_organizer(output: PathBuf) -> eyre::Result<[u8; 32]> {
+    use rand_core::RngCore as _;
+    use zeroize::Zeroizing;
+
+    let mut secret_key = Zeroizing::new([0_u8; 32]);
+    rand_core::OsRng.fill_bytes(secret_key.as_mut_slice());
+    let hex = Zeroizing::new(format!("{}\n", hex::encode(secret_key.as_slice())));
+    write_sensitive_output(&output, &hex).await?;
+
+    let public_key = organizer_public_key(&secret_key);
+    info!(file = ?output, public_key = %hex::encode(public_key), "Organizer key written");
+    Ok(public_key)
+}
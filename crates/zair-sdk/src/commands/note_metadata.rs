@@ -3,6 +3,8 @@
 //! This module defines the `NoteMetadata` trait and pool-specific metadata types
 //! that enable generic proof generation for both Sapling and Orchard pools.
 
+use std::collections::BTreeMap;
+
 use group::{Group as _, GroupEncoding as _};
 use pasta_curves::arithmetic::CurveExt;
 use pasta_curves::pallas;
@@ -31,6 +33,9 @@ pub trait NoteMetadata {
     /// Returns the hiding nullifier for this note.
     fn hiding_nullifier(&self) -> Nullifier;
 
+    /// Returns the hiding nullifiers for this note under every configured target id.
+    fn target_nullifiers(&self) -> &BTreeMap<String, Nullifier>;
+
     /// Returns the block height where this note was created.
     fn block_height(&self) -> u64;
 
@@ -51,6 +56,8 @@ pub trait NoteMetadata {
 pub struct SaplingNoteMetadata {
     /// The hiding nullifier (public input)
     pub hiding_nullifier: Nullifier,
+    /// Hiding nullifiers under every target id of the airdrop configuration.
+    pub target_nullifiers: BTreeMap<String, Nullifier>,
     /// Diversifier (11 bytes)
     pub diversifier: [u8; 11],
     /// Diversified transmission key
@@ -76,6 +83,10 @@ impl NoteMetadata for SaplingNoteMetadata {
         self.hiding_nullifier
     }
 
+    fn target_nullifiers(&self) -> &BTreeMap<String, Nullifier> {
+        &self.target_nullifiers
+    }
+
     fn block_height(&self) -> u64 {
         self.block_height
     }
@@ -125,6 +136,8 @@ impl NoteMetadata for SaplingNoteMetadata {
 pub struct OrchardNoteMetadata {
     /// The hiding nullifier (public input)
     pub hiding_nullifier: Nullifier,
+    /// Hiding nullifiers under every target id of the airdrop configuration.
+    pub target_nullifiers: BTreeMap<String, Nullifier>,
     /// Note rho.
     pub rho: [u8; 32],
     /// Note rseed.
@@ -152,6 +165,10 @@ impl NoteMetadata for OrchardNoteMetadata {
         self.hiding_nullifier
     }
 
+    fn target_nullifiers(&self) -> &BTreeMap<String, Nullifier> {
+        &self.target_nullifiers
+    }
+
     fn block_height(&self) -> u64 {
         self.block_height
     }
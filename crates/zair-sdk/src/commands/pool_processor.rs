@@ -3,13 +3,13 @@
 //! This module defines the `PoolProcessor` trait and its implementations
 //! for Sapling and Orchard pools, enabling generic claim processing.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use zair_core::base::{Nullifier, Pool};
-use zair_core::schema::config::AirdropConfiguration;
+use zair_core::schema::config::{AirdropConfiguration, AirdropTarget, ShieldedTarget};
 use zair_core::schema::proof_inputs::{ClaimInput, OrchardPrivateInputs, SaplingPrivateInputs};
 use zair_scan::ViewingKeys;
-use zair_scan::scanner::AccountNotesVisitor;
+use zair_scan::scanner::AccountNotes;
 use zair_scan::user_nullifiers::NoteNullifier as _;
 
 use super::note_metadata::{
@@ -45,15 +45,37 @@ pub trait PoolProcessor {
     /// Returns the expected merkle root from the airdrop configuration.
     fn expected_root(config: &AirdropConfiguration) -> Option<[u8; 32]>;
 
-    /// Collects note metadata from the visitor.
+    /// Collects note metadata from the account's notes.
     /// Returns `None` if the viewing key is not available.
     fn collect_notes(
-        visitor: &AccountNotesVisitor,
+        account_notes: &dyn AccountNotes,
         viewing_keys: &ViewingKeys,
         airdrop_config: &AirdropConfiguration,
     ) -> eyre::Result<Option<HashMap<Nullifier, Self::Metadata>>>;
 }
 
+/// Target ids of a pool across the configuration's targets, including the default one.
+///
+/// Empty for configurations without targets, whose claims only carry the default nullifier.
+fn target_ids<'a>(
+    airdrop_config: &'a AirdropConfiguration,
+    default_target_id: &'a str,
+    pool_target: fn(&AirdropTarget) -> Option<&ShieldedTarget>,
+) -> BTreeSet<&'a str> {
+    if airdrop_config.targets.is_empty() {
+        return BTreeSet::new();
+    }
+    std::iter::once(default_target_id)
+        .chain(
+            airdrop_config
+                .targets
+                .iter()
+                .filter_map(pool_target)
+                .map(|target| target.target_id.as_str()),
+        )
+        .collect()
+}
+
 /// Sapling pool processor.
 pub struct SaplingPool;
 
@@ -68,7 +90,7 @@ impl PoolProcessor for SaplingPool {
     }
 
     fn collect_notes(
-        visitor: &AccountNotesVisitor,
+        account_notes: &dyn AccountNotes,
         viewing_keys: &ViewingKeys,
         airdrop_config: &AirdropConfiguration,
     ) -> eyre::Result<Option<HashMap<Nullifier, Self::Metadata>>> {
@@ -83,13 +105,26 @@ impl PoolProcessor for SaplingPool {
         let hiding_factor = zair_scan::user_nullifiers::SaplingHidingFactor {
             personalization: sapling_config.target_id.as_bytes(),
         };
+        let target_ids = target_ids(airdrop_config, &sapling_config.target_id, |target| {
+            target.sapling.as_ref()
+        });
 
         let mut notes = HashMap::new();
-        for found_note in visitor.sapling_notes() {
+        for found_note in account_notes.sapling_notes() {
             let nullifier = found_note.nullifier(sapling_key);
             let hiding_nullifier = found_note.hiding_nullifier(sapling_key, &hiding_factor)?;
-
-            let cm_merkle_proof = visitor
+            let mut target_nullifiers = BTreeMap::new();
+            for target_id in &target_ids {
+                let hiding_factor = zair_scan::user_nullifiers::SaplingHidingFactor {
+                    personalization: target_id.as_bytes(),
+                };
+                target_nullifiers.insert(
+                    (*target_id).to_owned(),
+                    found_note.hiding_nullifier(sapling_key, &hiding_factor)?,
+                );
+            }
+
+            let cm_merkle_proof = account_notes
                 .sapling_witness(found_note.note.position)?
                 .ok_or_else(|| {
                     eyre::eyre!(
@@ -103,6 +138,7 @@ impl PoolProcessor for SaplingPool {
                 SaplingNoteMetadata {
                     diversifier: found_note.note.diversifier(),
                     hiding_nullifier,
+                    target_nullifiers,
                     pk_d: found_note.note.pk_d(),
                     value: found_note.note.note.value().inner(),
                     rcm: found_note.note.rcm(),
@@ -131,7 +167,7 @@ impl PoolProcessor for OrchardPool {
     }
 
     fn collect_notes(
-        visitor: &AccountNotesVisitor,
+        account_notes: &dyn AccountNotes,
         viewing_keys: &ViewingKeys,
         airdrop_config: &AirdropConfiguration,
     ) -> eyre::Result<Option<HashMap<Nullifier, Self::Metadata>>> {
@@ -147,13 +183,27 @@ impl PoolProcessor for OrchardPool {
             domain: &orchard_config.target_id,
             tag: b"K",
         };
+        let target_ids = target_ids(airdrop_config, &orchard_config.target_id, |target| {
+            target.orchard.as_ref()
+        });
 
         let mut notes = HashMap::new();
-        for found_note in visitor.orchard_notes() {
+        for found_note in account_notes.orchard_notes() {
             let nullifier = found_note.nullifier(orchard_key);
             let hiding_nullifier = found_note.hiding_nullifier(orchard_key, &hiding_factor)?;
-
-            let cm_merkle_proof = visitor
+            let mut target_nullifiers = BTreeMap::new();
+            for target_id in &target_ids {
+                let hiding_factor = zair_scan::user_nullifiers::OrchardHidingFactor {
+                    domain: target_id,
+                    tag: b"K",
+                };
+                target_nullifiers.insert(
+                    (*target_id).to_owned(),
+                    found_note.hiding_nullifier(orchard_key, &hiding_factor)?,
+                );
+            }
+
+            let cm_merkle_proof = account_notes
                 .orchard_witness(found_note.metadata.position)?
                 .ok_or_else(|| {
                     eyre::eyre!(
@@ -174,6 +224,7 @@ impl PoolProcessor for OrchardPool {
                 nullifier,
                 OrchardNoteMetadata {
                     hiding_nullifier,
+                    target_nullifiers,
                     rho: found_note.note.rho().to_bytes(),
                     rseed: *found_note.note.rseed().as_bytes(),
                     g_d,
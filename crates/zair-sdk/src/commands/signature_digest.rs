@@ -3,7 +3,10 @@
 use blake2b_simd::Params;
 use eyre::ensure;
 use zair_core::base::{Nullifier, Pool};
-use zair_core::schema::submission::{OrchardSignedClaim, SaplingSignedClaim};
+use zair_core::schema::config::{ClaimWindow, ClaimWindowUnit};
+use zair_core::schema::submission::{
+    OrchardSignedClaim, SaplingSignedClaim, TransparentSignedClaim,
+};
 
 use super::claim_proofs::{OrchardClaimProofResult, SaplingClaimProofResult};
 
@@ -11,10 +14,14 @@ use super::claim_proofs::{OrchardClaimProofResult, SaplingClaimProofResult};
 pub const SIGNATURE_PREIMAGE_TAG: &[u8; 8] = b"ZAIR_SIG";
 /// Protocol version byte included in signature digest preimages.
 pub const SIGNATURE_VERSION: u8 = 1;
+/// Protocol version byte of signature digest preimages that bind a claim window.
+pub const SIGNATURE_VERSION_WINDOWED: u8 = 2;
 /// Domain tag for Sapling proof-hash preimages.
 pub const SAPLING_PROOF_TAG: &[u8; 21] = b"ZAIR_SAPLING_PROOF_V1";
 /// Domain tag for Orchard proof-hash preimages.
 pub const ORCHARD_PROOF_TAG: &[u8; 21] = b"ZAIR_ORCHARD_PROOF_V1";
+/// Domain tag for transparent claim-hash preimages.
+pub const TRANSPARENT_PROOF_TAG: &[u8; 25] = b"ZAIR_TRANSPARENT_PROOF_V1";
 
 /// Hash arbitrary bytes to 32 bytes with `BLAKE2b`.
 #[must_use]
@@ -142,14 +149,59 @@ pub fn hash_orchard_signed_claim_proof(claim: &OrchardSignedClaim) -> eyre::Resu
     )
 }
 
-/// Build the 32-byte message signed by spend authorization keys.
+/// Hash the unsigned fields of a transparent claim entry.
+///
+/// The Merkle inclusion path takes the place of a zero-knowledge proof.
+pub fn hash_transparent_claim_fields(
+    pubkey: &[u8; 33],
+    balance: u64,
+    leaf_index: u64,
+    merkle_path: &[[u8; 32]],
+    airdrop_nullifier: Nullifier,
+) -> eyre::Result<[u8; 32]> {
+    let path_len = u32::try_from(merkle_path.len())
+        .map_err(|_| eyre::eyre!("Transparent Merkle path length exceeds u32::MAX"))?;
+    let mut preimage = Vec::new();
+    preimage.extend_from_slice(TRANSPARENT_PROOF_TAG);
+    preimage.extend_from_slice(pubkey);
+    preimage.extend_from_slice(&balance.to_le_bytes());
+    preimage.extend_from_slice(&leaf_index.to_le_bytes());
+    preimage.extend_from_slice(&path_len.to_le_bytes());
+    for node in merkle_path {
+        preimage.extend_from_slice(node);
+    }
+    let nf: [u8; 32] = airdrop_nullifier.into();
+    preimage.extend_from_slice(&nf);
+    Ok(hash_bytes(&preimage))
+}
+
+/// Hash the unsigned fields of a signed transparent claim entry.
+pub fn hash_transparent_signed_claim_proof(
+    claim: &TransparentSignedClaim,
+) -> eyre::Result<[u8; 32]> {
+    hash_transparent_claim_fields(
+        &claim.pubkey,
+        claim.balance,
+        claim.leaf_index,
+        &claim.merkle_path,
+        claim.airdrop_nullifier,
+    )
+}
+
+/// Build the 32-byte message signed by spend authorization keys, or by the secp256k1 key of a
+/// transparent claim.
 ///
 /// Preimage layout:
 /// `ZAIR_SIG_V1 || version:u8 || pool:u8 || target_id_len:u8 || target_id || proof_hash ||
 /// message_hash`
+///
+/// When the airdrop has a `claim_window`, the version is [`SIGNATURE_VERSION_WINDOWED`] and
+/// `unit:u8 || start_flag:u8 || start:u64 || end_flag:u8 || end:u64` follows `target_id`, so a
+/// signature cannot be replayed into a campaign with another window.
 pub fn signature_digest(
     pool: Pool,
     target_id: &str,
+    claim_window: Option<&ClaimWindow>,
     proof_hash: &[u8; 32],
     message_hash: &[u8; 32],
 ) -> eyre::Result<[u8; 32]> {
@@ -161,10 +213,24 @@ pub fn signature_digest(
 
     let mut preimage = Vec::new();
     preimage.extend_from_slice(SIGNATURE_PREIMAGE_TAG);
-    preimage.push(SIGNATURE_VERSION);
+    preimage.push(if claim_window.is_some() {
+        SIGNATURE_VERSION_WINDOWED
+    } else {
+        SIGNATURE_VERSION
+    });
     preimage.push(pool.as_byte());
     preimage.push(target_len);
     preimage.extend_from_slice(target_id.as_bytes());
+    if let Some(window) = claim_window {
+        preimage.push(match window.unit {
+            ClaimWindowUnit::Height => 0,
+            ClaimWindowUnit::Timestamp => 1,
+        });
+        for bound in [window.start, window.end] {
+            preimage.push(u8::from(bound.is_some()));
+            preimage.extend_from_slice(&bound.unwrap_or(0).to_le_bytes());
+        }
+    }
     preimage.extend_from_slice(proof_hash);
     preimage.extend_from_slice(message_hash);
 
@@ -223,4 +289,43 @@ mod tests {
         assert_eq!(h0, h0_again);
         assert_ne!(h0, h1);
     }
+
+    #[test]
+    fn signature_digest_binds_claim_window() {
+        let digest = |window: Option<&ClaimWindow>| {
+            signature_digest(Pool::Sapling, "ZAIRTEST", window, &[1; 32], &[2; 32])
+                .expect("digest should succeed")
+        };
+        let window = ClaimWindow {
+            unit: ClaimWindowUnit::Height,
+            start: Some(100),
+            end: Some(200),
+        };
+        let later = ClaimWindow {
+            start: Some(300),
+            end: Some(400),
+            ..window
+        };
+        let open_ended = ClaimWindow {
+            end: None,
+            ..window
+        };
+        let by_time = ClaimWindow {
+            unit: ClaimWindowUnit::Timestamp,
+            ..window
+        };
+
+        let digests = [
+            digest(None),
+            digest(Some(&window)),
+            digest(Some(&later)),
+            digest(Some(&open_ended)),
+            digest(Some(&by_time)),
+        ];
+        for (i, a) in digests.iter().enumerate() {
+            for b in digests.iter().skip(i + 1) {
+                assert_ne!(a, b);
+            }
+        }
+    }
 }
@@ -2,3 +2,4 @@
 
 pub mod orchard;
 pub mod sapling;
+pub mod transparent;
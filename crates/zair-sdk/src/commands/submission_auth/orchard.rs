@@ -5,10 +5,9 @@ use group::ff::PrimeField as _;
 use orchard::keys::SpendAuthorizingKey;
 use orchard::primitives::redpallas::{Signature, SpendAuth, VerificationKey};
 use pasta_curves::pallas;
-use zcash_keys::keys::UnifiedSpendingKey;
-use zcash_protocol::consensus::Network;
-use zip32::AccountId;
+use rand_core::{CryptoRng, RngCore};
 
+use super::super::claim_keys::ClaimKeys;
 use super::super::claim_proofs::{OrchardClaimProofResult, OrchardClaimSecretResult};
 
 /// Seed-derived Orchard spend-authorizing key.
@@ -17,28 +16,19 @@ pub struct OrchardSpendAuthKey {
 }
 
 /// Derive the Orchard spend-authorizing key for an account.
-pub fn derive_spend_auth_key(
-    network: Network,
-    seed: &[u8; 64],
-    account_id: u32,
-) -> eyre::Result<OrchardSpendAuthKey> {
-    let account_id =
-        AccountId::try_from(account_id).map_err(|_| eyre::eyre!("Invalid account-id"))?;
-
-    let usk = UnifiedSpendingKey::from_seed(&network, seed, account_id)
-        .map_err(|e| eyre::eyre!("Failed to derive spending key: {e:?}"))?;
-
-    Ok(OrchardSpendAuthKey {
-        key: SpendAuthorizingKey::from(usk.orchard()),
-    })
+pub fn derive_spend_auth_key(keys: &ClaimKeys) -> OrchardSpendAuthKey {
+    OrchardSpendAuthKey {
+        key: SpendAuthorizingKey::from(keys.spending_key().orchard()),
+    }
 }
 
 /// Sign an Orchard claim proof entry digest.
-pub fn sign_claim(
+pub fn sign_claim<R: RngCore + CryptoRng>(
     proof: &OrchardClaimProofResult,
     secret: &OrchardClaimSecretResult,
     key: &OrchardSpendAuthKey,
     digest: &[u8; 32],
+    rng: R,
 ) -> eyre::Result<[u8; 64]> {
     ensure!(
         proof.airdrop_nullifier == secret.airdrop_nullifier,
@@ -58,7 +48,7 @@ pub fn sign_claim(
         "Cannot match Orchard proof rk to a seed-derived Orchard spend key"
     );
 
-    let signature = signing_key.sign(rand_core::OsRng, digest);
+    let signature = signing_key.sign(rng, digest);
     Ok((&signature).into())
 }
 
@@ -2,10 +2,9 @@
 
 use eyre::{ContextCompat as _, ensure};
 use jubjub::Fr;
-use zcash_keys::keys::UnifiedSpendingKey;
-use zcash_protocol::consensus::Network;
-use zip32::AccountId;
+use rand_core::{CryptoRng, RngCore};
 
+use super::super::claim_keys::ClaimKeys;
 use super::super::claim_proofs::{SaplingClaimProofResult, SaplingClaimSecretResult};
 
 /// Seed-derived Sapling spend-auth keys for both scopes.
@@ -15,30 +14,21 @@ pub struct SaplingSpendAuthKeys {
 }
 
 /// Derive Sapling spend-authorizing keys for external and internal scopes.
-pub fn derive_spend_auth_keys(
-    network: Network,
-    seed: &[u8; 64],
-    account_id: u32,
-) -> eyre::Result<SaplingSpendAuthKeys> {
-    let account_id =
-        AccountId::try_from(account_id).map_err(|_| eyre::eyre!("Invalid account-id"))?;
-
-    let usk = UnifiedSpendingKey::from_seed(&network, seed, account_id)
-        .map_err(|e| eyre::eyre!("Failed to derive spending key: {e:?}"))?;
-
-    let extsk = usk.sapling();
-    Ok(SaplingSpendAuthKeys {
+pub fn derive_spend_auth_keys(keys: &ClaimKeys) -> SaplingSpendAuthKeys {
+    let extsk = keys.spending_key().sapling();
+    SaplingSpendAuthKeys {
         external: extsk.expsk.ask.clone(),
         internal: extsk.derive_internal().expsk.ask,
-    })
+    }
 }
 
 /// Sign a Sapling claim proof entry digest.
-pub fn sign_claim(
+pub fn sign_claim<R: RngCore + CryptoRng>(
     proof: &SaplingClaimProofResult,
     secret: &SaplingClaimSecretResult,
     keys: &SaplingSpendAuthKeys,
     digest: &[u8; 32],
+    rng: R,
 ) -> eyre::Result<[u8; 64]> {
     ensure!(
         proof.airdrop_nullifier == secret.airdrop_nullifier,
@@ -71,7 +61,7 @@ pub fn sign_claim(
     );
 
     let signing_key = matched_signing_key.context("Missing matched Sapling signing key")?;
-    let signature = signing_key.sign(rand_core::OsRng, digest);
+    let signature = signing_key.sign(rng, digest);
     Ok(signature.into())
 }
 
@@ -0,0 +1,75 @@
+//! Transparent secp256k1 key derivation/signature helpers.
+
+use secp256k1::ecdsa::Signature;
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use zair_core::transparent::PUBKEY_HASH_SIZE;
+use zcash_transparent::address::TransparentAddress;
+use zcash_transparent::keys::NonHardenedChildIndex;
+use zip32::Scope;
+
+use super::super::claim_keys::ClaimKeys;
+
+/// Seed-derived secp256k1 key of one transparent address.
+pub struct TransparentAddressKey {
+    secret: SecretKey,
+    /// Compressed public key.
+    pub pubkey: [u8; 33],
+    /// P2PKH public key hash of `pubkey`.
+    pub pubkey_hash: [u8; PUBKEY_HASH_SIZE],
+}
+
+/// Derive the key of the transparent address at `scope` and `index` of an account.
+pub fn derive_address_key(
+    keys: &ClaimKeys,
+    scope: Scope,
+    index: NonHardenedChildIndex,
+) -> eyre::Result<TransparentAddressKey> {
+    let account_key = keys.spending_key().transparent();
+    let secret = match scope {
+        Scope::External => account_key.derive_external_secret_key(index),
+        Scope::Internal => account_key.derive_internal_secret_key(index),
+    }
+    .map_err(|e| eyre::eyre!("Failed to derive transparent key: {e}"))?;
+    let pubkey = PublicKey::from_secret_key(&Secp256k1::signing_only(), &secret);
+    Ok(TransparentAddressKey {
+        secret,
+        pubkey: pubkey.serialize(),
+        pubkey_hash: p2pkh_hash(&pubkey)?,
+    })
+}
+
+/// P2PKH public key hash of a compressed secp256k1 public key.
+pub fn pubkey_hash(pubkey: &[u8; 33]) -> eyre::Result<[u8; PUBKEY_HASH_SIZE]> {
+    let pubkey = PublicKey::from_slice(pubkey)
+        .map_err(|_| eyre::eyre!("Invalid transparent public key encoding"))?;
+    p2pkh_hash(&pubkey)
+}
+
+fn p2pkh_hash(pubkey: &PublicKey) -> eyre::Result<[u8; PUBKEY_HASH_SIZE]> {
+    match TransparentAddress::from_pubkey(pubkey) {
+        TransparentAddress::PublicKeyHash(hash) => Ok(hash),
+        TransparentAddress::ScriptHash(_) => Err(eyre::eyre!("Expected a P2PKH address")),
+    }
+}
+
+/// Sign a submission digest with a transparent address key.
+pub fn sign_digest(key: &TransparentAddressKey, digest: &[u8; 32]) -> [u8; 64] {
+    Secp256k1::signing_only()
+        .sign_ecdsa(&Message::from_digest(*digest), &key.secret)
+        .serialize_compact()
+}
+
+/// Verify a compact ECDSA signature against a submission digest.
+pub fn verify_signature(
+    pubkey: &[u8; 33],
+    signature: &[u8; 64],
+    digest: &[u8; 32],
+) -> eyre::Result<bool> {
+    let pubkey = PublicKey::from_slice(pubkey)
+        .map_err(|_| eyre::eyre!("Invalid transparent public key encoding"))?;
+    let signature = Signature::from_compact(signature)
+        .map_err(|_| eyre::eyre!("Invalid transparent signature encoding"))?;
+    Ok(Secp256k1::verification_only()
+        .verify_ecdsa(&Message::from_digest(*digest), &signature, &pubkey)
+        .is_ok())
+}
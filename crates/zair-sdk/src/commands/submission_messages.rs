@@ -1,15 +1,24 @@
 //! Per-claim message assignment helpers for submission signing/verification.
 
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+#[cfg(feature = "native")]
+use std::path::{Path, PathBuf};
 
+#[cfg(feature = "native")]
 use eyre::{Context as _, ensure};
+#[cfg(feature = "native")]
 use serde::{Deserialize, Serialize};
 use zair_core::base::Nullifier;
+#[cfg(feature = "native")]
+use zair_core::schema::config::MessagePolicy;
 
+#[cfg(feature = "native")]
+use super::claim_message::AddressValidators;
+#[cfg(feature = "native")]
 use super::signature_digest::hash_message;
 
 /// One per-claim message-file assignment.
+#[cfg(feature = "native")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaimMessageAssignment {
     /// Airdrop nullifier identifying the claim entry.
@@ -19,6 +28,7 @@ pub struct ClaimMessageAssignment {
 }
 
 /// JSON payload for per-claim message assignments.
+#[cfg(feature = "native")]
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClaimMessagesFile {
     /// Sapling claim message assignments.
@@ -27,6 +37,9 @@ pub struct ClaimMessagesFile {
     /// Orchard claim message assignments.
     #[serde(default)]
     pub orchard: Vec<ClaimMessageAssignment>,
+    /// Transparent claim message assignments.
+    #[serde(default)]
+    pub transparent: Vec<ClaimMessageAssignment>,
 }
 
 /// Resolved message hashes for submission sign/verify.
@@ -35,9 +48,32 @@ pub struct ResolvedMessageHashes {
     shared: Option<[u8; 32]>,
     sapling: BTreeMap<Nullifier, [u8; 32]>,
     orchard: BTreeMap<Nullifier, [u8; 32]>,
+    transparent: BTreeMap<Nullifier, [u8; 32]>,
 }
 
 impl ResolvedMessageHashes {
+    /// Message hashes from a shared hash and per-claim overrides.
+    #[must_use]
+    pub const fn new(
+        shared: Option<[u8; 32]>,
+        sapling: BTreeMap<Nullifier, [u8; 32]>,
+        orchard: BTreeMap<Nullifier, [u8; 32]>,
+    ) -> Self {
+        Self {
+            shared,
+            sapling,
+            orchard,
+            transparent: BTreeMap::new(),
+        }
+    }
+
+    /// Add per-claim overrides for transparent claims.
+    #[must_use]
+    pub fn with_transparent(mut self, transparent: BTreeMap<Nullifier, [u8; 32]>) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
     /// Resolve Sapling message hash for a given nullifier.
     #[must_use]
     pub fn sapling_hash(&self, nullifier: Nullifier) -> Option<[u8; 32]> {
@@ -55,11 +91,45 @@ impl ResolvedMessageHashes {
             .copied()
             .or_else(|| self.shared.as_ref().copied())
     }
+
+    /// Resolve transparent message hash for a given nullifier.
+    #[must_use]
+    pub fn transparent_hash(&self, nullifier: Nullifier) -> Option<[u8; 32]> {
+        self.transparent
+            .get(&nullifier)
+            .copied()
+            .or_else(|| self.shared.as_ref().copied())
+    }
+}
+
+/// Check a message against the configuration's `policy` and, if it is a structured claim
+/// message, its recipient against `validators`.
+#[cfg(feature = "native")]
+fn check_message(
+    policy: &MessagePolicy,
+    validators: &AddressValidators,
+    message: &[u8],
+    path: &Path,
+) -> eyre::Result<()> {
+    ensure!(
+        policy.accepts(message),
+        "Message file {} is {} bytes, above the {} byte limit of the airdrop configuration",
+        path.display(),
+        message.len(),
+        policy.max_bytes.unwrap_or_default()
+    );
+    validators
+        .check_message(message)
+        .with_context(|| format!("Refusing claim message {}", path.display()))?;
+    Ok(())
 }
 
+#[cfg(feature = "native")]
 async fn load_assignment_hashes(
     assignments: Vec<ClaimMessageAssignment>,
     pool_name: &str,
+    policy: &MessagePolicy,
+    validators: &AddressValidators,
 ) -> eyre::Result<BTreeMap<Nullifier, [u8; 32]>> {
     let mut by_nullifier = BTreeMap::new();
     for assignment in assignments {
@@ -73,6 +143,7 @@ async fn load_assignment_hashes(
                     assignment.message_file.display()
                 )
             })?;
+        check_message(policy, validators, &message_bytes, &assignment.message_file)?;
         let hash = hash_message(&message_bytes);
         let previous = by_nullifier.insert(assignment.airdrop_nullifier, hash);
         ensure!(
@@ -88,14 +159,20 @@ async fn load_assignment_hashes(
 /// Load shared/per-claim message hashes.
 ///
 /// If both are provided, per-claim mappings override the shared message for matching nullifiers.
+/// Every message must be accepted by the configuration's message `policy`, and structured claim
+/// messages must name a recipient that passes its address format's validator in `validators`.
+#[cfg(feature = "native")]
 pub async fn resolve_message_hashes(
     shared_message_file: Option<&PathBuf>,
     messages_file: Option<&PathBuf>,
+    policy: &MessagePolicy,
+    validators: &AddressValidators,
 ) -> eyre::Result<ResolvedMessageHashes> {
     let shared = if let Some(path) = shared_message_file {
         let bytes = tokio::fs::read(path)
             .await
             .with_context(|| format!("Failed to read shared message file at {}", path.display()))?;
+        check_message(policy, validators, &bytes, path)?;
         Some(hash_message(&bytes))
     } else {
         None
@@ -118,14 +195,76 @@ pub async fn resolve_message_hashes(
         )
     })?;
 
-    let (sapling, orchard) = tokio::try_join!(
-        load_assignment_hashes(payload.sapling, "Sapling"),
-        load_assignment_hashes(payload.orchard, "Orchard"),
+    let (sapling, orchard, transparent) = tokio::try_join!(
+        load_assignment_hashes(payload.sapling, "Sapling", policy, validators),
+        load_assignment_hashes(payload.orchard, "Orchard", policy, validators),
+        load_assignment_hashes(payload.transparent, "Transparent", policy, validators),
     )?;
 
     Ok(ResolvedMessageHashes {
         shared,
         sapling,
         orchard,
+        transparent,
     })
 }
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use zair_core::schema::message::StructuredClaimMessage;
+
+    use super::*;
+    use crate::commands::InvalidClaimMessage;
+
+    #[tokio::test]
+    async fn structured_messages_are_validated_before_hashing() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("claim-message.json");
+        let message = StructuredClaimMessage::new(
+            "namada.5f5de2dd1b88cba30586420".to_owned(),
+            "namada".to_owned(),
+            "tnam1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq".to_owned(),
+            None,
+        );
+        let bytes = serde_json::to_vec(&message).expect("serialize message");
+        std::fs::write(&path, &bytes).expect("write message");
+
+        let err = resolve_message_hashes(
+            Some(&path),
+            None,
+            &MessagePolicy::default(),
+            &AddressValidators::default(),
+        )
+        .await
+        .expect_err("recipient with a bad checksum must be refused");
+        assert!(matches!(
+            err.downcast_ref::<InvalidClaimMessage>(),
+            Some(InvalidClaimMessage::InvalidRecipient { .. })
+        ));
+
+        let resolved = resolve_message_hashes(
+            Some(&path),
+            None,
+            &MessagePolicy::default(),
+            &AddressValidators::empty().with("namada", AcceptAll),
+        )
+        .await
+        .expect("message accepted by the registered validator");
+        assert_eq!(
+            resolved.sapling_hash(Nullifier::from([0; 32])),
+            Some(hash_message(&bytes))
+        );
+    }
+
+    struct AcceptAll;
+
+    impl crate::commands::AddressValidator for AcceptAll {
+        fn validate(
+            &self,
+            _address: &str,
+            _chain_id: &str,
+        ) -> Result<(), crate::commands::AddressError> {
+            Ok(())
+        }
+    }
+}
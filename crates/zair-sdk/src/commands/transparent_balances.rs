@@ -0,0 +1,158 @@
+//! Transparent balance listings for the transparent snapshot.
+//!
+//! Compact blocks carry no transparent data, so the UTXO set at the snapshot height comes from
+//! a full node export instead: one `address,zatoshis` row per UTXO or per address. Rows for the
+//! same address are summed. Blank lines, `#` comments and an `address,...` header are ignored.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use eyre::{Context as _, ContextCompat as _, ensure};
+use tracing::{info, warn};
+use zair_core::schema::config::AirdropNetwork;
+use zair_core::transparent::{BalanceTree, PUBKEY_HASH_SIZE, TransparentBalance};
+use zcash_keys::address::Address;
+use zcash_transparent::address::TransparentAddress;
+
+use crate::common::to_zcash_network;
+
+/// Parse a balance listing into the balance tree of its P2PKH addresses.
+///
+/// P2SH addresses cannot sign a claim and are skipped.
+///
+/// # Errors
+/// Returns an error if a row is malformed, an address is not a transparent address of
+/// `network`, a balance overflows, or no P2PKH address has a balance.
+pub fn parse_transparent_balances(
+    network: AirdropNetwork,
+    listing: &str,
+) -> eyre::Result<BalanceTree> {
+    let params = to_zcash_network(network);
+    let mut by_pubkey_hash: BTreeMap<[u8; PUBKEY_HASH_SIZE], u64> = BTreeMap::new();
+    let mut skipped_p2sh = 0_usize;
+
+    for (line_index, line) in listing.lines().enumerate() {
+        let line_number = line_index.saturating_add(1);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("address,") {
+            continue;
+        }
+        let (address, zatoshis) = line
+            .split_once(',')
+            .with_context(|| format!("Line {line_number}: expected `address,zatoshis`"))?;
+        let zatoshis: u64 = zatoshis
+            .trim()
+            .parse()
+            .with_context(|| format!("Line {line_number}: invalid zatoshi amount"))?;
+        let pubkey_hash = match Address::decode(&params, address.trim()) {
+            Some(Address::Transparent(TransparentAddress::PublicKeyHash(hash))) => hash,
+            Some(Address::Transparent(TransparentAddress::ScriptHash(_))) => {
+                skipped_p2sh = skipped_p2sh.saturating_add(1);
+                continue;
+            }
+            _ => {
+                eyre::bail!(
+                    "Line {line_number}: `{}` is not a transparent {network:?} address",
+                    address.trim()
+                );
+            }
+        };
+        let balance = by_pubkey_hash.entry(pubkey_hash).or_default();
+        *balance = balance
+            .checked_add(zatoshis)
+            .with_context(|| format!("Line {line_number}: address balance overflows u64"))?;
+    }
+
+    if skipped_p2sh > 0 {
+        warn!(
+            rows = skipped_p2sh,
+            "Skipped P2SH rows; only P2PKH addresses can claim"
+        );
+    }
+    let balances: Vec<TransparentBalance> = by_pubkey_hash
+        .into_iter()
+        .filter(|(_, balance)| *balance > 0)
+        .map(|(pubkey_hash, balance)| TransparentBalance {
+            pubkey_hash,
+            balance,
+        })
+        .collect();
+    ensure!(
+        !balances.is_empty(),
+        "Transparent balance listing has no funded P2PKH address"
+    );
+    let tree = BalanceTree::new(balances)?;
+    ensure!(
+        tree.total_balance().is_some(),
+        "Total transparent balance overflows u64"
+    );
+    Ok(tree)
+}
+
+/// Read a balance listing file into a balance tree.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed.
+pub async fn read_transparent_balances(
+    network: AirdropNetwork,
+    path: &Path,
+) -> eyre::Result<BalanceTree> {
+    info!(file = ?path, "Loading transparent balances...");
+    let listing = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read transparent balances {}", path.display()))?;
+    let tree = parse_transparent_balances(network, &listing)
+        .with_context(|| format!("Invalid transparent balances {}", path.display()))?;
+    info!(
+        addresses = tree.balances().len(),
+        root = %hex::encode(tree.root()),
+        "Built transparent balance tree"
+    );
+    Ok(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use zair_core::transparent::root_from_path;
+
+    use super::*;
+
+    fn address(byte: u8) -> String {
+        TransparentAddress::PublicKeyHash([byte; PUBKEY_HASH_SIZE])
+            .to_zcash_address(zcash_protocol::consensus::NetworkType::Test)
+            .encode()
+    }
+
+    #[test]
+    fn sums_rows_per_address_and_skips_p2sh() {
+        let p2sh = TransparentAddress::ScriptHash([9_u8; 20])
+            .to_zcash_address(zcash_protocol::consensus::NetworkType::Test)
+            .encode();
+        let listing = format!(
+            "address,zatoshis\n# utxos\n{a},100\n{b},5\n\n{a}, 50\n{p2sh},1000\n",
+            a = address(1),
+            b = address(2),
+        );
+
+        let tree = parse_transparent_balances(AirdropNetwork::Testnet, &listing).expect("listing");
+        assert_eq!(tree.balances().len(), 2);
+        assert_eq!(tree.total_balance(), Some(155));
+        let (index, entry) = tree.find(&[1_u8; PUBKEY_HASH_SIZE]).expect("address");
+        assert_eq!(entry.balance, 150);
+        let path = tree.path(index).expect("path");
+        assert_eq!(
+            root_from_path(entry.leaf_hash(), index, &path),
+            Some(tree.root())
+        );
+    }
+
+    #[test]
+    fn rejects_addresses_of_another_network() {
+        let mainnet = TransparentAddress::PublicKeyHash([1_u8; 20])
+            .to_zcash_address(zcash_protocol::consensus::NetworkType::Main)
+            .encode();
+        let err = parse_transparent_balances(AirdropNetwork::Testnet, &format!("{mainnet},1"))
+            .expect_err("mainnet address on testnet");
+        assert!(err.to_string().contains("Line 1"), "{err:?}");
+    }
+}
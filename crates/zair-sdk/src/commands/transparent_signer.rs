@@ -0,0 +1,205 @@
+//! In-memory signing of transparent balance claims.
+
+use eyre::{ContextCompat as _, ensure};
+use zair_core::base::Pool;
+use zair_core::schema::config::{AirdropConfiguration, ClaimWindow};
+use zair_core::schema::submission::TransparentSignedClaim;
+use zair_core::transparent::{BalanceTree, airdrop_nullifier};
+use zcash_transparent::keys::NonHardenedChildIndex;
+use zip32::Scope;
+
+use super::claim_keys::ClaimKeys;
+use super::signature_digest::{hash_transparent_claim_fields, signature_digest};
+use super::submission_auth::transparent::{self, TransparentAddressKey};
+use super::submission_messages::ResolvedMessageHashes;
+
+/// Default number of consecutive unfunded addresses after which address discovery stops.
+pub const DEFAULT_TRANSPARENT_GAP_LIMIT: u32 = 20;
+
+/// Sign a claim for every funded transparent address of the account in `keys`.
+///
+/// External and internal addresses are derived in order until `gap_limit` consecutive
+/// addresses have no balance in `balances`. Each claim carries the address's Merkle path and is
+/// signed with its secp256k1 key over a digest binding the transparent `target_id`, the claim
+/// window, the claim hash and the claim's message hash.
+///
+/// # Errors
+/// Returns an error if `airdrop_config` has no transparent pool, `balances` does not match its
+/// balance root, `gap_limit` is zero, a claim has no message or key derivation fails.
+pub fn sign_transparent_claims(
+    balances: &BalanceTree,
+    keys: &ClaimKeys,
+    airdrop_config: &AirdropConfiguration,
+    message_hashes: &ResolvedMessageHashes,
+    gap_limit: u32,
+) -> eyre::Result<Vec<TransparentSignedClaim>> {
+    let snapshot = airdrop_config
+        .transparent
+        .as_ref()
+        .context("Airdrop configuration has no transparent pool")?;
+    ensure!(
+        balances.root() == snapshot.balance_root,
+        "Transparent balances do not match the configured balance root"
+    );
+    ensure!(gap_limit > 0, "Transparent gap limit must be at least 1");
+
+    let mut claims = Vec::new();
+    for scope in [Scope::External, Scope::Internal] {
+        let mut index = Some(NonHardenedChildIndex::ZERO);
+        let mut gap = 0_u32;
+        while let Some(current) = index &&
+            gap < gap_limit
+        {
+            let key = transparent::derive_address_key(keys, scope, current)?;
+            if balances.find(&key.pubkey_hash).is_some() {
+                claims.push(sign_address_claim(
+                    balances,
+                    &key,
+                    &snapshot.target_id,
+                    airdrop_config.claim_window.as_ref(),
+                    message_hashes,
+                )?);
+                gap = 0;
+            } else {
+                gap = gap.saturating_add(1);
+            }
+            index = current.next();
+        }
+    }
+    Ok(claims)
+}
+
+fn sign_address_claim(
+    balances: &BalanceTree,
+    key: &TransparentAddressKey,
+    target_id: &str,
+    claim_window: Option<&ClaimWindow>,
+    message_hashes: &ResolvedMessageHashes,
+) -> eyre::Result<TransparentSignedClaim> {
+    let (leaf_index, entry) = balances
+        .find(&key.pubkey_hash)
+        .context("Transparent address is not in the balance tree")?;
+    let merkle_path = balances
+        .path(leaf_index)
+        .context("Missing Merkle path for transparent balance")?;
+    let airdrop_nullifier = airdrop_nullifier(target_id.as_bytes(), &key.pubkey_hash);
+    let message_hash = message_hashes
+        .transparent_hash(airdrop_nullifier)
+        .with_context(|| {
+            format!(
+                "No message provided for transparent claim with airdrop nullifier {airdrop_nullifier}. Provide --message or --messages entry"
+            )
+        })?;
+    let proof_hash = hash_transparent_claim_fields(
+        &key.pubkey,
+        entry.balance,
+        leaf_index,
+        &merkle_path,
+        airdrop_nullifier,
+    )?;
+    let digest = signature_digest(
+        Pool::Transparent,
+        target_id,
+        claim_window,
+        &proof_hash,
+        &message_hash,
+    )?;
+
+    Ok(TransparentSignedClaim {
+        pubkey: key.pubkey,
+        balance: entry.balance,
+        leaf_index,
+        merkle_path,
+        airdrop_nullifier,
+        proof_hash,
+        message_hash,
+        signature: transparent::sign_digest(key, &digest),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use zair_core::schema::config::{AirdropNetwork, TransparentSnapshot};
+    use zair_core::transparent::TransparentBalance;
+
+    use super::*;
+    use crate::commands::claim_verifier::{
+        TransparentClaimVerifier, TransparentInclusionError, check_transparent_inclusion,
+    };
+    use crate::commands::verification_report::{CheckOutcome, FailureReason};
+
+    const SEED: [u8; 64] = [7_u8; 64];
+    const MESSAGE_HASH: [u8; 32] = [3_u8; 32];
+
+    fn index(value: u32) -> NonHardenedChildIndex {
+        NonHardenedChildIndex::from_index(value).expect("non-hardened index")
+    }
+
+    #[test]
+    fn signed_claims_verify_against_the_snapshot() {
+        let keys = ClaimKeys::from_seed(AirdropNetwork::Testnet, &SEED, 0).expect("keys");
+        let funded = [
+            (Scope::External, index(0), 100),
+            (Scope::External, index(3), 40),
+            (Scope::Internal, index(1), 7),
+        ];
+        let mut balances: Vec<TransparentBalance> = funded
+            .iter()
+            .map(|(scope, child, balance)| TransparentBalance {
+                pubkey_hash: transparent::derive_address_key(&keys, *scope, *child)
+                    .expect("derive")
+                    .pubkey_hash,
+                balance: *balance,
+            })
+            .collect();
+        balances.push(TransparentBalance {
+            pubkey_hash: [9_u8; 20],
+            balance: 1_000,
+        });
+        let tree = BalanceTree::new(balances).expect("tree");
+        let snapshot = TransparentSnapshot {
+            balance_root: tree.root(),
+            address_count: 4,
+            total_balance: 1_147,
+            target_id: "ZAIRTEST:T".to_owned(),
+        };
+        let config = AirdropConfiguration::new(AirdropNetwork::Testnet, 1, None, None)
+            .with_transparent(snapshot.clone());
+        let messages =
+            ResolvedMessageHashes::new(Some(MESSAGE_HASH), BTreeMap::new(), BTreeMap::new());
+
+        // A gap limit of 2 stops external discovery before the address at index 3.
+        let claims =
+            sign_transparent_claims(&tree, &keys, &config, &messages, 2).expect("sign claims");
+        assert_eq!(claims.len(), 2);
+        let claims =
+            sign_transparent_claims(&tree, &keys, &config, &messages, 3).expect("sign claims");
+        assert_eq!(
+            claims.iter().map(|claim| claim.balance).collect::<Vec<_>>(),
+            vec![100, 40, 7]
+        );
+
+        let verifier = TransparentClaimVerifier::new(&snapshot);
+        for (index, claim) in claims.iter().enumerate() {
+            assert!(
+                verifier.check(index, claim, Some(MESSAGE_HASH)).is_valid(),
+                "claim verifies"
+            );
+        }
+
+        let mut inflated = claims.first().expect("claim").clone();
+        inflated.balance = 1_000;
+        assert_eq!(
+            check_transparent_inclusion(&inflated, &snapshot),
+            Err(TransparentInclusionError::NotInSnapshot)
+        );
+        assert_eq!(
+            verifier.check(0, &inflated, Some(MESSAGE_HASH)).proof,
+            Some(CheckOutcome::Invalid {
+                reason: FailureReason::NotInSnapshot
+            })
+        );
+    }
+}
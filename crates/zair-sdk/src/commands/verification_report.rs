@@ -0,0 +1,327 @@
+//! Per-claim verdicts of the `verify` commands and the verification service.
+//!
+//! Verification checks every claim instead of stopping at the first bad one: each claim gets a
+//! [`ClaimVerdict`] with the outcome of every check that was run on it, and each failed check
+//! carries a typed [`FailureReason`].
+
+#[cfg(feature = "native")]
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use zair_core::base::{Nullifier, Pool};
+
+/// Why a check failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FailureReason {
+    /// `proof_hash` does not commit to the claim's proof fields.
+    #[error("proof_hash does not match the claim's proof fields")]
+    ProofHashMismatch,
+    /// No message was provided for the claim.
+    #[error("no message provided for the claim")]
+    MissingMessage,
+    /// `message_hash` does not match the message provided for the claim.
+    #[error("message_hash does not match the claim's message")]
+    MessageHashMismatch,
+    /// The message sent for the claim exceeds the airdrop's message policy or is an invalid
+    /// structured claim message.
+    #[error("invalid claim message: {detail}")]
+    InvalidMessage {
+        /// Why the message is refused.
+        detail: String,
+    },
+    /// The spend authorization signature does not verify.
+    #[error("invalid spend authorization signature")]
+    InvalidSignature,
+    /// A claim field cannot be decoded.
+    #[error("malformed claim: {detail}")]
+    Malformed {
+        /// Decoding error.
+        detail: String,
+    },
+    /// The value commitment fields do not match the configured scheme.
+    #[error("commitment fields are incompatible with the configured scheme")]
+    SchemeMismatch,
+    /// Orchard params were not loaded, so the proof could not be checked.
+    #[error("Orchard params not loaded")]
+    MissingOrchardParams,
+    /// The zero-knowledge proof does not verify.
+    #[error("invalid proof: {detail}")]
+    InvalidProof {
+        /// Verifier error.
+        detail: String,
+    },
+    /// The airdrop nullifier is not derived from the claimed transparent address.
+    #[error("airdrop nullifier does not match the claim's address")]
+    NullifierMismatch,
+    /// The transparent address and balance are not a leaf of the balance tree.
+    #[error("address balance is not in the transparent snapshot")]
+    NotInSnapshot,
+    /// The airdrop nullifier is already recorded in the claim registry.
+    #[error("airdrop nullifier already claimed at {recorded_at}")]
+    AlreadyClaimed {
+        /// Unix timestamp (seconds) at which the earlier claim was recorded.
+        recorded_at: u64,
+    },
+}
+
+/// Outcome of one check on one claim.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CheckOutcome {
+    /// The check passed.
+    Valid,
+    /// The check failed.
+    Invalid {
+        /// Why the check failed.
+        reason: FailureReason,
+    },
+}
+
+impl CheckOutcome {
+    /// Whether the check passed.
+    #[must_use]
+    pub const fn is_valid(&self) -> bool {
+        matches!(self, Self::Valid)
+    }
+
+    /// Why the check failed, if it did.
+    #[must_use]
+    pub const fn reason(&self) -> Option<&FailureReason> {
+        match self {
+            Self::Valid => None,
+            Self::Invalid { reason } => Some(reason),
+        }
+    }
+}
+
+impl From<Result<(), FailureReason>> for CheckOutcome {
+    fn from(result: Result<(), FailureReason>) -> Self {
+        match result {
+            Ok(()) => Self::Valid,
+            Err(reason) => Self::Invalid { reason },
+        }
+    }
+}
+
+/// Outcomes of the checks run on one claim; checks a command does not run are `None`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimVerdict {
+    /// Pool of the claim.
+    pub pool: Pool,
+    /// Index of the claim within its pool.
+    pub index: usize,
+    /// Airdrop nullifier of the claim.
+    pub airdrop_nullifier: Nullifier,
+    /// Whether `proof_hash` commits to the claim's proof fields.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_hash: Option<CheckOutcome>,
+    /// Whether `message_hash` matches the message provided for the claim.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_hash: Option<CheckOutcome>,
+    /// Whether the spend authorization signature verifies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<CheckOutcome>,
+    /// Whether the zero-knowledge proof, or a transparent claim's inclusion proof, verifies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof: Option<CheckOutcome>,
+    /// Whether the airdrop nullifier is not yet recorded in the claim registry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry: Option<CheckOutcome>,
+}
+
+impl ClaimVerdict {
+    /// Verdict of a claim with no checks run yet.
+    pub(super) const fn new(pool: Pool, index: usize, airdrop_nullifier: Nullifier) -> Self {
+        Self {
+            pool,
+            index,
+            airdrop_nullifier,
+            proof_hash: None,
+            message_hash: None,
+            signature: None,
+            proof: None,
+            registry: None,
+        }
+    }
+
+    fn checks(&self) -> impl Iterator<Item = &CheckOutcome> {
+        [
+            &self.proof_hash,
+            &self.message_hash,
+            &self.signature,
+            &self.proof,
+            &self.registry,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Whether every check run on the claim passed.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.checks().all(CheckOutcome::is_valid)
+    }
+
+    /// Reasons of the failed checks, in check order.
+    pub fn failures(&self) -> impl Iterator<Item = &FailureReason> {
+        self.checks().filter_map(CheckOutcome::reason)
+    }
+
+    /// Take the outcomes of checks `other` ran and `self` did not.
+    #[cfg(feature = "native")]
+    fn merge(&mut self, other: Self) {
+        self.proof_hash = self.proof_hash.take().or(other.proof_hash);
+        self.message_hash = self.message_hash.take().or(other.message_hash);
+        self.signature = self.signature.take().or(other.signature);
+        self.proof = self.proof.take().or(other.proof);
+        self.registry = self.registry.take().or(other.registry);
+    }
+}
+
+/// Verdicts of a verification run, one per claim in submission order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    /// One verdict per claim.
+    pub claims: Vec<ClaimVerdict>,
+}
+
+impl VerificationReport {
+    /// Fold the verdicts of another check pass over the same claims into this report.
+    #[cfg(feature = "native")]
+    pub(super) fn merge(&mut self, other: Self) {
+        let mut positions: HashMap<(Pool, usize), usize> = self
+            .claims
+            .iter()
+            .enumerate()
+            .map(|(position, claim)| ((claim.pool, claim.index), position))
+            .collect();
+        for verdict in other.claims {
+            let key = (verdict.pool, verdict.index);
+            if let Some(claim) = positions
+                .get(&key)
+                .and_then(|&position| self.claims.get_mut(position))
+            {
+                claim.merge(verdict);
+            } else {
+                positions.insert(key, self.claims.len());
+                self.claims.push(verdict);
+            }
+        }
+    }
+
+    /// Indices of the claims of `pool` that passed every check.
+    #[must_use]
+    pub fn valid_indices(&self, pool: Pool) -> HashSet<usize> {
+        self.claims
+            .iter()
+            .filter(|claim| claim.pool == pool && claim.is_valid())
+            .map(|claim| claim.index)
+            .collect()
+    }
+
+    /// Whether every failed check is a claim already recorded in the registry.
+    #[must_use]
+    pub fn only_replays_failed(&self) -> bool {
+        self.claims
+            .iter()
+            .flat_map(ClaimVerdict::failures)
+            .all(|reason| matches!(reason, FailureReason::AlreadyClaimed { .. }))
+    }
+
+    /// Number of claims that failed at least one check.
+    #[must_use]
+    pub fn invalid_count(&self) -> usize {
+        self.claims.iter().filter(|claim| !claim.is_valid()).count()
+    }
+
+    /// Fail with [`VerificationFailed`] if any claim failed a check.
+    ///
+    /// # Errors
+    /// Returns [`VerificationFailed`] carrying the report, naming the failed items `subject`.
+    #[cfg(feature = "native")]
+    pub(super) fn into_result(self, subject: &'static str) -> Result<Self, VerificationFailed> {
+        if self.invalid_count() == 0 {
+            Ok(self)
+        } else {
+            Err(VerificationFailed {
+                report: self,
+                subject,
+            })
+        }
+    }
+}
+
+/// Error returned when at least one claim fails verification; carries the full report.
+#[derive(Debug, thiserror::Error)]
+#[error("{} {subject} failed verification", .report.invalid_count())]
+pub struct VerificationFailed {
+    /// Verdicts of every checked claim.
+    pub report: VerificationReport,
+    /// Name of the failed items, e.g. `proofs`.
+    pub subject: &'static str,
+}
+
+/// Response of the verification service: whether every claim passed, and its verdicts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    /// Whether every claim passed every check.
+    pub valid: bool,
+    /// One verdict per claim.
+    #[serde(flatten)]
+    pub report: VerificationReport,
+}
+
+impl From<VerificationReport> for VerifyResponse {
+    fn from(report: VerificationReport) -> Self {
+        Self {
+            valid: report.invalid_count() == 0,
+            report,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_combines_checks_of_the_same_claim() {
+        let nullifier = Nullifier::from([1_u8; 32]);
+        let verdict = |pool| ClaimVerdict::new(pool, 0, nullifier);
+        let mut signatures = VerificationReport {
+            claims: vec![
+                ClaimVerdict {
+                    signature: Some(CheckOutcome::Valid),
+                    ..verdict(Pool::Sapling)
+                },
+                ClaimVerdict {
+                    signature: Some(Err(FailureReason::InvalidSignature).into()),
+                    ..verdict(Pool::Orchard)
+                },
+            ],
+        };
+        let proofs = VerificationReport {
+            claims: [Pool::Sapling, Pool::Orchard]
+                .map(|pool| ClaimVerdict {
+                    proof: Some(CheckOutcome::Valid),
+                    ..verdict(pool)
+                })
+                .into(),
+        };
+
+        signatures.merge(proofs);
+
+        assert_eq!(signatures.claims.len(), 2);
+        assert_eq!(signatures.valid_indices(Pool::Sapling), HashSet::from([0]));
+        assert!(signatures.valid_indices(Pool::Orchard).is_empty());
+        assert_eq!(signatures.invalid_count(), 1);
+        let orchard = signatures.claims.get(1).expect("orchard verdict");
+        assert!(orchard.proof.as_ref().is_some_and(CheckOutcome::is_valid));
+        assert_eq!(
+            orchard.failures().collect::<Vec<_>>(),
+            vec![&FailureReason::InvalidSignature]
+        );
+    }
+}
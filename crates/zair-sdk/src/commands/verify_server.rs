@@ -0,0 +1,638 @@
+//! Claim verification HTTP service.
+//!
+//! The server loads the Sapling verifying key and Orchard Halo2 params once and checks every
+//! claim of a posted submission: `proof_hash`, message hash, spend-auth signature and proof.
+//! Like `verify run`, it keeps going after a failing claim and reports a typed verdict per claim.
+
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use eyre::{Context as _, ensure};
+use tokio::sync::Semaphore;
+use tracing::{info, instrument};
+use zair_core::schema::config::AirdropConfiguration;
+use zair_core::schema::verification::VerifyRequest;
+
+use super::airdrop_configuration::{chain_clock, read_airdrop_configuration};
+use super::claim_verifier::{
+    OrchardClaimVerifier, SaplingClaimVerifier, SubmissionVerifier, TransparentClaimVerifier,
+    VerifyRequestError,
+};
+use super::config_signature::TrustedOrganizer;
+use super::orchard_params::{OrchardParamsMode, load_or_prepare_orchard_params};
+use super::verification_report::VerifyResponse;
+
+/// Maximum number of claims accepted in a single verification request.
+pub const MAX_VERIFY_BATCH: usize = 256;
+
+/// Default number of submissions verified concurrently.
+pub const DEFAULT_MAX_CONCURRENT_VERIFICATIONS: usize = 4;
+
+/// Upper bound on a verification request body.
+const MAX_VERIFY_BODY_BYTES: usize = 32 * 1024 * 1024;
+
+/// Route answering liveness probes.
+const HEALTH_ROUTE: &str = "/v1/health";
+/// Route serving a [`VerifyResponse`] for a [`VerifyRequest`].
+const VERIFY_ROUTE: &str = "/v1/verify";
+/// Route serving Prometheus text-format counters.
+const METRICS_ROUTE: &str = "/metrics";
+
+/// Request and claim counters exposed on the metrics route.
+#[derive(Default)]
+struct VerifyMetrics {
+    requests: AtomicU64,
+    rejected_requests: AtomicU64,
+    valid_claims: AtomicU64,
+    invalid_claims: AtomicU64,
+}
+
+/// Verification service shared by all request handlers.
+pub struct VerifyService {
+    verifier: SubmissionVerifier,
+    permits: Arc<Semaphore>,
+    max_concurrent: usize,
+    metrics: VerifyMetrics,
+    current_height: Option<u64>,
+    current_time: Option<u64>,
+}
+
+/// Errors returned to verification clients for requests that cannot be verified at all.
+#[derive(Debug, thiserror::Error)]
+enum VerifyServiceError {
+    #[error("at most {MAX_VERIFY_BATCH} claims may be verified at once, got {0}")]
+    BatchTooLarge(usize),
+    #[error(transparent)]
+    Rejected(#[from] VerifyRequestError),
+    #[error("verification task failed: {0}")]
+    Internal(String),
+}
+
+impl IntoResponse for VerifyServiceError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Self::BatchTooLarge(_) | Self::Rejected(_) => StatusCode::BAD_REQUEST,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+impl VerifyService {
+    /// Load the verifying material for every pool enabled in `airdrop_config`.
+    ///
+    /// # Errors
+    /// Returns an error if the Sapling verifying key or Orchard params cannot be loaded, or
+    /// `max_concurrent` is zero.
+    pub async fn load(
+        airdrop_config: &AirdropConfiguration,
+        verifying_key_file: PathBuf,
+        orchard_params_file: PathBuf,
+        orchard_params_mode: OrchardParamsMode,
+        max_concurrent: usize,
+    ) -> eyre::Result<Self> {
+        ensure!(
+            max_concurrent > 0,
+            "Verification concurrency must be at least 1"
+        );
+        let sapling = match &airdrop_config.sapling {
+            Some(sapling) => {
+                let bytes = tokio::fs::read(&verifying_key_file)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "Failed to read Sapling verifying key {}",
+                            verifying_key_file.display()
+                        )
+                    })?;
+                let vk = bellman::groth16::VerifyingKey::read(&bytes[..])
+                    .context("Failed to read verifying key")?;
+                Some(SaplingClaimVerifier::new(sapling, &vk))
+            }
+            None => None,
+        };
+        let orchard = match &airdrop_config.orchard {
+            Some(orchard) => Some(OrchardClaimVerifier::new(
+                orchard,
+                load_or_prepare_orchard_params(
+                    orchard_params_file,
+                    orchard.value_commitment_scheme.into(),
+                    orchard_params_mode,
+                )
+                .await?,
+            )),
+            None => None,
+        };
+        let transparent = airdrop_config
+            .transparent
+            .as_ref()
+            .map(TransparentClaimVerifier::new);
+        let verifier = SubmissionVerifier::new(sapling, orchard, transparent)
+            .with_claim_window(airdrop_config.claim_window)
+            .with_message_policy(airdrop_config.message_policy);
+        ensure!(
+            !verifier.is_empty(),
+            "Airdrop configuration must enable at least one pool (sapling/orchard/transparent)"
+        );
+
+        Ok(Self {
+            verifier,
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            metrics: VerifyMetrics::default(),
+            current_height: None,
+            current_time: None,
+        })
+    }
+
+    /// Check a claim window against the operator's `current_height` and `current_time`, the
+    /// latter defaulting to the server clock.
+    ///
+    /// Clients cannot supply either: a height-bounded window rejects every claim unless
+    /// `current_height` is set here.
+    #[must_use]
+    pub const fn with_chain_clock(
+        mut self,
+        current_height: Option<u64>,
+        current_time: Option<u64>,
+    ) -> Self {
+        self.current_height = current_height;
+        self.current_time = current_time;
+        self
+    }
+
+    /// Verify every claim of a submission of at most [`MAX_VERIFY_BATCH`] claims.
+    ///
+    /// A claim window is checked against the server's own chain clock, never the request's.
+    fn verify(&self, request: &VerifyRequest) -> Result<VerifyResponse, VerifyServiceError> {
+        let claim_count = request
+            .submission
+            .sapling
+            .len()
+            .saturating_add(request.submission.orchard.len())
+            .saturating_add(request.submission.transparent.len());
+        if claim_count > MAX_VERIFY_BATCH {
+            return Err(VerifyServiceError::BatchTooLarge(claim_count));
+        }
+        let clock = chain_clock(self.current_height, self.current_time);
+        Ok(self.verifier.verify(request, &clock)?)
+    }
+
+    fn record(&self, response: &VerifyResponse) {
+        let invalid = response.report.invalid_count();
+        let valid = response.report.claims.len().saturating_sub(invalid);
+        self.metrics
+            .valid_claims
+            .fetch_add(u64::try_from(valid).unwrap_or(u64::MAX), Ordering::Relaxed);
+        self.metrics.invalid_claims.fetch_add(
+            u64::try_from(invalid).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn render_metrics(&self) -> String {
+        let in_flight = self
+            .max_concurrent
+            .saturating_sub(self.permits.available_permits());
+        let mut out = String::new();
+        for (name, kind, help, value) in [
+            (
+                "zair_verify_requests_total",
+                "counter",
+                "Verification requests received.",
+                self.metrics.requests.load(Ordering::Relaxed),
+            ),
+            (
+                "zair_verify_rejected_requests_total",
+                "counter",
+                "Verification requests rejected before any claim was checked.",
+                self.metrics.rejected_requests.load(Ordering::Relaxed),
+            ),
+            (
+                "zair_verify_valid_claims_total",
+                "counter",
+                "Claims that passed verification.",
+                self.metrics.valid_claims.load(Ordering::Relaxed),
+            ),
+            (
+                "zair_verify_invalid_claims_total",
+                "counter",
+                "Claims that failed verification.",
+                self.metrics.invalid_claims.load(Ordering::Relaxed),
+            ),
+            (
+                "zair_verify_in_flight",
+                "gauge",
+                "Submissions currently being verified.",
+                u64::try_from(in_flight).unwrap_or(u64::MAX),
+            ),
+        ] {
+            let _ = writeln!(
+                out,
+                "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}"
+            );
+        }
+        out
+    }
+}
+
+/// Build the HTTP routes for a verification service.
+pub fn verify_router(service: Arc<VerifyService>) -> Router {
+    Router::new()
+        .route(HEALTH_ROUTE, get(health_handler))
+        .route(VERIFY_ROUTE, post(verify_handler))
+        .route(METRICS_ROUTE, get(metrics_handler))
+        .layer(DefaultBodyLimit::max(MAX_VERIFY_BODY_BYTES))
+        .with_state(service)
+}
+
+async fn health_handler() -> &'static str {
+    "ok"
+}
+
+async fn metrics_handler(State(service): State<Arc<VerifyService>>) -> String {
+    service.render_metrics()
+}
+
+async fn verify_handler(
+    State(service): State<Arc<VerifyService>>,
+    Json(request): Json<VerifyRequest>,
+) -> Result<Json<VerifyResponse>, VerifyServiceError> {
+    service.metrics.requests.fetch_add(1, Ordering::Relaxed);
+    let permit = Arc::clone(&service.permits)
+        .acquire_owned()
+        .await
+        .map_err(|e| VerifyServiceError::Internal(e.to_string()))?;
+    let result = tokio::task::spawn_blocking({
+        let service = Arc::clone(&service);
+        move || {
+            let _permit = permit;
+            service.verify(&request)
+        }
+    })
+    .await
+    .map_err(|e| VerifyServiceError::Internal(e.to_string()))?;
+
+    match result {
+        Ok(response) => {
+            service.record(&response);
+            Ok(Json(response))
+        }
+        Err(err) => {
+            service
+                .metrics
+                .rejected_requests
+                .fetch_add(1, Ordering::Relaxed);
+            Err(err)
+        }
+    }
+}
+
+/// Serve claim verification over HTTP.
+///
+/// Exposes `GET /v1/health`, `POST /v1/verify` and `GET /metrics` until the process is
+/// stopped. At most `max_concurrent` submissions are verified at once; further requests wait.
+/// With `target`, claims are verified for that airdrop target of the configuration. With
+/// `trusted_organizer`, the server refuses to start on a configuration the organizer did not
+/// sign. A claim window is checked against `current_height` and `current_time`, the latter
+/// defaulting to the server clock.
+///
+/// # Errors
+/// Returns an error if the configuration or verifying material cannot be loaded or the
+/// listener cannot be bound.
+#[instrument(level = "debug", skip_all, fields(%listen))]
+#[allow(clippy::too_many_arguments, reason = "CLI entrypoint parameters")]
+pub async fn serve_verifier(
+    airdrop_configuration_file: PathBuf,
+    target: Option<String>,
+    trusted_organizer: Option<TrustedOrganizer>,
+    verifying_key_file: PathBuf,
+    orchard_params_file: PathBuf,
+    orchard_params_mode: OrchardParamsMode,
+    max_concurrent: usize,
+    current_height: Option<u64>,
+    current_time: Option<u64>,
+    listen: SocketAddr,
+) -> eyre::Result<()> {
+    let airdrop_config = read_airdrop_configuration(
+        &airdrop_configuration_file,
+        target.as_deref(),
+        trusted_organizer.as_ref(),
+    )
+    .await?;
+    let service = VerifyService::load(
+        &airdrop_config,
+        verifying_key_file,
+        orchard_params_file,
+        orchard_params_mode,
+        max_concurrent,
+    )
+    .await?
+    .with_chain_clock(current_height, current_time);
+
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to bind verification server to {listen}"))?;
+    info!(address = %listener.local_addr()?, max_concurrent, "Verification server listening");
+    axum::serve(listener, verify_router(Arc::new(service))).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::poly::commitment::Params;
+    use zair_core::base::{Nullifier, Pool};
+    use zair_core::schema::config::{
+        ClaimWindow, ClaimWindowUnit, MessagePolicy, OrchardGapTreeHash, OrchardSnapshot,
+        ValueCommitmentScheme,
+    };
+    use zair_core::schema::message::StructuredClaimMessage;
+    use zair_core::schema::submission::{ClaimSubmission, OrchardSignedClaim};
+    use zair_core::schema::verification::{ClaimMessage, ClaimMessageEntry};
+
+    use super::super::signature_digest::{hash_message, hash_orchard_signed_claim_proof};
+    use super::super::verification_report::{CheckOutcome, ClaimVerdict, FailureReason};
+    use super::*;
+
+    fn service() -> VerifyService {
+        let orchard = OrchardSnapshot {
+            note_commitment_root: [0_u8; 32],
+            nullifier_gap_root: [0_u8; 32],
+            target_id: "ZAIRTEST:O".to_owned(),
+            value_commitment_scheme: ValueCommitmentScheme::Native,
+            nullifier_gap_tree_hash: OrchardGapTreeHash::Sinsemilla,
+        };
+        VerifyService {
+            verifier: SubmissionVerifier::new(
+                None,
+                Some(OrchardClaimVerifier::new(
+                    &orchard,
+                    Arc::new(Params::new(4)),
+                )),
+                None,
+            ),
+            permits: Arc::new(Semaphore::new(1)),
+            max_concurrent: 1,
+            metrics: VerifyMetrics::default(),
+            current_height: None,
+            current_time: None,
+        }
+    }
+
+    fn orchard_claim(byte: u8) -> OrchardSignedClaim {
+        let mut claim = OrchardSignedClaim {
+            zkproof: vec![1_u8, 2, 3],
+            rk: [7_u8; 32],
+            cv: Some([9_u8; 32]),
+            cv_sha256: None,
+            airdrop_nullifier: Nullifier::from([byte; 32]),
+            proof_hash: [0_u8; 32],
+            message_hash: hash_message(b"claim"),
+            spend_auth_sig: [0_u8; 64],
+        };
+        claim.proof_hash = hash_orchard_signed_claim_proof(&claim).expect("proof hash");
+        claim
+    }
+
+    fn orchard_verdict(response: &VerifyResponse, index: usize) -> &ClaimVerdict {
+        response
+            .report
+            .claims
+            .iter()
+            .find(|verdict| verdict.pool == Pool::Orchard && verdict.index == index)
+            .expect("verdict per claim")
+    }
+
+    #[test]
+    fn every_claim_gets_a_verdict() {
+        let mut tampered = orchard_claim(1);
+        tampered.proof_hash = [0_u8; 32];
+        let unmessaged = orchard_claim(2);
+        let request = VerifyRequest {
+            submission: ClaimSubmission {
+                sapling: vec![],
+                orchard: vec![tampered, unmessaged],
+                transparent: vec![],
+            },
+            message: None,
+            sapling_messages: vec![],
+            orchard_messages: vec![ClaimMessageEntry {
+                airdrop_nullifier: Nullifier::from([1_u8; 32]),
+                message: ClaimMessage::Bytes(b"claim".to_vec()),
+            }],
+            transparent_messages: vec![],
+        };
+
+        let response = service().verify(&request).expect("request should verify");
+        assert!(!response.valid);
+        let [tampered, unmessaged] = [0, 1].map(|index| orchard_verdict(&response, index));
+        assert_eq!(
+            tampered.proof_hash.as_ref().and_then(CheckOutcome::reason),
+            Some(&FailureReason::ProofHashMismatch)
+        );
+        assert_eq!(tampered.message_hash, Some(CheckOutcome::Valid));
+        assert!(tampered.signature.is_some() && tampered.proof.is_some());
+        assert_eq!(unmessaged.proof_hash, Some(CheckOutcome::Valid));
+        assert_eq!(
+            unmessaged
+                .message_hash
+                .as_ref()
+                .and_then(CheckOutcome::reason),
+            Some(&FailureReason::MissingMessage)
+        );
+    }
+
+    #[test]
+    fn structured_messages_with_invalid_recipients_fail_their_claim() {
+        let message = StructuredClaimMessage::new(
+            "namada.5f5de2dd1b88cba30586420".to_owned(),
+            "namada".to_owned(),
+            "tnam1notanaddress".to_owned(),
+            None,
+        );
+        let request = VerifyRequest {
+            submission: ClaimSubmission {
+                sapling: vec![],
+                orchard: vec![orchard_claim(1), orchard_claim(2)],
+                transparent: vec![],
+            },
+            message: Some(ClaimMessage::Bytes(b"claim".to_vec())),
+            sapling_messages: vec![],
+            orchard_messages: vec![ClaimMessageEntry {
+                airdrop_nullifier: Nullifier::from([1_u8; 32]),
+                message: ClaimMessage::Bytes(
+                    serde_json::to_vec(&message).expect("serialize message"),
+                ),
+            }],
+            transparent_messages: vec![],
+        };
+
+        let response = service().verify(&request).expect("request should verify");
+        let refused = orchard_verdict(&response, 0)
+            .message_hash
+            .as_ref()
+            .and_then(CheckOutcome::reason);
+        assert!(
+            matches!(
+                refused,
+                Some(FailureReason::InvalidMessage { detail }) if detail.starts_with("invalid namada")
+            ),
+            "{refused:?}"
+        );
+        assert_eq!(
+            orchard_verdict(&response, 1).message_hash,
+            Some(CheckOutcome::Valid),
+            "a plain message is not checked"
+        );
+    }
+
+    #[test]
+    fn messages_above_the_policy_limit_fail_their_claim() {
+        let mut service = service();
+        service.verifier = service
+            .verifier
+            .with_message_policy(MessagePolicy { max_bytes: Some(4) });
+        let request = VerifyRequest {
+            submission: ClaimSubmission {
+                sapling: vec![],
+                orchard: vec![orchard_claim(1), orchard_claim(2)],
+                transparent: vec![],
+            },
+            message: Some(ClaimMessage::Bytes(b"claim".to_vec())),
+            sapling_messages: vec![],
+            orchard_messages: vec![ClaimMessageEntry {
+                airdrop_nullifier: Nullifier::from([2_u8; 32]),
+                message: ClaimMessage::Hash(hash_message(b"claim")),
+            }],
+            transparent_messages: vec![],
+        };
+
+        let response = service.verify(&request).expect("request should verify");
+        assert_eq!(
+            orchard_verdict(&response, 0)
+                .message_hash
+                .as_ref()
+                .and_then(CheckOutcome::reason),
+            Some(&FailureReason::InvalidMessage {
+                detail: "message is 5 bytes, above the 4 byte limit of the airdrop configuration"
+                    .to_owned()
+            })
+        );
+        assert_eq!(
+            orchard_verdict(&response, 1).message_hash,
+            Some(CheckOutcome::Valid),
+            "a message hash cannot be measured"
+        );
+    }
+
+    #[test]
+    fn claim_window_uses_the_server_height() {
+        let mut service = service();
+        service.verifier = service.verifier.with_claim_window(Some(ClaimWindow {
+            unit: ClaimWindowUnit::Height,
+            start: Some(100),
+            end: None,
+        }));
+        let request = VerifyRequest {
+            submission: ClaimSubmission {
+                sapling: vec![],
+                orchard: vec![orchard_claim(1)],
+                transparent: vec![],
+            },
+            message: Some(ClaimMessage::Bytes(b"claim".to_vec())),
+            sapling_messages: vec![],
+            orchard_messages: vec![],
+            transparent_messages: vec![],
+        };
+
+        let err = service
+            .verify(&request)
+            .expect_err("the server has no height to check the window with");
+        assert!(err.to_string().contains("no current height"), "{err}");
+        let service = service.with_chain_clock(Some(150), None);
+        service
+            .verify(&request)
+            .expect("the window is open at the server's height");
+    }
+
+    #[tokio::test]
+    async fn router_serves_health_verify_and_metrics() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("listener should bind");
+        let url = format!("http://{}", listener.local_addr().expect("local address"));
+        tokio::spawn(
+            async move { axum::serve(listener, verify_router(Arc::new(service()))).await },
+        );
+
+        let (health, rejected, verified, metrics) = tokio::task::spawn_blocking(move || {
+            let health = ureq::get(&format!("{url}{HEALTH_ROUTE}"))
+                .call()
+                .expect("health should respond")
+                .body_mut()
+                .read_to_string()
+                .expect("health body");
+            let empty = VerifyRequest {
+                submission: ClaimSubmission {
+                    sapling: vec![],
+                    orchard: vec![],
+                    transparent: vec![],
+                },
+                message: None,
+                sapling_messages: vec![],
+                orchard_messages: vec![],
+                transparent_messages: vec![],
+            };
+            let rejected = ureq::post(&format!("{url}{VERIFY_ROUTE}"))
+                .send_json(&empty)
+                .is_err();
+            let verified = ureq::post(&format!("{url}{VERIFY_ROUTE}"))
+                .send_json(&VerifyRequest {
+                    submission: ClaimSubmission {
+                        sapling: vec![],
+                        orchard: vec![orchard_claim(3)],
+                        transparent: vec![],
+                    },
+                    message: Some(ClaimMessage::Hash([0_u8; 32])),
+                    ..empty
+                })
+                .expect("verify should respond")
+                .body_mut()
+                .read_json::<VerifyResponse>()
+                .expect("verify response");
+            let metrics = ureq::get(&format!("{url}{METRICS_ROUTE}"))
+                .call()
+                .expect("metrics should respond")
+                .body_mut()
+                .read_to_string()
+                .expect("metrics body");
+            (health, rejected, verified, metrics)
+        })
+        .await
+        .expect("client task");
+
+        assert_eq!(health, "ok");
+        assert!(rejected, "empty submission must be rejected");
+        assert!(!verified.valid);
+        assert!(
+            metrics.contains("zair_verify_requests_total 2"),
+            "{metrics}"
+        );
+        assert!(
+            metrics.contains("zair_verify_rejected_requests_total 1"),
+            "{metrics}"
+        );
+        assert!(
+            metrics.contains("zair_verify_invalid_claims_total 1"),
+            "{metrics}"
+        );
+    }
+}
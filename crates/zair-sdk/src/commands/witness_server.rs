@@ -0,0 +1,754 @@
+//! Gap-tree witness server and the matching remote-witness client.
+//!
+//! The server holds the snapshot nullifiers and memory-mapped gap trees and answers
+//! `(left, right)` bounds, leaf position and Merkle path for a batch of nullifiers, so thin
+//! clients never download the full files. Clients trust nothing from the server: every
+//! witness is checked against `nullifier_gap_root` from the airdrop configuration.
+//!
+//! The server learns which nullifiers are queried, so claimants should only use a server run
+//! by a party they are willing to reveal their nullifiers to.
+
+use std::collections::{BTreeSet, HashMap};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use eyre::{Context as _, ensure};
+use tracing::{info, instrument};
+use zair_core::base::{Nullifier, Pool, SanitiseNullifiers};
+use zair_core::container::ContainerOptions;
+use zair_core::schema::config::{AirdropConfiguration, OrchardGapTreeHash};
+use zair_core::schema::witness::{
+    NullifierWitness, WitnessRequest, WitnessResponse, WitnessServerInfo,
+};
+use zair_nonmembership::{
+    GapLocator, MerklePathError, OrchardGapTree, OrchardPoseidonGapTree, SaplingGapTree,
+    TreePosition, nullifier_in_gap,
+};
+
+use super::airdrop_claim::{
+    DEFAULT_ORCHARD_GAP_TREE_FILE, DEFAULT_ORCHARD_SNAPSHOT_FILE, DEFAULT_SAPLING_GAP_TREE_FILE,
+    DEFAULT_SAPLING_SNAPSHOT_FILE, LoadedPoolData, PoolMerkleTree, load_nullifiers_from_file,
+    map_dense_tree,
+};
+use super::airdrop_configuration::read_airdrop_configuration;
+use super::config_signature::TrustedOrganizer;
+
+/// Maximum number of nullifiers accepted in a single witness request.
+pub const MAX_WITNESS_BATCH: usize = 1024;
+
+/// Time allowed for one witness batch request, from connecting to reading the response.
+///
+/// A stalled witness server would otherwise hang `claim prepare` forever.
+const WITNESS_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Route serving [`WitnessServerInfo`].
+const INFO_ROUTE: &str = "/v1/info";
+/// Route serving [`WitnessResponse`] for a [`WitnessRequest`].
+const WITNESSES_ROUTE: &str = "/v1/witnesses";
+
+/// Gap-tree state for one served pool.
+struct ServedPool {
+    locator: GapLocator,
+    tree: PoolMerkleTree,
+}
+
+/// In-memory witness service shared by all request handlers.
+pub struct WitnessService {
+    info: WitnessServerInfo,
+    sapling: Option<ServedPool>,
+    orchard: Option<ServedPool>,
+}
+
+/// Errors returned to witness clients.
+#[derive(Debug, thiserror::Error)]
+enum WitnessServiceError {
+    #[error("{0} pool is not served")]
+    PoolNotServed(Pool),
+    #[error("at most {MAX_WITNESS_BATCH} nullifiers may be requested at once, got {0}")]
+    BatchTooLarge(usize),
+    #[error("{0}")]
+    InvalidNullifier(MerklePathError),
+    #[error("{0}")]
+    Tree(MerklePathError),
+}
+
+impl IntoResponse for WitnessServiceError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Self::PoolNotServed(_) => StatusCode::NOT_FOUND,
+            Self::BatchTooLarge(_) | Self::InvalidNullifier(_) => StatusCode::BAD_REQUEST,
+            Self::Tree(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+impl WitnessService {
+    /// Load the snapshot and gap tree of every pool enabled in `airdrop_config`.
+    ///
+    /// Missing paths fall back to the default `snapshot-*.bin` / `gaptree-*.bin` files.
+    ///
+    /// # Errors
+    /// Returns an error if a file cannot be loaded, does not match the configuration's
+    /// network and height, or its gap-tree root differs from the configured root.
+    pub async fn load(
+        airdrop_config: &AirdropConfiguration,
+        sapling_snapshot_nullifiers: Option<PathBuf>,
+        orchard_snapshot_nullifiers: Option<PathBuf>,
+        sapling_gap_tree_file: Option<PathBuf>,
+        orchard_gap_tree_file: Option<PathBuf>,
+    ) -> eyre::Result<Self> {
+        let options = ContainerOptions::new(airdrop_config.network, airdrop_config.snapshot_height);
+        let orchard_gap_tree_hash = airdrop_config
+            .orchard
+            .as_ref()
+            .map(|orchard| orchard.nullifier_gap_tree_hash)
+            .unwrap_or_default();
+
+        let sapling = match &airdrop_config.sapling {
+            Some(sapling) => Some(
+                load_served_pool(
+                    Pool::Sapling,
+                    sapling_snapshot_nullifiers
+                        .unwrap_or_else(|| PathBuf::from(DEFAULT_SAPLING_SNAPSHOT_FILE)),
+                    sapling_gap_tree_file
+                        .unwrap_or_else(|| PathBuf::from(DEFAULT_SAPLING_GAP_TREE_FILE)),
+                    OrchardGapTreeHash::default(),
+                    sapling.nullifier_gap_root,
+                    &options,
+                )
+                .await?,
+            ),
+            None => None,
+        };
+        let orchard = match &airdrop_config.orchard {
+            Some(orchard) => Some(
+                load_served_pool(
+                    Pool::Orchard,
+                    orchard_snapshot_nullifiers
+                        .unwrap_or_else(|| PathBuf::from(DEFAULT_ORCHARD_SNAPSHOT_FILE)),
+                    orchard_gap_tree_file
+                        .unwrap_or_else(|| PathBuf::from(DEFAULT_ORCHARD_GAP_TREE_FILE)),
+                    orchard_gap_tree_hash,
+                    orchard.nullifier_gap_root,
+                    &options,
+                )
+                .await?,
+            ),
+            None => None,
+        };
+        ensure!(
+            sapling.is_some() || orchard.is_some(),
+            "Airdrop configuration must enable at least one pool (sapling/orchard)"
+        );
+
+        Ok(Self {
+            info: WitnessServerInfo {
+                network: airdrop_config.network,
+                snapshot_height: airdrop_config.snapshot_height,
+                sapling_root: sapling.as_ref().map(|pool| pool.tree.root_bytes()),
+                orchard_root: orchard.as_ref().map(|pool| pool.tree.root_bytes()),
+                orchard_gap_tree_hash,
+            },
+            sapling,
+            orchard,
+        })
+    }
+
+    /// Description of the served snapshot.
+    #[must_use]
+    pub const fn info(&self) -> &WitnessServerInfo {
+        &self.info
+    }
+
+    fn witnesses(&self, request: &WitnessRequest) -> Result<WitnessResponse, WitnessServiceError> {
+        let served = match request.pool {
+            Pool::Sapling => self.sapling.as_ref(),
+            Pool::Orchard => self.orchard.as_ref(),
+            Pool::Transparent => None,
+        }
+        .ok_or(WitnessServiceError::PoolNotServed(request.pool))?;
+        if request.nullifiers.len() > MAX_WITNESS_BATCH {
+            return Err(WitnessServiceError::BatchTooLarge(request.nullifiers.len()));
+        }
+
+        let witness = |position: TreePosition| {
+            let leaf_position = u64::from(position.leaf_position);
+            let merkle_path = served
+                .tree
+                .witness_bytes(leaf_position)
+                .map_err(WitnessServiceError::Tree)?;
+            Ok::<_, WitnessServiceError>(NullifierWitness {
+                nullifier: position.nullifier,
+                left_bound: position.left_bound,
+                right_bound: position.right_bound,
+                leaf_position,
+                merkle_path,
+            })
+        };
+
+        let mut witnesses = Vec::with_capacity(request.nullifiers.len());
+        let mut spent = Vec::new();
+        for nullifier in &request.nullifiers {
+            if let Some(position) = served
+                .locator
+                .locate(*nullifier)
+                .map_err(WitnessServiceError::InvalidNullifier)?
+            {
+                witnesses.push(witness(position)?);
+                continue;
+            }
+            let position = served
+                .locator
+                .locate_spent(*nullifier)
+                .map_err(WitnessServiceError::InvalidNullifier)?
+                .ok_or(WitnessServiceError::Tree(MerklePathError::Unexpected(
+                    "nullifier is neither in a gap nor in the snapshot",
+                )))?;
+            spent.push(witness(position)?);
+        }
+
+        Ok(WitnessResponse {
+            pool: request.pool,
+            root: served.tree.root_bytes(),
+            witnesses,
+            spent,
+        })
+    }
+}
+
+async fn load_served_pool(
+    pool: Pool,
+    snapshot_nullifiers: PathBuf,
+    gap_tree_file: PathBuf,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    expected_root: [u8; 32],
+    options: &ContainerOptions,
+) -> eyre::Result<ServedPool> {
+    let chain_nullifiers = load_nullifiers_from_file(&snapshot_nullifiers, pool, options).await?;
+    let locator = GapLocator::new(pool, &chain_nullifiers)
+        .with_context(|| format!("Failed to index {pool} snapshot nullifiers"))?;
+    let tree = map_dense_tree(&gap_tree_file, pool, orchard_gap_tree_hash)
+        .with_context(|| format!("Failed to load {pool} gap-tree {}", gap_tree_file.display()))?;
+    if let Some(header) = tree.header() {
+        header.ensure_snapshot(options).with_context(|| {
+            format!(
+                "{pool} gap-tree {} does not match the airdrop configuration",
+                gap_tree_file.display()
+            )
+        })?;
+    }
+    ensure!(
+        tree.root_bytes() == expected_root,
+        "{pool} gap-tree root does not match the airdrop configuration"
+    );
+
+    info!(%pool, count = locator.len(), "Serving gap-tree witnesses");
+    Ok(ServedPool { locator, tree })
+}
+
+/// Build the HTTP routes for a witness service.
+pub fn witness_router(service: Arc<WitnessService>) -> Router {
+    Router::new()
+        .route(INFO_ROUTE, get(info_handler))
+        .route(WITNESSES_ROUTE, post(witnesses_handler))
+        .with_state(service)
+}
+
+async fn info_handler(State(service): State<Arc<WitnessService>>) -> Json<WitnessServerInfo> {
+    Json(service.info.clone())
+}
+
+async fn witnesses_handler(
+    State(service): State<Arc<WitnessService>>,
+    Json(request): Json<WitnessRequest>,
+) -> Result<Json<WitnessResponse>, WitnessServiceError> {
+    // Witness paths are read from memory-mapped files, which may block on page faults.
+    tokio::task::spawn_blocking(move || service.witnesses(&request))
+        .await
+        .map_err(|e| WitnessServiceError::Tree(MerklePathError::Io(e.to_string())))?
+        .map(Json)
+}
+
+/// Serve gap-tree witnesses over HTTP.
+///
+/// Exposes `GET /v1/info` and `POST /v1/witnesses` until the process is stopped. With
+/// `trusted_organizer`, the server refuses to start on a configuration the organizer did not
+/// sign.
+///
+/// # Errors
+/// Returns an error if the configuration or pool files cannot be loaded or the listener
+/// cannot be bound.
+#[instrument(level = "debug", skip_all, fields(%listen))]
+pub async fn serve_witnesses(
+    airdrop_configuration_file: PathBuf,
+    trusted_organizer: Option<TrustedOrganizer>,
+    sapling_snapshot_nullifiers: Option<PathBuf>,
+    orchard_snapshot_nullifiers: Option<PathBuf>,
+    sapling_gap_tree_file: Option<PathBuf>,
+    orchard_gap_tree_file: Option<PathBuf>,
+    listen: SocketAddr,
+) -> eyre::Result<()> {
+    let airdrop_config = read_airdrop_configuration(
+        &airdrop_configuration_file,
+        None,
+        trusted_organizer.as_ref(),
+    )
+    .await?;
+    let service = WitnessService::load(
+        &airdrop_config,
+        sapling_snapshot_nullifiers,
+        orchard_snapshot_nullifiers,
+        sapling_gap_tree_file,
+        orchard_gap_tree_file,
+    )
+    .await?;
+
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to bind witness server to {listen}"))?;
+    info!(address = %listener.local_addr()?, "Witness server listening");
+    axum::serve(listener, witness_router(Arc::new(service))).await?;
+    Ok(())
+}
+
+/// Gap witnesses fetched from a witness server or spliced from shards, keyed by leaf position.
+pub struct RemoteWitnesses {
+    root: [u8; 32],
+    paths: HashMap<u64, Vec<[u8; 32]>>,
+}
+
+impl RemoteWitnesses {
+    /// Gap-tree root every fetched witness was verified against.
+    #[must_use]
+    pub const fn root_bytes(&self) -> [u8; 32] {
+        self.root
+    }
+
+    /// Merkle path for a fetched leaf position.
+    ///
+    /// # Errors
+    /// Returns an error if no witness was fetched for `leaf_position`.
+    pub fn witness_bytes(&self, leaf_position: u64) -> Result<Vec<[u8; 32]>, MerklePathError> {
+        self.paths
+            .get(&leaf_position)
+            .cloned()
+            .ok_or(MerklePathError::NotMarked(leaf_position))
+    }
+}
+
+/// Fetch and verify gap witnesses for the user's nullifiers from a witness server.
+///
+/// Nullifiers the server proves spent are skipped, matching how local trees skip them.
+pub(super) async fn fetch_remote_pool_data(
+    server_url: &str,
+    pool: Pool,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    expected_root: [u8; 32],
+    user_nullifiers: &SanitiseNullifiers,
+) -> eyre::Result<LoadedPoolData> {
+    let url = format!("{}{WITNESSES_ROUTE}", server_url.trim_end_matches('/'));
+    info!(%pool, url, count = user_nullifiers.len(), "Fetching gap-tree witnesses");
+
+    let mut responses = Vec::new();
+    for batch in user_nullifiers.chunks(MAX_WITNESS_BATCH) {
+        let request = WitnessRequest {
+            pool,
+            nullifiers: batch.to_vec(),
+        };
+        let url = url.clone();
+        let response = tokio::task::spawn_blocking(move || {
+            post_witness_batch(&url, &request, WITNESS_REQUEST_TIMEOUT)
+        })
+        .await?
+        .with_context(|| format!("Failed to fetch {pool} witnesses from {server_url}"))?;
+        responses.push(response);
+    }
+
+    verify_remote_witnesses(
+        pool,
+        orchard_gap_tree_hash,
+        expected_root,
+        user_nullifiers,
+        responses,
+    )
+}
+
+/// Post one witness batch to `url`, giving up after `timeout`.
+fn post_witness_batch(
+    url: &str,
+    request: &WitnessRequest,
+    timeout: Duration,
+) -> Result<WitnessResponse, ureq::Error> {
+    ureq::post(url)
+        .config()
+        .timeout_global(Some(timeout))
+        .build()
+        .send_json(request)?
+        .body_mut()
+        .read_json::<WitnessResponse>()
+}
+
+/// Recomputes a gap-tree root from `(left_bound, right_bound, leaf_position, path)`.
+type RootFromWitness =
+    fn(&Nullifier, &Nullifier, u64, &[[u8; 32]]) -> Result<[u8; 32], MerklePathError>;
+
+/// Check fetched witnesses against the configured root and the requested nullifiers.
+///
+/// Every requested nullifier must come back either with a gap witness or with a proof that it
+/// is spent; a server that leaves one out could otherwise hide a claimable note.
+pub(super) fn verify_remote_witnesses(
+    pool: Pool,
+    orchard_gap_tree_hash: OrchardGapTreeHash,
+    expected_root: [u8; 32],
+    user_nullifiers: &SanitiseNullifiers,
+    responses: Vec<WitnessResponse>,
+) -> eyre::Result<LoadedPoolData> {
+    let root_from_witness: RootFromWitness = match (pool, orchard_gap_tree_hash) {
+        (Pool::Sapling, _) => SaplingGapTree::root_from_witness,
+        (Pool::Orchard, OrchardGapTreeHash::Sinsemilla) => OrchardGapTree::root_from_witness,
+        (Pool::Orchard, OrchardGapTreeHash::Poseidon) => OrchardPoseidonGapTree::root_from_witness,
+        (Pool::Transparent, _) => return Err(MerklePathError::NoGapTree(pool).into()),
+    };
+
+    let mut positions = Vec::new();
+    let mut paths = HashMap::new();
+    let mut resolved = BTreeSet::new();
+    for response in responses {
+        ensure!(
+            response.pool == pool,
+            "Fetched witnesses are for {} instead of {pool}",
+            response.pool
+        );
+        ensure!(
+            response.root == expected_root,
+            "Fetched {pool} gap-tree root does not match the airdrop configuration"
+        );
+        for witness in response.witnesses {
+            let position = TreePosition::new(
+                witness.nullifier,
+                usize::try_from(witness.leaf_position)?,
+                witness.left_bound,
+                witness.right_bound,
+            )?;
+            ensure!(
+                nullifier_in_gap(pool, &position),
+                "Fetched gap does not contain nullifier {}",
+                witness.nullifier
+            );
+            check_remote_witness(
+                pool,
+                root_from_witness,
+                expected_root,
+                user_nullifiers,
+                &witness,
+            )?;
+            ensure!(
+                resolved.insert(witness.nullifier),
+                "Fetched two witnesses for nullifier {}",
+                witness.nullifier
+            );
+            positions.push(position);
+            paths.insert(witness.leaf_position, witness.merkle_path);
+        }
+        for witness in response.spent {
+            ensure!(
+                witness.right_bound == witness.nullifier,
+                "Fetched spent proof does not end at nullifier {}",
+                witness.nullifier
+            );
+            check_remote_witness(
+                pool,
+                root_from_witness,
+                expected_root,
+                user_nullifiers,
+                &witness,
+            )?;
+            ensure!(
+                resolved.insert(witness.nullifier),
+                "Fetched two witnesses for nullifier {}",
+                witness.nullifier
+            );
+        }
+    }
+
+    let missing = user_nullifiers
+        .iter()
+        .filter(|nullifier| !resolved.contains(*nullifier))
+        .count();
+    ensure!(
+        missing == 0,
+        "Witness source left out {missing} requested {pool} nullifiers; refusing to treat them as spent"
+    );
+    let spent = resolved.len().saturating_sub(positions.len());
+    if spent > 0 {
+        info!(%pool, count = spent, "Nullifiers proven spent in the snapshot");
+    }
+    info!(%pool, count = positions.len(), "Verified remote gap-tree witnesses");
+
+    Ok(LoadedPoolData {
+        tree: PoolMerkleTree::Remote(RemoteWitnesses {
+            root: expected_root,
+            paths,
+        }),
+        user_nullifiers: positions,
+    })
+}
+
+/// Check that `witness` answers a requested nullifier and opens to `expected_root`.
+fn check_remote_witness(
+    pool: Pool,
+    root_from_witness: RootFromWitness,
+    expected_root: [u8; 32],
+    user_nullifiers: &SanitiseNullifiers,
+    witness: &NullifierWitness,
+) -> eyre::Result<()> {
+    ensure!(
+        requested(user_nullifiers, pool, &witness.nullifier),
+        "Fetched a witness for unrequested nullifier {}",
+        witness.nullifier
+    );
+    let root = root_from_witness(
+        &witness.left_bound,
+        &witness.right_bound,
+        witness.leaf_position,
+        &witness.merkle_path,
+    )?;
+    ensure!(
+        root == expected_root,
+        "Witness for {pool} nullifier {} does not open to the configured gap-tree root",
+        witness.nullifier
+    );
+    Ok(())
+}
+
+/// Whether `nullifier` (as canonically echoed by the server) was part of the request.
+fn requested(user_nullifiers: &SanitiseNullifiers, pool: Pool, nullifier: &Nullifier) -> bool {
+    match pool {
+        Pool::Sapling => user_nullifiers.binary_search(nullifier).is_ok(),
+        Pool::Orchard => user_nullifiers.contains(nullifier),
+        Pool::Transparent => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use group::ff::PrimeField as _;
+    use pasta_curves::pallas;
+    use zair_core::schema::config::AirdropNetwork;
+
+    use super::*;
+
+    fn chain(pool: Pool) -> SanitiseNullifiers {
+        if pool == Pool::Orchard {
+            SanitiseNullifiers::new(vec![
+                Nullifier::from(pallas::Base::from(1_u64).to_repr()),
+                Nullifier::from(pallas::Base::from(5_u64).to_repr()),
+            ])
+        } else {
+            SanitiseNullifiers::new(vec![
+                Nullifier::from([1_u8; 32]),
+                Nullifier::from([5_u8; 32]),
+            ])
+        }
+    }
+
+    fn served_pool(pool: Pool) -> ServedPool {
+        let chain = chain(pool);
+        let tree = match pool {
+            Pool::Sapling => PoolMerkleTree::Sapling(
+                SaplingGapTree::from_nullifiers(&chain).expect("sapling tree should build"),
+            ),
+            Pool::Orchard => PoolMerkleTree::Orchard(
+                OrchardGapTree::from_nullifiers_with_progress(&chain, |_, _| {})
+                    .expect("orchard tree should build"),
+            ),
+            Pool::Transparent => panic!("transparent pool has no gap tree"),
+        };
+        ServedPool {
+            locator: GapLocator::new(pool, &chain).expect("locator should build"),
+            tree,
+        }
+    }
+
+    fn service() -> WitnessService {
+        let sapling = served_pool(Pool::Sapling);
+        let orchard = served_pool(Pool::Orchard);
+        WitnessService {
+            info: WitnessServerInfo {
+                network: AirdropNetwork::Testnet,
+                snapshot_height: 1,
+                sapling_root: Some(sapling.tree.root_bytes()),
+                orchard_root: Some(orchard.tree.root_bytes()),
+                orchard_gap_tree_hash: OrchardGapTreeHash::Sinsemilla,
+            },
+            sapling: Some(sapling),
+            orchard: Some(orchard),
+        }
+    }
+
+    fn user_nullifiers(pool: Pool) -> SanitiseNullifiers {
+        if pool == Pool::Orchard {
+            SanitiseNullifiers::new(vec![
+                Nullifier::from(pallas::Base::from(3_u64).to_repr()),
+                Nullifier::from(pallas::Base::from(5_u64).to_repr()),
+            ])
+        } else {
+            SanitiseNullifiers::new(vec![
+                Nullifier::from([3_u8; 32]),
+                Nullifier::from([5_u8; 32]),
+            ])
+        }
+    }
+
+    #[tokio::test]
+    async fn remote_witnesses_match_local_tree() {
+        let service = Arc::new(service());
+        let info = service.info().clone();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("listener should bind");
+        let url = format!("http://{}", listener.local_addr().expect("local address"));
+        tokio::spawn(async move { axum::serve(listener, witness_router(service)).await });
+
+        for (pool, root) in [
+            (Pool::Sapling, info.sapling_root),
+            (Pool::Orchard, info.orchard_root),
+        ] {
+            let root = root.expect("pool should be served");
+            let user = user_nullifiers(pool);
+            let data =
+                fetch_remote_pool_data(&url, pool, OrchardGapTreeHash::Sinsemilla, root, &user)
+                    .await
+                    .expect("remote witnesses should verify");
+
+            assert_eq!(data.tree.root_bytes(), root);
+            assert_eq!(data.user_nullifiers.len(), 1, "spent nullifier is omitted");
+            let local = served_pool(pool);
+            let position = data
+                .user_nullifiers
+                .first()
+                .expect("unspent nullifier has a witness");
+            let leaf_position = u64::from(position.leaf_position);
+            assert_eq!(
+                data.tree.witness_bytes(leaf_position),
+                local.tree.witness_bytes(leaf_position)
+            );
+        }
+    }
+
+    #[test]
+    fn tampered_remote_witness_is_rejected() {
+        let service = service();
+        let user = user_nullifiers(Pool::Sapling);
+        let request = WitnessRequest {
+            pool: Pool::Sapling,
+            nullifiers: user.to_vec(),
+        };
+        let response = service.witnesses(&request).expect("witnesses");
+        let root = response.root;
+        assert_eq!(response.witnesses.len(), 1);
+        assert_eq!(
+            response.spent.len(),
+            1,
+            "the spent nullifier is proven spent"
+        );
+
+        let mut wrong_gap = response.clone();
+        if let Some(witness) = wrong_gap.witnesses.first_mut() {
+            witness.left_bound = Nullifier::from([4_u8; 32]);
+        }
+        let mut wrong_path = response.clone();
+        if let Some(node) = wrong_path
+            .witnesses
+            .first_mut()
+            .and_then(|witness| witness.merkle_path.first_mut())
+        {
+            *node = [0_u8; 32];
+        }
+
+        let mut hidden_note = response.clone();
+        hidden_note.witnesses.clear();
+        let mut hidden_spend = response.clone();
+        hidden_spend.spent.clear();
+        let mut forged_spend = response.clone();
+        if let Some(witness) = forged_spend.spent.first_mut() {
+            witness.nullifier = Nullifier::from([3_u8; 32]);
+        }
+
+        for tampered in [
+            wrong_gap,
+            wrong_path,
+            hidden_note,
+            hidden_spend,
+            forged_spend,
+        ] {
+            assert!(
+                verify_remote_witnesses(
+                    Pool::Sapling,
+                    OrchardGapTreeHash::Sinsemilla,
+                    root,
+                    &user,
+                    vec![tampered],
+                )
+                .is_err()
+            );
+        }
+        assert!(
+            verify_remote_witnesses(
+                Pool::Sapling,
+                OrchardGapTreeHash::Sinsemilla,
+                [0_u8; 32],
+                &user,
+                vec![response],
+            )
+            .is_err(),
+            "a root other than the configured one must be rejected"
+        );
+    }
+
+    #[test]
+    fn unserved_pool_and_oversized_batch_are_rejected() {
+        let mut service = service();
+        service.orchard = None;
+        assert!(matches!(
+            service.witnesses(&WitnessRequest {
+                pool: Pool::Orchard,
+                nullifiers: vec![],
+            }),
+            Err(WitnessServiceError::PoolNotServed(Pool::Orchard))
+        ));
+        assert!(matches!(
+            service.witnesses(&WitnessRequest {
+                pool: Pool::Sapling,
+                nullifiers: vec![Nullifier::from([3_u8; 32]); MAX_WITNESS_BATCH + 1],
+            }),
+            Err(WitnessServiceError::BatchTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn stalled_witness_server_times_out() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let url = format!(
+            "http://{}{WITNESSES_ROUTE}",
+            listener.local_addr().expect("address")
+        );
+        let stall = std::thread::spawn(move || listener.accept().map(|(stream, _)| stream));
+        let request = WitnessRequest {
+            pool: Pool::Orchard,
+            nullifiers: vec![Nullifier::from([1_u8; 32])],
+        };
+        let err = post_witness_batch(&url, &request, Duration::from_millis(200))
+            .expect_err("a silent server must time out");
+        assert!(matches!(err, ureq::Error::Timeout(_)), "{err}");
+        drop(stall.join());
+    }
+}
@@ -4,24 +4,28 @@
 mod prove {
     use std::path::{Path, PathBuf};
 
-    use eyre::Context as _;
     use secrecy::ExposeSecret;
-    use zair_core::schema::config::AirdropConfiguration;
+    use zair_core::schema::proof_inputs::AirdropClaimInputs;
+    use zair_core::schema::submission::ClaimSubmission;
     use zcash_keys::keys::UnifiedSpendingKey;
     use zip32::AccountId;
 
-    use super::super::{GapTreeMode, airdrop_claim, generate_claim_proofs, sign_claim_submission};
+    use super::super::airdrop_configuration::read_airdrop_configuration;
+    use super::super::{
+        GapTreeMode, TrustedOrganizer, airdrop_claim, generate_claim_proofs, sign_claim_submission,
+    };
     use crate::common::to_zcash_network;
+    use crate::progress::Progress;
     use crate::seed::read_seed_file;
 
     async fn derive_ufvk_from_seed(
         seed_file: &Path,
         account_id: u32,
         airdrop_configuration_file: &Path,
+        trusted_organizer: Option<&TrustedOrganizer>,
     ) -> eyre::Result<String> {
-        let airdrop_config: AirdropConfiguration =
-            serde_json::from_str(&tokio::fs::read_to_string(airdrop_configuration_file).await?)
-                .context("Failed to parse airdrop configuration JSON")?;
+        let airdrop_config =
+            read_airdrop_configuration(airdrop_configuration_file, None, trusted_organizer).await?;
         let network = to_zcash_network(airdrop_config.network);
 
         let seed = read_seed_file(seed_file).await?;
@@ -34,8 +38,21 @@ mod prove {
         Ok(ufvk.encode(&network))
     }
 
+    /// Outputs of [`claim_run`]: the prepared claim inputs and the signed submission.
+    #[derive(Debug)]
+    pub struct ClaimRunOutput {
+        /// Claim inputs written by `claim prepare`; contains private note data.
+        pub claims: AirdropClaimInputs,
+        /// Submission written by `claim sign`.
+        pub submission: ClaimSubmission,
+    }
+
     /// Run the full claim pipeline: `claim prepare -> claim prove -> claim sign`.
     ///
+    /// The claims are prepared for every target of the configuration and proven and signed for
+    /// `target`, or for the default airdrop when it is `None`. With `trusted_organizer`, every
+    /// step refuses a configuration the organizer did not sign.
+    ///
     /// # Errors
     /// Returns an error if any pipeline step fails.
     #[allow(
@@ -50,7 +67,11 @@ mod prove {
         sapling_gap_tree_file: Option<PathBuf>,
         orchard_gap_tree_file: Option<PathBuf>,
         gap_tree_mode: GapTreeMode,
-        birthday_height: u64,
+        witness_server_url: Option<String>,
+        gap_tree_shards: Option<String>,
+        birthday_height: Option<u64>,
+        scan_cache_dir: Option<PathBuf>,
+        wallet_db: Option<PathBuf>,
         airdrop_claims_output_file: PathBuf,
         claim_proofs_output_file: PathBuf,
         claim_secrets_output_file: PathBuf,
@@ -63,21 +84,34 @@ mod prove {
         message_file: Option<PathBuf>,
         messages_file: Option<PathBuf>,
         airdrop_configuration_file: PathBuf,
-    ) -> eyre::Result<()> {
-        let unified_full_viewing_key =
-            derive_ufvk_from_seed(&seed_file, account_id, &airdrop_configuration_file).await?;
+        target: Option<String>,
+        trusted_organizer: Option<TrustedOrganizer>,
+    ) -> eyre::Result<ClaimRunOutput> {
+        let unified_full_viewing_key = derive_ufvk_from_seed(
+            &seed_file,
+            account_id,
+            &airdrop_configuration_file,
+            trusted_organizer.as_ref(),
+        )
+        .await?;
 
-        airdrop_claim(
+        let claims = airdrop_claim(
             lightwalletd_url,
             sapling_snapshot_nullifiers,
             orchard_snapshot_nullifiers,
             sapling_gap_tree_file,
             orchard_gap_tree_file,
             gap_tree_mode,
+            witness_server_url,
+            gap_tree_shards,
             unified_full_viewing_key,
             birthday_height,
+            scan_cache_dir,
+            wallet_db,
             airdrop_claims_output_file.clone(),
             airdrop_configuration_file.clone(),
+            trusted_organizer.clone(),
+            &Progress::default(),
         )
         .await?;
 
@@ -91,42 +125,82 @@ mod prove {
             orchard_params_mode,
             claim_secrets_output_file.clone(),
             airdrop_configuration_file.clone(),
+            target.clone(),
+            trusted_organizer.clone(),
+            &Progress::default(),
         )
         .await?;
 
-        sign_claim_submission(
+        let submission = sign_claim_submission(
             claim_proofs_output_file,
             claim_secrets_output_file,
             seed_file,
             account_id,
             airdrop_configuration_file,
+            target,
+            trusted_organizer,
             message_file,
             messages_file,
             claim_submission_output_file,
         )
-        .await
+        .await?;
+
+        Ok(ClaimRunOutput { claims, submission })
     }
 }
 
 mod verify {
     use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
 
     use eyre::Context as _;
+    use tracing::info;
+    use zair_core::base::Pool;
     use zair_core::schema::submission::ClaimSubmission;
 
-    use super::super::claim_proofs::{
-        ClaimProofsOutput, OrchardClaimProofResult, SaplingClaimProofResult,
-        verify_claim_proofs_inner,
+    use super::super::TrustedOrganizer;
+    use super::super::airdrop_configuration::chain_clock;
+    use super::super::claim_proofs::ClaimProofsOutput;
+    use super::super::claim_proofs_verify::proof_verifiers;
+    use super::super::claim_registry::{
+        AlreadyClaimed, ClaimRegistry, registry_verdicts, submission_entries,
     };
-    use super::super::verify_claim_submission_signature;
+    use super::super::claim_submission_verify::{LoadedSubmission, load_submission, log_verdict};
+    use super::super::claim_verifier::{SubmissionVerifier, TransparentClaimVerifier};
+    use super::super::verification_report::VerificationReport;
+    use crate::progress::{Phase, Progress};
 
-    /// Run full verification: `verify proof -> verify signature`.
+    /// Run full verification: every check of `verify signature` and `verify proof` in one pass.
+    ///
+    /// Every claim is checked for its `proof_hash`, message hash, signature and proof, and the
+    /// returned report has one verdict per claim; a failing claim does not stop the others from
+    /// being checked. With `valid_submission_out`, the claims that passed every check are
+    /// written there as a filtered submission, whether or not other claims failed.
+    ///
+    /// With `target`, the claims are verified for that airdrop target of the configuration. With
+    /// `trusted_organizer`, a configuration the organizer did not sign is refused; the signature
+    /// and the proofs are checked against the same reading of the file.
+    ///
+    /// A claim window in the configuration is checked against `current_height` and
+    /// `current_time`, the latter defaulting to the system clock.
+    ///
+    /// With `registry_dir`, a claim already recorded in the claim registry fails with
+    /// [`FailureReason::AlreadyClaimed`](super::super::FailureReason::AlreadyClaimed), and the
+    /// claims that pass every check are recorded before `valid_submission_out` is written, even
+    /// when others fail.
+    ///
+    /// Verification progress is reported to `progress`, and verification stops once it is
+    /// cancelled.
     ///
     /// # Errors
-    /// Returns an error if either verification step fails.
+    /// Returns an error if the inputs cannot be loaded, the submission is outside the claim
+    /// window, [`AlreadyClaimed`] if replayed claims are the only failing ones, or
+    /// [`VerificationFailed`](super::super::VerificationFailed) with the per-claim report if any
+    /// claim fails a check.
     #[allow(
+        clippy::too_many_arguments,
         clippy::similar_names,
-        reason = "message_file vs messages_file are distinct CLI args"
+        reason = "CLI entrypoint parameters"
     )]
     pub async fn verify_run(
         verifying_key_file: PathBuf,
@@ -136,55 +210,140 @@ mod verify {
         message_file: Option<PathBuf>,
         messages_file: Option<PathBuf>,
         airdrop_configuration_file: PathBuf,
-    ) -> eyre::Result<()> {
-        verify_claim_submission_signature(
-            submission_file.clone(),
+        target: Option<String>,
+        trusted_organizer: Option<TrustedOrganizer>,
+        current_height: Option<u64>,
+        current_time: Option<u64>,
+        registry_dir: Option<PathBuf>,
+        valid_submission_out: Option<PathBuf>,
+        progress: &Progress,
+    ) -> eyre::Result<VerificationReport> {
+        let LoadedSubmission {
+            submission,
+            airdrop_config,
+            messages,
+        } = load_submission(
+            submission_file,
             message_file,
             messages_file,
-            airdrop_configuration_file.clone(),
+            airdrop_configuration_file,
+            target,
+            trusted_organizer,
+            chain_clock(current_height, current_time),
+        )
+        .await?;
+        let (sapling, orchard) = proof_verifiers(
+            &airdrop_config,
+            &ClaimProofsOutput::from(&submission),
+            verifying_key_file,
+            orchard_params_file,
+            orchard_params_mode,
         )
         .await?;
+        let verifier = SubmissionVerifier::new(
+            sapling,
+            orchard,
+            airdrop_config
+                .transparent
+                .as_ref()
+                .map(TransparentClaimVerifier::new),
+        )
+        .with_claim_window(airdrop_config.claim_window);
 
-        let submission: ClaimSubmission =
-            serde_json::from_str(&tokio::fs::read_to_string(&submission_file).await?)
-                .context("Failed to parse submission JSON")?;
+        let progress = progress.clone();
+        let (submission, mut report) = tokio::task::spawn_blocking(move || {
+            verifier
+                .check_submission(&submission, &messages, |pool, checked, total| {
+                    progress.ensure_not_cancelled()?;
+                    progress.report_count(Phase::Verify, Some(pool), checked, total);
+                    Ok::<_, eyre::Report>(())
+                })
+                .map(|report| (submission, report))
+        })
+        .await??;
+        report
+            .claims
+            .iter()
+            .for_each(|verdict| log_verdict(verdict, "Claim"));
+
+        let registry = registry_dir
+            .map(|dir| ClaimRegistry::open(&dir))
+            .transpose()?;
+        let recorded_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let replayed = if let Some(registry) = &registry {
+            let entries = submission_entries(&submission, &airdrop_config, recorded_at)?;
+            let (registry_report, claimed) = registry_verdicts(registry, &entries)?;
+            report.merge(registry_report);
+            claimed
+        } else {
+            Vec::new()
+        };
+
+        let valid = valid_subset(submission, &report);
+        if let Some(registry) = &registry {
+            let entries = submission_entries(&valid, &airdrop_config, recorded_at)?;
+            if !entries.is_empty() {
+                registry.record(&entries)?;
+                info!(
+                    claims = entries.len(),
+                    "Recorded verified claims in the registry"
+                );
+            }
+        }
+        if let Some(path) = valid_submission_out {
+            tokio::fs::write(&path, serde_json::to_string_pretty(&valid)?)
+                .await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            info!(
+                file = ?path,
+                sapling = valid.sapling.len(),
+                orchard = valid.orchard.len(),
+                transparent = valid.transparent.len(),
+                "Valid claims written"
+            );
+        }
+
+        if !replayed.is_empty() && report.only_replays_failed() {
+            return Err(AlreadyClaimed {
+                claimed: replayed,
+                total: report.claims.len(),
+            }
+            .into());
+        }
+        Ok(report.into_result("claims")?)
+    }
 
-        let proofs = ClaimProofsOutput {
-            sapling_proofs: submission
+    /// The claims of `submission` that passed every check in `report`.
+    fn valid_subset(submission: ClaimSubmission, report: &VerificationReport) -> ClaimSubmission {
+        let sapling_valid = report.valid_indices(Pool::Sapling);
+        let orchard_valid = report.valid_indices(Pool::Orchard);
+        let transparent_valid = report.valid_indices(Pool::Transparent);
+        ClaimSubmission {
+            sapling: submission
                 .sapling
-                .iter()
-                .map(|entry| SaplingClaimProofResult {
-                    zkproof: entry.zkproof,
-                    rk: entry.rk,
-                    cv: entry.cv,
-                    cv_sha256: entry.cv_sha256,
-                    airdrop_nullifier: entry.airdrop_nullifier,
-                })
+                .into_iter()
+                .enumerate()
+                .filter(|(index, _)| sapling_valid.contains(index))
+                .map(|(_, claim)| claim)
                 .collect(),
-            orchard_proofs: submission
+            orchard: submission
                 .orchard
-                .iter()
-                .map(|entry| OrchardClaimProofResult {
-                    zkproof: entry.zkproof.clone(),
-                    rk: entry.rk,
-                    cv: entry.cv,
-                    cv_sha256: entry.cv_sha256,
-                    airdrop_nullifier: entry.airdrop_nullifier,
-                })
+                .into_iter()
+                .enumerate()
+                .filter(|(index, _)| orchard_valid.contains(index))
+                .map(|(_, claim)| claim)
                 .collect(),
-        };
-
-        verify_claim_proofs_inner(
-            proofs,
-            verifying_key_file,
-            orchard_params_file,
-            orchard_params_mode,
-            airdrop_configuration_file,
-        )
-        .await
+            transparent: submission
+                .transparent
+                .into_iter()
+                .enumerate()
+                .filter(|(index, _)| transparent_valid.contains(index))
+                .map(|(_, claim)| claim)
+                .collect(),
+        }
     }
 }
 
 #[cfg(feature = "prove")]
-pub use prove::claim_run;
+pub use prove::{ClaimRunOutput, claim_run};
 pub use verify::verify_run;
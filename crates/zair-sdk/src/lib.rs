@@ -2,6 +2,10 @@
 
 pub mod commands;
 pub mod common;
+pub mod namada;
 pub mod network_params;
+#[cfg(feature = "native")]
+pub mod progress;
 
+#[cfg(feature = "native")]
 mod seed;
@@ -0,0 +1,418 @@
+//! Namada claim transaction payloads.
+//!
+//! A Namada claim transaction carries one signed claim as a Borsh-encoded [`NamadaClaim`].
+//! Fixed-size fields are encoded as raw bytes, the Orchard proof as a `u32` length-prefixed
+//! byte vector, and enums with a one-byte variant tag. The airdrop nullifier is encoded in its
+//! internal byte order, not the reversed order used by its hex display.
+//!
+//! [`NamadaAddressValidator`] checks the recipients of structured claim messages.
+
+use bech32::Bech32m;
+use bech32::primitives::decode::CheckedHrpstring;
+use borsh::{BorshDeserialize, BorshSerialize};
+use zair_core::base::{Nullifier, Pool};
+use zair_core::schema::submission::{ClaimSubmission, OrchardSignedClaim, SaplingSignedClaim};
+
+use crate::commands::{AddressError, AddressValidator};
+
+/// `address_format` of structured claim messages paying out to a Namada address.
+pub const NAMADA_ADDRESS_FORMAT: &str = "namada";
+
+/// Bech32m prefix and payload length of every Namada address kind a claim can pay out to:
+/// transparent addresses and shielded payment addresses.
+const NAMADA_RECIPIENT_PREFIXES: [(&str, usize); 2] = [("tnam", 21), ("znam", 43)];
+
+/// Errors raised while building Namada claim payloads.
+#[derive(Debug, thiserror::Error)]
+pub enum NamadaPayloadError {
+    /// A claim does not carry exactly one value commitment.
+    #[error("{pool} claim {airdrop_nullifier} must carry exactly one of cv and cv_sha256")]
+    ValueCommitment {
+        /// Pool of the offending claim.
+        pool: Pool,
+        /// Airdrop nullifier of the offending claim.
+        airdrop_nullifier: Nullifier,
+    },
+    /// The submission holds transparent claims, which have no Namada payload.
+    #[error(
+        "{0} transparent claim(s) cannot be exported to Namada payloads; submit them separately"
+    )]
+    TransparentClaims(usize),
+    /// Borsh encoding or decoding failed.
+    #[error("Borsh encoding failed: {0}")]
+    Borsh(#[from] std::io::Error),
+}
+
+/// Value commitment of a claim, matching the airdrop's configured scheme.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum NamadaValueCommitment {
+    /// Native value commitment `cv`.
+    Native([u8; 32]),
+    /// SHA-256 value commitment `cv_sha256`.
+    Sha256([u8; 32]),
+}
+
+impl NamadaValueCommitment {
+    const fn from_fields(
+        cv: Option<[u8; 32]>,
+        cv_sha256: Option<[u8; 32]>,
+        pool: Pool,
+        airdrop_nullifier: Nullifier,
+    ) -> Result<Self, NamadaPayloadError> {
+        match (cv, cv_sha256) {
+            (Some(cv), None) => Ok(Self::Native(cv)),
+            (None, Some(cv_sha256)) => Ok(Self::Sha256(cv_sha256)),
+            (Some(_), Some(_)) | (None, None) => Err(NamadaPayloadError::ValueCommitment {
+                pool,
+                airdrop_nullifier,
+            }),
+        }
+    }
+}
+
+/// Sapling claim as carried by a Namada claim transaction.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct NamadaSaplingClaim {
+    /// The Groth16 proof bytes.
+    pub zkproof: [u8; 192],
+    /// The re-randomized spend verification key.
+    pub rk: [u8; 32],
+    /// The claim's value commitment.
+    pub value_commitment: NamadaValueCommitment,
+    /// Airdrop nullifier used for double-claim prevention.
+    pub airdrop_nullifier: [u8; 32],
+    /// Spend authorization signature over the submission digest.
+    pub spend_auth_sig: [u8; 64],
+}
+
+/// Orchard claim as carried by a Namada claim transaction.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct NamadaOrchardClaim {
+    /// The Halo2 proof bytes.
+    pub zkproof: Vec<u8>,
+    /// The randomized spend verification key.
+    pub rk: [u8; 32],
+    /// The claim's value commitment.
+    pub value_commitment: NamadaValueCommitment,
+    /// Airdrop nullifier used for double-claim prevention.
+    pub airdrop_nullifier: [u8; 32],
+    /// Spend authorization signature over the submission digest.
+    pub spend_auth_sig: [u8; 64],
+}
+
+/// Payload of a Namada claim transaction.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum NamadaClaim {
+    /// A Sapling claim.
+    Sapling(NamadaSaplingClaim),
+    /// An Orchard claim.
+    Orchard(NamadaOrchardClaim),
+}
+
+impl NamadaClaim {
+    /// Pool the claim was made from.
+    #[must_use]
+    pub const fn pool(&self) -> Pool {
+        match self {
+            Self::Sapling(_) => Pool::Sapling,
+            Self::Orchard(_) => Pool::Orchard,
+        }
+    }
+
+    /// Airdrop nullifier of the claim.
+    #[must_use]
+    pub fn airdrop_nullifier(&self) -> Nullifier {
+        match self {
+            Self::Sapling(claim) => Nullifier::from(claim.airdrop_nullifier),
+            Self::Orchard(claim) => Nullifier::from(claim.airdrop_nullifier),
+        }
+    }
+
+    /// Borsh-encode the payload.
+    ///
+    /// # Errors
+    /// Returns an error if Borsh serialization fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, NamadaPayloadError> {
+        Ok(borsh::to_vec(self)?)
+    }
+
+    /// Decode a Borsh-encoded payload, rejecting trailing bytes.
+    ///
+    /// # Errors
+    /// Returns an error if `bytes` is not a valid encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, NamadaPayloadError> {
+        Ok(borsh::from_slice(bytes)?)
+    }
+}
+
+impl TryFrom<&SaplingSignedClaim> for NamadaClaim {
+    type Error = NamadaPayloadError;
+
+    fn try_from(claim: &SaplingSignedClaim) -> Result<Self, Self::Error> {
+        Ok(Self::Sapling(NamadaSaplingClaim {
+            zkproof: claim.zkproof,
+            rk: claim.rk,
+            value_commitment: NamadaValueCommitment::from_fields(
+                claim.cv,
+                claim.cv_sha256,
+                Pool::Sapling,
+                claim.airdrop_nullifier,
+            )?,
+            airdrop_nullifier: claim.airdrop_nullifier.into(),
+            spend_auth_sig: claim.spend_auth_sig,
+        }))
+    }
+}
+
+impl TryFrom<&OrchardSignedClaim> for NamadaClaim {
+    type Error = NamadaPayloadError;
+
+    fn try_from(claim: &OrchardSignedClaim) -> Result<Self, Self::Error> {
+        Ok(Self::Orchard(NamadaOrchardClaim {
+            zkproof: claim.zkproof.clone(),
+            rk: claim.rk,
+            value_commitment: NamadaValueCommitment::from_fields(
+                claim.cv,
+                claim.cv_sha256,
+                Pool::Orchard,
+                claim.airdrop_nullifier,
+            )?,
+            airdrop_nullifier: claim.airdrop_nullifier.into(),
+            spend_auth_sig: claim.spend_auth_sig,
+        }))
+    }
+}
+
+/// Namada payloads for every claim in a submission, Sapling first, in submission order.
+///
+/// Namada claim transactions carry shielded claims only, so a submission with transparent
+/// claims is refused rather than exported without them.
+///
+/// # Errors
+/// Returns an error if the submission holds transparent claims or a claim does not carry
+/// exactly one value commitment.
+pub fn submission_payloads(
+    submission: &ClaimSubmission,
+) -> Result<Vec<NamadaClaim>, NamadaPayloadError> {
+    if !submission.transparent.is_empty() {
+        return Err(NamadaPayloadError::TransparentClaims(
+            submission.transparent.len(),
+        ));
+    }
+    submission
+        .sapling
+        .iter()
+        .map(NamadaClaim::try_from)
+        .chain(submission.orchard.iter().map(NamadaClaim::try_from))
+        .collect()
+}
+
+/// Validates Namada recipients: bech32m `tnam` transparent addresses and `znam` shielded
+/// payment addresses.
+///
+/// The checksum, prefix and payload length are checked; the chain id is not. Namada mainnet and
+/// every testnet share the `tnam` and `znam` prefixes, so an address cannot tell which chain it
+/// belongs to: a message naming the mainnet chain id with an address meant for a testnet
+/// passes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NamadaAddressValidator;
+
+impl AddressValidator for NamadaAddressValidator {
+    fn validate(&self, address: &str, _chain_id: &str) -> Result<(), AddressError> {
+        let decoded = CheckedHrpstring::new::<Bech32m>(address)
+            .map_err(|err| AddressError::Encoding(err.to_string()))?;
+        let prefix = decoded.hrp().to_lowercase();
+        let (_, expected) = NAMADA_RECIPIENT_PREFIXES
+            .into_iter()
+            .find(|(known, _)| *known == prefix)
+            .ok_or_else(|| AddressError::UnexpectedPrefix {
+                found: prefix.clone(),
+                expected: NAMADA_RECIPIENT_PREFIXES.map(|(known, _)| known).join(", "),
+            })?;
+        let found = decoded.byte_iter().count();
+        if found == expected {
+            Ok(())
+        } else {
+            Err(AddressError::UnexpectedLength {
+                prefix,
+                expected,
+                found,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bech32::Hrp;
+    use zair_core::schema::submission::TransparentSignedClaim;
+
+    use super::*;
+
+    fn sapling_claim() -> SaplingSignedClaim {
+        SaplingSignedClaim {
+            zkproof: [0x11; 192],
+            rk: [0x22; 32],
+            cv: Some([0x33; 32]),
+            cv_sha256: None,
+            airdrop_nullifier: Nullifier::from([0x44; 32]),
+            proof_hash: [0xaa; 32],
+            message_hash: [0xbb; 32],
+            spend_auth_sig: [0x55; 64],
+        }
+    }
+
+    fn orchard_claim() -> OrchardSignedClaim {
+        let mut airdrop_nullifier = [0_u8; 32];
+        airdrop_nullifier[0] = 0x01;
+        airdrop_nullifier[31] = 0x02;
+        OrchardSignedClaim {
+            zkproof: vec![0xde, 0xad, 0xbe],
+            rk: [0x66; 32],
+            cv: None,
+            cv_sha256: Some([0x77; 32]),
+            airdrop_nullifier: Nullifier::from(airdrop_nullifier),
+            proof_hash: [0xaa; 32],
+            message_hash: [0xbb; 32],
+            spend_auth_sig: [0x88; 64],
+        }
+    }
+
+    #[test]
+    fn sapling_payload_matches_fixed_vector() {
+        let expected = hex::decode(format!(
+            "00{}{}00{}{}{}",
+            "11".repeat(192),
+            "22".repeat(32),
+            "33".repeat(32),
+            "44".repeat(32),
+            "55".repeat(64),
+        ))
+        .expect("vector is hex");
+
+        let payload = NamadaClaim::try_from(&sapling_claim()).expect("claim converts");
+        let bytes = payload.to_bytes().expect("payload encodes");
+        assert_eq!(bytes, expected);
+        assert_eq!(NamadaClaim::from_bytes(&bytes).expect("decodes"), payload);
+    }
+
+    #[test]
+    fn orchard_payload_matches_fixed_vector() {
+        let expected = hex::decode(
+            format!(
+                "01 03000000 deadbe {} 01{} 01{}02 {}",
+                "66".repeat(32),
+                "77".repeat(32),
+                "00".repeat(30),
+                "88".repeat(64),
+            )
+            .replace(' ', ""),
+        )
+        .expect("vector is hex");
+
+        let payload = NamadaClaim::try_from(&orchard_claim()).expect("claim converts");
+        let bytes = payload.to_bytes().expect("payload encodes");
+        assert_eq!(bytes, expected);
+        let decoded = NamadaClaim::from_bytes(&bytes).expect("decodes");
+        assert_eq!(decoded, payload);
+        assert_eq!(decoded.pool(), Pool::Orchard);
+        assert_eq!(
+            decoded.airdrop_nullifier(),
+            orchard_claim().airdrop_nullifier
+        );
+    }
+
+    #[test]
+    fn ambiguous_value_commitment_is_rejected() {
+        let claim = SaplingSignedClaim {
+            cv_sha256: Some([0x99; 32]),
+            ..sapling_claim()
+        };
+        assert!(matches!(
+            NamadaClaim::try_from(&claim),
+            Err(NamadaPayloadError::ValueCommitment { .. })
+        ));
+        let mut bytes = NamadaClaim::try_from(&sapling_claim())
+            .expect("claim converts")
+            .to_bytes()
+            .expect("payload encodes");
+        bytes.push(0);
+        assert!(NamadaClaim::from_bytes(&bytes).is_err(), "trailing bytes");
+    }
+
+    #[test]
+    fn submissions_with_transparent_claims_are_refused() {
+        let mut submission = ClaimSubmission {
+            sapling: vec![sapling_claim()],
+            orchard: vec![orchard_claim()],
+            transparent: vec![],
+        };
+        let payloads = submission_payloads(&submission).expect("shielded claims export");
+        assert_eq!(
+            payloads.iter().map(NamadaClaim::pool).collect::<Vec<_>>(),
+            [Pool::Sapling, Pool::Orchard]
+        );
+
+        submission.transparent.push(TransparentSignedClaim {
+            pubkey: [0x02; 33],
+            balance: 1,
+            leaf_index: 0,
+            merkle_path: vec![],
+            airdrop_nullifier: Nullifier::from([0x99; 32]),
+            proof_hash: [0xaa; 32],
+            message_hash: [0xbb; 32],
+            signature: [0xcc; 64],
+        });
+        assert!(matches!(
+            submission_payloads(&submission),
+            Err(NamadaPayloadError::TransparentClaims(1))
+        ));
+    }
+
+    fn namada_address(prefix: &str, payload: &[u8]) -> String {
+        let hrp = Hrp::parse(prefix).expect("valid prefix");
+        bech32::encode::<Bech32m>(hrp, payload).expect("address encodes")
+    }
+
+    #[test]
+    fn namada_validator_checks_checksum_prefix_and_length() {
+        let validator = NamadaAddressValidator;
+        let transparent = namada_address("tnam", &[7; 21]);
+        let shielded = namada_address("znam", &[7; 43]);
+        assert_eq!(validator.validate(&transparent, "namada"), Ok(()));
+        assert_eq!(validator.validate(&shielded, "namada"), Ok(()));
+
+        let mut typo = transparent.into_bytes();
+        if let Some(last) = typo.last_mut() {
+            *last = if *last == b'q' { b'p' } else { b'q' };
+        }
+        let typo = String::from_utf8(typo).expect("ascii address");
+        assert!(matches!(
+            validator.validate(&typo, "namada"),
+            Err(AddressError::Encoding(_))
+        ));
+
+        let bech32 =
+            bech32::encode::<bech32::Bech32>(Hrp::parse("tnam").expect("prefix"), &[7; 21])
+                .expect("address encodes");
+        assert!(matches!(
+            validator.validate(&bech32, "namada"),
+            Err(AddressError::Encoding(_))
+        ));
+        assert_eq!(
+            validator.validate(&namada_address("cosmos", &[7; 20]), "namada"),
+            Err(AddressError::UnexpectedPrefix {
+                found: "cosmos".to_owned(),
+                expected: "tnam, znam".to_owned(),
+            })
+        );
+        assert_eq!(
+            validator.validate(&namada_address("tnam", &[7; 20]), "namada"),
+            Err(AddressError::UnexpectedLength {
+                prefix: "tnam".to_owned(),
+                expected: 21,
+                found: 20,
+            })
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Progress events and cancellation for long-running SDK operations.
+//!
+//! Scanning, gap-tree building, proving and verification report [`ProgressEvent`]s through a
+//! [`Progress`] handle and stop early once its [`CancellationToken`] is cancelled. A cancelled
+//! operation fails with an error whose chain contains [`Cancelled`].
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+pub use tokio_util::sync::CancellationToken;
+use zair_core::base::Pool;
+
+/// Phase of a long-running operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Fetching snapshot nullifiers from lightwalletd; `current` counts fetched blocks.
+    FetchNullifiers,
+    /// Building a non-membership gap tree; `current` counts processed nullifiers.
+    BuildGapTree,
+    /// Scanning blocks for the user's notes; `current` counts scanned blocks.
+    ScanNotes,
+    /// Generating claim proofs; `current` counts finished proofs.
+    Prove,
+    /// Verifying claim proofs or signatures; `current` counts verified claims.
+    Verify,
+}
+
+/// A single progress update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressEvent {
+    /// Phase the update belongs to.
+    pub phase: Phase,
+    /// Pool the update belongs to, if the phase is per pool.
+    pub pool: Option<Pool>,
+    /// Units done so far.
+    pub current: u64,
+    /// Total units, or 0 if unknown.
+    pub total: u64,
+}
+
+/// Error returned when an operation stops because its cancellation token was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Operation cancelled")]
+pub struct Cancelled;
+
+type ProgressSink = Arc<dyn Fn(ProgressEvent) + Send + Sync>;
+
+/// Progress sink and cancellation token handed to long-running operations.
+///
+/// The default handle drops every event and is never cancelled. Clones share the sink and token.
+#[derive(Clone, Default)]
+pub struct Progress {
+    sink: Option<ProgressSink>,
+    cancel: CancellationToken,
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("sink", &self.sink.is_some())
+            .field("cancelled", &self.cancel.is_cancelled())
+            .finish()
+    }
+}
+
+impl Progress {
+    /// Report events to `sink`. The sink runs on the reporting thread and should return quickly.
+    pub fn new(sink: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        Self {
+            sink: Some(Arc::new(sink)),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Report events into an unbounded channel.
+    #[must_use]
+    pub fn channel() -> (Self, tokio::sync::mpsc::UnboundedReceiver<ProgressEvent>) {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let progress = Self::new(move |event| {
+            // A dropped receiver only means nobody is listening anymore.
+            let _ = tx.send(event);
+        });
+        (progress, rx)
+    }
+
+    /// Use `cancel` as the cancellation token.
+    #[must_use]
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// The cancellation token; cancel it to stop the operation.
+    #[must_use]
+    pub const fn cancellation(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// Whether the operation has been cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Report `current` of `total` units done in `phase`.
+    pub fn report(&self, phase: Phase, pool: Option<Pool>, current: u64, total: u64) {
+        if let Some(sink) = &self.sink {
+            sink(ProgressEvent {
+                phase,
+                pool,
+                current,
+                total,
+            });
+        }
+    }
+
+    /// Report `current` of `total` units done in `phase`, from `usize` counters.
+    pub(crate) fn report_count(
+        &self,
+        phase: Phase,
+        pool: Option<Pool>,
+        current: usize,
+        total: usize,
+    ) {
+        self.report(
+            phase,
+            pool,
+            u64::try_from(current).unwrap_or(u64::MAX),
+            u64::try_from(total).unwrap_or(u64::MAX),
+        );
+    }
+
+    /// Fail with [`Cancelled`] if the operation has been cancelled.
+    ///
+    /// # Errors
+    /// Returns [`Cancelled`] once the token is cancelled.
+    pub fn ensure_not_cancelled(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Run `fut` until it finishes or the operation is cancelled, whichever comes first.
+    ///
+    /// # Errors
+    /// Returns [`Cancelled`] on cancellation, otherwise the error of `fut`.
+    pub async fn run_cancellable<T>(
+        &self,
+        fut: impl Future<Output = eyre::Result<T>>,
+    ) -> eyre::Result<T> {
+        tokio::select! {
+            biased;
+            () = self.cancel.cancelled() => Err(Cancelled.into()),
+            result = fut => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn channel_delivers_events_in_order() {
+        let (progress, mut rx) = Progress::channel();
+        progress.report(Phase::ScanNotes, None, 1, 2);
+        progress.report(Phase::Prove, Some(Pool::Orchard), 2, 2);
+        drop(progress);
+
+        let first = rx.recv().await.expect("first event");
+        let second = rx.recv().await.expect("second event");
+        assert_eq!(first.phase, Phase::ScanNotes);
+        assert_eq!((first.current, first.total), (1, 2));
+        assert_eq!(second.pool, Some(Pool::Orchard));
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn cancelled_token_stops_pending_future() {
+        let progress = Progress::default();
+        progress.cancellation().cancel();
+
+        let err = progress
+            .run_cancellable(std::future::pending::<eyre::Result<()>>())
+            .await
+            .expect_err("cancelled future must fail");
+        assert!(err.downcast_ref::<Cancelled>().is_some());
+        assert!(progress.ensure_not_cancelled().is_err());
+    }
+}
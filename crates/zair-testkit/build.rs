@@ -0,0 +1,57 @@
+//! Generates the `CompactTxStreamer` server stub served by the mock lightwalletd.
+//!
+//! `zcash_client_backend` only ships the generated client, so the server half is produced here
+//! from a manual service description reusing its message types.
+
+use tonic_build::manual::{Builder, Method, MethodBuilder, Service};
+
+const SERVICE_PROTO: &str = "zcash_client_backend::proto::service";
+const COMPACT_FORMATS_PROTO: &str = "zcash_client_backend::proto::compact_formats";
+const CODEC: &str = "tonic_prost::ProstCodec";
+
+fn method(name: &str, route_name: &str, input_type: &str, output_type: &str) -> MethodBuilder {
+    Method::builder()
+        .name(name)
+        .route_name(route_name)
+        .input_type(input_type)
+        .output_type(output_type)
+        .codec_path(CODEC)
+}
+
+fn main() {
+    let block_id = format!("{SERVICE_PROTO}::BlockId");
+    let service = Service::builder()
+        .name("CompactTxStreamer")
+        .package("cash.z.wallet.sdk.rpc")
+        .method(
+            method(
+                "get_latest_block",
+                "GetLatestBlock",
+                &format!("{SERVICE_PROTO}::ChainSpec"),
+                &block_id,
+            )
+            .build(),
+        )
+        .method(
+            method(
+                "get_block_range",
+                "GetBlockRange",
+                &format!("{SERVICE_PROTO}::BlockRange"),
+                &format!("{COMPACT_FORMATS_PROTO}::CompactBlock"),
+            )
+            .server_streaming()
+            .build(),
+        )
+        .method(
+            method(
+                "get_tree_state",
+                "GetTreeState",
+                &block_id,
+                &format!("{SERVICE_PROTO}::TreeState"),
+            )
+            .build(),
+        )
+        .build();
+
+    Builder::new().build_client(false).compile(&[service]);
+}
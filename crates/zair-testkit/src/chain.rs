@@ -0,0 +1,548 @@
+//! Deterministic synthetic chains of compact blocks.
+//!
+//! Notes are encrypted to the ZIP-32 account 0 of a known seed on testnet, so the chain can be
+//! scanned with the same seed the CLI is given. Spends carry random nullifiers and every block
+//! records the commitment tree sizes and tree state lightwalletd would report for it.
+
+use std::collections::BTreeMap;
+use std::io;
+
+use incrementalmerkletree::frontier::CommitmentTree;
+use orchard::keys::Scope;
+use orchard::note::{ExtractedNoteCommitment, RandomSeed, Rho};
+use orchard::note_encryption::{OrchardDomain, OrchardNoteEncryption};
+use orchard::tree::MerkleHashOrchard;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore as _, SeedableRng as _};
+use sapling::note_encryption::{SaplingDomain, sapling_note_encryption};
+use sha2::{Digest as _, Sha256};
+use zcash_client_backend::proto::compact_formats::{
+    ChainMetadata, CompactBlock, CompactOrchardAction, CompactSaplingOutput, CompactSaplingSpend,
+    CompactTx,
+};
+use zcash_client_backend::proto::service::{BlockId, TreeState};
+use zcash_keys::keys::UnifiedSpendingKey;
+use zcash_note_encryption::{COMPACT_NOTE_SIZE, Domain as _};
+use zcash_primitives::merkle_tree::write_commitment_tree;
+use zcash_protocol::consensus::Network;
+use zip32::AccountId;
+
+/// Network name lightwalletd reports for testnet tree states.
+const NETWORK_NAME: &str = "test";
+/// Timestamp of the first synthetic block.
+const GENESIS_TIME: u32 = 1_700_000_000;
+/// Seconds between synthetic blocks.
+const BLOCK_INTERVAL_SECS: u32 = 75;
+/// Depth of the Orchard note commitment tree.
+const ORCHARD_TREE_DEPTH: u8 = 32;
+/// Memo of every synthetic note: the "no memo" marker followed by zeroes.
+const EMPTY_MEMO: [u8; 512] = {
+    let mut memo = [0_u8; 512];
+    memo[0] = 0xF6;
+    memo
+};
+
+type SaplingTree = CommitmentTree<sapling::Node, { sapling::NOTE_COMMITMENT_TREE_DEPTH }>;
+type OrchardTree = CommitmentTree<MerkleHashOrchard, ORCHARD_TREE_DEPTH>;
+
+/// Errors raised while generating a synthetic chain.
+#[derive(Debug, thiserror::Error)]
+pub enum ChainError {
+    /// A block was planned below the first block of the chain.
+    #[error("block {height} is below the chain start height {start_height}")]
+    BelowStart {
+        /// Height of the planned block.
+        height: u64,
+        /// First height of the chain.
+        start_height: u64,
+    },
+    /// Keys could not be derived from the seed.
+    #[error("key derivation failed: {0}")]
+    Keys(String),
+    /// A note commitment tree is full.
+    #[error("{0} note commitment tree is full")]
+    TreeFull(&'static str),
+    /// A commitment tree could not be serialized.
+    #[error("failed to serialize tree state: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Contents planned for one block.
+#[derive(Debug, Clone, Default)]
+struct BlockPlan {
+    sapling_notes: Vec<u64>,
+    orchard_notes: Vec<u64>,
+    sapling_spends: usize,
+    orchard_spends: usize,
+}
+
+/// Builder of a [`SyntheticChain`].
+///
+/// Blocks without planned contents are empty, so the chain is contiguous from the start height
+/// up to the tip.
+#[derive(Debug, Clone)]
+pub struct ChainBuilder {
+    seed: [u8; 64],
+    start_height: u64,
+    tip_height: u64,
+    plans: BTreeMap<u64, BlockPlan>,
+}
+
+impl ChainBuilder {
+    /// Start a testnet chain at `start_height` whose notes are owned by account 0 of `seed`.
+    #[must_use]
+    pub const fn new(seed: [u8; 64], start_height: u64) -> Self {
+        Self {
+            seed,
+            start_height,
+            tip_height: start_height,
+            plans: BTreeMap::new(),
+        }
+    }
+
+    /// Extend the chain with empty blocks up to `height`.
+    #[must_use]
+    pub fn tip_height(mut self, height: u64) -> Self {
+        self.tip_height = self.tip_height.max(height);
+        self
+    }
+
+    /// Add a Sapling note of `value` zatoshis to the block at `height`.
+    #[must_use]
+    pub fn sapling_note(mut self, height: u64, value: u64) -> Self {
+        self.plan(height).sapling_notes.push(value);
+        self
+    }
+
+    /// Add an Orchard note of `value` zatoshis to the block at `height`.
+    #[must_use]
+    pub fn orchard_note(mut self, height: u64, value: u64) -> Self {
+        self.plan(height).orchard_notes.push(value);
+        self
+    }
+
+    /// Add `count` Sapling spends with random nullifiers to the block at `height`.
+    #[must_use]
+    pub fn sapling_spends(mut self, height: u64, count: usize) -> Self {
+        let plan = self.plan(height);
+        plan.sapling_spends = plan.sapling_spends.saturating_add(count);
+        self
+    }
+
+    /// Add `count` Orchard actions with random nullifiers, paying someone else, to the block at
+    /// `height`.
+    #[must_use]
+    pub fn orchard_spends(mut self, height: u64, count: usize) -> Self {
+        let plan = self.plan(height);
+        plan.orchard_spends = plan.orchard_spends.saturating_add(count);
+        self
+    }
+
+    fn plan(&mut self, height: u64) -> &mut BlockPlan {
+        self.tip_height = self.tip_height.max(height);
+        self.plans.entry(height).or_default()
+    }
+
+    /// Generate the chain.
+    ///
+    /// # Errors
+    /// Returns [`ChainError`] if a block is planned below the start height, keys cannot be derived
+    /// from the seed, or a note commitment tree overflows.
+    pub fn build(self) -> Result<SyntheticChain, ChainError> {
+        if let Some(&height) = self.plans.keys().next() &&
+            height < self.start_height
+        {
+            return Err(ChainError::BelowStart {
+                height,
+                start_height: self.start_height,
+            });
+        }
+
+        let mut rng = ChaCha20Rng::from_seed(Sha256::digest(self.seed).into());
+        let owner = Recipients::from_seed(&self.seed)?;
+        let mut other_seed = [0_u8; 64];
+        rng.fill_bytes(&mut other_seed);
+        let other = Recipients::from_seed(&other_seed)?;
+
+        let mut state = ChainState {
+            sapling_tree: SaplingTree::empty(),
+            orchard_tree: OrchardTree::empty(),
+            hash: block_hash(self.start_height.saturating_sub(1), &[0; 32]),
+        };
+        let initial_tree_state =
+            state.tree_state(self.start_height.saturating_sub(1), GENESIS_TIME)?;
+
+        let mut blocks = Vec::new();
+        let mut tree_states = Vec::new();
+        let mut time = GENESIS_TIME;
+        for height in self.start_height..=self.tip_height {
+            time = time.saturating_add(BLOCK_INTERVAL_SECS);
+            let plan = self.plans.get(&height).cloned().unwrap_or_default();
+            let block = state.next_block(height, time, &plan, &owner, &other, &mut rng)?;
+            tree_states.push(state.tree_state(height, time)?);
+            blocks.push(block);
+        }
+
+        Ok(SyntheticChain {
+            start_height: self.start_height,
+            blocks,
+            tree_states,
+            initial_tree_state,
+        })
+    }
+}
+
+/// Addresses notes are paid to.
+struct Recipients {
+    sapling: sapling::PaymentAddress,
+    orchard: orchard::Address,
+}
+
+impl Recipients {
+    fn from_seed(seed: &[u8; 64]) -> Result<Self, ChainError> {
+        let ufvk = UnifiedSpendingKey::from_seed(&Network::TestNetwork, seed, AccountId::ZERO)
+            .map_err(|e| ChainError::Keys(e.to_string()))?
+            .to_unified_full_viewing_key();
+        let sapling = ufvk
+            .sapling()
+            .ok_or_else(|| ChainError::Keys("missing Sapling key".to_owned()))?
+            .default_address()
+            .1;
+        let orchard = ufvk
+            .orchard()
+            .ok_or_else(|| ChainError::Keys("missing Orchard key".to_owned()))?
+            .address_at(0_u32, Scope::External);
+        Ok(Self { sapling, orchard })
+    }
+}
+
+/// Commitment trees and block hash at the current tip of the chain being generated.
+struct ChainState {
+    sapling_tree: SaplingTree,
+    orchard_tree: OrchardTree,
+    hash: [u8; 32],
+}
+
+impl ChainState {
+    fn next_block(
+        &mut self,
+        height: u64,
+        time: u32,
+        plan: &BlockPlan,
+        owner: &Recipients,
+        other: &Recipients,
+        rng: &mut ChaCha20Rng,
+    ) -> Result<CompactBlock, ChainError> {
+        let prev_hash = self.hash;
+        self.hash = block_hash(height, &prev_hash);
+
+        let spends = (0..plan.sapling_spends)
+            .map(|_| CompactSaplingSpend {
+                nf: random_bytes(rng).to_vec(),
+            })
+            .collect();
+        let outputs = plan
+            .sapling_notes
+            .iter()
+            .map(|&value| self.sapling_output(&owner.sapling, value, rng))
+            .collect::<Result<_, _>>()?;
+        let actions = plan
+            .orchard_notes
+            .iter()
+            .map(|&value| (&owner.orchard, value))
+            .chain((0..plan.orchard_spends).map(|_| (&other.orchard, 0)))
+            .map(|(recipient, value)| self.orchard_action(recipient, value, rng))
+            .collect::<Result<_, _>>()?;
+        let tx = CompactTx {
+            index: 0,
+            hash: Sha256::digest(self.hash).to_vec(),
+            fee: 0,
+            spends,
+            outputs,
+            actions,
+        };
+        let vtx = if tx.spends.is_empty() && tx.outputs.is_empty() && tx.actions.is_empty() {
+            Vec::new()
+        } else {
+            vec![tx]
+        };
+
+        Ok(CompactBlock {
+            proto_version: 1,
+            height,
+            hash: self.hash.to_vec(),
+            prev_hash: prev_hash.to_vec(),
+            time,
+            header: Vec::new(),
+            vtx,
+            chain_metadata: Some(ChainMetadata {
+                sapling_commitment_tree_size: tree_size(self.sapling_tree.size(), "Sapling")?,
+                orchard_commitment_tree_size: tree_size(self.orchard_tree.size(), "Orchard")?,
+            }),
+        })
+    }
+
+    fn sapling_output(
+        &mut self,
+        recipient: &sapling::PaymentAddress,
+        value: u64,
+        rng: &mut ChaCha20Rng,
+    ) -> Result<CompactSaplingOutput, ChainError> {
+        let note = sapling::Note::from_parts(
+            *recipient,
+            sapling::value::NoteValue::from_raw(value),
+            sapling::Rseed::AfterZip212(random_bytes(rng)),
+        );
+        let cmu = note.cmu();
+        let encryption = sapling_note_encryption(None, note, EMPTY_MEMO, rng);
+        let ephemeral_key = SaplingDomain::epk_bytes(encryption.epk());
+        let ciphertext = encryption.encrypt_note_plaintext();
+
+        self.sapling_tree
+            .append(sapling::Node::from_cmu(&cmu))
+            .map_err(|()| ChainError::TreeFull("Sapling"))?;
+        Ok(CompactSaplingOutput {
+            cmu: cmu.to_bytes().to_vec(),
+            ephemeral_key: ephemeral_key.0.to_vec(),
+            ciphertext: compact_ciphertext(ciphertext.as_ref()),
+        })
+    }
+
+    fn orchard_action(
+        &mut self,
+        recipient: &orchard::Address,
+        value: u64,
+        rng: &mut ChaCha20Rng,
+    ) -> Result<CompactOrchardAction, ChainError> {
+        let (nullifier, note) = loop {
+            let mut nullifier = random_bytes(rng);
+            // Clear the top bits so the bytes are a canonical Pallas base field element.
+            if let Some(last) = nullifier.last_mut() {
+                *last &= 0x3F;
+            }
+            let Some(rho) = Option::from(Rho::from_bytes(&nullifier)) else {
+                continue;
+            };
+            let Some(rseed) = Option::from(RandomSeed::from_bytes(random_bytes(rng), &rho)) else {
+                continue;
+            };
+            let note = orchard::Note::from_parts(
+                *recipient,
+                orchard::value::NoteValue::from_raw(value),
+                rho,
+                rseed,
+            );
+            if let Some(note) = Option::<orchard::Note>::from(note) {
+                break (nullifier, note);
+            }
+        };
+        let cmx = ExtractedNoteCommitment::from(note.commitment());
+        let encryption = OrchardNoteEncryption::new(None, note, EMPTY_MEMO);
+        let ephemeral_key = OrchardDomain::epk_bytes(encryption.epk());
+        let ciphertext = encryption.encrypt_note_plaintext();
+
+        self.orchard_tree
+            .append(MerkleHashOrchard::from_cmx(&cmx))
+            .map_err(|()| ChainError::TreeFull("Orchard"))?;
+        Ok(CompactOrchardAction {
+            nullifier: nullifier.to_vec(),
+            cmx: cmx.to_bytes().to_vec(),
+            ephemeral_key: ephemeral_key.0.to_vec(),
+            ciphertext: compact_ciphertext(ciphertext.as_ref()),
+        })
+    }
+
+    /// Tree state after the block at `height`, in lightwalletd's encoding.
+    fn tree_state(&self, height: u64, time: u32) -> Result<TreeState, ChainError> {
+        let mut sapling_tree = Vec::new();
+        write_commitment_tree(&self.sapling_tree, &mut sapling_tree)?;
+        let mut orchard_tree = Vec::new();
+        write_commitment_tree(&self.orchard_tree, &mut orchard_tree)?;
+
+        Ok(TreeState {
+            network: NETWORK_NAME.to_owned(),
+            height,
+            hash: display_hash(&self.hash),
+            time,
+            sapling_tree: hex::encode(sapling_tree),
+            orchard_tree: hex::encode(orchard_tree),
+        })
+    }
+}
+
+/// A generated chain and the tree state after each of its blocks.
+#[derive(Debug, Clone)]
+pub struct SyntheticChain {
+    start_height: u64,
+    blocks: Vec<CompactBlock>,
+    tree_states: Vec<TreeState>,
+    initial_tree_state: TreeState,
+}
+
+impl SyntheticChain {
+    /// Height of the first block.
+    #[must_use]
+    pub const fn start_height(&self) -> u64 {
+        self.start_height
+    }
+
+    /// Height of the last block.
+    #[must_use]
+    pub fn tip_height(&self) -> u64 {
+        self.blocks
+            .last()
+            .map_or(self.start_height, |block| block.height)
+    }
+
+    /// All blocks, in height order.
+    #[must_use]
+    pub fn blocks(&self) -> &[CompactBlock] {
+        &self.blocks
+    }
+
+    /// Block at `height`, if the chain has one.
+    #[must_use]
+    pub fn block(&self, height: u64) -> Option<&CompactBlock> {
+        let offset = usize::try_from(height.checked_sub(self.start_height)?).ok()?;
+        self.blocks.get(offset)
+    }
+
+    /// Identifier of the last block.
+    #[must_use]
+    pub fn latest_block_id(&self) -> BlockId {
+        self.blocks.last().map_or_else(
+            || BlockId {
+                height: self.start_height,
+                hash: Vec::new(),
+            },
+            |block| BlockId {
+                height: block.height,
+                hash: block.hash.clone(),
+            },
+        )
+    }
+
+    /// Tree state after the block at `height`.
+    ///
+    /// The height just below the start resolves to the empty trees the chain starts from, so a
+    /// wallet with that birthday can scan the whole chain.
+    #[must_use]
+    pub fn tree_state(&self, height: u64) -> Option<&TreeState> {
+        if height == self.initial_tree_state.height && height < self.start_height {
+            return Some(&self.initial_tree_state);
+        }
+        let offset = usize::try_from(height.checked_sub(self.start_height)?).ok()?;
+        self.tree_states.get(offset)
+    }
+}
+
+impl SyntheticChain {
+    /// Sapling and Orchard note commitment tree roots after the block at `height`.
+    #[must_use]
+    pub fn note_commitment_roots(&self, height: u64) -> Option<([u8; 32], [u8; 32])> {
+        let state = self.tree_state(height)?;
+        Some((
+            state.sapling_tree().ok()?.root().to_bytes(),
+            state.orchard_tree().ok()?.root().to_bytes(),
+        ))
+    }
+}
+
+fn random_bytes(rng: &mut ChaCha20Rng) -> [u8; 32] {
+    let mut bytes = [0_u8; 32];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn block_hash(height: u64, prev_hash: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(height.to_le_bytes())
+        .chain_update(prev_hash)
+        .finalize()
+        .into()
+}
+
+/// Block hash in the byte-reversed hex form used by tree states.
+fn display_hash(hash: &[u8; 32]) -> String {
+    let mut reversed = *hash;
+    reversed.reverse();
+    hex::encode(reversed)
+}
+
+fn compact_ciphertext(ciphertext: &[u8]) -> Vec<u8> {
+    ciphertext.iter().take(COMPACT_NOTE_SIZE).copied().collect()
+}
+
+fn tree_size(size: usize, pool: &'static str) -> Result<u32, ChainError> {
+    u32::try_from(size).map_err(|_| ChainError::TreeFull(pool))
+}
+
+#[cfg(test)]
+mod tests {
+    use zcash_client_backend::proto::service::TreeState;
+
+    use super::*;
+
+    const SEED: [u8; 64] = [7; 64];
+
+    fn chain() -> SyntheticChain {
+        ChainBuilder::new(SEED, 1_900_000)
+            .sapling_note(1_900_001, 10_000)
+            .orchard_note(1_900_001, 20_000)
+            .sapling_spends(1_900_002, 2)
+            .orchard_spends(1_900_002, 3)
+            .tip_height(1_900_004)
+            .build()
+            .expect("build chain")
+    }
+
+    #[test]
+    fn blocks_link_and_record_tree_sizes() {
+        let chain = chain();
+
+        assert_eq!(chain.blocks().len(), 5);
+        for pair in chain.blocks().windows(2) {
+            let [prev, next] = pair else {
+                unreachable!("windows of two")
+            };
+            assert_eq!(next.prev_hash, prev.hash);
+            assert_eq!(next.height, prev.height.saturating_add(1));
+        }
+        let metadata = chain
+            .block(1_900_004)
+            .and_then(|block| block.chain_metadata)
+            .expect("metadata");
+        assert_eq!(metadata.sapling_commitment_tree_size, 1);
+        assert_eq!(metadata.orchard_commitment_tree_size, 4);
+    }
+
+    #[test]
+    fn tree_states_decode_and_track_the_tip() {
+        let chain = chain();
+        let initial = chain.tree_state(1_899_999).expect("initial state");
+        assert_eq!(initial.sapling_tree().expect("sapling tree").size(), 0);
+        assert!(chain.tree_state(1_899_998).is_none());
+
+        let tip: &TreeState = chain.tree_state(chain.tip_height()).expect("tip state");
+        assert_eq!(tip.orchard_tree().expect("orchard tree").size(), 4);
+        let mut hash = hex::decode(&tip.hash).expect("hex hash");
+        hash.reverse();
+        assert_eq!(hash, chain.latest_block_id().hash);
+    }
+
+    #[test]
+    fn same_seed_builds_the_same_chain() {
+        assert_eq!(
+            chain().blocks(),
+            ChainBuilder::new(SEED, 1_900_000)
+                .sapling_note(1_900_001, 10_000)
+                .orchard_note(1_900_001, 20_000)
+                .sapling_spends(1_900_002, 2)
+                .orchard_spends(1_900_002, 3)
+                .tip_height(1_900_004)
+                .build()
+                .expect("build chain")
+                .blocks()
+        );
+    }
+}
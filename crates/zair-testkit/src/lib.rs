@@ -0,0 +1,11 @@
+//! Test support for running ZAIR end to end without network access.
+//!
+//! [`ChainBuilder`] generates a deterministic chain of `CompactBlock`s holding Sapling and Orchard
+//! notes owned by a known seed, and [`MockLightwalletd`] serves it over gRPC from inside the test
+//! process, so `config build`, `claim run` and `verify run` can point at a local endpoint.
+
+pub mod chain;
+pub mod lightwalletd;
+
+pub use chain::{ChainBuilder, SyntheticChain};
+pub use lightwalletd::MockLightwalletd;
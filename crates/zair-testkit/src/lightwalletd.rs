@@ -0,0 +1,208 @@
+//! In-process lightwalletd serving a [`SyntheticChain`].
+//!
+//! Implements the `CompactTxStreamer` calls ZAIR makes: `GetLatestBlock`, `GetBlockRange` and
+//! `GetTreeState`. Heights missing from the chain are skipped by block ranges, so nullifier scans
+//! starting at Sapling activation only see the synthetic blocks.
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use futures::{Stream, stream};
+use tokio::sync::oneshot;
+use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status};
+use zcash_client_backend::proto::compact_formats::CompactBlock;
+use zcash_client_backend::proto::service::{BlockId, BlockRange, ChainSpec, TreeState};
+
+use crate::chain::SyntheticChain;
+
+#[allow(
+    missing_docs,
+    unused_qualifications,
+    clippy::all,
+    clippy::pedantic,
+    clippy::nursery,
+    clippy::restriction,
+    reason = "Generated by tonic-build"
+)]
+mod proto {
+    include!(concat!(
+        env!("OUT_DIR"),
+        "/cash.z.wallet.sdk.rpc.CompactTxStreamer.rs"
+    ));
+}
+
+use proto::compact_tx_streamer_server::{CompactTxStreamer, CompactTxStreamerServer};
+
+type BoxedError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A lightwalletd listening on a local port until dropped.
+#[derive(Debug)]
+pub struct MockLightwalletd {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    server: Option<JoinHandle<Result<(), BoxedError>>>,
+}
+
+impl MockLightwalletd {
+    /// Serve `chain` on an ephemeral port of `127.0.0.1`.
+    ///
+    /// The server runs on its own thread and runtime, so it can back both async tests and CLI
+    /// processes spawned from sync tests.
+    ///
+    /// # Errors
+    /// Returns an error if the listener or the runtime cannot be created.
+    pub fn start(chain: SyntheticChain) -> std::io::Result<Self> {
+        let listener = std::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let service = Streamer {
+            chain: Arc::new(chain),
+        };
+
+        let server = std::thread::spawn(move || {
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener)?;
+                let serve = Server::builder()
+                    .add_service(CompactTxStreamerServer::new(service))
+                    .serve_with_incoming(TcpIncoming::from(listener));
+                // Stop without waiting for clients to hang up: dropping the runtime closes their
+                // connections. A dropped sender also stops the server.
+                tokio::select! {
+                    served = serve => served?,
+                    _ = shutdown_rx => {}
+                }
+                Ok(())
+            })
+        });
+
+        Ok(Self {
+            addr,
+            shutdown: Some(shutdown),
+            server: Some(server),
+        })
+    }
+
+    /// Address the server listens on.
+    #[must_use]
+    pub const fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Plain-text endpoint URL to pass as `--lightwalletd`.
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+impl Drop for MockLightwalletd {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
+
+struct Streamer {
+    chain: Arc<SyntheticChain>,
+}
+
+type BlockStream = Pin<Box<dyn Stream<Item = Result<CompactBlock, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl CompactTxStreamer for Streamer {
+    async fn get_latest_block(
+        &self,
+        _request: Request<ChainSpec>,
+    ) -> Result<Response<BlockId>, Status> {
+        Ok(Response::new(self.chain.latest_block_id()))
+    }
+
+    type GetBlockRangeStream = BlockStream;
+
+    async fn get_block_range(
+        &self,
+        request: Request<BlockRange>,
+    ) -> Result<Response<Self::GetBlockRangeStream>, Status> {
+        let range = request.into_inner();
+        let (Some(start), Some(end)) = (range.start, range.end) else {
+            return Err(Status::invalid_argument(
+                "block range needs a start and an end",
+            ));
+        };
+        let (low, high) = (start.height.min(end.height), start.height.max(end.height));
+        let mut blocks: Vec<_> = self
+            .chain
+            .blocks()
+            .iter()
+            .filter(|block| (low..=high).contains(&block.height))
+            .cloned()
+            .map(Ok)
+            .collect();
+        // lightwalletd streams descending ranges from the start height down.
+        if start.height > end.height {
+            blocks.reverse();
+        }
+        Ok(Response::new(Box::pin(stream::iter(blocks))))
+    }
+
+    async fn get_tree_state(
+        &self,
+        request: Request<BlockId>,
+    ) -> Result<Response<TreeState>, Status> {
+        let height = request.into_inner().height;
+        self.chain
+            .tree_state(height)
+            .cloned()
+            .map(Response::new)
+            .ok_or_else(|| Status::not_found(format!("no tree state at height {height}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zair_scan::light_walletd::LightWalletd;
+    use zair_scan::scanner::ChainNullifiersVisitor;
+
+    use super::*;
+    use crate::ChainBuilder;
+
+    #[tokio::test]
+    async fn serves_blocks_and_tree_states() {
+        let chain = ChainBuilder::new([3; 64], 1_900_000)
+            .sapling_spends(1_900_001, 2)
+            .orchard_spends(1_900_002, 1)
+            .tip_height(1_900_003)
+            .build()
+            .expect("build chain");
+        let tip_state = chain.tree_state(1_900_003).cloned().expect("tip state");
+        let server = MockLightwalletd::start(chain).expect("start server");
+        let client = LightWalletd::connect(server.url().parse().expect("url"))
+            .await
+            .expect("connect");
+
+        let state = client.get_tree_state(1_900_003).await.expect("tree state");
+        assert_eq!(state, tip_state);
+        assert!(client.get_tree_state(1_900_004).await.is_err());
+
+        let mut visitor = ChainNullifiersVisitor::default();
+        client
+            .scan_nullifiers(&mut visitor, &(280_000..=1_900_003))
+            .await
+            .expect("scan nullifiers");
+        let (sapling, orchard) = visitor.sanitise_nullifiers();
+        assert_eq!(sapling.len(), 2);
+        assert_eq!(orchard.len(), 1);
+    }
+}
@@ -0,0 +1,501 @@
+//! Byte- and JSON-level claim operations behind the JavaScript bindings.
+//!
+//! Inputs are the JSON documents and key files the `zair` CLI uses, passed as strings and
+//! byte slices. Randomness comes from caller-supplied entropy so no OS RNG is needed.
+//!
+//! With a [`TrustedOrganizer`], the configuration is refused unless the organizer's detached
+//! signature covers its exact text, so pass the configuration file contents unchanged. A
+//! `target` selects one of the configuration's airdrop targets; without one, its default
+//! airdrop is used.
+
+use std::collections::BTreeMap;
+
+use bellman::groth16::VerifyingKey;
+use bls12_381::Bls12;
+use eyre::{Context as _, ensure};
+use halo2_proofs::poly::commitment::Params;
+use pasta_curves::vesta;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng as _;
+use serde::{Deserialize, Serialize};
+use zair_core::schema::config::{AirdropConfiguration, ChainClock, OrganizerSignature};
+use zair_core::schema::proof_inputs::AirdropClaimInputs;
+use zair_core::schema::verification::VerifyRequest;
+use zair_sapling_proofs::prover::{ClaimParameters, read_parameters};
+use zair_sdk::commands::{
+    ClaimKeys, ClaimProofsOutput, ClaimSecretsOutput, OrchardClaimVerifier, ResolvedMessageHashes,
+    SaplingClaimVerifier, SubmissionVerifier, TransparentClaimVerifier, hash_message,
+    parse_organizer_public_key, parse_signed_configuration, prove_claims, sign_claim_proofs,
+};
+
+/// Organizer an airdrop configuration must be signed by.
+#[derive(Debug, Clone, Copy)]
+pub struct TrustedOrganizer<'a> {
+    /// Ed25519 public key of the organizer as 64 hex characters.
+    pub public_key: &'a str,
+    /// Detached signature JSON written by `zair config sign`.
+    pub signature_json: &'a str,
+}
+
+/// Proofs and the local secrets needed to sign them, as returned by [`prove`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProvedClaims {
+    /// Claim proofs; safe to share.
+    pub proofs: ClaimProofsOutput,
+    /// Per-claim signing secrets; keep local.
+    pub secrets: ClaimSecretsOutput,
+}
+
+/// Prove every claim of a claim-inputs document.
+///
+/// `sapling_proving_key` is only read when there are Sapling claims. An empty
+/// `orchard_params` generates the Orchard params in memory.
+///
+/// # Errors
+/// Returns an error if an input fails to parse, `seed` is not 64 bytes, `entropy` is not
+/// 32 bytes, or proving fails.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "Mirrors the flat JavaScript binding"
+)]
+pub fn prove(
+    config_json: &str,
+    claim_inputs_json: &str,
+    sapling_proving_key: &[u8],
+    orchard_params: &[u8],
+    seed: &[u8],
+    account_id: u32,
+    entropy: &[u8],
+    trusted_organizer: Option<TrustedOrganizer<'_>>,
+    target: Option<&str>,
+) -> eyre::Result<String> {
+    let airdrop_config = parse_config(config_json, trusted_organizer, target)?;
+    let inputs: AirdropClaimInputs =
+        serde_json::from_str(claim_inputs_json).context("Failed to parse claim inputs")?;
+    let keys = claim_keys(&airdrop_config, seed, account_id)?;
+    let mut rng = rng_from_entropy(entropy)?;
+
+    let sapling_params = if inputs.sapling_claim_input.is_empty() {
+        None
+    } else {
+        Some(read_sapling_proving_key(sapling_proving_key)?)
+    };
+    let orchard_params = match &airdrop_config.orchard {
+        Some(orchard) if !inputs.orchard_claim_input.is_empty() => Some(read_orchard_params(
+            orchard_params,
+            zair_orchard_proofs::k_for_scheme(orchard.value_commitment_scheme.into()),
+        )?),
+        _ => None,
+    };
+
+    let (proofs, secrets) = prove_claims(
+        &inputs,
+        &airdrop_config,
+        &keys,
+        sapling_params.as_ref(),
+        orchard_params.as_ref(),
+        &mut rng,
+    )?;
+    Ok(serde_json::to_string(&ProvedClaims { proofs, secrets })?)
+}
+
+/// Sign proofs into a submission over one message shared by all claims.
+///
+/// # Errors
+/// Returns an error if an input fails to parse, `seed` is not 64 bytes, `entropy` is not
+/// 32 bytes, or signing fails.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "Mirrors the flat JavaScript binding"
+)]
+pub fn sign(
+    config_json: &str,
+    proofs_json: &str,
+    secrets_json: &str,
+    seed: &[u8],
+    account_id: u32,
+    message: &[u8],
+    entropy: &[u8],
+    trusted_organizer: Option<TrustedOrganizer<'_>>,
+    target: Option<&str>,
+) -> eyre::Result<String> {
+    let airdrop_config = parse_config(config_json, trusted_organizer, target)?;
+    let proofs: ClaimProofsOutput =
+        serde_json::from_str(proofs_json).context("Failed to parse claim proofs")?;
+    let secrets: ClaimSecretsOutput =
+        serde_json::from_str(secrets_json).context("Failed to parse claim secrets")?;
+    let keys = claim_keys(&airdrop_config, seed, account_id)?;
+    let mut rng = rng_from_entropy(entropy)?;
+    let message_hashes = ResolvedMessageHashes::new(
+        Some(hash_message(message)),
+        BTreeMap::default(),
+        BTreeMap::default(),
+    );
+
+    let submission = sign_claim_proofs(
+        &proofs,
+        secrets,
+        &keys,
+        &airdrop_config,
+        &message_hashes,
+        &mut rng,
+    )?;
+    Ok(serde_json::to_string(&submission)?)
+}
+
+/// Verify a signed submission and return the per-claim verdicts.
+///
+/// `sapling_verifying_key` is only read when the airdrop has a Sapling pool. An empty
+/// `orchard_params` generates the Orchard params in memory. A claim window is checked against
+/// the caller's `current_height` on the target chain and `current_time`, in seconds since the
+/// Unix epoch; the request cannot supply either.
+///
+/// # Errors
+/// Returns an error if an input fails to parse or the request is rejected as a whole.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "Mirrors the flat JavaScript binding"
+)]
+pub fn verify(
+    config_json: &str,
+    request_json: &str,
+    sapling_verifying_key: &[u8],
+    orchard_params: &[u8],
+    current_height: Option<u64>,
+    current_time: Option<u64>,
+    trusted_organizer: Option<TrustedOrganizer<'_>>,
+    target: Option<&str>,
+) -> eyre::Result<String> {
+    let airdrop_config = parse_config(config_json, trusted_organizer, target)?;
+    let request: VerifyRequest =
+        serde_json::from_str(request_json).context("Failed to parse verify request")?;
+
+    let sapling = match &airdrop_config.sapling {
+        Some(sapling) => {
+            let vk = VerifyingKey::<Bls12>::read(sapling_verifying_key)
+                .context("Failed to read Sapling verifying key")?;
+            Some(SaplingClaimVerifier::new(sapling, &vk))
+        }
+        None => None,
+    };
+    let orchard = match &airdrop_config.orchard {
+        Some(orchard) => {
+            let params = read_orchard_params(
+                orchard_params,
+                zair_orchard_proofs::k_for_scheme(orchard.value_commitment_scheme.into()),
+            )?;
+            Some(OrchardClaimVerifier::new(orchard, params.into()))
+        }
+        None => None,
+    };
+
+    let transparent = airdrop_config
+        .transparent
+        .as_ref()
+        .map(TransparentClaimVerifier::new);
+
+    let clock = ChainClock {
+        height: current_height,
+        timestamp: current_time,
+    };
+    let response = SubmissionVerifier::new(sapling, orchard, transparent)
+        .with_claim_window(airdrop_config.claim_window)
+        .with_message_policy(airdrop_config.message_policy)
+        .verify(&request, &clock)?;
+    Ok(serde_json::to_string(&response)?)
+}
+
+/// Parse the configuration, refusing it unless `trusted_organizer` signed its exact text, and
+/// resolve `target` if given.
+fn parse_config(
+    config_json: &str,
+    trusted_organizer: Option<TrustedOrganizer<'_>>,
+    target: Option<&str>,
+) -> eyre::Result<AirdropConfiguration> {
+    let config = match trusted_organizer {
+        Some(organizer) => {
+            let public_key = parse_organizer_public_key(organizer.public_key)?;
+            let signature: OrganizerSignature = serde_json::from_str(organizer.signature_json)
+                .context("Failed to parse configuration signature")?;
+            parse_signed_configuration(config_json.as_bytes(), &signature, &public_key)
+                .context("Refusing airdrop configuration")?
+        }
+        None => serde_json::from_str::<AirdropConfiguration>(config_json)
+            .context("Failed to parse airdrop configuration")?,
+    };
+    config.validate_targets()?;
+    match target {
+        Some(target) => Ok(config.for_target(target)?),
+        None => Ok(config),
+    }
+}
+
+fn claim_keys(
+    airdrop_config: &AirdropConfiguration,
+    seed: &[u8],
+    account_id: u32,
+) -> eyre::Result<ClaimKeys> {
+    let seed: &[u8; 64] = seed
+        .try_into()
+        .map_err(|_| eyre::eyre!("Seed must be 64 bytes, got {}", seed.len()))?;
+    ClaimKeys::from_seed(airdrop_config.network, seed, account_id)
+}
+
+fn rng_from_entropy(entropy: &[u8]) -> eyre::Result<ChaCha20Rng> {
+    let entropy: [u8; 32] = entropy
+        .try_into()
+        .map_err(|_| eyre::eyre!("Entropy must be 32 bytes, got {}", entropy.len()))?;
+    Ok(ChaCha20Rng::from_seed(entropy))
+}
+
+fn read_sapling_proving_key(bytes: &[u8]) -> eyre::Result<ClaimParameters> {
+    ensure!(
+        !bytes.is_empty(),
+        "Sapling claims present but no proving key given"
+    );
+    read_parameters(bytes, false).context("Failed to read Sapling proving key")
+}
+
+fn read_orchard_params(bytes: &[u8], expected_k: u32) -> eyre::Result<Params<vesta::Affine>> {
+    if bytes.is_empty() {
+        return Ok(Params::new(expected_k));
+    }
+    let params =
+        Params::<vesta::Affine>::read(&mut &bytes[..]).context("Failed to read Orchard params")?;
+    ensure!(
+        params.k() == expected_k,
+        "Orchard params have k = {}, expected {expected_k}",
+        params.k()
+    );
+    Ok(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use zair_core::schema::config::{
+        AirdropNetwork, AirdropTarget, ClaimWindow, ClaimWindowUnit, MessagePolicy,
+        OrchardGapTreeHash, OrchardSnapshot, ValueCommitmentScheme,
+    };
+    use zair_sdk::commands::{UntrustedConfiguration, organizer_public_key, sign_configuration};
+
+    use super::*;
+
+    const SEED: [u8; 64] = [7_u8; 64];
+    const ENTROPY: [u8; 32] = [1_u8; 32];
+
+    fn orchard_config_json() -> String {
+        serde_json::to_string(&AirdropConfiguration {
+            network: AirdropNetwork::Testnet,
+            snapshot_height: 1,
+            sapling: None,
+            orchard: Some(OrchardSnapshot {
+                note_commitment_root: [0_u8; 32],
+                nullifier_gap_root: [0_u8; 32],
+                target_id: "ZAIRTEST:O".to_owned(),
+                value_commitment_scheme: ValueCommitmentScheme::Native,
+                nullifier_gap_tree_hash: OrchardGapTreeHash::default(),
+            }),
+            transparent: None,
+            message_policy: MessagePolicy::default(),
+            claim_window: None,
+            targets: Vec::new(),
+        })
+        .expect("serialize config")
+    }
+
+    #[test]
+    fn prove_without_claims_returns_empty_outputs() {
+        let inputs = r#"{"sapling_claim_input":[],"orchard_claim_input":[]}"#;
+        let proved: ProvedClaims = serde_json::from_str(
+            &prove(
+                &orchard_config_json(),
+                inputs,
+                &[],
+                &[],
+                &SEED,
+                0,
+                &ENTROPY,
+                None,
+                None,
+            )
+            .expect("prove"),
+        )
+        .expect("parse output");
+
+        assert!(proved.proofs.sapling_proofs.is_empty());
+        assert!(proved.proofs.orchard_proofs.is_empty());
+        assert!(proved.secrets.orchard.is_empty());
+    }
+
+    #[test]
+    fn prove_refuses_configuration_not_signed_by_trusted_organizer() {
+        let inputs = r#"{"sapling_claim_input":[],"orchard_claim_input":[]}"#;
+        let config = orchard_config_json();
+        let secret = [3_u8; 32];
+        let public_key = hex::encode(organizer_public_key(&secret));
+        let signature_json = serde_json::to_string(&sign_configuration(config.as_bytes(), &secret))
+            .expect("serialize signature");
+        let organizer = TrustedOrganizer {
+            public_key: &public_key,
+            signature_json: &signature_json,
+        };
+
+        prove(
+            &config,
+            inputs,
+            &[],
+            &[],
+            &SEED,
+            0,
+            &ENTROPY,
+            Some(organizer),
+            None,
+        )
+        .expect("signed configuration is accepted");
+        let tampered = config.replace("\"snapshot_height\":1", "\"snapshot_height\":2");
+        let err = prove(
+            &tampered,
+            inputs,
+            &[],
+            &[],
+            &SEED,
+            0,
+            &ENTROPY,
+            Some(organizer),
+            None,
+        )
+        .expect_err("tampered configuration must be refused");
+        assert!(
+            err.downcast_ref::<UntrustedConfiguration>().is_some(),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn sign_rejects_short_entropy() {
+        let proofs = r#"{"sapling_proofs":[],"orchard_proofs":[]}"#;
+        let secrets = r#"{"sapling":[],"orchard":[]}"#;
+        let err = sign(
+            &orchard_config_json(),
+            proofs,
+            secrets,
+            &SEED,
+            0,
+            b"claim",
+            &ENTROPY[..16],
+            None,
+            None,
+        )
+        .expect_err("short entropy must fail");
+
+        assert!(err.to_string().contains("Entropy must be 32 bytes"));
+    }
+
+    #[test]
+    fn sign_rejects_short_seed() {
+        let proofs = r#"{"sapling_proofs":[],"orchard_proofs":[]}"#;
+        let secrets = r#"{"sapling":[],"orchard":[]}"#;
+        let err = sign(
+            &orchard_config_json(),
+            proofs,
+            secrets,
+            &SEED[..32],
+            0,
+            b"claim",
+            &ENTROPY,
+            None,
+            None,
+        )
+        .expect_err("short seed must fail");
+
+        assert!(err.to_string().contains("Seed must be 64 bytes"));
+    }
+
+    #[test]
+    fn verify_rejects_orchard_params_of_wrong_size() {
+        let mut params = Vec::new();
+        Params::<vesta::Affine>::new(4)
+            .write(&mut params)
+            .expect("write params");
+        let request = r#"{"submission":{"sapling":[],"orchard":[]}}"#;
+        let err = verify(
+            &orchard_config_json(),
+            request,
+            &[],
+            &params,
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect_err("params of the wrong size must fail");
+
+        assert!(err.to_string().contains("Orchard params have k = 4"));
+    }
+
+    #[test]
+    fn verify_checks_the_claim_window_against_the_callers_height() {
+        let mut config: AirdropConfiguration =
+            serde_json::from_str(&orchard_config_json()).expect("parse config");
+        config.orchard = None;
+        config.claim_window = Some(ClaimWindow {
+            unit: ClaimWindowUnit::Height,
+            start: Some(100),
+            end: None,
+        });
+        let config = serde_json::to_string(&config).expect("serialize config");
+        let claim = format!(
+            r#"{{"zkproof":"00","rk":"{zero}","airdrop_nullifier":"{zero}","proof_hash":"{zero}","message_hash":"{zero}","spend_auth_sig":"{zero}{zero}"}}"#,
+            zero = "00".repeat(32)
+        );
+        // A height in the request body is not trusted.
+        let request = format!(r#"{{"submission":{{"orchard":[{claim}]}},"current_height":500}}"#);
+
+        let missing = verify(&config, &request, &[], &[], None, None, None, None)
+            .expect_err("a height-bounded window needs the caller's height");
+        assert!(
+            missing.to_string().contains("no current height"),
+            "{missing:#}"
+        );
+        let early = verify(&config, &request, &[], &[], Some(50), None, None, None)
+            .expect_err("the window is not open yet");
+        assert!(
+            early.to_string().contains("opens at height 100"),
+            "{early:#}"
+        );
+    }
+
+    #[test]
+    fn verify_resolves_the_requested_target() {
+        let mut config: AirdropConfiguration =
+            serde_json::from_str(&orchard_config_json()).expect("parse config");
+        config.targets.push(AirdropTarget {
+            name: "season-2".to_owned(),
+            sapling: None,
+            orchard: None,
+            transparent: None,
+            message_policy: MessagePolicy::default(),
+            claim_window: Some(ClaimWindow {
+                unit: ClaimWindowUnit::Height,
+                start: Some(100),
+                end: None,
+            }),
+        });
+        let config = serde_json::to_string(&config).expect("serialize config");
+        let claim = format!(
+            r#"{{"zkproof":"00","rk":"{zero}","airdrop_nullifier":"{zero}","proof_hash":"{zero}","message_hash":"{zero}","spend_auth_sig":"{zero}{zero}"}}"#,
+            zero = "00".repeat(32)
+        );
+        let request = format!(r#"{{"submission":{{"orchard":[{claim}]}}}}"#);
+        let verify_target = |target| verify(&config, &request, &[], &[], None, None, None, target);
+
+        let targeted = verify_target(Some("season-2"))
+            .expect_err("the target's claim window needs the caller's height");
+        assert!(
+            targeted.to_string().contains("no current height"),
+            "{targeted:#}"
+        );
+        let unknown = verify_target(Some("season-3")).expect_err("unknown targets are refused");
+        assert!(unknown.to_string().contains("season-3"), "{unknown:#}");
+    }
+}
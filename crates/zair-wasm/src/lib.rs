@@ -0,0 +1,158 @@
+//! WebAssembly bindings for proving, signing and verifying ZAIR airdrop claims.
+//!
+//! The bindings work on in-memory JSON documents and key bytes, in the same formats the `zair`
+//! CLI reads and writes. Nothing touches the filesystem or the network, and proving is
+//! single-threaded. Callers pass 32 bytes of entropy (e.g. from `crypto.getRandomValues`) to
+//! seed proof and signature randomness.
+//!
+//! Every binding takes an optional trusted organizer key (hex) and the configuration's detached
+//! signature JSON from `zair config sign`. With a key, the configuration text must be the
+//! signed file's contents, unchanged. The last argument optionally names the airdrop target of
+//! the configuration to use; without it, the default airdrop is used.
+
+pub mod api;
+
+use wasm_bindgen::prelude::*;
+
+fn js_error(err: &eyre::Report) -> JsError {
+    JsError::new(&format!("{err:#}"))
+}
+
+/// Pair the organizer key with the configuration signature; without a key the configuration is
+/// not checked.
+fn trusted_organizer<'a>(
+    public_key: Option<&'a str>,
+    signature_json: Option<&'a str>,
+) -> Result<Option<api::TrustedOrganizer<'a>>, JsError> {
+    let Some(public_key) = public_key else {
+        return Ok(None);
+    };
+    let signature_json = signature_json.ok_or_else(|| {
+        JsError::new("A trusted organizer key needs the configuration signature JSON")
+    })?;
+    Ok(Some(api::TrustedOrganizer {
+        public_key,
+        signature_json,
+    }))
+}
+
+/// Prove every claim of a claim-inputs JSON document.
+///
+/// Returns `{"proofs": ..., "secrets": ...}` as JSON. Pass an empty `orchard_params` to
+/// generate the Orchard params in memory.
+///
+/// # Errors
+/// Throws if an input is malformed or proving fails.
+#[allow(
+    clippy::too_many_arguments,
+    clippy::needless_pass_by_value,
+    reason = "Flat arguments map directly onto JavaScript parameters"
+)]
+#[wasm_bindgen(js_name = proveClaims)]
+pub fn prove_claims(
+    config_json: &str,
+    claim_inputs_json: &str,
+    sapling_proving_key: &[u8],
+    orchard_params: &[u8],
+    seed: &[u8],
+    account_id: u32,
+    entropy: &[u8],
+    trusted_organizer_key: Option<String>,
+    config_signature_json: Option<String>,
+    target: Option<String>,
+) -> Result<String, JsError> {
+    api::prove(
+        config_json,
+        claim_inputs_json,
+        sapling_proving_key,
+        orchard_params,
+        seed,
+        account_id,
+        entropy,
+        trusted_organizer(
+            trusted_organizer_key.as_deref(),
+            config_signature_json.as_deref(),
+        )?,
+        target.as_deref(),
+    )
+    .map_err(|err| js_error(&err))
+}
+
+/// Sign claim proofs into a submission JSON over a message shared by all claims.
+///
+/// # Errors
+/// Throws if an input is malformed or signing fails.
+#[allow(
+    clippy::too_many_arguments,
+    clippy::needless_pass_by_value,
+    reason = "Flat arguments map directly onto JavaScript parameters"
+)]
+#[wasm_bindgen(js_name = signClaims)]
+pub fn sign_claims(
+    config_json: &str,
+    proofs_json: &str,
+    secrets_json: &str,
+    seed: &[u8],
+    account_id: u32,
+    message: &[u8],
+    entropy: &[u8],
+    trusted_organizer_key: Option<String>,
+    config_signature_json: Option<String>,
+    target: Option<String>,
+) -> Result<String, JsError> {
+    api::sign(
+        config_json,
+        proofs_json,
+        secrets_json,
+        seed,
+        account_id,
+        message,
+        entropy,
+        trusted_organizer(
+            trusted_organizer_key.as_deref(),
+            config_signature_json.as_deref(),
+        )?,
+        target.as_deref(),
+    )
+    .map_err(|err| js_error(&err))
+}
+
+/// Verify a `VerifyRequest` JSON and return the `VerifyResponse` JSON with per-claim verdicts.
+///
+/// A claim window is checked against `current_height` on the target chain and `current_time`
+/// (seconds since the Unix epoch), never against the request.
+///
+/// # Errors
+/// Throws if an input is malformed or the request is rejected as a whole.
+#[allow(
+    clippy::too_many_arguments,
+    clippy::needless_pass_by_value,
+    reason = "Flat arguments map directly onto JavaScript parameters"
+)]
+#[wasm_bindgen(js_name = verifySubmission)]
+pub fn verify_submission(
+    config_json: &str,
+    request_json: &str,
+    sapling_verifying_key: &[u8],
+    orchard_params: &[u8],
+    current_height: Option<u64>,
+    current_time: Option<u64>,
+    trusted_organizer_key: Option<String>,
+    config_signature_json: Option<String>,
+    target: Option<String>,
+) -> Result<String, JsError> {
+    api::verify(
+        config_json,
+        request_json,
+        sapling_verifying_key,
+        orchard_params,
+        current_height,
+        current_time,
+        trusted_organizer(
+            trusted_organizer_key.as_deref(),
+            config_signature_json.as_deref(),
+        )?,
+        target.as_deref(),
+    )
+    .map_err(|err| js_error(&err))
+}
@@ -0,0 +1,75 @@
+//! Browser tests for the JavaScript bindings; run with `wasm-pack test --headless --firefox`.
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+use zair_wasm::{prove_claims, sign_claims, verify_submission};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+const CONFIG: &str = r#"{
+  "network": "testnet",
+  "snapshot_height": 1,
+  "orchard": {
+    "note_commitment_root": "0000000000000000000000000000000000000000000000000000000000000000",
+    "nullifier_gap_root": "0000000000000000000000000000000000000000000000000000000000000000",
+    "target_id": "ZAIRTEST:O",
+    "value_commitment_scheme": "native"
+  }
+}"#;
+
+#[wasm_bindgen_test]
+fn prove_without_claims_succeeds() {
+    let inputs = r#"{"sapling_claim_input":[],"orchard_claim_input":[]}"#;
+    let output = prove_claims(
+        CONFIG,
+        inputs,
+        &[],
+        &[],
+        &[7; 64],
+        0,
+        &[1; 32],
+        None,
+        None,
+        None,
+    )
+    .unwrap_or_else(|_| panic!("prove must succeed"));
+    assert!(output.contains("\"orchard_proofs\":[]"));
+}
+
+#[wasm_bindgen_test]
+fn sign_without_proofs_fails() {
+    let proofs = r#"{"sapling_proofs":[],"orchard_proofs":[]}"#;
+    let secrets = r#"{"sapling":[],"orchard":[]}"#;
+    assert!(
+        sign_claims(
+            CONFIG, proofs, secrets, &[7; 64], 0, b"claim", &[1; 32], None, None, None
+        )
+        .is_err()
+    );
+}
+
+#[wasm_bindgen_test]
+fn verify_empty_submission_fails() {
+    let request = r#"{"submission":{"sapling":[],"orchard":[]}}"#;
+    assert!(verify_submission(CONFIG, request, &[], &[], None, None, None, None, None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn unknown_target_fails() {
+    let inputs = r#"{"sapling_claim_input":[],"orchard_claim_input":[]}"#;
+    assert!(
+        prove_claims(
+            CONFIG,
+            inputs,
+            &[],
+            &[],
+            &[7; 64],
+            0,
+            &[1; 32],
+            None,
+            None,
+            Some("season-2".to_owned()),
+        )
+        .is_err()
+    );
+}